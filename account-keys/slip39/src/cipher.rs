@@ -0,0 +1,130 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! The passphrase-keyed Feistel cipher SLIP-0039 applies to the master secret
+//! before splitting it.
+
+use alloc::vec::Vec;
+use hmac::Hmac;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// The total number of PBKDF2 iterations at iteration exponent 0.
+const BASE_ITERATION_COUNT: u32 = 10_000;
+
+/// The number of Feistel rounds.
+const ROUND_COUNT: u8 = 4;
+
+/// The salt prefix for non-extendable shares.
+const CUSTOMIZATION_STRING: &[u8] = b"shamir";
+
+fn round_function(
+    round: u8,
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    salt: &[u8],
+    r: &[u8],
+) -> Zeroizing<Vec<u8>> {
+    let mut password = Zeroizing::new(Vec::with_capacity(passphrase.len() + 1));
+    password.push(round);
+    password.extend_from_slice(passphrase);
+
+    let mut full_salt = Vec::with_capacity(salt.len() + r.len());
+    full_salt.extend_from_slice(salt);
+    full_salt.extend_from_slice(r);
+
+    let mut result = Zeroizing::new(alloc::vec![0u8; r.len()]);
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(
+        &password,
+        &full_salt,
+        iterations(iteration_exponent),
+        &mut result,
+    );
+    result
+}
+
+/// The number of PBKDF2 iterations in each round, saturating rather than
+/// overflowing for exponents the share format allows but we never accept.
+fn iterations(iteration_exponent: u8) -> u32 {
+    let total = (BASE_ITERATION_COUNT as u64) << iteration_exponent;
+    (total / ROUND_COUNT as u64).min(u32::MAX as u64) as u32
+}
+
+fn salt(identifier: u16) -> Vec<u8> {
+    let mut salt = CUSTOMIZATION_STRING.to_vec();
+    salt.extend_from_slice(&identifier.to_be_bytes());
+    salt
+}
+
+fn feistel<I: Iterator<Item = u8>>(
+    input: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    rounds: I,
+) -> Zeroizing<Vec<u8>> {
+    let salt = salt(identifier);
+    let half = input.len() / 2;
+    let mut l = Zeroizing::new(input[..half].to_vec());
+    let mut r = Zeroizing::new(input[half..].to_vec());
+    for round in rounds {
+        let f = round_function(round, passphrase, iteration_exponent, &salt, &r);
+        for (byte, mask) in l.iter_mut().zip(f.iter()) {
+            *byte ^= mask;
+        }
+        core::mem::swap(&mut l, &mut r);
+    }
+
+    let mut result = Zeroizing::new(Vec::with_capacity(input.len()));
+    result.extend_from_slice(&r);
+    result.extend_from_slice(&l);
+    result
+}
+
+/// Encrypt a master secret with a passphrase.
+pub fn encrypt(
+    master_secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        0..ROUND_COUNT,
+    )
+}
+
+/// Decrypt an encrypted master secret with a passphrase.
+pub fn decrypt(
+    encrypted_master_secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        encrypted_master_secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        (0..ROUND_COUNT).rev(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Decryption inverts encryption with the same passphrase, and not with a
+    /// different one.
+    #[test]
+    fn roundtrip() {
+        let secret = [7u8; 16];
+        let encrypted = encrypt(&secret, b"TREZOR", 0, 1234);
+        assert_ne!(&encrypted[..], &secret[..]);
+        assert_eq!(&decrypt(&encrypted, b"TREZOR", 0, 1234)[..], &secret[..]);
+        assert_ne!(&decrypt(&encrypted, b"", 0, 1234)[..], &secret[..]);
+        assert_ne!(&decrypt(&encrypted, b"TREZOR", 0, 1235)[..], &secret[..]);
+    }
+}
@@ -0,0 +1,497 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! MobileCoin SLIP-0039 Shamir Backups
+//!
+//! This crate splits a master secret, such as the entropy of a BIP-39
+//! [`Mnemonic`] or an account's root entropy, into SLIP-0039 mnemonic shares,
+//! and recovers it from a sufficient subset of them.
+
+#![no_std]
+#![warn(missing_docs)]
+#![deny(unsafe_code)]
+#![feature(custom_test_frameworks)]
+#![test_runner(datatest::runner)]
+
+extern crate alloc;
+
+mod cipher;
+mod rs1024;
+mod shamir;
+mod share;
+mod wordlist;
+
+pub use crate::{
+    shamir::MAX_SHARE_COUNT,
+    share::{Share, MIN_STRENGTH_BITS},
+    wordlist::WORDLIST,
+};
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use bip39::{Language, Mnemonic};
+use core::result::Result as CoreResult;
+use displaydoc::Display;
+use mc_account_keys_slip10::{Slip10Key, Slip10KeyGenerator};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+/// The iteration exponent used when none is specified, giving 20,000 PBKDF2
+/// iterations in total.
+pub const DEFAULT_ITERATION_EXPONENT: u8 = 1;
+
+/// The largest supported iteration exponent. The share format allows up to 31,
+/// but recovering such a share would take an unbounded amount of work, so
+/// shares beyond this (about 655 million PBKDF2 iterations) are rejected.
+const MAX_ITERATION_EXPONENT: u8 = 16;
+
+/// An enumeration of errors which can occur while splitting or recovering a
+/// secret
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum Error {
+    /// The word "{0}" is not in the SLIP-0039 wordlist
+    InvalidWord(String),
+    /// A share mnemonic cannot have {0} words
+    InvalidMnemonicLength(usize),
+    /// A share mnemonic has an invalid checksum
+    InvalidChecksum,
+    /// A share mnemonic has non-zero padding bits
+    InvalidPadding,
+    /// The master secret must be an even number of bytes, at least 16, not {0}
+    InvalidSecretLength(usize),
+    /// A threshold of {0} is invalid for {1} shares
+    InvalidThreshold(u8, u8),
+    /// Splitting into multiple shares with a threshold of 1 is not allowed, use
+    /// a single 1-of-1 share instead
+    SingleThresholdMultipleShares,
+    /// The iteration exponent {0} is too large
+    InvalidIterationExponent(u8),
+    /// The passphrase must only contain printable ASCII characters
+    InvalidPassphrase,
+    /// The shares do not belong to the same secret
+    ShareMismatch,
+    /// Two different shares have the same index
+    DuplicateShare,
+    /// Not enough shares were provided to recover the secret
+    InsufficientShares,
+    /// The recovered secret failed its digest check, some shares are corrupt
+    InvalidDigest,
+    /// The recovered secret is not valid BIP-39 entropy
+    InvalidEntropy,
+}
+
+/// The result type
+pub type Result<T> = CoreResult<T, Error>;
+
+/// The threshold and number of member shares in one group.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GroupSpec {
+    /// The number of member shares needed to recover the group's share
+    pub threshold: u8,
+    /// The number of member shares to create
+    pub count: u8,
+}
+
+impl GroupSpec {
+    /// Create a new M-of-N group specification.
+    pub fn new(threshold: u8, count: u8) -> Self {
+        Self { threshold, count }
+    }
+}
+
+fn check_passphrase(passphrase: &[u8]) -> Result<()> {
+    if passphrase.iter().all(|c| (32..=126).contains(c)) {
+        Ok(())
+    } else {
+        Err(Error::InvalidPassphrase)
+    }
+}
+
+/// Split a master secret into groups of SLIP-0039 mnemonic shares.
+///
+/// Recovering the secret requires `group_threshold` of the groups, and from
+/// each of those, the group's threshold of member shares. The same passphrase
+/// must be supplied on recovery: any other passphrase recovers a different,
+/// valid-looking secret.
+pub fn split_secret<R: CryptoRng + RngCore>(
+    group_threshold: u8,
+    groups: &[GroupSpec],
+    master_secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    rng: &mut R,
+) -> Result<Vec<Vec<String>>> {
+    if master_secret.len() * 8 < MIN_STRENGTH_BITS || master_secret.len() % 2 != 0 {
+        return Err(Error::InvalidSecretLength(master_secret.len()));
+    }
+    if iteration_exponent > MAX_ITERATION_EXPONENT {
+        return Err(Error::InvalidIterationExponent(iteration_exponent));
+    }
+    check_passphrase(passphrase)?;
+    if groups.len() > MAX_SHARE_COUNT as usize || group_threshold as usize > groups.len() {
+        return Err(Error::InvalidThreshold(group_threshold, groups.len() as u8));
+    }
+    if groups
+        .iter()
+        .any(|group| group.threshold == 1 && group.count > 1)
+    {
+        return Err(Error::SingleThresholdMultipleShares);
+    }
+
+    let identifier = (rng.next_u32() & 0x7FFF) as u16;
+    let encrypted_secret =
+        cipher::encrypt(master_secret, passphrase, iteration_exponent, identifier);
+
+    let group_shares =
+        shamir::split_secret(group_threshold, groups.len() as u8, &encrypted_secret, rng)?;
+
+    groups
+        .iter()
+        .zip(group_shares.iter())
+        .map(
+            |(group, (group_index, group_secret))| -> Result<Vec<String>> {
+                Ok(
+                    shamir::split_secret(group.threshold, group.count, group_secret, rng)?
+                        .into_iter()
+                        .map(|(member_index, value)| {
+                            Share {
+                                identifier,
+                                iteration_exponent,
+                                group_index: *group_index,
+                                group_threshold,
+                                group_count: groups.len() as u8,
+                                member_index,
+                                member_threshold: group.threshold,
+                                value,
+                            }
+                            .to_mnemonic()
+                        })
+                        .collect(),
+                )
+            },
+        )
+        .collect()
+}
+
+/// Split a master secret into a single group of `count` shares, any
+/// `threshold` of which recover it.
+pub fn split_secret_simple<R: CryptoRng + RngCore>(
+    threshold: u8,
+    count: u8,
+    master_secret: &[u8],
+    passphrase: &[u8],
+    rng: &mut R,
+) -> Result<Vec<String>> {
+    // A 1-of-N split is just N copies of a 1-of-1 group.
+    let (group_threshold, groups) = if threshold == 1 {
+        (1, alloc::vec![GroupSpec::new(1, 1); count as usize])
+    } else {
+        (1, alloc::vec![GroupSpec::new(threshold, count)])
+    };
+    Ok(split_secret(
+        group_threshold,
+        &groups,
+        master_secret,
+        passphrase,
+        DEFAULT_ITERATION_EXPONENT,
+        rng,
+    )?
+    .into_iter()
+    .flatten()
+    .collect())
+}
+
+/// Recover a master secret from SLIP-0039 mnemonic shares.
+///
+/// Shares beyond those needed to meet each threshold are ignored.
+pub fn combine_shares<S: AsRef<str>>(
+    mnemonics: &[S],
+    passphrase: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+    check_passphrase(passphrase)?;
+    let shares = mnemonics
+        .iter()
+        .map(|mnemonic| Share::from_mnemonic(mnemonic.as_ref()))
+        .collect::<Result<Vec<Share>>>()?;
+    let first = shares.first().ok_or(Error::InsufficientShares)?;
+    if first.iteration_exponent > MAX_ITERATION_EXPONENT {
+        return Err(Error::InvalidIterationExponent(first.iteration_exponent));
+    }
+
+    let mut groups: BTreeMap<u8, BTreeMap<u8, &Share>> = BTreeMap::new();
+    for share in &shares {
+        if share.identifier != first.identifier
+            || share.iteration_exponent != first.iteration_exponent
+            || share.group_threshold != first.group_threshold
+            || share.group_count != first.group_count
+            || share.value.len() != first.value.len()
+        {
+            return Err(Error::ShareMismatch);
+        }
+        let group = groups.entry(share.group_index).or_default();
+        if let Some(existing) = group.get(&share.member_index) {
+            if *existing != share {
+                return Err(Error::DuplicateShare);
+            }
+        }
+        if group
+            .values()
+            .any(|other| other.member_threshold != share.member_threshold)
+        {
+            return Err(Error::ShareMismatch);
+        }
+        group.insert(share.member_index, share);
+    }
+
+    let mut group_shares = Vec::new();
+    for (group_index, members) in groups.iter() {
+        let member_threshold = members
+            .values()
+            .next()
+            .map(|share| share.member_threshold)
+            .ok_or(Error::InsufficientShares)?;
+        if members.len() < member_threshold as usize {
+            continue;
+        }
+        let member_shares = members
+            .values()
+            .take(member_threshold as usize)
+            .map(|share| (share.member_index, share.value.clone()))
+            .collect::<Vec<_>>();
+        group_shares.push((
+            *group_index,
+            shamir::recover_secret(member_threshold, &member_shares)?,
+        ));
+        if group_shares.len() == first.group_threshold as usize {
+            break;
+        }
+    }
+    if group_shares.len() < first.group_threshold as usize {
+        return Err(Error::InsufficientShares);
+    }
+
+    let encrypted_secret = shamir::recover_secret(first.group_threshold, &group_shares)?;
+    Ok(cipher::decrypt(
+        &encrypted_secret,
+        passphrase,
+        first.iteration_exponent,
+        first.identifier,
+    ))
+}
+
+/// Split the entropy of a BIP-39 mnemonic into `count` SLIP-0039 shares, any
+/// `threshold` of which recover it.
+pub fn split_mnemonic<R: CryptoRng + RngCore>(
+    mnemonic: &Mnemonic,
+    threshold: u8,
+    count: u8,
+    passphrase: &[u8],
+    rng: &mut R,
+) -> Result<Vec<String>> {
+    split_secret_simple(threshold, count, mnemonic.entropy(), passphrase, rng)
+}
+
+/// Recover a BIP-39 mnemonic from SLIP-0039 shares of its entropy.
+pub fn combine_mnemonic<S: AsRef<str>>(mnemonics: &[S], passphrase: &[u8]) -> Result<Mnemonic> {
+    let entropy = combine_shares(mnemonics, passphrase)?;
+    Mnemonic::from_entropy(&entropy, Language::English).map_err(|_| Error::InvalidEntropy)
+}
+
+/// Recover the [`Slip10Key`] for the given account index from SLIP-0039 shares
+/// of a BIP-39 mnemonic's entropy.
+pub fn recover_slip10_key<S: AsRef<str>>(
+    mnemonics: &[S],
+    passphrase: &[u8],
+    account_index: u32,
+) -> Result<Slip10Key> {
+    Ok(combine_mnemonic(mnemonics, passphrase)?.derive_slip10_key(account_index))
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use alloc::boxed::Box;
+    use datatest::data;
+    use mc_account_keys::AccountKey;
+    use mc_test_vectors_account_keys::*;
+    use mc_util_test_vector::TestVector;
+    use rand_core::SeedableRng;
+    use rand_hc::Hc128Rng;
+
+    /// A 1-of-1 share from the SLIP-0039 reference test vectors.
+    #[test]
+    fn reference_single_share() {
+        let secret = combine_shares(
+            &["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard"],
+            b"TREZOR",
+        )
+        .unwrap();
+        assert_eq!(
+            &secret[..],
+            &hex::decode("bb54aac4b89dc868ba37d9cc21b2cece").unwrap()[..]
+        );
+    }
+
+    /// A 2-of-3 share set from the SLIP-0039 reference test vectors.
+    #[test]
+    fn reference_two_of_three() {
+        let secret = combine_shares(
+            &[
+                "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+                "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+            ],
+            b"TREZOR",
+        )
+        .unwrap();
+        assert_eq!(
+            &secret[..],
+            &hex::decode("b43ceb7e57a0ea8766221624d01b0864").unwrap()[..]
+        );
+    }
+
+    /// A two-level split recovers with any qualifying groups and members, and
+    /// not without enough of them.
+    #[test]
+    fn groups_roundtrip() {
+        let mut rng = Hc128Rng::from_seed([3u8; 32]);
+        let secret = [0xa5u8; 32];
+        let groups = [
+            GroupSpec::new(1, 1),
+            GroupSpec::new(2, 3),
+            GroupSpec::new(3, 5),
+        ];
+        let shares = split_secret(2, &groups, &secret, b"", 0, &mut rng).unwrap();
+        assert_eq!(shares.len(), 3);
+        assert_eq!(shares[2].len(), 5);
+
+        let recovered = combine_shares(
+            &[&shares[0][0], &shares[2][4], &shares[2][1], &shares[2][0]],
+            b"",
+        )
+        .unwrap();
+        assert_eq!(&recovered[..], &secret[..]);
+
+        let recovered =
+            combine_shares(&[&shares[1][2], &shares[2][3], &shares[1][0]], b"").unwrap_err();
+        assert_eq!(recovered, Error::InsufficientShares);
+
+        let recovered = combine_shares(
+            &[
+                &shares[1][2],
+                &shares[2][3],
+                &shares[1][0],
+                &shares[2][1],
+                &shares[2][2],
+            ],
+            b"",
+        )
+        .unwrap();
+        assert_eq!(&recovered[..], &secret[..]);
+    }
+
+    /// Shares of different secrets cannot be combined.
+    #[test]
+    fn mismatched_shares() {
+        let mut rng = Hc128Rng::from_seed([3u8; 32]);
+        let a = split_secret_simple(2, 3, &[1u8; 16], b"", &mut rng).unwrap();
+        let b = split_secret_simple(2, 3, &[1u8; 16], b"", &mut rng).unwrap();
+        assert_eq!(
+            combine_shares(&[&a[0], &b[1]], b""),
+            Err(Error::ShareMismatch)
+        );
+    }
+
+    /// Invalid split parameters are rejected.
+    #[test]
+    fn invalid_parameters() {
+        let mut rng = Hc128Rng::from_seed([3u8; 32]);
+        assert_eq!(
+            split_secret_simple(2, 3, &[1u8; 15], b"", &mut rng),
+            Err(Error::InvalidSecretLength(15))
+        );
+        assert_eq!(
+            split_secret_simple(2, 3, &[1u8; 17], b"", &mut rng),
+            Err(Error::InvalidSecretLength(17))
+        );
+        assert_eq!(
+            split_secret_simple(4, 3, &[1u8; 16], b"", &mut rng),
+            Err(Error::InvalidThreshold(4, 3))
+        );
+        assert_eq!(
+            split_secret_simple(2, 3, &[1u8; 16], "caf\u{e9}".as_bytes(), &mut rng),
+            Err(Error::InvalidPassphrase)
+        );
+        assert_eq!(
+            split_secret(1, &[GroupSpec::new(1, 3)], &[1u8; 16], b"", 0, &mut rng),
+            Err(Error::SingleThresholdMultipleShares)
+        );
+        assert_eq!(
+            split_secret(1, &[GroupSpec::new(1, 1)], &[1u8; 16], b"", 17, &mut rng),
+            Err(Error::InvalidIterationExponent(17))
+        );
+    }
+
+    /// Shares with an iteration exponent above the supported maximum are
+    /// rejected before any key derivation work is done.
+    #[test]
+    fn oversized_iteration_exponent() {
+        let mut rng = Hc128Rng::from_seed([3u8; 32]);
+        let shares = split_secret_simple(1, 1, &[1u8; 16], b"", &mut rng).unwrap();
+        let mut share = Share::from_mnemonic(&shares[0]).unwrap();
+        share.iteration_exponent = 31;
+        assert_eq!(
+            combine_shares(&[share.to_mnemonic()], b""),
+            Err(Error::InvalidIterationExponent(31))
+        );
+    }
+
+    #[data(AcctPrivKeysFromSlip39Shares::from_jsonl("../../test-vectors/vectors"))]
+    #[test]
+    fn acct_priv_keys_from_slip39_shares(case: AcctPrivKeysFromSlip39Shares) {
+        let passphrase = case.passphrase.as_bytes();
+        assert_eq!(
+            &combine_shares(&case.shares, passphrase).unwrap()[..],
+            &case.entropy[..]
+        );
+        assert_eq!(
+            combine_mnemonic(&case.shares, passphrase).unwrap().phrase(),
+            case.mnemonic
+        );
+
+        let account_key = AccountKey::from(
+            recover_slip10_key(&case.shares, passphrase, case.account_index).unwrap(),
+        );
+        assert_eq!(
+            account_key.view_private_key().to_bytes(),
+            case.view_private_key
+        );
+        assert_eq!(
+            account_key.spend_private_key().to_bytes(),
+            case.spend_private_key
+        );
+    }
+
+    /// A mnemonic split into shares recovers to the same account key.
+    #[test]
+    fn mnemonic_into_account_key() {
+        let mut rng = Hc128Rng::from_seed([3u8; 32]);
+        let mnemonic = Mnemonic::from_phrase(
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            Language::English,
+        )
+        .unwrap();
+        let expected = AccountKey::from(mnemonic.clone().derive_slip10_key(3));
+
+        let shares = split_mnemonic(&mnemonic, 3, 5, b"", &mut rng).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        let key = recover_slip10_key(&[&shares[4], &shares[0], &shares[2]], b"", 3).unwrap();
+        assert_eq!(AccountKey::from(key), expected);
+        assert_eq!(
+            combine_mnemonic(&[&shares[1], &shares[3], &shares[4]], b"")
+                .unwrap()
+                .phrase(),
+            mnemonic.phrase()
+        );
+    }
+}
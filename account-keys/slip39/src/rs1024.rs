@@ -0,0 +1,84 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! The RS1024 checksum used by SLIP-0039 mnemonics.
+//!
+//! This is a Reed-Solomon code over GF(1024) which guarantees detection of
+//! any error affecting at most 3 words, and has less than a 1 in 10^9 chance
+//! of failing to detect more errors.
+
+/// The customization string mixed into every checksum.
+const CUSTOMIZATION_STRING: &[u8] = b"shamir";
+
+/// The number of words used by the checksum.
+pub const CHECKSUM_LENGTH_WORDS: usize = 3;
+
+const GENERATOR: [u32; 10] = [
+    0xE0_E040,
+    0x1C1_C080,
+    0x383_8100,
+    0x707_0200,
+    0xE0E_0009,
+    0x1C0C_2412,
+    0x3808_6C24,
+    0x3090_FC48,
+    0x21B1_F890,
+    0x3F3_F120,
+];
+
+fn polymod<I: IntoIterator<Item = u16>>(values: I) -> u32 {
+    let mut chk = 1u32;
+    for value in CUSTOMIZATION_STRING
+        .iter()
+        .map(|byte| *byte as u16)
+        .chain(values)
+    {
+        let b = chk >> 20;
+        chk = ((chk & 0xF_FFFF) << 10) ^ value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+/// Compute the checksum words to append to the given data words.
+pub fn create_checksum(data: &[u16]) -> [u16; CHECKSUM_LENGTH_WORDS] {
+    let polymod = polymod(
+        data.iter()
+            .copied()
+            .chain([0u16; CHECKSUM_LENGTH_WORDS].iter().copied()),
+    ) ^ 1;
+    let mut result = [0u16; CHECKSUM_LENGTH_WORDS];
+    for (i, word) in result.iter_mut().enumerate() {
+        *word = ((polymod >> (10 * (CHECKSUM_LENGTH_WORDS - 1 - i))) & 1023) as u16;
+    }
+    result
+}
+
+/// Check that data words ending with their checksum words are valid.
+pub fn verify_checksum(data: &[u16]) -> bool {
+    polymod(data.iter().copied()) == 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A checksum created over some data should verify, and should fail to
+    /// verify once any word is changed.
+    #[test]
+    fn roundtrip() {
+        let mut data = [1u16, 2, 3, 1000, 1023, 0, 0, 0];
+        let checksum = create_checksum(&data[..5]);
+        data[5..].copy_from_slice(&checksum);
+        assert!(verify_checksum(&data));
+
+        for i in 0..data.len() {
+            let mut corrupted = data;
+            corrupted[i] ^= 1;
+            assert!(!verify_checksum(&corrupted));
+        }
+    }
+}
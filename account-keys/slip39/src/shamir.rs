@@ -0,0 +1,198 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Shamir's secret sharing over GF(256), as specified by SLIP-0039.
+//!
+//! Secrets are split bytewise using the Rijndael field, with a 4-byte HMAC
+//! digest of the secret stored as a share at a fixed x-coordinate so that
+//! recovering from an inconsistent set of shares is detected.
+
+use crate::{Error, Result};
+use alloc::vec::Vec;
+use hmac::{Hmac, Mac, NewMac};
+use rand_core::{CryptoRng, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// The maximum number of shares a secret can be split into.
+pub const MAX_SHARE_COUNT: u8 = 16;
+
+/// The x-coordinate of the share holding the secret digest.
+const DIGEST_INDEX: u8 = 254;
+
+/// The x-coordinate of the secret itself.
+const SECRET_INDEX: u8 = 255;
+
+/// The number of bytes of digest stored alongside the secret.
+const DIGEST_LENGTH_BYTES: usize = 4;
+
+/// A single share of a secret: an x-coordinate and the polynomial values at
+/// that point.
+pub type RawShare = (u8, Zeroizing<Vec<u8>>);
+
+/// Exponent and logarithm tables for GF(256) with generator 3.
+struct Tables {
+    exp: [u8; 255],
+    log: [u8; 256],
+}
+
+impl Tables {
+    const fn new() -> Self {
+        let mut exp = [0u8; 255];
+        let mut log = [0u8; 256];
+        let mut poly: u16 = 1;
+        let mut i = 0;
+        while i < 255 {
+            exp[i] = poly as u8;
+            log[poly as usize] = i as u8;
+            // Multiply by the generator (x + 1), reducing by the Rijndael
+            // polynomial x^8 + x^4 + x^3 + x + 1.
+            poly = (poly << 1) ^ poly;
+            if poly & 0x100 != 0 {
+                poly ^= 0x11B;
+            }
+            i += 1;
+        }
+        Self { exp, log }
+    }
+}
+
+const TABLES: Tables = Tables::new();
+
+/// Evaluate the polynomial passing through the given shares at `x`.
+fn interpolate(shares: &[RawShare], x: u8) -> Result<Zeroizing<Vec<u8>>> {
+    if let Some((_, value)) = shares.iter().find(|(share_x, _)| *share_x == x) {
+        return Ok(value.clone());
+    }
+    let length = shares.first().ok_or(Error::InsufficientShares)?.1.len();
+    if shares.iter().any(|(_, value)| value.len() != length) {
+        return Err(Error::ShareMismatch);
+    }
+
+    let log = |value: u8| TABLES.log[value as usize] as u32;
+    let log_prod: u32 = shares.iter().map(|(share_x, _)| log(share_x ^ x)).sum();
+
+    let mut result = Zeroizing::new(alloc::vec![0u8; length]);
+    for (share_x, value) in shares {
+        let others: u32 = shares
+            .iter()
+            .map(|(other_x, _)| log(share_x ^ other_x))
+            .sum();
+        // Work modulo 255 while keeping the intermediate values positive.
+        let log_basis =
+            (log_prod + 255 * (shares.len() as u32 + 1) - log(share_x ^ x) - others) % 255;
+        for (out, byte) in result.iter_mut().zip(value.iter()) {
+            if *byte != 0 {
+                *out ^= TABLES.exp[((log(*byte) + log_basis) % 255) as usize];
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn create_digest(random_data: &[u8], secret: &[u8]) -> [u8; DIGEST_LENGTH_BYTES] {
+    let mut mac = Hmac::<Sha256>::new_varkey(random_data).expect("HMAC accepts all key sizes");
+    mac.update(secret);
+    let mut digest = [0u8; DIGEST_LENGTH_BYTES];
+    digest.copy_from_slice(&mac.finalize().into_bytes()[..DIGEST_LENGTH_BYTES]);
+    digest
+}
+
+/// Split a secret into `count` shares, any `threshold` of which recover it.
+pub fn split_secret<R: CryptoRng + RngCore>(
+    threshold: u8,
+    count: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Vec<RawShare>> {
+    if threshold == 0 || threshold > count || count > MAX_SHARE_COUNT {
+        return Err(Error::InvalidThreshold(threshold, count));
+    }
+    if threshold == 1 {
+        return Ok((0..count)
+            .map(|i| (i, Zeroizing::new(secret.to_vec())))
+            .collect());
+    }
+
+    let random_share_count = threshold - 2;
+    let mut shares: Vec<RawShare> = (0..random_share_count)
+        .map(|i| {
+            let mut value = Zeroizing::new(alloc::vec![0u8; secret.len()]);
+            rng.fill_bytes(&mut value);
+            (i, value)
+        })
+        .collect();
+
+    let mut digest_share = Zeroizing::new(alloc::vec![0u8; secret.len()]);
+    rng.fill_bytes(&mut digest_share[DIGEST_LENGTH_BYTES..]);
+    let digest = create_digest(&digest_share[DIGEST_LENGTH_BYTES..], secret);
+    digest_share[..DIGEST_LENGTH_BYTES].copy_from_slice(&digest);
+
+    let mut base_shares = shares.clone();
+    base_shares.push((DIGEST_INDEX, digest_share));
+    base_shares.push((SECRET_INDEX, Zeroizing::new(secret.to_vec())));
+
+    for i in random_share_count..count {
+        shares.push((i, interpolate(&base_shares, i)?));
+    }
+    Ok(shares)
+}
+
+/// Recover a secret from exactly `threshold` shares, checking its digest.
+pub fn recover_secret(threshold: u8, shares: &[RawShare]) -> Result<Zeroizing<Vec<u8>>> {
+    if threshold == 1 {
+        return shares
+            .first()
+            .map(|(_, value)| value.clone())
+            .ok_or(Error::InsufficientShares);
+    }
+
+    let secret = interpolate(shares, SECRET_INDEX)?;
+    let digest_share = interpolate(shares, DIGEST_INDEX)?;
+    let digest = create_digest(&digest_share[DIGEST_LENGTH_BYTES..], &secret);
+    if digest[..] != digest_share[..DIGEST_LENGTH_BYTES] {
+        return Err(Error::InvalidDigest);
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::SeedableRng;
+    use rand_hc::Hc128Rng;
+
+    /// Every subset of `threshold` shares should recover the secret.
+    #[test]
+    fn split_and_recover() {
+        let mut rng = Hc128Rng::from_seed([7u8; 32]);
+        let secret = [42u8; 16];
+        let shares = split_secret(3, 5, &secret, &mut rng).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for a in 0..5 {
+            for b in (a + 1)..5 {
+                for c in (b + 1)..5 {
+                    let subset = [shares[a].clone(), shares[b].clone(), shares[c].clone()];
+                    assert_eq!(&recover_secret(3, &subset).unwrap()[..], &secret[..]);
+                }
+            }
+        }
+    }
+
+    /// Too few shares recover garbage, which the digest check rejects.
+    #[test]
+    fn insufficient_shares() {
+        let mut rng = Hc128Rng::from_seed([7u8; 32]);
+        let shares = split_secret(3, 5, &[42u8; 16], &mut rng).unwrap();
+        assert_eq!(recover_secret(3, &shares[..2]), Err(Error::InvalidDigest));
+    }
+
+    /// Invalid thresholds are rejected.
+    #[test]
+    fn invalid_threshold() {
+        let mut rng = Hc128Rng::from_seed([7u8; 32]);
+        assert!(split_secret(0, 5, &[0u8; 16], &mut rng).is_err());
+        assert!(split_secret(6, 5, &[0u8; 16], &mut rng).is_err());
+        assert!(split_secret(2, 17, &[0u8; 16], &mut rng).is_err());
+    }
+}
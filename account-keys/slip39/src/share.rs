@@ -0,0 +1,244 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! The SLIP-0039 share structure and its mnemonic encoding.
+
+use crate::{
+    rs1024::{create_checksum, verify_checksum, CHECKSUM_LENGTH_WORDS},
+    wordlist::WORDLIST,
+    Error, Result,
+};
+use alloc::{string::String, vec::Vec};
+use zeroize::Zeroizing;
+
+/// The number of bits encoded by each word.
+const RADIX_BITS: u32 = 10;
+
+/// The number of words used by the share metadata before the share value.
+const HEADER_LENGTH_WORDS: usize = 4;
+
+/// The minimum number of bits in a master secret.
+pub const MIN_STRENGTH_BITS: usize = 128;
+
+/// The minimum number of words in a share mnemonic.
+const MIN_MNEMONIC_LENGTH_WORDS: usize = HEADER_LENGTH_WORDS
+    + CHECKSUM_LENGTH_WORDS
+    + (MIN_STRENGTH_BITS + RADIX_BITS as usize - 1) / RADIX_BITS as usize;
+
+/// A single SLIP-0039 member share.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Share {
+    /// A random 15-bit identifier common to all shares of a secret
+    pub identifier: u16,
+    /// The exponent of the PBKDF2 iteration count used for encryption
+    pub iteration_exponent: u8,
+    /// The index of this share's group
+    pub group_index: u8,
+    /// The number of groups required to recover the secret
+    pub group_threshold: u8,
+    /// The total number of groups
+    pub group_count: u8,
+    /// The index of this share within its group
+    pub member_index: u8,
+    /// The number of member shares required to recover the group secret
+    pub member_threshold: u8,
+    /// The share value
+    pub value: Zeroizing<Vec<u8>>,
+}
+
+/// Packs integers of arbitrary bit widths into 10-bit words.
+#[derive(Default)]
+struct WordWriter {
+    words: Vec<u16>,
+    acc: u32,
+    bits: u32,
+}
+
+impl WordWriter {
+    fn push(&mut self, value: u32, bits: u32) {
+        self.acc = (self.acc << bits) | value;
+        self.bits += bits;
+        while self.bits >= RADIX_BITS {
+            self.bits -= RADIX_BITS;
+            self.words.push(((self.acc >> self.bits) & 1023) as u16);
+        }
+        self.acc &= (1 << self.bits) - 1;
+    }
+}
+
+/// Unpacks integers of arbitrary bit widths from 10-bit words.
+struct WordReader<'a> {
+    words: &'a [u16],
+    acc: u32,
+    bits: u32,
+}
+
+impl<'a> WordReader<'a> {
+    fn new(words: &'a [u16]) -> Self {
+        Self {
+            words,
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    fn read(&mut self, bits: u32) -> u32 {
+        while self.bits < bits {
+            self.acc = (self.acc << RADIX_BITS) | self.words[0] as u32;
+            self.words = &self.words[1..];
+            self.bits += RADIX_BITS;
+        }
+        self.bits -= bits;
+        let result = self.acc >> self.bits;
+        self.acc &= (1 << self.bits) - 1;
+        result
+    }
+}
+
+impl Share {
+    /// Encode this share as a mnemonic phrase.
+    pub fn to_mnemonic(&self) -> String {
+        let mut writer = WordWriter::default();
+        writer.push(self.identifier as u32, 15);
+        writer.push(self.iteration_exponent as u32, 5);
+        writer.push(self.group_index as u32, 4);
+        writer.push(self.group_threshold as u32 - 1, 4);
+        writer.push(self.group_count as u32 - 1, 4);
+        writer.push(self.member_index as u32, 4);
+        writer.push(self.member_threshold as u32 - 1, 4);
+
+        // The value is left-padded with zero bits to a whole number of words.
+        let value_bits = self.value.len() as u32 * 8;
+        writer.push(0, (RADIX_BITS - value_bits % RADIX_BITS) % RADIX_BITS);
+        for byte in self.value.iter() {
+            writer.push(*byte as u32, 8);
+        }
+
+        let mut words = writer.words;
+        let checksum = create_checksum(&words);
+        words.extend_from_slice(&checksum);
+
+        let mut phrase = String::new();
+        for (i, word) in words.iter().enumerate() {
+            if i > 0 {
+                phrase.push(' ');
+            }
+            phrase.push_str(WORDLIST[*word as usize]);
+        }
+        phrase
+    }
+
+    /// Decode a share from a mnemonic phrase, checking its checksum.
+    pub fn from_mnemonic(mnemonic: &str) -> Result<Self> {
+        let words = mnemonic
+            .split_whitespace()
+            .map(|word| {
+                let word = word.to_ascii_lowercase();
+                WORDLIST
+                    .binary_search(&word.as_str())
+                    .map(|index| index as u16)
+                    .map_err(|_| Error::InvalidWord(word))
+            })
+            .collect::<Result<Vec<u16>>>()?;
+
+        if words.len() < MIN_MNEMONIC_LENGTH_WORDS {
+            return Err(Error::InvalidMnemonicLength(words.len()));
+        }
+        let value_words = words.len() - HEADER_LENGTH_WORDS - CHECKSUM_LENGTH_WORDS;
+        let padding_bits = (RADIX_BITS as usize * value_words) % 16;
+        if padding_bits > 8 {
+            return Err(Error::InvalidMnemonicLength(words.len()));
+        }
+        if !verify_checksum(&words) {
+            return Err(Error::InvalidChecksum);
+        }
+
+        let mut reader = WordReader::new(&words[..words.len() - CHECKSUM_LENGTH_WORDS]);
+        let identifier = reader.read(15) as u16;
+        let iteration_exponent = reader.read(5) as u8;
+        let group_index = reader.read(4) as u8;
+        let group_threshold = reader.read(4) as u8 + 1;
+        let group_count = reader.read(4) as u8 + 1;
+        let member_index = reader.read(4) as u8;
+        let member_threshold = reader.read(4) as u8 + 1;
+        if group_threshold > group_count {
+            return Err(Error::InvalidThreshold(group_threshold, group_count));
+        }
+
+        if reader.read(padding_bits as u32) != 0 {
+            return Err(Error::InvalidPadding);
+        }
+        let value_bytes = (RADIX_BITS as usize * value_words - padding_bits) / 8;
+        let value = Zeroizing::new(
+            (0..value_bytes)
+                .map(|_| reader.read(8) as u8)
+                .collect::<Vec<u8>>(),
+        );
+
+        Ok(Self {
+            identifier,
+            iteration_exponent,
+            group_index,
+            group_threshold,
+            group_count,
+            member_index,
+            member_threshold,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn share(value: Vec<u8>) -> Share {
+        Share {
+            identifier: 0x5a5a,
+            iteration_exponent: 1,
+            group_index: 2,
+            group_threshold: 2,
+            group_count: 3,
+            member_index: 4,
+            member_threshold: 3,
+            value: Zeroizing::new(value),
+        }
+    }
+
+    /// Shares of 128- and 256-bit secrets round-trip through their mnemonics.
+    #[test]
+    fn mnemonic_roundtrip() {
+        for value in &[[0x11u8; 16].to_vec(), [0xeeu8; 32].to_vec()] {
+            let share = share(value.clone());
+            let mnemonic = share.to_mnemonic();
+            assert_eq!(Share::from_mnemonic(&mnemonic).unwrap(), share);
+        }
+        assert_eq!(
+            share([0u8; 16].to_vec()).to_mnemonic().split(' ').count(),
+            20
+        );
+        assert_eq!(
+            share([0u8; 32].to_vec()).to_mnemonic().split(' ').count(),
+            33
+        );
+    }
+
+    /// Changing any word of a mnemonic is detected.
+    #[test]
+    fn corrupted_word() {
+        let mnemonic = share([0x11u8; 16].to_vec()).to_mnemonic();
+        let mut words: Vec<&str> = mnemonic.split(' ').collect();
+        words[7] = if words[7] == "academic" {
+            "acid"
+        } else {
+            "academic"
+        };
+        assert_eq!(
+            Share::from_mnemonic(&words.join(" ")),
+            Err(Error::InvalidChecksum)
+        );
+        assert!(matches!(
+            Share::from_mnemonic("academic acid nonsense"),
+            Err(Error::InvalidWord(_))
+        ));
+    }
+}
@@ -4,19 +4,32 @@
 
 #![feature(proc_macro_hygiene, decl_macro)]
 
-use grpcio::ChannelBuilder;
-use mc_common::logger::{create_app_logger, log, o};
-use mc_util_grpc::{admin, admin_grpc::AdminApiClient, ConnectionUriGrpcioChannel, Empty};
+mod origin;
+mod rust_log;
+
+use grpcio::{ChannelBuilder, Error as GrpcError, RpcStatusCode};
+use mc_common::{
+    logger::{create_app_logger, log, o},
+    time::SystemTimeProvider,
+};
+use mc_util_grpc::{
+    admin, admin_grpc::AdminApiClient, health_api::HealthCheckRequest,
+    health_api_grpc::HealthClient, AnonymousAuthenticator, Authenticator, BasicCredentials,
+    ConnectionUriGrpcioChannel, Empty, HealthCheckStatus, PasswordAuthenticator,
+    TokenAuthenticator,
+};
 use mc_util_uri::AdminUri;
 use rocket::{
-    get, post,
-    request::Form,
-    response::{content, Redirect},
-    routes, FromForm,
+    catch, catchers, get,
+    http::{ContentType, Status},
+    post,
+    request::{self, Form, FromRequest, Request},
+    response::{content, status, Redirect, Response},
+    routes, FromForm, Outcome,
 };
 use rocket_contrib::json::Json;
 use serde_derive::Serialize;
-use std::{convert::TryFrom, sync::Arc};
+use std::{convert::TryFrom, sync::Arc, time::Duration};
 use structopt::StructOpt;
 
 #[derive(Clone, Debug, StructOpt)]
@@ -36,14 +49,138 @@ pub struct Config {
     /// Service admin URI to connect to.
     #[structopt(long)]
     pub admin_uri: AdminUri,
+
+    /// Require HTTP basic authentication with this username and password,
+    /// given as `username:password`.
+    #[structopt(long, env = "MC_ADMIN_BASIC_AUTH", parse(try_from_str=parse_basic_auth), conflicts_with = "auth_token_secret")]
+    pub basic_auth: Option<(String, String)>,
+
+    /// Require tokens generated with this shared secret (64 hex characters)
+    /// in a basic Authorization header.
+    #[structopt(long, env = "MC_ADMIN_AUTH_TOKEN_SECRET", parse(try_from_str=hex::FromHex::from_hex))]
+    pub auth_token_secret: Option<[u8; 32]>,
+
+    /// Maximal token lifetime, in seconds (only relevant when
+    /// --auth-token-secret is used).
+    #[structopt(long, default_value = "86400", parse(try_from_str=parse_duration_in_seconds))]
+    pub auth_token_max_lifetime: Duration,
+}
+
+fn parse_basic_auth(src: &str) -> Result<(String, String), String> {
+    let mut parts = src.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(username), Some(password)) if !username.is_empty() && !password.is_empty() => {
+            Ok((username.to_owned(), password.to_owned()))
+        }
+        _ => Err("expected username:password".to_owned()),
+    }
+}
+
+fn parse_duration_in_seconds(src: &str) -> Result<Duration, std::num::ParseIntError> {
+    Ok(Duration::from_secs(src.parse()?))
+}
+
+impl Config {
+    /// The authenticator for HTTP requests. Without credentials configured,
+    /// every request is allowed.
+    fn authenticator(&self) -> Arc<dyn Authenticator + Send + Sync> {
+        if let Some((username, password)) = self.basic_auth.as_ref() {
+            Arc::new(PasswordAuthenticator::new(username, password))
+        } else if let Some(shared_secret) = self.auth_token_secret {
+            Arc::new(TokenAuthenticator::new(
+                shared_secret,
+                self.auth_token_max_lifetime,
+                SystemTimeProvider::default(),
+            ))
+        } else {
+            Arc::new(AnonymousAuthenticator::default())
+        }
+    }
 }
 
 struct State {
     pub admin_api_client: AdminApiClient,
+    pub health_client: HealthClient,
+    pub authenticator: Arc<dyn Authenticator + Send + Sync>,
+}
+
+/// A request guard requiring the request to carry valid credentials in its
+/// Authorization header.
+struct Authenticated;
+
+impl<'a, 'r> FromRequest<'a, 'r> for Authenticated {
+    type Error = String;
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let state = match request.guard::<rocket::State<State>>() {
+            Outcome::Success(state) => state,
+            _ => {
+                return Outcome::Failure((
+                    Status::InternalServerError,
+                    "Missing gateway state".to_owned(),
+                ))
+            }
+        };
+
+        let result = request
+            .headers()
+            .get_one("Authorization")
+            .map(|header| BasicCredentials::try_from(header.as_bytes()))
+            .transpose()
+            .map_err(|err| err.to_string())
+            .and_then(|credentials| {
+                state
+                    .authenticator
+                    .authenticate(credentials)
+                    .map_err(|err| err.to_string())
+            });
+
+        match result {
+            Ok(_) => Outcome::Success(Authenticated),
+            Err(err) => Outcome::Failure((Status::Unauthorized, err)),
+        }
+    }
+}
+
+/// A request guard for state-changing requests, requiring their `Origin`
+/// header (or, without one, their `Referer` header) to match the `Host`
+/// header. Browsers attach cached basic credentials to cross-site form posts,
+/// so authentication alone does not stop other sites from submitting them.
+struct SameOrigin;
+
+impl<'a, 'r> FromRequest<'a, 'r> for SameOrigin {
+    type Error = String;
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let headers = request.headers();
+        let source = headers
+            .get_one("Origin")
+            .or_else(|| headers.get_one("Referer"));
+
+        match (source, headers.get_one("Host")) {
+            (Some(source), Some(host)) if origin::is_same_origin(source, host) => {
+                Outcome::Success(SameOrigin)
+            }
+            _ => Outcome::Failure((
+                Status::Forbidden,
+                "Requests must have an Origin or Referer header matching the Host header"
+                    .to_owned(),
+            )),
+        }
+    }
+}
+
+/// Ask browsers to prompt for credentials.
+#[catch(401)]
+fn unauthorized() -> Response<'static> {
+    Response::build()
+        .status(Status::Unauthorized)
+        .raw_header("WWW-Authenticate", "Basic realm=\"admin\"")
+        .finalize()
 }
 
 #[get("/")]
-fn index() -> content::Html<String> {
+fn index(_auth: Authenticated) -> content::Html<String> {
     content::Html(include_str!("../templates/index.html").to_owned())
 }
 
@@ -77,7 +214,10 @@ impl TryFrom<&admin::GetInfoResponse> for JsonInfoResponse {
 }
 
 #[get("/info")]
-fn info(state: rocket::State<State>) -> Result<Json<JsonInfoResponse>, String> {
+fn info(
+    state: rocket::State<State>,
+    _auth: Authenticated,
+) -> Result<Json<JsonInfoResponse>, String> {
     let info = state
         .admin_api_client
         .get_info(&Empty::new())
@@ -91,9 +231,30 @@ struct SetRustLogForm {
     rust_log: String,
 }
 
+/// Service-specific status, or null if the service does not report any.
+#[get("/status")]
+fn service_status(
+    state: rocket::State<State>,
+    _auth: Authenticated,
+) -> Result<Json<serde_json::Value>, String> {
+    match state.admin_api_client.get_service_status(&Empty::new()) {
+        Ok(resp) => Ok(Json(serde_json::from_str(&resp.status_json).map_err(
+            |err| format!("failed parsing status '{}': {}", resp.status_json, err),
+        )?)),
+        Err(GrpcError::RpcFailure(rpc_status))
+            if rpc_status.code() == RpcStatusCode::UNIMPLEMENTED =>
+        {
+            Ok(Json(serde_json::Value::Null))
+        }
+        Err(err) => Err(format!("Failed getting status: {}", err)),
+    }
+}
+
 #[post("/set-rust-log", data = "<form>")]
 fn set_rust_log(
     state: rocket::State<State>,
+    _auth: Authenticated,
+    _origin: SameOrigin,
     form: Form<SetRustLogForm>,
 ) -> Result<Redirect, String> {
     let mut req = admin::SetRustLogRequest::new();
@@ -107,13 +268,71 @@ fn set_rust_log(
     Ok(Redirect::to("/"))
 }
 
+#[derive(FromForm)]
+struct SetLogLevelForm {
+    module: String,
+    level: String,
+}
+
+/// Change the log level of a single module, keeping the rest of RUST_LOG.
+#[post("/set-log-level", data = "<form>")]
+fn set_log_level(
+    state: rocket::State<State>,
+    _auth: Authenticated,
+    _origin: SameOrigin,
+    form: Form<SetLogLevelForm>,
+) -> Result<Redirect, String> {
+    let info = state
+        .admin_api_client
+        .get_info(&Empty::new())
+        .map_err(|err| format!("Failed getting info: {}", err))?;
+
+    let mut req = admin::SetRustLogRequest::new();
+    req.set_rust_log(rust_log::set_module_level(
+        &info.rust_log,
+        &form.module,
+        &form.level,
+    )?);
+
+    let _resp = state
+        .admin_api_client
+        .set_rust_log(&req)
+        .map_err(|err| format!("failed setting rust_log: {}", err))?;
+
+    Ok(Redirect::to("/"))
+}
+
+/// Prometheus metrics, in the text exposition format.
 #[get("/metrics")]
-fn metrics(state: rocket::State<State>) -> Result<String, String> {
+fn metrics(
+    state: rocket::State<State>,
+    _auth: Authenticated,
+) -> Result<content::Content<String>, String> {
     let resp = state
         .admin_api_client
         .get_prometheus_metrics(&Empty::new())
         .map_err(|err| format!("failed getting metrics: {}", err))?;
-    Ok(resp.metrics)
+    let content_type = ContentType::with_params("text", "plain", ("version", "0.0.4"));
+    Ok(content::Content(content_type, resp.metrics))
+}
+
+/// The service's health check, for load balancers and orchestrators. This does
+/// not require authentication.
+#[get("/healthz")]
+fn healthz(state: rocket::State<State>) -> status::Custom<String> {
+    match state.health_client.check(&HealthCheckRequest::new()) {
+        Ok(resp) if resp.get_status() == HealthCheckStatus::SERVING => {
+            status::Custom(Status::Ok, "SERVING".to_owned())
+        }
+        Ok(resp) => status::Custom(
+            Status::ServiceUnavailable,
+            format!("{:?}", resp.get_status()),
+        ),
+        Err(err) => status::Custom(
+            Status::ServiceUnavailable,
+            format!("Failed checking health: {}", err),
+        ),
+    }
 }
 
 fn main() {
@@ -134,7 +353,9 @@ fn main() {
     let env = Arc::new(grpcio::EnvBuilder::new().build());
     let ch =
         ChannelBuilder::default_channel_builder(env).connect_to_uri(&config.admin_uri, &logger);
-    let admin_api_client = AdminApiClient::new(ch);
+    let admin_api_client = AdminApiClient::new(ch.clone());
+    let health_client = HealthClient::new(ch);
+    let authenticator = config.authenticator();
 
     let rocket_config = rocket::Config::build(rocket::config::Environment::Production)
         .address(&config.listen_host)
@@ -142,7 +363,23 @@ fn main() {
         .unwrap();
 
     rocket::custom(rocket_config)
-        .mount("/", routes![index, info, set_rust_log, metrics])
-        .manage(State { admin_api_client })
+        .mount(
+            "/",
+            routes![
+                index,
+                info,
+                service_status,
+                set_rust_log,
+                set_log_level,
+                metrics,
+                healthz
+            ],
+        )
+        .register(catchers![unauthorized])
+        .manage(State {
+            admin_api_client,
+            health_client,
+            authenticator,
+        })
         .launch();
 }
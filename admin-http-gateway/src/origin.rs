@@ -0,0 +1,57 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Same-origin checks for state-changing requests.
+
+/// Whether an `Origin` or `Referer` header value names the same host (and
+/// port) as the request's `Host` header.
+///
+/// # Arguments
+/// * `source` - The `Origin` or `Referer` header value, e.g. `http://127.0.0.1:9090/`.
+/// * `host` - The `Host` header value, e.g. `127.0.0.1:9090`.
+pub fn is_same_origin(source: &str, host: &str) -> bool {
+    let authority = match source
+        .strip_prefix("http://")
+        .or_else(|| source.strip_prefix("https://"))
+    {
+        Some(rest) => rest.split(|c| c == '/' || c == '?' || c == '#').next(),
+        // Opaque origins (`null`) and other schemes never match.
+        None => None,
+    };
+
+    match authority {
+        Some(authority) => !authority.is_empty() && authority.eq_ignore_ascii_case(host.trim()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_origin() {
+        assert!(is_same_origin("http://127.0.0.1:9090", "127.0.0.1:9090"));
+        assert!(is_same_origin(
+            "https://admin.example.com",
+            "Admin.Example.com"
+        ));
+        assert!(is_same_origin(
+            "http://127.0.0.1:9090/?tab=logs#top",
+            "127.0.0.1:9090"
+        ));
+    }
+
+    #[test]
+    fn cross_origin() {
+        assert!(!is_same_origin("http://evil.example.com", "127.0.0.1:9090"));
+        assert!(!is_same_origin("http://127.0.0.1:9091/", "127.0.0.1:9090"));
+        assert!(!is_same_origin("http://127.0.0.1", "127.0.0.1:9090"));
+        assert!(!is_same_origin(
+            "http://127.0.0.1:9090.evil.example.com/",
+            "127.0.0.1:9090"
+        ));
+        assert!(!is_same_origin("null", "127.0.0.1:9090"));
+        assert!(!is_same_origin("file:///index.html", ""));
+        assert!(!is_same_origin("http://", ""));
+    }
+}
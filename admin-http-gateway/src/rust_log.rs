@@ -0,0 +1,93 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Editing of RUST_LOG directives, e.g. `info,mc_consensus_service=debug`.
+
+/// Set the log level of a single module in a RUST_LOG value, leaving the
+/// directives for other modules untouched.
+///
+/// # Arguments
+/// * `rust_log` - The current RUST_LOG value.
+/// * `module` - The module path, or an empty string for the default level.
+/// * `level` - The new level, or an empty string to remove the directive.
+pub fn set_module_level(rust_log: &str, module: &str, level: &str) -> Result<String, String> {
+    let module = module.trim();
+    let level = level.trim();
+
+    if !module
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    {
+        return Err(format!("Invalid module name '{}'", module));
+    }
+    if !level.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!("Invalid log level '{}'", level));
+    }
+
+    let mut directives: Vec<String> = rust_log
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .filter(|directive| directive_module(directive) != module)
+        .map(str::to_owned)
+        .collect();
+
+    if !level.is_empty() {
+        let directive = if module.is_empty() {
+            level.to_owned()
+        } else {
+            format!("{}={}", module, level)
+        };
+        // Keep the default level first, as it is usually written.
+        if module.is_empty() {
+            directives.insert(0, directive);
+        } else {
+            directives.push(directive);
+        }
+    }
+
+    Ok(directives.join(","))
+}
+
+/// The module a directive applies to, or an empty string for a directive
+/// setting the default level.
+fn directive_module(directive: &str) -> &str {
+    match directive.find('=') {
+        Some(index) => directive[..index].trim(),
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn set_module_levels() {
+        assert_eq!(
+            set_module_level("info", "mc_consensus_service", "debug").unwrap(),
+            "info,mc_consensus_service=debug"
+        );
+        assert_eq!(
+            set_module_level("info,mc_ledger_db=warn,mc_peers=trace", "mc_peers", "error").unwrap(),
+            "info,mc_ledger_db=warn,mc_peers=error"
+        );
+        assert_eq!(
+            set_module_level("info,mc_peers=trace", "", "debug").unwrap(),
+            "debug,mc_peers=trace"
+        );
+        assert_eq!(
+            set_module_level("info,mc_peers=trace", "mc_peers", "").unwrap(),
+            "info"
+        );
+        assert_eq!(
+            set_module_level("", "mc_peers", "info").unwrap(),
+            "mc_peers=info"
+        );
+    }
+
+    #[test]
+    fn reject_invalid_directives() {
+        assert!(set_module_level("info", "mc_peers,other", "debug").is_err());
+        assert!(set_module_level("info", "mc_peers", "debug,other=trace").is_err());
+    }
+}
@@ -34,6 +34,16 @@ pub enum Error {
     EncryptError(CipherError),
     /// The message could not be decrypted: {0}
     DecryptError(CipherError),
+    /// The session key could not be rotated: {0}
+    Rekey(CipherError),
+    /// The rekey message contained unexpected data
+    UnexpectedRekeyPayload,
+    /// The message was not correctly framed
+    InvalidFrame,
+    /// The resumption ticket has expired
+    ResumptionTicketExpired,
+    /// The remote identity does not match the resumption ticket
+    ResumptionIdentityMismatch,
     /// Unknown error while initiating a new AKE
     Unknown,
 }
@@ -5,11 +5,12 @@
 use crate::mealy::{Input as MealyInput, Output as MealyOutput};
 use aead::{AeadMut, NewAead};
 use alloc::vec::Vec;
-use core::marker::PhantomData;
+use core::{marker::PhantomData, time::Duration};
 use digest::{BlockInput, FixedOutput, Reset, Update};
 use mc_attest_core::{VerificationReport, Verifier};
 use mc_crypto_keys::Kex;
 use mc_crypto_noise::{HandshakeIX, HandshakeNX, HandshakePattern, NoiseCipher, ProtocolName};
+use serde::{Deserialize, Serialize};
 
 /// An input used to inject the relevant local data needed to transform Start
 /// into an AuthPending for node-to-node authentication.
@@ -313,9 +314,74 @@ impl From<AuthResponseInput> for Vec<u8> {
 /// An authentication response input to a responder
 impl MealyInput for AuthResponseInput {}
 
+/// A record that an initiator verified the report of a remote identity, which
+/// lets it skip verification when handshaking with that identity again.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ResumptionTicket {
+    remote_identity: Vec<u8>,
+    expires_at: Duration,
+}
+
+impl ResumptionTicket {
+    pub(crate) fn new(remote_identity: Vec<u8>, expires_at: Duration) -> Self {
+        Self {
+            remote_identity,
+            expires_at,
+        }
+    }
+
+    /// The public key bytes of the verified remote identity.
+    pub fn remote_identity(&self) -> &[u8] {
+        self.remote_identity.as_ref()
+    }
+
+    /// When this ticket may no longer be used, in the caller's time base.
+    pub fn expires_at(&self) -> Duration {
+        self.expires_at
+    }
+}
+
+/// A resumed session outputs the ticket it was resumed with.
+impl MealyOutput for ResumptionTicket {}
+
+/// The authentication response is combined with a resumption ticket for an
+/// initiator resuming a session with a previously verified responder.
+///
+/// The responder's report is not verified, instead the handshake must prove
+/// the responder holds the identity key named in the ticket.
+pub struct AuthResponseResumeInput {
+    pub(crate) data: Vec<u8>,
+    pub(crate) ticket: ResumptionTicket,
+    pub(crate) now: Duration,
+}
+
+impl AuthResponseResumeInput {
+    /// Create a new resumption input, where `now` is in the same time base as
+    /// the ticket's expiry.
+    pub fn new(data: AuthResponseOutput, ticket: ResumptionTicket, now: Duration) -> Self {
+        Self {
+            data: data.0,
+            ticket,
+            now,
+        }
+    }
+}
+
+impl AsRef<[u8]> for AuthResponseResumeInput {
+    fn as_ref(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+}
+
+/// A resumption response input to an initiator
+impl MealyInput for AuthResponseResumeInput {}
+
 /// The IAS report is the final output when authentication succeeds.
 impl MealyOutput for VerificationReport {}
 
+/// A node responder outputs its response along with the initiator's report.
+impl MealyOutput for (AuthResponseOutput, VerificationReport) {}
+
 /// A type similar to aead::Payload used to distinguish writer inputs from
 /// outputs.
 pub struct Plaintext<'aad, 'msg> {
@@ -350,3 +416,31 @@ impl MealyInput for Ciphertext<'_, '_> {}
 
 /// Our outputs may be simple vectors for the proto-inside-grpc use case.
 impl MealyOutput for Vec<u8> {}
+
+/// An input used to rotate a Ready session's writer key.
+///
+/// The resulting message must be delivered to the remote party as a
+/// [`RekeyMessage`], in order with any ciphertexts.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RekeyRequest;
+
+/// A rekey request may be provided to a FST to produce a rekey message
+impl MealyInput for RekeyRequest {}
+
+/// A rekey message from the remote party, used to rotate a Ready session's
+/// reader key.
+pub struct RekeyMessage<'msg> {
+    pub msg: &'msg [u8],
+}
+
+impl<'msg> RekeyMessage<'msg> {
+    pub fn new(msg: &'msg [u8]) -> Self {
+        Self { msg }
+    }
+}
+
+/// A rekey message may be provided to a FST to rotate its reader key
+impl MealyInput for RekeyMessage<'_> {}
+
+/// Transitions with no output, such as processing a rekey message.
+impl MealyOutput for () {}
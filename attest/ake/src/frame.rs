@@ -0,0 +1,127 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Framing for messages exchanged over a Ready session.
+//!
+//! Each message is zero or more rekey frames followed by one data frame:
+//!
+//! - A rekey frame is `0x01`, the length of the rekey message as a
+//!   little-endian u16, then the rekey message itself.
+//! - A data frame is `0x00` followed by the ciphertext, which runs to the end
+//!   of the message.
+//!
+//! A standalone rekey message, as produced by a `RekeyRequest`, is one or more
+//! rekey frames with no data frame.
+
+use crate::error::Error;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+const DATA_FRAME: u8 = 0;
+const REKEY_FRAME: u8 = 1;
+
+/// A single frame read from a message.
+pub(crate) enum Frame<'msg> {
+    /// A rekey message, encrypted with the previous key.
+    Rekey(&'msg [u8]),
+    /// The ciphertext of the message.
+    Data(&'msg [u8]),
+}
+
+/// Append a rekey frame to the given message.
+pub(crate) fn push_rekey(message: &mut Vec<u8>, rekey: &[u8]) -> Result<(), Error> {
+    let len = u16::try_from(rekey.len()).map_err(|_| Error::InvalidFrame)?;
+    message.push(REKEY_FRAME);
+    message.extend_from_slice(&len.to_le_bytes());
+    message.extend_from_slice(rekey);
+    Ok(())
+}
+
+/// Append a data frame to the given message.
+pub(crate) fn push_data(message: &mut Vec<u8>, ciphertext: &[u8]) {
+    message.push(DATA_FRAME);
+    message.extend_from_slice(ciphertext);
+}
+
+/// Iterate over the frames of a message.
+pub(crate) struct Frames<'msg> {
+    remaining: &'msg [u8],
+}
+
+impl<'msg> Frames<'msg> {
+    pub(crate) fn new(message: &'msg [u8]) -> Self {
+        Self { remaining: message }
+    }
+}
+
+impl<'msg> Iterator for Frames<'msg> {
+    type Item = Result<Frame<'msg>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (tag, rest) = self.remaining.split_first()?;
+        let frame = match *tag {
+            DATA_FRAME => {
+                self.remaining = &[];
+                Ok(Frame::Data(rest))
+            }
+            REKEY_FRAME if rest.len() >= 2 => {
+                let len = u16::from_le_bytes([rest[0], rest[1]]) as usize;
+                let rest = &rest[2..];
+                if rest.len() < len {
+                    self.remaining = &[];
+                    Err(Error::InvalidFrame)
+                } else {
+                    self.remaining = &rest[len..];
+                    Ok(Frame::Rekey(&rest[..len]))
+                }
+            }
+            _ => {
+                self.remaining = &[];
+                Err(Error::InvalidFrame)
+            }
+        };
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Ensure frames are read back in the order they were written.
+    #[test]
+    fn roundtrip() {
+        let mut message = Vec::new();
+        push_rekey(&mut message, b"first").unwrap();
+        push_rekey(&mut message, b"").unwrap();
+        push_data(&mut message, b"data");
+
+        let frames = Frames::new(&message)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        assert!(matches!(frames[0], Frame::Rekey(b"first")));
+        assert!(matches!(frames[1], Frame::Rekey(b"")));
+        assert!(matches!(frames[2], Frame::Data(b"data")));
+    }
+
+    /// Ensure truncated or unknown frames are rejected.
+    #[test]
+    fn invalid() {
+        let mut message = Vec::new();
+        push_rekey(&mut message, b"first").unwrap();
+
+        assert!(matches!(
+            Frames::new(&message[..message.len() - 1]).next(),
+            Some(Err(Error::InvalidFrame))
+        ));
+        assert!(matches!(
+            Frames::new(&[REKEY_FRAME, 0]).next(),
+            Some(Err(Error::InvalidFrame))
+        ));
+        assert!(matches!(
+            Frames::new(&[2, 0, 0]).next(),
+            Some(Err(Error::InvalidFrame))
+        ));
+        assert!(Frames::new(&[]).next().is_none());
+    }
+}
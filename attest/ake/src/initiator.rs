@@ -4,7 +4,10 @@
 
 use crate::{
     error::Error,
-    event::{AuthRequestOutput, AuthResponseInput, ClientInitiate, NodeInitiate},
+    event::{
+        AuthRequestOutput, AuthResponseInput, AuthResponseResumeInput, ClientInitiate,
+        NodeInitiate, ResumptionTicket,
+    },
     mealy::Transition,
     state::{AuthPending, Ready, Start},
 };
@@ -167,6 +170,7 @@ where
                 let remote_report = VerificationReport::decode(output.payload.as_slice())
                     .map_err(|_e| Error::ReportDeserialization)?;
 
+                let remote_identity = result.remote_identity.ok_or(Error::MissingRemoteIdentity)?;
                 let mut verifier = input.verifier;
 
                 // We are not returning the report data and instead returning the raw report
@@ -176,25 +180,72 @@ where
                 // safely construct a VerificationReportData object out of the
                 // VerificationReport returned.
                 let _report_data = verifier
-                    .report_data(
-                        &result
-                            .remote_identity
-                            .ok_or(Error::MissingRemoteIdentity)?
-                            .map_bytes(|bytes| {
-                                ReportDataMask::try_from(bytes)
-                                    .map_err(|_| Error::BadRemoteIdentity)
-                            })?,
-                    )
+                    .report_data(&remote_identity.map_bytes(|bytes| {
+                        ReportDataMask::try_from(bytes).map_err(|_| Error::BadRemoteIdentity)
+                    })?)
                     .verify(&remote_report)?;
                 Ok((
-                    Ready {
-                        writer: result.initiator_cipher,
-                        reader: result.responder_cipher,
-                        binding: result.channel_binding,
-                    },
+                    Ready::new(
+                        result.initiator_cipher,
+                        result.responder_cipher,
+                        result.channel_binding,
+                        Some(remote_identity.map_bytes(|bytes| bytes.to_vec())),
+                    ),
                     remote_report,
                 ))
             }
         }
     }
 }
+
+/// AuthPending + AuthResponseResumeInput => Ready + ResumptionTicket
+///
+/// The responder's report is not verified, instead its identity must match
+/// the one verified when the unexpired ticket was issued.
+impl<KexAlgo, Cipher, DigestType>
+    Transition<Ready<Cipher>, AuthResponseResumeInput, ResumptionTicket>
+    for AuthPending<KexAlgo, Cipher, DigestType>
+where
+    KexAlgo: Kex,
+    Cipher: AeadMut + NewAead + NoiseCipher + Sized,
+    DigestType: BlockInput + Clone + Default + Digest + FixedOutput + Update + Reset,
+{
+    type Error = Error;
+
+    fn try_next<R: CryptoRng + RngCore>(
+        self,
+        _csprng: &mut R,
+        input: AuthResponseResumeInput,
+    ) -> Result<(Ready<Cipher>, ResumptionTicket), Self::Error> {
+        if input.now >= input.ticket.expires_at() {
+            return Err(Error::ResumptionTicketExpired);
+        }
+
+        let output = self
+            .state
+            .read_message(input.as_ref())
+            .map_err(Error::HandshakeRead)?;
+        match output.status {
+            HandshakeStatus::InProgress(_state) => Err(Error::HandshakeNotComplete),
+            HandshakeStatus::Complete(result) => {
+                let remote_identity = result
+                    .remote_identity
+                    .ok_or(Error::MissingRemoteIdentity)?
+                    .map_bytes(|bytes| bytes.to_vec());
+                if remote_identity.as_slice() != input.ticket.remote_identity() {
+                    return Err(Error::ResumptionIdentityMismatch);
+                }
+
+                Ok((
+                    Ready::new(
+                        result.initiator_cipher,
+                        result.responder_cipher,
+                        result.channel_binding,
+                        Some(remote_identity),
+                    ),
+                    input.ticket,
+                ))
+            }
+        }
+    }
+}
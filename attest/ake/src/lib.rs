@@ -12,6 +12,7 @@ extern crate alloc;
 
 mod error;
 mod event;
+mod frame;
 mod initiator;
 mod mealy;
 mod responder;
@@ -21,11 +22,14 @@ mod state;
 pub use self::{
     error::Error,
     event::{
-        AuthRequestOutput, AuthResponseInput, AuthResponseOutput, Ciphertext,
-        ClientAuthRequestInput, ClientInitiate, NodeAuthRequestInput, NodeInitiate, Plaintext,
+        AuthRequestOutput, AuthResponseInput, AuthResponseOutput, AuthResponseResumeInput,
+        Ciphertext, ClientAuthRequestInput, ClientInitiate, NodeAuthRequestInput, NodeInitiate,
+        Plaintext, RekeyMessage, RekeyRequest, ResumptionTicket,
     },
     mealy::Transition,
-    state::{AuthPending, Ready, Start},
+    state::{
+        AuthPending, Ready, RekeyPolicy, Start, DEFAULT_REKEY_MAX_AGE, DEFAULT_REKEY_MAX_MESSAGES,
+    },
 };
 
 #[cfg(test)]
@@ -36,8 +40,11 @@ mod test {
 
     use super::*;
     use aes_gcm::Aes256Gcm;
-    use core::convert::TryFrom;
-    use mc_attest_core::{MrSignerVerifier, Quote, Verifier, IAS_SIM_ROOT_ANCHORS};
+    use alloc::vec::Vec;
+    use core::{convert::TryFrom, time::Duration};
+    use mc_attest_core::{
+        MrSignerVerifier, Quote, VerificationReport, Verifier, IAS_SIM_ROOT_ANCHORS,
+    };
     use mc_attest_net::{Client, RaClient};
     use mc_crypto_keys::{X25519Private, X25519Public, X25519};
     use mc_util_encodings::{FromBase64, ToX64};
@@ -48,15 +55,15 @@ mod test {
 
     const RESPONDER_ID_STR: &str = "node1.unittest.mobilenode.com";
 
-    #[test]
-    fn ix_handshake() {
+    /// Create a new identity, a sim-signed report whose report data contains
+    /// its public key, and a verifier which accepts that report.
+    fn new_identity(csprng: &mut Hc128Rng) -> (X25519Private, VerificationReport, Verifier) {
         // Read an existing, valid quote
         let data = include_str!("../test_data/ok_quote.txt");
         let quote = Quote::from_base64(data.trim()).expect("Could not parse quote");
 
         // Create a new identity pubkey for our "enclave"
-        let mut csprng = Hc128Rng::seed_from_u64(0);
-        let identity = X25519Private::from_random(&mut csprng);
+        let identity = X25519Private::from_random(csprng);
         let pubkey = X25519Public::from(&identity);
 
         // Get the bytes from our quote
@@ -91,6 +98,14 @@ mod test {
             .expect("Could not construct verifier with sim root anchors");
         verifier.mr_signer(mr_signer).debug(true);
 
+        (identity, ias_report, verifier)
+    }
+
+    #[test]
+    fn ix_handshake() {
+        let mut csprng = Hc128Rng::seed_from_u64(0);
+        let (identity, ias_report, verifier) = new_identity(&mut csprng);
+
         let initiator = Start::new(RESPONDER_ID_STR.into());
         let responder = Start::new(RESPONDER_ID_STR.into());
 
@@ -102,11 +117,16 @@ mod test {
 
         // initiator = authpending, responder = start
 
-        let auth_request_input =
-            NodeAuthRequestInput::new(auth_request_output, identity, ias_report, verifier.clone());
-        let (responder, auth_response_output) = responder
+        let auth_request_input = NodeAuthRequestInput::new(
+            auth_request_output,
+            identity,
+            ias_report.clone(),
+            verifier.clone(),
+        );
+        let (responder, (auth_response_output, initiator_report)) = responder
             .try_next(&mut csprng, auth_request_input)
             .expect("Responder could not process auth request");
+        assert_eq!(initiator_report, ias_report);
 
         // initiator = authpending, responder = ready
 
@@ -153,4 +173,272 @@ mod test {
 
         assert_eq!(plaintext2.as_slice(), response.as_bytes());
     }
+
+    /// Complete an IX handshake between two nodes, returning the initiator
+    /// and responder sessions.
+    fn ix_sessions(csprng: &mut Hc128Rng) -> (Ready<Aes256Gcm>, Ready<Aes256Gcm>) {
+        let (identity, ias_report, verifier) = new_identity(csprng);
+
+        let node_init =
+            NodeInitiate::<X25519, Aes256Gcm, Sha512>::new(identity.clone(), ias_report.clone());
+        let (initiator, auth_request_output) = Start::new(RESPONDER_ID_STR.into())
+            .try_next(csprng, node_init)
+            .expect("Initiator could not be initiated");
+
+        let auth_request_input =
+            NodeAuthRequestInput::new(auth_request_output, identity, ias_report, verifier.clone());
+        let (responder, (auth_response_output, _)) = Start::new(RESPONDER_ID_STR.into())
+            .try_next(csprng, auth_request_input)
+            .expect("Responder could not process auth request");
+
+        let auth_response_input = AuthResponseInput::new(auth_response_output, verifier);
+        let (initiator, _) = initiator
+            .try_next(csprng, auth_response_input)
+            .expect("Initiator not process auth response");
+
+        (initiator, responder)
+    }
+
+    /// Start an NX handshake with a responder using the given identity and
+    /// report, returning the pending initiator and the responder's reply.
+    fn nx_response(
+        csprng: &mut Hc128Rng,
+        identity: X25519Private,
+        ias_report: VerificationReport,
+    ) -> (
+        AuthPending<X25519, Aes256Gcm, Sha512>,
+        Ready<Aes256Gcm>,
+        AuthResponseOutput,
+    ) {
+        let (initiator, auth_request_output) = Start::new(RESPONDER_ID_STR.into())
+            .try_next(
+                csprng,
+                ClientInitiate::<X25519, Aes256Gcm, Sha512>::default(),
+            )
+            .expect("Initiator could not be initiated");
+
+        let auth_request_input =
+            ClientAuthRequestInput::new(auth_request_output, identity, ias_report);
+        let (responder, (auth_response_output, _)) = Start::new(RESPONDER_ID_STR.into())
+            .try_next(csprng, auth_request_input)
+            .expect("Responder could not process auth request");
+
+        (initiator, responder, auth_response_output)
+    }
+
+    /// Send a message from one session to the other, and ensure it arrives
+    /// intact.
+    fn roundtrip(
+        csprng: &mut Hc128Rng,
+        sender: Ready<Aes256Gcm>,
+        receiver: Ready<Aes256Gcm>,
+        msg: &[u8],
+    ) -> (Ready<Aes256Gcm>, Ready<Aes256Gcm>) {
+        let aad = b"roundtrip";
+        let (sender, ciphertext) = sender
+            .try_next(csprng, Plaintext::new(aad, msg))
+            .expect("Could not encrypt message");
+        let (receiver, plaintext) = receiver
+            .try_next(csprng, Ciphertext::new(aad, &ciphertext))
+            .expect("Could not decrypt message");
+        assert_eq!(plaintext.as_slice(), msg);
+        (sender, receiver)
+    }
+
+    #[test]
+    fn ix_rekey() {
+        let mut csprng = Hc128Rng::seed_from_u64(0);
+        let (initiator, responder) = ix_sessions(&mut csprng);
+        let policy = RekeyPolicy::new(2, Duration::from_secs(3600));
+
+        let (initiator, responder) = roundtrip(&mut csprng, initiator, responder, b"one");
+        assert!(!initiator.rekey_due(&policy, Duration::from_secs(0)));
+        let (initiator, responder) = roundtrip(&mut csprng, initiator, responder, b"two");
+        assert!(initiator.rekey_due(&policy, Duration::from_secs(0)));
+
+        // The initiator rotates its writer key, and the responder follows.
+        let (initiator, rekey_message) = initiator
+            .try_next(&mut csprng, RekeyRequest)
+            .expect("Could not rekey initiator");
+        assert_eq!(initiator.messages_since_rekey(), 0);
+        assert!(!initiator.rekey_due(&policy, Duration::from_secs(0)));
+        let (responder, ()) = responder
+            .try_next(&mut csprng, RekeyMessage::new(&rekey_message))
+            .expect("Could not process rekey message");
+
+        let (initiator, responder) = roundtrip(&mut csprng, initiator, responder, b"three");
+
+        // The responder rotates independently.
+        let (responder, rekey_message) = responder
+            .try_next(&mut csprng, RekeyRequest)
+            .expect("Could not rekey responder");
+        let (initiator, ()) = initiator
+            .try_next(&mut csprng, RekeyMessage::new(&rekey_message))
+            .expect("Could not process rekey message");
+
+        let (responder, initiator) = roundtrip(&mut csprng, responder, initiator, b"four");
+        let (initiator, responder) = roundtrip(&mut csprng, initiator, responder, b"five");
+
+        // A reader which missed the rekey message cannot decrypt new messages.
+        let (initiator, _rekey_message) = initiator
+            .try_next(&mut csprng, RekeyRequest)
+            .expect("Could not rekey initiator");
+        let (_, ciphertext) = initiator
+            .try_next(&mut csprng, Plaintext::new(b"", b"six"))
+            .expect("Could not encrypt message");
+        let result: Result<(Ready<Aes256Gcm>, Vec<u8>), _> =
+            responder.try_next(&mut csprng, Ciphertext::new(b"", &ciphertext));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rekey_message_is_not_ciphertext() {
+        let mut csprng = Hc128Rng::seed_from_u64(0);
+        let (initiator, responder) = ix_sessions(&mut csprng);
+
+        let (_, ciphertext) = initiator
+            .try_next(&mut csprng, Plaintext::new(b"", b""))
+            .expect("Could not encrypt message");
+        let result = responder.try_next(&mut csprng, RekeyMessage::new(&ciphertext));
+        assert!(matches!(result, Err(Error::InvalidFrame)));
+    }
+
+    #[test]
+    fn ciphertext_is_not_rekey_message() {
+        let mut csprng = Hc128Rng::seed_from_u64(0);
+        let (initiator, responder) = ix_sessions(&mut csprng);
+
+        let (_, rekey_message) = initiator
+            .try_next(&mut csprng, RekeyRequest)
+            .expect("Could not rekey initiator");
+        let result: Result<(Ready<Aes256Gcm>, Vec<u8>), _> =
+            responder.try_next(&mut csprng, Ciphertext::new(b"", &rekey_message));
+        assert!(matches!(result, Err(Error::InvalidFrame)));
+    }
+
+    #[test]
+    fn ix_rekey_policy() {
+        let mut csprng = Hc128Rng::seed_from_u64(0);
+        let (mut initiator, mut responder) = ix_sessions(&mut csprng);
+        initiator.set_rekey_policy(RekeyPolicy::new(2, Duration::from_secs(60)));
+
+        // The initiator rotates its writer key in-band every two messages, and
+        // the responder follows.
+        for i in 0..5u64 {
+            let msg = i.to_le_bytes();
+            let ciphertext = initiator
+                .encrypt(b"aad", &msg)
+                .expect("Could not encrypt message");
+            assert_eq!(initiator.messages_since_rekey(), i % 2 + 1);
+            assert_eq!(
+                responder
+                    .decrypt(b"aad", &ciphertext)
+                    .expect("Could not decrypt message"),
+                msg
+            );
+        }
+
+        // Having seen the initiator rotate its key, the responder rotates its
+        // own on its next message, which does not make the initiator rotate
+        // again.
+        let ciphertext = responder
+            .encrypt(b"aad", b"reply")
+            .expect("Could not encrypt reply");
+        assert_eq!(
+            initiator
+                .decrypt(b"aad", &ciphertext)
+                .expect("Could not decrypt reply"),
+            b"reply"
+        );
+        let ciphertext = initiator
+            .encrypt(b"aad", b"after")
+            .expect("Could not encrypt message");
+        assert_eq!(initiator.messages_since_rekey(), 2);
+        assert_eq!(
+            responder
+                .decrypt(b"aad", &ciphertext)
+                .expect("Could not decrypt message"),
+            b"after"
+        );
+
+        // Keys are also rotated once they are too old.
+        let (mut initiator, mut responder) = ix_sessions(&mut csprng);
+        initiator.set_rekey_policy(RekeyPolicy::new(100, Duration::from_secs(60)));
+        for (now, msg) in [(0, b"one"), (59, b"two"), (60, b"six")].iter() {
+            let ciphertext = initiator
+                .encrypt_at(b"", *msg, Duration::from_secs(*now))
+                .expect("Could not encrypt message");
+            assert_eq!(
+                responder
+                    .decrypt(b"", &ciphertext)
+                    .expect("Could not decrypt message"),
+                &msg[..]
+            );
+        }
+        assert_eq!(initiator.messages_since_rekey(), 1);
+    }
+
+    #[test]
+    fn nx_resumption() {
+        let mut csprng = Hc128Rng::seed_from_u64(0);
+        let (identity, ias_report, verifier) = new_identity(&mut csprng);
+
+        // A full handshake verifies the report, and yields a ticket.
+        let (initiator, responder, auth_response_output) =
+            nx_response(&mut csprng, identity.clone(), ias_report.clone());
+        let (initiator, _) = initiator
+            .try_next(
+                &mut csprng,
+                AuthResponseInput::new(auth_response_output, verifier),
+            )
+            .expect("Initiator could not process auth response");
+        let ticket = initiator
+            .resumption_ticket(Duration::from_secs(100))
+            .expect("Initiator did not learn the responder identity");
+        assert_eq!(
+            ticket.remote_identity(),
+            X25519Public::from(&identity).as_ref()
+        );
+        let _ = roundtrip(&mut csprng, initiator, responder, b"full");
+
+        // Resuming with the ticket skips verification.
+        let (initiator, responder, auth_response_output) =
+            nx_response(&mut csprng, identity.clone(), ias_report.clone());
+        let (initiator, resumed_ticket) = initiator
+            .try_next(
+                &mut csprng,
+                AuthResponseResumeInput::new(
+                    auth_response_output,
+                    ticket.clone(),
+                    Duration::from_secs(50),
+                ),
+            )
+            .expect("Could not resume session");
+        assert_eq!(resumed_ticket, ticket);
+        let (_, responder) = roundtrip(&mut csprng, initiator, responder, b"resumed");
+        assert!(responder.remote_identity().is_none());
+
+        // Expired tickets are rejected.
+        let (initiator, _, auth_response_output) =
+            nx_response(&mut csprng, identity, ias_report.clone());
+        let result = initiator.try_next(
+            &mut csprng,
+            AuthResponseResumeInput::new(
+                auth_response_output,
+                ticket.clone(),
+                Duration::from_secs(100),
+            ),
+        );
+        assert!(matches!(result, Err(Error::ResumptionTicketExpired)));
+
+        // Tickets for another identity are rejected.
+        let (other_identity, _, _) = new_identity(&mut csprng);
+        let (initiator, _, auth_response_output) =
+            nx_response(&mut csprng, other_identity, ias_report);
+        let result = initiator.try_next(
+            &mut csprng,
+            AuthResponseResumeInput::new(auth_response_output, ticket, Duration::from_secs(50)),
+        );
+        assert!(matches!(result, Err(Error::ResumptionIdentityMismatch)));
+    }
 }
@@ -104,24 +104,32 @@ impl ResponderTransitionMixin for Start {
         match output.status {
             HandshakeStatus::InProgress(_state) => Err(Error::HandshakeNotComplete),
             HandshakeStatus::Complete(result) => Ok((
-                Ready {
-                    writer: result.responder_cipher,
-                    reader: result.initiator_cipher,
-                    binding: result.channel_binding,
-                },
+                Ready::new(
+                    result.responder_cipher,
+                    result.initiator_cipher,
+                    result.channel_binding,
+                    result
+                        .remote_identity
+                        .map(|identity| identity.map_bytes(|bytes| bytes.to_vec())),
+                ),
                 AuthResponseOutput::from(output.payload),
             )),
         }
     }
 }
 
-/// Start + NodeAuthRequestInput => Ready + AuthResponseOutput
+/// Start + NodeAuthRequestInput => Ready + (AuthResponseOutput,
+/// VerificationReport)
 ///
 /// This defines the responder's action when an AuthRequestInput for an IX
-/// exchange is provided.
+/// exchange is provided. The initiator's verified report is returned alongside
+/// the response, so the caller can apply further checks to it.
 impl<KexAlgo, Cipher, DigestType>
-    Transition<Ready<Cipher>, NodeAuthRequestInput<KexAlgo, Cipher, DigestType>, AuthResponseOutput>
-    for Start
+    Transition<
+        Ready<Cipher>,
+        NodeAuthRequestInput<KexAlgo, Cipher, DigestType>,
+        (AuthResponseOutput, VerificationReport),
+    > for Start
 where
     KexAlgo: Kex,
     Cipher: AeadMut + NewAead + NoiseCipher + Sized,
@@ -134,7 +142,7 @@ where
         self,
         csprng: &mut R,
         input: NodeAuthRequestInput<KexAlgo, Cipher, DigestType>,
-    ) -> Result<(Ready<Cipher>, AuthResponseOutput), Error> {
+    ) -> Result<(Ready<Cipher>, (AuthResponseOutput, VerificationReport)), Error> {
         // Read the request and return the payload and state
         let (handshake_state, payload) = self
             .handle_request::<HandshakeIX, KexAlgo, Cipher, DigestType>(
@@ -160,7 +168,9 @@ where
             )
             .verify(&remote_report)?;
 
-        Self::handle_response(csprng, handshake_state, input.ias_report)
+        let (responder, auth_response) =
+            Self::handle_response(csprng, handshake_state, input.ias_report)?;
+        Ok((responder, (auth_response, remote_report)))
     }
 }
 
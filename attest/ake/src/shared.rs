@@ -3,13 +3,15 @@
 //! Common transitions between initiator and responder.
 
 use crate::{
-    event::{Ciphertext, Plaintext},
+    error::Error,
+    event::{Ciphertext, Plaintext, RekeyMessage, RekeyRequest},
+    frame::{self, Frame, Frames},
     mealy::Transition,
     state::Ready,
 };
 use aead::{AeadMut, NewAead};
 use alloc::vec::Vec;
-use mc_crypto_noise::{CipherError, NoiseCipher};
+use mc_crypto_noise::NoiseCipher;
 use rand_core::{CryptoRng, RngCore};
 
 /// Ready + Ciphertext => Ready + Vec-of-plaintext
@@ -17,7 +19,7 @@ impl<Cipher> Transition<Ready<Cipher>, Ciphertext<'_, '_>, Vec<u8>> for Ready<Ci
 where
     Cipher: AeadMut + NewAead + NoiseCipher + Sized,
 {
-    type Error = CipherError;
+    type Error = Error;
 
     fn try_next<R: CryptoRng + RngCore>(
         self,
@@ -35,7 +37,7 @@ impl<Cipher> Transition<Ready<Cipher>, Plaintext<'_, '_>, Vec<u8>> for Ready<Cip
 where
     Cipher: AeadMut + NewAead + NoiseCipher + Sized,
 {
-    type Error = CipherError;
+    type Error = Error;
 
     fn try_next<R: CryptoRng + RngCore>(
         self,
@@ -47,3 +49,57 @@ where
         Ok((retval, ciphertext))
     }
 }
+
+/// Ready + RekeyRequest => Ready + Vec-of-rekey-message
+///
+/// The rekey message is encrypted with the current writer key, which is then
+/// rotated. It must be delivered before any later ciphertexts.
+impl<Cipher> Transition<Ready<Cipher>, RekeyRequest, Vec<u8>> for Ready<Cipher>
+where
+    Cipher: AeadMut + NewAead + NoiseCipher + Sized,
+{
+    type Error = Error;
+
+    fn try_next<R: CryptoRng + RngCore>(
+        self,
+        _csprng: &mut R,
+        _input: RekeyRequest,
+    ) -> Result<(Ready<Cipher>, Vec<u8>), Self::Error> {
+        let mut retval = self;
+        let rekey = retval.rekey_writer()?;
+        let mut message = Vec::new();
+        frame::push_rekey(&mut message, &rekey)?;
+        Ok((retval, message))
+    }
+}
+
+/// Ready + RekeyMessage => Ready
+///
+/// Each rekey frame is decrypted with the current reader key, which is then
+/// rotated to match the remote writer. Messages carrying ciphertext are
+/// rejected.
+impl<Cipher> Transition<Ready<Cipher>, RekeyMessage<'_>, ()> for Ready<Cipher>
+where
+    Cipher: AeadMut + NewAead + NoiseCipher + Sized,
+{
+    type Error = Error;
+
+    fn try_next<R: CryptoRng + RngCore>(
+        self,
+        _csprng: &mut R,
+        input: RekeyMessage,
+    ) -> Result<(Ready<Cipher>, ()), Self::Error> {
+        if input.msg.is_empty() {
+            return Err(Error::InvalidFrame);
+        }
+
+        let mut retval = self;
+        for frame in Frames::new(input.msg) {
+            match frame? {
+                Frame::Rekey(rekey) => retval.rekey_reader(rekey)?,
+                Frame::Data(_) => return Err(Error::InvalidFrame),
+            }
+        }
+        Ok((retval, ()))
+    }
+}
@@ -2,12 +2,18 @@
 
 //! Transducer states used by initiators and/or responders.
 
-use crate::mealy::State;
+use crate::{
+    error::Error,
+    event::ResumptionTicket,
+    frame::{self, Frame, Frames},
+    mealy::State,
+};
 use aead::{AeadMut, NewAead};
 use alloc::{string::String, vec::Vec};
+use core::{cmp::max, time::Duration};
 use digest::{BlockInput, FixedOutput, Reset, Update};
 use mc_crypto_keys::Kex;
-use mc_crypto_noise::{CipherError, CipherState, HandshakeState, NoiseCipher};
+use mc_crypto_noise::{CipherState, HandshakeState, NoiseCipher};
 
 /// The state of a node (initiator or responder) before anything has happened
 /// yet.
@@ -57,8 +63,17 @@ where
     }
 }
 
+/// The associated data used for rekey messages, which keeps them from being
+/// mistaken for application ciphertexts.
+const REKEY_AAD: &[u8] = b"mc-attest-ake rekey";
+
 /// The state after an auth response has been sent by a responder/received by
 /// an initiator.
+///
+/// Messages are framed so that the writer key can be rotated in-band: when
+/// the session's [`RekeyPolicy`] says the writer key is due, `encrypt`
+/// prefixes the message with a rekey frame, and `decrypt` rotates the reader
+/// key when it finds one.
 pub struct Ready<Cipher>
 where
     Cipher: AeadMut + NewAead + NoiseCipher + Sized,
@@ -66,25 +81,244 @@ where
     pub(crate) writer: CipherState<Cipher>,
     pub(crate) reader: CipherState<Cipher>,
     pub(crate) binding: Vec<u8>,
+    /// The remote party's static identity, if it sent one
+    pub(crate) remote_identity: Option<Vec<u8>>,
+    /// When the writer key should be rotated
+    pub(crate) rekey_policy: RekeyPolicy,
+    /// The number of messages encrypted since the writer was last keyed
+    pub(crate) sent_since_rekey: u64,
+    /// When the writer was last keyed, in the caller's time base, if the
+    /// caller has a clock
+    pub(crate) writer_keyed_at: Option<Duration>,
+    /// The number of times the writer key was rotated, or the number of times
+    /// the reader key had been rotated when the writer caught up to it
+    pub(crate) writer_rekeys: u64,
+    /// The number of times the reader key was rotated
+    pub(crate) reader_rekeys: u64,
 }
 
 impl<Cipher> Ready<Cipher>
 where
     Cipher: AeadMut + NewAead + NoiseCipher + Sized,
 {
+    pub(crate) fn new(
+        writer: CipherState<Cipher>,
+        reader: CipherState<Cipher>,
+        binding: Vec<u8>,
+        remote_identity: Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            writer,
+            reader,
+            binding,
+            remote_identity,
+            rekey_policy: RekeyPolicy::default(),
+            sent_since_rekey: 0,
+            writer_keyed_at: None,
+            writer_rekeys: 0,
+            reader_rekeys: 0,
+        }
+    }
+
     /// Retrieve the channel binding as a byte slice
     pub fn binding(&self) -> &[u8] {
         self.binding.as_ref()
     }
-    /// Using the writer cipher, encrypt the given plaintext.
-    pub fn encrypt(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, CipherError> {
-        self.writer.encrypt_with_ad(aad, plaintext)
+
+    /// Retrieve the remote party's static identity public key bytes, if it
+    /// sent one during the handshake.
+    pub fn remote_identity(&self) -> Option<&[u8]> {
+        self.remote_identity.as_deref()
+    }
+
+    /// Retrieve the policy used to decide when to rotate the writer key.
+    pub fn rekey_policy(&self) -> &RekeyPolicy {
+        &self.rekey_policy
+    }
+
+    /// Replace the policy used to decide when to rotate the writer key.
+    pub fn set_rekey_policy(&mut self, rekey_policy: RekeyPolicy) {
+        self.rekey_policy = rekey_policy;
+    }
+
+    /// The number of messages encrypted since the writer key was established
+    /// or last rotated.
+    pub fn messages_since_rekey(&self) -> u64 {
+        self.sent_since_rekey
+    }
+
+    /// Whether the writer key should be rotated under the given policy, given
+    /// how long ago the caller established or last rotated it.
+    pub fn rekey_due(&self, policy: &RekeyPolicy, key_age: Duration) -> bool {
+        policy.is_due(self.sent_since_rekey, key_age)
+    }
+
+    /// Create a ticket which lets an initiator resume a session with the same
+    /// remote identity, without re-verifying its report, until `expires_at`.
+    ///
+    /// This should only be called after the remote party's report has been
+    /// verified, i.e. by an initiator which completed a full handshake.
+    pub fn resumption_ticket(&self, expires_at: Duration) -> Option<ResumptionTicket> {
+        self.remote_identity
+            .as_ref()
+            .map(|remote_identity| ResumptionTicket::new(remote_identity.clone(), expires_at))
     }
 
-    /// Using the reader cipher, decrypt the provided ciphertext.
-    pub fn decrypt(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, CipherError> {
-        self.reader.decrypt_with_ad(aad, ciphertext)
+    /// Using the writer cipher, encrypt the given plaintext into a framed
+    /// message.
+    ///
+    /// The writer key is rotated first if the session's policy message limit
+    /// has been reached, or if the remote party rotated its writer key more
+    /// often than we rotated ours.
+    /// Callers without a clock (e.g. enclaves) use this method, so the policy
+    /// age limit is applied by whichever party has one.
+    pub fn encrypt(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        self.encrypt_framed(aad, plaintext, None)
+    }
+
+    /// Using the writer cipher, encrypt the given plaintext into a framed
+    /// message, where `now` is the current time in the caller's time base.
+    ///
+    /// This is [`Ready::encrypt`], but also rotates the writer key once it is
+    /// older than the session's policy allows.
+    pub fn encrypt_at(
+        &mut self,
+        aad: &[u8],
+        plaintext: &[u8],
+        now: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        self.encrypt_framed(aad, plaintext, Some(now))
+    }
+
+    /// Using the reader cipher, decrypt the provided framed message, rotating
+    /// the reader key for each rekey frame it starts with.
+    pub fn decrypt(&mut self, aad: &[u8], message: &[u8]) -> Result<Vec<u8>, Error> {
+        for frame in Frames::new(message) {
+            match frame? {
+                Frame::Rekey(rekey) => self.rekey_reader(rekey)?,
+                Frame::Data(ciphertext) => {
+                    return self
+                        .reader
+                        .decrypt_with_ad(aad, ciphertext)
+                        .map_err(Error::DecryptError)
+                }
+            }
+        }
+        Err(Error::InvalidFrame)
+    }
+
+    fn encrypt_framed(
+        &mut self,
+        aad: &[u8],
+        plaintext: &[u8],
+        now: Option<Duration>,
+    ) -> Result<Vec<u8>, Error> {
+        let key_age = match now {
+            Some(now) => now
+                .checked_sub(*self.writer_keyed_at.get_or_insert(now))
+                .unwrap_or_default(),
+            None => Duration::default(),
+        };
+
+        let mut message = Vec::new();
+        if self.reader_rekeys > self.writer_rekeys || self.rekey_due(&self.rekey_policy, key_age) {
+            let rekey = self.rekey_writer()?;
+            frame::push_rekey(&mut message, &rekey)?;
+            if now.is_some() {
+                self.writer_keyed_at = now;
+            }
+        }
+
+        let ciphertext = self
+            .writer
+            .encrypt_with_ad(aad, plaintext)
+            .map_err(Error::EncryptError)?;
+        self.sent_since_rekey += 1;
+        frame::push_data(&mut message, &ciphertext);
+        Ok(message)
+    }
+
+    /// Encrypt a rekey message with the current writer key, then rotate it.
+    pub(crate) fn rekey_writer(&mut self) -> Result<Vec<u8>, Error> {
+        let rekey = self
+            .writer
+            .encrypt_with_ad(REKEY_AAD, &[])
+            .map_err(Error::EncryptError)?;
+        self.writer.rekey().map_err(Error::Rekey)?;
+        self.sent_since_rekey = 0;
+        // Catching up to the remote writer must not prompt it to rotate again.
+        self.writer_rekeys = max(self.writer_rekeys + 1, self.reader_rekeys);
+        Ok(rekey)
+    }
+
+    /// Decrypt a rekey message with the current reader key, then rotate it to
+    /// match the remote writer.
+    pub(crate) fn rekey_reader(&mut self, rekey: &[u8]) -> Result<(), Error> {
+        let payload = self
+            .reader
+            .decrypt_with_ad(REKEY_AAD, rekey)
+            .map_err(Error::DecryptError)?;
+        if !payload.is_empty() {
+            return Err(Error::UnexpectedRekeyPayload);
+        }
+        self.reader.rekey().map_err(Error::Rekey)?;
+        self.reader_rekeys += 1;
+        Ok(())
     }
 }
 
 impl<Cipher> State for Ready<Cipher> where Cipher: AeadMut + NewAead + NoiseCipher + Sized {}
+
+/// When a [`Ready`] session should rotate its writer key.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RekeyPolicy {
+    /// The maximum number of messages to encrypt with a single key.
+    pub max_messages: u64,
+    /// The maximum age of a key.
+    pub max_age: Duration,
+}
+
+/// The default number of messages to encrypt with a single key.
+pub const DEFAULT_REKEY_MAX_MESSAGES: u64 = 10_000;
+
+/// The default maximum age of a key.
+pub const DEFAULT_REKEY_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+impl Default for RekeyPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_REKEY_MAX_MESSAGES, DEFAULT_REKEY_MAX_AGE)
+    }
+}
+
+impl RekeyPolicy {
+    /// Create a new policy which rotates keys after `max_messages` messages or
+    /// `max_age`, whichever comes first.
+    pub fn new(max_messages: u64, max_age: Duration) -> Self {
+        Self {
+            max_messages,
+            max_age,
+        }
+    }
+
+    /// Whether a key which has encrypted `messages` messages and is `key_age`
+    /// old should be rotated.
+    pub fn is_due(&self, messages: u64, key_age: Duration) -> bool {
+        messages >= self.max_messages || key_age >= self.max_age
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Ensure either limit triggers a rekey.
+    #[test]
+    fn rekey_policy_is_due() {
+        let policy = RekeyPolicy::new(100, Duration::from_secs(60));
+
+        assert!(!policy.is_due(0, Duration::from_secs(0)));
+        assert!(!policy.is_due(99, Duration::from_secs(59)));
+        assert!(policy.is_due(100, Duration::from_secs(0)));
+        assert!(policy.is_due(0, Duration::from_secs(60)));
+    }
+}
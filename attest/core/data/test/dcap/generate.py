@@ -0,0 +1,363 @@
+#!/usr/bin/env python3
+#
+# Test fixture generator for DCAP quote verification.
+#
+# This creates a self-contained set of DCAP evidence: a test root CA standing
+# in for the Intel SGX Root CA, a PCK certificate chain carrying the SGX
+# extensions, an ECDSA quote (v3) whose certification data is that chain, and
+# TCB info / QE identity collateral signed by a TCB signing certificate issued
+# by the same root, and CRLs for the root and PCK platform CAs.
+#
+# The outputs are checked in, and used by the tests in mc-attest-core. They
+# only need to be regenerated if the fixture contents change.
+#
+# This code should not be re-used in a production system.
+#
+
+import datetime
+import hashlib
+import json
+import os
+import struct
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec
+from cryptography.hazmat.primitives.asymmetric.utils import decode_dss_signature
+from cryptography.x509.oid import NameOID
+
+OUT_DIR = os.path.dirname(os.path.abspath(__file__))
+
+NOT_BEFORE = datetime.datetime(2021, 1, 1)
+NOT_AFTER = datetime.datetime(2049, 12, 31)
+
+FMSPC = bytes.fromhex("00906ed50000")
+PCE_ID = bytes.fromhex("0000")
+# The TCB of the platform, as recorded in the PCK certificate.
+CPU_SVN = bytes([2] * 16)
+PCE_SVN = 10
+
+QE_VENDOR_ID = bytes.fromhex("939a7233f79c4ca9940a0db3957f0607")
+QE_MRSIGNER = hashlib.sha256(b"test quoting enclave signer").digest()
+QE_SVN = 6
+
+APP_MRENCLAVE = bytes(range(32))
+APP_MRSIGNER = bytes(range(32, 64))
+APP_PROD_ID = 1
+APP_SVN = 3
+APP_REPORT_DATA = b"dcap test report data".ljust(64, b"\0")
+
+SGX_EXTENSION_OID = "1.2.840.113741.1.13.1"
+
+
+# Minimal DER encoding helpers.
+def der_len(length):
+    if length < 0x80:
+        return bytes([length])
+    encoded = length.to_bytes((length.bit_length() + 7) // 8, "big")
+    return bytes([0x80 | len(encoded)]) + encoded
+
+
+def der(tag, value):
+    return bytes([tag]) + der_len(len(value)) + value
+
+
+def der_oid(oid):
+    parts = [int(p) for p in oid.split(".")]
+    body = bytes([parts[0] * 40 + parts[1]])
+    for part in parts[2:]:
+        chunk = [part & 0x7F]
+        part >>= 7
+        while part:
+            chunk.insert(0, 0x80 | (part & 0x7F))
+            part >>= 7
+        body += bytes(chunk)
+    return der(0x06, body)
+
+
+def der_int(value):
+    body = value.to_bytes(max(1, (value.bit_length() + 8) // 8), "big")
+    return der(0x02, body)
+
+
+def der_seq(*items):
+    return der(0x30, b"".join(items))
+
+
+def sgx_extension():
+    tcb_items = [
+        der_seq(der_oid(f"{SGX_EXTENSION_OID}.2.{i + 1}"), der_int(svn))
+        for i, svn in enumerate(CPU_SVN)
+    ]
+    tcb_items.append(der_seq(der_oid(f"{SGX_EXTENSION_OID}.2.17"), der_int(PCE_SVN)))
+    tcb_items.append(der_seq(der_oid(f"{SGX_EXTENSION_OID}.2.18"), der(0x04, CPU_SVN)))
+    return der_seq(
+        der_seq(der_oid(f"{SGX_EXTENSION_OID}.1"), der(0x04, bytes(16))),
+        der_seq(der_oid(f"{SGX_EXTENSION_OID}.2"), der_seq(*tcb_items)),
+        der_seq(der_oid(f"{SGX_EXTENSION_OID}.3"), der(0x04, PCE_ID)),
+        der_seq(der_oid(f"{SGX_EXTENSION_OID}.4"), der(0x04, FMSPC)),
+        der_seq(der_oid(f"{SGX_EXTENSION_OID}.5"), der(0x0A, b"\0")),
+    )
+
+
+def name(common_name):
+    return x509.Name(
+        [
+            x509.NameAttribute(NameOID.COMMON_NAME, common_name),
+            x509.NameAttribute(NameOID.ORGANIZATION_NAME, "MobileCoin Test"),
+        ]
+    )
+
+
+def make_cert(subject, key, issuer, issuer_key, ca, extensions=()):
+    builder = (
+        x509.CertificateBuilder()
+        .subject_name(name(subject))
+        .issuer_name(name(issuer))
+        .public_key(key.public_key())
+        .serial_number(x509.random_serial_number())
+        .not_valid_before(NOT_BEFORE)
+        .not_valid_after(NOT_AFTER)
+        .add_extension(x509.BasicConstraints(ca=ca, path_length=None), critical=True)
+    )
+    for ext in extensions:
+        builder = builder.add_extension(ext, critical=False)
+    return builder.sign(issuer_key, hashes.SHA256())
+
+
+def make_crl(issuer, issuer_key, revoked=()):
+    builder = (
+        x509.CertificateRevocationListBuilder()
+        .issuer_name(name(issuer))
+        .last_update(NOT_BEFORE)
+        .next_update(NOT_AFTER)
+    )
+    for cert in revoked:
+        builder = builder.add_revoked_certificate(
+            x509.RevokedCertificateBuilder()
+            .serial_number(cert.serial_number)
+            .revocation_date(NOT_BEFORE)
+            .build()
+        )
+    return builder.sign(issuer_key, hashes.SHA256())
+
+
+def der_crl(crl):
+    return crl.public_bytes(serialization.Encoding.DER)
+
+
+def pem(*certs):
+    return b"".join(cert.public_bytes(serialization.Encoding.PEM) for cert in certs)
+
+
+def raw_sign(key, data):
+    r, s = decode_dss_signature(key.sign(data, ec.ECDSA(hashes.SHA256())))
+    return r.to_bytes(32, "big") + s.to_bytes(32, "big")
+
+
+def raw_public_key(key):
+    numbers = key.public_key().public_numbers()
+    return numbers.x.to_bytes(32, "big") + numbers.y.to_bytes(32, "big")
+
+
+def report_body(cpu_svn, attributes, mr_enclave, mr_signer, prod_id, svn, report_data):
+    body = (
+        cpu_svn
+        + struct.pack("<I", 0)
+        + bytes(12)
+        + bytes(16)
+        + attributes
+        + mr_enclave
+        + bytes(32)
+        + mr_signer
+        + bytes(32)
+        + bytes(64)
+        + struct.pack("<HHH", prod_id, svn, 0)
+        + bytes(42)
+        + bytes(16)
+        + report_data
+    )
+    assert len(body) == 384
+    return body
+
+
+def signed_json(key, name, body):
+    text = json.dumps(body, separators=(",", ":"))
+    signature = raw_sign(key, text.encode()).hex()
+    return '{"%s":%s,"signature":"%s"}' % (name, text, signature)
+
+
+def tcb_level_v2(svn, pce_svn, status):
+    tcb = {"sgxtcbcomp%02dsvn" % (i + 1): svn for i in range(16)}
+    tcb["pcesvn"] = pce_svn
+    return {"tcb": tcb, "tcbDate": "2021-01-01T00:00:00Z", "tcbStatus": status}
+
+
+def tcb_level_v3(svn, pce_svn, status, advisory_ids):
+    level = {
+        "tcb": {"sgxtcbcomponents": [{"svn": svn}] * 16, "pcesvn": pce_svn},
+        "tcbDate": "2021-01-01T00:00:00Z",
+        "tcbStatus": status,
+    }
+    if advisory_ids:
+        level["advisoryIDs"] = advisory_ids
+    return level
+
+
+def tcb_info(version, levels):
+    info = {
+        "version": version,
+        "issueDate": "2021-06-01T00:00:00Z",
+        "nextUpdate": "2049-12-31T00:00:00Z",
+        "fmspc": FMSPC.hex(),
+        "pceId": PCE_ID.hex(),
+        "tcbType": 0,
+        "tcbEvaluationDataNumber": 11,
+        "tcbLevels": levels,
+    }
+    if version == 3:
+        info = dict({"id": "SGX"}, **info)
+    return info
+
+
+def write(filename, data):
+    mode = "wb" if isinstance(data, bytes) else "w"
+    with open(os.path.join(OUT_DIR, filename), mode) as f:
+        f.write(data)
+
+
+def main():
+    root_key = ec.generate_private_key(ec.SECP256R1())
+    platform_key = ec.generate_private_key(ec.SECP256R1())
+    pck_key = ec.generate_private_key(ec.SECP256R1())
+    tcb_signing_key = ec.generate_private_key(ec.SECP256R1())
+    attestation_key = ec.generate_private_key(ec.SECP256R1())
+
+    root_name = "Test SGX Root CA"
+    root = make_cert(root_name, root_key, root_name, root_key, True)
+    platform = make_cert("Test SGX PCK Platform CA", platform_key, root_name, root_key, True)
+    pck = make_cert(
+        "Test SGX PCK Certificate",
+        pck_key,
+        "Test SGX PCK Platform CA",
+        platform_key,
+        False,
+        [
+            x509.UnrecognizedExtension(
+                x509.ObjectIdentifier(SGX_EXTENSION_OID), sgx_extension()
+            )
+        ],
+    )
+    tcb_signing = make_cert("Test SGX TCB Signing", tcb_signing_key, root_name, root_key, False)
+
+    # The quoting enclave's report binds the attestation key.
+    qe_auth_data = bytes(range(32))
+    qe_report_data = hashlib.sha256(raw_public_key(attestation_key) + qe_auth_data).digest()
+    qe_report = report_body(
+        CPU_SVN,
+        struct.pack("<QQ", 0x11, 0xE7),
+        hashlib.sha256(b"test quoting enclave").digest(),
+        QE_MRSIGNER,
+        1,
+        QE_SVN,
+        qe_report_data + bytes(32),
+    )
+
+    header = struct.pack("<HHIHH", 3, 2, 0, QE_SVN, PCE_SVN) + QE_VENDOR_ID + bytes(20)
+    app_report = report_body(
+        CPU_SVN,
+        struct.pack("<QQ", 0x05, 0x03),
+        APP_MRENCLAVE,
+        APP_MRSIGNER,
+        APP_PROD_ID,
+        APP_SVN,
+        APP_REPORT_DATA,
+    )
+    certification_data = pem(pck, platform, root)
+    signature_data = (
+        raw_sign(attestation_key, header + app_report)
+        + raw_public_key(attestation_key)
+        + qe_report
+        + raw_sign(pck_key, qe_report)
+        + struct.pack("<H", len(qe_auth_data))
+        + qe_auth_data
+        + struct.pack("<HI", 5, len(certification_data))
+        + certification_data
+    )
+    quote = header + app_report + struct.pack("<I", len(signature_data)) + signature_data
+
+    write("root_ca.pem", pem(root))
+    write("quote.dat", quote)
+    write("tcb_signing_chain.pem", pem(tcb_signing, root))
+    write("root_ca_crl.der", der_crl(make_crl(root_name, root_key)))
+    write("pck_crl.der", der_crl(make_crl("Test SGX PCK Platform CA", platform_key)))
+    # A PCK CRL which revokes the PCK certificate in the quote.
+    write(
+        "pck_crl_revoked.der",
+        der_crl(make_crl("Test SGX PCK Platform CA", platform_key, [pck])),
+    )
+    write(
+        "tcb_info_v2.json",
+        signed_json(
+            tcb_signing_key,
+            "tcbInfo",
+            tcb_info(
+                2,
+                [
+                    tcb_level_v2(2, 10, "UpToDate"),
+                    tcb_level_v2(1, 5, "OutOfDate"),
+                ],
+            ),
+        ),
+    )
+    write(
+        "tcb_info_v3.json",
+        signed_json(
+            tcb_signing_key,
+            "tcbInfo",
+            tcb_info(
+                3,
+                [
+                    tcb_level_v3(3, 11, "UpToDate", []),
+                    tcb_level_v3(2, 10, "SWHardeningNeeded", ["INTEL-SA-00334"]),
+                    tcb_level_v3(1, 5, "OutOfDate", ["INTEL-SA-00334", "INTEL-SA-00219"]),
+                ],
+            ),
+        ),
+    )
+    write(
+        "qe_identity.json",
+        signed_json(
+            tcb_signing_key,
+            "enclaveIdentity",
+            {
+                "id": "QE",
+                "version": 2,
+                "issueDate": "2021-06-01T00:00:00Z",
+                "nextUpdate": "2049-12-31T00:00:00Z",
+                "tcbEvaluationDataNumber": 11,
+                "miscselect": "00000000",
+                "miscselectMask": "FFFFFFFF",
+                "attributes": "11000000000000000000000000000000",
+                "attributesMask": "FBFFFFFFFFFFFFFF0000000000000000",
+                "mrsigner": QE_MRSIGNER.hex().upper(),
+                "isvprodid": 1,
+                "tcbLevels": [
+                    {
+                        "tcb": {"isvsvn": QE_SVN},
+                        "tcbDate": "2021-01-01T00:00:00Z",
+                        "tcbStatus": "UpToDate",
+                    },
+                    {
+                        "tcb": {"isvsvn": 0},
+                        "tcbDate": "2021-01-01T00:00:00Z",
+                        "tcbStatus": "OutOfDate",
+                    },
+                ],
+            },
+        ),
+    )
+
+
+if __name__ == "__main__":
+    main()
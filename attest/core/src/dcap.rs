@@ -0,0 +1,8 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Intel Data Center Attestation Primitives (DCAP) Support
+
+pub mod collateral;
+mod der;
+pub mod quote;
+pub mod verify;
@@ -0,0 +1,581 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! DCAP verification collateral
+//!
+//! The collateral is fetched from the Intel Provisioning Certification Service
+//! (or a caching service) for the platform which generated a quote. It is
+//! signed by Intel, so it may be delivered to the verifier by an untrusted
+//! party.
+
+use crate::{
+    error::{DcapError, JsonError},
+    ias::json::{parse, JsonValue},
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use binascii::hex2bin;
+use chrono::DateTime;
+use core::convert::TryInto;
+use mc_common::HashMap;
+use serde::{Deserialize, Serialize};
+
+/// The name of the TCB info collateral, used in errors.
+pub(crate) const TCB_INFO: &str = "TCB info";
+
+/// The name of the QE identity collateral, used in errors.
+pub(crate) const QE_IDENTITY: &str = "QE identity";
+
+/// The name of the PCK CRL, used in errors.
+pub(crate) const PCK_CRL: &str = "PCK";
+
+/// The name of the root CA CRL, used in errors.
+pub(crate) const ROOT_CA_CRL: &str = "root CA";
+
+/// The collateral needed to verify a DCAP quote.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct DcapCollateral {
+    /// The TCB info JSON for the platform's FMSPC, exactly as returned by the
+    /// PCS.
+    pub tcb_info: String,
+    /// The TCB info signing certificate chain, as an array of DER-formatted
+    /// bytes, from the SGX-TCB-Info-Issuer-Chain HTTP header.
+    pub tcb_info_issuer_chain: Vec<Vec<u8>>,
+    /// The QE identity JSON, exactly as returned by the PCS.
+    pub qe_identity: String,
+    /// The QE identity signing certificate chain, as an array of
+    /// DER-formatted bytes, from the SGX-Enclave-Identity-Issuer-Chain HTTP
+    /// header.
+    pub qe_identity_issuer_chain: Vec<Vec<u8>>,
+    /// The DER-formatted CRL of the CA which issued the PCK certificate, as
+    /// returned by the PCS.
+    pub pck_crl: Vec<u8>,
+    /// The DER-formatted CRL of the SGX Root CA.
+    pub root_ca_crl: Vec<u8>,
+}
+
+/// The status of a platform or quoting enclave TCB level.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum TcbStatus {
+    /// The TCB is up to date.
+    UpToDate,
+    /// The TCB is up to date, but the enclave requires software mitigations.
+    SwHardeningNeeded,
+    /// The TCB is up to date, but requires additional BIOS configuration.
+    ConfigurationNeeded,
+    /// The TCB is up to date, but requires additional BIOS configuration and
+    /// software mitigations.
+    ConfigurationAndSwHardeningNeeded,
+    /// The TCB is out of date.
+    OutOfDate,
+    /// The TCB is out of date, and requires additional BIOS configuration.
+    OutOfDateConfigurationNeeded,
+    /// The TCB has been revoked.
+    Revoked,
+}
+
+impl TcbStatus {
+    fn from_json(value: &str) -> Option<Self> {
+        Some(match value {
+            "UpToDate" => TcbStatus::UpToDate,
+            "SWHardeningNeeded" => TcbStatus::SwHardeningNeeded,
+            "ConfigurationNeeded" => TcbStatus::ConfigurationNeeded,
+            "ConfigurationAndSWHardeningNeeded" => TcbStatus::ConfigurationAndSwHardeningNeeded,
+            "OutOfDate" => TcbStatus::OutOfDate,
+            "OutOfDateConfigurationNeeded" => TcbStatus::OutOfDateConfigurationNeeded,
+            "Revoked" => TcbStatus::Revoked,
+            _ => return None,
+        })
+    }
+}
+
+/// A TCB level of a platform.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TcbLevel {
+    /// The minimum SVNs of the CPU TCB components.
+    pub components: [u8; 16],
+    /// The minimum SVN of the provisioning certification enclave.
+    pub pce_svn: u16,
+    /// The status of platforms at this level.
+    pub status: TcbStatus,
+    /// The advisories which apply to platforms at this level (version 3 TCB
+    /// info only).
+    pub advisory_ids: Vec<String>,
+}
+
+/// The parsed contents of a TCB info document.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TcbInfo {
+    /// The FMSPC the TCB info applies to.
+    pub fmspc: Vec<u8>,
+    /// The PCE ID the TCB info applies to.
+    pub pce_id: Vec<u8>,
+    /// When the TCB info will next be updated.
+    pub next_update: String,
+    /// The TCB levels, from highest to lowest.
+    pub tcb_levels: Vec<TcbLevel>,
+}
+
+impl TcbInfo {
+    /// Find the highest TCB level the given platform TCB meets.
+    pub fn tcb_level(&self, components: &[u8; 16], pce_svn: u16) -> Option<&TcbLevel> {
+        self.tcb_levels.iter().find(|level| {
+            pce_svn >= level.pce_svn
+                && components
+                    .iter()
+                    .zip(level.components.iter())
+                    .all(|(svn, min_svn)| svn >= min_svn)
+        })
+    }
+}
+
+/// A TCB level of the quoting enclave.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QeTcbLevel {
+    /// The minimum security version of the quoting enclave.
+    pub isv_svn: u16,
+    /// The status of quoting enclaves at this level.
+    pub status: TcbStatus,
+}
+
+/// The parsed contents of a QE identity document.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QeIdentity {
+    /// The expected misc select bits, in report byte order.
+    pub miscselect: Vec<u8>,
+    /// The misc select bits which are checked.
+    pub miscselect_mask: Vec<u8>,
+    /// The expected attributes, in report byte order.
+    pub attributes: Vec<u8>,
+    /// The attribute bits which are checked.
+    pub attributes_mask: Vec<u8>,
+    /// The expected MRSIGNER of the quoting enclave.
+    pub mr_signer: Vec<u8>,
+    /// The expected product ID of the quoting enclave.
+    pub isv_prod_id: u16,
+    /// When the QE identity will next be updated.
+    pub next_update: String,
+    /// The TCB levels, from highest to lowest.
+    pub tcb_levels: Vec<QeTcbLevel>,
+}
+
+impl QeIdentity {
+    /// Find the highest TCB level the given quoting enclave version meets.
+    pub fn tcb_level(&self, isv_svn: u16) -> Option<&QeTcbLevel> {
+        self.tcb_levels
+            .iter()
+            .find(|level| isv_svn >= level.isv_svn)
+    }
+}
+
+/// Check that a collateral document has not passed its next update time.
+///
+/// # Arguments
+/// * `kind` - The name of the document, used in errors.
+/// * `next_update` - The document's nextUpdate field, as an RFC 3339 string.
+/// * `now` - The current time, in seconds since the Unix epoch.
+pub(crate) fn check_next_update(kind: &str, next_update: &str, now: i64) -> Result<(), DcapError> {
+    let next_update_time = DateTime::parse_from_rfc3339(next_update)
+        .map_err(|_| DcapError::CollateralField(format!("{} nextUpdate", kind)))?;
+    if next_update_time.timestamp() <= now {
+        return Err(DcapError::CollateralExpired(
+            kind.to_string(),
+            next_update.to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// A collateral document, split into its signed body and signature.
+pub(crate) struct SignedCollateral<'a, T> {
+    /// The exact bytes which were signed.
+    pub body: &'a str,
+    /// The raw (r || s) signature of the body.
+    pub signature: Vec<u8>,
+    /// The parsed contents of the body.
+    pub contents: T,
+}
+
+/// Split a signed PCS document of the form
+/// `{"<name>":{...},"signature":"..."}`.
+///
+/// The signature is over the exact text of the inner object, so it is sliced
+/// out of the document rather than re-serialized.
+fn split_signed<'a>(
+    kind: &str,
+    name: &str,
+    text: &'a str,
+) -> Result<(&'a str, Vec<u8>), DcapError> {
+    const SIGNATURE_PREFIX: &str = ",\"signature\":\"";
+    const SUFFIX: &str = "\"}";
+
+    let malformed = || DcapError::CollateralField(format!("{} document", kind));
+
+    let text = text.trim();
+    let prefix = format!("{{\"{}\":", name);
+    if !text.starts_with(&prefix) || !text.ends_with(SUFFIX) {
+        return Err(malformed());
+    }
+    let signature_start = text.rfind(SIGNATURE_PREFIX).ok_or_else(malformed)?;
+    if signature_start < prefix.len()
+        || signature_start + SIGNATURE_PREFIX.len() > text.len() - SUFFIX.len()
+    {
+        return Err(malformed());
+    }
+
+    let body = &text[prefix.len()..signature_start];
+    let signature_hex = &text[signature_start + SIGNATURE_PREFIX.len()..text.len() - SUFFIX.len()];
+    let signature = decode_hex(signature_hex)
+        .filter(|signature| signature.len() == 64)
+        .ok_or_else(|| DcapError::CollateralField(format!("{} signature", kind)))?;
+
+    Ok((body, signature))
+}
+
+fn decode_hex(src: &str) -> Option<Vec<u8>> {
+    if src.len() % 2 != 0 {
+        return None;
+    }
+    let mut retval = vec![0u8; src.len() / 2];
+    hex2bin(src.as_bytes(), &mut retval).ok()?;
+    Some(retval)
+}
+
+/// Parse a JSON object.
+fn parse_object(kind: &str, text: &str) -> Result<HashMap<String, JsonValue>, DcapError> {
+    let json_err = |err| DcapError::CollateralJson(kind.to_string(), err);
+
+    let (chars_parsed, data) = parse(text);
+    let data = data.ok_or_else(|| json_err(JsonError::NoData))?;
+    if chars_parsed < text.chars().count() {
+        return Err(json_err(JsonError::IncompleteParse(chars_parsed)));
+    }
+    match data {
+        JsonValue::Object(object) => Ok(object),
+        _ => Err(json_err(JsonError::RootNotObject)),
+    }
+}
+
+/// Helpers for extracting typed fields from a JSON object.
+struct Fields<'a> {
+    kind: &'a str,
+    object: HashMap<String, JsonValue>,
+}
+
+impl<'a> Fields<'a> {
+    fn json_err(&self, err: JsonError) -> DcapError {
+        DcapError::CollateralJson(self.kind.to_string(), err)
+    }
+
+    fn invalid(&self, name: &str) -> DcapError {
+        DcapError::CollateralField(format!("{} {}", self.kind, name))
+    }
+
+    fn value(&mut self, name: &str) -> Result<JsonValue, DcapError> {
+        self.object
+            .remove(name)
+            .ok_or_else(|| self.json_err(JsonError::FieldMissing(name.to_string())))
+    }
+
+    fn string(&mut self, name: &str) -> Result<String, DcapError> {
+        self.value(name)?
+            .try_into()
+            .map_err(|err| self.json_err(err))
+    }
+
+    fn number(&mut self, name: &str) -> Result<u16, DcapError> {
+        let value: f64 = self
+            .value(name)?
+            .try_into()
+            .map_err(|err| self.json_err(err))?;
+        json_u16(value).ok_or_else(|| self.invalid(name))
+    }
+
+    fn hex(&mut self, name: &str, len: usize) -> Result<Vec<u8>, DcapError> {
+        let value = self.string(name)?;
+        decode_hex(&value)
+            .filter(|bytes| bytes.len() == len)
+            .ok_or_else(|| self.invalid(name))
+    }
+
+    fn array(&mut self, name: &str) -> Result<Vec<JsonValue>, DcapError> {
+        self.value(name)?
+            .try_into()
+            .map_err(|err| self.json_err(err))
+    }
+
+    fn object(&mut self, name: &str) -> Result<Fields<'a>, DcapError> {
+        let object: HashMap<String, JsonValue> = self
+            .value(name)?
+            .try_into()
+            .map_err(|err| self.json_err(err))?;
+        Ok(Fields {
+            kind: self.kind,
+            object,
+        })
+    }
+
+    fn status(&mut self) -> Result<TcbStatus, DcapError> {
+        let status = self.string("tcbStatus")?;
+        TcbStatus::from_json(&status).ok_or_else(|| self.invalid("tcbStatus"))
+    }
+
+    fn component(&mut self, name: &str) -> Result<u8, DcapError> {
+        let value = self.number(name)?;
+        if value > u8::MAX as u16 {
+            return Err(self.invalid(name));
+        }
+        Ok(value as u8)
+    }
+
+    fn from_value(kind: &'a str, value: JsonValue) -> Result<Fields<'a>, DcapError> {
+        let object: HashMap<String, JsonValue> = value
+            .try_into()
+            .map_err(|err| DcapError::CollateralJson(kind.to_string(), err))?;
+        Ok(Fields { kind, object })
+    }
+}
+
+fn json_u16(value: f64) -> Option<u16> {
+    if (0.0..=u16::MAX as f64).contains(&value) && value.fract() == 0.0 {
+        Some(value as u16)
+    } else {
+        None
+    }
+}
+
+/// Parse a signed TCB info document.
+pub(crate) fn parse_tcb_info(text: &str) -> Result<SignedCollateral<TcbInfo>, DcapError> {
+    let (body, signature) = split_signed(TCB_INFO, "tcbInfo", text)?;
+    let mut fields = Fields {
+        kind: TCB_INFO,
+        object: parse_object(TCB_INFO, body)?,
+    };
+
+    let version = fields.number("version")?;
+    if version != 2 && version != 3 {
+        return Err(fields.invalid("version"));
+    }
+    let fmspc = fields.hex("fmspc", 6)?;
+    let pce_id = fields.hex("pceId", 2)?;
+    let next_update = fields.string("nextUpdate")?;
+
+    let tcb_levels = fields
+        .array("tcbLevels")?
+        .into_iter()
+        .map(|level| -> Result<TcbLevel, DcapError> {
+            let mut level = Fields::from_value(TCB_INFO, level)?;
+            let mut tcb = level.object("tcb")?;
+
+            let mut components = [0u8; 16];
+            if version == 2 {
+                for (i, component) in components.iter_mut().enumerate() {
+                    let name = format!("sgxtcbcomp{:02}svn", i + 1);
+                    *component = tcb.component(&name)?;
+                }
+            } else {
+                let values = tcb.array("sgxtcbcomponents")?;
+                if values.len() != components.len() {
+                    return Err(tcb.invalid("sgxtcbcomponents"));
+                }
+                for (component, value) in components.iter_mut().zip(values) {
+                    *component = Fields::from_value(TCB_INFO, value)?.component("svn")?;
+                }
+            }
+
+            let advisory_ids = match level.object.remove("advisoryIDs") {
+                Some(ids) => TryInto::<Vec<JsonValue>>::try_into(ids)
+                    .and_then(|ids| {
+                        ids.into_iter()
+                            .map(TryInto::<String>::try_into)
+                            .collect::<Result<Vec<String>, JsonError>>()
+                    })
+                    .map_err(|err| level.json_err(err))?,
+                None => Vec::new(),
+            };
+
+            Ok(TcbLevel {
+                components,
+                pce_svn: tcb.number("pcesvn")?,
+                status: level.status()?,
+                advisory_ids,
+            })
+        })
+        .collect::<Result<Vec<TcbLevel>, DcapError>>()?;
+
+    Ok(SignedCollateral {
+        body,
+        signature,
+        contents: TcbInfo {
+            fmspc,
+            pce_id,
+            next_update,
+            tcb_levels,
+        },
+    })
+}
+
+/// Parse a signed QE identity document.
+pub(crate) fn parse_qe_identity(text: &str) -> Result<SignedCollateral<QeIdentity>, DcapError> {
+    let (body, signature) = split_signed(QE_IDENTITY, "enclaveIdentity", text)?;
+    let mut fields = Fields {
+        kind: QE_IDENTITY,
+        object: parse_object(QE_IDENTITY, body)?,
+    };
+
+    if fields.string("id")? != "QE" {
+        return Err(fields.invalid("id"));
+    }
+
+    let tcb_levels = fields
+        .array("tcbLevels")?
+        .into_iter()
+        .map(|level| -> Result<QeTcbLevel, DcapError> {
+            let mut level = Fields::from_value(QE_IDENTITY, level)?;
+            Ok(QeTcbLevel {
+                isv_svn: level.object("tcb")?.number("isvsvn")?,
+                status: level.status()?,
+            })
+        })
+        .collect::<Result<Vec<QeTcbLevel>, DcapError>>()?;
+
+    Ok(SignedCollateral {
+        body,
+        signature,
+        contents: QeIdentity {
+            miscselect: fields.hex("miscselect", 4)?,
+            miscselect_mask: fields.hex("miscselectMask", 4)?,
+            attributes: fields.hex("attributes", 16)?,
+            attributes_mask: fields.hex("attributesMask", 16)?,
+            mr_signer: fields.hex("mrsigner", 32)?,
+            isv_prod_id: fields.number("isvprodid")?,
+            next_update: fields.string("nextUpdate")?,
+            tcb_levels,
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern crate std;
+
+    const TCB_INFO_V2: &str = include_str!("../../data/test/dcap/tcb_info_v2.json");
+    const TCB_INFO_V3: &str = include_str!("../../data/test/dcap/tcb_info_v3.json");
+    const QE_IDENTITY_JSON: &str = include_str!("../../data/test/dcap/qe_identity.json");
+
+    /// Ensure a version 2 TCB info document is parsed.
+    #[test]
+    fn tcb_info_v2_ok() {
+        let tcb_info = parse_tcb_info(TCB_INFO_V2).expect("Could not parse TCB info");
+        assert!(tcb_info.body.starts_with("{\"version\":2,"));
+        assert_eq!(tcb_info.signature.len(), 64);
+
+        let contents = tcb_info.contents;
+        assert_eq!(contents.fmspc, vec![0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00]);
+        assert_eq!(contents.tcb_levels.len(), 2);
+        assert_eq!(contents.tcb_levels[0].components, [2u8; 16]);
+        assert_eq!(contents.tcb_levels[0].pce_svn, 10);
+        assert_eq!(contents.tcb_levels[0].status, TcbStatus::UpToDate);
+        assert!(contents.tcb_levels[0].advisory_ids.is_empty());
+    }
+
+    /// Ensure a version 3 TCB info document is parsed, and TCB levels are
+    /// matched from highest to lowest.
+    #[test]
+    fn tcb_info_v3_ok() {
+        let contents = parse_tcb_info(TCB_INFO_V3)
+            .expect("Could not parse TCB info")
+            .contents;
+        assert_eq!(contents.tcb_levels.len(), 3);
+
+        let level = contents
+            .tcb_level(&[2u8; 16], 10)
+            .expect("No matching TCB level");
+        assert_eq!(level.status, TcbStatus::SwHardeningNeeded);
+        assert_eq!(level.advisory_ids, vec!["INTEL-SA-00334".to_string()]);
+
+        let mut components = [3u8; 16];
+        assert_eq!(
+            contents
+                .tcb_level(&components, 11)
+                .map(|level| level.status),
+            Some(TcbStatus::UpToDate)
+        );
+        // A single lower component drops the platform to a lower level.
+        components[7] = 2;
+        assert_eq!(
+            contents
+                .tcb_level(&components, 11)
+                .map(|level| level.status),
+            Some(TcbStatus::SwHardeningNeeded)
+        );
+        assert!(contents.tcb_level(&[0u8; 16], 11).is_none());
+    }
+
+    /// Ensure a QE identity document is parsed.
+    #[test]
+    fn qe_identity_ok() {
+        let contents = parse_qe_identity(QE_IDENTITY_JSON)
+            .expect("Could not parse QE identity")
+            .contents;
+        assert_eq!(contents.isv_prod_id, 1);
+        assert_eq!(contents.attributes[0], 0x11);
+        assert_eq!(contents.mr_signer.len(), 32);
+        assert_eq!(
+            contents.tcb_level(6).map(|level| level.status),
+            Some(TcbStatus::UpToDate)
+        );
+        assert_eq!(
+            contents.tcb_level(5).map(|level| level.status),
+            Some(TcbStatus::OutOfDate)
+        );
+    }
+
+    /// Ensure collateral is rejected once its next update time has passed.
+    #[test]
+    fn next_update() {
+        let next_update = parse_tcb_info(TCB_INFO_V2)
+            .expect("Could not parse TCB info")
+            .contents
+            .next_update;
+        let next_update_time = DateTime::parse_from_rfc3339(&next_update)
+            .expect("Could not parse nextUpdate")
+            .timestamp();
+
+        assert_eq!(
+            check_next_update(TCB_INFO, &next_update, next_update_time - 1),
+            Ok(())
+        );
+        assert_eq!(
+            check_next_update(TCB_INFO, &next_update, next_update_time),
+            Err(DcapError::CollateralExpired(
+                TCB_INFO.to_string(),
+                next_update.clone()
+            ))
+        );
+        assert_eq!(
+            check_next_update(TCB_INFO, "tomorrow", 0),
+            Err(DcapError::CollateralField(
+                "TCB info nextUpdate".to_string()
+            ))
+        );
+    }
+
+    /// Ensure documents without a signature are rejected.
+    #[test]
+    fn unsigned_document() {
+        let unsigned = TCB_INFO_V2
+            .trim()
+            .rsplitn(2, ",\"signature\"")
+            .last()
+            .expect("Fixture has no signature")
+            .to_string()
+            + "}";
+        assert!(parse_tcb_info(&unsigned).is_err());
+    }
+}
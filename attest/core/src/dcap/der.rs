@@ -0,0 +1,320 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Minimal DER support for DCAP verification.
+//!
+//! mbedtls does not expose arbitrary X509 extensions, nor does it accept the
+//! raw ECDSA keys and signatures used by quotes and collateral, so this module
+//! contains just enough ASN.1 to bridge the gap.
+
+use crate::error::DcapError;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_EXTENSIONS: u8 = 0xA3;
+
+/// DER encoding of OID 1.2.840.113741.1.13.1, the SGX extensions of a PCK
+/// certificate.
+const SGX_EXTENSION_OID: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF8, 0x4D, 0x01, 0x0D, 0x01];
+
+/// The sub-identifiers of the SGX extensions we use.
+const SGX_EXT_TCB: u8 = 2;
+const SGX_EXT_PCE_ID: u8 = 3;
+const SGX_EXT_FMSPC: u8 = 4;
+
+/// The sub-identifier of the PCE SVN within the TCB extension. Components
+/// 1-16 are the CPU SVN components.
+const SGX_TCB_PCE_SVN: u8 = 17;
+
+/// The SubjectPublicKeyInfo prefix of an uncompressed NIST P-256 public key.
+const P256_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01, 0x06, 0x08, 0x2A,
+    0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00, 0x04,
+];
+
+/// The platform details recorded in the SGX extensions of a PCK certificate.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PckExtensions {
+    /// The family-model-stepping-platform-custom SKU of the platform.
+    pub fmspc: Vec<u8>,
+    /// The ID of the provisioning certification enclave.
+    pub pce_id: Vec<u8>,
+    /// The SVNs of the CPU TCB components.
+    pub tcb_components: [u8; 16],
+    /// The SVN of the provisioning certification enclave.
+    pub pce_svn: u16,
+}
+
+/// A reader over a sequence of DER-encoded values.
+struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Read the next value, returning its tag and contents.
+    fn read(&mut self) -> Result<(u8, &'a [u8]), DcapError> {
+        let malformed = || DcapError::PckExtension("malformed DER".to_string());
+
+        let (&tag, rest) = self.data.split_first().ok_or_else(malformed)?;
+        let (&first_len, rest) = rest.split_first().ok_or_else(malformed)?;
+
+        let (len, rest) = if first_len < 0x80 {
+            (first_len as usize, rest)
+        } else {
+            let num_bytes = (first_len & 0x7F) as usize;
+            if num_bytes == 0 || num_bytes > 3 || rest.len() < num_bytes {
+                return Err(malformed());
+            }
+            let len = rest[..num_bytes]
+                .iter()
+                .fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+            (len, &rest[num_bytes..])
+        };
+
+        if rest.len() < len {
+            return Err(malformed());
+        }
+        self.data = &rest[len..];
+        Ok((tag, &rest[..len]))
+    }
+
+    /// Read the next value, which must have the given tag.
+    fn expect(&mut self, expected_tag: u8) -> Result<&'a [u8], DcapError> {
+        match self.read()? {
+            (tag, contents) if tag == expected_tag => Ok(contents),
+            (tag, _) => Err(DcapError::PckExtension(format!(
+                "expected tag {:#x}, found {:#x}",
+                expected_tag, tag
+            ))),
+        }
+    }
+}
+
+/// Parse a non-negative DER integer which fits in a u16.
+fn parse_u16(contents: &[u8]) -> Result<u16, DcapError> {
+    let trimmed = match contents.iter().position(|byte| *byte != 0) {
+        Some(index) => &contents[index..],
+        None => &[],
+    };
+    if contents.first().map_or(true, |byte| byte & 0x80 != 0) || trimmed.len() > 2 {
+        return Err(DcapError::PckExtension("integer out of range".to_string()));
+    }
+    Ok(trimmed
+        .iter()
+        .fold(0u16, |acc, byte| (acc << 8) | *byte as u16))
+}
+
+/// Find the SGX extensions in a DER-encoded PCK certificate.
+pub fn pck_extensions(cert_der: &[u8]) -> Result<PckExtensions, DcapError> {
+    let certificate = DerReader::new(cert_der).expect(TAG_SEQUENCE)?;
+    let tbs_certificate = DerReader::new(certificate).expect(TAG_SEQUENCE)?;
+
+    // The extensions are the only explicitly tagged [3] field of the
+    // TBSCertificate.
+    let mut fields = DerReader::new(tbs_certificate);
+    let extensions = loop {
+        if fields.is_empty() {
+            return Err(DcapError::PckExtension("no extensions".to_string()));
+        }
+        if let (TAG_EXTENSIONS, contents) = fields.read()? {
+            break DerReader::new(contents).expect(TAG_SEQUENCE)?;
+        }
+    };
+
+    let mut extensions = DerReader::new(extensions);
+    while !extensions.is_empty() {
+        let mut extension = DerReader::new(extensions.expect(TAG_SEQUENCE)?);
+        if extension.expect(TAG_OID)? != SGX_EXTENSION_OID {
+            continue;
+        }
+        let mut value = extension.read()?;
+        if value.0 == TAG_BOOLEAN {
+            value = extension.read()?;
+        }
+        if value.0 != TAG_OCTET_STRING {
+            return Err(DcapError::PckExtension("malformed extension".to_string()));
+        }
+        return parse_sgx_extensions(value.1);
+    }
+
+    Err(DcapError::PckExtension("no SGX extensions".to_string()))
+}
+
+/// Get the final sub-identifier of an OID within the SGX extensions arc, if it
+/// is a direct child of `parent`.
+fn sgx_sub_id(oid: &[u8], parent: &[u8]) -> Option<u8> {
+    if oid.len() == parent.len() + 1 && oid.starts_with(parent) {
+        oid.last().copied()
+    } else {
+        None
+    }
+}
+
+fn parse_sgx_extensions(contents: &[u8]) -> Result<PckExtensions, DcapError> {
+    let mut fmspc = None;
+    let mut pce_id = None;
+    let mut tcb = None;
+
+    let mut items = DerReader::new(DerReader::new(contents).expect(TAG_SEQUENCE)?);
+    while !items.is_empty() {
+        let mut item = DerReader::new(items.expect(TAG_SEQUENCE)?);
+        let oid = item.expect(TAG_OID)?;
+        match sgx_sub_id(oid, SGX_EXTENSION_OID) {
+            Some(SGX_EXT_FMSPC) => fmspc = Some(item.expect(TAG_OCTET_STRING)?.to_vec()),
+            Some(SGX_EXT_PCE_ID) => pce_id = Some(item.expect(TAG_OCTET_STRING)?.to_vec()),
+            Some(SGX_EXT_TCB) => tcb = Some(parse_tcb(oid, item.expect(TAG_SEQUENCE)?)?),
+            _ => {}
+        }
+    }
+
+    let missing = |name: &str| DcapError::PckExtension(format!("missing {}", name));
+    let (tcb_components, pce_svn) = tcb.ok_or_else(|| missing("TCB"))?;
+    Ok(PckExtensions {
+        fmspc: fmspc.ok_or_else(|| missing("FMSPC"))?,
+        pce_id: pce_id.ok_or_else(|| missing("PCE ID"))?,
+        tcb_components,
+        pce_svn,
+    })
+}
+
+fn parse_tcb(tcb_oid: &[u8], contents: &[u8]) -> Result<([u8; 16], u16), DcapError> {
+    let mut components = [None; 16];
+    let mut pce_svn = None;
+
+    let mut items = DerReader::new(contents);
+    while !items.is_empty() {
+        let mut item = DerReader::new(items.expect(TAG_SEQUENCE)?);
+        match sgx_sub_id(item.expect(TAG_OID)?, tcb_oid) {
+            Some(index @ 1..=16) => {
+                let svn = parse_u16(item.expect(TAG_INTEGER)?)?;
+                if svn > u8::MAX as u16 {
+                    return Err(DcapError::PckExtension(
+                        "TCB component too large".to_string(),
+                    ));
+                }
+                components[(index - 1) as usize] = Some(svn as u8);
+            }
+            Some(SGX_TCB_PCE_SVN) => pce_svn = Some(parse_u16(item.expect(TAG_INTEGER)?)?),
+            _ => {}
+        }
+    }
+
+    let mut tcb_components = [0u8; 16];
+    for (dest, src) in tcb_components.iter_mut().zip(components.iter()) {
+        *dest = src.ok_or_else(|| DcapError::PckExtension("missing TCB component".to_string()))?;
+    }
+    let pce_svn = pce_svn.ok_or_else(|| DcapError::PckExtension("missing PCE SVN".to_string()))?;
+    Ok((tcb_components, pce_svn))
+}
+
+/// Encode a big-endian unsigned integer as a DER INTEGER.
+fn encode_integer(value: &[u8]) -> Vec<u8> {
+    let start = value
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(value.len() - 1);
+    let value = &value[start..];
+
+    let mut retval = vec![TAG_INTEGER];
+    if value[0] & 0x80 != 0 {
+        retval.push(value.len() as u8 + 1);
+        retval.push(0);
+    } else {
+        retval.push(value.len() as u8);
+    }
+    retval.extend_from_slice(value);
+    retval
+}
+
+/// Convert a raw (r || s) ECDSA P-256 signature into the DER encoding
+/// expected by mbedtls.
+pub fn ecdsa_signature(raw: &[u8]) -> Result<Vec<u8>, DcapError> {
+    if raw.len() != 64 {
+        return Err(DcapError::QuoteLength);
+    }
+    let r = encode_integer(&raw[..32]);
+    let s = encode_integer(&raw[32..]);
+
+    let mut retval = vec![TAG_SEQUENCE, (r.len() + s.len()) as u8];
+    retval.extend_from_slice(&r);
+    retval.extend_from_slice(&s);
+    Ok(retval)
+}
+
+/// Convert a raw (x || y) P-256 public key into a DER SubjectPublicKeyInfo.
+pub fn p256_public_key(raw: &[u8]) -> Result<Vec<u8>, DcapError> {
+    if raw.len() != 64 {
+        return Err(DcapError::QuoteLength);
+    }
+    let mut retval = P256_SPKI_PREFIX.to_vec();
+    retval.extend_from_slice(raw);
+    Ok(retval)
+}
+
+/// Render bytes as lowercase hex, for error messages.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dcap::quote::DcapQuote;
+    use core::convert::TryFrom;
+
+    extern crate std;
+
+    const QUOTE: &[u8] = include_bytes!("../../data/test/dcap/quote.dat");
+
+    /// Ensure raw signatures with and without high bits set encode properly.
+    #[test]
+    fn ecdsa_signature_encoding() {
+        let mut raw = [0u8; 64];
+        raw[0] = 0x80;
+        raw[31] = 1;
+        raw[63] = 0x7f;
+
+        let der = ecdsa_signature(&raw).expect("Could not encode signature");
+        let mut expected = vec![0x30, 37, 0x02, 33, 0x00];
+        expected.extend_from_slice(&raw[..32]);
+        expected.extend_from_slice(&[0x02, 1, 0x7f]);
+        assert_eq!(der, expected);
+    }
+
+    /// Ensure the SGX extensions of the fixture PCK certificate are parsed.
+    #[test]
+    fn pck_extensions_ok() {
+        let quote = DcapQuote::try_from(QUOTE).expect("Could not parse quote");
+        let chain = quote.pck_cert_chain().expect("Could not parse PCK chain");
+        let extensions = pck_extensions(&chain[0]).expect("Could not parse extensions");
+
+        assert_eq!(extensions.fmspc, vec![0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00]);
+        assert_eq!(extensions.pce_id, vec![0, 0]);
+        assert_eq!(extensions.tcb_components, [2u8; 16]);
+        assert_eq!(extensions.pce_svn, 10);
+    }
+
+    /// Ensure certificates without SGX extensions are rejected.
+    #[test]
+    fn pck_extensions_missing() {
+        let quote = DcapQuote::try_from(QUOTE).expect("Could not parse quote");
+        let chain = quote.pck_cert_chain().expect("Could not parse PCK chain");
+        assert!(pck_extensions(&chain[1]).is_err());
+    }
+}
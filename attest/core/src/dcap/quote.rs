@@ -0,0 +1,329 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! DCAP (ECDSA) quotes
+
+use crate::{error::DcapError, types::report_body::ReportBody};
+use alloc::{string::String, vec::Vec};
+use core::{
+    convert::{TryFrom, TryInto},
+    fmt::{Debug, Formatter, Result as FmtResult},
+    str,
+};
+use mbedtls::x509::Certificate;
+use serde::{Deserialize, Serialize};
+
+/// The only quote version we support.
+const QUOTE_VERSION: u16 = 3;
+
+/// The attestation key type for ECDSA-256-with-P-256.
+const ATTESTATION_KEY_TYPE_ECDSA_P256: u16 = 2;
+
+/// The certification data type for a PEM-encoded PCK certificate chain.
+const CERTIFICATION_DATA_PCK_CERT_CHAIN: u16 = 5;
+
+const HEADER_SIZE: usize = 48;
+const REPORT_BODY_SIZE: usize = 384;
+const SIGNATURE_SIZE: usize = 64;
+const ATTESTATION_KEY_SIZE: usize = 64;
+
+// Offsets of the header fields.
+const HEADER_VERSION_START: usize = 0;
+const HEADER_KEY_TYPE_START: usize = 2;
+const HEADER_QE_SVN_START: usize = 8;
+const HEADER_PCE_SVN_START: usize = 10;
+const HEADER_QE_VENDOR_ID_START: usize = 12;
+const HEADER_QE_VENDOR_ID_END: usize = 28;
+
+// Offsets of the quote fields.
+const REPORT_BODY_START: usize = HEADER_SIZE;
+const REPORT_BODY_END: usize = REPORT_BODY_START + REPORT_BODY_SIZE;
+const SIGNATURE_DATA_LEN_START: usize = REPORT_BODY_END;
+const SIGNATURE_DATA_START: usize = SIGNATURE_DATA_LEN_START + 4;
+
+// Offsets of the fixed-size signature data fields, relative to the start of
+// the signature data.
+const SIG_SIGNATURE_END: usize = SIGNATURE_SIZE;
+const SIG_ATTESTATION_KEY_END: usize = SIG_SIGNATURE_END + ATTESTATION_KEY_SIZE;
+const SIG_QE_REPORT_END: usize = SIG_ATTESTATION_KEY_END + REPORT_BODY_SIZE;
+const SIG_QE_REPORT_SIGNATURE_END: usize = SIG_QE_REPORT_END + SIGNATURE_SIZE;
+const SIG_QE_AUTH_DATA_START: usize = SIG_QE_REPORT_SIGNATURE_END + 2;
+
+const PEM_END_MARKER: &str = "-----END CERTIFICATE-----";
+
+/// An ECDSA quote, as generated by the DCAP quoting enclave.
+///
+/// This is the version 3 quote format, with an ECDSA-256-with-P-256
+/// attestation key and a PCK certificate chain as certification data. The
+/// structure is validated on construction (including deserialization), so the
+/// accessors cannot fail.
+#[derive(Clone, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(try_from = "Vec<u8>", into = "Vec<u8>")]
+pub struct DcapQuote {
+    bytes: Vec<u8>,
+    /// The offset of the QE authentication data, within the signature data.
+    qe_auth_data_end: usize,
+}
+
+impl DcapQuote {
+    /// The quote version.
+    pub fn version(&self) -> u16 {
+        self.read_u16(HEADER_VERSION_START)
+    }
+
+    /// The security version of the quoting enclave.
+    pub fn qe_svn(&self) -> u16 {
+        self.read_u16(HEADER_QE_SVN_START)
+    }
+
+    /// The security version of the provisioning certification enclave.
+    pub fn pce_svn(&self) -> u16 {
+        self.read_u16(HEADER_PCE_SVN_START)
+    }
+
+    /// The vendor of the quoting enclave.
+    pub fn qe_vendor_id(&self) -> &[u8] {
+        &self.bytes[HEADER_QE_VENDOR_ID_START..HEADER_QE_VENDOR_ID_END]
+    }
+
+    /// The report body of the attested enclave.
+    pub fn report_body(&self) -> ReportBody {
+        ReportBody::try_from(&self.bytes[REPORT_BODY_START..REPORT_BODY_END])
+            .expect("Report body was validated on construction")
+    }
+
+    /// The quote header and report body, which are signed by the attestation
+    /// key.
+    pub fn signed_data(&self) -> &[u8] {
+        &self.bytes[..REPORT_BODY_END]
+    }
+
+    /// The raw (r || s) signature of the signed data, by the attestation key.
+    pub fn signature(&self) -> &[u8] {
+        &self.signature_data()[..SIG_SIGNATURE_END]
+    }
+
+    /// The raw (x || y) attestation public key.
+    pub fn attestation_key(&self) -> &[u8] {
+        &self.signature_data()[SIG_SIGNATURE_END..SIG_ATTESTATION_KEY_END]
+    }
+
+    /// The raw bytes of the quoting enclave's report body.
+    pub fn qe_report_bytes(&self) -> &[u8] {
+        &self.signature_data()[SIG_ATTESTATION_KEY_END..SIG_QE_REPORT_END]
+    }
+
+    /// The quoting enclave's report body, which binds the attestation key.
+    pub fn qe_report_body(&self) -> ReportBody {
+        ReportBody::try_from(self.qe_report_bytes())
+            .expect("QE report body was validated on construction")
+    }
+
+    /// The raw (r || s) signature of the QE report, by the PCK certificate.
+    pub fn qe_report_signature(&self) -> &[u8] {
+        &self.signature_data()[SIG_QE_REPORT_END..SIG_QE_REPORT_SIGNATURE_END]
+    }
+
+    /// The QE authentication data.
+    pub fn qe_auth_data(&self) -> &[u8] {
+        &self.signature_data()[SIG_QE_AUTH_DATA_START..self.qe_auth_data_end]
+    }
+
+    /// The certification data, a PEM-encoded PCK certificate chain.
+    pub fn certification_data(&self) -> &[u8] {
+        &self.signature_data()[self.qe_auth_data_end + 6..]
+    }
+
+    /// Parse the PCK certificate chain into DER-encoded certificates, leaf
+    /// first.
+    pub fn pck_cert_chain(&self) -> Result<Vec<Vec<u8>>, DcapError> {
+        let pem = str::from_utf8(self.certification_data())
+            .map_err(|_| DcapError::PckCertChain)?
+            .trim_end_matches('\0');
+
+        pem.split_inclusive(PEM_END_MARKER)
+            .map(str::trim)
+            .filter(|cert_pem| !cert_pem.is_empty())
+            .map(|cert_pem| {
+                let mut cert_pem = String::from(cert_pem);
+                cert_pem.push('\0');
+                Certificate::from_pem(cert_pem.as_bytes())
+                    .map(|cert| cert.as_der().to_vec())
+                    .map_err(|_| DcapError::PckCertChain)
+            })
+            .collect()
+    }
+
+    fn signature_data(&self) -> &[u8] {
+        &self.bytes[SIGNATURE_DATA_START..]
+    }
+
+    fn read_u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes(self.bytes[offset..offset + 2].try_into().unwrap())
+    }
+}
+
+impl AsRef<[u8]> for DcapQuote {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Debug for DcapQuote {
+    fn fmt(&self, formatter: &mut Formatter) -> FmtResult {
+        write!(
+            formatter,
+            "DcapQuote {{ version: {}, qe_svn: {}, pce_svn: {}, report_body: {:?} }}",
+            self.version(),
+            self.qe_svn(),
+            self.pce_svn(),
+            self.report_body()
+        )
+    }
+}
+
+impl<'bytes> TryFrom<&'bytes [u8]> for DcapQuote {
+    type Error = DcapError;
+
+    fn try_from(src: &[u8]) -> Result<Self, DcapError> {
+        let read_u16 = |offset: usize| -> Result<u16, DcapError> {
+            src.get(offset..offset + 2)
+                .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or(DcapError::QuoteLength)
+        };
+        let read_u32 = |offset: usize| -> Result<usize, DcapError> {
+            src.get(offset..offset + 4)
+                .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+                .ok_or(DcapError::QuoteLength)
+        };
+
+        let version = read_u16(HEADER_VERSION_START)?;
+        if version != QUOTE_VERSION {
+            return Err(DcapError::UnsupportedQuoteVersion(version));
+        }
+        let key_type = read_u16(HEADER_KEY_TYPE_START)?;
+        if key_type != ATTESTATION_KEY_TYPE_ECDSA_P256 {
+            return Err(DcapError::UnsupportedAttestationKeyType(key_type));
+        }
+
+        // The signature data must fill the remainder of the quote exactly.
+        let signature_data_len = read_u32(SIGNATURE_DATA_LEN_START)?;
+        if src.len() != SIGNATURE_DATA_START + signature_data_len {
+            return Err(DcapError::QuoteLength);
+        }
+
+        let qe_auth_data_len = read_u16(SIGNATURE_DATA_START + SIG_QE_REPORT_SIGNATURE_END)?;
+        let qe_auth_data_end = SIG_QE_AUTH_DATA_START + qe_auth_data_len as usize;
+
+        let certification_data_type = read_u16(SIGNATURE_DATA_START + qe_auth_data_end)?;
+        if certification_data_type != CERTIFICATION_DATA_PCK_CERT_CHAIN {
+            return Err(DcapError::UnsupportedCertificationDataType(
+                certification_data_type,
+            ));
+        }
+        let certification_data_len = read_u32(SIGNATURE_DATA_START + qe_auth_data_end + 2)?;
+        if signature_data_len != qe_auth_data_end + 6 + certification_data_len {
+            return Err(DcapError::QuoteLength);
+        }
+
+        let retval = Self {
+            bytes: src.to_vec(),
+            qe_auth_data_end,
+        };
+
+        // Validate the report bodies once, so the accessors can't fail.
+        ReportBody::try_from(&retval.bytes[REPORT_BODY_START..REPORT_BODY_END])
+            .map_err(|_| DcapError::QuoteLength)?;
+        ReportBody::try_from(retval.qe_report_bytes()).map_err(|_| DcapError::QuoteLength)?;
+
+        Ok(retval)
+    }
+}
+
+impl TryFrom<Vec<u8>> for DcapQuote {
+    type Error = DcapError;
+
+    fn try_from(src: Vec<u8>) -> Result<Self, DcapError> {
+        Self::try_from(&src[..])
+    }
+}
+
+impl From<DcapQuote> for Vec<u8> {
+    fn from(src: DcapQuote) -> Vec<u8> {
+        src.bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::measurement::MrEnclave;
+
+    extern crate std;
+
+    const QUOTE: &[u8] = include_bytes!("../../data/test/dcap/quote.dat");
+
+    /// Ensure the fixture quote is parsed.
+    #[test]
+    fn parse_ok() {
+        let quote = DcapQuote::try_from(QUOTE).expect("Could not parse quote");
+
+        assert_eq!(quote.version(), 3);
+        assert_eq!(quote.qe_svn(), 6);
+        assert_eq!(quote.pce_svn(), 10);
+        let mut mr_enclave = [0u8; 32];
+        for (i, byte) in mr_enclave.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        assert_eq!(
+            quote.report_body().mr_enclave(),
+            MrEnclave::from(mr_enclave)
+        );
+        assert_eq!(quote.report_body().product_id(), 1);
+        assert_eq!(quote.report_body().security_version(), 3);
+        assert_eq!(quote.qe_auth_data().len(), 32);
+        assert_eq!(
+            quote.pck_cert_chain().expect("Could not parse chain").len(),
+            3
+        );
+    }
+
+    /// Ensure truncated quotes are rejected.
+    #[test]
+    fn parse_truncated() {
+        assert_eq!(
+            DcapQuote::try_from(&QUOTE[..QUOTE.len() - 1]),
+            Err(DcapError::QuoteLength)
+        );
+        assert_eq!(
+            DcapQuote::try_from(&QUOTE[..100]),
+            Err(DcapError::QuoteLength)
+        );
+    }
+
+    /// Ensure quotes are serialized as their bytes, and validated when
+    /// deserialized.
+    #[test]
+    fn serde() {
+        let quote = DcapQuote::try_from(QUOTE).expect("Could not parse quote");
+        let serialized = mc_util_serial::serialize(&quote).expect("Could not serialize quote");
+        assert_eq!(
+            mc_util_serial::deserialize::<DcapQuote>(&serialized).expect("Could not deserialize"),
+            quote
+        );
+
+        let truncated = mc_util_serial::serialize(&QUOTE[..QUOTE.len() - 1].to_vec())
+            .expect("Could not serialize bytes");
+        assert!(mc_util_serial::deserialize::<DcapQuote>(&truncated).is_err());
+    }
+
+    /// Ensure other quote versions are rejected.
+    #[test]
+    fn parse_bad_version() {
+        let mut quote = QUOTE.to_vec();
+        quote[0] = 2;
+        assert_eq!(
+            DcapQuote::try_from(quote),
+            Err(DcapError::UnsupportedQuoteVersion(2))
+        );
+    }
+}
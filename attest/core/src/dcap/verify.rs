@@ -0,0 +1,269 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! DCAP evidence and its cryptographic verification
+
+use crate::{
+    dcap::{
+        collateral::{
+            check_next_update, parse_qe_identity, parse_tcb_info, DcapCollateral, TcbStatus,
+            PCK_CRL, QE_IDENTITY, ROOT_CA_CRL, TCB_INFO,
+        },
+        der::{ecdsa_signature, p256_public_key, pck_extensions, to_hex},
+        quote::DcapQuote,
+    },
+    error::DcapError,
+};
+use alloc::{string::String, vec, vec::Vec};
+use mbedtls::{
+    alloc::{Box as MbedtlsBox, List as MbedtlsList},
+    hash::Type as HashType,
+    pk::{EcGroupId, Pk, Type as PkType},
+    x509::{Certificate, Crl, Profile},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{digest::Digest, Sha256};
+
+// Offsets of the fields of a report body which the QE identity constrains.
+const RB_MISCSELECT_START: usize = 16;
+const RB_MISCSELECT_END: usize = 20;
+const RB_ATTRIBUTES_START: usize = 48;
+const RB_ATTRIBUTES_END: usize = 64;
+
+/// Evidence that an enclave is running on a DCAP-capable platform: a quote,
+/// and the collateral needed to verify it.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct DcapEvidence {
+    /// The ECDSA quote of the enclave.
+    pub quote: DcapQuote,
+    /// The collateral for the platform which generated the quote.
+    pub collateral: DcapCollateral,
+}
+
+/// The contents of DCAP evidence which passed cryptographic verification.
+///
+/// This is the DCAP counterpart to
+/// [`VerificationReportData`](crate::VerificationReportData).
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct DcapReportData {
+    /// The verified quote.
+    pub quote: DcapQuote,
+    /// The status of the platform's TCB, taking the quoting enclave's TCB into
+    /// account.
+    pub tcb_status: TcbStatus,
+    /// The advisories which apply to the platform's TCB level.
+    pub advisory_ids: Vec<String>,
+    /// The FMSPC of the platform.
+    pub fmspc: Vec<u8>,
+    /// When the TCB info used for verification will next be updated.
+    pub tcb_info_next_update: String,
+    /// When the QE identity used for verification will next be updated.
+    pub qe_identity_next_update: String,
+}
+
+impl DcapEvidence {
+    /// Verify the quote and collateral chain back to the given trust anchors,
+    /// and determine the platform's TCB status.
+    ///
+    /// The collateral must be current as of `now`, in seconds since the Unix
+    /// epoch, and no certificate may have been revoked by its CRLs. This does
+    /// not apply any policy to the enclave itself.
+    pub(crate) fn verify(
+        &self,
+        trust_anchors: &[MbedtlsBox<Certificate>],
+        now: i64,
+    ) -> Result<DcapReportData, DcapError> {
+        if trust_anchors.is_empty() {
+            return Err(DcapError::NoTrustAnchors);
+        }
+
+        // Certificates are checked against the CRLs of the CAs which issued them.
+        let mut crls = Crl::new();
+        crls.push_from_der(&self.collateral.root_ca_crl)
+            .map_err(|_| DcapError::Crl(ROOT_CA_CRL.into()))?;
+        crls.push_from_der(&self.collateral.pck_crl)
+            .map_err(|_| DcapError::Crl(PCK_CRL.into()))?;
+
+        // The PCK certificate chain vouches for the quoting enclave.
+        let pck_chain = self.quote.pck_cert_chain()?;
+        let mut pck_cert =
+            verify_chain(&pck_chain, trust_anchors, &mut crls).ok_or(DcapError::PckCertChain)?;
+        let pck = pck_extensions(&pck_chain[0])?;
+
+        verify_signature(
+            pck_cert.public_key_mut(),
+            self.quote.qe_report_bytes(),
+            self.quote.qe_report_signature(),
+        )
+        .map_err(|_| DcapError::QeReportSignature)?;
+
+        // The quoting enclave vouches for the attestation key.
+        let qe_report_body = self.quote.qe_report_body();
+        let report_data = qe_report_body.report_data();
+        let report_data: &[u8] = report_data.as_ref();
+        let mut hasher = Sha256::new();
+        hasher.update(self.quote.attestation_key());
+        hasher.update(self.quote.qe_auth_data());
+        let expected_hash = hasher.finalize();
+        if report_data[..32] != expected_hash[..] || report_data[32..].iter().any(|byte| *byte != 0)
+        {
+            return Err(DcapError::QeReportData);
+        }
+
+        // The attestation key vouches for the enclave.
+        let mut attestation_key =
+            Pk::from_public_key(&p256_public_key(self.quote.attestation_key())?)
+                .map_err(|_| DcapError::QuoteSignature)?;
+        verify_signature(
+            &mut attestation_key,
+            self.quote.signed_data(),
+            self.quote.signature(),
+        )
+        .map_err(|_| DcapError::QuoteSignature)?;
+
+        // The TCB info determines the status of the platform.
+        let tcb_info = parse_tcb_info(&self.collateral.tcb_info)?;
+        let mut tcb_info_signer = verify_chain(
+            &self.collateral.tcb_info_issuer_chain,
+            trust_anchors,
+            &mut crls,
+        )
+        .ok_or_else(|| DcapError::CollateralChain(TCB_INFO.into()))?;
+        verify_signature(
+            tcb_info_signer.public_key_mut(),
+            tcb_info.body.as_bytes(),
+            &tcb_info.signature,
+        )
+        .map_err(|_| DcapError::CollateralSignature(TCB_INFO.into()))?;
+
+        let tcb_info = tcb_info.contents;
+        check_next_update(TCB_INFO, &tcb_info.next_update, now)?;
+        if tcb_info.fmspc != pck.fmspc {
+            return Err(DcapError::FmspcMismatch(
+                to_hex(&tcb_info.fmspc),
+                to_hex(&pck.fmspc),
+            ));
+        }
+        if tcb_info.pce_id != pck.pce_id {
+            return Err(DcapError::PceIdMismatch(
+                to_hex(&tcb_info.pce_id),
+                to_hex(&pck.pce_id),
+            ));
+        }
+        let tcb_level = tcb_info
+            .tcb_level(&pck.tcb_components, pck.pce_svn)
+            .ok_or(DcapError::NoMatchingTcbLevel)?;
+
+        // The QE identity determines whether the quoting enclave is genuine and
+        // up to date.
+        let qe_identity = parse_qe_identity(&self.collateral.qe_identity)?;
+        let mut qe_identity_signer = verify_chain(
+            &self.collateral.qe_identity_issuer_chain,
+            trust_anchors,
+            &mut crls,
+        )
+        .ok_or_else(|| DcapError::CollateralChain(QE_IDENTITY.into()))?;
+        verify_signature(
+            qe_identity_signer.public_key_mut(),
+            qe_identity.body.as_bytes(),
+            &qe_identity.signature,
+        )
+        .map_err(|_| DcapError::CollateralSignature(QE_IDENTITY.into()))?;
+
+        let qe_identity = qe_identity.contents;
+        check_next_update(QE_IDENTITY, &qe_identity.next_update, now)?;
+        let qe_report_bytes = self.quote.qe_report_bytes();
+        let qe_mr_signer = qe_report_body.mr_signer();
+        let qe_mr_signer: &[u8] = qe_mr_signer.as_ref();
+        if qe_mr_signer != qe_identity.mr_signer.as_slice()
+            || qe_report_body.product_id() != qe_identity.isv_prod_id
+            || !masked_eq(
+                &qe_report_bytes[RB_MISCSELECT_START..RB_MISCSELECT_END],
+                &qe_identity.miscselect,
+                &qe_identity.miscselect_mask,
+            )
+            || !masked_eq(
+                &qe_report_bytes[RB_ATTRIBUTES_START..RB_ATTRIBUTES_END],
+                &qe_identity.attributes,
+                &qe_identity.attributes_mask,
+            )
+        {
+            return Err(DcapError::QeIdentityMismatch);
+        }
+        let qe_tcb_level = qe_identity
+            .tcb_level(qe_report_body.security_version())
+            .ok_or(DcapError::NoMatchingTcbLevel)?;
+
+        // An outdated quoting enclave makes the whole platform out of date.
+        let tcb_status = match qe_tcb_level.status {
+            TcbStatus::UpToDate => tcb_level.status,
+            TcbStatus::Revoked => TcbStatus::Revoked,
+            _ if tcb_level.status == TcbStatus::Revoked => TcbStatus::Revoked,
+            _ => TcbStatus::OutOfDate,
+        };
+
+        Ok(DcapReportData {
+            quote: self.quote.clone(),
+            tcb_status,
+            advisory_ids: tcb_level.advisory_ids.clone(),
+            fmspc: pck.fmspc,
+            tcb_info_next_update: tcb_info.next_update,
+            qe_identity_next_update: qe_identity.next_update,
+        })
+    }
+}
+
+/// Compare `actual` against `expected`, ignoring bits not set in `mask`.
+fn masked_eq(actual: &[u8], expected: &[u8], mask: &[u8]) -> bool {
+    actual.len() == expected.len()
+        && actual.len() == mask.len()
+        && actual
+            .iter()
+            .zip(expected.iter())
+            .zip(mask.iter())
+            .all(|((actual, expected), mask)| actual & mask == expected & mask)
+}
+
+/// Verify a raw ECDSA P-256 signature over the SHA-256 hash of `data`.
+fn verify_signature(key: &mut Pk, data: &[u8], raw_signature: &[u8]) -> Result<(), DcapError> {
+    let hash = Sha256::digest(data);
+    let signature = ecdsa_signature(raw_signature)?;
+    key.verify(HashType::Sha256, hash.as_slice(), &signature)
+        .map_err(|_| DcapError::QuoteSignature)
+}
+
+/// Verify a DER-encoded certificate chain (leaf first) against the given trust
+/// anchors and CRLs, returning the leaf certificate.
+///
+/// Unlike IAS report signing chains, DCAP chains are always provided in order.
+fn verify_chain(
+    chain: &[Vec<u8>],
+    trust_anchors: &[MbedtlsBox<Certificate>],
+    crls: &mut Crl,
+) -> Option<MbedtlsBox<Certificate>> {
+    let certs = chain
+        .iter()
+        .map(|der| Certificate::from_der(der))
+        .collect::<Result<Vec<MbedtlsBox<Certificate>>, _>>()
+        .ok()?;
+    let leaf = certs.first()?.clone();
+
+    let mut chain_list = MbedtlsList::new();
+    for cert in certs {
+        chain_list.push(cert);
+    }
+    let mut trust_list = MbedtlsList::new();
+    for cert in trust_anchors {
+        trust_list.push(cert.clone());
+    }
+
+    // Intel signs DCAP certificates and collateral with NIST P-256 keys.
+    let profile = Profile::new(
+        vec![HashType::Sha256],
+        vec![PkType::Ecdsa],
+        vec![EcGroupId::SecP256R1],
+        2048,
+    );
+    Certificate::verify_with_profile(&chain_list, &trust_list, Some(crls), Some(&profile), None)
+        .ok()
+        .map(|_| leaf)
+}
@@ -24,6 +24,80 @@ use mc_sgx_types::sgx_status_t;
 use mc_util_encodings::Error as EncodingError;
 use serde::{Deserialize, Serialize};
 
+/// An enumeration of errors which can occur while parsing or verifying DCAP
+/// evidence
+#[derive(Clone, Debug, Deserialize, Display, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum DcapError {
+    /// The quote is too short, or its lengths are inconsistent
+    QuoteLength,
+    /// Unsupported quote version: {0}
+    UnsupportedQuoteVersion(u16),
+    /// Unsupported attestation key type: {0}
+    UnsupportedAttestationKeyType(u16),
+    /// Unsupported certification data type: {0}
+    UnsupportedCertificationDataType(u16),
+    /// No trust anchors were configured for DCAP evidence
+    NoTrustAnchors,
+    /// The PCK certificate chain is malformed, revoked, or not issued by a
+    /// trust anchor
+    PckCertChain,
+    /// The SGX extensions of the PCK certificate could not be parsed: {0}
+    PckExtension(String),
+    /// The quoting enclave report was not signed by the PCK certificate
+    QeReportSignature,
+    /// The quoting enclave report does not bind the attestation key
+    QeReportData,
+    /// The quote was not signed by the attestation key
+    QuoteSignature,
+    /// The {0} signing chain is malformed, revoked, or not issued by a trust
+    /// anchor
+    CollateralChain(String),
+    /// The {0} CRL could not be parsed
+    Crl(String),
+    /// The {0} expired at {1}
+    CollateralExpired(String, String),
+    /// No time was given to check the DCAP collateral against
+    NoVerificationTime,
+    /// The {0} signature is invalid
+    CollateralSignature(String),
+    /// There was an error parsing the {0} JSON: {1}
+    CollateralJson(String, JsonError),
+    /// The {0} field contains an invalid value
+    CollateralField(String),
+    /// The TCB info is for FMSPC {0}, but the PCK certificate is for {1}
+    FmspcMismatch(String, String),
+    /// The TCB info is for PCE ID {0}, but the PCK certificate is for {1}
+    PceIdMismatch(String, String),
+    /// The platform TCB is lower than all levels in the TCB info
+    NoMatchingTcbLevel,
+    /// The quoting enclave does not match the QE identity
+    QeIdentityMismatch,
+}
+
+/// An enumeration of errors which can occur while loading an attestation
+/// policy
+#[derive(Clone, Debug, Deserialize, Display, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum PolicyError {
+    /// The policy could not be read: {0}
+    Io(String),
+    /// The policy could not be parsed: {0}
+    Parse(String),
+    /// Unrecognized policy file extension, expected .toml or .json: {0}
+    UnknownFormat(String),
+    /// The policy does not allow any enclaves
+    NoEnclaves,
+    /// Enclave {0} must specify exactly one of mr_enclave or mr_signer
+    Measurement(usize),
+    /// Enclave {0} has an invalid measurement
+    InvalidMeasurement(usize),
+    /// Enclave {0} specifies mr_signer without product_id and minimum_svn
+    MissingSignerFields(usize),
+    /// Enclave {0} specifies product_id or minimum_svn with mr_enclave
+    UnexpectedSignerFields(usize),
+    /// The policy contains an invalid trust anchor: {0}
+    TrustAnchor(String),
+}
+
 /// A collection of errors surrounding the EPID pseudonym
 #[derive(
     Clone, Copy, Debug, Deserialize, Eq, Fail, Hash, Ord, PartialEq, PartialOrd, Serialize,
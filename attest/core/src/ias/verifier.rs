@@ -5,7 +5,11 @@
 use alloc::vec;
 
 use crate::{
-    error::{IasQuoteError, IasQuoteResult, VerifyError},
+    dcap::{
+        collateral::TcbStatus,
+        verify::{DcapEvidence, DcapReportData},
+    },
+    error::{DcapError, IasQuoteError, IasQuoteResult, VerifyError},
     ias::verify::{VerificationReport, VerificationReportData},
     nonce::IasNonce,
     quote::{Quote, QuoteSignType},
@@ -41,6 +45,7 @@ use mbedtls::{
 
 use mc_sgx_css::Signature;
 use mc_sgx_types::SGX_FLAGS_DEBUG;
+use mc_util_encodings::Error as EncodingError;
 use serde::{Deserialize, Serialize};
 use sha2::{digest::Digest, Sha256};
 
@@ -73,6 +78,41 @@ pub enum Error {
      * requirements, report contents: {0:?}
      */
     Verification(VerificationReportData),
+    /// The DCAP evidence could not be verified: {0}
+    Dcap(DcapError),
+    /**
+     * The DCAP evidence was properly constructed, but did not meet security
+     * requirements, evidence contents: {0:?}
+     */
+    DcapVerification(DcapReportData),
+}
+
+/// Evidence that an enclave is genuine, which a [`Verifier`] can check.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Evidence {
+    /// An IAS verification report for an EPID quote.
+    Ias(VerificationReport),
+    /// A DCAP quote and its collateral.
+    Dcap(DcapEvidence),
+}
+
+/// The contents of evidence which a [`Verifier`] accepted.
+#[derive(Clone, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub enum VerifiedEvidence {
+    /// The data from a verified IAS report.
+    Ias(VerificationReportData),
+    /// The data from verified DCAP evidence.
+    Dcap(DcapReportData),
+}
+
+impl VerifiedEvidence {
+    /// Retrieve the report body of the attested enclave.
+    pub fn report_body(&self) -> Result<ReportBody, EncodingError> {
+        match self {
+            VerifiedEvidence::Ias(data) => data.quote.report_body(),
+            VerifiedEvidence::Dcap(data) => Ok(data.quote.report_body()),
+        }
+    }
 }
 
 /// A builder structure used to construct a report verifier based on the
@@ -81,6 +121,11 @@ pub enum Error {
 pub struct Verifier {
     /// A list of DER-encoded trust anchor certificates.
     trust_anchors: Vec<Vec<u8>>,
+    /// A list of DER-encoded trust anchor certificates for DCAP evidence.
+    dcap_trust_anchors: Vec<Vec<u8>>,
+    /// The time DCAP collateral must be current as of, in seconds since the
+    /// Unix epoch, or `None` for the current time.
+    dcap_verification_time: Option<i64>,
     report_body_verifiers: Vec<VerifyReportBodyType>,
     quote_verifiers: Vec<VerifyQuoteType>,
     ias_verifiers: Vec<VerifyIasReportDataType>,
@@ -99,27 +144,10 @@ impl Verifier {
     /// Create a new builder object to generate an IAS report verifier using the
     /// given trust anchor.
     pub fn new(pem_trust_anchors: &[&str]) -> Result<Self, Error> {
-        // We parse the PEM into certificates first, then back into the DER
-        // bytes.
-        let trust_anchors = pem_trust_anchors
-            .iter()
-            .map(|pem| {
-                if !pem.ends_with('\0') {
-                    let mut tmp_str = String::from(*pem);
-                    tmp_str.push('\0');
-                    Certificate::from_pem(tmp_str.as_bytes())
-                } else {
-                    Certificate::from_pem(pem.as_bytes())
-                }
-            })
-            .collect::<Result<Vec<MbedtlsBox<Certificate>>, TlsError>>()
-            .map_err(|e| Error::InvalidTrustAnchor(e.to_string()))?
-            .into_iter()
-            .map(|cert| cert.as_der().to_owned())
-            .collect::<Vec<Vec<u8>>>();
-
         Ok(Self {
-            trust_anchors,
+            trust_anchors: parse_trust_anchors(pem_trust_anchors)?,
+            dcap_trust_anchors: Default::default(),
+            dcap_verification_time: None,
             report_body_verifiers: Default::default(),
             quote_verifiers: Default::default(),
             ias_verifiers: Default::default(),
@@ -127,6 +155,25 @@ impl Verifier {
         })
     }
 
+    /// Trust DCAP evidence which chains back to the given root certificates.
+    ///
+    /// No DCAP evidence is accepted until this is set, typically to the Intel
+    /// SGX Root CA.
+    pub fn dcap_trust_anchors(&mut self, pem_trust_anchors: &[&str]) -> Result<&mut Self, Error> {
+        self.dcap_trust_anchors = parse_trust_anchors(pem_trust_anchors)?;
+        Ok(self)
+    }
+
+    /// Check DCAP collateral against the given time, in seconds since the Unix
+    /// epoch, rather than the current time.
+    ///
+    /// Without the `std` feature there is no clock, so this must be set for
+    /// DCAP evidence to be accepted.
+    pub fn dcap_verification_time(&mut self, unix_time: i64) -> &mut Self {
+        self.dcap_verification_time = Some(unix_time);
+        self
+    }
+
     /// Verify that the nonce contained within the report matches the nonce
     /// provided.
     ///
@@ -362,6 +409,92 @@ impl Verifier {
         }
         .verify(report)
     }
+
+    /// Verify DCAP evidence against the trust anchors and criteria given.
+    ///
+    /// The report body and MRENCLAVE/MRSIGNER criteria are applied exactly as
+    /// they are for IAS reports, with the platform's TCB status standing in
+    /// for the IAS quote status. Criteria which only make sense for EPID
+    /// quotes or IAS reports (nonce, PSE, basename, EPID group, etc.) are
+    /// ignored.
+    pub fn verify_dcap(&self, evidence: &DcapEvidence) -> Result<DcapReportData, Error> {
+        let trust_anchors = self
+            .dcap_trust_anchors
+            .iter()
+            .map(|cert_der| {
+                Certificate::from_der(cert_der.as_slice())
+                    .expect("Trust anchors modified after Verifier creation")
+            })
+            .collect::<Vec<MbedtlsBox<Certificate>>>();
+
+        let now = match self.dcap_verification_time {
+            Some(unix_time) => unix_time,
+            None => current_time().ok_or(Error::Dcap(DcapError::NoVerificationTime))?,
+        };
+
+        let report_data = evidence.verify(&trust_anchors, now).map_err(Error::Dcap)?;
+        let report_body = report_data.quote.report_body();
+
+        if self
+            .report_body_verifiers
+            .iter()
+            .all(|verifier| verifier.verify(&report_body))
+            && (self.status_verifiers.is_empty()
+                || self
+                    .status_verifiers
+                    .iter()
+                    .any(|verifier| verifier.verify(&report_data)))
+        {
+            Ok(report_data)
+        } else {
+            Err(Error::DcapVerification(report_data))
+        }
+    }
+
+    /// Verify either kind of evidence against the criteria given.
+    pub fn verify_evidence(&self, evidence: &Evidence) -> Result<VerifiedEvidence, Error> {
+        match evidence {
+            Evidence::Ias(report) => self.verify(report).map(VerifiedEvidence::Ias),
+            Evidence::Dcap(evidence) => self.verify_dcap(evidence).map(VerifiedEvidence::Dcap),
+        }
+    }
+}
+
+/// The current time, in seconds since the Unix epoch, if there is a clock.
+#[cfg(feature = "std")]
+fn current_time() -> Option<i64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_secs() as i64)
+}
+
+/// The current time, in seconds since the Unix epoch, if there is a clock.
+#[cfg(not(feature = "std"))]
+fn current_time() -> Option<i64> {
+    None
+}
+
+/// Parse PEM-encoded certificates into DER bytes.
+fn parse_trust_anchors(pem_trust_anchors: &[&str]) -> Result<Vec<Vec<u8>>, Error> {
+    // We parse the PEM into certificates first, then back into the DER
+    // bytes.
+    Ok(pem_trust_anchors
+        .iter()
+        .map(|pem| {
+            if !pem.ends_with('\0') {
+                let mut tmp_str = String::from(*pem);
+                tmp_str.push('\0');
+                Certificate::from_pem(tmp_str.as_bytes())
+            } else {
+                Certificate::from_pem(pem.as_bytes())
+            }
+        })
+        .collect::<Result<Vec<MbedtlsBox<Certificate>>, TlsError>>()
+        .map_err(|e| Error::InvalidTrustAnchor(e.to_string()))?
+        .into_iter()
+        .map(|cert| cert.as_der().to_owned())
+        .collect())
 }
 
 /// A structure which can verify a top-level report.
@@ -611,6 +744,20 @@ impl Verify<VerificationReportData> for VerifyIasReportDataType {
     }
 }
 
+impl Verify<DcapReportData> for VerifyIasReportDataType {
+    fn verify(&self, report_data: &DcapReportData) -> bool {
+        match self {
+            VerifyIasReportDataType::Enclave(v) => v.verify(report_data),
+            VerifyIasReportDataType::Signer(v) => v.verify(report_data),
+            // These only apply to IAS reports, and are never used as status
+            // verifiers.
+            VerifyIasReportDataType::Nonce(_)
+            | VerifyIasReportDataType::Quote(_)
+            | VerifyIasReportDataType::Pse(_) => false,
+        }
+    }
+}
+
 /// A [`VerifyIasReportData`] implementation that will check report data for the
 /// presence of the given IAS nonce.
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -645,6 +792,24 @@ fn check_ids(quote_status: &IasQuoteResult, config_ids: &[String], sw_ids: &[Str
     }
 }
 
+/// A helper function used to check exceptions to the TCB status = fail rule.
+fn check_tcb_ids(
+    tcb_status: TcbStatus,
+    advisory_ids: &[String],
+    config_ids: &[String],
+    sw_ids: &[String],
+) -> bool {
+    match tcb_status {
+        TcbStatus::UpToDate => true,
+        TcbStatus::ConfigurationNeeded => advisory_ids.iter().all(|id| config_ids.contains(id)),
+        TcbStatus::SwHardeningNeeded => advisory_ids.iter().all(|id| sw_ids.contains(id)),
+        TcbStatus::ConfigurationAndSwHardeningNeeded => advisory_ids
+            .iter()
+            .all(|id| config_ids.contains(id) || sw_ids.contains(id)),
+        _ => false,
+    }
+}
+
 /// A [`VerifyIasReportData`] implementation that will check if the enclave in
 /// question has the given MrEnclave, and has no other IAS report status issues.
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -723,6 +888,18 @@ impl Verify<VerificationReportData> for MrEnclaveVerifier {
     }
 }
 
+impl Verify<DcapReportData> for MrEnclaveVerifier {
+    fn verify(&self, data: &DcapReportData) -> bool {
+        self.mr_enclave == data.quote.report_body().mr_enclave()
+            && check_tcb_ids(
+                data.tcb_status,
+                &data.advisory_ids,
+                &self.config_ids,
+                &self.sw_ids,
+            )
+    }
+}
+
 /// A [`VerifyIasReportData`] implementation that will check if the enclave in
 /// question has the given MrSigner value, and has no other IAS report status
 /// issues.
@@ -818,6 +995,21 @@ impl Verify<VerificationReportData> for MrSignerVerifier {
     }
 }
 
+impl Verify<DcapReportData> for MrSignerVerifier {
+    fn verify(&self, data: &DcapReportData) -> bool {
+        let report_body = data.quote.report_body();
+        self.mr_signer == report_body.mr_signer()
+            && report_body.product_id() == self.product_id
+            && report_body.security_version() >= self.minimum_svn
+            && check_tcb_ids(
+                data.tcb_status,
+                &data.advisory_ids,
+                &self.config_ids,
+                &self.sw_ids,
+            )
+    }
+}
+
 /// A [`VerifyIasReportData`] implementation which applies a list of verifiers
 /// against the quote structure.
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -1217,7 +1409,10 @@ impl Verify<ReportBody> for VersionVerifier {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::ias::verify::VerificationSignature;
+    use crate::{
+        dcap::{collateral::DcapCollateral, quote::DcapQuote},
+        ias::verify::VerificationSignature,
+    };
     use mc_sgx_types::{
         sgx_attributes_t, sgx_basename_t, sgx_cpu_svn_t, sgx_measurement_t, sgx_report_body_t,
         sgx_report_data_t,
@@ -2309,4 +2504,243 @@ mod test {
 
         assert!(!verifier.verify(&report_body));
     }
+
+    const DCAP_ROOT_CA: &str = include_str!("../../data/test/dcap/root_ca.pem");
+    const DCAP_QUOTE: &[u8] = include_bytes!("../../data/test/dcap/quote.dat");
+    const DCAP_TCB_SIGNING_CHAIN: &str = include_str!("../../data/test/dcap/tcb_signing_chain.pem");
+    const DCAP_TCB_INFO_V2: &str = include_str!("../../data/test/dcap/tcb_info_v2.json");
+    const DCAP_TCB_INFO_V3: &str = include_str!("../../data/test/dcap/tcb_info_v3.json");
+    const DCAP_QE_IDENTITY: &str = include_str!("../../data/test/dcap/qe_identity.json");
+    const DCAP_PCK_CRL: &[u8] = include_bytes!("../../data/test/dcap/pck_crl.der");
+    const DCAP_PCK_CRL_REVOKED: &[u8] = include_bytes!("../../data/test/dcap/pck_crl_revoked.der");
+    const DCAP_ROOT_CA_CRL: &[u8] = include_bytes!("../../data/test/dcap/root_ca_crl.der");
+    /// The nextUpdate of the DCAP collateral fixtures, 2049-12-31T00:00:00Z.
+    const DCAP_NEXT_UPDATE: i64 = 2_524_521_600;
+
+    fn dcap_mr_enclave() -> MrEnclave {
+        let mut mr_enclave = [0u8; 32];
+        for (i, byte) in mr_enclave.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        MrEnclave::from(mr_enclave)
+    }
+
+    fn dcap_mr_signer() -> MrSigner {
+        let mut mr_signer = [0u8; 32];
+        for (i, byte) in mr_signer.iter_mut().enumerate() {
+            *byte = 32 + i as u8;
+        }
+        MrSigner::from(mr_signer)
+    }
+
+    fn get_dcap_evidence(tcb_info: &str) -> DcapEvidence {
+        let chain = pem::parse_many(DCAP_TCB_SIGNING_CHAIN)
+            .into_iter()
+            .map(|pem| pem.contents)
+            .collect::<Vec<Vec<u8>>>();
+
+        DcapEvidence {
+            quote: DcapQuote::try_from(DCAP_QUOTE).expect("Could not parse DCAP quote"),
+            collateral: DcapCollateral {
+                tcb_info: tcb_info.trim().to_owned(),
+                tcb_info_issuer_chain: chain.clone(),
+                qe_identity: DCAP_QE_IDENTITY.trim().to_owned(),
+                qe_identity_issuer_chain: chain,
+                pck_crl: DCAP_PCK_CRL.to_vec(),
+                root_ca_crl: DCAP_ROOT_CA_CRL.to_vec(),
+            },
+        }
+    }
+
+    fn get_dcap_verifier() -> Verifier {
+        let mut verifier = Verifier::new(TEST_ANCHORS).expect("Could not initialize new verifier");
+        verifier
+            .dcap_trust_anchors(&[DCAP_ROOT_CA])
+            .expect("Could not parse DCAP trust anchors");
+        verifier
+    }
+
+    /// Ensure DCAP evidence for an up-to-date platform passes an MRENCLAVE
+    /// verifier.
+    #[test]
+    fn dcap_mrenclave_ok() {
+        let data = get_dcap_verifier()
+            .mr_enclave(MrEnclaveVerifier::new(dcap_mr_enclave()))
+            .verify_dcap(&get_dcap_evidence(DCAP_TCB_INFO_V2))
+            .expect("Could not verify DCAP evidence");
+
+        assert_eq!(data.tcb_status, TcbStatus::UpToDate);
+        assert!(data.advisory_ids.is_empty());
+        assert_eq!(data.fmspc, vec![0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00]);
+    }
+
+    /// Ensure DCAP evidence with the wrong MRENCLAVE fails.
+    #[test]
+    fn dcap_mrenclave_fail() {
+        let result = get_dcap_verifier()
+            .mr_enclave(MrEnclaveVerifier::new(MrEnclave::from(&MR_ENCLAVE)))
+            .verify_dcap(&get_dcap_evidence(DCAP_TCB_INFO_V2));
+
+        assert!(matches!(result, Err(Error::DcapVerification(_))));
+    }
+
+    /// Ensure DCAP evidence passes an MRSIGNER verifier, and report body
+    /// verifiers are applied.
+    #[test]
+    fn dcap_mrsigner_ok() {
+        get_dcap_verifier()
+            .mr_signer(MrSignerVerifier::new(dcap_mr_signer(), 1, 3))
+            .debug(true)
+            .product_id(1)
+            .verify_dcap(&get_dcap_evidence(DCAP_TCB_INFO_V2))
+            .expect("Could not verify DCAP evidence");
+    }
+
+    /// Ensure DCAP evidence fails a report body verifier it doesn't match.
+    #[test]
+    fn dcap_product_id_fail() {
+        let result = get_dcap_verifier()
+            .product_id(2)
+            .verify_dcap(&get_dcap_evidence(DCAP_TCB_INFO_V2));
+
+        assert!(matches!(result, Err(Error::DcapVerification(_))));
+    }
+
+    /// Ensure a SWHardeningNeeded TCB status requires the advisory to be
+    /// allowed.
+    #[test]
+    fn dcap_sw_hardening() {
+        let evidence = get_dcap_evidence(DCAP_TCB_INFO_V3);
+
+        let result = get_dcap_verifier()
+            .mr_enclave(MrEnclaveVerifier::new(dcap_mr_enclave()))
+            .verify_dcap(&evidence);
+        match result {
+            Err(Error::DcapVerification(data)) => {
+                assert_eq!(data.tcb_status, TcbStatus::SwHardeningNeeded);
+                assert_eq!(data.advisory_ids, vec!["INTEL-SA-00334".to_owned()]);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        let mut mr_enclave = MrEnclaveVerifier::new(dcap_mr_enclave());
+        mr_enclave.allow_hardening_advisory("INTEL-SA-00334");
+        get_dcap_verifier()
+            .mr_enclave(mr_enclave)
+            .verify_dcap(&evidence)
+            .expect("Could not verify DCAP evidence");
+    }
+
+    /// Ensure DCAP evidence is rejected when no DCAP trust anchors are set.
+    #[test]
+    fn dcap_no_trust_anchors() {
+        let result = Verifier::new(TEST_ANCHORS)
+            .expect("Could not initialize new verifier")
+            .verify_dcap(&get_dcap_evidence(DCAP_TCB_INFO_V2));
+
+        assert_eq!(result, Err(Error::Dcap(DcapError::NoTrustAnchors)));
+    }
+
+    /// Ensure DCAP evidence is rejected when it does not chain to a trust
+    /// anchor.
+    #[test]
+    fn dcap_untrusted_root() {
+        let result = Verifier::new(TEST_ANCHORS)
+            .expect("Could not initialize new verifier")
+            .dcap_trust_anchors(TEST_ANCHORS)
+            .expect("Could not parse DCAP trust anchors")
+            .verify_dcap(&get_dcap_evidence(DCAP_TCB_INFO_V2));
+
+        assert_eq!(result, Err(Error::Dcap(DcapError::PckCertChain)));
+    }
+
+    /// Ensure a modified quote is rejected.
+    #[test]
+    fn dcap_tampered_quote() {
+        let mut evidence = get_dcap_evidence(DCAP_TCB_INFO_V2);
+        let mut quote = DCAP_QUOTE.to_vec();
+        // Flip a bit in the enclave's report data.
+        quote[48 + 320] ^= 1;
+        evidence.quote = DcapQuote::try_from(quote).expect("Could not parse DCAP quote");
+
+        let result = get_dcap_verifier().verify_dcap(&evidence);
+        assert_eq!(result, Err(Error::Dcap(DcapError::QuoteSignature)));
+    }
+
+    /// Ensure modified collateral is rejected.
+    #[test]
+    fn dcap_tampered_tcb_info() {
+        let tcb_info = DCAP_TCB_INFO_V2.replace("\"pcesvn\":5", "\"pcesvn\":4");
+        let result = get_dcap_verifier().verify_dcap(&get_dcap_evidence(&tcb_info));
+
+        assert_eq!(
+            result,
+            Err(Error::Dcap(DcapError::CollateralSignature(
+                "TCB info".to_owned()
+            )))
+        );
+    }
+
+    /// Ensure DCAP evidence is rejected once its collateral has expired.
+    #[test]
+    fn dcap_expired_collateral() {
+        get_dcap_verifier()
+            .dcap_verification_time(DCAP_NEXT_UPDATE - 1)
+            .verify_dcap(&get_dcap_evidence(DCAP_TCB_INFO_V2))
+            .expect("Could not verify DCAP evidence");
+
+        let result = get_dcap_verifier()
+            .dcap_verification_time(DCAP_NEXT_UPDATE)
+            .verify_dcap(&get_dcap_evidence(DCAP_TCB_INFO_V2));
+        assert_eq!(
+            result,
+            Err(Error::Dcap(DcapError::CollateralExpired(
+                "TCB info".to_owned(),
+                "2049-12-31T00:00:00Z".to_owned()
+            )))
+        );
+    }
+
+    /// Ensure DCAP evidence is rejected when the PCK certificate was revoked.
+    #[test]
+    fn dcap_revoked_pck() {
+        let mut evidence = get_dcap_evidence(DCAP_TCB_INFO_V2);
+        evidence.collateral.pck_crl = DCAP_PCK_CRL_REVOKED.to_vec();
+
+        let result = get_dcap_verifier().verify_dcap(&evidence);
+        assert_eq!(result, Err(Error::Dcap(DcapError::PckCertChain)));
+    }
+
+    /// Ensure DCAP evidence is rejected without CRLs.
+    #[test]
+    fn dcap_missing_crl() {
+        let mut evidence = get_dcap_evidence(DCAP_TCB_INFO_V2);
+        evidence.collateral.pck_crl = Vec::new();
+
+        let result = get_dcap_verifier().verify_dcap(&evidence);
+        assert_eq!(result, Err(Error::Dcap(DcapError::Crl("PCK".to_owned()))));
+    }
+
+    /// Ensure both kinds of evidence can be checked by the same verifier.
+    #[test]
+    fn verify_evidence() {
+        let mut verifier = get_dcap_verifier();
+        verifier.debug(true);
+
+        let verified = verifier
+            .verify_evidence(&Evidence::Dcap(get_dcap_evidence(DCAP_TCB_INFO_V2)))
+            .expect("Could not verify DCAP evidence");
+        assert_eq!(
+            verified
+                .report_body()
+                .expect("Could not read report body")
+                .mr_enclave(),
+            dcap_mr_enclave()
+        );
+
+        let verified = verifier
+            .verify_evidence(&Evidence::Ias(get_ias_report()))
+            .expect("Could not verify IAS report");
+        assert!(matches!(verified, VerifiedEvidence::Ias(_)));
+    }
 }
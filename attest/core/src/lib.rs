@@ -7,12 +7,16 @@
 #![feature(core_intrinsics)]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 use cfg_if::cfg_if;
 
+mod dcap;
 mod error;
 mod ias;
 mod nonce;
+mod policy;
 mod quote;
 mod report;
 mod seal;
@@ -21,19 +25,29 @@ mod traits;
 mod types;
 
 pub use crate::{
+    dcap::{
+        collateral::{DcapCollateral, TcbStatus},
+        quote::DcapQuote,
+        verify::{DcapEvidence, DcapReportData},
+    },
     error::{
-        EpidPseudonymError, IasQuoteError, IasQuoteResult, JsonError, NonceError, PibError,
-        PseManifestError, PseManifestHashError, PseManifestResult, QuoteError, QuoteSignTypeError,
-        QuoteVerifyError, ReportBodyVerifyError, ReportDetailsError, RevocationCause, SgxError,
-        SgxResult, SignatureError, TargetInfoError, VerifyError,
+        DcapError, EpidPseudonymError, IasQuoteError, IasQuoteResult, JsonError, NonceError,
+        PibError, PolicyError, PseManifestError, PseManifestHashError, PseManifestResult,
+        QuoteError, QuoteSignTypeError, QuoteVerifyError, ReportBodyVerifyError,
+        ReportDetailsError, RevocationCause, SgxError, SgxResult, SignatureError, TargetInfoError,
+        VerifyError,
     },
     ias::{
-        verifier::{Error as VerifierError, MrEnclaveVerifier, MrSignerVerifier, Verifier},
+        verifier::{
+            Error as VerifierError, Evidence, MrEnclaveVerifier, MrSignerVerifier,
+            VerifiedEvidence, Verifier,
+        },
         verify::{
             EpidPseudonym, VerificationReport, VerificationReportData, VerificationSignature,
         },
     },
     nonce::{IasNonce, Nonce, QuoteNonce},
+    policy::{AttestationPolicy, EnclavePolicy},
     quote::{Quote, QuoteSignType},
     report::Report,
     seal::{IntelSealed, ParseSealedError, Sealed},
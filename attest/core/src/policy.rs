@@ -0,0 +1,376 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Declarative attestation policies
+//!
+//! An attestation policy describes which enclaves a [`Verifier`] should
+//! accept, so that operators can change what is trusted without rebuilding.
+//! Policies are typically written as TOML or JSON, e.g.:
+//!
+//! ```toml
+//! allow_debug = false
+//!
+//! [[enclaves]]
+//! mr_signer = "7ee5e29d74623fdbc6fbf1454be6f3bb0b86c12366b7b478ad13353e44de8411"
+//! product_id = 1
+//! minimum_svn = 1
+//! hardening_advisories = ["INTEL-SA-00334"]
+//! ```
+
+use crate::{
+    error::PolicyError,
+    ias::verifier::{Error as VerifierError, MrEnclaveVerifier, MrSignerVerifier, Verifier},
+    types::{
+        measurement::{MrEnclave, MrSigner},
+        ProductId, SecurityVersion,
+    },
+    DEBUG_ENCLAVE, IAS_SIGNING_ROOT_CERT_PEMS,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use mc_util_encodings::FromHex;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+/// A description of the enclaves a verifier should accept, and the roots of
+/// trust their evidence must chain back to.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AttestationPolicy {
+    /// PEM-encoded IAS report signing root certificates. The baked-in IAS
+    /// roots are used if this is empty.
+    #[serde(default)]
+    pub trust_anchors: Vec<String>,
+    /// PEM-encoded DCAP root certificates. DCAP evidence is rejected if this
+    /// is empty.
+    #[serde(default)]
+    pub dcap_trust_anchors: Vec<String>,
+    /// Whether debug enclaves are acceptable. Defaults to the build's
+    /// [`DEBUG_ENCLAVE`] setting.
+    #[serde(default)]
+    pub allow_debug: Option<bool>,
+    /// The enclaves to accept, any one of which may match.
+    #[serde(default)]
+    pub enclaves: Vec<EnclavePolicy>,
+}
+
+/// A single acceptable enclave, identified either by its exact measurement,
+/// or by its signer, product ID and minimum security version.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnclavePolicy {
+    /// The hex-encoded MRENCLAVE value to accept.
+    #[serde(default)]
+    pub mr_enclave: Option<String>,
+    /// The hex-encoded MRSIGNER value to accept.
+    #[serde(default)]
+    pub mr_signer: Option<String>,
+    /// The product ID to accept, required with `mr_signer`.
+    #[serde(default)]
+    pub product_id: Option<ProductId>,
+    /// The minimum security version to accept, required with `mr_signer`.
+    #[serde(default)]
+    pub minimum_svn: Option<SecurityVersion>,
+    /// Advisory IDs whose BIOS configuration changes are not needed.
+    #[serde(default)]
+    pub config_advisories: Vec<String>,
+    /// Advisory IDs whose software hardening the enclave already contains.
+    #[serde(default)]
+    pub hardening_advisories: Vec<String>,
+}
+
+impl AttestationPolicy {
+    /// Read and validate a policy from a `.toml` or `.json` file.
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PolicyError> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path).map_err(|err| PolicyError::Io(err.to_string()))?;
+
+        let policy = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&data)?,
+            Some("json") => Self::from_json(&data)?,
+            _ => return Err(PolicyError::UnknownFormat(path.display().to_string())),
+        };
+
+        // Catch invalid measurements and trust anchors at load time.
+        policy.verifier()?;
+        Ok(policy)
+    }
+
+    /// Parse a policy from a TOML document.
+    #[cfg(feature = "std")]
+    pub fn from_toml(src: &str) -> Result<Self, PolicyError> {
+        toml::from_str(src).map_err(|err| PolicyError::Parse(err.to_string()))
+    }
+
+    /// Parse a policy from a JSON document.
+    #[cfg(feature = "std")]
+    pub fn from_json(src: &str) -> Result<Self, PolicyError> {
+        serde_json::from_str(src).map_err(|err| PolicyError::Parse(err.to_string()))
+    }
+
+    /// Build a verifier which enforces this policy.
+    ///
+    /// Callers may add further checks (e.g. a nonce) to the result.
+    pub fn verifier(&self) -> Result<Verifier, PolicyError> {
+        if self.enclaves.is_empty() {
+            return Err(PolicyError::NoEnclaves);
+        }
+
+        let mut verifier = if self.trust_anchors.is_empty() {
+            Verifier::new(IAS_SIGNING_ROOT_CERT_PEMS)
+        } else {
+            Verifier::new(&str_refs(&self.trust_anchors))
+        }
+        .map_err(trust_anchor_error)?;
+
+        if !self.dcap_trust_anchors.is_empty() {
+            verifier
+                .dcap_trust_anchors(&str_refs(&self.dcap_trust_anchors))
+                .map_err(trust_anchor_error)?;
+        }
+
+        for (index, enclave) in self.enclaves.iter().enumerate() {
+            let config_ids = str_refs(&enclave.config_advisories);
+            let sw_ids = str_refs(&enclave.hardening_advisories);
+
+            match (&enclave.mr_enclave, &enclave.mr_signer) {
+                (Some(mr_enclave), None) => {
+                    if enclave.product_id.is_some() || enclave.minimum_svn.is_some() {
+                        return Err(PolicyError::UnexpectedSignerFields(index));
+                    }
+                    let mr_enclave = MrEnclave::from_hex(mr_enclave)
+                        .map_err(|_| PolicyError::InvalidMeasurement(index))?;
+                    let mut mr_enclave_verifier = MrEnclaveVerifier::new(mr_enclave);
+                    mr_enclave_verifier
+                        .allow_config_advisories(&config_ids)
+                        .allow_hardening_advisories(&sw_ids);
+                    verifier.mr_enclave(mr_enclave_verifier);
+                }
+                (None, Some(mr_signer)) => {
+                    let (product_id, minimum_svn) = enclave
+                        .product_id
+                        .zip(enclave.minimum_svn)
+                        .ok_or(PolicyError::MissingSignerFields(index))?;
+                    let mr_signer = MrSigner::from_hex(mr_signer)
+                        .map_err(|_| PolicyError::InvalidMeasurement(index))?;
+                    let mut mr_signer_verifier =
+                        MrSignerVerifier::new(mr_signer, product_id, minimum_svn);
+                    mr_signer_verifier
+                        .allow_config_advisories(&config_ids)
+                        .allow_hardening_advisories(&sw_ids);
+                    verifier.mr_signer(mr_signer_verifier);
+                }
+                _ => return Err(PolicyError::Measurement(index)),
+            }
+        }
+
+        verifier.debug(self.allow_debug.unwrap_or(DEBUG_ENCLAVE));
+        Ok(verifier)
+    }
+}
+
+fn str_refs(strings: &[String]) -> Vec<&str> {
+    strings.iter().map(String::as_str).collect()
+}
+
+fn trust_anchor_error(err: VerifierError) -> PolicyError {
+    PolicyError::TrustAnchor(err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::{borrow::ToOwned, vec};
+
+    extern crate std;
+
+    const DEV_ANCHOR: &str = include_str!("../data/Dev_AttestationReportSigningCACert.pem");
+    const MR_ENCLAVE_HEX: &str = "45fb24224c3633ec8db51d090bf11de4de76c2866c060102315020d99786b82c";
+    const MR_SIGNER_HEX: &str = "d11f4699bfe0b7b547ce63e1882e01eed0c6547928ab789a315a87898f2c534d";
+
+    fn signer_policy() -> EnclavePolicy {
+        EnclavePolicy {
+            mr_signer: Some(MR_SIGNER_HEX.to_owned()),
+            product_id: Some(1),
+            minimum_svn: Some(1),
+            hardening_advisories: vec!["INTEL-SA-00334".to_owned()],
+            ..Default::default()
+        }
+    }
+
+    /// Ensure a TOML policy is parsed into the expected structure.
+    #[test]
+    fn from_toml() {
+        let src = r#"
+            allow_debug = true
+
+            [[enclaves]]
+            mr_enclave = "45fb24224c3633ec8db51d090bf11de4de76c2866c060102315020d99786b82c"
+            config_advisories = ["INTEL-SA-00239"]
+
+            [[enclaves]]
+            mr_signer = "d11f4699bfe0b7b547ce63e1882e01eed0c6547928ab789a315a87898f2c534d"
+            product_id = 1
+            minimum_svn = 1
+            hardening_advisories = ["INTEL-SA-00334"]
+        "#;
+
+        let expected = AttestationPolicy {
+            allow_debug: Some(true),
+            enclaves: vec![
+                EnclavePolicy {
+                    mr_enclave: Some(MR_ENCLAVE_HEX.to_owned()),
+                    config_advisories: vec!["INTEL-SA-00239".to_owned()],
+                    ..Default::default()
+                },
+                signer_policy(),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            AttestationPolicy::from_toml(src).expect("Could not parse TOML policy"),
+            expected
+        );
+    }
+
+    /// Ensure a JSON policy is parsed into the expected structure, and unknown
+    /// fields are rejected.
+    #[test]
+    fn from_json() {
+        let src = r#"{
+            "enclaves": [{
+                "mr_signer": "d11f4699bfe0b7b547ce63e1882e01eed0c6547928ab789a315a87898f2c534d",
+                "product_id": 1,
+                "minimum_svn": 1,
+                "hardening_advisories": ["INTEL-SA-00334"]
+            }]
+        }"#;
+
+        let expected = AttestationPolicy {
+            enclaves: vec![signer_policy()],
+            ..Default::default()
+        };
+        assert_eq!(
+            AttestationPolicy::from_json(src).expect("Could not parse JSON policy"),
+            expected
+        );
+
+        assert!(matches!(
+            AttestationPolicy::from_json(r#"{"enclaves": [], "allow_debgu": true}"#),
+            Err(PolicyError::Parse(_))
+        ));
+    }
+
+    /// Ensure a policy builds the same verifier as the equivalent builder
+    /// calls.
+    #[test]
+    fn verifier_matches_builder() {
+        let policy = AttestationPolicy {
+            trust_anchors: vec![DEV_ANCHOR.to_owned()],
+            allow_debug: Some(true),
+            enclaves: vec![
+                EnclavePolicy {
+                    mr_enclave: Some(MR_ENCLAVE_HEX.to_owned()),
+                    config_advisories: vec!["INTEL-SA-00239".to_owned()],
+                    ..Default::default()
+                },
+                signer_policy(),
+            ],
+            ..Default::default()
+        };
+
+        let mut mr_enclave_verifier =
+            MrEnclaveVerifier::new(MrEnclave::from_hex(MR_ENCLAVE_HEX).unwrap());
+        mr_enclave_verifier.allow_config_advisory("INTEL-SA-00239");
+        let mut mr_signer_verifier =
+            MrSignerVerifier::new(MrSigner::from_hex(MR_SIGNER_HEX).unwrap(), 1, 1);
+        mr_signer_verifier.allow_hardening_advisory("INTEL-SA-00334");
+
+        let mut expected = Verifier::new(&[DEV_ANCHOR]).expect("Could not create verifier");
+        expected
+            .mr_enclave(mr_enclave_verifier)
+            .mr_signer(mr_signer_verifier)
+            .debug(true);
+
+        assert_eq!(
+            policy.verifier().expect("Could not build verifier"),
+            expected
+        );
+    }
+
+    /// Ensure a policy without enclaves is rejected, rather than accepting
+    /// any enclave.
+    #[test]
+    fn no_enclaves() {
+        assert_eq!(
+            AttestationPolicy::default().verifier(),
+            Err(PolicyError::NoEnclaves)
+        );
+    }
+
+    /// Ensure malformed enclave entries are rejected.
+    #[test]
+    fn invalid_enclaves() {
+        let check = |enclave: EnclavePolicy| {
+            AttestationPolicy {
+                enclaves: vec![signer_policy(), enclave],
+                ..Default::default()
+            }
+            .verifier()
+        };
+
+        assert_eq!(
+            check(EnclavePolicy::default()),
+            Err(PolicyError::Measurement(1))
+        );
+        assert_eq!(
+            check(EnclavePolicy {
+                mr_enclave: Some(MR_ENCLAVE_HEX.to_owned()),
+                ..signer_policy()
+            }),
+            Err(PolicyError::Measurement(1))
+        );
+        assert_eq!(
+            check(EnclavePolicy {
+                mr_enclave: Some(MR_ENCLAVE_HEX.to_owned()),
+                minimum_svn: Some(1),
+                ..Default::default()
+            }),
+            Err(PolicyError::UnexpectedSignerFields(1))
+        );
+        assert_eq!(
+            check(EnclavePolicy {
+                product_id: None,
+                ..signer_policy()
+            }),
+            Err(PolicyError::MissingSignerFields(1))
+        );
+        assert_eq!(
+            check(EnclavePolicy {
+                mr_signer: Some("d11f".to_owned()),
+                ..signer_policy()
+            }),
+            Err(PolicyError::InvalidMeasurement(1))
+        );
+    }
+
+    /// Ensure an invalid trust anchor is rejected.
+    #[test]
+    fn invalid_trust_anchor() {
+        let policy = AttestationPolicy {
+            trust_anchors: vec!["not a certificate".to_owned()],
+            enclaves: vec![signer_policy()],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            policy.verifier(),
+            Err(PolicyError::TrustAnchor(_))
+        ));
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! A generated report signing authority
+
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use core::convert::TryFrom;
+use mbedtls::{
+    hash::Type as HashType,
+    pk::Pk,
+    rng::{CtrDrbg, OsEntropy},
+    x509::{certificate::Builder, KeyUsage, Time},
+    Error as TlsError,
+};
+use sha2::{digest::Digest, Sha256};
+use std::sync::Arc;
+
+const ROOT_SUBJECT: &str =
+    "C=US,ST=CA,L=Santa Clara,O=MobileCoin,CN=Mock Intel SGX Attestation Report Signing CA\0";
+const SIGNER_SUBJECT: &str =
+    "C=US,ST=CA,L=Santa Clara,O=MobileCoin,CN=Mock Intel SGX Attestation Report Signer\0";
+
+/// A test certificate authority, and a report signer it issued.
+///
+/// This is generated at runtime, so verifiers must be configured with
+/// [`Authority::root_anchor_pem()`] to trust the reports it signs.
+pub struct Authority {
+    root_anchor_pem: String,
+    signer_chain_pem: String,
+    signer_key_der: Vec<u8>,
+}
+
+impl Authority {
+    /// Generate a new root CA and report signer, valid for the given duration.
+    pub fn generate(validity: Duration) -> Result<Self, TlsError> {
+        let mut csprng = new_rng();
+
+        // Starting 1 hour ago
+        let now = Utc::now();
+        let not_before = mbedtls_time(now - Duration::hours(1))?;
+        let not_after = mbedtls_time(now + validity)?;
+
+        let mut root_key = Pk::generate_rsa(&mut csprng, 3072, 65537)?;
+        let mut root_issuer_key = Pk::from_private_key(&root_key.write_private_der_vec()?, None)?;
+        let root_anchor_pem = Builder::new()
+            .subject_with_nul(ROOT_SUBJECT)?
+            .issuer_with_nul(ROOT_SUBJECT)?
+            .basic_constraints(true, Some(0))?
+            .key_usage(KeyUsage::CRL_SIGN | KeyUsage::KEY_CERT_SIGN)?
+            .validity(not_before, not_after)?
+            .serial(&[1u8])?
+            .subject_key(&mut root_key)
+            .issuer_key(&mut root_issuer_key)
+            .signature_hash(HashType::Sha256)
+            .write_pem_string(&mut csprng)?;
+
+        let mut signer_key = Pk::generate_rsa(&mut csprng, 2048, 65537)?;
+        let signer_key_der = signer_key.write_private_der_vec()?;
+        let signer_pem = Builder::new()
+            .subject_with_nul(SIGNER_SUBJECT)?
+            .issuer_with_nul(ROOT_SUBJECT)?
+            .basic_constraints(false, None)?
+            .key_usage(KeyUsage::DIGITAL_SIGNATURE | KeyUsage::NON_REPUDIATION)?
+            .validity(not_before, not_after)?
+            .serial(&[2u8])?
+            .subject_key(&mut signer_key)
+            .issuer_key(&mut root_key)
+            .signature_hash(HashType::Sha256)
+            .write_pem_string(&mut csprng)?;
+
+        Ok(Self {
+            signer_chain_pem: signer_pem + &root_anchor_pem,
+            root_anchor_pem,
+            signer_key_der,
+        })
+    }
+
+    /// The PEM-encoded root certificate, which verifiers should trust.
+    pub fn root_anchor_pem(&self) -> &str {
+        &self.root_anchor_pem
+    }
+
+    /// The PEM-encoded signer certificate chain, signer first.
+    pub fn signer_chain_pem(&self) -> &str {
+        &self.signer_chain_pem
+    }
+
+    /// Sign the given report body with the signer key, using RSA-SHA256.
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, TlsError> {
+        let hash = Sha256::digest(data);
+        let mut signer = Pk::from_private_key(&self.signer_key_der, None)?;
+        let mut signature = vec![0u8; 1024];
+        let bytes_signed = signer.sign(
+            HashType::Sha256,
+            hash.as_slice(),
+            &mut signature,
+            &mut new_rng(),
+        )?;
+        signature.truncate(bytes_signed);
+        Ok(signature)
+    }
+}
+
+fn new_rng() -> CtrDrbg {
+    CtrDrbg::new(Arc::new(OsEntropy::new()), None).expect("Could not create CtrDrbg")
+}
+
+fn mbedtls_time(time: DateTime<Utc>) -> Result<Time, TlsError> {
+    Time::new(
+        u16::try_from(time.year()).expect("Year not a u16"),
+        u8::try_from(time.month()).expect("Month not a u8"),
+        u8::try_from(time.day()).expect("Day not a u8"),
+        u8::try_from(time.hour()).expect("Hour not a u8"),
+        u8::try_from(time.minute()).expect("Minute not a u8"),
+        u8::try_from(time.second()).expect("Second not a u8"),
+    )
+    .ok_or(TlsError::X509InvalidDate)
+}
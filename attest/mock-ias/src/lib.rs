@@ -0,0 +1,19 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! A mock Intel Attestation Service, for end-to-end attestation tests without
+//! network access.
+//!
+//! Reports are signed by a test CA generated at startup, so verifiers must be
+//! given [`Authority::root_anchor_pem()`] as their trust anchor.
+
+#![feature(proc_macro_hygiene, decl_macro)]
+
+mod authority;
+mod scenario;
+mod server;
+
+pub use crate::{
+    authority::Authority,
+    scenario::{ParseScenarioError, Scenario},
+    server::{MockIas, SignedReport, BASE_PATH},
+};
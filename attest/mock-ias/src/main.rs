@@ -0,0 +1,85 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! A mock Intel Attestation Service, for end-to-end attestation tests.
+
+use chrono::Duration;
+use mc_attest_mock_ias::{Authority, MockIas, Scenario, BASE_PATH};
+use mc_common::logger::{create_app_logger, log, o};
+use std::{fs, path::PathBuf, sync::Arc};
+use structopt::StructOpt;
+
+#[derive(Clone, Debug, StructOpt)]
+#[structopt(
+    name = "mc-attest-mock-ias",
+    about = "A mock Intel Attestation Service, for end-to-end attestation tests."
+)]
+pub struct Config {
+    /// Host to listen on.
+    #[structopt(long, default_value = "127.0.0.1")]
+    pub listen_host: String,
+
+    /// Port to start webserver on.
+    #[structopt(long, default_value = "8443")]
+    pub listen_port: u16,
+
+    /// Scenarios to answer report requests with, in order, e.g.
+    /// `group-revoked` or `configuration-needed:INTEL-SA-00239`.
+    ///
+    /// One of ok, group-revoked, group-out-of-date, configuration-needed,
+    /// sw-hardening-needed or bad-signature, with optional advisory IDs.
+    #[structopt(long = "scenario")]
+    pub scenarios: Vec<Scenario>,
+
+    /// The scenario to answer report requests with once the scripted
+    /// scenarios have been used.
+    #[structopt(long, default_value = "ok")]
+    pub default_scenario: Scenario,
+
+    /// How long the generated certificates are valid for, in hours.
+    #[structopt(long, default_value = "168")]
+    pub validity_hours: i64,
+
+    /// Where to write the generated root certificate, for verifiers to trust.
+    #[structopt(long, parse(from_os_str))]
+    pub root_anchor_out: Option<PathBuf>,
+}
+
+fn main() {
+    mc_common::setup_panic_handler();
+
+    let config = Config::from_args();
+    let (logger, _global_logger_guard) = create_app_logger(o!());
+
+    let authority = Authority::generate(Duration::hours(config.validity_hours))
+        .expect("Could not generate report signing authority");
+    if let Some(path) = &config.root_anchor_out {
+        fs::write(path, authority.root_anchor_pem())
+            .unwrap_or_else(|err| panic!("Could not write root anchor to {:?}: {}", path, err));
+        log::info!(logger, "Wrote root anchor to {:?}", path);
+    }
+
+    let mock = Arc::new(MockIas::new(
+        authority,
+        config.default_scenario.clone(),
+        logger.clone(),
+    ));
+    for scenario in config.scenarios {
+        mock.push_scenario(scenario);
+    }
+
+    log::info!(
+        logger,
+        "Starting mock IAS on http://{}:{}{}",
+        config.listen_host,
+        config.listen_port,
+        BASE_PATH
+    );
+
+    let rocket_config = rocket::Config::build(rocket::config::Environment::Production)
+        .address(&config.listen_host)
+        .port(config.listen_port)
+        .unwrap();
+
+    let error = mock.rocket(rocket_config).launch();
+    panic!("Could not launch mock IAS: {}", error);
+}
@@ -0,0 +1,165 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Scripted attestation service responses
+
+use displaydoc::Display;
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+
+/// The advisory URL included in responses which carry advisories.
+const ADVISORY_URL: &str = "https://security-center.intel.com";
+
+/// A platform info blob, included in responses which require one.
+const PLATFORM_INFO_BLOB: &str = "1502006504000900000606020401010000000000000000000008000009000000020000000000000B2E053D80C01A089F0FA1A633CC53611B1A0036EDB3F1F2BE80EC8EBFBE7068A89AB679C2AA72E244115A57E18CA3A6F7447E56FBECE241C99F8656F14398E5971C";
+
+/// The advisory used when a scenario which needs advisories has none given.
+const DEFAULT_ADVISORY_ID: &str = "INTEL-SA-00334";
+
+/// The outcome the mock service should report for a quote.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Scenario {
+    /// The quote is OK.
+    Ok,
+    /// The EPID group of the platform has been revoked.
+    GroupRevoked,
+    /// The platform TCB is out of date, with the given advisories.
+    GroupOutOfDate(Vec<String>),
+    /// The platform needs BIOS configuration changes, with the given
+    /// advisories.
+    ConfigurationNeeded(Vec<String>),
+    /// The enclave needs software hardening, with the given advisories.
+    SwHardeningNeeded(Vec<String>),
+    /// The quote is OK, but the report signature is corrupted.
+    BadSignature,
+}
+
+/// An error parsing a scenario string.
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum ParseScenarioError {
+    /// Unknown scenario: {0}
+    Unknown(String),
+    /// The {0} scenario does not take advisory IDs
+    UnexpectedAdvisories(String),
+}
+
+impl Scenario {
+    /// Build the JSON body of a verification report for the given quote.
+    pub fn report_json(
+        &self,
+        id: &str,
+        timestamp: &str,
+        quote_body: &str,
+        nonce: Option<&str>,
+    ) -> Value {
+        let mut report = Map::new();
+        if let Some(nonce) = nonce {
+            report.insert("nonce".to_owned(), json!(nonce));
+        }
+        report.insert("id".to_owned(), json!(id));
+        report.insert("timestamp".to_owned(), json!(timestamp));
+        report.insert("version".to_owned(), json!(4));
+
+        let (status, advisory_ids, needs_pib) = match self {
+            Scenario::Ok | Scenario::BadSignature => ("OK", None, false),
+            Scenario::GroupRevoked => {
+                // Revoked because of a key compromise.
+                report.insert("revocationReason".to_owned(), json!(1));
+                ("GROUP_REVOKED", None, true)
+            }
+            Scenario::GroupOutOfDate(ids) => ("GROUP_OUT_OF_DATE", Some(ids), true),
+            Scenario::ConfigurationNeeded(ids) => ("CONFIGURATION_NEEDED", Some(ids), true),
+            Scenario::SwHardeningNeeded(ids) => ("SW_HARDENING_NEEDED", Some(ids), false),
+        };
+
+        report.insert("isvEnclaveQuoteStatus".to_owned(), json!(status));
+        if needs_pib {
+            report.insert("platformInfoBlob".to_owned(), json!(PLATFORM_INFO_BLOB));
+        }
+        if let Some(ids) = advisory_ids {
+            report.insert("advisoryURL".to_owned(), json!(ADVISORY_URL));
+            report.insert("advisoryIDs".to_owned(), json!(ids));
+        }
+        report.insert("isvEnclaveQuoteBody".to_owned(), json!(quote_body));
+
+        Value::Object(report)
+    }
+}
+
+/// Parse a scenario of the form `name[:ADVISORY-ID,...]`, e.g.
+/// `configuration-needed:INTEL-SA-00239`.
+impl FromStr for Scenario {
+    type Err = ParseScenarioError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let mut parts = src.splitn(2, ':');
+        let name = parts.next().unwrap_or_default();
+        let advisory_ids = parts.next().map(|ids| {
+            ids.split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(ToOwned::to_owned)
+                .collect::<Vec<String>>()
+        });
+        let with_advisories = |advisory_ids: Option<Vec<String>>| {
+            advisory_ids
+                .filter(|ids| !ids.is_empty())
+                .unwrap_or_else(|| vec![DEFAULT_ADVISORY_ID.to_owned()])
+        };
+
+        let scenario = match name {
+            "ok" => Scenario::Ok,
+            "group-revoked" => Scenario::GroupRevoked,
+            "bad-signature" => Scenario::BadSignature,
+            "group-out-of-date" => {
+                return Ok(Scenario::GroupOutOfDate(with_advisories(advisory_ids)))
+            }
+            "configuration-needed" => {
+                return Ok(Scenario::ConfigurationNeeded(with_advisories(advisory_ids)))
+            }
+            "sw-hardening-needed" => {
+                return Ok(Scenario::SwHardeningNeeded(with_advisories(advisory_ids)))
+            }
+            other => return Err(ParseScenarioError::Unknown(other.to_owned())),
+        };
+
+        if advisory_ids.is_some() {
+            Err(ParseScenarioError::UnexpectedAdvisories(name.to_owned()))
+        } else {
+            Ok(scenario)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Ensure scenarios with and without advisories are parsed.
+    #[test]
+    fn parse() {
+        assert_eq!(Scenario::from_str("ok"), Ok(Scenario::Ok));
+        assert_eq!(
+            Scenario::from_str("configuration-needed:INTEL-SA-00239, INTEL-SA-00123"),
+            Ok(Scenario::ConfigurationNeeded(vec![
+                "INTEL-SA-00239".to_owned(),
+                "INTEL-SA-00123".to_owned()
+            ]))
+        );
+        assert_eq!(
+            Scenario::from_str("sw-hardening-needed"),
+            Ok(Scenario::SwHardeningNeeded(vec![
+                DEFAULT_ADVISORY_ID.to_owned()
+            ]))
+        );
+        assert_eq!(
+            Scenario::from_str("group-revoked:INTEL-SA-00239"),
+            Err(ParseScenarioError::UnexpectedAdvisories(
+                "group-revoked".to_owned()
+            ))
+        );
+        assert_eq!(
+            Scenario::from_str("revoked"),
+            Err(ParseScenarioError::Unknown("revoked".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,300 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! The mock attestation service's HTTP API
+
+use crate::{authority::Authority, scenario::Scenario};
+use chrono::Utc;
+use mc_attest_core::Quote;
+use mc_common::logger::{log, Logger};
+use mc_util_encodings::{FromBase64, ToBase64};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rocket::{
+    config::{Environment, LoggingLevel},
+    get,
+    http::{ContentType, Status},
+    post,
+    response::{self, Responder},
+    routes, Request, Response, Rocket, State,
+};
+use rocket_contrib::json::Json;
+use serde::Deserialize;
+use std::{
+    collections::VecDeque,
+    io::Cursor,
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// The path the API is served under, matching Intel's.
+pub const BASE_PATH: &str = "/sgx/attestation/v4";
+
+/// The header used for the IAS verification report signature
+const IAS_SIGNATURE: &str = "X-IASReport-Signature";
+/// The header used to store the certificate chain of the signer
+const IAS_SIGNING_CERTS: &str = "X-IASReport-Signing-Certificate";
+
+/// A mock attestation service, which answers each report request with the
+/// next scripted scenario, or the default scenario once the script runs out.
+pub struct MockIas {
+    authority: Authority,
+    default_scenario: Scenario,
+    script: Mutex<VecDeque<Scenario>>,
+    next_id: AtomicU64,
+    logger: Logger,
+}
+
+/// A verification report, and the headers needed to check it.
+pub struct SignedReport {
+    /// The JSON report body.
+    pub http_body: String,
+    /// The base64-encoded report signature.
+    pub signature: String,
+    /// The URL-encoded PEM signer certificate chain.
+    pub signing_certs: String,
+}
+
+impl MockIas {
+    /// Create a new mock service, signing reports with the given authority.
+    pub fn new(authority: Authority, default_scenario: Scenario, logger: Logger) -> Self {
+        Self {
+            authority,
+            default_scenario,
+            script: Default::default(),
+            next_id: AtomicU64::new(1),
+            logger,
+        }
+    }
+
+    /// The authority which signs reports.
+    pub fn authority(&self) -> &Authority {
+        &self.authority
+    }
+
+    /// Append a scenario to the script of upcoming report responses.
+    pub fn push_scenario(&self, scenario: Scenario) {
+        self.script
+            .lock()
+            .expect("Script lock poisoned")
+            .push_back(scenario);
+    }
+
+    /// Build and sign a verification report for the given quote.
+    pub fn report(&self, quote: &Quote, nonce: Option<&str>) -> SignedReport {
+        let scenario = self
+            .script
+            .lock()
+            .expect("Script lock poisoned")
+            .pop_front()
+            .unwrap_or_else(|| self.default_scenario.clone());
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst).to_string();
+        let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string();
+
+        log::debug!(self.logger, "Answering report {} with {:?}", id, scenario);
+
+        let http_body = scenario
+            .report_json(&id, &timestamp, &quote.to_base64_owned(), nonce)
+            .to_string();
+        let mut signature = self
+            .authority
+            .sign(http_body.as_bytes())
+            .expect("Could not sign report");
+        if scenario == Scenario::BadSignature {
+            signature[0] ^= 0xff;
+        }
+
+        SignedReport {
+            http_body,
+            signature: base64::encode(&signature),
+            signing_certs: utf8_percent_encode(self.authority.signer_chain_pem(), NON_ALPHANUMERIC)
+                .to_string(),
+        }
+    }
+
+    /// Build a rocket instance serving this mock service.
+    pub fn rocket(self: Arc<Self>, config: rocket::Config) -> Rocket {
+        rocket::custom(config)
+            .mount(BASE_PATH, routes![sigrl, report])
+            .manage(self)
+    }
+
+    /// Serve this mock service on a free local port in a background thread,
+    /// and return the base URI clients should use once it is listening.
+    pub fn spawn(self: Arc<Self>) -> String {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Could not find a free port")
+            .port();
+        let config = rocket::Config::build(Environment::Production)
+            .address("127.0.0.1")
+            .port(port)
+            .log_level(LoggingLevel::Off)
+            .unwrap();
+
+        let rocket = self.rocket(config);
+        thread::Builder::new()
+            .name("mock-ias".to_owned())
+            .spawn(move || rocket.launch())
+            .expect("Could not spawn mock IAS thread");
+
+        for _ in 0..100 {
+            if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                return format!("http://127.0.0.1:{}{}", port, BASE_PATH);
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("Mock IAS did not start listening on port {}", port);
+    }
+}
+
+impl<'r> Responder<'r> for SignedReport {
+    fn respond_to(self, _request: &Request) -> response::Result<'r> {
+        Response::build()
+            .header(ContentType::JSON)
+            .raw_header(IAS_SIGNATURE, self.signature)
+            .raw_header(IAS_SIGNING_CERTS, self.signing_certs)
+            .sized_body(Cursor::new(self.http_body))
+            .ok()
+    }
+}
+
+/// The body of a report request.
+#[derive(Deserialize)]
+struct ReportRequest {
+    #[serde(rename = "isvEnclaveQuote")]
+    isv_enclave_quote: String,
+    nonce: Option<String>,
+}
+
+/// Every platform has an empty SigRL.
+#[get("/sigrl/<_gid>")]
+fn sigrl(_gid: String) -> String {
+    String::new()
+}
+
+#[post("/report", format = "json", data = "<request>")]
+fn report(
+    state: State<Arc<MockIas>>,
+    request: Json<ReportRequest>,
+) -> Result<SignedReport, Status> {
+    let quote = Quote::from_base64(&request.isv_enclave_quote).map_err(|_| Status::BadRequest)?;
+    Ok(state.report(&quote, request.nonce.as_deref()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+    use mc_attest_core::{
+        EpidGroupId, IasNonce, IasQuoteError, Nonce, VerificationReportData, Verifier,
+        VerifierError,
+    };
+    use mc_attest_net::{IasClient, RaClient};
+    use mc_common::logger::{test_with_logger, Logger};
+
+    const QUOTE_OK: &str = include_str!("../../net/data/quote_ok.txt");
+
+    fn start(logger: Logger) -> (Arc<MockIas>, IasClient) {
+        let authority =
+            Authority::generate(ChronoDuration::hours(1)).expect("Could not generate authority");
+        let mock = Arc::new(MockIas::new(authority, Scenario::Ok, logger));
+        let base_uri = mock.clone().spawn();
+        let client =
+            IasClient::with_base_uri("mock api key", &base_uri).expect("Could not create client");
+        (mock, client)
+    }
+
+    fn verify(
+        mock: &MockIas,
+        client: &IasClient,
+        nonce: &IasNonce,
+    ) -> Result<VerificationReportData, VerifierError> {
+        let quote = Quote::from_base64(QUOTE_OK.trim()).expect("Could not parse quote");
+        let report = client
+            .verify_quote(&quote, Some(nonce.clone()))
+            .expect("Could not retrieve report");
+
+        Verifier::new(&[mock.authority().root_anchor_pem()])
+            .expect("Could not initialize new verifier")
+            .debug(true)
+            .nonce(nonce)
+            .verify(&report)
+    }
+
+    // The client should retrieve an empty SigRL, and a report which verifies.
+    #[test_with_logger]
+    fn sigrl_and_report_ok(logger: Logger) {
+        let (mock, client) = start(logger);
+
+        let sigrl = client
+            .get_sigrl(EpidGroupId::default())
+            .expect("Could not retrieve SigRL");
+        assert!(sigrl.is_empty());
+
+        let nonce = IasNonce::new(&mut rand::thread_rng()).expect("Could not create nonce");
+        let data = verify(&mock, &client, &nonce).expect("Could not verify report");
+        assert_eq!(data.quote_status, Ok(None));
+        assert_eq!(data.nonce, Some(nonce));
+    }
+
+    // Scripted scenarios should be returned in order, then the default.
+    #[test_with_logger]
+    fn scripted_scenarios(logger: Logger) {
+        let (mock, client) = start(logger);
+        mock.push_scenario(Scenario::GroupRevoked);
+        mock.push_scenario(Scenario::GroupOutOfDate(vec!["INTEL-SA-00219".to_owned()]));
+        mock.push_scenario(Scenario::ConfigurationNeeded(vec![
+            "INTEL-SA-00239".to_owned()
+        ]));
+        mock.push_scenario(Scenario::BadSignature);
+
+        let nonce = IasNonce::new(&mut rand::thread_rng()).expect("Could not create nonce");
+
+        let data = verify(&mock, &client, &nonce).expect("Could not verify report");
+        assert!(matches!(
+            data.quote_status,
+            Err(IasQuoteError::GroupRevoked(_, _))
+        ));
+
+        let data = verify(&mock, &client, &nonce).expect("Could not verify report");
+        match data.quote_status {
+            Err(IasQuoteError::GroupOutOfDate { advisory_ids, .. }) => {
+                assert_eq!(advisory_ids, vec!["INTEL-SA-00219".to_owned()])
+            }
+            other => panic!("Unexpected quote status: {:?}", other),
+        }
+
+        let data = verify(&mock, &client, &nonce).expect("Could not verify report");
+        match data.quote_status {
+            Err(IasQuoteError::ConfigurationNeeded { advisory_ids, .. }) => {
+                assert_eq!(advisory_ids, vec!["INTEL-SA-00239".to_owned()])
+            }
+            other => panic!("Unexpected quote status: {:?}", other),
+        }
+
+        assert_eq!(
+            verify(&mock, &client, &nonce),
+            Err(VerifierError::BadSignature)
+        );
+
+        let data = verify(&mock, &client, &nonce).expect("Could not verify report");
+        assert_eq!(data.quote_status, Ok(None));
+    }
+
+    // Reports from the mock should not verify against other trust anchors.
+    #[test_with_logger]
+    fn untrusted_authority(logger: Logger) {
+        let (_mock, client) = start(logger.clone());
+        let (other, _) = start(logger);
+
+        let nonce = IasNonce::new(&mut rand::thread_rng()).expect("Could not create nonce");
+        assert_eq!(
+            verify(&other, &client, &nonce),
+            Err(VerifierError::BadSignature)
+        );
+    }
+}
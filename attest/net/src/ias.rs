@@ -37,14 +37,17 @@ const IAS_SIGNING_CERTS: &str = "x-iasreport-signing-certificate";
 #[derive(Clone)]
 pub struct IasClient {
     client: Client,
+    base_uri: String,
     api_key: String,
     json_headers: HeaderMap,
 }
 
-impl RaClient for IasClient {
-    /// Create a new IAS client, using the PEM-encoded certificate data
-    /// as the client credentials.
-    fn new(api_key: &str) -> Result<Self> {
+impl IasClient {
+    /// Create a new IAS client which talks to the attestation service at the
+    /// given base URI, rather than Intel's.
+    ///
+    /// This is primarily useful for testing against a mock service.
+    pub fn with_base_uri(api_key: &str, base_uri: &str) -> Result<Self> {
         let client = Client::builder().gzip(true).use_rustls_tls().build()?;
         let mut json_headers = HeaderMap::new();
         json_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
@@ -54,16 +57,25 @@ impl RaClient for IasClient {
         );
         Ok(Self {
             client,
+            base_uri: base_uri.trim_end_matches('/').to_owned(),
             json_headers,
             api_key: api_key.to_owned(),
         })
     }
+}
+
+impl RaClient for IasClient {
+    /// Create a new IAS client, using the PEM-encoded certificate data
+    /// as the client credentials.
+    fn new(api_key: &str) -> Result<Self> {
+        Self::with_base_uri(api_key, IAS_BASEURI)
+    }
 
     /// Retrieve the SigRL for the given EPID Group ID.
     fn get_sigrl(&self, gid: EpidGroupId) -> Result<SigRL> {
         let response = self
             .client
-            .get(format!("{}/sigrl/{}", IAS_BASEURI, gid).as_str())
+            .get(format!("{}/sigrl/{}", self.base_uri, gid).as_str())
             .header(OCP_APIM_SUBSCRIPTION_KEY, &self.api_key)
             .send()?
             .error_for_status()?;
@@ -88,12 +100,12 @@ impl RaClient for IasClient {
             "Submitting JSON request for {:?} to IAS: '{}' at {}",
             quote,
             jsvalue.to_string(),
-            format!("{}/report", IAS_BASEURI),
+            format!("{}/report", self.base_uri),
         );
 
         let response = self
             .client
-            .post(format!("{}/report", IAS_BASEURI).as_str())
+            .post(format!("{}/report", self.base_uri).as_str())
             .headers(self.json_headers.clone())
             .body(jsvalue.to_string())
             .send()?
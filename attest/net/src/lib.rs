@@ -8,9 +8,11 @@ extern crate core;
 
 mod traits;
 
-pub use self::traits::{Error, RaClient, Result};
+pub use self::{
+    ias::IasClient,
+    traits::{Error, RaClient, Result},
+};
 
-#[cfg(not(feature = "sgx-sim"))]
 mod ias;
 #[cfg(feature = "sgx-sim")]
 mod sim;
@@ -0,0 +1,445 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! An asynchronous attested client, for use from futures-based code.
+
+use crate::{
+    credentials::{AuthenticationError, CredentialsProvider},
+    error::{Error, Result},
+    thick::{call_option, ThickClientAttestationError},
+    traits::{
+        AsyncAttestedConnection, AsyncBlockchainConnection, AsyncUserTxConnection, BlockInfo,
+        Connection,
+    },
+};
+use aes_gcm::Aes256Gcm;
+use cookie::CookieJar;
+use futures::{future::BoxFuture, lock::Mutex, FutureExt};
+use grpcio::{CallOption, ChannelBuilder, ClientUnaryReceiver, Environment, Error as GrpcError};
+use mc_attest_ake::{AuthResponseInput, ClientInitiate, Ready, Start, Transition};
+use mc_attest_api::{
+    attest::{AuthMessage, Message},
+    attest_grpc::AttestedApiClient,
+};
+use mc_attest_core::{VerificationReport, Verifier};
+use mc_common::{
+    logger::{log, o, Logger},
+    trace_time,
+};
+use mc_consensus_api::{
+    consensus_client::{TxStatusRequest, TxStatusResponse},
+    consensus_client_grpc::ConsensusClientApiClient,
+    consensus_common::{BlocksRequest, BlocksResponse, ProposeTxResult},
+    consensus_common_grpc::BlockchainApiClient,
+    empty::Empty,
+};
+use mc_crypto_keys::X25519;
+use mc_crypto_rand::McRng;
+use mc_transaction_core::{
+    tx::{Tx, TxHash},
+    Block, BlockID, BlockIndex,
+};
+use mc_util_grpc::{ConnectionUriGrpcioChannel, GrpcCookieStore};
+use mc_util_serial::encode;
+use mc_util_uri::{ConnectionUri, ConsensusClientUri as ClientUri};
+use secrecy::{ExposeSecret, SecretVec};
+use sha2::Sha512;
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt::{Display, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
+    ops::Range,
+    result::Result as StdResult,
+    sync::Arc,
+    time::Instant,
+};
+
+/// The session state of an `AsyncThickClient`, shared between its clones.
+#[derive(Default)]
+struct Session {
+    /// The AKE state machine object, if one is available.
+    enclave_connection: Option<Ready<Aes256Gcm>>,
+    /// A hash map of metadata to set on outbound requests, filled by inbound
+    /// `Set-Cookie` metadata
+    cookies: CookieJar,
+}
+
+impl Session {
+    fn deattest(&mut self, logger: &Logger) {
+        if self.enclave_connection.is_some() {
+            log::trace!(
+                logger,
+                "Tearing down existing attested connection and clearing cookies."
+            );
+            self.enclave_connection = None;
+            self.cookies = CookieJar::default();
+        }
+    }
+}
+
+/// Wait for the response to a unary call, keeping any cookies the server sets
+/// in its headers.
+async fn receive<T>(
+    mut receiver: ClientUnaryReceiver<T>,
+    cookies: &mut CookieJar,
+    logger: &Logger,
+) -> StdResult<T, GrpcError> {
+    let header = receiver.headers().await?;
+
+    // Update cookies from server-sent metadata
+    if let Err(e) = cookies.update_from_server_metadata(Some(header), None) {
+        log::warn!(logger, "Could not update cookies from gRPC metadata: {}", e)
+    }
+
+    receiver.message().await
+}
+
+/// An asynchronous connection from a client to a consensus enclave.
+///
+/// Clones share the same attested session, so a clone can be taken out of a
+/// `SyncConnection` and awaited without holding its lock. Calls made over
+/// the same session are serialized, as they are for a `ThickClient`.
+pub struct AsyncThickClient<CP: CredentialsProvider> {
+    /// The destination's URI
+    uri: ClientUri,
+    /// The logging instance
+    logger: Logger,
+    /// The gRPC API client we will use for blockchain detail retrieval.
+    blockchain_api_client: BlockchainApiClient,
+    /// The gRPC API client we will use for attestation and (eventually)
+    /// transactions
+    attested_api_client: AttestedApiClient,
+    /// The gRPC API client we will use for legacy transaction submission.
+    consensus_client_api_client: ConsensusClientApiClient,
+    /// An object which can verify a consensus node's provided IAS report
+    verifier: Verifier,
+    /// Generic interface for retreiving GRPC credentials.
+    credentials_provider: Arc<CP>,
+    /// The attested session, if any, and its cookies.
+    session: Arc<Mutex<Session>>,
+    /// The time base for key ages.
+    epoch: Instant,
+}
+
+impl<CP: CredentialsProvider> AsyncThickClient<CP> {
+    /// Create a new attested connection to the given consensus node.
+    pub fn new(
+        uri: ClientUri,
+        verifier: Verifier,
+        env: Arc<Environment>,
+        credentials_provider: CP,
+        logger: Logger,
+    ) -> Result<Self> {
+        let logger = logger.new(o!("mc.cxn" => uri.to_string()));
+
+        let ch = ChannelBuilder::default_channel_builder(env).connect_to_uri(&uri, &logger);
+
+        let attested_api_client = AttestedApiClient::new(ch.clone());
+        let blockchain_api_client = BlockchainApiClient::new(ch.clone());
+        let consensus_client_api_client = ConsensusClientApiClient::new(ch);
+
+        Ok(Self {
+            uri,
+            logger,
+            blockchain_api_client,
+            consensus_client_api_client,
+            attested_api_client,
+            verifier,
+            credentials_provider: Arc::new(credentials_provider),
+            session: Arc::new(Mutex::new(Session::default())),
+            epoch: Instant::now(),
+        })
+    }
+
+    /// Start a call with the session's cookies and our credentials, and wait
+    /// for its response, keeping any cookies the server sets.
+    async fn authenticated_call<T>(
+        &self,
+        session: &mut Session,
+        start: impl FnOnce(&Self, CallOption) -> StdResult<ClientUnaryReceiver<T>, GrpcError>,
+    ) -> StdResult<T, ThickClientAttestationError> {
+        let call_option = call_option(&session.cookies, self.credentials_provider.as_ref())?;
+
+        let result = match start(self, call_option) {
+            Ok(receiver) => receive(receiver, &mut session.cookies, &self.logger).await,
+            Err(err) => Err(err),
+        };
+
+        // If the call failed due to authentication (credentials) error, reset creds so
+        // that it gets re-created on the next call.
+        if let Err(err) = result.as_ref() {
+            if err.is_unauthenticated() {
+                self.credentials_provider.clear();
+            }
+        }
+        Ok(result?)
+    }
+
+    /// Perform the attestation handshake, replacing the session's existing
+    /// attestation, if any.
+    async fn attest_session(
+        &self,
+        session: &mut Session,
+    ) -> StdResult<VerificationReport, ThickClientAttestationError> {
+        trace_time!(self.logger, "AsyncThickClient::attest");
+        // If we have an existing attestation, nuke it.
+        session.deattest(&self.logger);
+
+        let initiator = Start::new(self.uri.responder_id()?.to_string());
+
+        let init_input = ClientInitiate::<X25519, Aes256Gcm, Sha512>::default();
+        let (initiator, auth_request_output) =
+            initiator.try_next(&mut McRng::default(), init_input)?;
+
+        // Do the gRPC Call
+        let auth_request: AuthMessage = auth_request_output.into();
+        let auth_response_msg = self
+            .authenticated_call(session, |this, call_option| {
+                this.attested_api_client
+                    .auth_async_opt(&auth_request, call_option)
+            })
+            .await?;
+
+        let auth_response_event =
+            AuthResponseInput::new(auth_response_msg.into(), self.verifier.clone());
+        let (initiator, verification_report) =
+            initiator.try_next(&mut McRng::default(), auth_response_event)?;
+
+        session.enclave_connection = Some(initiator);
+
+        Ok(verification_report)
+    }
+
+    /// Make an authenticated call over an attested session, attesting first
+    /// if there is no session. If the call fails, the session is dropped, so
+    /// that the next call attests again.
+    async fn authenticated_attested_call<T>(
+        &self,
+        session: &mut Session,
+        start: impl FnOnce(&Self, CallOption) -> StdResult<ClientUnaryReceiver<T>, GrpcError>,
+    ) -> StdResult<T, ThickClientAttestationError> {
+        if session.enclave_connection.is_none() {
+            let _verification_report = self.attest_session(session).await?;
+        }
+
+        let result = self.authenticated_call(session, start).await;
+
+        if let Err(ThickClientAttestationError::Grpc(GrpcError::RpcFailure(_rpc_status))) = &result
+        {
+            session.deattest(&self.logger);
+        }
+
+        result
+    }
+
+    /// Retrieve the given range of blocks.
+    async fn get_blocks(&self, range: Range<BlockIndex>) -> Result<BlocksResponse> {
+        let mut request = BlocksRequest::new();
+        request.set_offset(range.start);
+        let limit = u32::try_from(range.end - range.start).or(Err(Error::RequestTooLarge))?;
+        request.set_limit(limit);
+
+        let mut session = self.session.lock().await;
+        Ok(self
+            .authenticated_attested_call(&mut session, |this, call_option| {
+                this.blockchain_api_client
+                    .get_blocks_async_opt(&request, call_option)
+            })
+            .await?)
+    }
+
+    /// Ask the consensus node what it knows about a previously proposed
+    /// transaction.
+    pub async fn fetch_tx_status(&self, tx_hash: &TxHash) -> Result<TxStatusResponse> {
+        trace_time!(self.logger, "AsyncThickClient::fetch_tx_status");
+
+        let mut request = TxStatusRequest::new();
+        request.mut_tx_hash().set_hash(tx_hash.to_vec());
+
+        let mut session = self.session.lock().await;
+        Ok(self
+            .authenticated_attested_call(&mut session, |this, call_option| {
+                this.consensus_client_api_client
+                    .get_tx_status_async_opt(&request, call_option)
+            })
+            .await?)
+    }
+}
+
+impl<CP: CredentialsProvider> Clone for AsyncThickClient<CP> {
+    fn clone(&self) -> Self {
+        Self {
+            uri: self.uri.clone(),
+            logger: self.logger.clone(),
+            blockchain_api_client: self.blockchain_api_client.clone(),
+            attested_api_client: self.attested_api_client.clone(),
+            consensus_client_api_client: self.consensus_client_api_client.clone(),
+            verifier: self.verifier.clone(),
+            credentials_provider: self.credentials_provider.clone(),
+            session: self.session.clone(),
+            epoch: self.epoch,
+        }
+    }
+}
+
+impl<CP: CredentialsProvider> Connection for AsyncThickClient<CP> {
+    type Uri = ClientUri;
+
+    fn uri(&self) -> Self::Uri {
+        self.uri.clone()
+    }
+}
+
+impl<CP: CredentialsProvider> AsyncAttestedConnection for AsyncThickClient<CP> {
+    type Error = ThickClientAttestationError;
+
+    fn is_attested(&self) -> BoxFuture<'_, bool> {
+        async move { self.session.lock().await.enclave_connection.is_some() }.boxed()
+    }
+
+    fn attest(&self) -> BoxFuture<'_, StdResult<VerificationReport, Self::Error>> {
+        async move {
+            let mut session = self.session.lock().await;
+            self.attest_session(&mut session).await
+        }
+        .boxed()
+    }
+
+    fn deattest(&self) -> BoxFuture<'_, ()> {
+        async move { self.session.lock().await.deattest(&self.logger) }.boxed()
+    }
+}
+
+impl<CP: CredentialsProvider> AsyncBlockchainConnection for AsyncThickClient<CP> {
+    fn fetch_blocks(&self, range: Range<BlockIndex>) -> BoxFuture<'_, Result<Vec<Block>>> {
+        async move {
+            trace_time!(self.logger, "AsyncThickClient::get_blocks");
+
+            self.get_blocks(range)
+                .await?
+                .get_blocks()
+                .iter()
+                .map(|proto_block| Block::try_from(proto_block).map_err(Error::from))
+                .collect::<Result<Vec<Block>>>()
+        }
+        .boxed()
+    }
+
+    fn fetch_block_ids(&self, range: Range<BlockIndex>) -> BoxFuture<'_, Result<Vec<BlockID>>> {
+        async move {
+            trace_time!(self.logger, "AsyncThickClient::get_block_ids");
+
+            self.get_blocks(range)
+                .await?
+                .get_blocks()
+                .iter()
+                .map(|proto_block| BlockID::try_from(proto_block.get_id()).map_err(Error::from))
+                .collect::<Result<Vec<BlockID>>>()
+        }
+        .boxed()
+    }
+
+    fn fetch_block_height(&self) -> BoxFuture<'_, Result<BlockIndex>> {
+        async move {
+            trace_time!(self.logger, "AsyncThickClient::fetch_block_height");
+
+            Ok(self.fetch_block_info().await?.block_index)
+        }
+        .boxed()
+    }
+
+    fn fetch_block_info(&self) -> BoxFuture<'_, Result<BlockInfo>> {
+        async move {
+            trace_time!(self.logger, "AsyncThickClient::fetch_block_info");
+
+            let mut session = self.session.lock().await;
+            let block_info = self
+                .authenticated_attested_call(&mut session, |this, call_option| {
+                    this.blockchain_api_client
+                        .get_last_block_info_async_opt(&Empty::new(), call_option)
+                })
+                .await?;
+
+            Ok(block_info.into())
+        }
+        .boxed()
+    }
+}
+
+impl<CP: CredentialsProvider> AsyncUserTxConnection for AsyncThickClient<CP> {
+    fn propose_tx<'a>(&'a self, tx: &'a Tx) -> BoxFuture<'a, Result<BlockIndex>> {
+        async move {
+            trace_time!(self.logger, "AsyncThickClient::propose_tx");
+
+            let mut session = self.session.lock().await;
+            if session.enclave_connection.is_none() {
+                let _verification_report = self.attest_session(&mut session).await?;
+            }
+
+            let enclave_connection = session
+                .enclave_connection
+                .as_mut()
+                .expect("no enclave_connection even though attest succeeded");
+
+            let mut msg = Message::new();
+            msg.set_channel_id(Vec::from(enclave_connection.binding()));
+
+            // Don't leave the plaintext serialization floating around
+            let tx_plaintext = SecretVec::new(encode(tx));
+            let tx_ciphertext = enclave_connection
+                .encrypt_at(
+                    &[],
+                    tx_plaintext.expose_secret().as_ref(),
+                    self.epoch.elapsed(),
+                )
+                .map_err(ThickClientAttestationError::from)?;
+            msg.set_data(tx_ciphertext);
+
+            let resp = self
+                .authenticated_attested_call(&mut session, |this, call_option| {
+                    this.consensus_client_api_client
+                        .client_tx_propose_async_opt(&msg, call_option)
+                })
+                .await?;
+
+            if resp.get_result() == ProposeTxResult::Ok {
+                Ok(resp.get_block_count())
+            } else {
+                Err(resp.get_result().into())
+            }
+        }
+        .boxed()
+    }
+}
+
+impl<CP: CredentialsProvider> Display for AsyncThickClient<CP> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.uri)
+    }
+}
+
+impl<CP: CredentialsProvider> Eq for AsyncThickClient<CP> {}
+
+impl<CP: CredentialsProvider> Hash for AsyncThickClient<CP> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.uri.addr().hash(hasher);
+    }
+}
+
+impl<CP: CredentialsProvider> PartialEq for AsyncThickClient<CP> {
+    fn eq(&self, other: &Self) -> bool {
+        self.uri.addr() == other.uri.addr()
+    }
+}
+
+impl<CP: CredentialsProvider> Ord for AsyncThickClient<CP> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.uri.addr().cmp(&other.uri.addr())
+    }
+}
+
+impl<CP: CredentialsProvider> PartialOrd for AsyncThickClient<CP> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.uri.addr().partial_cmp(&other.uri.addr())
+    }
+}
@@ -0,0 +1,36 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Connection health metrics.
+
+use crate::health::{CircuitState, ConnectionHealth};
+use mc_common::ResponderId;
+use mc_util_metrics::{IntCounter, OpMetrics};
+
+lazy_static::lazy_static! {
+    pub static ref OP_COUNTERS: OpMetrics = OpMetrics::new_and_registered("connection");
+
+    // Number of times a connection's circuit was opened.
+    pub static ref CIRCUITS_OPENED: IntCounter = OP_COUNTERS.counter("circuits_opened");
+}
+
+/// Export the state of a connection's circuit breaker, as 0 (closed), 1
+/// (half-open) or 2 (open).
+pub fn publish_state(responder_id: &ResponderId, state: CircuitState) {
+    OP_COUNTERS
+        .peer_gauge("circuit_state", &responder_id.to_string())
+        .set(state.as_metric());
+}
+
+/// Export the health of a connection.
+pub fn publish_health(responder_id: &ResponderId, health: &ConnectionHealth, state: CircuitState) {
+    let peer = responder_id.to_string();
+    publish_state(responder_id, state);
+    OP_COUNTERS
+        .peer_gauge("consecutive_failures", &peer)
+        .set(health.consecutive_failures() as i64);
+    if let Some(rtt) = health.rtt() {
+        OP_COUNTERS
+            .peer_gauge("rtt_ms", &peer)
+            .set(rtt.as_millis() as i64);
+    }
+}
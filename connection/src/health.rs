@@ -0,0 +1,158 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Per-connection health tracking and circuit breaking.
+
+use std::time::{Duration, Instant};
+
+/// The weight given to each new round-trip time sample in the smoothed
+/// round-trip time, as in TCP.
+const RTT_SMOOTHING: f64 = 0.125;
+
+/// When to stop using a connection which keeps failing, and when to try it
+/// again.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive retryable failures after which the circuit
+    /// opens, and the connection is no longer selected.
+    pub failure_threshold: u32,
+
+    /// How long the circuit stays open before the connection may be tried
+    /// again.
+    pub reset_timeout: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// The state of a connection's circuit breaker.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum CircuitState {
+    /// The connection is healthy, and may be selected.
+    Closed,
+    /// The connection failed too many times in a row, and the reset timeout
+    /// has passed, so it may be tried again. Another failure re-opens the
+    /// circuit, a success closes it.
+    HalfOpen,
+    /// The connection failed too many times in a row, and will not be selected
+    /// until the reset timeout passes.
+    Open,
+}
+
+impl CircuitState {
+    /// The value of the `circuit_state` metric for this state.
+    pub fn as_metric(self) -> i64 {
+        match self {
+            Self::Closed => 0,
+            Self::HalfOpen => 1,
+            Self::Open => 2,
+        }
+    }
+}
+
+/// The recent history of calls made over a connection.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConnectionHealth {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    rtt: Option<Duration>,
+}
+
+impl ConnectionHealth {
+    /// The state of the circuit breaker at `now`.
+    pub fn state(&self, config: &CircuitBreakerConfig, now: Instant) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if now < opened_at + config.reset_timeout => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// The number of retryable failures since the last successful call.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// The smoothed round-trip time of successful calls, if there have been
+    /// any.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Record a call which got a response, taking `rtt`. This closes the
+    /// circuit.
+    pub fn record_success(&mut self, rtt: Duration) {
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.rtt = Some(match self.rtt {
+            Some(smoothed) => smoothed.mul_f64(1.0 - RTT_SMOOTHING) + rtt.mul_f64(RTT_SMOOTHING),
+            None => rtt,
+        });
+    }
+
+    /// Record a call which failed with a retryable error at `now`. Returns
+    /// true if this opened the circuit.
+    pub fn record_failure(&mut self, config: &CircuitBreakerConfig, now: Instant) -> bool {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.consecutive_failures < config.failure_threshold {
+            return false;
+        }
+        let was_open = self.state(config, now) == CircuitState::Open;
+        self.opened_at = Some(now);
+        !was_open
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn circuit_opens_and_resets() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 3,
+            reset_timeout: Duration::from_secs(10),
+        };
+        let start = Instant::now();
+        let mut health = ConnectionHealth::default();
+
+        assert!(!health.record_failure(&config, start));
+        assert!(!health.record_failure(&config, start));
+        assert_eq!(health.state(&config, start), CircuitState::Closed);
+        assert!(health.record_failure(&config, start));
+        assert_eq!(health.consecutive_failures(), 3);
+        assert_eq!(health.state(&config, start), CircuitState::Open);
+
+        // After the reset timeout, a trial is allowed, and failing it re-opens
+        // the circuit.
+        let later = start + Duration::from_secs(10);
+        assert_eq!(health.state(&config, later), CircuitState::HalfOpen);
+        assert!(health.record_failure(&config, later));
+        assert_eq!(
+            health.state(&config, later + Duration::from_secs(5)),
+            CircuitState::Open
+        );
+
+        // A success closes it.
+        health.record_success(Duration::from_millis(100));
+        assert_eq!(health.state(&config, later), CircuitState::Closed);
+        assert_eq!(health.consecutive_failures(), 0);
+    }
+
+    #[test]
+    fn rtt_is_smoothed() {
+        let mut health = ConnectionHealth::default();
+        assert_eq!(health.rtt(), None);
+
+        health.record_success(Duration::from_secs(8));
+        assert_eq!(health.rtt(), Some(Duration::from_secs(8)));
+
+        health.record_success(Duration::from_secs(0));
+        assert_eq!(health.rtt(), Some(Duration::from_secs(7)));
+    }
+}
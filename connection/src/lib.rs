@@ -2,23 +2,34 @@
 
 //! Connection support
 
+mod async_thick;
+mod counters;
 mod credentials;
 mod error;
+mod health;
 mod manager;
+mod policy;
 mod sync;
 mod thick;
 mod traits;
 
 pub use self::{
+    async_thick::AsyncThickClient,
     credentials::{
         AnyCredentialsError, AnyCredentialsProvider, AuthenticationError, CredentialsProvider,
         CredentialsProviderError, HardcodedCredentialsProvider, TokenBasicCredentialsProvider,
     },
     error::{Error, Result, RetryError, RetryResult},
+    health::{CircuitBreakerConfig, CircuitState, ConnectionHealth},
     manager::ConnectionManager,
+    policy::{
+        Candidate, LeastLatency, RoundRobin, SelectionPolicy, SelectionPolicyKind,
+        StickyWithFailover,
+    },
     sync::SyncConnection,
-    thick::{ThickClient, ThickClientAttestationError},
+    thick::{ThickClient, ThickClientAttestationError, RESUMPTION_TICKET_LIFETIME},
     traits::{
+        AsyncAttestedConnection, AsyncBlockchainConnection, AsyncUserTxConnection,
         AttestationError, AttestedConnection, BlockInfo, BlockchainConnection, Connection,
         RetryableBlockchainConnection, RetryableUserTxConnection, UserTxConnection,
     },
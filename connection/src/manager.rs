@@ -2,63 +2,137 @@
 
 //! Common connection manager implementation
 
-use crate::{sync::SyncConnection, traits::Connection};
+use crate::{
+    counters,
+    error::{Error, Result, RetryError, RetryResult},
+    health::{CircuitBreakerConfig, CircuitState, ConnectionHealth},
+    policy::{Candidate, RoundRobin, SelectionPolicy},
+    sync::SyncConnection,
+    traits::Connection,
+};
 use mc_common::{
-    logger::{o, Logger},
+    logger::{log, o, Logger},
     ResponderId,
 };
 use mc_util_uri::ConnectionUri;
 use std::{
     collections::BTreeMap,
-    sync::{Arc, RwLock, RwLockReadGuard},
+    future::Future,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::{Duration, Instant},
 };
 
 struct ConnectionManagerInner<C: Connection> {
     /// Map of responder id -> retryable connection.
     id_to_conn: BTreeMap<ResponderId, SyncConnection<C>>,
+
+    /// Map of responder id -> recent health of the connection.
+    id_to_health: BTreeMap<ResponderId, ConnectionHealth>,
 }
 
 /// A connection manager manages a list of peers it is connected to.
 pub struct ConnectionManager<C: Connection> {
     inner: Arc<RwLock<ConnectionManagerInner<C>>>,
+    policy: Arc<dyn SelectionPolicy>,
+    circuit_breaker: CircuitBreakerConfig,
+    logger: Logger,
 }
 
 impl<C: Connection> Clone for ConnectionManager<C> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            policy: self.policy.clone(),
+            circuit_breaker: self.circuit_breaker,
+            logger: self.logger.clone(),
         }
     }
 }
 
 /// A collection of connections
 impl<C: Connection> ConnectionManager<C> {
+    /// Create a manager which selects connections round-robin, with the
+    /// default circuit breaker.
     pub fn new(conns: Vec<C>, logger: Logger) -> Self {
+        Self::with_policy(
+            conns,
+            Arc::new(RoundRobin::default()),
+            CircuitBreakerConfig::default(),
+            logger,
+        )
+    }
+
+    /// Create a manager which selects connections using the given policy.
+    pub fn with_policy(
+        conns: Vec<C>,
+        policy: Arc<dyn SelectionPolicy>,
+        circuit_breaker: CircuitBreakerConfig,
+        logger: Logger,
+    ) -> Self {
+        let id_to_conn: BTreeMap<ResponderId, SyncConnection<C>> = conns
+            .into_iter()
+            .map(|conn| Self::sync_conn(conn, &logger))
+            .collect();
+        let id_to_health = id_to_conn
+            .keys()
+            .map(|responder_id| (responder_id.clone(), ConnectionHealth::default()))
+            .collect();
         Self {
             inner: Arc::new(RwLock::new(ConnectionManagerInner {
-                id_to_conn: conns
-                    .into_iter()
-                    .map(|conn| {
-                        let name = conn.to_string();
-                        let responder_id = conn.uri().responder_id().unwrap_or_else(|_| {
-                            panic!(
-                                "Could not create responder_id from {:?}",
-                                conn.uri().to_string()
-                            )
-                        });
-                        let sync_conn =
-                            SyncConnection::new(conn, logger.new(o!("mc.peers.peer_name" => name)));
-                        (responder_id, sync_conn)
-                    })
-                    .collect(),
+                id_to_conn,
+                id_to_health,
             })),
+            policy,
+            circuit_breaker,
+            logger,
         }
     }
 
+    /// Wrap a connection in a SyncConnection, keyed by its responder id.
+    fn sync_conn(conn: C, logger: &Logger) -> (ResponderId, SyncConnection<C>) {
+        let name = conn.to_string();
+        let responder_id = conn.uri().responder_id().unwrap_or_else(|_| {
+            panic!(
+                "Could not create responder_id from {:?}",
+                conn.uri().to_string()
+            )
+        });
+        let sync_conn = SyncConnection::new(conn, logger.new(o!("mc.peers.peer_name" => name)));
+        (responder_id, sync_conn)
+    }
+
     fn read(&self) -> RwLockReadGuard<ConnectionManagerInner<C>> {
         self.inner.read().expect("ConnectionManager lock poisoned")
     }
 
+    fn write(&self) -> RwLockWriteGuard<ConnectionManagerInner<C>> {
+        self.inner.write().expect("ConnectionManager lock poisoned")
+    }
+
+    /// Replace the managed connections with the given ones.
+    ///
+    /// An existing connection to the same URI is kept rather than replaced, so
+    /// that peers which remain configured keep their established sessions.
+    pub fn set_conns(&self, conns: Vec<C>) {
+        let mut inner = self.write();
+        let mut id_to_conn = BTreeMap::new();
+        let mut id_to_health = BTreeMap::new();
+        for conn in conns {
+            let (responder_id, sync_conn) = Self::sync_conn(conn, &self.logger);
+            let (sync_conn, health) = match inner.id_to_conn.remove(&responder_id) {
+                Some(existing) if existing.uri() == sync_conn.uri() => {
+                    let health = inner.id_to_health.remove(&responder_id).unwrap_or_default();
+                    (existing, health)
+                }
+                _ => (sync_conn, ConnectionHealth::default()),
+            };
+            id_to_health.insert(responder_id.clone(), health);
+            id_to_conn.insert(responder_id, sync_conn);
+        }
+        inner.id_to_conn = id_to_conn;
+        inner.id_to_health = id_to_health;
+    }
+
     /// Retrieve a vector of all the connection URLs owned by this manager.
     pub fn responder_ids(&self) -> Vec<ResponderId> {
         self.read().id_to_conn.keys().cloned().collect()
@@ -88,4 +162,164 @@ impl<C: Connection> ConnectionManager<C> {
     pub fn is_empty(&self) -> bool {
         self.read().id_to_conn.is_empty()
     }
+
+    /// Retrieve the recent health of each connection.
+    pub fn health(&self) -> BTreeMap<ResponderId, ConnectionHealth> {
+        self.read().id_to_health.clone()
+    }
+
+    /// Retrieve the connections in the order the selection policy prefers.
+    ///
+    /// Connections whose circuit is open are left out, unless every circuit is
+    /// open, in which case they are all included.
+    pub fn select(&self) -> Vec<(ResponderId, SyncConnection<C>)> {
+        let inner = self.read();
+        let now = Instant::now();
+        let candidates: Vec<Candidate> = inner
+            .id_to_health
+            .iter()
+            .map(|(responder_id, health)| {
+                let state = health.state(&self.circuit_breaker, now);
+                counters::publish_state(responder_id, state);
+                Candidate {
+                    responder_id: responder_id.clone(),
+                    state,
+                    rtt: health.rtt(),
+                }
+            })
+            .collect();
+
+        let available: Vec<Candidate> = candidates
+            .iter()
+            .filter(|candidate| candidate.state != CircuitState::Open)
+            .cloned()
+            .collect();
+        let candidates = if available.is_empty() {
+            candidates
+        } else {
+            available
+        };
+
+        self.policy
+            .order(&candidates)
+            .into_iter()
+            .filter_map(|responder_id| {
+                let conn = inner.id_to_conn.get(&responder_id)?.clone();
+                Some((responder_id, conn))
+            })
+            .collect()
+    }
+
+    /// Record a call to a connection which got a response, taking `rtt`.
+    pub fn record_success(&self, responder_id: &ResponderId, rtt: Duration) {
+        self.policy.record_result(responder_id, true);
+        let mut inner = self.write();
+        if let Some(health) = inner.id_to_health.get_mut(responder_id) {
+            health.record_success(rtt);
+            counters::publish_health(responder_id, health, CircuitState::Closed);
+        }
+    }
+
+    /// Record a call to a connection which failed with a retryable error.
+    pub fn record_failure(&self, responder_id: &ResponderId) {
+        self.policy.record_result(responder_id, false);
+        let mut inner = self.write();
+        if let Some(health) = inner.id_to_health.get_mut(responder_id) {
+            let now = Instant::now();
+            if health.record_failure(&self.circuit_breaker, now) {
+                log::warn!(
+                    self.logger,
+                    "Opening circuit to {} after {} consecutive failures",
+                    responder_id,
+                    health.consecutive_failures()
+                );
+                counters::CIRCUITS_OPENED.inc();
+            }
+            counters::publish_health(
+                responder_id,
+                health,
+                health.state(&self.circuit_breaker, now),
+            );
+        }
+    }
+
+    /// Make a call over the connections in the order the selection policy
+    /// prefers, failing over to the next connection whenever a call fails
+    /// with an error which should be retried.
+    ///
+    /// Every call is recorded in the health of its connection. Errors which
+    /// should not be retried, such as a rejected transaction, mean the node
+    /// responded, so they count as successful calls, and are returned
+    /// immediately.
+    pub fn call<T>(
+        &self,
+        mut func: impl FnMut(&SyncConnection<C>) -> RetryResult<T>,
+    ) -> RetryResult<T> {
+        let mut last_error = None;
+        for (responder_id, conn) in self.select() {
+            let start = Instant::now();
+            let result = func(&conn);
+            let elapsed = start.elapsed();
+
+            match &result {
+                Err(RetryError::Operation { error, .. }) if error.should_retry() => {
+                    log::debug!(
+                        self.logger,
+                        "Call to {} failed, failing over: {}",
+                        responder_id,
+                        error
+                    );
+                    self.record_failure(&responder_id);
+                    last_error = result.err();
+                }
+                Err(RetryError::Internal(_)) => return result,
+                _ => {
+                    self.record_success(&responder_id, elapsed);
+                    return result;
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| RetryError::Internal("No connections".to_owned())))
+    }
+}
+
+impl<C: Connection + Clone> ConnectionManager<C> {
+    /// Make an asynchronous call over the connections in the order the
+    /// selection policy prefers, failing over to the next connection whenever
+    /// a call fails with an error which should be retried.
+    ///
+    /// This is the asynchronous counterpart of [ConnectionManager::call]. Each
+    /// connection is cloned out of its `SyncConnection` before `func` is
+    /// called, so no lock is held while the call is awaited, and connections
+    /// are failed over immediately rather than retried after a delay.
+    pub async fn call_async<T, Fut>(&self, mut func: impl FnMut(C) -> Fut) -> Result<T>
+    where
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+        for (responder_id, conn) in self.select() {
+            let conn = conn.read().clone();
+            let start = Instant::now();
+            let result = func(conn).await;
+            let elapsed = start.elapsed();
+
+            match &result {
+                Err(error) if error.should_retry() => {
+                    log::debug!(
+                        self.logger,
+                        "Call to {} failed, failing over: {}",
+                        responder_id,
+                        error
+                    );
+                    self.record_failure(&responder_id);
+                    last_error = result.err();
+                }
+                _ => {
+                    self.record_success(&responder_id, elapsed);
+                    return result;
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| Error::Other("No connections".to_owned())))
+    }
 }
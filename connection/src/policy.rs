@@ -0,0 +1,261 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Policies for choosing which connection a `ConnectionManager` uses next.
+
+use crate::health::CircuitState;
+use mc_common::ResponderId;
+use mc_crypto_rand::{McRng, RngCore};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// A connection which may be selected, and what is known about its health.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Candidate {
+    /// The connection's responder ID.
+    pub responder_id: ResponderId,
+    /// The state of the connection's circuit breaker.
+    pub state: CircuitState,
+    /// The connection's smoothed round-trip time, if it has been measured.
+    pub rtt: Option<Duration>,
+}
+
+/// A policy which decides the order in which connections are tried.
+pub trait SelectionPolicy: Send + Sync {
+    /// Order `candidates` by preference. The candidates are in responder ID
+    /// order, and only include connections whose circuit is open if every
+    /// circuit is open.
+    fn order(&self, candidates: &[Candidate]) -> Vec<ResponderId>;
+
+    /// Notify the policy of the outcome of a call made over a connection.
+    fn record_result(&self, _responder_id: &ResponderId, _success: bool) {}
+}
+
+/// Start with a different connection on each call, in turn.
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+
+impl RoundRobin {
+    /// Start with the connection at `offset`, modulo the number of
+    /// connections.
+    pub fn new(offset: usize) -> Self {
+        Self {
+            next: AtomicUsize::new(offset),
+        }
+    }
+}
+
+impl SelectionPolicy for RoundRobin {
+    fn order(&self, candidates: &[Candidate]) -> Vec<ResponderId> {
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+        let start = self.next.fetch_add(1, Ordering::SeqCst) % candidates.len();
+        candidates[start..]
+            .iter()
+            .chain(candidates[..start].iter())
+            .map(|candidate| candidate.responder_id.clone())
+            .collect()
+    }
+}
+
+/// Prefer the connection with the lowest measured round-trip time.
+/// Connections which have not been measured yet are tried first, so that
+/// every connection gets measured.
+#[derive(Debug, Default)]
+pub struct LeastLatency;
+
+impl SelectionPolicy for LeastLatency {
+    fn order(&self, candidates: &[Candidate]) -> Vec<ResponderId> {
+        let mut candidates = candidates.to_vec();
+        candidates.sort_by_key(|candidate| candidate.rtt.map(|rtt| (1, rtt)).unwrap_or_default());
+        candidates
+            .into_iter()
+            .map(|candidate| candidate.responder_id)
+            .collect()
+    }
+}
+
+/// Keep using the same connection for as long as it works, failing over to the
+/// next connection when it doesn't. The connection which last succeeded
+/// becomes the sticky one.
+#[derive(Debug, Default)]
+pub struct StickyWithFailover {
+    current: Mutex<Option<ResponderId>>,
+}
+
+impl SelectionPolicy for StickyWithFailover {
+    fn order(&self, candidates: &[Candidate]) -> Vec<ResponderId> {
+        let current = self.current.lock().expect("mutex poisoned");
+        let mut order: Vec<ResponderId> = candidates
+            .iter()
+            .map(|candidate| candidate.responder_id.clone())
+            .collect();
+        if let Some(position) = order
+            .iter()
+            .position(|responder_id| Some(responder_id) == current.as_ref())
+        {
+            order[..=position].rotate_right(1);
+        }
+        order
+    }
+
+    fn record_result(&self, responder_id: &ResponderId, success: bool) {
+        let mut current = self.current.lock().expect("mutex poisoned");
+        if success {
+            *current = Some(responder_id.clone());
+        } else if current.as_ref() == Some(responder_id) {
+            *current = None;
+        }
+    }
+}
+
+/// The built-in selection policies, by name.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SelectionPolicyKind {
+    /// [RoundRobin]
+    RoundRobin,
+    /// [LeastLatency]
+    LeastLatency,
+    /// [StickyWithFailover]
+    Sticky,
+}
+
+impl SelectionPolicyKind {
+    /// Create a policy of this kind. Round-robin starts at a random
+    /// connection.
+    pub fn build(self) -> Arc<dyn SelectionPolicy> {
+        match self {
+            Self::RoundRobin => Arc::new(RoundRobin::new(McRng::default().next_u64() as usize)),
+            Self::LeastLatency => Arc::new(LeastLatency),
+            Self::Sticky => Arc::new(StickyWithFailover::default()),
+        }
+    }
+}
+
+impl Default for SelectionPolicyKind {
+    fn default() -> Self {
+        Self::RoundRobin
+    }
+}
+
+impl Display for SelectionPolicyKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match self {
+            Self::RoundRobin => "round-robin",
+            Self::LeastLatency => "least-latency",
+            Self::Sticky => "sticky",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for SelectionPolicyKind {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "round-robin" => Ok(Self::RoundRobin),
+            "least-latency" => Ok(Self::LeastLatency),
+            "sticky" => Ok(Self::Sticky),
+            _ => Err(format!(
+                "Unknown selection policy {}, expected round-robin, least-latency or sticky",
+                src
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn candidate(name: &str, rtt_millis: Option<u64>) -> Candidate {
+        Candidate {
+            responder_id: ResponderId(name.to_owned()),
+            state: CircuitState::Closed,
+            rtt: rtt_millis.map(Duration::from_millis),
+        }
+    }
+
+    fn names(order: Vec<ResponderId>) -> Vec<String> {
+        order
+            .into_iter()
+            .map(|responder_id| responder_id.0)
+            .collect()
+    }
+
+    #[test]
+    fn round_robin_rotates() {
+        let candidates = vec![
+            candidate("a", None),
+            candidate("b", None),
+            candidate("c", None),
+        ];
+        let policy = RoundRobin::new(1);
+        assert_eq!(names(policy.order(&candidates)), vec!["b", "c", "a"]);
+        assert_eq!(names(policy.order(&candidates)), vec!["c", "a", "b"]);
+        assert_eq!(names(policy.order(&candidates)), vec!["a", "b", "c"]);
+        assert!(policy.order(&[]).is_empty());
+    }
+
+    #[test]
+    fn least_latency_probes_then_prefers_fastest() {
+        let candidates = vec![
+            candidate("a", Some(30)),
+            candidate("b", Some(10)),
+            candidate("c", None),
+            candidate("d", Some(20)),
+        ];
+        assert_eq!(
+            names(LeastLatency.order(&candidates)),
+            vec!["c", "b", "d", "a"]
+        );
+    }
+
+    #[test]
+    fn sticky_fails_over() {
+        let candidates = vec![
+            candidate("a", None),
+            candidate("b", None),
+            candidate("c", None),
+        ];
+        let policy = StickyWithFailover::default();
+        assert_eq!(names(policy.order(&candidates)), vec!["a", "b", "c"]);
+
+        // b succeeded after a failed, so b is preferred until it fails.
+        policy.record_result(&ResponderId("a".to_owned()), false);
+        policy.record_result(&ResponderId("b".to_owned()), true);
+        assert_eq!(names(policy.order(&candidates)), vec!["b", "a", "c"]);
+        policy.record_result(&ResponderId("c".to_owned()), false);
+        assert_eq!(names(policy.order(&candidates)), vec!["b", "a", "c"]);
+        policy.record_result(&ResponderId("b".to_owned()), false);
+        assert_eq!(names(policy.order(&candidates)), vec!["a", "b", "c"]);
+
+        // A sticky connection which is no longer a candidate is skipped.
+        policy.record_result(&ResponderId("c".to_owned()), true);
+        assert_eq!(names(policy.order(&candidates[..2])), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn parse_kinds() {
+        for kind in [
+            SelectionPolicyKind::RoundRobin,
+            SelectionPolicyKind::LeastLatency,
+            SelectionPolicyKind::Sticky,
+        ]
+        .iter()
+        {
+            assert_eq!(kind.to_string().parse::<SelectionPolicyKind>(), Ok(*kind));
+        }
+        assert!("random".parse::<SelectionPolicyKind>().is_err());
+    }
+}
@@ -16,7 +16,8 @@ use cookie::CookieJar;
 use displaydoc::Display;
 use grpcio::{CallOption, ChannelBuilder, Environment, Error as GrpcError, MetadataBuilder};
 use mc_attest_ake::{
-    AuthResponseInput, ClientInitiate, Error as AkeError, Ready, Start, Transition,
+    AuthResponseInput, AuthResponseResumeInput, ClientInitiate, Error as AkeError, Ready,
+    RekeyPolicy, ResumptionTicket, Start, Transition,
 };
 use mc_attest_api::{attest::Message, attest_grpc::AttestedApiClient};
 use mc_attest_core::{VerificationReport, Verifier};
@@ -25,6 +26,7 @@ use mc_common::{
     trace_time,
 };
 use mc_consensus_api::{
+    consensus_client::{TxStatusRequest, TxStatusResponse},
     consensus_client_grpc::ConsensusClientApiClient,
     consensus_common::{BlocksRequest, ProposeTxResult},
     consensus_common_grpc::BlockchainApiClient,
@@ -33,9 +35,13 @@ use mc_consensus_api::{
 use mc_crypto_keys::X25519;
 use mc_crypto_noise::CipherError;
 use mc_crypto_rand::McRng;
-use mc_transaction_core::{tx::Tx, Block, BlockID, BlockIndex};
-use mc_util_grpc::{ConnectionUriGrpcioChannel, GrpcCookieStore};
+use mc_transaction_core::{
+    tx::{Tx, TxHash},
+    Block, BlockID, BlockIndex,
+};
+use mc_util_grpc::{inject_trace_context, ConnectionUriGrpcioChannel, GrpcCookieStore};
 use mc_util_serial::encode;
+use mc_util_telemetry::Context;
 use mc_util_uri::{ConnectionUri, ConsensusClientUri as ClientUri, UriConversionError};
 use secrecy::{ExposeSecret, SecretVec};
 use sha2::Sha512;
@@ -47,8 +53,13 @@ use std::{
     ops::Range,
     result::Result as StdResult,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
+/// How long a verified enclave identity may be trusted without verifying its
+/// report again, when re-attesting to the same enclave.
+pub const RESUMPTION_TICKET_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
 /// Attestation failures a thick client can generate
 #[derive(Debug, Display)]
 pub enum ThickClientAttestationError {
@@ -129,6 +140,13 @@ pub struct ThickClient<CP: CredentialsProvider> {
     verifier: Verifier,
     /// The AKE state machine object, if one is available.
     enclave_connection: Option<Ready<Aes256Gcm>>,
+    /// When to rotate the session's writer key.
+    rekey_policy: RekeyPolicy,
+    /// A ticket for resuming a session with the last verified enclave, and
+    /// the report it was verified with.
+    resumption: Option<(ResumptionTicket, VerificationReport)>,
+    /// The time base for key ages and ticket expiry.
+    epoch: Instant,
     /// Generic interface for retreiving GRPC credentials.
     credentials_provider: CP,
     /// A hash map of metadata to set on outbound requests, filled by inbound
@@ -161,11 +179,29 @@ impl<CP: CredentialsProvider> ThickClient<CP> {
             attested_api_client,
             verifier,
             enclave_connection: None,
+            rekey_policy: RekeyPolicy::default(),
+            resumption: None,
+            epoch: Instant::now(),
             credentials_provider,
             cookies: CookieJar::default(),
         })
     }
 
+    /// Replace the policy used to decide when to rotate the session's writer
+    /// key, including for the current session.
+    pub fn set_rekey_policy(&mut self, rekey_policy: RekeyPolicy) {
+        self.rekey_policy = rekey_policy;
+        if let Some(enclave_connection) = self.enclave_connection.as_mut() {
+            enclave_connection.set_rekey_policy(rekey_policy);
+        }
+    }
+
+    /// The ticket which will be used to resume a session with the last
+    /// verified enclave, if any.
+    pub fn resumption_ticket(&self) -> Option<&ResumptionTicket> {
+        self.resumption.as_ref().map(|(ticket, _report)| ticket)
+    }
+
     /// A wrapper for performing an authenticated call. This also takes care to
     /// properly include cookie information in the request.
     fn authenticated_call<
@@ -199,31 +235,70 @@ impl<CP: CredentialsProvider> ThickClient<CP> {
         })
     }
 
-    fn call_option(&self) -> StdResult<CallOption, Box<dyn CredentialsProviderError + 'static>> {
-        let retval = CallOption::default();
+    /// Ask the consensus node what it knows about a previously proposed
+    /// transaction.
+    pub fn fetch_tx_status(&mut self, tx_hash: &TxHash) -> Result<TxStatusResponse> {
+        trace_time!(self.logger, "ThickClient::fetch_tx_status");
 
-        // Create metadata from cookies and credentials
-        let mut metadata_builder = self
-            .cookies
-            .to_client_metadata()
-            .unwrap_or_else(|_| MetadataBuilder::new());
+        let mut request = TxStatusRequest::new();
+        request.mut_tx_hash().set_hash(tx_hash.to_vec());
 
-        if let Some(creds) = self
-            .credentials_provider
-            .get_credentials()
-            .map_err(|err| -> Box<dyn CredentialsProviderError + 'static> { Box::new(err) })?
-        {
-            if !creds.username().is_empty() && !creds.password().is_empty() {
-                metadata_builder
-                    .add_str("Authorization", &creds.authorization_header())
-                    .expect("Error setting authorization header");
+        Ok(self.authenticated_attested_call(|this, call_option| {
+            let (header, message, trailer) = this
+                .consensus_client_api_client
+                .get_tx_status_full(&request, call_option)?;
+
+            // Update cookies from server-sent metadata
+            if let Err(e) = this
+                .cookies
+                .update_from_server_metadata(header.as_ref(), trailer.as_ref())
+            {
+                log::warn!(
+                    this.logger,
+                    "Could not update cookies from gRPC metadata: {}",
+                    e
+                )
             }
-        }
 
-        Ok(retval.headers(metadata_builder.build()))
+            Ok(message)
+        })?)
+    }
+
+    fn call_option(&self) -> StdResult<CallOption, Box<dyn CredentialsProviderError + 'static>> {
+        call_option(&self.cookies, &self.credentials_provider)
     }
 }
 
+/// Create the options for a call, carrying the given cookies and credentials,
+/// and the current trace context.
+pub(crate) fn call_option(
+    cookies: &CookieJar,
+    credentials_provider: &impl CredentialsProvider,
+) -> StdResult<CallOption, Box<dyn CredentialsProviderError + 'static>> {
+    let retval = CallOption::default();
+
+    // Create metadata from cookies and credentials
+    let mut metadata_builder = cookies
+        .to_client_metadata()
+        .unwrap_or_else(|_| MetadataBuilder::new());
+
+    if let Some(creds) = credentials_provider
+        .get_credentials()
+        .map_err(|err| -> Box<dyn CredentialsProviderError + 'static> { Box::new(err) })?
+    {
+        if !creds.username().is_empty() && !creds.password().is_empty() {
+            metadata_builder
+                .add_str("Authorization", &creds.authorization_header())
+                .expect("Error setting authorization header");
+        }
+    }
+
+    // Continue the caller's trace, if any, on the server.
+    inject_trace_context(&Context::current(), &mut metadata_builder);
+
+    Ok(retval.headers(metadata_builder.build()))
+}
+
 impl<CP: CredentialsProvider> Connection for ThickClient<CP> {
     type Uri = ClientUri;
 
@@ -271,11 +346,43 @@ impl<CP: CredentialsProvider> AttestedConnection for ThickClient<CP> {
             )
         }
 
-        let auth_response_event =
-            AuthResponseInput::new(auth_response_msg.into(), self.verifier.clone());
-        let (initiator, verification_report) =
-            initiator.try_next(&mut csprng, auth_response_event)?;
+        // Skip verifying the report of an enclave we recently verified, as
+        // long as it proves it still holds the same identity.
+        let now = self.epoch.elapsed();
+        let resumption = self
+            .resumption
+            .clone()
+            .filter(|(ticket, _report)| now < ticket.expires_at());
+        let (mut initiator, verification_report) = match resumption {
+            Some((ticket, verification_report)) => {
+                let auth_response_event =
+                    AuthResponseResumeInput::new(auth_response_msg.into(), ticket, now);
+                match initiator.try_next(&mut csprng, auth_response_event) {
+                    Ok((initiator, _ticket)) => (initiator, verification_report),
+                    Err(AkeError::ResumptionIdentityMismatch) => {
+                        log::debug!(
+                            self.logger,
+                            "Enclave identity changed, attesting without resumption"
+                        );
+                        self.resumption = None;
+                        return self.attest();
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            None => {
+                let auth_response_event =
+                    AuthResponseInput::new(auth_response_msg.into(), self.verifier.clone());
+                let (initiator, verification_report) =
+                    initiator.try_next(&mut csprng, auth_response_event)?;
+                self.resumption = initiator
+                    .resumption_ticket(now + RESUMPTION_TICKET_LIFETIME)
+                    .map(|ticket| (ticket, verification_report.clone()));
+                (initiator, verification_report)
+            }
+        };
 
+        initiator.set_rekey_policy(self.rekey_policy);
         self.enclave_connection = Some(initiator);
 
         Ok(verification_report)
@@ -431,8 +538,13 @@ impl<CP: CredentialsProvider> UserTxConnection for ThickClient<CP> {
 
         // Don't leave the plaintext serialization floating around
         let tx_plaintext = SecretVec::new(encode(tx));
-        let tx_ciphertext =
-            enclave_connection.encrypt(&[], tx_plaintext.expose_secret().as_ref())?;
+        let tx_ciphertext = enclave_connection
+            .encrypt_at(
+                &[],
+                tx_plaintext.expose_secret().as_ref(),
+                self.epoch.elapsed(),
+            )
+            .map_err(ThickClientAttestationError::from)?;
         msg.set_data(tx_ciphertext);
 
         let resp = self.authenticated_attested_call(|this, call_option| {
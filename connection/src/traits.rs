@@ -3,6 +3,7 @@
 //! Traits which connection implementations can implement.
 
 use crate::error::{Result, RetryResult};
+use futures::future::BoxFuture;
 use grpcio::Error as GrpcError;
 use mc_attest_core::VerificationReport;
 use mc_consensus_api::consensus_common::LastBlockInfoResponse;
@@ -108,6 +109,49 @@ pub trait UserTxConnection: Connection {
     fn propose_tx(&mut self, tx: &Tx) -> Result<BlockIndex>;
 }
 
+// Asynchronous connections: these traits mirror the ones above for use from
+// futures-based code. They take `&self`, so implementations are expected to be
+// cheap to clone, sharing their session between clones.
+
+/// An asynchronous connection which must attest to its peer before use.
+pub trait AsyncAttestedConnection: Connection {
+    type Error: AttestationError + From<GrpcError>;
+
+    /// Whether the connection currently has an attested session.
+    fn is_attested(&self) -> BoxFuture<'_, bool>;
+
+    /// Perform the attestation handshake, replacing any existing session.
+    fn attest(&self) -> BoxFuture<'_, StdResult<VerificationReport, Self::Error>>;
+
+    /// Drop the attested session, so the next call attests again.
+    fn deattest(&self) -> BoxFuture<'_, ()>;
+}
+
+/// An asynchronous connection trait providing APIs for use in retrieving
+/// blocks from a consensus node.
+pub trait AsyncBlockchainConnection: Connection {
+    /// Retrieve the block metadata from the blockchain service.
+    fn fetch_blocks(&self, range: Range<BlockIndex>) -> BoxFuture<'_, Result<Vec<Block>>>;
+
+    /// Retrieve the BlockIDs (hashes) of the given blocks from the blockchain
+    /// service.
+    fn fetch_block_ids(&self, range: Range<BlockIndex>) -> BoxFuture<'_, Result<Vec<BlockID>>>;
+
+    /// Retrieve the consensus node's current block height
+    fn fetch_block_height(&self) -> BoxFuture<'_, Result<BlockIndex>>;
+
+    /// Retrieve the consensus node's current block height and fee
+    fn fetch_block_info(&self) -> BoxFuture<'_, Result<BlockInfo>>;
+}
+
+/// An asynchronous trait supporting the submission of transactions to a node
+pub trait AsyncUserTxConnection: Connection {
+    /// Propose a transaction over the encrypted channel.
+    /// Returns the number of blocks in the ledger at the time the call was
+    /// received.
+    fn propose_tx<'a>(&'a self, tx: &'a Tx) -> BoxFuture<'a, Result<BlockIndex>>;
+}
+
 // Retryable connections: these traits exist to allow SyncConnection to extend
 // itself when its inner connection API supports additional APIs.
 
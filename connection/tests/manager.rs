@@ -0,0 +1,317 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Connection selection, failover and circuit breaking in ConnectionManager.
+
+use futures::{executor::block_on, future::BoxFuture, FutureExt};
+use mc_common::{
+    logger::{test_with_logger, Logger},
+    ResponderId,
+};
+use mc_connection::{
+    AsyncUserTxConnection, CircuitBreakerConfig, CircuitState, Connection, ConnectionManager,
+    Error, Result as ConnectionResult, RetryableUserTxConnection, RoundRobin, StickyWithFailover,
+    UserTxConnection,
+};
+use mc_transaction_core::{tx::Tx, BlockIndex};
+use mc_util_uri::{ConnectionUri, ConsensusClientUri};
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
+    iter::empty,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// How a mock node responds to proposed transactions.
+#[derive(Clone, Copy, Debug)]
+enum Behavior {
+    Accept,
+    Unavailable,
+    Reject,
+}
+
+/// A connection to a mock node whose behavior can be changed while it is
+/// managed.
+#[derive(Clone)]
+struct MockConnection {
+    uri: ConsensusClientUri,
+    behavior: Arc<Mutex<Behavior>>,
+    calls: Arc<AtomicUsize>,
+}
+
+impl MockConnection {
+    fn new(node_id: u32, behavior: Behavior) -> Self {
+        Self {
+            uri: ConsensusClientUri::from_str(&format!("mc://node{}.test.com/", node_id))
+                .expect("Could not construct client uri from string"),
+            behavior: Arc::new(Mutex::new(behavior)),
+            calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn responder_id(&self) -> ResponderId {
+        self.uri.responder_id().unwrap()
+    }
+
+    fn set_behavior(&self, behavior: Behavior) {
+        *self.behavior.lock().unwrap() = behavior;
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(AtomicOrdering::SeqCst)
+    }
+}
+
+impl Display for MockConnection {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.uri)
+    }
+}
+
+impl Eq for MockConnection {}
+
+impl Hash for MockConnection {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uri.addr().hash(state);
+    }
+}
+
+impl Ord for MockConnection {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.uri.addr().cmp(&other.uri.addr())
+    }
+}
+
+impl PartialEq for MockConnection {
+    fn eq(&self, other: &Self) -> bool {
+        self.uri.addr() == other.uri.addr()
+    }
+}
+
+impl PartialOrd for MockConnection {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.uri.addr().partial_cmp(&other.uri.addr())
+    }
+}
+
+impl Connection for MockConnection {
+    type Uri = ConsensusClientUri;
+
+    fn uri(&self) -> Self::Uri {
+        self.uri.clone()
+    }
+}
+
+impl UserTxConnection for MockConnection {
+    fn propose_tx(&mut self, _tx: &Tx) -> ConnectionResult<BlockIndex> {
+        self.calls.fetch_add(1, AtomicOrdering::SeqCst);
+        match *self.behavior.lock().unwrap() {
+            Behavior::Accept => Ok(1),
+            Behavior::Unavailable => Err(Error::Grpc(grpcio::Error::RemoteStopped)),
+            Behavior::Reject => Err(Error::Other("rejected".to_owned())),
+        }
+    }
+}
+
+impl AsyncUserTxConnection for MockConnection {
+    fn propose_tx<'a>(&'a self, tx: &'a Tx) -> BoxFuture<'a, ConnectionResult<BlockIndex>> {
+        let mut conn = self.clone();
+        async move { UserTxConnection::propose_tx(&mut conn, tx) }.boxed()
+    }
+}
+
+fn propose(manager: &ConnectionManager<MockConnection>) -> bool {
+    manager
+        .call(|conn| conn.propose_tx(&Tx::default(), empty()))
+        .is_ok()
+}
+
+fn never_reset() -> CircuitBreakerConfig {
+    CircuitBreakerConfig {
+        failure_threshold: 2,
+        reset_timeout: Duration::from_secs(3600),
+    }
+}
+
+#[test_with_logger]
+fn call_fails_over_to_next_connection(logger: Logger) {
+    let node1 = MockConnection::new(1, Behavior::Unavailable);
+    let node2 = MockConnection::new(2, Behavior::Accept);
+    let manager = ConnectionManager::with_policy(
+        vec![node1.clone(), node2.clone()],
+        Arc::new(StickyWithFailover::default()),
+        never_reset(),
+        logger,
+    );
+
+    assert!(propose(&manager));
+    assert_eq!((node1.calls(), node2.calls()), (1, 1));
+
+    // node2 is now sticky, so node1 isn't tried again.
+    assert!(propose(&manager));
+    assert_eq!((node1.calls(), node2.calls()), (1, 2));
+
+    let health = manager.health();
+    assert_eq!(health[&node1.responder_id()].consecutive_failures(), 1);
+    assert_eq!(health[&node2.responder_id()].consecutive_failures(), 0);
+    assert!(health[&node2.responder_id()].rtt().is_some());
+}
+
+#[test_with_logger]
+fn circuit_opens_after_repeated_failures(logger: Logger) {
+    let node1 = MockConnection::new(1, Behavior::Unavailable);
+    let node2 = MockConnection::new(2, Behavior::Accept);
+    let config = never_reset();
+    let manager = ConnectionManager::with_policy(
+        vec![node1.clone(), node2.clone()],
+        Arc::new(RoundRobin::new(0)),
+        config,
+        logger,
+    );
+
+    // Round-robin starts with node1, then node2, then node1 again.
+    for _ in 0..3 {
+        assert!(propose(&manager));
+    }
+    assert_eq!(node1.calls(), 2);
+
+    let health = manager.health();
+    assert_eq!(
+        health[&node1.responder_id()].state(&config, Instant::now()),
+        CircuitState::Open
+    );
+
+    // node1 is no longer selected, even though it is working again.
+    node1.set_behavior(Behavior::Accept);
+    let selected: Vec<ResponderId> = manager
+        .select()
+        .into_iter()
+        .map(|(responder_id, _conn)| responder_id)
+        .collect();
+    assert_eq!(selected, vec![node2.responder_id()]);
+    for _ in 0..3 {
+        assert!(propose(&manager));
+    }
+    assert_eq!(node1.calls(), 2);
+}
+
+#[test_with_logger]
+fn every_connection_is_selected_when_all_circuits_are_open(logger: Logger) {
+    let node1 = MockConnection::new(1, Behavior::Unavailable);
+    let node2 = MockConnection::new(2, Behavior::Unavailable);
+    let manager = ConnectionManager::with_policy(
+        vec![node1.clone(), node2.clone()],
+        Arc::new(RoundRobin::new(0)),
+        never_reset(),
+        logger,
+    );
+
+    for _ in 0..2 {
+        assert!(!propose(&manager));
+    }
+    assert_eq!((node1.calls(), node2.calls()), (2, 2));
+    assert_eq!(manager.select().len(), 2);
+
+    // A success closes the circuit again.
+    node2.set_behavior(Behavior::Accept);
+    assert!(propose(&manager));
+    assert_eq!(
+        manager.health()[&node2.responder_id()].consecutive_failures(),
+        0
+    );
+}
+
+#[test_with_logger]
+fn rejections_are_not_failed_over(logger: Logger) {
+    let node1 = MockConnection::new(1, Behavior::Reject);
+    let node2 = MockConnection::new(2, Behavior::Accept);
+    let manager = ConnectionManager::with_policy(
+        vec![node1.clone(), node2.clone()],
+        Arc::new(RoundRobin::new(0)),
+        never_reset(),
+        logger,
+    );
+
+    assert!(!propose(&manager));
+    assert_eq!((node1.calls(), node2.calls()), (1, 0));
+    assert_eq!(
+        manager.health()[&node1.responder_id()].consecutive_failures(),
+        0
+    );
+}
+
+#[test_with_logger]
+fn health_survives_set_conns(logger: Logger) {
+    let node1 = MockConnection::new(1, Behavior::Unavailable);
+    let node2 = MockConnection::new(2, Behavior::Accept);
+    let node3 = MockConnection::new(3, Behavior::Accept);
+    let manager = ConnectionManager::with_policy(
+        vec![node1.clone(), node2.clone()],
+        Arc::new(RoundRobin::new(0)),
+        never_reset(),
+        logger,
+    );
+
+    assert!(propose(&manager));
+    manager.set_conns(vec![node1.clone(), node3.clone()]);
+
+    let health = manager.health();
+    assert_eq!(health.len(), 2);
+    assert_eq!(health[&node1.responder_id()].consecutive_failures(), 1);
+    assert_eq!(health[&node3.responder_id()].consecutive_failures(), 0);
+}
+
+fn propose_async(manager: &ConnectionManager<MockConnection>) -> ConnectionResult<BlockIndex> {
+    let tx = Tx::default();
+    block_on(manager.call_async(|conn| {
+        let tx = &tx;
+        async move { AsyncUserTxConnection::propose_tx(&conn, tx).await }
+    }))
+}
+
+#[test_with_logger]
+fn call_async_fails_over_to_next_connection(logger: Logger) {
+    let node1 = MockConnection::new(1, Behavior::Unavailable);
+    let node2 = MockConnection::new(2, Behavior::Accept);
+    let manager = ConnectionManager::with_policy(
+        vec![node1.clone(), node2.clone()],
+        Arc::new(StickyWithFailover::default()),
+        never_reset(),
+        logger,
+    );
+
+    assert_eq!(propose_async(&manager).unwrap(), 1);
+    assert_eq!((node1.calls(), node2.calls()), (1, 1));
+    assert_eq!(propose_async(&manager).unwrap(), 1);
+    assert_eq!((node1.calls(), node2.calls()), (1, 2));
+
+    // Rejections are returned without failing over.
+    node2.set_behavior(Behavior::Reject);
+    match propose_async(&manager) {
+        Err(Error::Other(_)) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+    assert_eq!((node1.calls(), node2.calls()), (1, 3));
+
+    // Once every connection has failed, the last error is returned.
+    node2.set_behavior(Behavior::Unavailable);
+    match propose_async(&manager) {
+        Err(Error::Grpc(_)) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+    assert_eq!(
+        manager.health()[&node2.responder_id()].consecutive_failures(),
+        1
+    );
+}
+
+#[test_with_logger]
+fn call_async_without_connections(logger: Logger) {
+    let manager = ConnectionManager::<MockConnection>::new(vec![], logger);
+    assert!(propose_async(&manager).is_err());
+}
@@ -242,8 +242,12 @@ pub trait ConsensusEnclave: ReportableEnclave {
     /// Start a new outbound connection.
     fn peer_init(&self, peer_id: &ResponderId) -> Result<PeerAuthRequest>;
 
-    /// Accept an inbound authentication request
-    fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)>;
+    /// Accept an inbound authentication request, returning the peer's
+    /// verification report along with the response and session
+    fn peer_accept(
+        &self,
+        req: PeerAuthRequest,
+    ) -> Result<(PeerAuthResponse, PeerSession, VerificationReport)>;
 
     /// Complete the connection
     fn peer_connect(
@@ -271,7 +271,10 @@ impl ConsensusEnclave for SgxConsensusEnclave {
         Ok(peer_auth_request)
     }
 
-    fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
+    fn peer_accept(
+        &self,
+        req: PeerAuthRequest,
+    ) -> Result<(PeerAuthResponse, PeerSession, VerificationReport)> {
         Ok(self.ake.peer_accept(req)?)
     }
 
@@ -458,15 +461,14 @@ impl ConsensusEnclave for SgxConsensusEnclave {
         let mut root_elements = Vec::new();
         let mut rng = McRng::default();
 
-        for (tx, proofs) in transactions_with_proofs.iter() {
-            mc_transaction_core::validation::validate(
-                tx,
-                parent_block.index + 1,
-                proofs,
-                self.minimum_fee.load(Ordering::SeqCst),
-                &mut rng,
-            )?;
+        mc_transaction_core::validation::validate_batch(
+            &transactions_with_proofs,
+            parent_block.index + 1,
+            self.minimum_fee.load(Ordering::SeqCst),
+            &mut rng,
+        )?;
 
+        for (_tx, proofs) in transactions_with_proofs.iter() {
             for proof in proofs {
                 let root_element = compute_implied_merkle_root(proof)
                     .map_err(|_e| TransactionValidationError::InvalidLedgerContext)?;
@@ -162,8 +162,15 @@ impl ConsensusEnclave for ConsensusServiceMockEnclave {
         Ok(vec![].into())
     }
 
-    fn peer_accept(&self, _req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
-        Ok((PeerAuthResponse::default(), PeerSession::default()))
+    fn peer_accept(
+        &self,
+        _req: PeerAuthRequest,
+    ) -> Result<(PeerAuthResponse, PeerSession, VerificationReport)> {
+        Ok((
+            PeerAuthResponse::default(),
+            PeerSession::default(),
+            VerificationReport::default(),
+        ))
     }
 
     fn peer_connect(
@@ -230,15 +237,14 @@ impl ConsensusEnclave for ConsensusServiceMockEnclave {
         let mut root_elements = Vec::new();
         let mut rng = McRng::default();
 
-        for (tx, proofs) in transactions_with_proofs.iter() {
-            mc_transaction_core::validation::validate(
-                tx,
-                parent_block.index + 1,
-                proofs,
-                MINIMUM_FEE,
-                &mut rng,
-            )?;
+        mc_transaction_core::validation::validate_batch(
+            &transactions_with_proofs,
+            parent_block.index + 1,
+            MINIMUM_FEE,
+            &mut rng,
+        )?;
 
+        for (_tx, proofs) in transactions_with_proofs.iter() {
             for proof in proofs {
                 let root_element = compute_implied_merkle_root(proof)
                     .map_err(|_e| TransactionValidationError::InvalidLedgerContext)?;
@@ -48,7 +48,7 @@ mock! {
 
         fn peer_init(&self, peer_id: &ResponderId) -> ConsensusEnclaveResult<PeerAuthRequest>;
 
-        fn peer_accept(&self, req: PeerAuthRequest) -> ConsensusEnclaveResult<(PeerAuthResponse, PeerSession)>;
+        fn peer_accept(&self, req: PeerAuthRequest) -> ConsensusEnclaveResult<(PeerAuthResponse, PeerSession, VerificationReport)>;
 
         fn peer_connect(&self, peer_id: &ResponderId, res: PeerAuthResponse) -> ConsensusEnclaveResult<(PeerSession, VerificationReport)>;
 
@@ -179,7 +179,10 @@ impl ConsensusEnclave for ConsensusServiceSgxEnclave {
         mc_util_serial::deserialize(&outbuf[..])?
     }
 
-    fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
+    fn peer_accept(
+        &self,
+        req: PeerAuthRequest,
+    ) -> Result<(PeerAuthResponse, PeerSession, VerificationReport)> {
         let inbuf = mc_util_serial::serialize(&EnclaveCall::PeerAccept(req))?;
         let outbuf = self.enclave_call(&inbuf)?;
         mc_util_serial::deserialize(&outbuf[..])?
@@ -160,6 +160,12 @@ impl<V: Value, ValidationError: Clone + Display + 'static> ScpNode<V> for Node<V
         self.Q.clone()
     }
 
+    /// Set the local node's quorum set. The current slot keeps using the
+    /// quorum set it was created with.
+    fn set_quorum_set(&mut self, quorum_set: QuorumSet) {
+        self.Q = quorum_set;
+    }
+
     /// Propose values for this node to nominate.
     fn propose_values(&mut self, values: BTreeSet<V>) -> Result<Option<Msg<V>>, String> {
         if values.is_empty() {
@@ -363,6 +369,20 @@ mod tests {
         assert!(node.externalized_slots.is_empty());
     }
 
+    #[test_with_logger]
+    // Should replace the quorum set without affecting the current slot.
+    fn test_set_quorum_set(logger: Logger) {
+        let slot_index = 6;
+        let mut node = get_node(slot_index, logger);
+
+        let new_quorum_set =
+            QuorumSet::new_with_node_ids(2, vec![test_node_id(2), test_node_id(3)]);
+        node.set_quorum_set(new_quorum_set.clone());
+
+        assert_eq!(node.quorum_set(), new_quorum_set);
+        assert_eq!(node.current_slot_index(), slot_index);
+    }
+
     #[test_with_logger]
     // Should pass values to the appropriate slot.
     fn test_propose_values_no_outgoing_message(logger: Logger) {
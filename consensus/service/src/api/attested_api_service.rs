@@ -4,6 +4,7 @@
 
 use grpcio::{RpcContext, UnarySink};
 use mc_attest_api::{attest::AuthMessage, attest_grpc::AttestedApi};
+use mc_attest_core::Verifier;
 use mc_attest_enclave_api::{ClientSession, PeerSession, Session};
 use mc_common::{
     logger::{log, Logger},
@@ -20,6 +21,7 @@ pub struct AttestedApiService<S: Session> {
     authenticator: Arc<dyn Authenticator + Send + Sync>,
     logger: Logger,
     sessions: Arc<Mutex<HashSet<S>>>,
+    peer_verifier: Option<Verifier>,
 }
 
 impl<S: Session> AttestedApiService<S> {
@@ -33,8 +35,16 @@ impl<S: Session> AttestedApiService<S> {
             authenticator,
             logger,
             sessions: Arc::new(Mutex::new(HashSet::default())),
+            peer_verifier: None,
         }
     }
+
+    /// Require inbound peers' verification reports to also satisfy the given
+    /// operator policy, on top of the local enclave's own checks.
+    pub fn with_peer_verifier(mut self, peer_verifier: Option<Verifier>) -> Self {
+        self.peer_verifier = peer_verifier;
+        self
+    }
 }
 
 impl AttestedApi for AttestedApiService<PeerSession> {
@@ -46,7 +56,27 @@ impl AttestedApi for AttestedApiService<PeerSession> {
             }
 
             // TODO: Use the prost message directly, once available
-            match self.enclave.peer_accept(request.into()) {
+            let result = self
+                .enclave
+                .peer_accept(request.into())
+                .map_err(|err| err.to_string())
+                .and_then(|(response, session_id, verification_report)| {
+                    if let Some(verifier) = &self.peer_verifier {
+                        if let Err(err) = verifier.verify(&verification_report) {
+                            // Don't leave the rejected session established in the enclave.
+                            if let Err(close_err) = self.enclave.peer_close(&session_id) {
+                                log::warn!(
+                                    logger,
+                                    "Failed to close rejected peer session: {}",
+                                    close_err
+                                );
+                            }
+                            return Err(format!("Peer rejected by attestation policy: {}", err));
+                        }
+                    }
+                    Ok((response, session_id))
+                });
+            match result {
                 Ok((response, session_id)) => {
                     {
                         self.sessions
@@ -59,11 +89,7 @@ impl AttestedApi for AttestedApiService<PeerSession> {
                 Err(peer_error) => {
                     // This is debug because there's no requirement on the remote party to trigger
                     // it.
-                    log::debug!(
-                        logger,
-                        "ConsensusEnclave::peer_accept failed: {}",
-                        peer_error
-                    );
+                    log::debug!(logger, "Peer authentication failed: {}", peer_error);
                     send_result(
                         ctx,
                         sink,
@@ -130,9 +156,11 @@ mod peer_tests {
         ChannelBuilder, Environment, Error as GrpcError, RpcStatusCode, Server, ServerBuilder,
     };
     use mc_attest_api::attest_grpc::{self, AttestedApiClient};
+    use mc_attest_core::VerificationReport;
+    use mc_attest_enclave_api::PeerAuthResponse;
     use mc_common::{logger::test_with_logger, time::SystemTimeProvider};
     use mc_consensus_enclave_mock::MockConsensusEnclave;
-    use mc_util_grpc::TokenAuthenticator;
+    use mc_util_grpc::{AnonymousAuthenticator, TokenAuthenticator};
     use std::{
         sync::atomic::{AtomicUsize, Ordering::SeqCst},
         time::Duration,
@@ -187,6 +215,46 @@ mod peer_tests {
             }
         }
     }
+
+    #[test_with_logger]
+    // `auth` should reject, and close, inbound sessions whose verification report
+    // fails the peer verifier.
+    fn test_peer_auth_rejected_by_verifier(logger: Logger) {
+        let mut enclave = MockConsensusEnclave::new();
+        enclave.expect_peer_accept().times(1).return_const(Ok((
+            PeerAuthResponse::default(),
+            PeerSession::default(),
+            VerificationReport::default(),
+        )));
+        enclave
+            .expect_peer_close()
+            .times(1)
+            .withf(|session| *session == PeerSession::default())
+            .return_const(Ok(()));
+
+        let attested_api_service = AttestedApiService::<PeerSession>::new(
+            Arc::new(enclave),
+            Arc::new(AnonymousAuthenticator::default()),
+            logger,
+        )
+        .with_peer_verifier(Some(Verifier::default()));
+        let sessions = attested_api_service.sessions.clone();
+
+        let (client, _server) = get_client_server(attested_api_service);
+
+        match client.auth(&AuthMessage::default()) {
+            Ok(response) => {
+                panic!("Unexpected response {:?}", response);
+            }
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::PERMISSION_DENIED);
+            }
+            Err(err @ _) => {
+                panic!("Unexpected error {:?}", err);
+            }
+        }
+        assert!(sessions.lock().unwrap().is_empty());
+    }
 }
 
 #[cfg(test)]
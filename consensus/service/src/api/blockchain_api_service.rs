@@ -2,6 +2,10 @@
 
 //! Serves blockchain-related API requests.
 
+use crate::{
+    api::grpc_error::ConsensusGrpcError,
+    client_rate_limits::{ClientCall, ClientRateLimits},
+};
 use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
 use mc_common::logger::{log, Logger};
 use mc_consensus_api::{
@@ -34,6 +38,9 @@ pub struct BlockchainApiService<L: Ledger + Clone> {
 
     /// Configured minimum-fee
     minimum_fee: Option<u64>,
+
+    /// Limits how often each client may fetch blocks, if set.
+    rate_limits: Option<ClientRateLimits>,
 }
 
 impl<L: Ledger + Clone> BlockchainApiService<L> {
@@ -49,6 +56,7 @@ impl<L: Ledger + Clone> BlockchainApiService<L> {
             max_page_size: 2000,
             logger,
             minimum_fee,
+            rate_limits: None,
         }
     }
 
@@ -58,6 +66,11 @@ impl<L: Ledger + Clone> BlockchainApiService<L> {
         self.max_page_size = max_page_size;
     }
 
+    /// Limit how often each client may fetch blocks.
+    pub fn set_rate_limits(&mut self, rate_limits: ClientRateLimits) {
+        self.rate_limits = Some(rate_limits);
+    }
+
     /// Returns information about the last block.
     fn get_last_block_info_helper(&mut self) -> Result<LastBlockInfoResponse, mc_ledger_db::Error> {
         let num_blocks = self.ledger.num_blocks()?;
@@ -144,8 +157,18 @@ impl<L: Ledger + Clone> BlockchainApi for BlockchainApiService<L> {
         let _timer = SVC_COUNTERS.req(&ctx);
 
         mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
-            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
-                return send_result(ctx, sink, err.into(), &logger);
+            let username = match self.authenticator.authenticate_rpc(&ctx) {
+                Ok(username) => username,
+                Err(err) => return send_result(ctx, sink, err.into(), &logger),
+            };
+
+            if let Some(rate_limits) = self.rate_limits.as_ref() {
+                if let Err(retry_after) =
+                    rate_limits.admit_rpc(&ctx, &username, ClientCall::GetBlocks)
+                {
+                    let err = ConsensusGrpcError::RateLimited(retry_after);
+                    return send_result(ctx, sink, Err(err.into()), &logger);
+                }
             }
 
             log::trace!(
@@ -166,6 +189,7 @@ impl<L: Ledger + Clone> BlockchainApi for BlockchainApiService<L> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client_rate_limits::RateLimit;
     use grpcio::{ChannelBuilder, Environment, Error as GrpcError, Server, ServerBuilder};
     use mc_common::{logger::test_with_logger, time::SystemTimeProvider};
     use mc_consensus_api::consensus_common_grpc::{self, BlockchainApiClient};
@@ -373,4 +397,42 @@ mod tests {
             }
         }
     }
+
+    #[test_with_logger]
+    // `get_blocks` should reject clients exceeding their rate limit.
+    fn test_get_blocks_rate_limited(logger: Logger) {
+        let mut ledger_db = create_ledger();
+        let authenticator = Arc::new(AnonymousAuthenticator::default());
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let _blocks = initialize_ledger(&mut ledger_db, 3, &account_key, &mut rng);
+
+        let mut blockchain_api_service =
+            BlockchainApiService::new(ledger_db, authenticator, logger, None);
+        blockchain_api_service.set_rate_limits(ClientRateLimits::new(
+            RateLimit::new(1, 1),
+            RateLimit::new(1, 2),
+        ));
+
+        let (client, _server) = get_client_server(blockchain_api_service);
+
+        let mut request = BlocksRequest::default();
+        request.set_limit(3);
+        for _ in 0..2 {
+            let response = client.get_blocks(&request).unwrap();
+            assert_eq!(response.blocks.len(), 3);
+        }
+
+        match client.get_blocks(&request) {
+            Ok(response) => {
+                panic!("Unexpected response {:?}", response);
+            }
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::RESOURCE_EXHAUSTED);
+            }
+            Err(err) => {
+                panic!("Unexpected error {:?}", err);
+            }
+        }
+    }
 }
@@ -4,22 +4,27 @@
 
 use crate::{
     api::grpc_error::ConsensusGrpcError,
+    client_rate_limits::{ClientCall, ClientRateLimits},
     consensus_service::ProposeTxCallback,
     counters,
-    tx_manager::{TxManager, TxManagerError},
+    tx_manager::{TxManager, TxManagerError, TxStatus},
+    TRACER_NAME,
 };
 use grpcio::{RpcContext, RpcStatus, UnarySink};
 use mc_attest_api::attest::Message;
 use mc_common::logger::Logger;
 use mc_consensus_api::{
+    consensus_client::{TxStatus as TxStatusProto, TxStatusRequest, TxStatusResponse},
     consensus_client_grpc::ConsensusClientApi,
     consensus_common::{ProposeTxResponse, ProposeTxResult},
 };
 use mc_consensus_enclave::ConsensusEnclave;
 use mc_ledger_db::Ledger;
-use mc_util_grpc::{rpc_logger, send_result, Authenticator};
+use mc_transaction_core::tx::TxHash;
+use mc_util_grpc::{rpc_logger, rpc_span, send_result, Authenticator};
 use mc_util_metrics::{self, SVC_COUNTERS};
-use std::sync::Arc;
+use mc_util_telemetry::{global, Context, KeyValue, TraceContextExt, Tracer};
+use std::{convert::TryFrom, sync::Arc};
 
 /// Maximum number of pending values for consensus service before rejecting
 /// add_transaction requests.
@@ -35,6 +40,8 @@ pub struct ClientApiService {
     /// Returns true if this node is able to process proposed transactions.
     is_serving_fn: Arc<(dyn Fn() -> bool + Sync + Send)>,
     authenticator: Arc<dyn Authenticator + Send + Sync>,
+    /// Limits how often each client may propose transactions.
+    rate_limits: ClientRateLimits,
     logger: Logger,
 }
 
@@ -46,6 +53,7 @@ impl ClientApiService {
         tx_manager: Arc<dyn TxManager + Send + Sync>,
         is_serving_fn: Arc<(dyn Fn() -> bool + Sync + Send)>,
         authenticator: Arc<dyn Authenticator + Send + Sync>,
+        rate_limits: ClientRateLimits,
         logger: Logger,
     ) -> Self {
         Self {
@@ -55,6 +63,7 @@ impl ClientApiService {
             propose_tx_callback: scp_client_value_sender,
             is_serving_fn,
             authenticator,
+            rate_limits,
             logger,
         }
     }
@@ -81,17 +90,50 @@ impl ClientApiService {
             }
             err
         })?;
+        Context::current()
+            .span()
+            .set_attribute(KeyValue::new("tx_hash", tx_hash.to_string()));
 
         // Validate the transaction.
         // This is done here as a courtesy to give clients immediate feedback about the
         // transaction.
-        self.tx_manager.validate(&tx_hash)?;
+        global::tracer(TRACER_NAME).in_span("TxManager::validate", |_| {
+            self.tx_manager.validate(&tx_hash)
+        })?;
 
         // The transaction can be considered by the network.
         (*self.propose_tx_callback)(tx_hash, None, None);
         counters::ADD_TX.inc();
         Ok(response)
     }
+
+    /// Reports what this node knows about a transaction.
+    fn handle_tx_status(
+        &mut self,
+        request: TxStatusRequest,
+    ) -> Result<TxStatusResponse, ConsensusGrpcError> {
+        let tx_hash = TxHash::try_from(request.get_tx_hash().get_hash())
+            .map_err(|_| ConsensusGrpcError::InvalidArgument("tx_hash".to_string()))?;
+
+        let mut response = TxStatusResponse::new();
+        match self.tx_manager.tx_status(&tx_hash) {
+            TxStatus::Unknown => response.set_status(TxStatusProto::Unknown),
+            TxStatus::Pending => response.set_status(TxStatusProto::Pending),
+            TxStatus::Rejected(err) => {
+                response.set_status(TxStatusProto::Rejected);
+                response.set_rejection_reason(ProposeTxResult::from(err));
+            }
+            TxStatus::Expired => response.set_status(TxStatusProto::Expired),
+            TxStatus::Evicted => response.set_status(TxStatusProto::Evicted),
+            TxStatus::Externalized(block_index) => {
+                response.set_status(TxStatusProto::Externalized);
+                response.set_block_index(block_index);
+            }
+        }
+
+        response.set_block_count(self.ledger.num_blocks()?);
+        Ok(response)
+    }
 }
 
 impl ConsensusClientApi for ClientApiService {
@@ -102,30 +144,43 @@ impl ConsensusClientApi for ClientApiService {
         sink: UnarySink<ProposeTxResponse>,
     ) {
         let _timer = SVC_COUNTERS.req(&ctx);
+        let _trace_guard =
+            rpc_span(&global::tracer(TRACER_NAME), &ctx, "client_tx_propose").attach();
 
-        if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
-            return send_result(ctx, sink, err.into(), &self.logger);
-        }
+        let username = match self.authenticator.authenticate_rpc(&ctx) {
+            Ok(username) => username,
+            Err(err) => return send_result(ctx, sink, err.into(), &self.logger),
+        };
 
-        let mut result: Result<ProposeTxResponse, RpcStatus> =
-            if counters::CUR_NUM_PENDING_VALUES.get() >= PENDING_LIMIT {
-                // This node is over capacity, and is not accepting proposed transaction.
-                if let Err(e) = self.enclave.client_discard_message(msg.into()) {
-                    ConsensusGrpcError::Enclave(e).into()
-                } else {
-                    ConsensusGrpcError::OverCapacity.into()
-                }
-            } else if !(self.is_serving_fn)() {
-                // This node is unable to process transactions (e.g. is syncing its ledger).
-                if let Err(e) = self.enclave.client_discard_message(msg.into()) {
-                    ConsensusGrpcError::Enclave(e).into()
-                } else {
-                    ConsensusGrpcError::NotServing.into()
-                }
+        let mut result: Result<ProposeTxResponse, RpcStatus> = if let Err(retry_after) = self
+            .rate_limits
+            .admit_rpc(&ctx, &username, ClientCall::ProposeTx)
+        {
+            // This client has proposed too many transactions recently.
+            if let Err(e) = self.enclave.client_discard_message(msg.into()) {
+                ConsensusGrpcError::Enclave(e).into()
+            } else {
+                ConsensusGrpcError::RateLimited(retry_after).into()
+            }
+        } else if counters::CUR_NUM_PENDING_VALUES.get() >= PENDING_LIMIT {
+            // This node is over capacity, and is not accepting proposed transaction.
+            if let Err(e) = self.enclave.client_discard_message(msg.into()) {
+                ConsensusGrpcError::Enclave(e).into()
+            } else {
+                ConsensusGrpcError::OverCapacity.into()
+            }
+        } else if !(self.is_serving_fn)() {
+            // This node is unable to process transactions (e.g. is syncing its ledger, or
+            // shutting down).
+            if let Err(e) = self.enclave.client_discard_message(msg.into()) {
+                ConsensusGrpcError::Enclave(e).into()
             } else {
-                self.handle_proposed_tx(msg)
-                    .or_else(ConsensusGrpcError::into)
-            };
+                ConsensusGrpcError::NotServing.into()
+            }
+        } else {
+            self.handle_proposed_tx(msg)
+                .or_else(ConsensusGrpcError::into)
+        };
 
         result = result.and_then(|mut response| {
             let num_blocks = self.ledger.num_blocks().map_err(ConsensusGrpcError::from)?;
@@ -137,14 +192,34 @@ impl ConsensusClientApi for ClientApiService {
             send_result(ctx, sink, result, &logger)
         });
     }
+
+    fn get_tx_status(
+        &mut self,
+        ctx: RpcContext,
+        request: TxStatusRequest,
+        sink: UnarySink<TxStatusResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+
+        if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+            return send_result(ctx, sink, err.into(), &self.logger);
+        }
+
+        let result = self.handle_tx_status(request).map_err(RpcStatus::from);
+
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            send_result(ctx, sink, result, &logger)
+        });
+    }
 }
 
 #[cfg(test)]
 mod client_api_tests {
     use crate::{
         api::client_api_service::{ClientApiService, PENDING_LIMIT},
+        client_rate_limits::{ClientRateLimits, RateLimit},
         counters,
-        tx_manager::{MockTxManager, TxManagerError},
+        tx_manager::{MockTxManager, TxManagerError, TxStatus},
     };
     use grpcio::{
         ChannelBuilder, Environment, Error as GrpcError, RpcStatusCode, Server, ServerBuilder,
@@ -156,7 +231,9 @@ mod client_api_tests {
         NodeID, ResponderId,
     };
     use mc_consensus_api::{
-        consensus_client_grpc, consensus_client_grpc::ConsensusClientApiClient,
+        consensus_client::{TxStatus as TxStatusProto, TxStatusRequest},
+        consensus_client_grpc,
+        consensus_client_grpc::ConsensusClientApiClient,
         consensus_common::ProposeTxResult,
     };
     use mc_consensus_enclave::TxContext;
@@ -233,6 +310,7 @@ mod client_api_tests {
             Arc::new(tx_manager),
             is_serving_fn,
             Arc::new(authenticator),
+            ClientRateLimits::default(),
             logger,
         );
 
@@ -301,6 +379,7 @@ mod client_api_tests {
             Arc::new(tx_manager),
             is_serving_fn,
             Arc::new(authenticator),
+            ClientRateLimits::default(),
             logger,
         );
 
@@ -363,6 +442,7 @@ mod client_api_tests {
             Arc::new(tx_manager),
             is_serving_fn,
             Arc::new(authenticator),
+            ClientRateLimits::default(),
             logger,
         );
 
@@ -407,6 +487,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             is_serving_fn,
             Arc::new(authenticator),
+            ClientRateLimits::default(),
             logger,
         );
 
@@ -453,6 +534,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             is_serving_fn,
             Arc::new(authenticator),
+            ClientRateLimits::default(),
             logger,
         );
 
@@ -503,6 +585,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             is_serving_fn,
             Arc::new(authenticator),
+            ClientRateLimits::default(),
             logger,
         );
 
@@ -522,4 +605,184 @@ mod client_api_tests {
             }
         };
     }
+
+    #[test_with_logger]
+    #[serial(counters)]
+    // Should return RpcStatus ResourceExhausted once a client exceeds its rate
+    // limit.
+    fn test_client_tx_propose_rate_limited(logger: Logger) {
+        let mut enclave = MockConsensusEnclave::new();
+        enclave
+            .expect_client_discard_message()
+            .times(2)
+            .return_const(Ok(()));
+
+        // Not serving, so that the first request is rejected without further mocks.
+        let is_serving_fn = Arc::new(|| -> bool { false });
+
+        let scp_client_value_sender = Arc::new(
+            |_tx_hash: TxHash, _node_id: Option<&NodeID>, _responder_id: Option<&ResponderId>| {},
+        );
+
+        let instance = ClientApiService::new(
+            Arc::new(enclave),
+            scp_client_value_sender,
+            Arc::new(MockLedger::new()),
+            Arc::new(MockTxManager::new()),
+            is_serving_fn,
+            Arc::new(AnonymousAuthenticator::default()),
+            ClientRateLimits::new(RateLimit::new(1, 1), RateLimit::new(1, 1)),
+            logger,
+        );
+
+        // gRPC client and server.
+        let (client, _server) = get_client_server(instance);
+
+        let message = Message::default();
+        match client.client_tx_propose(&message) {
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::UNAVAILABLE);
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        match client.client_tx_propose(&message) {
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::RESOURCE_EXHAUSTED);
+                assert!(rpc_status
+                    .message()
+                    .starts_with("Rate limit exceeded, retry after"));
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test_with_logger]
+    #[serial(counters)]
+    // Should report the status known by the tx manager.
+    fn test_get_tx_status(logger: Logger) {
+        let tx_hash = TxHash([7u8; 32]);
+
+        let num_blocks = 12;
+        let mut ledger = MockLedger::new();
+        ledger.expect_num_blocks().return_const(Ok(num_blocks));
+
+        let mut tx_manager = MockTxManager::new();
+        tx_manager
+            .expect_tx_status()
+            .times(1)
+            .return_const(TxStatus::Externalized(9));
+
+        let scp_client_value_sender = Arc::new(
+            |_tx_hash: TxHash, _node_id: Option<&NodeID>, _responder_id: Option<&ResponderId>| {},
+        );
+
+        let instance = ClientApiService::new(
+            Arc::new(MockConsensusEnclave::new()),
+            scp_client_value_sender,
+            Arc::new(ledger),
+            Arc::new(tx_manager),
+            Arc::new(|| -> bool { true }),
+            Arc::new(AnonymousAuthenticator::default()),
+            ClientRateLimits::default(),
+            logger,
+        );
+
+        // gRPC client and server.
+        let (client, _server) = get_client_server(instance);
+
+        let mut request = TxStatusRequest::new();
+        request.mut_tx_hash().set_hash(tx_hash.to_vec());
+
+        match client.get_tx_status(&request) {
+            Ok(response) => {
+                assert_eq!(response.get_status(), TxStatusProto::Externalized);
+                assert_eq!(response.get_block_index(), 9);
+                assert_eq!(response.get_block_count(), num_blocks);
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test_with_logger]
+    #[serial(counters)]
+    // Should report the rejection reason of a rejected transaction.
+    fn test_get_tx_status_rejected(logger: Logger) {
+        let mut ledger = MockLedger::new();
+        ledger.expect_num_blocks().return_const(Ok(3));
+
+        let mut tx_manager = MockTxManager::new();
+        tx_manager
+            .expect_tx_status()
+            .times(1)
+            .return_const(TxStatus::Rejected(
+                TransactionValidationError::ContainsSpentKeyImage,
+            ));
+
+        let scp_client_value_sender = Arc::new(
+            |_tx_hash: TxHash, _node_id: Option<&NodeID>, _responder_id: Option<&ResponderId>| {},
+        );
+
+        let instance = ClientApiService::new(
+            Arc::new(MockConsensusEnclave::new()),
+            scp_client_value_sender,
+            Arc::new(ledger),
+            Arc::new(tx_manager),
+            Arc::new(|| -> bool { true }),
+            Arc::new(AnonymousAuthenticator::default()),
+            ClientRateLimits::default(),
+            logger,
+        );
+
+        // gRPC client and server.
+        let (client, _server) = get_client_server(instance);
+
+        let mut request = TxStatusRequest::new();
+        request.mut_tx_hash().set_hash(vec![1u8; 32]);
+
+        match client.get_tx_status(&request) {
+            Ok(response) => {
+                assert_eq!(response.get_status(), TxStatusProto::Rejected);
+                assert_eq!(
+                    response.get_rejection_reason(),
+                    ProposeTxResult::ContainsSpentKeyImage
+                );
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test_with_logger]
+    #[serial(counters)]
+    // Should reject a malformed tx hash.
+    fn test_get_tx_status_invalid_tx_hash(logger: Logger) {
+        let scp_client_value_sender = Arc::new(
+            |_tx_hash: TxHash, _node_id: Option<&NodeID>, _responder_id: Option<&ResponderId>| {},
+        );
+
+        let instance = ClientApiService::new(
+            Arc::new(MockConsensusEnclave::new()),
+            scp_client_value_sender,
+            Arc::new(MockLedger::new()),
+            Arc::new(MockTxManager::new()),
+            Arc::new(|| -> bool { true }),
+            Arc::new(AnonymousAuthenticator::default()),
+            ClientRateLimits::default(),
+            logger,
+        );
+
+        // gRPC client and server.
+        let (client, _server) = get_client_server(instance);
+
+        let mut request = TxStatusRequest::new();
+        request.mut_tx_hash().set_hash(vec![1u8; 3]);
+
+        match client.get_tx_status(&request) {
+            Ok(response) => panic!("Unexpected response {:?}", response),
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::INVALID_ARGUMENT);
+            }
+            Err(err) => panic!("Unexpected error {:?}", err),
+        }
+    }
 }
@@ -8,6 +8,7 @@ use mc_consensus_api::consensus_common::{ProposeTxResponse, ProposeTxResult};
 use mc_consensus_enclave::Error as EnclaveError;
 use mc_ledger_db::Error as LedgerError;
 use mc_transaction_core::validation::TransactionValidationError;
+use std::time::Duration;
 
 #[derive(Debug, Display)]
 pub enum ConsensusGrpcError {
@@ -23,6 +24,9 @@ pub enum ConsensusGrpcError {
     /// Service is currently not serving requests
     NotServing,
 
+    /// Rate limit exceeded, retry after {0:?}
+    RateLimited(Duration),
+
     /// Enclave error: `{0}`
     Enclave(EnclaveError),
 
@@ -69,6 +73,7 @@ impl From<TxManagerError> for ConsensusGrpcError {
             TxManagerError::Enclave(err) => Self::from(err),
             TxManagerError::TransactionValidation(err) => Self::from(err),
             TxManagerError::LedgerDb(err) => Self::from(err),
+            TxManagerError::CacheFull => Self::OverCapacity,
             _ => Self::Other(format!("tx manager error: {}", src)),
         }
     }
@@ -89,6 +94,13 @@ impl From<ConsensusGrpcError> for RpcStatus {
                 RpcStatusCode::UNAVAILABLE,
                 "Temporarily not serving requests".into(),
             ),
+            ConsensusGrpcError::RateLimited(retry_after) => RpcStatus::with_message(
+                RpcStatusCode::RESOURCE_EXHAUSTED,
+                format!(
+                    "Rate limit exceeded, retry after {} ms",
+                    retry_after.as_millis().max(1)
+                ),
+            ),
             ConsensusGrpcError::Enclave(EnclaveError::Attest(err)) => {
                 global_log::error!("Permission denied: {}", err);
                 RpcStatus::with_message(
@@ -96,6 +108,10 @@ impl From<ConsensusGrpcError> for RpcStatus {
                     "Permission Denied (attestation)".into(),
                 )
             }
+            ConsensusGrpcError::InvalidArgument(err) => RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                format!("Invalid argument: {}", err),
+            ),
             ConsensusGrpcError::Other(err) => RpcStatus::with_message(RpcStatusCode::INTERNAL, err),
             ConsensusGrpcError::TransactionValidation(err) => {
                 global_log::error!("Attempting to convert a ConsensusGrpcError::TransactionValidation into RpcStatus, this should not happen! Error is: {}", err);
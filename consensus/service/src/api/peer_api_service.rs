@@ -7,6 +7,7 @@ use crate::{
     background_work_queue::BackgroundWorkQueueSenderFn,
     consensus_service::{IncomingConsensusMsg, ProposeTxCallback},
     counters,
+    peer_scores::{MsgAdmission, PeerKey, PeerOffense, PeerScores},
     tx_manager::{TxManager, TxManagerError},
 };
 use grpcio::{RpcContext, RpcStatus, UnarySink};
@@ -37,7 +38,7 @@ use mc_util_serial::deserialize;
 use std::{
     convert::{TryFrom, TryInto},
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 
 // Callback method for returning the latest SCP message issued by the local
@@ -70,7 +71,12 @@ pub struct PeerApiService {
     /// requests to. That is necessary for resolving TxHashes into Txs. If
     /// we received a consensus message from a peer not on this list, we
     /// won't be able to reach out to it to ask for the transaction contents.
-    known_responder_ids: Vec<ResponderId>,
+    /// Shared so that it can be updated when the network configuration
+    /// changes.
+    known_responder_ids: Arc<RwLock<Vec<ResponderId>>>,
+
+    /// Rate limits and reputation scores of peers.
+    peer_scores: PeerScores,
 
     /// Logger.
     logger: Logger,
@@ -89,6 +95,7 @@ impl PeerApiService {
     /// * `fetch_latest_msg_fn` - Returns highest message emitted by this node.
     /// * `known_responder_ids` - Messages from peers not on this "whitelist"
     ///   are ignored.
+    /// * `peer_scores` - Rate limits and reputation scores of peers.
     /// * `logger` - Logger.
     pub fn new(
         consensus_enclave: Arc<dyn ConsensusEnclave + Send + Sync>,
@@ -97,7 +104,8 @@ impl PeerApiService {
         incoming_consensus_msgs_sender: BackgroundWorkQueueSenderFn<IncomingConsensusMsg>,
         scp_client_value_sender: ProposeTxCallback,
         fetch_latest_msg_fn: FetchLatestMsgFn,
-        known_responder_ids: Vec<ResponderId>,
+        known_responder_ids: Arc<RwLock<Vec<ResponderId>>>,
+        peer_scores: PeerScores,
         logger: Logger,
     ) -> Self {
         Self {
@@ -108,10 +116,31 @@ impl PeerApiService {
             ledger,
             fetch_latest_msg_fn,
             known_responder_ids,
+            peer_scores,
             logger,
         }
     }
 
+    /// Returns true if `responder_id` is a peer we accept messages from.
+    fn is_known_peer(&self, responder_id: &ResponderId) -> bool {
+        self.known_responder_ids
+            .read()
+            .expect("lock poisoned")
+            .contains(responder_id)
+    }
+
+    /// Record misbehavior by a known peer.
+    fn penalize(&self, peer: &PeerKey, offense: PeerOffense) {
+        if self.is_known_peer(&peer.responder_id) && self.peer_scores.penalize(peer, offense) {
+            log::warn!(
+                self.logger,
+                "Temporarily ignoring peer {:?} due to misbehavior ({:?})",
+                peer,
+                offense
+            );
+        }
+    }
+
     /// Handle transactions proposed by clients to a different node.
     ///
     /// # Arguments
@@ -186,23 +215,42 @@ impl PeerApiService {
     }
 
     /// Handle a consensus message from another node.
+    ///
+    /// # Arguments
+    /// * `consensus_msg` - The message.
+    /// * `from` - The peer that delivered the message. Its responder ID is not
+    ///   authenticated, so rate limits and penalties also depend on the
+    ///   connection the message arrived on.
     fn handle_consensus_msg(
         &mut self,
         consensus_msg: mc_peers::ConsensusMsg,
-        from_responder_id: ResponderId,
+        from: PeerKey,
     ) -> Result<(), PeerServiceError> {
         // Ignore a consensus message from an unknown peer.
-        if !self.known_responder_ids.contains(&from_responder_id) {
-            return Err(PeerServiceError::UnknownPeer(from_responder_id.to_string()));
+        if !self.is_known_peer(&from.responder_id) {
+            return Err(PeerServiceError::UnknownPeer(from.responder_id.to_string()));
+        }
+
+        // Drop messages from peers that send too many, or that misbehaved recently.
+        match self.peer_scores.admit_msg(&from) {
+            MsgAdmission::Accept => {}
+            MsgAdmission::RateLimited => {
+                return Err(PeerServiceError::RateLimited(from.responder_id.to_string()))
+            }
+            MsgAdmission::Banned => {
+                return Err(PeerServiceError::Banned(from.responder_id.to_string()))
+            }
         }
 
         // A consensus message with a valid signature.
-        let verified_consensus_msg: mc_peers::VerifiedConsensusMsg = consensus_msg
-            .try_into()
-            .map_err(|_| PeerServiceError::ConsensusMsgInvalidSignature)?;
+        let verified_consensus_msg: mc_peers::VerifiedConsensusMsg =
+            consensus_msg.try_into().map_err(|_| {
+                self.penalize(&from, PeerOffense::InvalidMsg);
+                PeerServiceError::ConsensusMsgInvalidSignature
+            })?;
 
         (self.incoming_consensus_msgs_sender)(IncomingConsensusMsg {
-            from_responder_id,
+            from,
             consensus_msg: verified_consensus_msg,
         })
         .map_err(|_| PeerServiceError::InternalError)
@@ -271,8 +319,8 @@ impl ConsensusPeerApi for PeerApiService {
         let _timer = SVC_COUNTERS.req(&ctx);
         mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
             // The peer who delivered this message to us.
-            let from_responder_id = match ResponderId::from_str(request.get_from_responder_id()) {
-                Ok(responder_id) => responder_id,
+            let from = match ResponderId::from_str(request.get_from_responder_id()) {
+                Ok(responder_id) => PeerKey::delivered(responder_id, &ctx.peer()),
                 Err(_) => {
                     let result = Err(rpc_invalid_arg_error(
                         "send_consensus_msg",
@@ -287,6 +335,7 @@ impl ConsensusPeerApi for PeerApiService {
             let consensus_msg: mc_peers::ConsensusMsg = match deserialize(request.get_payload()) {
                 Ok(consensus_msg) => consensus_msg,
                 Err(_) => {
+                    self.penalize(&from, PeerOffense::InvalidMsg);
                     let result = Err(rpc_invalid_arg_error(
                         "send_consensus_msg",
                         "consensus_msg",
@@ -298,7 +347,7 @@ impl ConsensusPeerApi for PeerApiService {
             };
 
             let result: Result<ConsensusMsgResponse, RpcStatus> = match self
-                .handle_consensus_msg(consensus_msg, from_responder_id)
+                .handle_consensus_msg(consensus_msg, from)
             {
                 Ok(()) => {
                     let mut response = ConsensusMsgResponse::new();
@@ -310,6 +359,16 @@ impl ConsensusPeerApi for PeerApiService {
                     response.set_result(ConsensusMsgResult::UnknownPeer);
                     Ok(response)
                 }
+                Err(PeerServiceError::RateLimited(_)) => {
+                    let mut response = ConsensusMsgResponse::new();
+                    response.set_result(ConsensusMsgResult::RateLimited);
+                    Ok(response)
+                }
+                Err(PeerServiceError::Banned(_)) => {
+                    let mut response = ConsensusMsgResponse::new();
+                    response.set_result(ConsensusMsgResult::Banned);
+                    Ok(response)
+                }
                 Err(PeerServiceError::ConsensusMsgInvalidSignature) => Err(rpc_invalid_arg_error(
                     "send_consensus_msg",
                     "InvalidConsensusMsgSignature",
@@ -398,8 +457,11 @@ impl ConsensusPeerApi for PeerApiService {
 #[cfg(test)]
 mod tests {
     use crate::{
-        api::peer_api_service::PeerApiService, background_work_queue::BackgroundWorkQueueError,
-        consensus_service::IncomingConsensusMsg, tx_manager::MockTxManager,
+        api::{peer_api_service::PeerApiService, peer_service_error::PeerServiceError},
+        background_work_queue::BackgroundWorkQueueError,
+        consensus_service::IncomingConsensusMsg,
+        peer_scores::{MsgAdmission, PeerKey, PeerScores},
+        tx_manager::MockTxManager,
     };
     use grpcio::{ChannelBuilder, Environment, Error::RpcFailure, Server, ServerBuilder};
     use mc_common::{
@@ -422,7 +484,10 @@ mod tests {
     use mc_transaction_core::{tx::TxHash, Block};
     use mc_util_from_random::FromRandom;
     use rand::{rngs::StdRng, SeedableRng};
-    use std::sync::Arc;
+    use std::{
+        sync::{Arc, RwLock},
+        time::Duration,
+    };
 
     // Get sensibly-initialized mocks.
     fn get_mocks() -> (MockConsensusEnclave, MockLedger, MockTxManager) {
@@ -492,7 +557,8 @@ mod tests {
             get_incoming_consensus_msgs_sender_ok(),
             get_scp_client_value_sender(),
             get_fetch_latest_msg_fn(),
-            known_responder_ids.clone(),
+            Arc::new(RwLock::new(known_responder_ids.clone())),
+            PeerScores::default(),
             logger,
         );
 
@@ -566,7 +632,8 @@ mod tests {
             get_incoming_consensus_msgs_sender_ok(),
             get_scp_client_value_sender(),
             get_fetch_latest_msg_fn(),
-            known_responder_ids.clone(),
+            Arc::new(RwLock::new(known_responder_ids.clone())),
+            PeerScores::default(),
             logger,
         );
 
@@ -614,6 +681,82 @@ mod tests {
         // TODO: Should pass the message to incoming_consensus_msgs_sender
     }
 
+    #[test_with_logger]
+    // Should drop messages from a peer that exceeds its rate limit.
+    fn test_send_consensus_msg_rate_limited(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([77u8; 32]);
+        let (consensus_enclave, ledger, tx_manager) = get_mocks();
+
+        // Node A's private message signing keypair.
+        let node_a_signer_key = {
+            let private_key = Ed25519Private::from_random(&mut rng);
+            Ed25519Pair::from(private_key)
+        };
+
+        // ResponderIds seem to be "host:port" strings.
+        let known_responder_ids = vec![
+            ResponderId("A:port".to_owned()),
+            ResponderId("B:port".to_owned()),
+        ];
+
+        // Accept a single message per second.
+        let instance = PeerApiService::new(
+            Arc::new(consensus_enclave),
+            Arc::new(ledger),
+            Arc::new(tx_manager),
+            get_incoming_consensus_msgs_sender_ok(),
+            get_scp_client_value_sender(),
+            get_fetch_latest_msg_fn(),
+            Arc::new(RwLock::new(known_responder_ids.clone())),
+            PeerScores::new(1, 100, Duration::from_secs(60)),
+            logger,
+        );
+
+        let (client, _server) = get_client_server(instance);
+
+        // A message from a known peer.
+        let from = known_responder_ids[0].clone();
+
+        let scp_msg = Msg {
+            sender_id: NodeID {
+                responder_id: from.clone(),
+                public_key: node_a_signer_key.public_key(),
+            },
+            slot_index: 1,
+            quorum_set: QuorumSet {
+                threshold: 0,
+                members: vec![],
+            },
+            topic: Nominate(NominatePayload {
+                X: Default::default(),
+                Y: Default::default(),
+            }),
+        };
+
+        let payload = {
+            // Node A's ledger.
+            let mut ledger = MockLedger::new();
+            ledger
+                .expect_get_block()
+                .return_const(Ok(Block::new_origin_block(&vec![])));
+            mc_peers::ConsensusMsg::from_scp_msg(&ledger, scp_msg, &node_a_signer_key).unwrap()
+        };
+
+        let mut message = ConsensusMsg::new();
+        message.set_from_responder_id(from.to_string());
+        message.set_payload(mc_util_serial::serialize(&payload).unwrap());
+
+        let response = client
+            .send_consensus_msg(&message)
+            .expect("send_consensus_msg failed");
+        assert_eq!(response.get_result(), ConsensusMsgResult::Ok);
+
+        let response = client
+            .send_consensus_msg(&message)
+            .expect("send_consensus_msg failed");
+        assert_eq!(response.get_result(), ConsensusMsgResult::RateLimited);
+    }
+
     #[test_with_logger]
     // Should return an error if the message cannot be deserialized.
     fn test_send_consensus_msg_deserialize_error(logger: Logger) {
@@ -632,7 +775,8 @@ mod tests {
             get_incoming_consensus_msgs_sender_ok(),
             get_scp_client_value_sender(),
             get_fetch_latest_msg_fn(),
-            known_responder_ids.clone(),
+            Arc::new(RwLock::new(known_responder_ids.clone())),
+            PeerScores::default(),
             logger,
         );
 
@@ -680,7 +824,8 @@ mod tests {
             get_incoming_consensus_msgs_sender_ok(),
             get_scp_client_value_sender(),
             get_fetch_latest_msg_fn(),
-            known_responder_ids.clone(),
+            Arc::new(RwLock::new(known_responder_ids.clone())),
+            PeerScores::default(),
             logger,
         );
 
@@ -737,4 +882,79 @@ mod tests {
     // TODO: fetch_txs
 
     // TODO: peer_tx_propose
+
+    #[test_with_logger]
+    // A host claiming to be a known peer should not affect the score or rate limit
+    // of that peer.
+    fn test_send_consensus_msg_spoofed_sender(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([87u8; 32]);
+        let (consensus_enclave, ledger, tx_manager) = get_mocks();
+
+        let node_a_signer_key = Ed25519Pair::from(Ed25519Private::from_random(&mut rng));
+        let node_a_responder_id = ResponderId("A:port".to_owned());
+        let known_responder_ids = vec![node_a_responder_id.clone()];
+
+        let peer_scores = PeerScores::new(5, 25, Duration::from_secs(60));
+        let mut instance = PeerApiService::new(
+            Arc::new(consensus_enclave),
+            Arc::new(ledger),
+            Arc::new(tx_manager),
+            get_incoming_consensus_msgs_sender_ok(),
+            get_scp_client_value_sender(),
+            get_fetch_latest_msg_fn(),
+            Arc::new(RwLock::new(known_responder_ids)),
+            peer_scores.clone(),
+            logger,
+        );
+
+        let sign = |signer_key: &Ed25519Pair| {
+            let scp_msg = Msg {
+                sender_id: NodeID {
+                    responder_id: node_a_responder_id.clone(),
+                    public_key: node_a_signer_key.public_key(),
+                },
+                slot_index: 1,
+                quorum_set: QuorumSet {
+                    threshold: 0,
+                    members: vec![],
+                },
+                topic: Nominate(NominatePayload {
+                    X: Default::default(),
+                    Y: Default::default(),
+                }),
+            };
+            let mut ledger = MockLedger::new();
+            ledger
+                .expect_get_block()
+                .return_const(Ok(Block::new_origin_block(&vec![])));
+            mc_peers::ConsensusMsg::from_scp_msg(&ledger, scp_msg, signer_key).unwrap()
+        };
+        let valid_msg = sign(&node_a_signer_key);
+        let invalid_msg = sign(&Ed25519Pair::from(Ed25519Private::from_random(&mut rng)));
+
+        let real = PeerKey::delivered(node_a_responder_id.clone(), "ipv4:10.0.0.1:50000");
+        let spoofed = PeerKey::delivered(node_a_responder_id, "ipv4:10.6.6.6:50000");
+
+        // The spoofed sender only gets itself banned.
+        for _ in 0..3 {
+            assert!(matches!(
+                instance.handle_consensus_msg(invalid_msg.clone(), spoofed.clone()),
+                Err(PeerServiceError::ConsensusMsgInvalidSignature)
+            ));
+        }
+        assert!(peer_scores.is_banned(&spoofed));
+        assert!(matches!(
+            instance.handle_consensus_msg(invalid_msg, spoofed.clone()),
+            Err(PeerServiceError::Banned(_))
+        ));
+
+        // The real peer is unaffected.
+        assert!(!peer_scores.is_banned(&real));
+        assert!(instance
+            .handle_consensus_msg(valid_msg, real.clone())
+            .is_ok());
+        for _ in 0..4 {
+            assert_eq!(peer_scores.admit_msg(&real), MsgAdmission::Accept);
+        }
+    }
 }
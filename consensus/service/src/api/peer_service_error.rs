@@ -7,6 +7,12 @@ pub enum PeerServiceError {
     /// Unknown peer `{0}`.
     UnknownPeer(String),
 
+    /// Peer `{0}` exceeded its message rate limit.
+    RateLimited(String),
+
+    /// Peer `{0}` is temporarily banned.
+    Banned(String),
+
     /// The ConsensusMsg's signature is invalid.
     ConsensusMsgInvalidSignature,
 
@@ -24,6 +24,8 @@ pub enum BackgroundWorkQueueError {
 
 pub struct BackgroundWorkQueue<T: Send + 'static> {
     join_handle: Option<thread::JoinHandle<Result<(), BackgroundWorkQueueError>>>,
+    // Shared with the worker thread, which drops its reference when it exits.
+    alive: Arc<()>,
     sender: Sender<QueueMsg<T>>,
     receiver: Receiver<QueueMsg<T>>,
 }
@@ -37,6 +39,7 @@ impl<T: Send + 'static> BackgroundWorkQueue<T> {
 
         Self {
             join_handle: None,
+            alive: Arc::new(()),
             sender,
             receiver,
         }
@@ -52,26 +55,30 @@ impl<T: Send + 'static> BackgroundWorkQueue<T> {
         }
 
         let thread_receiver = self.receiver.clone();
+        let thread_alive = self.alive.clone();
         let join_handle = thread::Builder::new()
             .name(thread_name)
-            .spawn(move || loop {
-                match thread_receiver.recv() {
-                    // Successfully received something from the queue
-                    Ok(msg) => {
-                        match msg {
-                            // Request to call our handler function
-                            QueueMsg::Handle(msg) => handle_func(msg),
-
-                            // Request to stop the thread
-                            QueueMsg::StopRequested => {
-                                return Ok(());
-                            }
-                        };
-                    }
-
-                    // Error receiving from queue
-                    Err(_err) => {
-                        return Err(BackgroundWorkQueueError::RecvFailed);
+            .spawn(move || {
+                let _alive = thread_alive;
+                loop {
+                    match thread_receiver.recv() {
+                        // Successfully received something from the queue
+                        Ok(msg) => {
+                            match msg {
+                                // Request to call our handler function
+                                QueueMsg::Handle(msg) => handle_func(msg),
+
+                                // Request to stop the thread
+                                QueueMsg::StopRequested => {
+                                    return Ok(());
+                                }
+                            };
+                        }
+
+                        // Error receiving from queue
+                        Err(_err) => {
+                            return Err(BackgroundWorkQueueError::RecvFailed);
+                        }
                     }
                 }
             })
@@ -82,6 +89,11 @@ impl<T: Send + 'static> BackgroundWorkQueue<T> {
         Ok(())
     }
 
+    /// Whether the worker thread was started and has not exited.
+    pub fn is_running(&self) -> bool {
+        self.join_handle.is_some() && Arc::strong_count(&self.alive) > 1
+    }
+
     pub fn stop(&mut self) -> Result<(), BackgroundWorkQueueError> {
         // Send a stop request. We ignore return value since we might already be
         // stopped.
@@ -16,6 +16,7 @@ use mc_consensus_service::{
     validators::DefaultTxManagerUntrustedInterfaces,
 };
 use mc_ledger_db::LedgerDB;
+use mc_util_grpc::ShutdownSignal;
 use std::{
     env,
     fs::File,
@@ -28,24 +29,29 @@ use structopt::StructOpt;
 fn main() -> Result<(), ConsensusServiceError> {
     mc_common::setup_panic_handler();
     let _sentry_guard = mc_common::sentry::init();
+    let _telemetry_guard = mc_util_telemetry::init("consensus-service");
 
     let config = Config::from_args();
     let local_node_id = config.node_id();
 
+    let shutdown_signal =
+        ShutdownSignal::register().expect("Could not register shutdown signal handlers");
+
     let (logger, _global_logger_guard) = create_app_logger(o!(
         "mc.local_node_id" => local_node_id.responder_id.to_string(),
     ));
 
-    // load the sealed block signing key fron storage
-    let cached_key = match File::open(&config.sealed_block_signing_key) {
-        Ok(mut file) => {
+    // load the sealed block signing key fron storage. Observers do not sign blocks,
+    // so they use a fresh key that is never persisted.
+    let cached_key = match config.sealed_block_signing_key.as_ref().map(File::open) {
+        Some(Ok(mut file)) => {
             let mut contents = String::new();
             match file.read_to_string(&mut contents) {
                 Ok(_) => Some(contents.as_bytes().to_vec()),
                 Err(_) => None,
             }
         }
-        Err(_) => None,
+        _ => None,
     };
 
     mc_common::sentry::configure_scope(|scope| {
@@ -67,11 +73,17 @@ fn main() -> Result<(), ConsensusServiceError> {
     log::info!(logger, "Configured minimum fee: {:?}", config.minimum_fee());
 
     // write the sealed block signing key
-    let mut sealed_key_file =
-        File::create(&config.sealed_block_signing_key).expect("Failed to open sealed key file");
-    sealed_key_file
-        .write_all(&sealed_key)
-        .expect("Failed to write sealed key bytes");
+    if let Some(sealed_block_signing_key) = config.sealed_block_signing_key.as_ref() {
+        let mut sealed_key_file =
+            File::create(sealed_block_signing_key).expect("Failed to open sealed key file");
+        sealed_key_file
+            .write_all(&sealed_key)
+            .expect("Failed to write sealed key bytes");
+    }
+
+    if config.observer {
+        log::info!(logger, "Running as an observer");
+    }
 
     setup_ledger_dir(&config.origin_block_path, &config.ledger_path);
 
@@ -88,9 +100,10 @@ fn main() -> Result<(), ConsensusServiceError> {
         log::debug!(logger, "Enclave will be started in production mode");
     }
 
-    let tx_manager = TxManagerImpl::new(
+    let tx_manager = TxManagerImpl::new_with_capacity(
         enclave.clone(),
         DefaultTxManagerUntrustedInterfaces::new(local_ledger.clone()),
+        config.tx_cache_capacity,
         logger.clone(),
     );
 
@@ -109,10 +122,15 @@ fn main() -> Result<(), ConsensusServiceError> {
 
     log::info!(logger, "Listening...");
 
-    consensus_service.wait_for_all_threads()?;
+    // A worker thread which stops on its own leaves the node unable to make
+    // progress, so exit rather than keep serving.
+    if let Err(err) = shutdown_signal.wait_while_ok(|| consensus_service.check_threads()) {
+        log::crit!(logger, "{}", err);
+        return Err(err);
+    }
+    log::info!(logger, "Shutdown requested");
 
-    // Should never get here since our threads are not expected to die
-    panic!("Oh oh, our threads died");
+    consensus_service.shutdown()
 }
 
 fn setup_ledger_dir(config_origin_path: &Option<PathBuf>, ledger_path: &Path) {
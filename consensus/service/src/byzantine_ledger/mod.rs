@@ -13,9 +13,10 @@ mod worker;
 use crate::{
     byzantine_ledger::{task_message::TaskMessage, worker::ByzantineLedgerWorker},
     counters,
+    peer_scores::{PeerKey, PeerScores},
     tx_manager::TxManager,
 };
-use mc_common::{logger::Logger, NodeID, ResponderId};
+use mc_common::{logger::Logger, NodeID};
 use mc_connection::{BlockchainConnection, ConnectionManager};
 use mc_consensus_scp::{scp_log::LoggingScpNode, Node, QuorumSet, ScpNode};
 use mc_crypto_keys::Ed25519Pair;
@@ -28,7 +29,7 @@ use std::{
     path::PathBuf,
     sync::{
         atomic::{AtomicBool, AtomicU64, Ordering},
-        Arc, Mutex,
+        mpsc, Arc, Mutex,
     },
     thread,
     thread::JoinHandle,
@@ -49,6 +50,9 @@ pub struct ByzantineLedger {
     // Handle to a worker thread.
     worker_handle: Option<JoinHandle<()>>,
 
+    // Shared with the worker thread, which drops its reference when it exits.
+    worker_alive: Arc<()>,
+
     // Sender-end of the worker's task queue.
     task_sender: Sender<TaskMessage>,
 
@@ -72,6 +76,9 @@ impl ByzantineLedger {
     /// * `ledger` - The local node's ledger.
     /// * `tx_manager` - TxManager
     /// * `broadcaster` - Broadcaster
+    /// * `peer_scores` - Rate limits and reputation scores of peers.
+    /// * `observer` - If true, follow consensus without nominating values or
+    ///   issuing consensus messages.
     /// * `msg_signer_key` - Signs consensus messages issued by this node.
     /// * `tx_source_urls` - Source URLs for fetching block contents.
     /// * `scp_debug_dir` - If Some, debugging info will be written in this
@@ -88,6 +95,8 @@ impl ByzantineLedger {
         ledger: L,
         tx_manager: Arc<TXM>,
         broadcaster: Arc<Mutex<dyn Broadcast>>,
+        peer_scores: PeerScores,
+        observer: bool,
         msg_signer_key: Arc<Ed25519Pair>,
         tx_source_urls: Vec<String>,
         scp_debug_dir: Option<PathBuf>,
@@ -126,7 +135,9 @@ impl ByzantineLedger {
         let highest_issued_msg = Arc::new(Mutex::new(Option::<ConsensusMsg>::None));
 
         // Start worker thread
+        let worker_alive = Arc::new(());
         let worker_handle = {
+            let thread_alive = worker_alive.clone();
             let ledger_sync_service = LedgerSyncService::new(
                 ledger.clone(),
                 peer_manager.clone(),
@@ -142,6 +153,8 @@ impl ByzantineLedger {
                 peer_manager,
                 tx_manager,
                 broadcaster.clone(),
+                peer_scores,
+                observer,
                 task_receiver,
                 is_behind.clone(),
                 highest_peer_block.clone(),
@@ -152,11 +165,14 @@ impl ByzantineLedger {
             Some(
                 thread::Builder::new()
                     .name(format!("ByzantineLedger{:?}", &node_id))
-                    .spawn(move || loop {
-                        if !worker.tick() {
-                            break;
+                    .spawn(move || {
+                        let _alive = thread_alive;
+                        loop {
+                            if !worker.tick() {
+                                break;
+                            }
+                            thread::sleep(Duration::from_millis(10));
                         }
-                        thread::sleep(Duration::from_millis(10));
                     })
                     .expect("failed spawning ByzantineLedger"),
             )
@@ -164,6 +180,7 @@ impl ByzantineLedger {
 
         Self {
             worker_handle,
+            worker_alive,
             task_sender,
             is_behind,
             highest_peer_block,
@@ -179,16 +196,36 @@ impl ByzantineLedger {
     }
 
     /// Handle consensus messages received from the network.
-    pub fn handle_consensus_msg(
-        &self,
-        consensus_msg: VerifiedConsensusMsg,
-        from_responder_id: ResponderId,
-    ) {
+    pub fn handle_consensus_msg(&self, consensus_msg: VerifiedConsensusMsg, from: PeerKey) {
         self.task_sender
-            .send(TaskMessage::ConsensusMsg(consensus_msg, from_responder_id))
+            .send(TaskMessage::ConsensusMsg(consensus_msg, from))
             .expect("Could not send consensus msg");
     }
 
+    /// Replace the local node's quorum set. The current slot is completed with
+    /// the previous quorum set, and the new one is used from the next slot on.
+    pub fn set_quorum_set(&self, quorum_set: QuorumSet) {
+        self.task_sender
+            .send(TaskMessage::QuorumSet(quorum_set))
+            .expect("Could not send quorum set");
+    }
+
+    /// Let the worker finish the slot in progress, then stop it. Blocks until
+    /// the worker has stopped, which happens no later than `deadline` unless a
+    /// ledger sync batch is in progress. New values are not nominated in the
+    /// meantime.
+    pub fn drain(&self, deadline: Instant) {
+        let (drained_sender, drained_receiver) = mpsc::channel();
+        if self
+            .task_sender
+            .send(TaskMessage::Drain(deadline, drained_sender))
+            .is_ok()
+        {
+            // The worker drops the sender once it has stopped.
+            let _ = drained_receiver.recv();
+        }
+    }
+
     pub fn stop(&mut self) {
         let _ = self.task_sender.send(TaskMessage::StopTrigger);
         self.join();
@@ -200,6 +237,12 @@ impl ByzantineLedger {
         }
     }
 
+    /// Whether the worker thread is still running. It only stops on its own
+    /// once drained, or if its task queue is disconnected.
+    pub fn is_running(&self) -> bool {
+        self.worker_handle.is_some() && Arc::strong_count(&self.worker_alive) > 1
+    }
+
     /// Check if the node is currently behind it's peers.
     pub fn is_behind(&self) -> bool {
         self.is_behind.load(Ordering::SeqCst)
@@ -237,7 +280,7 @@ mod tests {
     use mc_consensus_enclave_mock::ConsensusServiceMockEnclave;
     use mc_consensus_scp::{core_types::Ballot, msg::*, SlotIndex};
     use mc_crypto_keys::{DistinguishedEncoding, Ed25519Private};
-    use mc_ledger_db::Ledger;
+    use mc_ledger_db::{Ledger, LedgerDB};
     use mc_peers::{MockBroadcast, ThreadedBroadcaster};
     use mc_peers_test_utils::MockPeerConnection;
     use mc_transaction_core_test_utils::{
@@ -375,6 +418,8 @@ mod tests {
             ledger.clone(),
             tx_manager.clone(),
             broadcaster,
+            PeerScores::default(),
+            false,
             msg_signer_key.clone(),
             Vec::new(),
             None,
@@ -455,6 +500,8 @@ mod tests {
             ledger.clone(),
             tx_manager.clone(),
             broadcaster,
+            PeerScores::default(),
+            false,
             local_signer_key.clone(),
             Vec::new(),
             None,
@@ -603,7 +650,7 @@ mod tests {
             .unwrap()
             .try_into()
             .unwrap(),
-            node_a.id.responder_id.clone(),
+            node_a.id.responder_id.clone().into(),
         );
 
         byzantine_ledger.handle_consensus_msg(
@@ -625,7 +672,7 @@ mod tests {
             .unwrap()
             .try_into()
             .unwrap(),
-            node_a.id.responder_id,
+            node_a.id.responder_id.into(),
         );
 
         // TODO MC-1055 write a test for this
@@ -697,6 +744,176 @@ mod tests {
         assert!(signature_verification_result.is_ok());
     }
 
+    /// Starts a ByzantineLedger whose peers are node 22 and node 33, and waits
+    /// until it has nominated three client transactions in its first slot.
+    ///
+    /// Returns the ByzantineLedger, its ledger, the peers and the nominated
+    /// values.
+    fn start_nominating(
+        logger: &Logger,
+    ) -> (Arc<ByzantineLedger>, LedgerDB, Vec<PeerConfig>, Vec<TxHash>) {
+        let mut rng: StdRng = SeedableRng::from_seed([209u8; 32]);
+        let peers = get_peers(&[22, 33], &mut rng);
+        let (local_node_id, _, local_signer_key) = get_local_node_config(11);
+        let local_quorum_set =
+            QuorumSet::new_with_node_ids(2, vec![peers[0].id.clone(), peers[1].id.clone()]);
+
+        let mut ledger = create_ledger();
+        let sender = AccountKey::random(&mut rng);
+        initialize_ledger(&mut ledger, 1, &sender, &mut rng);
+
+        let mock_peer = MockPeerConnection::new(
+            peers[0].uri.clone(),
+            local_node_id.clone(),
+            ledger.clone(),
+            10,
+        );
+        let mock_peer_state = mock_peer.state.clone();
+        let peer_manager = ConnectionManager::new(
+            vec![
+                mock_peer,
+                MockPeerConnection::new(
+                    peers[1].uri.clone(),
+                    local_node_id.clone(),
+                    ledger.clone(),
+                    10,
+                ),
+            ],
+            logger.clone(),
+        );
+        let broadcaster = Arc::new(Mutex::new(ThreadedBroadcaster::new(
+            &peer_manager,
+            &mc_peers::ThreadedBroadcasterFibonacciRetryPolicy::default(),
+            logger.clone(),
+        )));
+
+        let tx_manager = Arc::new(TxManagerImpl::new(
+            ConsensusServiceMockEnclave::default(),
+            DefaultTxManagerUntrustedInterfaces::new(ledger.clone()),
+            logger.clone(),
+        ));
+
+        let byzantine_ledger = Arc::new(ByzantineLedger::new(
+            local_node_id,
+            local_quorum_set,
+            peer_manager,
+            ledger.clone(),
+            tx_manager.clone(),
+            broadcaster,
+            PeerScores::default(),
+            false,
+            local_signer_key,
+            Vec::new(),
+            None,
+            logger.clone(),
+        ));
+
+        let block_contents = ledger.get_block_contents(0).unwrap();
+        let tx_hashes: Vec<TxHash> = block_contents.outputs[0..3]
+            .iter()
+            .map(|tx_out| {
+                let recipient = AccountKey::random(&mut rng);
+                let tx = create_transaction(
+                    &mut ledger,
+                    tx_out,
+                    &sender,
+                    &recipient.default_subaddress(),
+                    10,
+                    &mut rng,
+                );
+                tx_manager
+                    .insert(ConsensusServiceMockEnclave::tx_to_tx_context(&tx))
+                    .unwrap()
+            })
+            .collect();
+        byzantine_ledger.push_values(tx_hashes.clone(), Some(Instant::now()));
+
+        // Wait for the nomination to reach the network.
+        let deadline = Instant::now() + Duration::from_secs(60);
+        while mock_peer_state
+            .lock()
+            .expect("Could not lock mock peer state")
+            .msgs
+            .is_empty()
+        {
+            assert!(Instant::now() < deadline, "Values were not nominated");
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        (byzantine_ledger, ledger, peers, tx_hashes)
+    }
+
+    /// A Commit statement from `peer` for `values` in slot 1.
+    fn commit_msg(ledger: &LedgerDB, peer: &PeerConfig, values: &[TxHash]) -> VerifiedConsensusMsg {
+        ConsensusMsg::from_scp_msg(
+            ledger,
+            Msg::new(
+                peer.id.clone(),
+                peer.quorum_set.clone(),
+                1,
+                Topic::Commit(CommitPayload {
+                    B: Ballot::new(100, values),
+                    PN: 77,
+                    CN: 55,
+                    HN: 66,
+                }),
+            ),
+            &peer.signer_key,
+        )
+        .unwrap()
+        .try_into()
+        .unwrap()
+    }
+
+    // Draining in the middle of a slot should let the slot complete, and its
+    // block be committed, before the worker stops.
+    #[test_with_logger]
+    fn test_drain_completes_slot(logger: Logger) {
+        let (byzantine_ledger, ledger, peers, tx_hashes) = start_nominating(&logger);
+        assert_eq!(ledger.num_blocks().unwrap(), 1);
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let drain_thread = {
+            let byzantine_ledger = byzantine_ledger.clone();
+            thread::spawn(move || byzantine_ledger.drain(deadline))
+        };
+
+        // Peers complete the slot while the node is draining.
+        thread::sleep(Duration::from_millis(100));
+        for peer in &peers {
+            byzantine_ledger.handle_consensus_msg(
+                commit_msg(&ledger, peer, &tx_hashes),
+                peer.id.responder_id.clone().into(),
+            );
+        }
+
+        drain_thread.join().unwrap();
+        assert!(Instant::now() < deadline);
+
+        // The slot's block was appended before the worker stopped.
+        assert_eq!(ledger.num_blocks().unwrap(), 2);
+        let block = ledger.get_block(1).unwrap();
+        let signature = ledger.get_block_signature(1).unwrap();
+        assert!(signature.verify(&block).is_ok());
+        assert_eq!(ledger.get_block_contents(1).unwrap().key_images.len(), 3);
+    }
+
+    // A slot which cannot complete should be abandoned at the drain deadline,
+    // leaving the ledger as it was.
+    #[test_with_logger]
+    fn test_drain_deadline(logger: Logger) {
+        let (byzantine_ledger, ledger, _peers, _tx_hashes) = start_nominating(&logger);
+
+        let deadline = Instant::now() + Duration::from_secs(1);
+        byzantine_ledger.drain(deadline);
+        assert!(Instant::now() >= deadline);
+
+        // The ledger can still be read, and has no partial block.
+        assert_eq!(ledger.num_blocks().unwrap(), 1);
+        ledger.get_block(0).unwrap();
+        assert!(ledger.get_block_contents(1).is_err());
+    }
+
     #[test]
     #[ignore]
     // ByzantineLedger should sync its ledger with its peers, and then emit the
@@ -17,8 +17,8 @@ pub struct PendingValues<TXM: TxManager> {
     // Transaction manager instance, used for validating values.
     tx_manager: Arc<TXM>,
 
-    /// We need to store pending values vec so we can process values
-    /// on a first-come first-served basis. However, we want to be able to:
+    /// We need to store pending values vec so we can keep track of the order
+    /// in which values were received. However, we want to be able to:
     /// 1) Efficiently see if we already have a given transaction and ignore
     /// duplicates 2) Track how long each transaction took to externalize.
     ///
@@ -85,6 +85,13 @@ impl<TXM: TxManager> PendingValues<TXM> {
         self.pending_values.iter()
     }
 
+    /// The highest-priority pending values, at most `limit` of them.
+    pub fn prioritized(&self, limit: usize) -> Vec<TxHash> {
+        let mut values = self.tx_manager.prioritize(&self.pending_values);
+        values.truncate(limit);
+        values
+    }
+
     /// Try and get the timestamp associated with a given value.
     pub fn get_timestamp_for_value(&self, tx_hash: &TxHash) -> Option<Instant> {
         self.pending_values_map.get(tx_hash).cloned().flatten()
@@ -247,4 +254,38 @@ mod tests {
             HashSet::from_iter(expected_pending_values),
         );
     }
+
+    #[test]
+    /// Should return at most `limit` values, in the order given by the tx
+    /// manager.
+    fn test_prioritized() {
+        let mut tx_manager = MockTxManager::new();
+
+        // A few test values.
+        let values = vec![TxHash([1u8; 32]), TxHash([2u8; 32]), TxHash([3u8; 32])];
+
+        // All values are considered valid for this test.
+        tx_manager.expect_validate().return_const(Ok(()));
+
+        // The tx manager ranks the values in reverse order.
+        let expected_values = values.clone();
+        tx_manager
+            .expect_prioritize()
+            .withf(move |tx_hashes| tx_hashes == &expected_values[..])
+            .returning(|tx_hashes| tx_hashes.iter().rev().cloned().collect());
+
+        let mut pending_values = PendingValues::new(Arc::new(tx_manager));
+        for tx_hash in &values {
+            assert!(pending_values.push(*tx_hash, None));
+        }
+
+        assert_eq!(
+            pending_values.prioritized(2),
+            vec![values[2].clone(), values[1].clone()]
+        );
+        assert_eq!(
+            pending_values.prioritized(10),
+            vec![values[2].clone(), values[1].clone(), values[0].clone()]
+        );
+    }
 }
@@ -1,9 +1,10 @@
 // Copyright (c) 2018-2021 The MobileCoin Foundation
 
-use mc_common::ResponderId;
+use crate::peer_scores::PeerKey;
+use mc_consensus_scp::QuorumSet;
 use mc_peers::VerifiedConsensusMsg;
 use mc_transaction_core::tx::TxHash;
-use std::time::Instant;
+use std::{sync::mpsc::Sender, time::Instant};
 
 #[derive(Debug)]
 pub enum TaskMessage {
@@ -13,7 +14,14 @@ pub enum TaskMessage {
     Values(Option<Instant>, Vec<TxHash>),
 
     /// SCP Statement.
-    ConsensusMsg(VerifiedConsensusMsg, ResponderId),
+    ConsensusMsg(VerifiedConsensusMsg, PeerKey),
+
+    /// A new quorum set for the local node, used starting with the next slot.
+    QuorumSet(QuorumSet),
+
+    /// Finish the slot in progress, or give up on it at the given deadline,
+    /// then terminate. The sender is dropped once the worker has terminated.
+    Drain(Instant, Sender<()>),
 
     /// Stop trigger, used for notifying the worker thread to terminate.
     StopTrigger,
@@ -6,7 +6,9 @@ use crate::{
         task_message::TaskMessage, IS_BEHIND_GRACE_PERIOD, MAX_PENDING_VALUES_TO_NOMINATE,
     },
     counters,
-    tx_manager::TxManager,
+    peer_scores::{PeerKey, PeerOffense, PeerScores},
+    tx_manager::{TxManager, TxManagerError},
+    TRACER_NAME,
 };
 use mc_common::{
     logger::{log, Logger},
@@ -26,12 +28,14 @@ use mc_peers::{
 };
 use mc_transaction_core::tx::TxHash;
 use mc_util_metered_channel::Receiver;
+use mc_util_telemetry::{global, start_linked_span, Context, KeyValue, Span, TraceContextExt};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::{
     cmp::min,
     collections::{BTreeSet, HashMap},
     sync::{
         atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::Sender,
         Arc, Mutex,
     },
     thread,
@@ -41,6 +45,19 @@ use std::{
 /// Default number of consensus messages to process per batch.
 const CONSENSUS_MSG_BATCH_SIZE: usize = 5;
 
+/// A request to finish the slot in progress and then stop.
+struct Drain {
+    /// When to give up on the slot in progress.
+    deadline: Instant,
+
+    /// The slot that was in progress when the drain was requested.
+    slot_index: SlotIndex,
+
+    /// Dropped along with the worker, which lets the requester know that the
+    /// worker has stopped.
+    _drained: Sender<()>,
+}
+
 pub struct ByzantineLedgerWorker<
     L: Ledger + 'static,
     LS: LedgerSync<SCPNetworkState> + Send + 'static,
@@ -53,6 +70,10 @@ pub struct ByzantineLedgerWorker<
     connection_manager: ConnectionManager<PC>,
     broadcaster: Arc<Mutex<dyn Broadcast>>,
     tx_manager: Arc<TXM>,
+    // Reputation scores of peers, penalized for messages that cannot be processed.
+    peer_scores: PeerScores,
+    // An observer follows consensus but never nominates values or issues consensus messages.
+    observer: bool,
     // A map of responder id to a list of tx hashes that it is unable to provide. This allows us to
     // skip attempting to fetch txs that are bound to fail. A BTreeSet is used to speed up lookups
     // as expect to be doing more lookups than inserts.
@@ -81,7 +102,7 @@ pub struct ByzantineLedgerWorker<
     tasks: Receiver<TaskMessage>,
 
     // Pending scp messages we need to process.
-    pending_consensus_msgs: Vec<(VerifiedConsensusMsg, ResponderId)>,
+    pending_consensus_msgs: Vec<(VerifiedConsensusMsg, PeerKey)>,
 
     // Transactions that this node will attempt to submit to consensus.
     pending_values: PendingValues<TXM>,
@@ -90,6 +111,10 @@ pub struct ByzantineLedgerWorker<
     // scp_node.
     need_nominate: bool,
 
+    // Set when the worker has been asked to finish the current slot and stop. No new values are
+    // nominated while draining.
+    drain: Option<Drain>,
+
     logger: Logger,
 }
 
@@ -110,6 +135,8 @@ impl<
     /// * `connection_manager` - Manages connections to peers.
     /// * `tx_manager` - TxManager
     /// * `broadcaster` - Broadcaster
+    /// * `peer_scores` - Rate limits and reputation scores of peers.
+    /// * `observer` - If true, follow consensus without voting.
     /// * `tasks` - Receiver-end of a queue of task messages for this worker to
     ///   process.
     /// * `is_behind` - Worker sets to true when the local node is behind its
@@ -127,6 +154,8 @@ impl<
         connection_manager: ConnectionManager<PC>,
         tx_manager: Arc<TXM>,
         broadcaster: Arc<Mutex<dyn Broadcast>>,
+        peer_scores: PeerScores,
+        observer: bool,
         tasks: Receiver<TaskMessage>,
         is_behind: Arc<AtomicBool>,
         highest_peer_block: Arc<AtomicU64>,
@@ -147,12 +176,15 @@ impl<
             ledger,
             tx_manager: tx_manager.clone(),
             broadcaster,
+            peer_scores,
+            observer,
             connection_manager,
             logger,
             current_slot_index,
             pending_consensus_msgs: Default::default(),
             pending_values: PendingValues::new(tx_manager),
             need_nominate: false,
+            drain: None,
             network_state,
             ledger_sync_service,
             ledger_sync_state: LedgerSyncState::InSync,
@@ -168,6 +200,11 @@ impl<
             return false;
         }
 
+        if self.is_drained(Instant::now()) {
+            // Drain requested, and there is nothing left to finish.
+            return false;
+        }
+
         // Advance the "sync state" state machine.
         let previous_sync_state = {
             let next_state = self.next_sync_state(Instant::now());
@@ -263,8 +300,8 @@ impl<
         }
         assert!(!self.is_behind.load(Ordering::SeqCst));
 
-        // Nominate values for current slot.
-        if self.need_nominate {
+        // Nominate values for current slot, unless draining.
+        if self.need_nominate && self.drain.is_none() {
             self.propose_pending_values();
         }
 
@@ -336,11 +373,14 @@ impl<
             panic!("Attempted to sync when not behind?");
         };
 
+        let first_synced_block = self.ledger.num_blocks().unwrap();
         self.ledger_sync_state = match self
             .ledger_sync_service
             .attempt_ledger_sync(&self.network_state, num_blocks)
         {
             Ok(()) => {
+                self.record_synced_blocks(first_synced_block);
+
                 // Synced a chunk of blocks, but may still be behind.
                 LedgerSyncState::IsBehind {
                     attempt_sync_at: Instant::now(),
@@ -360,6 +400,25 @@ impl<
         };
     }
 
+    // Record which cached transactions the blocks appended by ledger sync,
+    // starting at `first_synced_block`, contain.
+    fn record_synced_blocks(&self, first_synced_block: u64) {
+        let num_blocks = self.ledger.num_blocks().unwrap();
+        for block_index in first_synced_block..num_blocks {
+            match self.ledger.get_block_contents(block_index) {
+                Ok(block_contents) => self
+                    .tx_manager
+                    .record_synced_block(block_index, &block_contents),
+                Err(err) => log::warn!(
+                    self.logger,
+                    "Could not read synced block {}: {:?}",
+                    block_index,
+                    err
+                ),
+            }
+        }
+    }
+
     // Reads tasks from the task queue.
     // Returns false if the worker has been asked to stop.
     fn receive_tasks(&mut self) -> bool {
@@ -368,6 +427,9 @@ impl<
                 // Transactions submitted by clients. These are assumed to be well-formed, but may
                 // not be valid.
                 TaskMessage::Values(timestamp, new_values) => {
+                    if self.observer || self.drain.is_some() {
+                        continue;
+                    }
                     for tx_hash in new_values {
                         if self.pending_values.push(tx_hash, timestamp) {
                             self.need_nominate = true;
@@ -376,12 +438,38 @@ impl<
                 }
 
                 // SCP Statement
-                TaskMessage::ConsensusMsg(consensus_msg, from_responder_id) => {
+                TaskMessage::ConsensusMsg(consensus_msg, from) => {
                     // Used to detect when we are behind.
                     self.network_state.push(consensus_msg.scp_msg().clone());
 
-                    self.pending_consensus_msgs
-                        .push((consensus_msg, from_responder_id));
+                    self.pending_consensus_msgs.push((consensus_msg, from));
+                }
+
+                // New quorum set. The SCP node keeps the current slot's quorum set, so this
+                // takes effect at the next slot boundary.
+                TaskMessage::QuorumSet(quorum_set) => {
+                    log::info!(
+                        self.logger,
+                        "Quorum set will change after slot {}: {:?}",
+                        self.current_slot_index,
+                        quorum_set
+                    );
+                    self.scp_node.set_quorum_set(quorum_set.clone());
+                    self.network_state.set_local_quorum_set(quorum_set);
+                }
+
+                // Request to finish the current slot, then stop.
+                TaskMessage::Drain(deadline, drained) => {
+                    log::info!(
+                        self.logger,
+                        "Draining: finishing slot {} before stopping.",
+                        self.current_slot_index
+                    );
+                    self.drain = Some(Drain {
+                        deadline,
+                        slot_index: self.current_slot_index,
+                        _drained: drained,
+                    });
                 }
 
                 // Request to stop thread
@@ -399,23 +487,85 @@ impl<
         true
     }
 
+    // Whether a requested drain is complete: the slot that was in progress when it
+    // was requested has ended, this node is not taking part in the current
+    // slot, or the deadline has passed.
+    fn is_drained(&mut self, now: Instant) -> bool {
+        let (deadline, slot_index) = match &self.drain {
+            Some(drain) => (drain.deadline, drain.slot_index),
+            None => return false,
+        };
+
+        if self.current_slot_index > slot_index {
+            log::info!(self.logger, "Drained: slot {} is complete.", slot_index);
+            return true;
+        }
+
+        // A sync batch runs within a single tick, so it has already completed.
+        if self.is_behind.load(Ordering::SeqCst) {
+            log::info!(self.logger, "Drained: ledger sync batch is complete.");
+            return true;
+        }
+
+        let slot_metrics = self.scp_node.get_current_slot_metrics();
+        if slot_metrics.num_voted_nominated == 0 && slot_metrics.bN == 0 {
+            log::info!(
+                self.logger,
+                "Drained: slot {} has not started.",
+                self.current_slot_index
+            );
+            return true;
+        }
+
+        if now >= deadline {
+            log::warn!(
+                self.logger,
+                "Drain deadline passed before slot {} completed.",
+                self.current_slot_index
+            );
+            return true;
+        }
+
+        false
+    }
+
     // Propose pending values for nomination in the current slot.
     fn propose_pending_values(&mut self) {
         assert!(!self.pending_values.is_empty());
 
         // Fairness heuristics:
-        // * Values are proposed in the order that they were received.
+        // * Values are proposed in priority order (fee per byte, then proximity to the
+        //   tombstone block).
         // * Each node limits the total number of values it proposes per slot.
         let values = self
             .pending_values
-            .iter()
-            .take(MAX_PENDING_VALUES_TO_NOMINATE)
-            .cloned()
+            .prioritized(MAX_PENDING_VALUES_TO_NOMINATE);
+
+        // Keep the proposed values cached until the slot completes. A value may
+        // have been evicted since it was prioritized, so only propose those that
+        // are still cached.
+        self.tx_manager.pin(&values);
+        let values: Vec<TxHash> = values
+            .into_iter()
+            .filter(|tx_hash| self.tx_manager.contains(tx_hash))
             .collect();
+        if values.is_empty() {
+            self.need_nominate = false;
+            return;
+        }
+
+        // Link the nomination to the requests that proposed the values.
+        let mut span = start_linked_span(
+            &global::tracer(TRACER_NAME),
+            "nominate",
+            self.tx_manager.span_contexts(&values),
+        );
+        span.set_attribute(KeyValue::new("slot_index", self.current_slot_index as i64));
+        let _trace_guard = Context::current_with_span(span).attach();
 
         let msg_opt = self
             .scp_node
-            .propose_values(values)
+            .propose_values(values.into_iter().collect())
             .expect("nominate failed");
 
         if let Some(msg) = msg_opt {
@@ -432,13 +582,25 @@ impl<
         let current_slot_index = self.current_slot_index;
         let max_externalized_slots = self.scp_node.max_externalized_slots() as u64;
         let oldest_slot_index = current_slot_index.saturating_sub(max_externalized_slots);
-        let (consensus_msgs, future_msgs): (Vec<_>, Vec<_>) = self
+        let peer_scores = self.peer_scores.clone();
+        let (msgs, stale_msgs): (Vec<_>, Vec<_>) = self
             .pending_consensus_msgs
             .drain(..)
-            // We do not perform consensus on the origin block.
-            .filter(|(consensus_msg, _)| consensus_msg.scp_msg().slot_index != 0)
-            .filter(|(consensus_msg, _)| consensus_msg.scp_msg().slot_index >= oldest_slot_index)
+            // Drop messages from peers that were banned after they were queued.
+            .filter(|(_, from)| !peer_scores.is_banned(from))
+            // We do not perform consensus on the origin block, and old slots can no longer be
+            // processed.
             .partition(|(consensus_msg, _)| {
+                consensus_msg.scp_msg().slot_index != 0
+                    && consensus_msg.scp_msg().slot_index >= oldest_slot_index
+            });
+
+        for (_, from) in &stale_msgs {
+            self.penalize(from, PeerOffense::StaleMsg);
+        }
+
+        let (consensus_msgs, future_msgs): (Vec<_>, Vec<_>) =
+            msgs.into_iter().partition(|(consensus_msg, _)| {
                 consensus_msg.scp_msg().slot_index <= current_slot_index
             });
 
@@ -455,24 +617,23 @@ impl<
                 *consensus_msg.prev_block_id() == previous_block_id
             });
 
-        for (consensus_msg, _) in &incompatible_msgs {
+        for (consensus_msg, from) in &incompatible_msgs {
             log::warn!(
                 self.logger,
                 "Msg refers to a different blockchain. Msg {}, previous block ID: {:?}",
                 consensus_msg.scp_msg(),
                 consensus_msg.prev_block_id(),
             );
+            self.penalize(from, PeerOffense::InvalidMsg);
         }
 
         // Process compatible messages in batches.
         for chunk in compatible_msgs.chunks(CONSENSUS_MSG_BATCH_SIZE) {
             // Omit a message if it references a transaction that cannot be obtained.
             let (resolved, failed): (Vec<_>, Vec<_>) =
-                chunk
-                    .iter()
-                    .partition(|(consensus_msg, from_responder_id)| {
-                        self.fetch_missing_txs(consensus_msg.scp_msg(), from_responder_id)
-                    });
+                chunk.iter().partition(|(consensus_msg, from)| {
+                    self.fetch_missing_txs(consensus_msg.scp_msg(), from)
+                });
 
             if !failed.is_empty() {
                 log::warn!(
@@ -482,12 +643,14 @@ impl<
                 );
             }
 
-            // Broadcast resolved messages.
-            for (consensus_msg, from_responder_id) in &resolved {
-                self.broadcaster
-                    .lock()
-                    .expect("mutex poisoned")
-                    .broadcast_consensus_msg(consensus_msg.as_ref(), &from_responder_id);
+            // Broadcast resolved messages. Observers do not relay messages.
+            if !self.observer {
+                for (consensus_msg, from) in &resolved {
+                    self.broadcaster
+                        .lock()
+                        .expect("mutex poisoned")
+                        .broadcast_consensus_msg(consensus_msg.as_ref(), &from.responder_id);
+                }
             }
 
             let scp_msgs: Vec<Msg<_>> = resolved
@@ -509,6 +672,16 @@ impl<
     }
 
     fn complete_current_slot(&mut self, externalized: Vec<TxHash>) {
+        // Link the new block to the requests that proposed its transactions.
+        let mut span = start_linked_span(
+            &global::tracer(TRACER_NAME),
+            "form_block",
+            self.tx_manager.span_contexts(&externalized),
+        );
+        span.set_attribute(KeyValue::new("slot_index", self.current_slot_index as i64));
+        span.set_attribute(KeyValue::new("num_values", externalized.len() as i64));
+        let _trace_guard = Context::current_with_span(span).attach();
+
         // Update pending value processing time metrics.
         for tx_hash in externalized.iter() {
             if let Some(timestamp) = self.pending_values.get_timestamp_for_value(tx_hash) {
@@ -543,18 +716,24 @@ impl<
             .tx_hashes_to_block(&externalized, &parent_block)
             .unwrap_or_else(|e| panic!("Failed to build block from {:?}: {:?}", externalized, e));
 
+        // An observer is not a block signer, so its blocks are appended unsigned.
+        let signature = if self.observer { None } else { Some(signature) };
+
         log::info!(
             self.logger,
-            "Appending block {} to ledger (sig: {}, tx_hashes: {:?}).",
+            "Appending block {} to ledger (sig: {:?}, tx_hashes: {:?}).",
             block.index,
             signature,
             &externalized,
         );
 
         self.ledger
-            .append_block(&block, &block_contents, Some(signature))
+            .append_block(&block, &block_contents, signature)
             .expect("failed appending block");
 
+        self.tx_manager
+            .record_externalized(&externalized, block.index);
+
         counters::TX_EXTERNALIZED_COUNT.inc_by(externalized.len() as i64);
 
         // Update current slot index.
@@ -574,17 +753,24 @@ impl<
             self.tx_manager.remove_expired(index)
         };
 
-        self.pending_values
-            .retain(|tx_hash| !purged_hashes.contains(tx_hash));
+        // Evict low-priority transactions if the cache has grown beyond its
+        // capacity. This happens only between slots, so that no value referenced
+        // by an ongoing slot is evicted.
+        let evicted_hashes = self.tx_manager.evict_over_capacity();
+
+        self.pending_values.retain(|tx_hash| {
+            !purged_hashes.contains(tx_hash) && !evicted_hashes.contains(tx_hash)
+        });
 
         // Drop pending values that are no longer considered valid.
         self.pending_values.clear_invalid_values();
 
         log::info!(
             self.logger,
-            "Number of pending values post cleanup: {} ({} expired)",
+            "Number of pending values post cleanup: {} ({} expired, {} evicted)",
             self.pending_values.len(),
             purged_hashes.len(),
+            evicted_hashes.len(),
         );
 
         // Previous slot metrics.
@@ -613,14 +799,16 @@ impl<
         }
     }
 
-    fn fetch_missing_txs(
-        &mut self,
-        scp_msg: &Msg<TxHash>,
-        from_responder_id: &ResponderId,
-    ) -> bool {
+    fn fetch_missing_txs(&mut self, scp_msg: &Msg<TxHash>, from: &PeerKey) -> bool {
+        let from_responder_id = &from.responder_id;
+
+        // Keep the transactions referenced by the message cached until the slot
+        // completes, so that they are not evicted to make room for new ones.
+        let values: Vec<TxHash> = scp_msg.values().into_iter().collect();
+        self.tx_manager.pin(&values);
+
         // Hashes of transactions that are not currently cached.
-        let missing_hashes: Vec<TxHash> = scp_msg
-            .values()
+        let missing_hashes: Vec<TxHash> = values
             .into_iter()
             .filter(|tx_hash| !self.tx_manager.contains(tx_hash))
             .collect();
@@ -668,23 +856,40 @@ impl<
                             self.ledger.num_blocks().unwrap(),
                             scp_msg.slot_index,
                         );
+                        self.penalize(from, PeerOffense::FailedGetTxs);
+                        return false;
+                    }
+                    // The message cannot be processed without all of its transactions, so
+                    // they are admitted even if the cache is full.
+                    let errors: Vec<TxManagerError> = tx_contexts
+                        .into_par_iter()
+                        .map_with(self.tx_manager.clone(), |tx_manager, tx_context| {
+                            tx_manager.insert_pinned(tx_context)
+                        })
+                        .filter_map(Result::err)
+                        .collect();
+
+                    if !errors.is_empty() {
+                        // Only a malformed transaction is the peer's fault. Others may
+                        // have become stale, e.g. if their key images were just spent.
+                        if errors.iter().any(TxManagerError::is_malformed) {
+                            log::crit!(
+                                self.logger,
+                                "Received malformed transaction(s) from node {}: {:?}",
+                                from_responder_id,
+                                errors,
+                            );
+                            self.penalize(from, PeerOffense::FailedGetTxs);
+                        } else {
+                            log::warn!(
+                                self.logger,
+                                "Unable to cache transaction(s) from node {}: {:?}",
+                                from_responder_id,
+                                errors,
+                            );
+                        }
                         return false;
                     }
-                    tx_contexts.into_par_iter().for_each_with(
-                        (self.tx_manager.clone(), self.logger.clone()),
-                        move |(tx_manager, logger), tx_context| match tx_manager.insert(tx_context)
-                        {
-                            Ok(_) => {}
-                            Err(err) => {
-                                log::crit!(
-                                    logger,
-                                    "Received malformed transaction from node {}: {:?}",
-                                    from_responder_id,
-                                    err,
-                                );
-                            }
-                        },
-                    );
                 }
                 Err(RetryError::Operation {
                     error: PeerError::TxHashesNotInCache(tx_hashes),
@@ -695,6 +900,8 @@ impl<
                         .entry(from_responder_id.clone())
                         .or_insert_with(BTreeSet::default);
                     entry.extend(tx_hashes);
+                    // The peer may have legitimately dropped the transactions, e.g. if
+                    // they expired or were evicted.
                     return false;
                 }
                 Err(err) => {
@@ -713,8 +920,26 @@ impl<
         true
     }
 
+    /// Record misbehavior by a peer.
+    fn penalize(&self, peer: &PeerKey, offense: PeerOffense) {
+        if self.peer_scores.penalize(peer, offense) {
+            log::warn!(
+                self.logger,
+                "Temporarily ignoring peer {:?} due to misbehavior ({:?})",
+                peer,
+                offense
+            );
+        }
+    }
+
     /// Broadcast a consensus message issued by this node.
     fn issue_consensus_message(&mut self, msg: Msg<TxHash>) -> Result<(), &'static str> {
+        // An observer's statements only drive its local SCP node, and are never sent to
+        // the network.
+        if self.observer {
+            return Ok(());
+        }
+
         let consensus_msg =
             ConsensusMsg::from_scp_msg(&self.ledger, msg, self.msg_signer_key.as_ref())
                 .map_err(|_| "Failed creating ConsensusMsg")?;
@@ -786,6 +1011,7 @@ mod tests {
     use mc_connection::ConnectionManager;
     use mc_consensus_scp::{
         msg::{NominatePayload, Topic::Nominate},
+        slot::{Phase, SlotMetrics},
         MockScpNode, Msg, QuorumSet,
     };
     use mc_crypto_keys::Ed25519Pair;
@@ -793,18 +1019,21 @@ mod tests {
     use mc_ledger_sync::{LedgerSyncError, MockLedgerSync, SCPNetworkState};
     use mc_peers::{ConsensusMsg, MockBroadcast, VerifiedConsensusMsg};
     use mc_peers_test_utils::MockPeerConnection;
-    use mc_transaction_core::{tx::TxHash, validation::TransactionValidationError, Block};
+    use mc_transaction_core::{
+        tx::TxHash, validation::TransactionValidationError, Block, BlockContents,
+    };
     use mc_util_metered_channel::{Receiver, Sender};
     use mc_util_metrics::OpMetrics;
     use mockall::predicate::eq;
     use rand::rngs::StdRng;
     use rand_core::SeedableRng;
     use std::{
+        collections::BTreeSet,
         convert::TryFrom,
         ops::Add,
         sync::{
             atomic::{AtomicBool, AtomicU64},
-            Arc, Mutex,
+            mpsc, Arc, Mutex,
         },
         time::{Duration, Instant},
     };
@@ -891,6 +1120,8 @@ mod tests {
             connection_manager,
             Arc::new(tx_manager),
             Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
             task_receiver,
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
@@ -941,6 +1172,8 @@ mod tests {
             connection_manager,
             Arc::new(tx_manager),
             Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
             task_receiver,
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
@@ -1058,6 +1291,8 @@ mod tests {
             connection_manager,
             Arc::new(tx_manager),
             Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
             task_receiver,
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
@@ -1089,6 +1324,71 @@ mod tests {
         }
     }
 
+    #[test_with_logger]
+    // Should record the cached transactions in each block appended by ledger
+    // sync.
+    fn test_sync_next_blocks_records_synced_blocks(logger: Logger) {
+        let (node_id, _local_node_uri, msg_signer_key) = get_local_node_config(11);
+        let mut rng: StdRng = SeedableRng::from_seed([97u8; 32]);
+        let peers = get_peers(&[22, 33], &mut rng);
+        let quorum_set =
+            QuorumSet::new_with_node_ids(2, vec![peers[0].id.clone(), peers[1].id.clone()]);
+
+        let (scp_node, _ledger, mut ledger_sync, mut tx_manager, broadcast) =
+            get_mocks(&node_id, &quorum_set, 12);
+        let connection_manager = get_connection_manager(&node_id, &peers, &logger);
+        let (_task_sender, task_receiver) = get_channel();
+
+        // Ledger sync appends blocks 12 and 13.
+        let mut ledger = MockLedger::new();
+        let mut num_blocks = vec![14, 12];
+        ledger
+            .expect_num_blocks()
+            .times(2)
+            .returning(move || Ok(num_blocks.pop().unwrap()));
+        ledger
+            .expect_get_block_contents()
+            .times(2)
+            .returning(|_| Ok(BlockContents::default()));
+        ledger_sync
+            .expect_attempt_ledger_sync()
+            .return_once(|_, _| Ok(()));
+
+        tx_manager
+            .expect_record_synced_block()
+            .with(eq(12), eq(BlockContents::default()))
+            .times(1)
+            .return_const(());
+        tx_manager
+            .expect_record_synced_block()
+            .with(eq(13), eq(BlockContents::default()))
+            .times(1)
+            .return_const(());
+
+        let mut worker = ByzantineLedgerWorker::new(
+            Box::new(scp_node),
+            msg_signer_key,
+            ledger,
+            ledger_sync,
+            connection_manager,
+            Arc::new(tx_manager),
+            Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
+            task_receiver,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            logger,
+        );
+
+        worker.ledger_sync_state = LedgerSyncState::IsBehind {
+            attempt_sync_at: Instant::now(),
+            num_sync_attempts: 0,
+        };
+        worker.sync_next_blocks(100);
+    }
+
     #[test_with_logger]
     // Should correctly update `ledger_sync_state` if syncing blocks fails.
     fn test_sync_next_blocks_failure(logger: Logger) {
@@ -1117,6 +1417,8 @@ mod tests {
             connection_manager,
             Arc::new(tx_manager),
             Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
             task_receiver,
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
@@ -1209,6 +1511,8 @@ mod tests {
             connection_manager,
             Arc::new(tx_manager),
             Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
             task_receiver,
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
@@ -1240,7 +1544,7 @@ mod tests {
         task_sender
             .send(TaskMessage::ConsensusMsg(
                 verified_consensus_msg,
-                responder_id,
+                responder_id.into(),
             ))
             .unwrap();
 
@@ -1251,6 +1555,195 @@ mod tests {
         assert_eq!(worker.pending_consensus_msgs.len(), 1);
     }
 
+    #[test_with_logger]
+    // Should hand a new quorum set to the SCP node.
+    fn test_receive_tasks_quorum_set(logger: Logger) {
+        let (node_id, _local_node_uri, msg_signer_key) = get_local_node_config(11);
+        let mut rng: StdRng = SeedableRng::from_seed([97u8; 32]);
+        let peers = get_peers(&[22, 33, 44], &mut rng);
+        let quorum_set =
+            QuorumSet::new_with_node_ids(2, vec![peers[0].id.clone(), peers[1].id.clone()]);
+        let new_quorum_set = QuorumSet::new_with_node_ids(
+            2,
+            vec![
+                peers[0].id.clone(),
+                peers[1].id.clone(),
+                peers[2].id.clone(),
+            ],
+        );
+
+        let num_blocks = 12;
+        let (mut scp_node, ledger, ledger_sync, tx_manager, broadcast) =
+            get_mocks(&node_id, &quorum_set, num_blocks);
+        scp_node
+            .expect_set_quorum_set()
+            .with(eq(new_quorum_set.clone()))
+            .times(1)
+            .return_const(());
+
+        let connection_manager = get_connection_manager(&node_id, &peers, &logger);
+        let (task_sender, task_receiver) = get_channel();
+
+        let mut worker = ByzantineLedgerWorker::new(
+            Box::new(scp_node),
+            msg_signer_key,
+            ledger,
+            ledger_sync,
+            connection_manager,
+            Arc::new(tx_manager),
+            Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
+            task_receiver,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            logger,
+        );
+
+        task_sender
+            .send(TaskMessage::QuorumSet(new_quorum_set))
+            .unwrap();
+        assert_eq!(worker.receive_tasks(), true);
+    }
+
+    #[test_with_logger]
+    // A draining worker should finish the slot in progress, without nominating new
+    // values, and stop once the slot completes or the deadline passes.
+    fn test_drain(logger: Logger) {
+        let (node_id, _local_node_uri, msg_signer_key) = get_local_node_config(11);
+        let mut rng: StdRng = SeedableRng::from_seed([97u8; 32]);
+        let peers = get_peers(&[22, 33], &mut rng);
+        let quorum_set =
+            QuorumSet::new_with_node_ids(2, vec![peers[0].id.clone(), peers[1].id.clone()]);
+
+        let num_blocks = 12;
+        // tx_manager expects no calls: values received while draining are ignored.
+        let (mut scp_node, ledger, ledger_sync, tx_manager, broadcast) =
+            get_mocks(&node_id, &quorum_set, num_blocks);
+
+        // The local node has voted in the current slot.
+        scp_node
+            .expect_get_current_slot_metrics()
+            .returning(|| SlotMetrics {
+                phase: Phase::Prepare,
+                num_voted_nominated: 1,
+                num_accepted_nominated: 1,
+                num_confirmed_nominated: 1,
+                cur_nomination_round: 1,
+                bN: 1,
+            });
+
+        let connection_manager = get_connection_manager(&node_id, &peers, &logger);
+        let (task_sender, task_receiver) = get_channel();
+
+        let mut worker = ByzantineLedgerWorker::new(
+            Box::new(scp_node),
+            msg_signer_key,
+            ledger,
+            ledger_sync,
+            connection_manager,
+            Arc::new(tx_manager),
+            Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
+            task_receiver,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            logger,
+        );
+
+        let now = Instant::now();
+        assert!(!worker.is_drained(now));
+
+        let deadline = now + Duration::from_secs(10);
+        let (drained_sender, drained_receiver) = mpsc::channel();
+        task_sender
+            .send(TaskMessage::Drain(deadline, drained_sender))
+            .unwrap();
+        task_sender
+            .send(TaskMessage::Values(Some(now), vec![TxHash([1u8; 32])]))
+            .unwrap();
+        assert_eq!(worker.receive_tasks(), true);
+        assert!(worker.pending_values.is_empty());
+
+        // The slot is still in progress.
+        assert!(!worker.is_drained(now));
+
+        // Drained once the deadline passes...
+        assert!(worker.is_drained(deadline));
+
+        // ...or once the slot completes.
+        worker.current_slot_index += 1;
+        assert!(worker.is_drained(now));
+
+        // The requester is notified once the worker is gone.
+        assert_eq!(drained_receiver.try_recv(), Err(mpsc::TryRecvError::Empty));
+        drop(worker);
+        assert_eq!(
+            drained_receiver.recv_timeout(Duration::from_secs(1)),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test_with_logger]
+    // A draining worker that is not taking part in the current slot should stop
+    // right away.
+    fn test_drain_idle(logger: Logger) {
+        let (node_id, _local_node_uri, msg_signer_key) = get_local_node_config(11);
+        let mut rng: StdRng = SeedableRng::from_seed([97u8; 32]);
+        let peers = get_peers(&[22, 33], &mut rng);
+        let quorum_set =
+            QuorumSet::new_with_node_ids(2, vec![peers[0].id.clone(), peers[1].id.clone()]);
+
+        let num_blocks = 12;
+        let (mut scp_node, ledger, ledger_sync, tx_manager, broadcast) =
+            get_mocks(&node_id, &quorum_set, num_blocks);
+        scp_node
+            .expect_get_current_slot_metrics()
+            .returning(|| SlotMetrics {
+                phase: Phase::NominatePrepare,
+                num_voted_nominated: 0,
+                num_accepted_nominated: 0,
+                num_confirmed_nominated: 0,
+                cur_nomination_round: 0,
+                bN: 0,
+            });
+
+        let connection_manager = get_connection_manager(&node_id, &peers, &logger);
+        let (task_sender, task_receiver) = get_channel();
+
+        let mut worker = ByzantineLedgerWorker::new(
+            Box::new(scp_node),
+            msg_signer_key,
+            ledger,
+            ledger_sync,
+            connection_manager,
+            Arc::new(tx_manager),
+            Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
+            task_receiver,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicU64::new(0)),
+            Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            logger,
+        );
+
+        let now = Instant::now();
+        let (drained_sender, _drained_receiver) = mpsc::channel();
+        task_sender
+            .send(TaskMessage::Drain(
+                now + Duration::from_secs(10),
+                drained_sender,
+            ))
+            .unwrap();
+
+        // The worker stops on the next tick.
+        assert_eq!(worker.tick(), false);
+    }
+
     /// Constructs a VerifiedConsensusMsg.
     ///
     /// # Arguments
@@ -1296,12 +1789,38 @@ mod tests {
         // `validate` will be called one for each pushed value.
         tx_manager.expect_validate().return_const(Ok(()));
 
-        // Up to MAX_PENDING_VALUES_TO_NOMINATE values should be proposed to the
-        // scp_node.
+        // Pending values are proposed in priority order.
+        tx_manager
+            .expect_prioritize()
+            .times(1)
+            .returning(|tx_hashes| tx_hashes.iter().rev().cloned().collect());
+
+        // The proposed values are pinned until the slot completes.
+        tx_manager
+            .expect_pin()
+            .times(1)
+            .withf(|tx_hashes| tx_hashes.len() == MAX_PENDING_VALUES_TO_NOMINATE)
+            .return_const(());
+        tx_manager.expect_contains().return_const(true);
+
+        // The span contexts of the proposed values are used to link the nomination
+        // to the requests that proposed them.
+        tx_manager
+            .expect_span_contexts()
+            .times(1)
+            .withf(|tx_hashes| tx_hashes.len() <= MAX_PENDING_VALUES_TO_NOMINATE)
+            .return_const(Vec::new());
+
+        // The MAX_PENDING_VALUES_TO_NOMINATE highest-priority values should be
+        // proposed to the scp_node.
+        let highest_priority: BTreeSet<TxHash> = (MAX_PENDING_VALUES_TO_NOMINATE
+            ..MAX_PENDING_VALUES_TO_NOMINATE * 2)
+            .map(|i| TxHash([i as u8; 32]))
+            .collect();
         scp_node
             .expect_propose_values()
             .times(1)
-            .withf(|values| values.len() <= MAX_PENDING_VALUES_TO_NOMINATE)
+            .withf(move |values| *values == highest_priority)
             .return_const(Ok(None));
 
         let mut worker = ByzantineLedgerWorker::new(
@@ -1312,6 +1831,8 @@ mod tests {
             connection_manager,
             Arc::new(tx_manager),
             Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            false,
             task_receiver,
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
@@ -1331,6 +1852,64 @@ mod tests {
         worker.propose_pending_values();
     }
 
+    #[test_with_logger]
+    // An observer should ignore values submitted to it, and should not broadcast
+    // or record consensus messages issued by its SCP node.
+    fn test_observer(logger: Logger) {
+        let (node_id, _local_node_uri, msg_signer_key) = get_local_node_config(11);
+        let mut rng: StdRng = SeedableRng::from_seed([97u8; 32]);
+        let peers = get_peers(&[22, 33], &mut rng);
+        let quorum_set =
+            QuorumSet::new_with_node_ids(2, vec![peers[0].id.clone(), peers[1].id.clone()]);
+
+        let num_blocks = 12;
+        // The mock broadcaster has no expectations, so broadcasting would panic.
+        let (scp_node, ledger, ledger_sync, tx_manager, broadcast) =
+            get_mocks(&node_id, &quorum_set, num_blocks);
+        let connection_manager = get_connection_manager(&node_id, &peers, &logger);
+        let (task_sender, task_receiver) = get_channel();
+        let highest_issued_msg = Arc::new(Mutex::new(Option::<ConsensusMsg>::None));
+
+        let mut worker = ByzantineLedgerWorker::new(
+            Box::new(scp_node),
+            msg_signer_key,
+            ledger,
+            ledger_sync,
+            connection_manager,
+            Arc::new(tx_manager),
+            Arc::new(Mutex::new(broadcast)),
+            PeerScores::default(),
+            true,
+            task_receiver,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicU64::new(0)),
+            highest_issued_msg.clone(),
+            logger,
+        );
+
+        task_sender
+            .send(TaskMessage::Values(
+                Some(Instant::now()),
+                vec![TxHash([1u8; 32]), TxHash([2u8; 32])],
+            ))
+            .unwrap();
+        assert!(worker.receive_tasks());
+        assert!(worker.pending_values.is_empty());
+        assert!(!worker.need_nominate);
+
+        let msg: Msg<TxHash, NodeID> = Msg {
+            sender_id: node_id.clone(),
+            slot_index: num_blocks,
+            quorum_set: quorum_set.clone(),
+            topic: Nominate(NominatePayload {
+                X: Default::default(),
+                Y: Default::default(),
+            }),
+        };
+        assert_eq!(worker.issue_consensus_message(msg), Ok(()));
+        assert!(highest_issued_msg.lock().unwrap().is_none());
+    }
+
     // TODO: test process_consensus_msgs
 
     // TODO: test complete_current_slot
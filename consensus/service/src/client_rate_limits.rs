@@ -0,0 +1,356 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Per-client rate limits for the client API.
+//!
+//! Clients are identified by their authenticated username or, when client
+//! authentication is disabled, by their address. Each client has a separate
+//! budget for proposing transactions and for fetching blocks, so that a single
+//! client cannot monopolize the enclave, the transactions cache or the ledger.
+
+use crate::counters;
+use grpcio::RpcContext;
+use mc_common::HashMap;
+use mc_util_grpc::ANONYMOUS_USER;
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Default number of transactions a client may propose per second.
+pub const DEFAULT_PROPOSE_TX_RATE_LIMIT: RateLimit = RateLimit::new(10, 50);
+
+/// Default number of `GetBlocks` calls a client may make per second.
+pub const DEFAULT_GET_BLOCKS_RATE_LIMIT: RateLimit = RateLimit::new(20, 100);
+
+/// Number of tracked clients above which clients with a full budget are
+/// forgotten. Forgetting them is harmless, since a new client starts with a
+/// full budget.
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+
+/// A client API call with its own budget.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ClientCall {
+    /// `ConsensusClientAPI::ClientTxPropose`.
+    ProposeTx,
+
+    /// `BlockchainAPI::GetBlocks`.
+    GetBlocks,
+}
+
+/// A budget of calls: a client may make up to `burst` calls at once, and the
+/// budget refills at `per_sec` calls per second.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    /// Calls allowed per second. Zero means unlimited.
+    pub per_sec: u32,
+
+    /// Largest number of calls allowed at once.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Create a new RateLimit.
+    pub const fn new(per_sec: u32, burst: u32) -> Self {
+        Self { per_sec, burst }
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.per_sec == 0
+    }
+
+    /// The budget of a client which has not made any calls.
+    fn max_tokens(&self) -> f64 {
+        self.burst.max(1) as f64
+    }
+}
+
+/// The remaining budget of a client for one kind of call.
+#[derive(Debug)]
+struct Budget {
+    /// Number of calls the client may currently make (token bucket).
+    tokens: f64,
+
+    /// When `tokens` was last updated.
+    updated_at: Instant,
+}
+
+/// The budgets of every tracked client.
+#[derive(Debug)]
+struct Budgets {
+    /// The budget of each client for each kind of call.
+    entries: HashMap<(String, ClientCall), Budget>,
+
+    /// Number of entries at which idle clients are next forgotten.
+    forget_at: usize,
+}
+
+impl Default for Budgets {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::default(),
+            forget_at: MAX_TRACKED_CLIENTS,
+        }
+    }
+}
+
+/// Rate limits of client API calls. Cloning shares the underlying state.
+#[derive(Clone)]
+pub struct ClientRateLimits {
+    budgets: Arc<Mutex<Budgets>>,
+
+    /// Budget for proposing transactions.
+    propose_tx: RateLimit,
+
+    /// Budget for fetching blocks.
+    get_blocks: RateLimit,
+}
+
+impl ClientRateLimits {
+    /// Create a new ClientRateLimits.
+    ///
+    /// # Arguments
+    /// * `propose_tx` - Budget of each client for proposing transactions.
+    /// * `get_blocks` - Budget of each client for `GetBlocks` calls.
+    pub fn new(propose_tx: RateLimit, get_blocks: RateLimit) -> Self {
+        Self {
+            budgets: Arc::new(Mutex::new(Budgets::default())),
+            propose_tx,
+            get_blocks,
+        }
+    }
+
+    /// Check whether the caller of an RPC may make `call`, and count it
+    /// against their budget.
+    ///
+    /// # Arguments
+    /// * `ctx` - The RPC context, used to identify anonymous callers.
+    /// * `username` - The username the caller authenticated as.
+    /// * `call` - The call being made.
+    ///
+    /// Returns how long the caller should wait before retrying if they are
+    /// over budget.
+    pub fn admit_rpc(
+        &self,
+        ctx: &RpcContext,
+        username: &str,
+        call: ClientCall,
+    ) -> Result<(), Duration> {
+        self.admit(&client_id(username, &ctx.peer()), call)
+    }
+
+    /// Check whether `client` may make `call`, and count it against their
+    /// budget.
+    pub fn admit(&self, client: &str, call: ClientCall) -> Result<(), Duration> {
+        self.admit_at(client, call, Instant::now())
+    }
+
+    fn lock(&self) -> MutexGuard<Budgets> {
+        self.budgets.lock().expect("mutex poisoned")
+    }
+
+    fn limit(&self, call: ClientCall) -> RateLimit {
+        match call {
+            ClientCall::ProposeTx => self.propose_tx,
+            ClientCall::GetBlocks => self.get_blocks,
+        }
+    }
+
+    fn admit_at(&self, client: &str, call: ClientCall, now: Instant) -> Result<(), Duration> {
+        let limit = self.limit(call);
+        if limit.is_unlimited() {
+            return Ok(());
+        }
+
+        let mut budgets = self.lock();
+        let key = (client.to_owned(), call);
+        if !budgets.entries.contains_key(&key) && budgets.entries.len() >= budgets.forget_at {
+            self.forget_idle(&mut budgets, now);
+        }
+
+        let budget = budgets.entries.entry(key).or_insert_with(|| Budget {
+            tokens: limit.max_tokens(),
+            updated_at: now,
+        });
+        Self::refill(budget, limit, now);
+
+        if budget.tokens < 1.0 {
+            match call {
+                ClientCall::ProposeTx => counters::CLIENT_TX_PROPOSE_RATE_LIMITED.inc(),
+                ClientCall::GetBlocks => counters::CLIENT_GET_BLOCKS_RATE_LIMITED.inc(),
+            }
+            let missing = 1.0 - budget.tokens;
+            return Err(Duration::from_secs_f64(missing / limit.per_sec as f64));
+        }
+
+        budget.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// Forget clients whose budget has refilled completely.
+    ///
+    /// This visits every client, so the next pass waits until the number of
+    /// clients has doubled, or is over the limit again. Its cost is then
+    /// spread over the clients added in between, even if none were idle.
+    fn forget_idle(&self, budgets: &mut Budgets, now: Instant) {
+        budgets.entries.retain(|(_, call), budget| {
+            let limit = self.limit(*call);
+            Self::refill(budget, limit, now);
+            budget.tokens < limit.max_tokens()
+        });
+        budgets.forget_at = MAX_TRACKED_CLIENTS.max(2 * budgets.entries.len());
+    }
+
+    /// Refill a budget for the time elapsed since it was last updated.
+    fn refill(budget: &mut Budget, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(budget.updated_at);
+        budget.tokens =
+            (budget.tokens + elapsed.as_secs_f64() * limit.per_sec as f64).min(limit.max_tokens());
+        budget.updated_at = now;
+    }
+}
+
+impl Default for ClientRateLimits {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROPOSE_TX_RATE_LIMIT, DEFAULT_GET_BLOCKS_RATE_LIMIT)
+    }
+}
+
+/// Identify a client by their username, or by their address if they did not
+/// authenticate. The port is ignored, since a client can open connections
+/// from any number of ports.
+fn client_id(username: &str, peer: &str) -> String {
+    if !username.is_empty() && username != ANONYMOUS_USER {
+        return format!("user:{}", username);
+    }
+    // gRPC peers look like `ipv4:1.2.3.4:5678` or `ipv6:[::1]:5678`.
+    let address = match peer.rfind(':') {
+        Some(index) if peer[..index].contains(':') => &peer[..index],
+        _ => peer,
+    };
+    format!("peer:{}", address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Should allow a burst of calls, and refill the budget over time.
+    fn test_rate_limit() {
+        let limits = ClientRateLimits::new(RateLimit::new(10, 20), RateLimit::new(0, 0));
+        let now = Instant::now();
+
+        for _ in 0..20 {
+            assert_eq!(limits.admit_at("a", ClientCall::ProposeTx, now), Ok(()));
+        }
+        let retry_after = limits
+            .admit_at("a", ClientCall::ProposeTx, now)
+            .unwrap_err();
+        assert_eq!(retry_after, Duration::from_millis(100));
+
+        // Other clients have their own budget.
+        assert_eq!(limits.admit_at("b", ClientCall::ProposeTx, now), Ok(()));
+
+        // Half a second later, five more calls are allowed.
+        let later = now + Duration::from_millis(500);
+        for _ in 0..5 {
+            assert_eq!(limits.admit_at("a", ClientCall::ProposeTx, later), Ok(()));
+        }
+        assert!(limits.admit_at("a", ClientCall::ProposeTx, later).is_err());
+    }
+
+    #[test]
+    // Each kind of call should have its own budget.
+    fn test_separate_budgets() {
+        let limits = ClientRateLimits::new(RateLimit::new(1, 1), RateLimit::new(1, 2));
+        let now = Instant::now();
+
+        assert_eq!(limits.admit_at("a", ClientCall::ProposeTx, now), Ok(()));
+        assert!(limits.admit_at("a", ClientCall::ProposeTx, now).is_err());
+
+        assert_eq!(limits.admit_at("a", ClientCall::GetBlocks, now), Ok(()));
+        assert_eq!(limits.admit_at("a", ClientCall::GetBlocks, now), Ok(()));
+        assert!(limits.admit_at("a", ClientCall::GetBlocks, now).is_err());
+    }
+
+    #[test]
+    // A rate of zero should disable the limit.
+    fn test_unlimited() {
+        let limits = ClientRateLimits::new(RateLimit::new(0, 0), RateLimit::new(0, 0));
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert_eq!(limits.admit_at("a", ClientCall::ProposeTx, now), Ok(()));
+        }
+        assert!(limits.lock().entries.is_empty());
+    }
+
+    #[test]
+    // Clients with a full budget should be forgotten once too many clients are
+    // tracked.
+    fn test_forget_idle() {
+        let limits = ClientRateLimits::new(RateLimit::new(10, 10), RateLimit::new(0, 0));
+        let now = Instant::now();
+        {
+            let mut budgets = limits.lock();
+            for i in 0..MAX_TRACKED_CLIENTS {
+                budgets.entries.insert(
+                    (i.to_string(), ClientCall::ProposeTx),
+                    Budget {
+                        tokens: if i == 0 { 0.0 } else { 10.0 },
+                        updated_at: now,
+                    },
+                );
+            }
+        }
+
+        assert_eq!(limits.admit_at("new", ClientCall::ProposeTx, now), Ok(()));
+        let budgets = limits.lock();
+        assert_eq!(budgets.entries.len(), 2);
+        assert!(budgets
+            .entries
+            .contains_key(&("0".to_owned(), ClientCall::ProposeTx)));
+        assert_eq!(budgets.forget_at, MAX_TRACKED_CLIENTS);
+    }
+
+    #[test]
+    // When no client is idle, forgetting should wait until the number of
+    // clients has doubled, instead of visiting every client on each new one.
+    fn test_forget_idle_is_amortized() {
+        let limits = ClientRateLimits::new(RateLimit::new(10, 10), RateLimit::new(0, 0));
+        let now = Instant::now();
+        {
+            let mut budgets = limits.lock();
+            for i in 0..MAX_TRACKED_CLIENTS {
+                budgets.entries.insert(
+                    (i.to_string(), ClientCall::ProposeTx),
+                    Budget {
+                        tokens: 0.0,
+                        updated_at: now,
+                    },
+                );
+            }
+        }
+
+        assert_eq!(limits.admit_at("new", ClientCall::ProposeTx, now), Ok(()));
+        assert_eq!(limits.lock().forget_at, 2 * MAX_TRACKED_CLIENTS);
+
+        // Once every budget has refilled, the next pass only happens when the
+        // threshold is reached.
+        let later = now + Duration::from_secs(10);
+        assert_eq!(
+            limits.admit_at("newer", ClientCall::ProposeTx, later),
+            Ok(())
+        );
+        assert_eq!(limits.lock().entries.len(), MAX_TRACKED_CLIENTS + 2);
+    }
+
+    #[test]
+    fn test_client_id() {
+        assert_eq!(client_id("alice", "ipv4:1.2.3.4:5678"), "user:alice");
+        assert_eq!(
+            client_id(ANONYMOUS_USER, "ipv4:1.2.3.4:5678"),
+            "peer:ipv4:1.2.3.4"
+        );
+        assert_eq!(client_id("", "ipv6:[::1]:5678"), "peer:ipv6:[::1]");
+    }
+}
@@ -2,7 +2,7 @@
 
 //! Configuration parameters for the Consensus Service application.
 
-use mc_attest_core::ProviderId;
+use mc_attest_core::{AttestationPolicy, ProviderId, Verifier};
 use mc_common::{HashMap, HashSet, NodeID, ResponderId};
 use mc_consensus_scp::{QuorumSet, QuorumSetMember};
 use mc_crypto_keys::{DistinguishedEncoding, Ed25519Pair, Ed25519Private};
@@ -65,6 +65,18 @@ pub struct Config {
     #[structopt(long)]
     pub admin_listen_uri: Option<AdminUri>,
 
+    /// Enables the SetConfigJson admin RPC, which changes the quorum set and
+    /// broadcast peers, for callers presenting Authorization tokens generated
+    /// with the provided hex-encoded 32 bytes shared secret. Without it, the
+    /// network config can only be changed by restarting.
+    #[structopt(long, env = "MC_ADMIN_AUTH_TOKEN_SECRET", parse(try_from_str=hex::FromHex::from_hex))]
+    pub admin_auth_token_secret: Option<[u8; 32]>,
+
+    /// Maximal admin authentication token lifetime, in seconds (only relevant
+    /// when --admin-auth-token-secret is used).
+    #[structopt(long, default_value = "3600", parse(try_from_str=parse_duration_in_seconds))]
+    pub admin_auth_token_max_lifetime: Duration,
+
     /// The location to write the externalized blocks for the ledger.
     #[structopt(long, parse(from_os_str))]
     pub ledger_path: PathBuf,
@@ -77,9 +89,16 @@ pub struct Config {
     #[structopt(long, parse(from_os_str))]
     pub scp_debug_dump: Option<PathBuf>,
 
-    /// Path to the sealed block signing key
-    #[structopt(long, parse(from_os_str))]
-    pub sealed_block_signing_key: PathBuf,
+    /// Path to the sealed block signing key. Not used by observers.
+    #[structopt(long, parse(from_os_str), required_unless = "observer")]
+    pub sealed_block_signing_key: Option<PathBuf>,
+
+    /// Run as an observer: follow consensus and serve the blockchain API,
+    /// without nominating values, issuing consensus messages or accepting
+    /// transactions from clients. Other nodes do not need to include an
+    /// observer in their quorum sets.
+    #[structopt(long)]
+    pub observer: bool,
 
     /// Enables authenticating client requests using Authorization tokens using
     /// the provided hex-encoded 32 bytes shared secret.
@@ -99,6 +118,61 @@ pub struct Config {
     /// Allow extreme (>= 1MOB, <= 0.000_000_01 MOB).
     #[structopt(long)]
     pub allow_any_fee: bool,
+
+    /// Maximum number of well-formed transactions to keep in the transactions
+    /// cache. Must be non-zero. When the cache is full, a new transaction
+    /// replaces the lowest-priority (by fee per byte) transaction if it
+    /// outranks it, and is rejected otherwise. Transactions needed by the
+    /// current slot are always accepted, and the cache is brought back within
+    /// capacity once the slot completes.
+    #[structopt(long, default_value = "10000", parse(try_from_str=parse_tx_cache_capacity))]
+    pub tx_cache_capacity: usize,
+
+    /// Number of transactions a single client may propose per second, on
+    /// average. Clients are identified by their authenticated username, or
+    /// their address when client authentication is disabled. Zero disables
+    /// the limit.
+    #[structopt(long, default_value = "10")]
+    pub client_propose_tx_rate_limit: u32,
+
+    /// Number of transactions a single client may propose at once.
+    #[structopt(long, default_value = "50")]
+    pub client_propose_tx_burst: u32,
+
+    /// Number of GetBlocks calls a single client may make per second, on
+    /// average. Zero disables the limit.
+    #[structopt(long, default_value = "20")]
+    pub client_get_blocks_rate_limit: u32,
+
+    /// Number of GetBlocks calls a single client may make at once.
+    #[structopt(long, default_value = "100")]
+    pub client_get_blocks_burst: u32,
+
+    /// Maximum number of consensus messages accepted from a single peer per
+    /// second. Messages claiming the same responder ID but arriving from
+    /// different hosts are limited separately.
+    #[structopt(long, default_value = "100")]
+    pub peer_msg_rate_limit: u32,
+
+    /// Misbehavior score above which a peer's consensus messages are ignored
+    /// for --peer-ban-duration seconds. Invalid messages, messages for stale
+    /// slots and failing to provide transactions increase a peer's score.
+    #[structopt(long, default_value = "100")]
+    pub peer_score_threshold: u32,
+
+    /// How long to ignore a misbehaving peer, in seconds.
+    #[structopt(long, default_value = "60", parse(try_from_str=parse_duration_in_seconds))]
+    pub peer_ban_duration: Duration,
+
+    /// How long to wait for the slot in progress to complete when shutting
+    /// down on SIGTERM or SIGINT, in seconds.
+    #[structopt(long, default_value = "30", parse(try_from_str=parse_duration_in_seconds))]
+    pub shutdown_timeout: Duration,
+
+    /// Optional attestation policy (.toml or .json) which peer enclaves must
+    /// satisfy, in addition to the consensus enclave's own checks.
+    #[structopt(long, parse(try_from_str=AttestationPolicy::load))]
+    pub attestation_policy: Option<AttestationPolicy>,
 }
 
 /// Decodes an Ed25519 private key.
@@ -119,6 +193,14 @@ fn parse_duration_in_seconds(src: &str) -> Result<Duration, std::num::ParseIntEr
     Ok(Duration::from_secs(u64::from_str(src)?))
 }
 
+/// Parses a transactions cache capacity, which must be non-zero.
+fn parse_tx_cache_capacity(src: &str) -> Result<usize, String> {
+    match usize::from_str(src).map_err(|err| err.to_string())? {
+        0 => Err("tx cache capacity must be non-zero".to_string()),
+        capacity => Ok(capacity),
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub struct NetworkConfig {
     /// The set of nodes which you trust to validate transactions.
@@ -136,35 +218,28 @@ pub struct NetworkConfig {
 
 impl NetworkConfig {
     pub fn quorum_set(&self) -> QuorumSet {
+        self.try_quorum_set()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Resolve the quorum set into node IDs, using the node IDs of the
+    /// broadcast and known peers.
+    pub fn try_quorum_set(&self) -> Result<QuorumSet, String> {
         if !self.quorum_set.is_valid() {
-            panic!("invalid quorum set: {:?}", self.quorum_set);
+            return Err(format!("invalid quorum set: {:?}", self.quorum_set));
         }
 
-        let mut peer_map: HashMap<ResponderId, NodeID> = self
-            .broadcast_peers
-            .iter()
-            .cloned()
-            .map(|uri| {
-                (
-                    uri.responder_id().unwrap_or_else(|e| {
-                        panic!("unable to get responder_id for {}: {:?}", uri, e)
-                    }),
-                    uri.node_id()
-                        .unwrap_or_else(|e| panic!("unable to get node_id for {}: {:?}", uri, e)),
-                )
-            })
-            .collect();
+        let mut peer_map: HashMap<ResponderId, NodeID> = HashMap::default();
+        for uri in self.broadcast_peers.iter() {
+            let (responder_id, node_id) = Self::peer_ids(uri)?;
+            peer_map.insert(responder_id, node_id);
+        }
 
         if let Some(known_peers) = self.known_peers.as_ref() {
             for uri in known_peers.iter() {
-                let responder_id = uri
-                    .responder_id()
-                    .unwrap_or_else(|e| panic!("unable to get responder_id for {}: {:?}", uri, e));
-                let node_id = uri
-                    .node_id()
-                    .unwrap_or_else(|e| panic!("unable to get node_id for {}: {:?}", uri, e));
+                let (responder_id, node_id) = Self::peer_ids(uri)?;
                 if peer_map.get(&responder_id).unwrap_or(&node_id) != &node_id {
-                    panic!("node id mismatch for {}", responder_id);
+                    return Err(format!("node id mismatch for {}", responder_id));
                 } else {
                     peer_map.insert(responder_id, node_id);
                 }
@@ -178,30 +253,87 @@ impl NetworkConfig {
         self.broadcast_peers.clone()
     }
 
+    /// Sanity-check the network configuration:
+    /// - The quorum set must be valid, and each of its members must be a
+    ///   broadcast or known peer.
+    /// - Our responder ID should not appear in `broadcast_peers` or
+    ///   `known_peers`. This also ensures it is not part of the quorum set.
+    /// - Each responder ID is unique.
+    /// - We should have at least one source of transactions, if we have any
+    ///   peers configured.
+    ///
+    /// # Arguments
+    /// * `local_responder_id` - The local node's peer responder ID.
+    pub fn validate(&self, local_responder_id: &ResponderId) -> Result<(), String> {
+        self.try_quorum_set()?;
+
+        let peer_uris = self
+            .broadcast_peers
+            .iter()
+            .chain(self.known_peers.iter().flatten());
+        let mut spotted_responder_ids = HashSet::default();
+        for peer_uri in peer_uris {
+            let responder_id = peer_uri
+                .responder_id()
+                .map_err(|e| format!("failed getting responder id for {:?}: {:?}", peer_uri, e))?;
+
+            if local_responder_id == &responder_id {
+                return Err(format!(
+                    "Our peer responder id ({}) should not appear in broadcast_peers or known_peers!",
+                    responder_id
+                ));
+            }
+
+            if !spotted_responder_ids.insert(responder_id.clone()) {
+                return Err(format!(
+                    "Duplicate responder_id {} found in network configuration",
+                    responder_id
+                ));
+            }
+        }
+
+        if !self.broadcast_peers.is_empty() && self.tx_source_urls.is_empty() {
+            return Err("Network configuration is missing tx_source_urls".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Get the (ResponderId, NodeID) of a peer.
+    fn peer_ids(uri: &PeerUri) -> Result<(ResponderId, NodeID), String> {
+        let responder_id = uri
+            .responder_id()
+            .map_err(|e| format!("unable to get responder_id for {}: {:?}", uri, e))?;
+        let node_id = uri
+            .node_id()
+            .map_err(|e| format!("unable to get node_id for {}: {:?}", uri, e))?;
+        Ok((responder_id, node_id))
+    }
+
     // Convert a QuorumSet<ResponderId> -> QuorumSet<NodeID> based on a
     // ResponderID -> NodeID map.
     fn resolve_quorum_set(
         src: &QuorumSet<ResponderId>,
         peer_map: &HashMap<ResponderId, NodeID>,
-    ) -> QuorumSet<NodeID> {
+    ) -> Result<QuorumSet<NodeID>, String> {
         let mut new_members = Vec::with_capacity(src.members.len());
         for member in src.members.iter() {
             let new_member = match member {
                 QuorumSetMember::Node(responder_id) => QuorumSetMember::Node(
                     peer_map
                         .get(&responder_id)
-                        .unwrap_or_else(|| {
-                            panic!("Unknown responder_id {} in quorum set", responder_id)
-                        })
+                        .ok_or_else(|| {
+                            format!("Unknown responder_id {} in quorum set", responder_id)
+                        })?
                         .clone(),
                 ),
                 QuorumSetMember::InnerSet(qs_config) => {
-                    QuorumSetMember::InnerSet(Self::resolve_quorum_set(&qs_config, peer_map))
+                    QuorumSetMember::InnerSet(Self::resolve_quorum_set(&qs_config, peer_map)?)
                 }
             };
             new_members.push(new_member);
         }
-        QuorumSet::new(src.threshold, new_members)
+        Ok(QuorumSet::new(src.threshold, new_members))
     }
 }
 
@@ -214,6 +346,16 @@ impl Config {
         }
     }
 
+    /// Get the verifier peer verification reports must satisfy, if an
+    /// attestation policy was given.
+    pub fn peer_verifier(&self) -> Option<Verifier> {
+        self.attestation_policy.as_ref().map(|policy| {
+            policy
+                .verifier()
+                .expect("Attestation policy was validated when loaded")
+        })
+    }
+
     /// Get the configured minimum fee.
     pub fn minimum_fee(&self) -> Result<Option<u64>, String> {
         if let Some(fee) = self.minimum_fee {
@@ -253,40 +395,9 @@ impl Config {
                 ),
             };
 
-        // Sanity tests:
-        // - Our responder ID should not appear in `broadcast_peers` or `known_peers`.
-        //   This also ensures it is not part of the quorum set.
-        // - Each responder ID is unique.
-        let peer_uris = network
-            .broadcast_peers
-            .iter()
-            .chain(network.known_peers.iter().flatten());
-        let mut spotted_responder_ids = HashSet::default();
-        for peer_uri in peer_uris {
-            let responder_id = peer_uri.responder_id().unwrap_or_else(|e| {
-                panic!("failed getting responder id for {:?}: {:?}", peer_uri, e)
-            });
-
-            if self.peer_responder_id == responder_id {
-                panic!(
-                    "Our peer responder id ({}) should not appear in broadcast_peers or known_peers!",
-                    responder_id
-                );
-            }
-
-            if !spotted_responder_ids.insert(responder_id.clone()) {
-                panic!(
-                    "Duplicate responder_id {} found in network configuration",
-                    responder_id
-                );
-            }
-        }
-
-        // Sanity test: We should have at least one source of transactions, if we have
-        // any peers configured.
-        if !network.broadcast_peers.is_empty() && network.tx_source_urls.is_empty() {
-            panic!("Network configuration is missing tx_source_urls");
-        }
+        network
+            .validate(&self.peer_responder_id)
+            .unwrap_or_else(|err| panic!("{}", err));
 
         // Success.
         network
@@ -448,14 +559,27 @@ mod tests {
             peer_listen_uri: PeerUri::from_str("insecure-mcp://0.0.0.0:8081/").unwrap(),
             client_listen_uri: ClientUri::from_str("insecure-mc://0.0.0.0:3223/").unwrap(),
             admin_listen_uri: Some(AdminUri::from_str("insecure-mca://0.0.0.0:9090/").unwrap()),
+            admin_auth_token_secret: None,
+            admin_auth_token_max_lifetime: Duration::from_secs(60),
             ledger_path: PathBuf::default(),
             scp_debug_dump: None,
             origin_block_path: None,
-            sealed_block_signing_key: PathBuf::default(),
+            sealed_block_signing_key: Some(PathBuf::default()),
+            observer: false,
             client_auth_token_secret: None,
             client_auth_token_max_lifetime: Duration::from_secs(60),
             minimum_fee: None,
             allow_any_fee: false,
+            tx_cache_capacity: 10000,
+            client_propose_tx_rate_limit: 10,
+            client_propose_tx_burst: 50,
+            client_get_blocks_rate_limit: 20,
+            client_get_blocks_burst: 100,
+            peer_msg_rate_limit: 100,
+            peer_score_threshold: 100,
+            peer_ban_duration: Duration::from_secs(60),
+            shutdown_timeout: Duration::from_secs(30),
+            attestation_policy: None,
         };
 
         assert_eq!(
@@ -505,14 +629,27 @@ mod tests {
             peer_listen_uri: PeerUri::from_str("mcp://0.0.0.0:8443/?tls-chain=./public/attest/test_certs/selfsigned_mobilecoin.crt&tls-key=./public/attest/test_certs/selfsigned_mobilecoin.key").unwrap(),
             client_listen_uri: ClientUri::from_str("insecure-mc://0.0.0.0:3223/").unwrap(),
             admin_listen_uri: Some(AdminUri::from_str("insecure-mca://0.0.0.0:9090/").unwrap()),
+            admin_auth_token_secret: None,
+            admin_auth_token_max_lifetime: Duration::from_secs(60),
             ledger_path: PathBuf::default(),
             scp_debug_dump: None,
             origin_block_path: None,
-            sealed_block_signing_key: PathBuf::default(),
+            sealed_block_signing_key: Some(PathBuf::default()),
+            observer: false,
             client_auth_token_secret: None,
             client_auth_token_max_lifetime: Duration::from_secs(60),
             minimum_fee: None,
             allow_any_fee: false,
+            tx_cache_capacity: 10000,
+            client_propose_tx_rate_limit: 10,
+            client_propose_tx_burst: 50,
+            client_get_blocks_rate_limit: 20,
+            client_get_blocks_burst: 100,
+            peer_msg_rate_limit: 100,
+            peer_score_threshold: 100,
+            peer_ban_duration: Duration::from_secs(60),
+            shutdown_timeout: Duration::from_secs(30),
+            attestation_policy: None,
         };
 
         assert_eq!(
@@ -549,6 +686,44 @@ mod tests {
         );
     }
 
+    #[test]
+    // Should reject network configurations that cannot be applied.
+    fn test_network_config_validate() {
+        let input_json: &str = r#"{
+            "broadcast_peers": [
+                "insecure-mcp://0.0.0.0:8082?consensus-msg-key=MCowBQYDK2VwAyEA_ii3rCch5qhMbLZ2vVgpQr1iTrq1BBN2-i0mMPuAJhQ=",
+                "insecure-mcp://0.0.0.0:8083?consensus-msg-key=MCowBQYDK2VwAyEA9C-J6AUm9XnSjrGEhplQpp_jMPNwIxBovFJrJRXtoVA="
+            ],
+            "tx_source_urls": ["file:///tmp/dump"],
+            "quorum_set": { "threshold": 2, "members": [
+                { "type": "Node", "args": "0.0.0.0:8082" },
+                { "type": "Node", "args": "0.0.0.0:8083" }
+            ] }
+        }"#;
+        let network: NetworkConfig = serde_json::from_str(input_json).expect("failed parsing json");
+        let local_responder_id = ResponderId::from_str("0.0.0.0:8081").unwrap();
+        assert!(network.validate(&local_responder_id).is_ok());
+
+        // Our own responder id should not be a peer.
+        let peer_responder_id = ResponderId::from_str("0.0.0.0:8082").unwrap();
+        assert!(network.validate(&peer_responder_id).is_err());
+
+        // The threshold cannot exceed the number of members.
+        let mut invalid_threshold = network.clone();
+        invalid_threshold.quorum_set.threshold = 3;
+        assert!(invalid_threshold.validate(&local_responder_id).is_err());
+
+        // Quorum set members must be peers.
+        let mut unknown_member = network.clone();
+        unknown_member.broadcast_peers.pop();
+        assert!(unknown_member.validate(&local_responder_id).is_err());
+
+        // Peers require a transaction source.
+        let mut no_tx_sources = network;
+        no_tx_sources.tx_source_urls.clear();
+        assert!(no_tx_sources.validate(&local_responder_id).is_err());
+    }
+
     #[test]
     /// Should successfully decode an Ed25519 private key.
     fn test_keypair_from_base64() {
@@ -557,4 +732,14 @@ mod tests {
 
         assert!(keypair_from_base64(private_key).is_ok());
     }
+
+    #[test]
+    /// A zero tx cache capacity should be rejected.
+    fn test_parse_tx_cache_capacity() {
+        assert_eq!(parse_tx_cache_capacity("10000"), Ok(10000));
+        assert_eq!(parse_tx_cache_capacity("1"), Ok(1));
+        assert!(parse_tx_cache_capacity("0").is_err());
+        assert!(parse_tx_cache_capacity("-1").is_err());
+        assert!(parse_tx_cache_capacity("many").is_err());
+    }
 }
@@ -6,15 +6,17 @@ use crate::{
     api::{AttestedApiService, BlockchainApiService, ClientApiService, PeerApiService},
     background_work_queue::BackgroundWorkQueue,
     byzantine_ledger::ByzantineLedger,
-    config::Config,
+    client_rate_limits::{ClientRateLimits, RateLimit},
+    config::{Config, NetworkConfig},
     counters,
     peer_keepalive::PeerKeepalive,
+    peer_scores::{PeerKey, PeerScores},
     tx_manager::TxManager,
 };
 use base64::{encode_config, URL_SAFE};
 use displaydoc::Display;
 use futures::executor::block_on;
-use grpcio::{EnvBuilder, Environment, Server, ServerBuilder};
+use grpcio::{EnvBuilder, Environment, RpcStatus, RpcStatusCode, Server, ServerBuilder};
 use mc_attest_api::attest_grpc::create_attested_api;
 use mc_attest_enclave_api::{ClientSession, PeerSession};
 use mc_attest_net::RaClient;
@@ -33,15 +35,19 @@ use mc_sgx_report_cache_untrusted::{Error as ReportCacheError, ReportCacheThread
 use mc_transaction_core::tx::TxHash;
 use mc_util_grpc::{
     AdminServer, AnonymousAuthenticator, Authenticator, BuildInfoService,
-    ConnectionUriGrpcioServer, GetConfigJsonFn, HealthCheckStatus, HealthService,
-    TokenAuthenticator,
+    ConnectionUriGrpcioServer, GetConfigJsonFn, GetStatusJsonFn, HealthCheckStatus, HealthService,
+    ReflectionService, ServiceHealthCheckCallback, SetConfigJsonFn, TokenAuthenticator,
 };
 use mc_util_uri::{ConnectionUri, ConsensusPeerUriApi};
 use once_cell::sync::OnceCell;
+use protobuf::descriptor::FileDescriptorProto;
 use serde_json::json;
 use std::{
     env,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
     time::Instant,
 };
 
@@ -60,6 +66,8 @@ pub enum ConsensusServiceError {
     BackgroundWorkQueueStop(String),
     /// Report cache error: `{0}`
     ReportCache(ReportCacheError),
+    /// Thread stopped unexpectedly: `{0}`
+    ThreadStopped(String),
 }
 impl From<ReportCacheError> for ConsensusServiceError {
     fn from(src: ReportCacheError) -> Self {
@@ -68,12 +76,12 @@ impl From<ReportCacheError> for ConsensusServiceError {
 }
 
 /// A consensus message relayed by the broadcast layer. In addition to the
-/// consensus message itself, it includes the node ID the message was received
+/// consensus message itself, it includes the peer the message was received
 /// from. Note that this could be different from the node ID that initiated the
 /// message due to relaying.
 pub struct IncomingConsensusMsg {
     /// The broadcast-layer sender.
-    pub from_responder_id: ResponderId,
+    pub from: PeerKey,
 
     /// The message we received from the network.
     pub consensus_msg: VerifiedConsensusMsg,
@@ -95,6 +103,9 @@ pub struct ConsensusService<
     TXM: TxManager + Clone + Send + Sync + 'static,
 > {
     config: Config,
+    // The network configuration. Initially loaded from `config`, it can be replaced at runtime
+    // through the admin API.
+    network: Arc<RwLock<NetworkConfig>>,
     local_node_id: NodeID,
     enclave: E,
     ledger_db: LedgerDB,
@@ -107,6 +118,10 @@ pub struct ConsensusService<
     consensus_msgs_from_network: BackgroundWorkQueue<IncomingConsensusMsg>,
 
     peer_manager: ConnectionManager<PeerConnection<E>>,
+    // Responder IDs of the peers we accept consensus messages from.
+    known_responder_ids: Arc<RwLock<Vec<ResponderId>>>,
+    // Rate limits and reputation scores of peers.
+    peer_scores: PeerScores,
     // This mutex is required because ThreadedBroadcaster API cannot be used concurrently,
     // the LRU cache requires exclusive access, among other reasons.
     // The contention for this is (at time of writing), (one) ByzantineLedger worker thread,
@@ -118,6 +133,11 @@ pub struct ConsensusService<
     peer_keepalive: Option<Arc<PeerKeepalive>>,
     // GRPC client requests authenticator
     client_authenticator: Arc<dyn Authenticator + Send + Sync>,
+    // Authenticator for admin requests changing the network config. None when
+    // such changes are disabled.
+    admin_authenticator: Option<Arc<dyn Authenticator + Send + Sync>>,
+    // Per-client rate limits of client API calls.
+    client_rate_limits: ClientRateLimits,
 
     admin_rpc_server: Option<AdminServer>,
     consensus_rpc_server: Option<Server>,
@@ -125,6 +145,8 @@ pub struct ConsensusService<
     // Option is only here because we need a way to drop the ByzantineLedger without mutex,
     // if we want to implement Stop as currently concieved
     byzantine_ledger: Option<Arc<OnceCell<ByzantineLedger>>>,
+    // Set once a graceful shutdown has begun, after which user requests are no longer served.
+    shutting_down: Arc<AtomicBool>,
 }
 
 impl<
@@ -155,9 +177,11 @@ impl<
 
         let local_node_id = config.node_id();
 
+        let network = config.network();
+        let peer_verifier = config.peer_verifier();
+
         // Peers
-        let peers: Vec<PeerConnection<E>> = config
-            .network()
+        let peers: Vec<PeerConnection<E>> = network
             .broadcast_peers()
             .into_iter()
             .map(|peer_uri| {
@@ -165,6 +189,7 @@ impl<
                     enclave.clone(),
                     local_node_id.clone(),
                     peer_uri,
+                    peer_verifier.clone(),
                     env.clone(),
                     logger.clone(),
                 )
@@ -172,6 +197,14 @@ impl<
             .collect();
 
         let peer_manager = ConnectionManager::new(peers, logger.clone());
+        let known_responder_ids = Arc::new(RwLock::new(peer_manager.responder_ids()));
+        counters::BROADCAST_PEERS.set(peer_manager.len() as i64);
+
+        let peer_scores = PeerScores::new(
+            config.peer_msg_rate_limit,
+            config.peer_score_threshold,
+            config.peer_ban_duration,
+        );
 
         // Broadcaster
         let broadcaster = Arc::new(Mutex::new(ThreadedBroadcaster::new(
@@ -187,7 +220,17 @@ impl<
             logger.clone(),
         )));
 
-        // Authenticator
+        // Authenticators
+        let admin_authenticator = config.admin_auth_token_secret.as_ref().map(
+            |shared_secret| -> Arc<dyn Authenticator + Sync + Send> {
+                Arc::new(TokenAuthenticator::new(
+                    *shared_secret,
+                    config.admin_auth_token_max_lifetime,
+                    time_provider.clone(),
+                ))
+            },
+        );
+
         let client_authenticator: Arc<dyn Authenticator + Sync + Send> =
             if let Some(shared_secret) = config.client_auth_token_secret.as_ref() {
                 Arc::new(TokenAuthenticator::new(
@@ -199,9 +242,21 @@ impl<
                 Arc::new(AnonymousAuthenticator::default())
             };
 
+        let client_rate_limits = ClientRateLimits::new(
+            RateLimit::new(
+                config.client_propose_tx_rate_limit,
+                config.client_propose_tx_burst,
+            ),
+            RateLimit::new(
+                config.client_get_blocks_rate_limit,
+                config.client_get_blocks_burst,
+            ),
+        );
+
         // Return
         Self {
             config,
+            network: Arc::new(RwLock::new(network)),
             local_node_id,
             enclave,
             ledger_db,
@@ -214,15 +269,20 @@ impl<
             consensus_msgs_from_network,
 
             peer_manager,
+            known_responder_ids,
+            peer_scores,
             broadcaster,
             tx_manager,
             peer_keepalive,
             client_authenticator,
+            admin_authenticator,
+            client_rate_limits,
 
             admin_rpc_server: None,
             consensus_rpc_server: None,
             user_rpc_server: None,
             byzantine_ledger: Some(Arc::new(Default::default())),
+            shutting_down: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -294,6 +354,59 @@ impl<
         Ok(())
     }
 
+    /// Stop the node gracefully. The health service reports NOT_SERVING and
+    /// new client transactions are rejected right away, the slot in progress
+    /// is given until `config.shutdown_timeout` to complete, and in-flight
+    /// client calls are completed before the servers stop.
+    pub fn shutdown(&mut self) -> Result<(), ConsensusServiceError> {
+        log::info!(
+            self.logger,
+            "Shutting down, draining for up to {:?}...",
+            self.config.shutdown_timeout
+        );
+        let deadline = Instant::now() + self.config.shutdown_timeout;
+        self.shutting_down.store(true, Ordering::SeqCst);
+
+        // Peers keep exchanging consensus messages with us until the slot in
+        // progress completes.
+        if let Some(byzantine_ledger) = self
+            .byzantine_ledger
+            .as_ref()
+            .and_then(|ledger| ledger.get())
+        {
+            byzantine_ledger.drain(deadline);
+        }
+
+        self.stop()?;
+        log::info!(self.logger, "Shutdown complete.");
+        Ok(())
+    }
+
+    /// Check that the worker threads started by `start` are still running.
+    /// They only exit on their own if something went wrong, in which case the
+    /// node can no longer make progress.
+    pub fn check_threads(&self) -> Result<(), ConsensusServiceError> {
+        if !self.consensus_msgs_from_network.is_running() {
+            return Err(ConsensusServiceError::ThreadStopped(
+                "consensus_msgs_from_network".to_string(),
+            ));
+        }
+
+        if let Some(byzantine_ledger) = self
+            .byzantine_ledger
+            .as_ref()
+            .and_then(|ledger| ledger.get())
+        {
+            if !byzantine_ledger.is_running() {
+                return Err(ConsensusServiceError::ThreadStopped(
+                    "byzantine_ledger".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub fn wait_for_all_threads(&mut self) -> Result<(), ConsensusServiceError> {
         log::debug!(
@@ -314,46 +427,23 @@ impl<
         );
 
         // Setup GRPC services.
-        let enclave = Arc::new(self.enclave.clone());
-
-        let client_service =
-            consensus_client_grpc::create_consensus_client_api(ClientApiService::new(
-                enclave.clone(),
-                self.create_scp_client_value_sender_fn(),
-                Arc::new(self.ledger_db.clone()),
-                self.tx_manager.clone(),
-                self.create_is_serving_user_requests_fn(),
-                self.client_authenticator.clone(),
-                self.logger.clone(),
-            ));
-
-        let attested_service = create_attested_api(AttestedApiService::<ClientSession>::new(
-            enclave,
+        let mut blockchain_api_service = BlockchainApiService::new(
+            self.ledger_db.clone(),
             self.client_authenticator.clone(),
             self.logger.clone(),
-        ));
-
+            self.config
+                .minimum_fee()
+                .expect("Could not read minimum fee"),
+        );
+        blockchain_api_service.set_rate_limits(self.client_rate_limits.clone());
         let blockchain_service =
-            consensus_common_grpc::create_blockchain_api(BlockchainApiService::new(
-                self.ledger_db.clone(),
-                self.client_authenticator.clone(),
-                self.logger.clone(),
-                self.config
-                    .minimum_fee()
-                    .expect("Could not read minimum fee"),
-            ));
+            consensus_common_grpc::create_blockchain_api(blockchain_api_service);
 
-        let is_serving_user_requests = self.create_is_serving_user_requests_fn();
-        let health_check_callback: Arc<dyn Fn(&str) -> HealthCheckStatus + Sync + Send> =
-            Arc::new(move |_| {
-                if is_serving_user_requests() {
-                    HealthCheckStatus::SERVING
-                } else {
-                    HealthCheckStatus::NOT_SERVING
-                }
-            });
-        let health_service =
-            HealthService::new(Some(health_check_callback), self.logger.clone()).into_service();
+        let health_service = HealthService::new(
+            Some(self.create_health_check_callback()),
+            self.logger.clone(),
+        )
+        .into_service();
         let build_info_service = BuildInfoService::new(self.logger.clone()).into_service();
 
         // Start GRPC server.
@@ -363,13 +453,52 @@ impl<
                 .build(),
         );
 
-        let server_builder = ServerBuilder::new(env)
-            .register_service(client_service)
+        let mut server_builder = ServerBuilder::new(env)
             .register_service(blockchain_service)
             .register_service(health_service)
-            .register_service(attested_service)
-            .register_service(build_info_service)
-            .bind_using_uri(&self.config.client_listen_uri, self.logger.clone());
+            .register_service(build_info_service);
+        let mut services = vec![
+            "build_info.BuildInfoApi",
+            "consensus_common.BlockchainAPI",
+            "grpc.health.v1.Health",
+        ];
+
+        // Observers do not accept transactions from clients, so they only serve the
+        // blockchain API.
+        if !self.config.observer {
+            let enclave = Arc::new(self.enclave.clone());
+
+            let client_service =
+                consensus_client_grpc::create_consensus_client_api(ClientApiService::new(
+                    enclave.clone(),
+                    self.create_scp_client_value_sender_fn(),
+                    Arc::new(self.ledger_db.clone()),
+                    self.tx_manager.clone(),
+                    self.create_is_serving_user_requests_fn(),
+                    self.client_authenticator.clone(),
+                    self.client_rate_limits.clone(),
+                    self.logger.clone(),
+                ));
+
+            let attested_service = create_attested_api(AttestedApiService::<ClientSession>::new(
+                enclave,
+                self.client_authenticator.clone(),
+                self.logger.clone(),
+            ));
+
+            server_builder = server_builder
+                .register_service(client_service)
+                .register_service(attested_service);
+            services.extend(&["attest.AttestedApi", "consensus_client.ConsensusClientAPI"]);
+        }
+
+        let reflection_service =
+            ReflectionService::new(&services, api_file_descriptors(), self.logger.clone())
+                .into_service();
+        server_builder = server_builder.register_service(reflection_service);
+
+        let server_builder =
+            server_builder.bind_using_uri(&self.config.client_listen_uri, self.logger.clone());
 
         let mut server = server_builder.build().unwrap();
         server.start();
@@ -386,6 +515,26 @@ impl<
 
     fn start_admin_rpc_server(&mut self) -> Result<(), ConsensusServiceError> {
         if let Some(admin_listen_uri) = self.config.admin_listen_uri.as_ref() {
+            // Network config changes are only accepted from authenticated callers.
+            let (set_config_json, set_config_json_authenticator) = match self
+                .admin_authenticator
+                .as_ref()
+            {
+                Some(authenticator) => (
+                    Some(self.create_set_config_json_fn()),
+                    authenticator.clone(),
+                ),
+                None => {
+                    log::info!(
+                        self.logger,
+                        "Network config changes are disabled, pass --admin-auth-token-secret to enable them"
+                    );
+                    let authenticator: Arc<dyn Authenticator + Send + Sync> =
+                        Arc::new(AnonymousAuthenticator::default());
+                    (None, authenticator)
+                }
+            };
+
             self.admin_rpc_server = Some(
                 AdminServer::start(
                     Some(self.env.clone()),
@@ -393,6 +542,10 @@ impl<
                     "Consensus Service".to_owned(),
                     self.config.peer_responder_id.to_string(),
                     Some(self.create_get_config_json_fn()),
+                    set_config_json,
+                    set_config_json_authenticator,
+                    Some(self.create_get_status_json_fn()),
+                    Some(self.create_health_check_callback()),
                     self.logger.clone(),
                 )
                 .expect("Failed starting admin grpc server"),
@@ -445,18 +598,34 @@ impl<
             self.consensus_msgs_from_network.get_sender_fn(),
             self.create_scp_client_value_sender_fn(),
             get_highest_scp_message_fn,
-            self.peer_manager.responder_ids(),
+            self.known_responder_ids.clone(),
+            self.peer_scores.clone(),
             self.logger.clone(),
         ));
 
-        let attested_service = create_attested_api(AttestedApiService::<PeerSession>::new(
-            enclave,
-            peer_authenticator,
-            self.logger.clone(),
-        ));
+        let attested_service = create_attested_api(
+            AttestedApiService::<PeerSession>::new(
+                enclave,
+                peer_authenticator,
+                self.logger.clone(),
+            )
+            .with_peer_verifier(self.config.peer_verifier()),
+        );
 
         let health_service = HealthService::new(None, self.logger.clone()).into_service();
         let build_info_service = BuildInfoService::new(self.logger.clone()).into_service();
+        let reflection_service = ReflectionService::new(
+            &[
+                "attest.AttestedApi",
+                "build_info.BuildInfoApi",
+                "consensus_common.BlockchainAPI",
+                "consensus_peer.ConsensusPeerAPI",
+                "grpc.health.v1.Health",
+            ],
+            api_file_descriptors(),
+            self.logger.clone(),
+        )
+        .into_service();
 
         // Start GRPC server.
         let server_builder = ServerBuilder::new(self.env.clone())
@@ -465,6 +634,7 @@ impl<
             .register_service(health_service)
             .register_service(attested_service)
             .register_service(build_info_service)
+            .register_service(reflection_service)
             .bind_using_uri(&self.config.peer_listen_uri, self.logger.clone());
 
         let mut server = server_builder.build().unwrap();
@@ -482,6 +652,8 @@ impl<
     fn start_byzantine_ledger_service(&mut self) -> Result<(), ConsensusServiceError> {
         log::info!(self.logger, "Starting ByzantineLedger service.");
 
+        let network = self.network.read().expect("lock poisoned").clone();
+
         let byzantine_ledger_arc = self
             .byzantine_ledger
             .as_mut()
@@ -489,13 +661,15 @@ impl<
         if byzantine_ledger_arc
             .set(ByzantineLedger::new(
                 self.local_node_id.clone(),
-                self.config.network().quorum_set(),
+                network.quorum_set(),
                 self.peer_manager.clone(),
                 self.ledger_db.clone(),
                 self.tx_manager.clone(),
                 self.broadcaster.clone(),
+                self.peer_scores.clone(),
+                self.config.observer,
                 self.config.msg_signer_key.clone(),
-                self.config.network().tx_source_urls,
+                network.tx_source_urls,
                 self.config.scp_debug_dump.clone(),
                 self.logger.clone(),
             ))
@@ -515,20 +689,20 @@ impl<
             .start(
                 "MsgsFromNetRecv".to_string(),
                 move |consensus_msg_from_network| {
-                    let (consensus_msg, from_responder_id) = (
+                    let (consensus_msg, from) = (
                         consensus_msg_from_network.consensus_msg,
-                        consensus_msg_from_network.from_responder_id,
+                        consensus_msg_from_network.from,
                     );
 
                     // Keep track that we heard from the sender of this message.
                     if let Some(peer_keepalive) = peer_keepalive_weak.upgrade() {
-                        peer_keepalive.heard_from_peer(from_responder_id.clone());
+                        peer_keepalive.heard_from_peer(from.responder_id.clone());
                     }
 
                     byzantine_ledger_weak.upgrade().and_then(|ledger| {
-                        ledger.get().map(|ledger| {
-                            ledger.handle_consensus_msg(consensus_msg, from_responder_id)
-                        })
+                        ledger
+                            .get()
+                            .map(|ledger| ledger.handle_consensus_msg(consensus_msg, from))
                     });
                 },
             )
@@ -542,19 +716,34 @@ impl<
     }
 
     /// Creates a function that returns true if the node is currently serving
-    /// user requests.
+    /// user requests, i.e. it is in sync with its peers and not shutting down.
     fn create_is_serving_user_requests_fn(&self) -> Arc<dyn Fn() -> bool + Sync + Send> {
         let byzantine_ledger = self
             .byzantine_ledger
             .as_ref()
             .map(Arc::downgrade)
             .expect("Server was not initialized");
+        let shutting_down = self.shutting_down.clone();
 
         Arc::new(move || {
-            byzantine_ledger
-                .upgrade()
-                .and_then(|ledger| ledger.get().map(|ledger| !ledger.is_behind()))
-                .unwrap_or(false)
+            !shutting_down.load(Ordering::SeqCst)
+                && byzantine_ledger
+                    .upgrade()
+                    .and_then(|ledger| ledger.get().map(|ledger| !ledger.is_behind()))
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Creates a health check callback reporting whether this node is serving
+    /// user requests.
+    fn create_health_check_callback(&self) -> ServiceHealthCheckCallback {
+        let is_serving_user_requests = self.create_is_serving_user_requests_fn();
+        Arc::new(move |_| {
+            if is_serving_user_requests() {
+                HealthCheckStatus::SERVING
+            } else {
+                HealthCheckStatus::NOT_SERVING
+            }
         })
     }
 
@@ -569,36 +758,40 @@ impl<
         let tx_manager = self.tx_manager.clone();
         let local_node_id = self.local_node_id.clone();
         let broadcaster = self.broadcaster.clone();
+        let observer = self.config.observer;
 
-        // Figure out which node IDs we are going to be relaying received transactions
-        // from. See comment below ("Broadcast to peers") for more details.
-        let relay_from_nodes: Vec<ResponderId> = self
-            .peer_manager
-            .conns()
-            .iter()
-            .filter_map(|conn| {
-                let uri = conn.uri();
-                if uri.consensus_relay_incoming_txs() {
-                    match uri.responder_id() {
-                        Ok(responder_id) => Some(responder_id),
-                        Err(_e) => {
-                            log::warn!(
-                                self.logger,
-                                "Could not get responder_id from {:?}",
-                                uri.to_string()
-                            );
-                            None
-                        }
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect();
+        let peer_manager = self.peer_manager.clone();
+        let logger = self.logger.clone();
 
         Arc::new(move |tx_hash, origin_node, relayed_from| {
             let origin_node = origin_node.unwrap_or(&local_node_id);
 
+            // Figure out which node IDs we are going to be relaying received transactions
+            // from. See comment below ("Broadcast to peers") for more details. This is
+            // computed on each call since the set of peers may change at runtime.
+            let relay_from_nodes: Vec<ResponderId> = peer_manager
+                .conns()
+                .iter()
+                .filter_map(|conn| {
+                    let uri = conn.uri();
+                    if uri.consensus_relay_incoming_txs() {
+                        match uri.responder_id() {
+                            Ok(responder_id) => Some(responder_id),
+                            Err(_e) => {
+                                log::warn!(
+                                    logger,
+                                    "Could not get responder_id from {:?}",
+                                    uri.to_string()
+                                );
+                                None
+                            }
+                        }
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
             // Broadcast to peers.
             //
             // Nodes always relay transactions sent to them by clients to all their peers.
@@ -609,7 +802,10 @@ impl<
             // However, in non-mesh configurations, network operators might want to
             // selectively have incoming transactions from certain peers be
             // relayed to other peers in order to improve consensus time.
-            if origin_node == &local_node_id || relay_from_nodes.contains(&origin_node.responder_id)
+            // Observers never relay transactions.
+            if !observer
+                && (origin_node == &local_node_id
+                    || relay_from_nodes.contains(&origin_node.responder_id))
             {
                 if let Some(encrypted_tx) = tx_manager.get_encrypted_tx(&tx_hash) {
                     broadcaster
@@ -653,8 +849,10 @@ impl<
             .map(Arc::downgrade)
             .expect("Server was not initialized");
         let config = self.config.clone();
+        let network = self.network.clone();
         let logger = self.logger.clone();
         Arc::new(move || {
+            let network = network.read().expect("lock poisoned").clone();
             let mut sync_status = "synced";
             let mut peer_block_height: u64 = 0;
             byzantine_ledger.upgrade().map(|ledger| {
@@ -725,13 +923,14 @@ impl<
                     "scp_debug_dump": config.scp_debug_dump,
                     "client_auth_token_enabled": config.client_auth_token_secret.map(|_| true).unwrap_or(false),
                     "client_auth_token_max_lifetime": config.client_auth_token_max_lifetime.as_secs(),
+                    "observer": config.observer,
                 },
-                "network": config.network(),
+                "network": network,
                 "status": {
                     "block_height": block_height,
                     "version": VERSION,
-                    "broadcast_peer_count": config.network().broadcast_peers.len(),
-                    "known_peer_count": config.network().known_peers.map_or(0, |x| x.len()),
+                    "broadcast_peer_count": network.broadcast_peers.len(),
+                    "known_peer_count": network.known_peers.as_ref().map_or(0, |x| x.len()),
                     "sync_status": sync_status,
                     "blocks_behind": blocks_behind,
                     "latest_block_hash": latest_block_hash,
@@ -741,6 +940,167 @@ impl<
             .to_string())
         })
     }
+
+    /// Helper method for creating the get status json function needed by the
+    /// GRPC admin service. It reports the slot this node is working on and the
+    /// state of its peers.
+    fn create_get_status_json_fn(&self) -> GetStatusJsonFn {
+        let ledger_db = self.ledger_db.clone();
+        let byzantine_ledger = self
+            .byzantine_ledger
+            .as_ref()
+            .map(Arc::downgrade)
+            .expect("Server was not initialized");
+        let peer_manager = self.peer_manager.clone();
+        let peer_scores = self.peer_scores.clone();
+        Arc::new(move || {
+            let current_slot = ledger_db.num_blocks().map_err(|err| {
+                RpcStatus::with_message(
+                    RpcStatusCode::INTERNAL,
+                    format!("Error getting block height: {}", err),
+                )
+            })?;
+
+            let mut is_behind = None;
+            let mut highest_peer_block = None;
+            let mut highest_issued_slot = None;
+            if let Some(ledger) = byzantine_ledger.upgrade() {
+                if let Some(ledger) = ledger.get() {
+                    is_behind = Some(ledger.is_behind());
+                    highest_peer_block = Some(ledger.highest_peer_block());
+                    highest_issued_slot = ledger
+                        .get_highest_issued_message()
+                        .map(|msg| msg.scp_msg.slot_index);
+                }
+            }
+
+            let health = peer_manager.health();
+            let peers: Vec<_> = peer_manager
+                .responder_ids()
+                .into_iter()
+                .map(|responder_id| {
+                    let peer_health = health.get(&responder_id);
+                    json!({
+                        "responder_id": responder_id.to_string(),
+                        "banned": peer_scores.is_responder_banned(&responder_id),
+                        "consecutive_failures": peer_health.map(|h| h.consecutive_failures()),
+                        "rtt_ms": peer_health.and_then(|h| h.rtt()).map(|rtt| rtt.as_millis() as u64),
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "current_slot": current_slot,
+                "highest_issued_slot": highest_issued_slot,
+                "highest_peer_block": highest_peer_block,
+                "is_behind": is_behind,
+                "pending_values": counters::CUR_NUM_PENDING_VALUES.get(),
+                "peers": peers,
+            })
+            .to_string())
+        })
+    }
+
+    /// Helper method for creating the set config json function needed by the
+    /// GRPC admin service. It accepts a network configuration, in the same
+    /// format as the `--network` JSON file, and applies its quorum set and
+    /// broadcast peers. The new quorum set is used starting with the next
+    /// slot. Transaction source URLs are only read at startup, so configs
+    /// changing them are rejected.
+    fn create_set_config_json_fn(&self) -> SetConfigJsonFn {
+        let network = self.network.clone();
+        let local_node_id = self.local_node_id.clone();
+        let peer_verifier = self.config.peer_verifier();
+        let enclave = self.enclave.clone();
+        let env = self.env.clone();
+        let peer_manager = self.peer_manager.clone();
+        let known_responder_ids = self.known_responder_ids.clone();
+        let broadcaster = self.broadcaster.clone();
+        let peer_keepalive = self
+            .peer_keepalive
+            .as_ref()
+            .map(Arc::downgrade)
+            .expect("Server was not initialized");
+        let byzantine_ledger = self
+            .byzantine_ledger
+            .as_ref()
+            .map(Arc::downgrade)
+            .expect("Server was not initialized");
+        let logger = self.logger.clone();
+        Arc::new(move |config_json| {
+            // Holding the lock for the duration of the update serializes concurrent
+            // updates.
+            let mut network = network.write().expect("lock poisoned");
+
+            let new_network: NetworkConfig = serde_json::from_str(&config_json)
+                .map_err(|err| format!("failed parsing network config: {}", err))
+                .and_then(|new_network: NetworkConfig| {
+                    new_network.validate(&local_node_id.responder_id)?;
+                    if new_network.tx_source_urls != network.tx_source_urls {
+                        return Err("tx_source_urls cannot be changed without a restart".to_owned());
+                    }
+                    Ok(new_network)
+                })
+                .map_err(|err| {
+                    log::warn!(logger, "Rejected network config: {}", err);
+                    counters::NETWORK_CONFIG_REJECTED.inc();
+                    RpcStatus::with_message(RpcStatusCode::INVALID_ARGUMENT, err)
+                })?;
+            let quorum_set = new_network.quorum_set();
+
+            let byzantine_ledger = byzantine_ledger.upgrade().ok_or_else(|| {
+                RpcStatus::with_message(RpcStatusCode::UNAVAILABLE, "Server is stopping".into())
+            })?;
+            let byzantine_ledger = byzantine_ledger.get().ok_or_else(|| {
+                RpcStatus::with_message(
+                    RpcStatusCode::UNAVAILABLE,
+                    "ByzantineLedger is not running yet".into(),
+                )
+            })?;
+
+            // Connections to peers that remain are kept.
+            let peers: Vec<PeerConnection<E>> = new_network
+                .broadcast_peers()
+                .into_iter()
+                .map(|peer_uri| {
+                    PeerConnection::new(
+                        enclave.clone(),
+                        local_node_id.clone(),
+                        peer_uri,
+                        peer_verifier.clone(),
+                        env.clone(),
+                        logger.clone(),
+                    )
+                })
+                .collect();
+            peer_manager.set_conns(peers);
+
+            let responder_ids = peer_manager.responder_ids();
+            *known_responder_ids.write().expect("lock poisoned") = responder_ids.clone();
+            broadcaster
+                .lock()
+                .expect("lock poisoned")
+                .set_peers(&peer_manager);
+            if let Some(peer_keepalive) = peer_keepalive.upgrade() {
+                peer_keepalive.set_peers(&responder_ids);
+            }
+            byzantine_ledger.set_quorum_set(quorum_set);
+
+            log::info!(
+                logger,
+                "Applied network config: quorum set {:?} -> {:?}, broadcast peers {:?} -> {:?}",
+                network.quorum_set,
+                new_network.quorum_set,
+                network.broadcast_peers,
+                new_network.broadcast_peers,
+            );
+            counters::NETWORK_CONFIG_UPDATED.inc();
+            counters::BROADCAST_PEERS.set(responder_ids.len() as i64);
+
+            *network = new_network;
+            Ok(())
+        })
+    }
 }
 
 impl<
@@ -753,3 +1113,13 @@ impl<
         let _ = self.stop();
     }
 }
+
+/// Descriptors of the consensus API proto files and of the files they import,
+/// served by the reflection service.
+fn api_file_descriptors() -> Vec<&'static FileDescriptorProto> {
+    let mut file_descriptors = mc_consensus_api::file_descriptors();
+    file_descriptors.extend(mc_attest_api::file_descriptors());
+    file_descriptors.push(mc_consensus_api::blockchain::file_descriptor_proto());
+    file_descriptors.push(mc_consensus_api::external::file_descriptor_proto());
+    file_descriptors
+}
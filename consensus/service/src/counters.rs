@@ -78,6 +78,12 @@ lazy_static::lazy_static! {
     // Number of entries in the transactions cache.
     pub static ref TX_CACHE_NUM_ENTRIES: IntGauge = OP_COUNTERS.gauge("tx_cache_num_entries");
 
+    // Number of transactions rejected because the transactions cache was full.
+    pub static ref TX_CACHE_REJECTED_FULL: IntCounter = OP_COUNTERS.counter("tx_cache_rejected_full");
+
+    // Number of low-priority transactions evicted from the transactions cache.
+    pub static ref TX_CACHE_EVICTED: IntCounter = OP_COUNTERS.counter("tx_cache_evicted");
+
     // Number of consensus messages dropped due to referencing an invalid previous block id.
     pub static ref SCP_MESSAGES_DROPPED_DUE_TO_INVALID_PREV_BLOCK_ID: IntCounter = OP_COUNTERS.counter("scp_messages_dropped_due_to_invalid_prev_block_id");
 
@@ -102,6 +108,30 @@ lazy_static::lazy_static! {
     // Consensus enclave report timestamp, represented as seconds of UTC time since Unix epoch 1970-01-01T00:00:00Z.
     pub static ref ENCLAVE_REPORT_TIMESTAMP: IntGauge = OP_COUNTERS.gauge("enclave_report_timestamp");
 
+    // Number of network configuration changes applied at runtime.
+    pub static ref NETWORK_CONFIG_UPDATED: IntCounter = OP_COUNTERS.counter("network_config_updated");
+
+    // Number of network configuration changes rejected.
+    pub static ref NETWORK_CONFIG_REJECTED: IntCounter = OP_COUNTERS.counter("network_config_rejected");
+
+    // Number of consensus messages dropped because the sending peer exceeded its rate limit.
+    pub static ref PEER_MSGS_RATE_LIMITED: IntCounter = OP_COUNTERS.counter("peer_msgs_rate_limited");
+
+    // Number of consensus messages dropped because the sending peer is banned.
+    pub static ref PEER_MSGS_FROM_BANNED: IntCounter = OP_COUNTERS.counter("peer_msgs_from_banned");
+
+    // Number of times a peer was banned for exceeding the score threshold.
+    pub static ref PEER_BANS: IntCounter = OP_COUNTERS.counter("peer_bans");
+
+    // Number of proposed transactions rejected because the client exceeded its rate limit.
+    pub static ref CLIENT_TX_PROPOSE_RATE_LIMITED: IntCounter = OP_COUNTERS.counter("client_tx_propose_rate_limited");
+
+    // Number of GetBlocks calls rejected because the client exceeded its rate limit.
+    pub static ref CLIENT_GET_BLOCKS_RATE_LIMITED: IntCounter = OP_COUNTERS.counter("client_get_blocks_rate_limited");
+
+    // Number of configured broadcast peers.
+    pub static ref BROADCAST_PEERS: IntGauge = OP_COUNTERS.gauge("broadcast_peers");
+
 }
 
 /// TxValidationErrorMetrics keeps track of tx validation errors upon ingress
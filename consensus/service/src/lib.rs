@@ -15,5 +15,10 @@ pub mod validators; // Public so that it can be benchmarked by the `benchmarks`
 mod api;
 mod background_work_queue;
 mod byzantine_ledger;
+mod client_rate_limits;
 mod counters;
 mod peer_keepalive;
+mod peer_scores;
+
+/// Name of the tracer used for the spans of the consensus service.
+const TRACER_NAME: &str = "mc_consensus_service";
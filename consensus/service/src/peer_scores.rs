@@ -0,0 +1,464 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Per-peer rate limiting and reputation scores for incoming consensus
+//! messages.
+//!
+//! Each peer may deliver a limited number of consensus messages per second.
+//! In addition, misbehavior (invalid messages, messages for stale slots and
+//! failing to provide transactions it referenced) increases the peer's score.
+//! Scores decay over time; a peer whose score exceeds a threshold is ignored
+//! for a while.
+//!
+//! The responder ID a peer claims when delivering a message is not
+//! authenticated, so rate limits and scores are kept per [PeerKey], which
+//! also includes the address of the connection that delivered the message.
+//! Another host claiming to be a peer then only affects its own score.
+
+use crate::counters;
+use mc_common::{HashMap, ResponderId};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Default maximum number of consensus messages accepted from a peer per
+/// second.
+pub const DEFAULT_PEER_MSG_RATE_LIMIT: u32 = 100;
+
+/// Default score above which a peer is ignored.
+pub const DEFAULT_PEER_SCORE_THRESHOLD: u32 = 100;
+
+/// Default amount of time a peer is ignored for once its score exceeds the
+/// threshold.
+pub const DEFAULT_PEER_BAN_DURATION: Duration = Duration::from_secs(60);
+
+/// Time it takes for a peer's score to decay to half its value.
+const SCORE_HALF_LIFE: Duration = Duration::from_secs(60);
+
+/// How often state that is no different from that of a new peer is dropped.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The sender of consensus messages.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PeerKey {
+    /// The responder ID the sender claims to be.
+    pub responder_id: ResponderId,
+
+    /// The host address of the connection that delivered the messages, or
+    /// None for messages this node fetched from the peer itself.
+    pub addr: Option<String>,
+}
+
+impl PeerKey {
+    /// The sender of messages delivered over a gRPC connection.
+    ///
+    /// # Arguments
+    /// * `responder_id` - The responder ID the sender claims to be.
+    /// * `grpc_peer` - The connection's peer, as returned by
+    ///   `RpcContext::peer`, e.g. `ipv4:10.0.0.1:51234`. The port is ignored,
+    ///   since it changes when the peer reconnects.
+    pub fn delivered(responder_id: ResponderId, grpc_peer: &str) -> Self {
+        let addr = match grpc_peer.rfind(':') {
+            Some(index) if grpc_peer[..index].contains(':') => &grpc_peer[..index],
+            _ => grpc_peer,
+        };
+        Self {
+            responder_id,
+            addr: Some(addr.to_owned()),
+        }
+    }
+}
+
+impl From<ResponderId> for PeerKey {
+    /// The sender of messages this node fetched from the peer itself.
+    fn from(responder_id: ResponderId) -> Self {
+        Self {
+            responder_id,
+            addr: None,
+        }
+    }
+}
+
+/// Misbehavior that counts against a peer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PeerOffense {
+    /// The peer sent a message that could not be deserialized, had an invalid
+    /// signature, or referred to a different blockchain history.
+    InvalidMsg,
+
+    /// The peer sent a message for a slot that is too old to be processed.
+    StaleMsg,
+
+    /// The peer did not provide transactions referenced by a message it sent,
+    /// or provided malformed ones.
+    FailedGetTxs,
+}
+
+impl PeerOffense {
+    /// How much the offense adds to the peer's score.
+    fn penalty(&self) -> f64 {
+        match self {
+            Self::InvalidMsg => 10.0,
+            Self::StaleMsg => 1.0,
+            Self::FailedGetTxs => 5.0,
+        }
+    }
+}
+
+/// Whether a consensus message from a peer should be accepted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MsgAdmission {
+    /// The message should be processed.
+    Accept,
+
+    /// The peer has exceeded its message rate limit.
+    RateLimited,
+
+    /// The peer is ignored because its score exceeded the threshold.
+    Banned,
+}
+
+/// State tracked for a single peer.
+#[derive(Debug)]
+struct PeerState {
+    /// Number of messages the peer may currently send (token bucket).
+    tokens: f64,
+
+    /// Misbehavior score, decayed as of `updated_at`.
+    score: f64,
+
+    /// When `tokens` and `score` were last updated.
+    updated_at: Instant,
+
+    /// The peer is ignored until this time.
+    banned_until: Option<Instant>,
+}
+
+/// The state of all senders.
+#[derive(Debug)]
+struct Peers {
+    states: HashMap<PeerKey, PeerState>,
+
+    /// When `states` was last swept.
+    swept_at: Instant,
+}
+
+/// Rate limits and reputation scores of peers. Cloning shares the underlying
+/// state.
+#[derive(Clone)]
+pub struct PeerScores {
+    peers: Arc<Mutex<Peers>>,
+
+    /// Maximum number of messages accepted from a peer per second.
+    max_msgs_per_sec: u32,
+
+    /// Score above which a peer is banned.
+    score_threshold: u32,
+
+    /// How long a peer is banned for.
+    ban_duration: Duration,
+}
+
+impl PeerScores {
+    /// Create a new PeerScores.
+    ///
+    /// # Arguments
+    /// * `max_msgs_per_sec` - Maximum number of messages accepted from a peer
+    ///   per second. Also the largest burst a peer may send.
+    /// * `score_threshold` - Score above which a peer is banned.
+    /// * `ban_duration` - How long a peer is banned for.
+    pub fn new(max_msgs_per_sec: u32, score_threshold: u32, ban_duration: Duration) -> Self {
+        Self {
+            peers: Arc::new(Mutex::new(Peers {
+                states: HashMap::default(),
+                swept_at: Instant::now(),
+            })),
+            max_msgs_per_sec,
+            score_threshold,
+            ban_duration,
+        }
+    }
+
+    /// Check whether a message from `peer` should be accepted, and count it
+    /// against the peer's rate limit.
+    pub fn admit_msg(&self, peer: &PeerKey) -> MsgAdmission {
+        self.admit_msg_at(peer, Instant::now())
+    }
+
+    /// Record misbehavior by a peer.
+    ///
+    /// Returns true if this caused the peer to be banned.
+    pub fn penalize(&self, peer: &PeerKey, offense: PeerOffense) -> bool {
+        self.penalize_at(peer, offense, Instant::now())
+    }
+
+    /// Returns true if the peer is currently banned.
+    pub fn is_banned(&self, peer: &PeerKey) -> bool {
+        let now = Instant::now();
+        self.lock()
+            .states
+            .get(peer)
+            .and_then(|state| state.banned_until)
+            .map_or(false, |banned_until| now < banned_until)
+    }
+
+    /// Returns true if messages claiming to be from `responder_id` are
+    /// currently ignored on any connection.
+    pub fn is_responder_banned(&self, responder_id: &ResponderId) -> bool {
+        let now = Instant::now();
+        self.lock().states.iter().any(|(peer, state)| {
+            peer.responder_id == *responder_id
+                && state.banned_until.map_or(false, |until| now < until)
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<Peers> {
+        self.peers.lock().expect("mutex poisoned")
+    }
+
+    fn admit_msg_at(&self, peer: &PeerKey, now: Instant) -> MsgAdmission {
+        let mut peers = self.lock();
+        self.sweep(&mut peers, now);
+        let state = self.state(&mut peers, peer, now);
+
+        // Lift an expired ban.
+        if state.banned_until.map_or(false, |until| until <= now) {
+            state.banned_until = None;
+            Self::update_gauges(peer, state);
+        }
+
+        let admission = if state.banned_until.is_some() {
+            MsgAdmission::Banned
+        } else if state.tokens < 1.0 {
+            MsgAdmission::RateLimited
+        } else {
+            state.tokens -= 1.0;
+            MsgAdmission::Accept
+        };
+
+        match admission {
+            MsgAdmission::Accept => {}
+            MsgAdmission::RateLimited => counters::PEER_MSGS_RATE_LIMITED.inc(),
+            MsgAdmission::Banned => counters::PEER_MSGS_FROM_BANNED.inc(),
+        }
+        admission
+    }
+
+    fn penalize_at(&self, peer: &PeerKey, offense: PeerOffense, now: Instant) -> bool {
+        let mut peers = self.lock();
+        let state = self.state(&mut peers, peer, now);
+
+        state.score += offense.penalty();
+
+        let newly_banned = state.score > self.score_threshold as f64
+            && state.banned_until.map_or(true, |until| until <= now);
+        if newly_banned {
+            state.banned_until = Some(now + self.ban_duration);
+            // The ban is the consequence of the accumulated score.
+            state.score = 0.0;
+            counters::PEER_BANS.inc();
+        }
+
+        Self::update_gauges(peer, state);
+        newly_banned
+    }
+
+    /// Export the state of a peer. The gauges are labelled with the responder
+    /// ID only, so with several connections claiming the same responder ID
+    /// they show the most recently updated one.
+    fn update_gauges(peer: &PeerKey, state: &PeerState) {
+        let peer = peer.responder_id.to_string();
+        counters::OP_COUNTERS
+            .peer_gauge("peer_score", &peer)
+            .set(state.score as i64);
+        counters::OP_COUNTERS
+            .peer_gauge("peer_banned", &peer)
+            .set(state.banned_until.is_some() as i64);
+    }
+
+    /// Drop the state of senders that is no different from that of a new
+    /// sender, so that connections which come and go do not accumulate. This
+    /// runs at most once per `SWEEP_INTERVAL`.
+    fn sweep(&self, peers: &mut Peers, now: Instant) {
+        if now.saturating_duration_since(peers.swept_at) < SWEEP_INTERVAL {
+            return;
+        }
+        peers.swept_at = now;
+
+        let max_tokens = self.max_msgs_per_sec as f64;
+        peers.states.retain(|_, state| {
+            self.refresh(state, now);
+            state.tokens < max_tokens || state.score >= 1.0 || state.banned_until.is_some()
+        });
+    }
+
+    /// Get the up to date state of a peer, creating it if necessary.
+    fn state<'a>(&self, peers: &'a mut Peers, peer: &PeerKey, now: Instant) -> &'a mut PeerState {
+        let max_tokens = self.max_msgs_per_sec as f64;
+        let state = peers
+            .states
+            .entry(peer.clone())
+            .or_insert_with(|| PeerState {
+                tokens: max_tokens,
+                score: 0.0,
+                updated_at: now,
+                banned_until: None,
+            });
+        self.refresh(state, now);
+        state
+    }
+
+    /// Refill the token bucket and decay the score of a peer.
+    fn refresh(&self, state: &mut PeerState, now: Instant) {
+        let elapsed = now.saturating_duration_since(state.updated_at);
+        let max_tokens = self.max_msgs_per_sec as f64;
+        state.tokens = (state.tokens + elapsed.as_secs_f64() * max_tokens).min(max_tokens);
+        state.score *= 0.5f64.powf(elapsed.as_secs_f64() / SCORE_HALF_LIFE.as_secs_f64());
+        state.updated_at = now;
+    }
+}
+
+impl Default for PeerScores {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_PEER_MSG_RATE_LIMIT,
+            DEFAULT_PEER_SCORE_THRESHOLD,
+            DEFAULT_PEER_BAN_DURATION,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn responder_id(name: &str) -> ResponderId {
+        ResponderId(format!("{}:443", name))
+    }
+
+    fn peer(name: &str) -> PeerKey {
+        PeerKey::delivered(responder_id(name), "ipv4:10.0.0.1:50000")
+    }
+
+    #[test]
+    // Should accept up to `max_msgs_per_sec` messages at once, and refill over
+    // time.
+    fn test_rate_limit() {
+        let peer_scores = PeerScores::new(10, 100, Duration::from_secs(60));
+        let peer = peer("a");
+        let now = Instant::now();
+
+        for _ in 0..10 {
+            assert_eq!(peer_scores.admit_msg_at(&peer, now), MsgAdmission::Accept);
+        }
+        assert_eq!(
+            peer_scores.admit_msg_at(&peer, now),
+            MsgAdmission::RateLimited
+        );
+
+        // Other peers have their own limit.
+        assert_eq!(
+            peer_scores.admit_msg_at(&peer("b"), now),
+            MsgAdmission::Accept
+        );
+
+        // Half a second later, five more messages are allowed.
+        let later = now + Duration::from_millis(500);
+        for _ in 0..5 {
+            assert_eq!(peer_scores.admit_msg_at(&peer, later), MsgAdmission::Accept);
+        }
+        assert_eq!(
+            peer_scores.admit_msg_at(&peer, later),
+            MsgAdmission::RateLimited
+        );
+    }
+
+    #[test]
+    // A peer should be banned once its score exceeds the threshold, until the
+    // ban expires.
+    fn test_ban() {
+        let peer_scores = PeerScores::new(10, 25, Duration::from_secs(60));
+        let peer = peer("a");
+        let now = Instant::now();
+
+        assert!(!peer_scores.penalize_at(&peer, PeerOffense::InvalidMsg, now));
+        assert!(!peer_scores.penalize_at(&peer, PeerOffense::InvalidMsg, now));
+        assert!(peer_scores.penalize_at(&peer, PeerOffense::InvalidMsg, now));
+        assert_eq!(peer_scores.admit_msg_at(&peer, now), MsgAdmission::Banned);
+
+        let after_ban = now + Duration::from_secs(61);
+        assert_eq!(
+            peer_scores.admit_msg_at(&peer, after_ban),
+            MsgAdmission::Accept
+        );
+    }
+
+    #[test]
+    // Scores should decay over time, so that occasional offenses do not lead to
+    // a ban.
+    fn test_score_decay() {
+        let peer_scores = PeerScores::new(10, 25, Duration::from_secs(60));
+        let peer = peer("a");
+        let mut now = Instant::now();
+
+        for _ in 0..10 {
+            assert!(!peer_scores.penalize_at(&peer, PeerOffense::InvalidMsg, now));
+            now += SCORE_HALF_LIFE;
+        }
+        assert_eq!(peer_scores.admit_msg_at(&peer, now), MsgAdmission::Accept);
+    }
+
+    #[test]
+    // Connections claiming the same responder ID should be scored separately.
+    fn test_connections_scored_separately() {
+        let peer_scores = PeerScores::new(10, 25, Duration::from_secs(60));
+        let real = PeerKey::delivered(responder_id("a"), "ipv4:10.0.0.1:50000");
+        let reconnected = PeerKey::delivered(responder_id("a"), "ipv4:10.0.0.1:50001");
+        let spoofed = PeerKey::delivered(responder_id("a"), "ipv4:10.6.6.6:50000");
+        let now = Instant::now();
+
+        assert_eq!(real, reconnected);
+        for _ in 0..3 {
+            peer_scores.penalize_at(&spoofed, PeerOffense::InvalidMsg, now);
+        }
+        assert_eq!(
+            peer_scores.admit_msg_at(&spoofed, now),
+            MsgAdmission::Banned
+        );
+        assert_eq!(peer_scores.admit_msg_at(&real, now), MsgAdmission::Accept);
+        assert!(peer_scores.is_responder_banned(&responder_id("a")));
+    }
+
+    #[test]
+    fn test_peer_key_ipv6() {
+        assert_eq!(
+            PeerKey::delivered(responder_id("a"), "ipv6:[::1]:50000").addr,
+            Some("ipv6:[::1]".to_owned())
+        );
+    }
+
+    #[test]
+    // State that is no different from a new peer's should be dropped.
+    fn test_sweep() {
+        let peer_scores = PeerScores::new(10, 100, Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert_eq!(
+            peer_scores.admit_msg_at(&peer("a"), now),
+            MsgAdmission::Accept
+        );
+        assert!(!peer_scores.penalize_at(&peer("b"), PeerOffense::InvalidMsg, now));
+        assert_eq!(peer_scores.lock().states.len(), 2);
+
+        // "a" has refilled its tokens, while "b" still has a score.
+        let later = now + SWEEP_INTERVAL + Duration::from_secs(1);
+        assert_eq!(
+            peer_scores.admit_msg_at(&peer("c"), later),
+            MsgAdmission::Accept
+        );
+        let peers = peer_scores.lock();
+        assert!(!peers.states.contains_key(&peer("a")));
+        assert!(peers.states.contains_key(&peer("b")));
+        assert!(peers.states.contains_key(&peer("c")));
+    }
+}
@@ -18,6 +18,31 @@ pub enum TxManagerError {
 
     /// Ledger error: {0}
     LedgerDb(LedgerDbError),
+
+    /// Cache is full and the tx does not outrank any cached tx
+    CacheFull,
+}
+
+impl TxManagerError {
+    /// Whether the error shows that the transaction itself is malformed, as
+    /// opposed to being stale w.r.t. the local ledger or a local failure.
+    pub fn is_malformed(&self) -> bool {
+        match self {
+            Self::TransactionValidation(err) => !matches!(
+                err,
+                TransactionValidationError::ContainsSpentKeyImage
+                    | TransactionValidationError::ContainsExistingOutputPublicKey
+                    | TransactionValidationError::TombstoneBlockExceeded
+                    | TransactionValidationError::TombstoneBlockTooFar
+                    | TransactionValidationError::MissingTxOutMembershipProof
+                    | TransactionValidationError::InvalidTxOutMembershipProof
+                    | TransactionValidationError::MembershipProofValidationError
+                    | TransactionValidationError::InvalidLedgerContext
+                    | TransactionValidationError::Ledger(_)
+            ),
+            _ => false,
+        }
+    }
 }
 
 impl From<ConsensusEnclaveError> for TxManagerError {
@@ -43,3 +68,30 @@ impl From<LedgerDbError> for TxManagerError {
 }
 
 pub type TxManagerResult<T> = Result<T, TxManagerError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Only errors that are independent of the local ledger and of local failures
+    // show that a transaction is malformed.
+    fn test_is_malformed() {
+        assert!(TxManagerError::from(TransactionValidationError::InvalidRangeProof).is_malformed());
+        assert!(TxManagerError::from(TransactionValidationError::NoInputs).is_malformed());
+
+        assert!(
+            !TxManagerError::from(TransactionValidationError::ContainsSpentKeyImage).is_malformed()
+        );
+        assert!(
+            !TxManagerError::from(TransactionValidationError::TombstoneBlockExceeded)
+                .is_malformed()
+        );
+        assert!(
+            !TxManagerError::from(TransactionValidationError::Ledger("db".to_string()))
+                .is_malformed()
+        );
+        assert!(!TxManagerError::NotInCache(vec![]).is_malformed());
+        assert!(!TxManagerError::CacheFull.is_malformed());
+    }
+}
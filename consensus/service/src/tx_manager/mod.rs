@@ -7,7 +7,7 @@
 //! that have been found to be well-formed. These can be thought of as the
 //! "working set" of transactions that the consensus service may operate on.
 
-use crate::counters;
+use crate::{counters, TRACER_NAME};
 use mc_attest_enclave_api::{EnclaveMessage, PeerSession};
 use mc_common::{
     logger::{log, Logger},
@@ -18,17 +18,29 @@ use mc_consensus_enclave::{
 };
 use mc_transaction_core::{
     constants::MAX_TRANSACTIONS_PER_BLOCK,
+    ring_signature::KeyImage,
     tx::{TxHash, TxOutMembershipProof},
+    validation::TransactionValidationError,
     Block, BlockContents, BlockSignature,
 };
-use std::sync::{Arc, Mutex, MutexGuard};
+use mc_util_telemetry::{
+    current_span_context, global, Context, SpanContext, TraceContextExt, Tracer,
+};
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex, MutexGuard},
+};
 
 mod error;
+mod priority;
 mod tx_manager_trait;
+mod tx_status;
 mod untrusted_interfaces;
 
 pub use error::{TxManagerError, TxManagerResult};
+pub use priority::TxPriority;
 pub use tx_manager_trait::TxManager;
+pub use tx_status::{TxStatus, TxStatusHistory, DEFAULT_TX_STATUS_HISTORY_SIZE};
 pub use untrusted_interfaces::UntrustedInterfaces;
 
 #[cfg(test)]
@@ -40,6 +52,10 @@ struct CacheEntry {
 
     /// Context exposed by the enclave about this transaction.
     context: Arc<WellFormedTxContext>,
+
+    /// The span in which this transaction was inserted, so that later work on
+    /// it can be linked to the request that proposed it.
+    span_context: SpanContext,
 }
 
 impl CacheEntry {
@@ -50,8 +66,108 @@ impl CacheEntry {
     pub fn context(&self) -> &Arc<WellFormedTxContext> {
         &self.context
     }
+
+    pub fn priority(&self) -> TxPriority {
+        TxPriority::new(
+            self.context.fee(),
+            self.encrypted_tx.0.len(),
+            self.context.tombstone_block(),
+            *self.context.tx_hash(),
+        )
+    }
 }
 
+/// Well-formed transactions, keyed by hash and indexed by priority.
+#[derive(Default)]
+struct TxCache {
+    /// Cached transactions.
+    entries: HashMap<TxHash, CacheEntry>,
+
+    /// Priorities of the cached transactions, lowest first.
+    priorities: BTreeSet<TxPriority>,
+
+    /// Transactions referenced by the current slot. These must remain cached
+    /// until the slot completes, so they are never evicted.
+    pinned: HashSet<TxHash>,
+
+    /// Why cached transactions last failed validation. A transaction is still
+    /// pending while it is cached, so this only becomes its outcome once it
+    /// leaves the cache.
+    rejections: HashMap<TxHash, TransactionValidationError>,
+}
+
+impl TxCache {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn get(&self, tx_hash: &TxHash) -> Option<&CacheEntry> {
+        self.entries.get(tx_hash)
+    }
+
+    fn contains_key(&self, tx_hash: &TxHash) -> bool {
+        self.entries.contains_key(tx_hash)
+    }
+
+    fn insert(&mut self, tx_hash: TxHash, entry: CacheEntry) -> Option<CacheEntry> {
+        // Remove the previous priority first, since it may equal the new one.
+        let prev_entry = self.entries.remove(&tx_hash);
+        if let Some(prev_entry) = prev_entry.as_ref() {
+            self.priorities.remove(&prev_entry.priority());
+        }
+        self.priorities.insert(entry.priority());
+        self.entries.insert(tx_hash, entry);
+        prev_entry
+    }
+
+    /// Remove a transaction and return its outcome: the reason it last
+    /// failed validation if it did, and `status` otherwise.
+    fn remove(&mut self, tx_hash: &TxHash, status: TxStatus) -> Option<TxStatus> {
+        let entry = self.entries.remove(tx_hash)?;
+        self.priorities.remove(&entry.priority());
+        self.pinned.remove(tx_hash);
+        Some(
+            self.rejections
+                .remove(tx_hash)
+                .map_or(status, TxStatus::Rejected),
+        )
+    }
+
+    /// Record why a cached transaction failed validation, or clear it if the
+    /// transaction is now valid.
+    fn set_rejection(&mut self, tx_hash: &TxHash, rejection: Option<TransactionValidationError>) {
+        match rejection {
+            Some(err) if self.entries.contains_key(tx_hash) => {
+                self.rejections.insert(*tx_hash, err);
+            }
+            _ => {
+                self.rejections.remove(tx_hash);
+            }
+        }
+    }
+
+    /// The lowest-priority entry that may be evicted.
+    fn lowest_unpinned(&self) -> Option<&TxPriority> {
+        self.priorities
+            .iter()
+            .find(|priority| !self.pinned.contains(priority.tx_hash()))
+    }
+
+    /// Keep the given transaction cached until `unpin_all` is called.
+    fn pin(&mut self, tx_hash: &TxHash) {
+        if self.entries.contains_key(tx_hash) {
+            self.pinned.insert(*tx_hash);
+        }
+    }
+
+    fn unpin_all(&mut self) {
+        self.pinned.clear();
+    }
+}
+
+/// Default maximum number of entries in the transactions cache.
+pub const DEFAULT_TX_CACHE_CAPACITY: usize = 10_000;
+
 #[derive(Clone)]
 pub struct TxManagerImpl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> {
     /// Enclave.
@@ -62,20 +178,44 @@ pub struct TxManagerImpl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + S
     untrusted: UI,
 
     /// Well-formed transactions, keyed by hash.
-    cache: Arc<Mutex<HashMap<TxHash, CacheEntry>>>,
+    cache: Arc<Mutex<TxCache>>,
+
+    /// Maximum number of entries the cache should hold once lower-priority
+    /// entries have been evicted. Pinned transactions may temporarily exceed
+    /// it.
+    capacity: usize,
+
+    /// Outcomes of transactions that are no longer pending.
+    history: Arc<Mutex<TxStatusHistory>>,
 
     /// Logger.
     logger: Logger,
 }
 
 impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManagerImpl<E, UI> {
-    /// Construct a new TxManager instance.
+    /// Construct a new TxManager instance with the default cache capacity.
     pub fn new(enclave: E, untrusted: UI, logger: Logger) -> Self {
+        Self::new_with_capacity(enclave, untrusted, DEFAULT_TX_CACHE_CAPACITY, logger)
+    }
+
+    /// Construct a new TxManager instance.
+    ///
+    /// # Arguments
+    /// * `enclave` - Enclave.
+    /// * `untrusted` - Untrusted interfaces.
+    /// * `capacity` - Maximum number of cached transactions. Must be non-zero.
+    /// * `logger` - Logger.
+    pub fn new_with_capacity(enclave: E, untrusted: UI, capacity: usize, logger: Logger) -> Self {
+        assert!(capacity > 0, "TxManager capacity must be non-zero");
         Self {
             enclave,
             untrusted,
+            capacity,
             logger,
-            cache: Arc::new(Mutex::new(HashMap::default())),
+            cache: Arc::new(Mutex::new(TxCache::default())),
+            history: Arc::new(Mutex::new(TxStatusHistory::new(
+                DEFAULT_TX_STATUS_HISTORY_SIZE,
+            ))),
         }
     }
 
@@ -99,38 +239,99 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManagerImpl<E
         Ok(CacheEntry {
             encrypted_tx: well_formed_encrypted_tx,
             context: Arc::new(well_formed_tx_context),
+            span_context: current_span_context(),
         })
     }
 
-    fn lock_cache(&self) -> MutexGuard<HashMap<TxHash, CacheEntry>> {
-        self.cache.lock().expect("Lock poisoned")
-    }
-}
-
-impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
-    for TxManagerImpl<E, UI>
-{
-    /// Insert a transaction into the cache. The transaction must be
-    /// well-formed.
-    fn insert(&self, tx_context: TxContext) -> TxManagerResult<TxHash> {
+    /// Insert a well-formed transaction into the cache.
+    ///
+    /// When the cache is full, an unpinned transaction is only accepted if it
+    /// outranks the lowest-priority unpinned transaction, which is evicted to
+    /// make room. A pinned transaction is always accepted, since the
+    /// current slot cannot proceed without it.
+    fn insert_impl(&self, tx_context: TxContext, pin: bool) -> TxManagerResult<TxHash> {
         let tx_hash = tx_context.tx_hash;
+        let _trace_guard =
+            Context::current_with_span(global::tracer(TRACER_NAME).start("TxManager::insert"))
+                .attach();
 
         {
-            let cache = self.lock_cache();
-            if let Some(entry) = cache.get(&tx_context.tx_hash) {
+            let mut cache = self.lock_cache();
+            if cache.contains_key(&tx_hash) {
                 // The transaction is well-formed and is in the cache.
-                return Ok(*entry.context.tx_hash());
+                if pin {
+                    cache.pin(&tx_hash);
+                }
+                return Ok(tx_hash);
             }
         }
 
-        let new_entry = self.is_well_formed(tx_context)?;
+        let new_entry = match self.is_well_formed(tx_context) {
+            Ok(entry) => entry,
+            // A ledger error says nothing about the transaction itself.
+            Err(
+                err @ TxManagerError::TransactionValidation(TransactionValidationError::Ledger(_)),
+            ) => return Err(err),
+            Err(TxManagerError::TransactionValidation(err)) => {
+                self.lock_history()
+                    .record(tx_hash, TxStatus::Rejected(err.clone()));
+                return Err(TxManagerError::TransactionValidation(err));
+            }
+            Err(err) => return Err(err),
+        };
 
         {
             let mut cache = self.lock_cache();
+
+            // Another thread may have cached it while the lock was released.
+            if cache.contains_key(&tx_hash) {
+                if pin {
+                    cache.pin(&tx_hash);
+                }
+                return Ok(tx_hash);
+            }
+
+            if !pin && cache.len() >= self.capacity {
+                let lowest = cache
+                    .lowest_unpinned()
+                    .filter(|lowest| new_entry.priority() > **lowest)
+                    .map(|lowest| *lowest.tx_hash());
+
+                match lowest {
+                    Some(evicted_hash) => {
+                        if let Some(status) = cache.remove(&evicted_hash, TxStatus::Evicted) {
+                            self.lock_history().record(evicted_hash, status);
+                        }
+                        counters::TX_CACHE_EVICTED.inc();
+                        log::debug!(
+                            self.logger,
+                            "Evicted transaction {hash}: cache is full",
+                            hash = evicted_hash.to_string(),
+                        );
+                    }
+                    None => {
+                        counters::TX_CACHE_REJECTED_FULL.inc();
+                        self.lock_history().record(tx_hash, TxStatus::Evicted);
+                        log::debug!(
+                            self.logger,
+                            "Rejected transaction {hash}: cache is full",
+                            hash = tx_hash.to_string(),
+                        );
+                        return Err(TxManagerError::CacheFull);
+                    }
+                }
+            }
+
             cache.insert(tx_hash, new_entry);
+            if pin {
+                cache.pin(&tx_hash);
+            }
             counters::TX_CACHE_NUM_ENTRIES.set(cache.len() as i64);
         }
 
+        // The transaction is pending again, even if it was previously dropped.
+        self.lock_history().forget(&tx_hash);
+
         log::trace!(
             self.logger,
             "Cached well-formed transaction {hash}",
@@ -140,25 +341,57 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
         Ok(tx_hash)
     }
 
+    fn lock_cache(&self) -> MutexGuard<TxCache> {
+        self.cache.lock().expect("Lock poisoned")
+    }
+
+    fn lock_history(&self) -> MutexGuard<TxStatusHistory> {
+        self.history.lock().expect("Lock poisoned")
+    }
+}
+
+impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
+    for TxManagerImpl<E, UI>
+{
+    /// Insert a transaction into the cache. The transaction must be
+    /// well-formed.
+    fn insert(&self, tx_context: TxContext) -> TxManagerResult<TxHash> {
+        self.insert_impl(tx_context, false)
+    }
+
+    /// Insert a transaction referenced by the current slot, and pin it.
+    fn insert_pinned(&self, tx_context: TxContext) -> TxManagerResult<TxHash> {
+        self.insert_impl(tx_context, true)
+    }
+
+    /// Keep the given transactions cached until the current slot completes.
+    fn pin(&self, tx_hashes: &[TxHash]) {
+        let mut cache = self.lock_cache();
+        for tx_hash in tx_hashes {
+            cache.pin(tx_hash);
+        }
+    }
+
     /// Remove expired transactions from the cache and return their hashes.
     ///
     /// # Arguments
     /// * `block_index` - Current block index.
     fn remove_expired(&self, block_index: u64) -> HashSet<TxHash> {
-        let mut expired = HashSet::<TxHash>::default();
-
         let mut cache = self.lock_cache();
 
-        // find the expired entries and remove them, storing their keys in expired,
-        // without destroying or re-allocating the cache
-        cache.retain(|key, entry| -> bool {
-            if entry.context().tombstone_block() < block_index {
-                expired.insert(*key);
-                false
-            } else {
-                true
+        let expired: HashSet<TxHash> = cache
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.context().tombstone_block() < block_index)
+            .map(|(tx_hash, _)| *tx_hash)
+            .collect();
+
+        let mut history = self.lock_history();
+        for tx_hash in &expired {
+            if let Some(status) = cache.remove(tx_hash, TxStatus::Expired) {
+                history.record(*tx_hash, status);
             }
-        });
+        }
 
         counters::TX_CACHE_NUM_ENTRIES.set(cache.len() as i64);
 
@@ -172,6 +405,106 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
         expired
     }
 
+    /// Unpin all transactions, then evict the lowest-priority transactions
+    /// until the cache holds at most `capacity` entries, and return their
+    /// hashes.
+    fn evict_over_capacity(&self) -> HashSet<TxHash> {
+        let mut evicted = HashSet::<TxHash>::default();
+
+        let mut cache = self.lock_cache();
+        cache.unpin_all();
+
+        let mut history = self.lock_history();
+        while cache.len() > self.capacity {
+            let lowest = match cache.lowest_unpinned() {
+                Some(lowest) => *lowest.tx_hash(),
+                None => break,
+            };
+            if let Some(status) = cache.remove(&lowest, TxStatus::Evicted) {
+                history.record(lowest, status);
+            }
+            evicted.insert(lowest);
+        }
+
+        if evicted.is_empty() {
+            return evicted;
+        }
+
+        counters::TX_CACHE_NUM_ENTRIES.set(cache.len() as i64);
+        counters::TX_CACHE_EVICTED.inc_by(evicted.len() as i64);
+
+        log::debug!(
+            self.logger,
+            "Evicted {} low-priority transactions, retained {}",
+            evicted.len(),
+            cache.len(),
+        );
+
+        evicted
+    }
+
+    /// Record that the given transactions were externalized in a block.
+    fn record_externalized(&self, tx_hashes: &[TxHash], block_index: u64) {
+        let mut history = self.lock_history();
+        for tx_hash in tx_hashes {
+            history.record(*tx_hash, TxStatus::Externalized(block_index));
+        }
+    }
+
+    /// Record that cached transactions were externalized in a block that was
+    /// appended by ledger sync rather than by this node's consensus.
+    ///
+    /// Blocks do not contain transaction hashes, so a cached transaction is
+    /// matched by its key images and outputs, all of which must be in the
+    /// block.
+    fn record_synced_block(&self, block_index: u64, block_contents: &BlockContents) {
+        // Neither key type is hashable, so compare their bytes.
+        let key_images: HashSet<&[u8; 32]> = block_contents
+            .key_images
+            .iter()
+            .map(KeyImage::as_bytes)
+            .collect();
+        let output_public_keys: HashSet<&[u8; 32]> = block_contents
+            .outputs
+            .iter()
+            .map(|tx_out| tx_out.public_key.as_bytes())
+            .collect();
+
+        let externalized: Vec<TxHash> = self
+            .lock_cache()
+            .entries
+            .values()
+            .map(CacheEntry::context)
+            .filter(|context| {
+                !context.key_images().is_empty()
+                    && context
+                        .key_images()
+                        .iter()
+                        .all(|key_image| key_images.contains(key_image.as_bytes()))
+                    && context
+                        .output_public_keys()
+                        .iter()
+                        .all(|public_key| output_public_keys.contains(public_key.as_bytes()))
+            })
+            .map(|context| *context.tx_hash())
+            .collect();
+
+        self.record_externalized(&externalized, block_index);
+    }
+
+    /// The status of the given transaction, as known by this node.
+    fn tx_status(&self, tx_hash: &TxHash) -> TxStatus {
+        if let Some(status) = self.lock_history().get(tx_hash) {
+            return status.clone();
+        }
+
+        if self.contains(tx_hash) {
+            TxStatus::Pending
+        } else {
+            TxStatus::Unknown
+        }
+    }
+
     /// Returns true if the cache contains the corresponding transaction.
     fn contains(&self, tx_hash: &TxHash) -> bool {
         self.lock_cache().contains_key(tx_hash)
@@ -182,8 +515,30 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
         self.lock_cache().len()
     }
 
+    /// Sort the given transactions from highest to lowest priority.
+    /// Transactions that are not in the cache are skipped.
+    fn prioritize(&self, tx_hashes: &[TxHash]) -> Vec<TxHash> {
+        let mut priorities: Vec<TxPriority> = {
+            let cache = self.lock_cache();
+            tx_hashes
+                .iter()
+                .filter_map(|tx_hash| cache.get(tx_hash))
+                .map(CacheEntry::priority)
+                .collect()
+        };
+        priorities.sort_by(|a, b| b.cmp(a));
+        priorities.dedup();
+        priorities
+            .iter()
+            .map(|priority| *priority.tx_hash())
+            .collect()
+    }
+
     /// Validate the transaction corresponding to the given hash against the
     /// current ledger.
+    ///
+    /// The transaction stays cached, and pending, if it is invalid. Why it
+    /// failed is recorded as its outcome once it leaves the cache.
     fn validate(&self, tx_hash: &TxHash) -> TxManagerResult<()> {
         let context_opt = {
             let cache = self.lock_cache();
@@ -192,8 +547,18 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
 
         if let Some(context) = context_opt {
             let _timer = counters::VALIDATE_TX_TIME.start_timer();
-            self.untrusted.is_valid(context)?;
-            Ok(())
+            match self.untrusted.is_valid(context) {
+                Ok(()) => {
+                    self.lock_cache().set_rejection(tx_hash, None);
+                    Ok(())
+                }
+                // A ledger error says nothing about the transaction itself.
+                Err(err @ TransactionValidationError::Ledger(_)) => Err(err.into()),
+                Err(err) => {
+                    self.lock_cache().set_rejection(tx_hash, Some(err.clone()));
+                    Err(err.into())
+                }
+            }
         } else {
             log::error!(
                 self.logger,
@@ -208,7 +573,7 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
     fn combine(&self, tx_hashes: &[TxHash]) -> TxManagerResult<Vec<TxHash>> {
         let tx_hashes: HashSet<&TxHash> = tx_hashes.iter().clone().collect(); // Dedup
 
-        let tx_contexts: TxManagerResult<Vec<Arc<WellFormedTxContext>>> = {
+        let tx_contexts: TxManagerResult<Vec<(TxPriority, Arc<WellFormedTxContext>)>> = {
             let cache = self.lock_cache();
 
             // Split `tx_hashes` into a list of found hashes and missing ones. This allows
@@ -232,14 +597,30 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
             // Collect tx contexts.
             Ok(entries
                 .into_iter()
-                .map(|(_tx_hash, entry)| entry.unwrap().context().clone())
+                .map(|(_tx_hash, entry)| {
+                    let entry = entry.unwrap();
+                    (entry.priority(), entry.context().clone())
+                })
                 .collect())
         };
+        let mut tx_contexts = tx_contexts?;
+
+        // If the candidates would not fit in a single block, keep the
+        // highest-priority ones.
+        if tx_contexts.len() > MAX_TRANSACTIONS_PER_BLOCK {
+            tx_contexts.sort_by(|(a, _), (b, _)| b.cmp(a));
+            tx_contexts.truncate(MAX_TRANSACTIONS_PER_BLOCK);
+        }
+
+        let tx_contexts: Vec<Arc<WellFormedTxContext>> = tx_contexts
+            .into_iter()
+            .map(|(_priority, context)| context)
+            .collect();
 
         // Perform the combine operation.
         Ok(self
             .untrusted
-            .combine(&tx_contexts?, MAX_TRANSACTIONS_PER_BLOCK))
+            .combine(&tx_contexts, MAX_TRANSACTIONS_PER_BLOCK))
     }
 
     /// Forms a Block containing the transactions that correspond to the given
@@ -347,6 +728,17 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
             .get(tx_hash)
             .map(|entry| entry.encrypted_tx().clone())
     }
+
+    /// The span contexts in which the given transactions were inserted.
+    /// Transactions that are not in the cache are skipped.
+    fn span_contexts(&self, tx_hashes: &[TxHash]) -> Vec<SpanContext> {
+        let cache = self.lock_cache();
+        tx_hashes
+            .iter()
+            .filter_map(|tx_hash| cache.get(tx_hash))
+            .map(|entry| entry.span_context.clone())
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -362,7 +754,6 @@ mod tests {
     };
     use mc_crypto_keys::{Ed25519Public, Ed25519Signature};
     use mc_ledger_db::Ledger;
-    use mc_transaction_core::validation::TransactionValidationError;
     use mc_transaction_core_test_utils::{
         create_ledger, create_transaction, initialize_ledger, AccountKey,
     };
@@ -503,6 +894,79 @@ mod tests {
         assert_eq!(tx_manager.num_entries(), 0);
     }
 
+    #[test_with_logger]
+    // A ledger error during the well-formed check should not mark the
+    // transaction as rejected.
+    fn test_insert_error_ledger_not_rejected(logger: Logger) {
+        let tx_context = TxContext::default();
+
+        let mut mock_untrusted = MockUntrustedInterfaces::new();
+        mock_untrusted
+            .expect_well_formed_check()
+            .times(1)
+            .return_const(Err(TransactionValidationError::Ledger(
+                "unavailable".to_owned(),
+            )));
+
+        // This should not be called.
+        let mock_enclave = MockConsensusEnclave::new();
+
+        let tx_manager = TxManagerImpl::new(mock_enclave, mock_untrusted, logger.clone());
+        assert!(tx_manager.insert(tx_context.clone()).is_err());
+        assert_eq!(tx_manager.tx_status(&tx_context.tx_hash), TxStatus::Unknown);
+    }
+
+    #[test_with_logger]
+    // A cached transaction whose key images and outputs are all in a synced
+    // block should be recorded as externalized in that block.
+    fn test_record_synced_block(logger: Logger) {
+        let mock_untrusted = MockUntrustedInterfaces::new();
+        let mock_enclave = MockConsensusEnclave::new();
+        let tx_manager = TxManagerImpl::new(mock_enclave, mock_untrusted, logger.clone());
+
+        let mut rng: StdRng = SeedableRng::from_seed([77u8; 32]);
+        let mut ledger = create_ledger();
+        let sender = AccountKey::random(&mut rng);
+        let recipient = AccountKey::random(&mut rng);
+        initialize_ledger(&mut ledger, 1, &sender, &mut rng);
+        let tx_out = ledger.get_tx_out_by_index(0).unwrap();
+
+        // Two transactions spending the same output, only one of which is in
+        // the synced block.
+        let txs: Vec<_> = (0..2)
+            .map(|_| {
+                create_transaction(
+                    &mut ledger,
+                    &tx_out,
+                    &sender,
+                    &recipient.default_subaddress(),
+                    10,
+                    &mut rng,
+                )
+            })
+            .collect();
+        for tx in &txs {
+            let context = WellFormedTxContext::from(tx);
+            tx_manager.lock_cache().insert(
+                *context.tx_hash(),
+                CacheEntry {
+                    encrypted_tx: Default::default(),
+                    context: Arc::new(context),
+                    span_context: SpanContext::empty_context(),
+                },
+            );
+        }
+
+        let block_contents = BlockContents::new(txs[0].key_images(), txs[0].prefix.outputs.clone());
+        tx_manager.record_synced_block(5, &block_contents);
+
+        assert_eq!(
+            tx_manager.tx_status(&txs[0].tx_hash()),
+            TxStatus::Externalized(5)
+        );
+        assert_eq!(tx_manager.tx_status(&txs[1].tx_hash()), TxStatus::Pending);
+    }
+
     #[test_with_logger]
     // Should remove all transactions that have expired by the given slot.
     fn test_remove_expired(logger: Logger) {
@@ -524,6 +988,7 @@ mod tests {
             let cache_entry = CacheEntry {
                 encrypted_tx: Default::default(),
                 context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
             };
 
             tx_manager
@@ -557,6 +1022,286 @@ mod tests {
         }
     }
 
+    #[test_with_logger]
+    // When the cache is full, a transaction that does not outrank the
+    // lowest-priority cached transaction should be rejected.
+    fn test_insert_cache_full_rejects_low_priority(logger: Logger) {
+        let tx_context = TxContext::default();
+        let tx_hash = tx_context.tx_hash;
+
+        let mut mock_untrusted = MockUntrustedInterfaces::new();
+        mock_untrusted
+            .expect_well_formed_check()
+            .times(1)
+            .return_const(Ok((0, vec![])));
+
+        // The new transaction pays a fee of 1.
+        let mut mock_enclave = MockConsensusEnclave::new();
+        mock_enclave
+            .expect_tx_is_well_formed()
+            .times(1)
+            .return_const(Ok((
+                WellFormedEncryptedTx(vec![0u8; 100]),
+                WellFormedTxContext::new(1, tx_hash, 10, vec![], vec![], vec![]),
+            )));
+
+        let tx_manager =
+            TxManagerImpl::new_with_capacity(mock_enclave, mock_untrusted, 2, logger.clone());
+
+        // Fill the cache with transactions that pay a fee of 10.
+        for i in 1..3 {
+            let context =
+                WellFormedTxContext::new(10, TxHash([i as u8; 32]), 10, vec![], vec![], vec![]);
+            let cache_entry = CacheEntry {
+                encrypted_tx: WellFormedEncryptedTx(vec![0u8; 100]),
+                context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
+            };
+            tx_manager
+                .lock_cache()
+                .insert(context.tx_hash().clone(), cache_entry);
+        }
+
+        match tx_manager.insert(tx_context) {
+            Err(TxManagerError::CacheFull) => {} // This is expected.
+            result => panic!("Unexpected result {:?}", result),
+        }
+        assert_eq!(tx_manager.num_entries(), 2);
+        assert!(!tx_manager.contains(&tx_hash));
+    }
+
+    #[test_with_logger]
+    // When the cache is full, a transaction that outranks the lowest-priority
+    // cached transaction should be accepted, and the lowest-priority transaction
+    // should be evicted.
+    fn test_insert_cache_full_accepts_high_priority(logger: Logger) {
+        let tx_context = TxContext::default();
+        let tx_hash = tx_context.tx_hash;
+
+        let mut mock_untrusted = MockUntrustedInterfaces::new();
+        mock_untrusted
+            .expect_well_formed_check()
+            .times(1)
+            .return_const(Ok((0, vec![])));
+
+        // The new transaction pays a fee of 1000.
+        let mut mock_enclave = MockConsensusEnclave::new();
+        mock_enclave
+            .expect_tx_is_well_formed()
+            .times(1)
+            .return_const(Ok((
+                WellFormedEncryptedTx(vec![0u8; 100]),
+                WellFormedTxContext::new(1000, tx_hash, 10, vec![], vec![], vec![]),
+            )));
+
+        let tx_manager =
+            TxManagerImpl::new_with_capacity(mock_enclave, mock_untrusted, 2, logger.clone());
+
+        // Fill the cache with transactions that pay fees of 10 and 20.
+        for i in 1..3 {
+            let context =
+                WellFormedTxContext::new(10 * i, TxHash([i as u8; 32]), 10, vec![], vec![], vec![]);
+            let cache_entry = CacheEntry {
+                encrypted_tx: WellFormedEncryptedTx(vec![0u8; 100]),
+                context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
+            };
+            tx_manager
+                .lock_cache()
+                .insert(context.tx_hash().clone(), cache_entry);
+        }
+
+        assert!(tx_manager.insert(tx_context).is_ok());
+
+        // The transaction with the lowest fee should be evicted.
+        assert_eq!(tx_manager.num_entries(), 2);
+        assert!(tx_manager.contains(&tx_hash));
+        assert!(!tx_manager.contains(&TxHash([1u8; 32])));
+        assert!(tx_manager.contains(&TxHash([2u8; 32])));
+        assert_eq!(tx_manager.tx_status(&TxHash([1u8; 32])), TxStatus::Evicted);
+
+        // Nothing to evict once the cache is within capacity.
+        assert!(tx_manager.evict_over_capacity().is_empty());
+    }
+
+    #[test_with_logger]
+    // Pinned transactions should not be evicted to make room for new ones, and a
+    // transaction referenced by the current slot should be accepted even when the
+    // cache is full.
+    fn test_insert_cache_full_pinned(logger: Logger) {
+        let tx_context = TxContext::default();
+        let tx_hash = tx_context.tx_hash;
+
+        let mut mock_untrusted = MockUntrustedInterfaces::new();
+        mock_untrusted
+            .expect_well_formed_check()
+            .times(2)
+            .return_const(Ok((0, vec![])));
+
+        // The new transaction pays a fee of 1000.
+        let mut mock_enclave = MockConsensusEnclave::new();
+        mock_enclave
+            .expect_tx_is_well_formed()
+            .times(2)
+            .return_const(Ok((
+                WellFormedEncryptedTx(vec![0u8; 100]),
+                WellFormedTxContext::new(1000, tx_hash, 10, vec![], vec![], vec![]),
+            )));
+
+        let tx_manager =
+            TxManagerImpl::new_with_capacity(mock_enclave, mock_untrusted, 2, logger.clone());
+
+        // Fill the cache with pinned transactions that pay fees of 10 and 20.
+        for i in 1..3 {
+            let context =
+                WellFormedTxContext::new(10 * i, TxHash([i as u8; 32]), 10, vec![], vec![], vec![]);
+            let cache_entry = CacheEntry {
+                encrypted_tx: WellFormedEncryptedTx(vec![0u8; 100]),
+                context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
+            };
+            tx_manager
+                .lock_cache()
+                .insert(context.tx_hash().clone(), cache_entry);
+        }
+        tx_manager.pin(&[TxHash([1u8; 32]), TxHash([2u8; 32])]);
+
+        // There is nothing to evict, so an unpinned transaction is rejected.
+        match tx_manager.insert(tx_context.clone()) {
+            Err(TxManagerError::CacheFull) => {} // This is expected.
+            result => panic!("Unexpected result {:?}", result),
+        }
+        assert_eq!(tx_manager.num_entries(), 2);
+
+        // A transaction referenced by the current slot is always accepted.
+        assert!(tx_manager.insert_pinned(tx_context).is_ok());
+        assert_eq!(tx_manager.num_entries(), 3);
+
+        // Once the slot completes, the cache is brought back within capacity.
+        let evicted = tx_manager.evict_over_capacity();
+        assert_eq!(evicted.len(), 1);
+        assert!(evicted.contains(&TxHash([1u8; 32])));
+        assert!(tx_manager.contains(&tx_hash));
+        assert!(tx_manager.contains(&TxHash([2u8; 32])));
+    }
+
+    #[test_with_logger]
+    // Transactions should be sorted from highest to lowest priority, skipping
+    // those that are not cached.
+    fn test_prioritize(logger: Logger) {
+        let mock_untrusted = MockUntrustedInterfaces::new();
+        let mock_enclave = MockConsensusEnclave::new();
+        let tx_manager = TxManagerImpl::new(mock_enclave, mock_untrusted, logger.clone());
+
+        for i in 1..4 {
+            let context =
+                WellFormedTxContext::new(10 * i, TxHash([i as u8; 32]), 10, vec![], vec![], vec![]);
+            let cache_entry = CacheEntry {
+                encrypted_tx: WellFormedEncryptedTx(vec![0u8; 100]),
+                context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
+            };
+            tx_manager
+                .lock_cache()
+                .insert(context.tx_hash().clone(), cache_entry);
+        }
+
+        let prioritized = tx_manager.prioritize(&[
+            TxHash([1u8; 32]),
+            TxHash([9u8; 32]),
+            TxHash([3u8; 32]),
+            TxHash([2u8; 32]),
+        ]);
+        assert_eq!(
+            prioritized,
+            vec![TxHash([3u8; 32]), TxHash([2u8; 32]), TxHash([1u8; 32])]
+        );
+    }
+
+    #[test_with_logger]
+    // Eviction should prefer transactions with a lower fee per byte.
+    fn test_evict_over_capacity_fee_per_byte(logger: Logger) {
+        let mock_untrusted = MockUntrustedInterfaces::new();
+        let mock_enclave = MockConsensusEnclave::new();
+        let tx_manager =
+            TxManagerImpl::new_with_capacity(mock_enclave, mock_untrusted, 1, logger.clone());
+
+        // A small transaction with a lower absolute fee but a higher fee per byte.
+        let small = WellFormedTxContext::new(100, TxHash([1u8; 32]), 10, vec![], vec![], vec![]);
+        // A large transaction with a higher absolute fee but a lower fee per byte.
+        let large = WellFormedTxContext::new(500, TxHash([2u8; 32]), 10, vec![], vec![], vec![]);
+
+        tx_manager.lock_cache().insert(
+            *small.tx_hash(),
+            CacheEntry {
+                encrypted_tx: WellFormedEncryptedTx(vec![0u8; 10]),
+                context: Arc::new(small.clone()),
+                span_context: SpanContext::empty_context(),
+            },
+        );
+        tx_manager.lock_cache().insert(
+            *large.tx_hash(),
+            CacheEntry {
+                encrypted_tx: WellFormedEncryptedTx(vec![0u8; 1000]),
+                context: Arc::new(large.clone()),
+                span_context: SpanContext::empty_context(),
+            },
+        );
+
+        let evicted = tx_manager.evict_over_capacity();
+        assert_eq!(evicted.len(), 1);
+        assert!(evicted.contains(large.tx_hash()));
+        assert!(tx_manager.contains(small.tx_hash()));
+    }
+
+    #[test_with_logger]
+    // Re-inserting an entry with the same priority should keep it indexed, so
+    // that it can still be evicted.
+    fn test_cache_reinsert_same_priority(logger: Logger) {
+        let mock_untrusted = MockUntrustedInterfaces::new();
+        let mock_enclave = MockConsensusEnclave::new();
+        let tx_manager =
+            TxManagerImpl::new_with_capacity(mock_enclave, mock_untrusted, 1, logger.clone());
+
+        let context = WellFormedTxContext::new(100, TxHash([1u8; 32]), 10, vec![], vec![], vec![]);
+        for _ in 0..2 {
+            tx_manager.lock_cache().insert(
+                *context.tx_hash(),
+                CacheEntry {
+                    encrypted_tx: WellFormedEncryptedTx(vec![0u8; 10]),
+                    context: Arc::new(context.clone()),
+                    span_context: SpanContext::empty_context(),
+                },
+            );
+        }
+
+        {
+            let cache = tx_manager.lock_cache();
+            assert_eq!(cache.len(), 1);
+            assert_eq!(cache.priorities.len(), 1);
+            assert_eq!(
+                cache.lowest_unpinned().map(|lowest| *lowest.tx_hash()),
+                Some(*context.tx_hash())
+            );
+        }
+
+        let other = WellFormedTxContext::new(100, TxHash([2u8; 32]), 10, vec![], vec![], vec![]);
+        tx_manager.lock_cache().insert(
+            *other.tx_hash(),
+            CacheEntry {
+                encrypted_tx: WellFormedEncryptedTx(vec![0u8; 10]),
+                context: Arc::new(other.clone()),
+                span_context: SpanContext::empty_context(),
+            },
+        );
+
+        // The re-inserted entry can still be evicted.
+        let evicted = tx_manager.evict_over_capacity();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(tx_manager.num_entries(), 1);
+        assert_eq!(tx_manager.lock_cache().priorities.len(), 1);
+    }
+
     #[test_with_logger]
     // Should return Ok if the transaction is in the cache and is valid.
     fn test_validate_ok(logger: Logger) {
@@ -579,6 +1324,7 @@ mod tests {
         let cache_entry = CacheEntry {
             encrypted_tx: Default::default(),
             context: Arc::new(Default::default()),
+            span_context: SpanContext::empty_context(),
         };
         tx_manager
             .cache
@@ -631,6 +1377,7 @@ mod tests {
         let cache_entry = CacheEntry {
             encrypted_tx: Default::default(),
             context: Arc::new(Default::default()),
+            span_context: SpanContext::empty_context(),
         };
         tx_manager
             .cache
@@ -644,6 +1391,16 @@ mod tests {
             )) => {} // This is expected.
             _ => panic!(),
         }
+
+        // The transaction is still cached, so it is pending until it leaves the
+        // cache, and then it was rejected.
+        assert_eq!(tx_manager.tx_status(&tx_context.tx_hash), TxStatus::Pending);
+        let expired = tx_manager.remove_expired(1);
+        assert!(expired.contains(&tx_context.tx_hash));
+        assert_eq!(
+            tx_manager.tx_status(&tx_context.tx_hash),
+            TxStatus::Rejected(TransactionValidationError::ContainsSpentKeyImage)
+        );
     }
 
     #[test_with_logger]
@@ -675,6 +1432,7 @@ mod tests {
             let cache_entry = CacheEntry {
                 encrypted_tx: Default::default(),
                 context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
             };
 
             tx_manager
@@ -691,6 +1449,60 @@ mod tests {
         }
     }
 
+    #[test_with_logger]
+    // When there are more candidates than fit in a block, only the
+    // highest-priority candidates should be combined.
+    fn test_combine_prefers_high_priority(logger: Logger) {
+        let num_txs = MAX_TRANSACTIONS_PER_BLOCK + 10;
+        let tx_hashes: Vec<_> = (0..num_txs)
+            .map(|i| {
+                let mut bytes = [0u8; 32];
+                bytes[..8].copy_from_slice(&(i as u64).to_le_bytes());
+                TxHash(bytes)
+            })
+            .collect();
+
+        // The ten lowest-fee transactions should not be passed to untrusted.
+        let expected_hashes: HashSet<TxHash> = tx_hashes[10..].iter().cloned().collect();
+        let mut mock_untrusted = MockUntrustedInterfaces::new();
+        mock_untrusted
+            .expect_combine()
+            .times(1)
+            .withf(move |tx_contexts, max_elements| {
+                *max_elements == MAX_TRANSACTIONS_PER_BLOCK
+                    && tx_contexts.len() == MAX_TRANSACTIONS_PER_BLOCK
+                    && tx_contexts
+                        .iter()
+                        .all(|context| expected_hashes.contains(context.tx_hash()))
+            })
+            .return_const(Vec::new());
+
+        let mock_enclave = MockConsensusEnclave::new();
+        let tx_manager = TxManagerImpl::new(mock_enclave, mock_untrusted, logger.clone());
+
+        // Transaction `i` pays a fee of `i`.
+        for (i, tx_hash) in tx_hashes.iter().enumerate() {
+            let context = WellFormedTxContext::new(
+                i as u64,
+                tx_hash.clone(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            );
+
+            let cache_entry = CacheEntry {
+                encrypted_tx: WellFormedEncryptedTx(vec![0u8; 100]),
+                context: Arc::new(context),
+                span_context: SpanContext::empty_context(),
+            };
+
+            tx_manager.lock_cache().insert(*tx_hash, cache_entry);
+        }
+
+        assert!(tx_manager.combine(&tx_hashes).is_ok());
+    }
+
     #[test_with_logger]
     // Should return Err if any transaction is not in the cache.
     fn test_combine_err_not_in_cache(logger: Logger) {
@@ -718,6 +1530,7 @@ mod tests {
             let cache_entry = CacheEntry {
                 encrypted_tx: Default::default(),
                 context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
             };
 
             tx_manager
@@ -773,6 +1586,7 @@ mod tests {
             let cache_entry = CacheEntry {
                 encrypted_tx: Default::default(),
                 context: Arc::new(Default::default()),
+                span_context: SpanContext::empty_context(),
             };
             tx_manager.lock_cache().insert(*tx_hash, cache_entry);
         }
@@ -806,6 +1620,7 @@ mod tests {
             let cache_entry = CacheEntry {
                 encrypted_tx: Default::default(),
                 context: Arc::new(Default::default()),
+                span_context: SpanContext::empty_context(),
             };
             tx_manager.lock_cache().insert(*tx_hash, cache_entry);
         }
@@ -989,6 +1804,7 @@ mod tests {
             let cache_entry = CacheEntry {
                 encrypted_tx: Default::default(),
                 context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
             };
 
             tx_manager
@@ -1053,6 +1869,7 @@ mod tests {
             let cache_entry = CacheEntry {
                 encrypted_tx: Default::default(),
                 context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
             };
 
             tx_manager
@@ -1083,6 +1900,7 @@ mod tests {
         let cache_entry = CacheEntry {
             encrypted_tx: WellFormedEncryptedTx(vec![1, 2, 3]),
             context: Default::default(),
+            span_context: SpanContext::empty_context(),
         };
 
         let tx_hash = TxHash([1u8; 32]);
@@ -1102,6 +1920,37 @@ mod tests {
         assert_eq!(tx_manager.get_encrypted_tx(&TxHash([88u8; 32])), None);
     }
 
+    #[test_with_logger]
+    // Should return the span contexts of cached transactions, skipping others.
+    fn test_span_contexts(logger: Logger) {
+        let mock_untrusted = MockUntrustedInterfaces::new();
+        let mock_enclave = MockConsensusEnclave::new();
+        let tx_manager = TxManagerImpl::new(mock_enclave, mock_untrusted, logger.clone());
+
+        let tx_hashes: Vec<_> = (0..3).map(|i| TxHash([i as u8; 32])).collect();
+        for tx_hash in &tx_hashes {
+            let cache_entry = CacheEntry {
+                encrypted_tx: Default::default(),
+                context: Default::default(),
+                span_context: SpanContext::empty_context(),
+            };
+            tx_manager
+                .cache
+                .lock()
+                .unwrap()
+                .insert(tx_hash.clone(), cache_entry);
+        }
+
+        assert_eq!(tx_manager.span_contexts(&tx_hashes).len(), 3);
+        assert_eq!(
+            tx_manager
+                .span_contexts(&[tx_hashes[0], TxHash([88u8; 32])])
+                .len(),
+            1
+        );
+        assert!(tx_manager.span_contexts(&[]).is_empty());
+    }
+
     #[test_with_logger]
     // Should return the number of elements in the cache.
     fn test_get_num_entries(logger: Logger) {
@@ -1127,6 +1976,7 @@ mod tests {
             let cache_entry = CacheEntry {
                 encrypted_tx: Default::default(),
                 context: Arc::new(context.clone()),
+                span_context: SpanContext::empty_context(),
             };
 
             tx_manager
@@ -0,0 +1,117 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Ordering of cached transactions, used when the cache is full or when there
+//! are more candidate transactions than fit in a single block.
+
+use mc_transaction_core::tx::TxHash;
+use std::cmp::Ordering;
+
+/// The priority of a cached transaction.
+///
+/// Transactions are ordered by fee per byte (high to low), then by tombstone
+/// block (transactions that are closer to expiring first), and finally by
+/// transaction hash so that the ordering is total and deterministic.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TxPriority {
+    /// Fee included in the tx.
+    fee: u64,
+
+    /// Size of the (encrypted) tx, in bytes.
+    size: u64,
+
+    /// Tombstone block.
+    tombstone_block: u64,
+
+    /// Tx hash.
+    tx_hash: TxHash,
+}
+
+impl TxPriority {
+    /// Create a new TxPriority.
+    ///
+    /// # Arguments
+    /// * `fee` - Fee included in the tx.
+    /// * `size` - Size of the tx, in bytes. A size of zero is treated as one
+    ///   byte.
+    /// * `tombstone_block` - Tombstone block of the tx.
+    /// * `tx_hash` - Tx hash.
+    pub fn new(fee: u64, size: usize, tombstone_block: u64, tx_hash: TxHash) -> Self {
+        Self {
+            fee,
+            size: (size as u64).max(1),
+            tombstone_block,
+            tx_hash,
+        }
+    }
+
+    pub fn tx_hash(&self) -> &TxHash {
+        &self.tx_hash
+    }
+}
+
+/// Greater means "more important".
+impl Ord for TxPriority {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Compare fee per byte without dividing: a/b < c/d <=> a*d < c*b.
+        let self_fee_per_byte = self.fee as u128 * other.size as u128;
+        let other_fee_per_byte = other.fee as u128 * self.size as u128;
+
+        self_fee_per_byte
+            .cmp(&other_fee_per_byte)
+            // A transaction that expires sooner is more urgent.
+            .then_with(|| other.tombstone_block.cmp(&self.tombstone_block))
+            .then_with(|| other.tx_hash.cmp(&self.tx_hash))
+    }
+}
+
+impl PartialOrd for TxPriority {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Higher fee per byte should be higher priority, regardless of absolute fee.
+    fn test_fee_per_byte() {
+        let small_cheap = TxPriority::new(100, 100, 10, TxHash([1u8; 32]));
+        let large_expensive = TxPriority::new(150, 300, 10, TxHash([2u8; 32]));
+        let small_expensive = TxPriority::new(300, 100, 10, TxHash([3u8; 32]));
+
+        assert!(small_cheap > large_expensive);
+        assert!(small_expensive > small_cheap);
+
+        let mut priorities = vec![
+            small_cheap.clone(),
+            large_expensive.clone(),
+            small_expensive.clone(),
+        ];
+        priorities.sort();
+        assert_eq!(
+            priorities,
+            vec![large_expensive, small_cheap, small_expensive]
+        );
+    }
+
+    #[test]
+    // With equal fee per byte, the transaction closer to its tombstone block
+    // should be higher priority.
+    fn test_tombstone_proximity() {
+        let expires_soon = TxPriority::new(100, 100, 10, TxHash([1u8; 32]));
+        let expires_later = TxPriority::new(200, 200, 50, TxHash([2u8; 32]));
+        assert!(expires_soon > expires_later);
+    }
+
+    #[test]
+    // Zero-sized transactions should not cause a division by zero, and distinct
+    // transactions should never compare equal.
+    fn test_total_order() {
+        let a = TxPriority::new(100, 0, 10, TxHash([1u8; 32]));
+        let b = TxPriority::new(100, 1, 10, TxHash([2u8; 32]));
+        assert_ne!(a.cmp(&b), Ordering::Equal);
+        assert_eq!(a.cmp(&a.clone()), Ordering::Equal);
+    }
+}
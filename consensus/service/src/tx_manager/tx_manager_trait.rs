@@ -1,10 +1,11 @@
 // Copyright (c) 2018-2021 The MobileCoin Foundation
 
-use crate::tx_manager::TxManagerResult;
+use crate::tx_manager::{TxManagerResult, TxStatus};
 use mc_attest_enclave_api::{EnclaveMessage, PeerSession};
 use mc_common::HashSet;
 use mc_consensus_enclave::{TxContext, WellFormedEncryptedTx};
 use mc_transaction_core::{tx::TxHash, Block, BlockContents, BlockSignature};
+use mc_util_telemetry::SpanContext;
 
 #[cfg(test)]
 use mockall::*;
@@ -15,18 +16,52 @@ pub trait TxManager: Send {
     /// well-formed.
     fn insert(&self, tx_context: TxContext) -> TxManagerResult<TxHash>;
 
+    /// Insert a transaction referenced by the current slot. It is accepted even
+    /// if the cache is full, and is pinned.
+    fn insert_pinned(&self, tx_context: TxContext) -> TxManagerResult<TxHash>;
+
+    /// Keep the given transactions cached until the current slot completes.
+    /// Transactions that are not in the cache are skipped.
+    fn pin(&self, tx_hashes: &[TxHash]);
+
     /// Remove expired transactions from the cache and return their hashes.
     ///
     /// # Arguments
     /// * `block_index` - Current block index.
     fn remove_expired(&self, block_index: u64) -> HashSet<TxHash>;
 
+    /// Unpin all transactions, then evict the lowest-priority transactions
+    /// until the cache is within its capacity, and return their hashes.
+    fn evict_over_capacity(&self) -> HashSet<TxHash>;
+
+    /// Record that the given transactions were externalized in a block.
+    ///
+    /// # Arguments
+    /// * `tx_hashes` - Hashes of the externalized transactions.
+    /// * `block_index` - Index of the block containing them.
+    fn record_externalized(&self, tx_hashes: &[TxHash], block_index: u64);
+
+    /// Record that cached transactions were externalized in a block that was
+    /// appended by ledger sync, matching them by key images and outputs.
+    ///
+    /// # Arguments
+    /// * `block_index` - Index of the synced block.
+    /// * `block_contents` - Contents of the synced block.
+    fn record_synced_block(&self, block_index: u64, block_contents: &BlockContents);
+
+    /// The status of the given transaction, as known by this node.
+    fn tx_status(&self, tx_hash: &TxHash) -> TxStatus;
+
     /// Returns true if the cache contains the corresponding transaction.
     fn contains(&self, tx_hash: &TxHash) -> bool;
 
     /// Number of cached entries.
     fn num_entries(&self) -> usize;
 
+    /// Sort the given transactions from highest to lowest priority.
+    /// Transactions that are not in the cache are skipped.
+    fn prioritize(&self, tx_hashes: &[TxHash]) -> Vec<TxHash>;
+
     /// Validate the transaction corresponding to the given hash against the
     /// current ledger.
     fn validate(&self, tx_hash: &TxHash) -> TxManagerResult<()>;
@@ -58,4 +93,8 @@ pub trait TxManager: Send {
 
     /// Get the encrypted transaction corresponding to the given hash.
     fn get_encrypted_tx(&self, tx_hash: &TxHash) -> Option<WellFormedEncryptedTx>;
+
+    /// The span contexts in which the given transactions were inserted.
+    /// Transactions that are not in the cache are skipped.
+    fn span_contexts(&self, tx_hashes: &[TxHash]) -> Vec<SpanContext>;
 }
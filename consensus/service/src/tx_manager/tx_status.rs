@@ -0,0 +1,157 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Tracks what happened to transactions that are no longer pending, so that
+//! clients can query their status.
+
+use mc_common::HashMap;
+use mc_transaction_core::{tx::TxHash, validation::TransactionValidationError};
+use std::collections::VecDeque;
+
+/// Default number of outcomes remembered by `TxStatusHistory`.
+pub const DEFAULT_TX_STATUS_HISTORY_SIZE: usize = 100_000;
+
+/// The status of a transaction, as known by this node.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TxStatus {
+    /// This node has no record of the transaction.
+    Unknown,
+
+    /// The transaction is well-formed and cached, waiting to be externalized.
+    Pending,
+
+    /// The transaction was rejected.
+    Rejected(TransactionValidationError),
+
+    /// The transaction's tombstone block passed before it was externalized.
+    Expired,
+
+    /// The transaction was dropped because the cache was full.
+    Evicted,
+
+    /// The transaction was externalized in the given block.
+    Externalized(u64),
+}
+
+/// A bounded record of transaction outcomes. When full, the oldest outcomes
+/// are forgotten first.
+pub struct TxStatusHistory {
+    /// Outcome of each transaction.
+    statuses: HashMap<TxHash, TxStatus>,
+
+    /// Order in which outcomes were recorded, oldest first.
+    order: VecDeque<TxHash>,
+
+    /// Maximum number of outcomes to remember.
+    max_size: usize,
+}
+
+impl TxStatusHistory {
+    /// Create a new TxStatusHistory.
+    ///
+    /// # Arguments
+    /// * `max_size` - Maximum number of outcomes to remember.
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            statuses: HashMap::default(),
+            order: VecDeque::new(),
+            max_size,
+        }
+    }
+
+    /// Record the outcome of a transaction.
+    ///
+    /// An externalized transaction keeps that status: it may still expire from
+    /// the cache or fail validation afterwards (e.g. because its key images are
+    /// now spent), but neither changes what happened to it.
+    pub fn record(&mut self, tx_hash: TxHash, status: TxStatus) {
+        if let Some(TxStatus::Externalized(_)) = self.statuses.get(&tx_hash) {
+            return;
+        }
+
+        if self.statuses.insert(tx_hash, status).is_none() {
+            self.order.push_back(tx_hash);
+        }
+
+        while self.order.len() > self.max_size {
+            if let Some(oldest) = self.order.pop_front() {
+                self.statuses.remove(&oldest);
+            }
+        }
+    }
+
+    /// Forget the outcome of a transaction, e.g. because it has been
+    /// re-submitted and is pending again.
+    pub fn forget(&mut self, tx_hash: &TxHash) {
+        if let Some(TxStatus::Externalized(_)) = self.statuses.get(tx_hash) {
+            return;
+        }
+
+        if self.statuses.remove(tx_hash).is_some() {
+            self.order.retain(|hash| hash != tx_hash);
+        }
+    }
+
+    /// The recorded outcome of a transaction, if any.
+    pub fn get(&self, tx_hash: &TxHash) -> Option<&TxStatus> {
+        self.statuses.get(tx_hash)
+    }
+
+    /// Number of recorded outcomes.
+    pub fn len(&self) -> usize {
+        self.statuses.len()
+    }
+
+    /// Returns true if no outcomes are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.statuses.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Externalized transactions should keep that status.
+    fn test_externalized_is_final() {
+        let mut history = TxStatusHistory::new(10);
+        let tx_hash = TxHash([1u8; 32]);
+
+        history.record(tx_hash, TxStatus::Externalized(7));
+        history.record(tx_hash, TxStatus::Expired);
+        history.record(
+            tx_hash,
+            TxStatus::Rejected(TransactionValidationError::ContainsSpentKeyImage),
+        );
+        history.forget(&tx_hash);
+
+        assert_eq!(history.get(&tx_hash), Some(&TxStatus::Externalized(7)));
+    }
+
+    #[test]
+    // Should forget the oldest outcomes first.
+    fn test_bounded() {
+        let mut history = TxStatusHistory::new(3);
+        for i in 0..5u8 {
+            history.record(TxHash([i; 32]), TxStatus::Expired);
+        }
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.get(&TxHash([0u8; 32])), None);
+        assert_eq!(history.get(&TxHash([1u8; 32])), None);
+        assert_eq!(history.get(&TxHash([4u8; 32])), Some(&TxStatus::Expired));
+    }
+
+    #[test]
+    // A non-final outcome can be forgotten.
+    fn test_forget() {
+        let mut history = TxStatusHistory::new(3);
+        let tx_hash = TxHash([1u8; 32]);
+
+        history.record(tx_hash, TxStatus::Evicted);
+        history.forget(&tx_hash);
+
+        assert!(history.is_empty());
+        assert_eq!(history.get(&tx_hash), None);
+    }
+}
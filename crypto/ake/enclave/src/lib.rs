@@ -47,6 +47,11 @@ pub use identity::{EnclaveIdentity, NullIdentity};
 /// State associated to Attested Authenticated Key Exchange held by an enclave,
 /// including for peers and for clients.
 /// This also includes cached IAS reports, and data that goes in those reports
+///
+/// Peer and client sessions rotate their writer keys in-band once they reach
+/// the default `RekeyPolicy` message limit, or when the remote party rotates
+/// its own. Enclaves have no trusted clock, so the policy's age limit is left
+/// to the remote party.
 pub struct AkeEnclaveState<EI: EnclaveIdentity> {
     /// ResponderId used for peer connections
     peer_self_id: Mutex<Option<ResponderId>>,
@@ -231,8 +236,12 @@ impl<EI: EnclaveIdentity> AkeEnclaveState<EI> {
         Ok(PeerAuthRequest::from(msg_vec))
     }
 
-    /// Accept a peer connection
-    pub fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
+    /// Accept a peer connection, returning the peer's verification report
+    /// along with the response and session
+    pub fn peer_accept(
+        &self,
+        req: PeerAuthRequest,
+    ) -> Result<(PeerAuthResponse, PeerSession, VerificationReport)> {
         let local_identity = self.kex_identity.clone();
         let ias_report = self.get_ias_report()?;
 
@@ -252,7 +261,8 @@ impl<EI: EnclaveIdentity> AkeEnclaveState<EI> {
 
         // Advance the state machine
         let mut csprng = McRng::default();
-        let (responder, auth_response) = responder.try_next(&mut csprng, auth_request)?;
+        let (responder, (auth_response, verification_report)) =
+            responder.try_next(&mut csprng, auth_request)?;
         let session_id = PeerSession::from(responder.binding());
 
         // This session is established as far as we are concerned.
@@ -261,7 +271,11 @@ impl<EI: EnclaveIdentity> AkeEnclaveState<EI> {
         // Massage the state machine output into the response message
         let auth_response: Vec<u8> = auth_response.into();
 
-        Ok((PeerAuthResponse::from(auth_response), session_id))
+        Ok((
+            PeerAuthResponse::from(auth_response),
+            session_id,
+            verification_report,
+        ))
     }
 
     /// Complete the connection to a peer that our accepted our PeerAuthRequest
@@ -7,6 +7,8 @@ use alloc::vec;
 use aead::{AeadMut, Error as AeadError, NewAead, Payload};
 use aes_gcm::Aes256Gcm;
 use alloc::vec::Vec;
+#[cfg(feature = "chacha20poly1305")]
+use chacha20poly1305::ChaCha20Poly1305;
 use core::cmp::min;
 use failure::Fail;
 use generic_array::{typenum::Unsigned, GenericArray};
@@ -113,6 +115,16 @@ pub trait NoiseCipher: AeadMut + NewAead + Sized {
 
 impl NoiseCipher for Aes256Gcm {}
 
+/// ChaChaPoly encodes its nonce counter as little-endian bytes, as described in
+/// [section 12.3](http://noiseprotocol.org/noise.html#the-chachapoly-cipher-functions)
+/// of the specification.
+#[cfg(feature = "chacha20poly1305")]
+impl NoiseCipher for ChaCha20Poly1305 {
+    fn nonce_to_arr(nonce: u64) -> GenericArray<u8, Self::NonceSize> {
+        Self::nonce_bytes_to_arr(&nonce.to_le_bytes()[..])
+    }
+}
+
 /// The Noise Protocol CipherState object, modified to support AEADs with
 /// differing key/nonce lengths.
 ///
@@ -400,4 +412,19 @@ mod test {
         assert_eq!(encryptor.nonce, 2);
         assert_eq!(encryptor.bytes_sent, key.len() as u64);
     }
+
+    #[test]
+    /// AES-GCM nonces are big-endian, padded on the left.
+    fn aesgcm_nonce() {
+        let nonce = Aes256Gcm::nonce_to_arr(0x0102);
+        assert_eq!(nonce.as_slice(), &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+    }
+
+    #[cfg(feature = "chacha20poly1305")]
+    #[test]
+    /// ChaChaPoly nonces are little-endian, padded on the left.
+    fn chachapoly_nonce() {
+        let nonce = ChaCha20Poly1305::nonce_to_arr(0x0102);
+        assert_eq!(nonce.as_slice(), &[0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0]);
+    }
 }
@@ -595,7 +595,7 @@ mod test {
     extern crate std;
 
     use super::*;
-    use crate::patterns::{HandshakeIX, HandshakeNX};
+    use crate::patterns::{HandshakeIK, HandshakeIX, HandshakeNX};
     use aes_gcm::Aes256Gcm;
     use mc_crypto_keys::{X25519Private, X25519};
     use rand_core::SeedableRng;
@@ -855,4 +855,23 @@ mod test {
             .expect("Initiator could not decrypt message2");
         assert_eq!(message2.as_bytes(), decrypted2.as_slice());
     }
+
+    #[test]
+    fn ik_initiator_requires_responder_identity() {
+        let protocol_name = ProtocolName::<HandshakeIK, X25519, Aes256Gcm, Sha512>::default();
+        let mut csprng = Hc128Rng::seed_from_u64(0);
+        let initiator_static = X25519Private::from_random(&mut csprng);
+
+        let result = HandshakeState::new(
+            true,
+            protocol_name,
+            &[],
+            Some(initiator_static),
+            None,
+            None,
+            None,
+        );
+
+        assert_eq!(result.err(), Some(HandshakeError::MissingResponderIdentity));
+    }
 }
@@ -17,7 +17,7 @@ mod symmetric_state;
 pub use crate::{
     cipher_state::{CipherError, CipherState, NoiseCipher},
     handshake_state::{HandshakeError, HandshakeOutput, HandshakeState, HandshakeStatus},
-    patterns::{HandshakeIX, HandshakeNX, HandshakePattern},
+    patterns::{HandshakeIK, HandshakeIX, HandshakeNX, HandshakePattern, HandshakeXX},
     protocol_name::{ProtocolName, ProtocolNameError},
     symmetric_state::SymmetricOutput,
 };
@@ -230,6 +230,45 @@ impl_handshake_patterns! {
         // msg 1: request
         MessagePattern::Initiator(vec![Token::Ephemeral]),
     ];
+    HandshakeXX, "XX", PreMessageToken::None, PreMessageToken::None, vec![
+        // msg 3: initiator identity
+        MessagePattern::Initiator(vec![Token::Static, Token::KexStaticEphemeral]),
+        // msg 2: response
+        MessagePattern::Responder(vec![
+            // token 1
+            Token::Ephemeral,
+            // token 2
+            Token::KexEphemeralEphemeral,
+            // token 3
+            Token::Static,
+            // token 4
+            Token::KexEphemeralStatic,
+        ]),
+        // msg 1: request
+        MessagePattern::Initiator(vec![Token::Ephemeral]),
+    ];
+    HandshakeIK, "IK", PreMessageToken::None, PreMessageToken::Static, vec![
+        // msg 2: response
+        MessagePattern::Responder(vec![
+            // token 1
+            Token::Ephemeral,
+            // token 2
+            Token::KexEphemeralEphemeral,
+            // token 3
+            Token::KexStaticEphemeral,
+        ]),
+        // msg 1: request
+        MessagePattern::Initiator(vec![
+            // token 1
+            Token::Ephemeral,
+            // token 2
+            Token::KexEphemeralStatic,
+            // token 3
+            Token::Static,
+            // token 4
+            Token::KexStaticStatic,
+        ]),
+    ];
 }
 
 #[cfg(test)]
@@ -248,5 +287,15 @@ mod test {
             format!("{}", HandshakeNX::default()),
             String::from("NX:\n  -> e\n  <- e, ee, s, es\n")
         );
+
+        assert_eq!(
+            format!("{}", HandshakeXX::default()),
+            String::from("XX:\n  -> e\n  <- e, ee, s, es\n  -> s, se\n")
+        );
+
+        assert_eq!(
+            format!("{}", HandshakeIK::default()),
+            String::from("IK:\n  <- s\n  ...\n  -> e, es, s, ss\n  <- e, ee, se\n")
+        );
     }
 }
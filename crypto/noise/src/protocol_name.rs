@@ -2,9 +2,11 @@
 
 //! A set of static ZWTs designed to aid the handling of noise protocol strings.
 
-use crate::patterns::{HandshakeIX, HandshakeNX, HandshakePattern};
+use crate::patterns::{HandshakeIK, HandshakeIX, HandshakeNX, HandshakePattern, HandshakeXX};
 use aead::AeadMut;
 use aes_gcm::Aes256Gcm;
+#[cfg(feature = "chacha20poly1305")]
+use chacha20poly1305::ChaCha20Poly1305;
 use core::marker::PhantomData;
 use digest::{FixedOutput, Update};
 use failure::Fail;
@@ -120,6 +122,16 @@ macro_rules! impl_protocol_names {
 impl_protocol_names! {
     "Noise_IX_25519_AESGCM_SHA512", HandshakeIX, X25519, Aes256Gcm, Sha512;
     "Noise_NX_25519_AESGCM_SHA512", HandshakeNX, X25519, Aes256Gcm, Sha512;
+    "Noise_XX_25519_AESGCM_SHA512", HandshakeXX, X25519, Aes256Gcm, Sha512;
+    "Noise_IK_25519_AESGCM_SHA512", HandshakeIK, X25519, Aes256Gcm, Sha512;
+}
+
+#[cfg(feature = "chacha20poly1305")]
+impl_protocol_names! {
+    "Noise_IX_25519_ChaChaPoly_SHA512", HandshakeIX, X25519, ChaCha20Poly1305, Sha512;
+    "Noise_NX_25519_ChaChaPoly_SHA512", HandshakeNX, X25519, ChaCha20Poly1305, Sha512;
+    "Noise_XX_25519_ChaChaPoly_SHA512", HandshakeXX, X25519, ChaCha20Poly1305, Sha512;
+    "Noise_IK_25519_ChaChaPoly_SHA512", HandshakeIK, X25519, ChaCha20Poly1305, Sha512;
 }
 
 #[cfg(test)]
@@ -157,6 +169,49 @@ mod test {
         assert_eq!(name, new_name);
     }
 
+    #[test]
+    fn mobilecoin_xx_25519_aesgcm_sha512_from_str() {
+        let name = "Noise_XX_25519_AESGCM_SHA512";
+        let parsed_name = ProtocolName::<
+            HandshakeXX,
+            X25519,    // Kex
+            Aes256Gcm, // AEAD
+            Sha512,    // Digest
+        >::from_str(name)
+        .unwrap_or_else(|_| panic!("Could not parse '{}'", name));
+        let new_name: &str = parsed_name.as_ref();
+        assert_eq!(name, new_name);
+    }
+
+    #[test]
+    fn mobilecoin_ik_25519_aesgcm_sha512_from_str() {
+        let name = "Noise_IK_25519_AESGCM_SHA512";
+        let parsed_name = ProtocolName::<
+            HandshakeIK,
+            X25519,    // Kex
+            Aes256Gcm, // AEAD
+            Sha512,    // Digest
+        >::from_str(name)
+        .unwrap_or_else(|_| panic!("Could not parse '{}'", name));
+        let new_name: &str = parsed_name.as_ref();
+        assert_eq!(name, new_name);
+    }
+
+    #[cfg(feature = "chacha20poly1305")]
+    #[test]
+    fn mobilecoin_xx_25519_chachapoly_sha512_from_str() {
+        let name = "Noise_XX_25519_ChaChaPoly_SHA512";
+        let parsed_name = ProtocolName::<
+            HandshakeXX,
+            X25519,           // Kex
+            ChaCha20Poly1305, // AEAD
+            Sha512,           // Digest
+        >::from_str(name)
+        .unwrap_or_else(|_| panic!("Could not parse '{}'", name));
+        let new_name: &str = parsed_name.as_ref();
+        assert_eq!(name, new_name);
+    }
+
     #[test]
     #[should_panic(
         expected = "Could not parse 'McNoise_XX_25519_CHACHA_SHA256': The string given does not match the type in question"
@@ -0,0 +1,266 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Known-answer tests against the cacophony Noise test vectors.
+//!
+//! The data in `tests/data/cacophony.json` is the subset of the cacophony
+//! vectors (as distributed with the `snow` crate) which use the patterns,
+//! key exchange, ciphers, and hash supported by this crate.
+
+use aead::{AeadMut, NewAead};
+use aes_gcm::Aes256Gcm;
+#[cfg(feature = "chacha20poly1305")]
+use chacha20poly1305::ChaCha20Poly1305;
+use core::{convert::TryFrom, str::FromStr};
+use mc_crypto_keys::{X25519Private, X25519Public, X25519};
+use mc_crypto_noise::{
+    HandshakeIK, HandshakeIX, HandshakeNX, HandshakePattern, HandshakeState, HandshakeStatus,
+    HandshakeXX, NoiseCipher, ProtocolName,
+};
+use rand_core::{impls, CryptoRng, Error as RandError, RngCore};
+use serde::Deserialize;
+use sha2::Sha512;
+
+#[derive(Deserialize)]
+struct TestVectors {
+    vectors: Vec<TestVector>,
+}
+
+#[derive(Deserialize)]
+struct TestVector {
+    protocol_name: String,
+    init_prologue: String,
+    init_static: Option<String>,
+    init_ephemeral: String,
+    init_remote_static: Option<String>,
+    resp_prologue: String,
+    resp_static: Option<String>,
+    resp_ephemeral: String,
+    resp_remote_static: Option<String>,
+    handshake_hash: String,
+    messages: Vec<TestMessage>,
+}
+
+#[derive(Deserialize)]
+struct TestMessage {
+    payload: String,
+    ciphertext: String,
+}
+
+type Status<Cipher> = HandshakeStatus<X25519, Cipher, Sha512>;
+
+/// An RNG which hands out the ephemeral private key given in a test vector.
+struct FixedRng(Vec<u8>);
+
+impl RngCore for FixedRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        assert!(dest.len() <= self.0.len(), "Ephemeral key requested twice");
+        let rest = self.0.split_off(dest.len());
+        dest.copy_from_slice(&self.0);
+        self.0 = rest;
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), RandError> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for FixedRng {}
+
+fn unhex(value: &str) -> Vec<u8> {
+    hex::decode(value).expect("Invalid hex in test vector")
+}
+
+fn private_key(value: &str) -> X25519Private {
+    X25519Private::try_from(&unhex(value)[..]).expect("Invalid private key in test vector")
+}
+
+fn public_key(value: &str) -> X25519Public {
+    X25519Public::try_from(&unhex(value)[..]).expect("Invalid public key in test vector")
+}
+
+/// Send a single message from the sender to the receiver, using either the
+/// handshake or the resulting transport ciphers.
+fn exchange<Cipher>(
+    sender: &mut Option<Status<Cipher>>,
+    receiver: &mut Option<Status<Cipher>>,
+    rng: &mut FixedRng,
+    initiator_sends: bool,
+    message: &TestMessage,
+) where
+    Cipher: AeadMut + NewAead + NoiseCipher + Sized,
+{
+    let payload = unhex(&message.payload);
+    let expected = unhex(&message.ciphertext);
+
+    match (sender.take(), receiver.take()) {
+        (Some(HandshakeStatus::InProgress(writer)), Some(HandshakeStatus::InProgress(reader))) => {
+            let written = writer
+                .write_message(rng, &payload)
+                .expect("Could not write handshake message");
+            assert_eq!(expected, written.payload);
+
+            let read = reader
+                .read_message(&written.payload)
+                .expect("Could not read handshake message");
+            assert_eq!(payload, read.payload);
+
+            *sender = Some(written.status);
+            *receiver = Some(read.status);
+        }
+        (
+            Some(HandshakeStatus::Complete(mut writer)),
+            Some(HandshakeStatus::Complete(mut reader)),
+        ) => {
+            let (encryptor, decryptor) = if initiator_sends {
+                (&mut writer.initiator_cipher, &mut reader.initiator_cipher)
+            } else {
+                (&mut writer.responder_cipher, &mut reader.responder_cipher)
+            };
+
+            let ciphertext = encryptor
+                .encrypt_with_ad(&[], &payload)
+                .expect("Could not encrypt transport message");
+            assert_eq!(expected, ciphertext);
+
+            let plaintext = decryptor
+                .decrypt_with_ad(&[], &ciphertext)
+                .expect("Could not decrypt transport message");
+            assert_eq!(payload, plaintext);
+
+            *sender = Some(HandshakeStatus::Complete(writer));
+            *receiver = Some(HandshakeStatus::Complete(reader));
+        }
+        _ => panic!("Handshake completed for only one party"),
+    }
+}
+
+/// Run the named test vector through an initiator and responder.
+fn run_vector<Handshake, Cipher>(name: &str)
+where
+    Handshake: HandshakePattern,
+    Cipher: AeadMut + NewAead + NoiseCipher + Sized,
+    ProtocolName<Handshake, X25519, Cipher, Sha512>: AsRef<str> + FromStr,
+    <ProtocolName<Handshake, X25519, Cipher, Sha512> as FromStr>::Err: core::fmt::Debug,
+{
+    let test_vectors: TestVectors =
+        serde_json::from_str(include_str!("data/cacophony.json")).expect("Invalid test vectors");
+    let vector = test_vectors
+        .vectors
+        .into_iter()
+        .find(|vector| vector.protocol_name == name)
+        .unwrap_or_else(|| panic!("No test vector for {}", name));
+
+    let protocol_name = ProtocolName::<Handshake, X25519, Cipher, Sha512>::from_str(name)
+        .expect("Could not parse protocol name");
+
+    let initiator = HandshakeState::new(
+        true,
+        protocol_name.clone(),
+        &unhex(&vector.init_prologue),
+        vector.init_static.as_deref().map(private_key),
+        None,
+        vector.init_remote_static.as_deref().map(public_key),
+        None,
+    )
+    .expect("Could not create initiator");
+    let responder = HandshakeState::new(
+        false,
+        protocol_name,
+        &unhex(&vector.resp_prologue),
+        vector.resp_static.as_deref().map(private_key),
+        None,
+        vector.resp_remote_static.as_deref().map(public_key),
+        None,
+    )
+    .expect("Could not create responder");
+
+    let mut initiator_rng = FixedRng(unhex(&vector.init_ephemeral));
+    let mut responder_rng = FixedRng(unhex(&vector.resp_ephemeral));
+    let mut initiator = Some(HandshakeStatus::InProgress(initiator));
+    let mut responder = Some(HandshakeStatus::InProgress(responder));
+
+    // Messages alternate between the parties, both during the handshake and
+    // afterwards.
+    for (index, message) in vector.messages.iter().enumerate() {
+        if index % 2 == 0 {
+            exchange(
+                &mut initiator,
+                &mut responder,
+                &mut initiator_rng,
+                true,
+                message,
+            );
+        } else {
+            exchange(
+                &mut responder,
+                &mut initiator,
+                &mut responder_rng,
+                false,
+                message,
+            );
+        }
+    }
+
+    let handshake_hash = unhex(&vector.handshake_hash);
+    for status in [initiator, responder].iter() {
+        match status {
+            Some(HandshakeStatus::Complete(output)) => {
+                assert_eq!(handshake_hash, output.channel_binding)
+            }
+            _ => panic!("Handshake did not complete"),
+        }
+    }
+}
+
+#[test]
+fn noise_ix_25519_aesgcm_sha512() {
+    run_vector::<HandshakeIX, Aes256Gcm>("Noise_IX_25519_AESGCM_SHA512");
+}
+
+#[test]
+fn noise_nx_25519_aesgcm_sha512() {
+    run_vector::<HandshakeNX, Aes256Gcm>("Noise_NX_25519_AESGCM_SHA512");
+}
+
+#[test]
+fn noise_xx_25519_aesgcm_sha512() {
+    run_vector::<HandshakeXX, Aes256Gcm>("Noise_XX_25519_AESGCM_SHA512");
+}
+
+#[test]
+fn noise_ik_25519_aesgcm_sha512() {
+    run_vector::<HandshakeIK, Aes256Gcm>("Noise_IK_25519_AESGCM_SHA512");
+}
+
+#[cfg(feature = "chacha20poly1305")]
+#[test]
+fn noise_ix_25519_chachapoly_sha512() {
+    run_vector::<HandshakeIX, ChaCha20Poly1305>("Noise_IX_25519_ChaChaPoly_SHA512");
+}
+
+#[cfg(feature = "chacha20poly1305")]
+#[test]
+fn noise_nx_25519_chachapoly_sha512() {
+    run_vector::<HandshakeNX, ChaCha20Poly1305>("Noise_NX_25519_ChaChaPoly_SHA512");
+}
+
+#[cfg(feature = "chacha20poly1305")]
+#[test]
+fn noise_xx_25519_chachapoly_sha512() {
+    run_vector::<HandshakeXX, ChaCha20Poly1305>("Noise_XX_25519_ChaChaPoly_SHA512");
+}
+
+#[cfg(feature = "chacha20poly1305")]
+#[test]
+fn noise_ik_25519_chachapoly_sha512() {
+    run_vector::<HandshakeIK, ChaCha20Poly1305>("Noise_IK_25519_ChaChaPoly_SHA512");
+}
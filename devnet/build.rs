@@ -0,0 +1,5 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+fn main() {
+    mc_sgx_build::handle_sgx_sim_feature();
+}
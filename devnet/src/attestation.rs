@@ -0,0 +1,109 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Simulated attestation evidence for devnet nodes.
+//!
+//! Clients such as mobilecoind attest to a consensus node before talking to
+//! it. Devnet nodes have no enclave, so each node presents a report for a
+//! canned quote whose report data is replaced with the node's identity, as
+//! signed by the simulated IAS. The matching [AttestationPolicy] trusts the
+//! simulated IAS root and the signer of the canned quote.
+
+use crate::error::DevnetError;
+use mc_attest_core::{
+    AttestationPolicy, EnclavePolicy, Quote, QuoteError, VerificationReport, IAS_SIM_ROOT_ANCHORS,
+};
+use mc_attest_net::{Client, RaClient};
+use mc_crypto_keys::X25519Public;
+use mc_util_encodings::{FromBase64, ToX64};
+use std::convert::TryFrom;
+
+/// A base64-encoded quote from a debug enclave.
+const QUOTE: &str = include_str!("../data/quote.txt");
+
+/// The offset of the report data within a quote: the 48-byte quote header is
+/// followed by the report body, which ends with 64 bytes of report data.
+const REPORT_DATA_OFFSET: usize = 368;
+
+fn quote() -> Result<Quote, DevnetError> {
+    Ok(Quote::from_base64(QUOTE.trim())?)
+}
+
+/// Create a simulated IAS report for a node with the given identity.
+pub fn ias_report(identity: &X25519Public) -> Result<VerificationReport, DevnetError> {
+    let mut quote_data = quote()?.to_x64_vec();
+    let identity_bytes: &[u8] = identity.as_ref();
+    quote_data[REPORT_DATA_OFFSET..REPORT_DATA_OFFSET + identity_bytes.len()]
+        .copy_from_slice(identity_bytes);
+    let quote = Quote::try_from(quote_data.as_slice())?;
+
+    Ok(Client::new("")?.verify_quote(&quote, None)?)
+}
+
+/// The policy clients should use to attest to devnet nodes.
+pub fn attestation_policy() -> Result<AttestationPolicy, DevnetError> {
+    let report_body = quote()?.report_body().map_err(QuoteError::from)?;
+
+    Ok(AttestationPolicy {
+        trust_anchors: vec![IAS_SIM_ROOT_ANCHORS.to_owned()],
+        allow_debug: Some(true),
+        enclaves: vec![EnclavePolicy {
+            mr_signer: Some(hex::encode(report_body.mr_signer())),
+            product_id: Some(report_body.product_id()),
+            minimum_svn: Some(report_body.security_version()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Ensure the policy survives being written to disk for mobilecoind.
+    #[test]
+    fn policy_json_roundtrip() {
+        let policy = attestation_policy().expect("Could not create policy");
+        let json = serde_json::to_string(&policy).expect("Could not serialize policy");
+        assert_eq!(
+            AttestationPolicy::from_json(&json).expect("Could not parse policy"),
+            policy
+        );
+        policy.verifier().expect("Could not create verifier");
+    }
+
+    /// Ensure the policy accepts a node's report only for that node's
+    /// identity.
+    #[cfg(feature = "sgx-sim")]
+    #[test]
+    fn policy_accepts_report() {
+        use mc_attest_core::ReportDataMask;
+        use mc_crypto_keys::X25519Private;
+        use mc_crypto_rand::McRng;
+        use mc_util_from_random::FromRandom;
+
+        let mut csprng = McRng::default();
+        let identity = X25519Public::from(&X25519Private::from_random(&mut csprng));
+        let other = X25519Public::from(&X25519Private::from_random(&mut csprng));
+        let report = ias_report(&identity).expect("Could not create report");
+        let policy = attestation_policy().expect("Could not create policy");
+
+        let identity_bytes: &[u8] = identity.as_ref();
+        let mask = ReportDataMask::try_from(identity_bytes).expect("Invalid mask");
+        policy
+            .verifier()
+            .expect("Could not create verifier")
+            .report_data(&mask)
+            .verify(&report)
+            .expect("Report was not accepted");
+
+        let other_bytes: &[u8] = other.as_ref();
+        let mask = ReportDataMask::try_from(other_bytes).expect("Invalid mask");
+        assert!(policy
+            .verifier()
+            .expect("Could not create verifier")
+            .report_data(&mask)
+            .verify(&report)
+            .is_err());
+    }
+}
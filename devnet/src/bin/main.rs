@@ -0,0 +1,45 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Run a local development network until one of its processes exits.
+
+use mc_common::logger::{create_app_logger, log, o};
+use mc_devnet::{config::DevnetConfig, Devnet};
+use std::process::exit;
+use structopt::StructOpt;
+
+fn main() {
+    mc_common::setup_panic_handler();
+    let (logger, _global_logger_guard) = create_app_logger(o!());
+
+    let config = DevnetConfig::from_args();
+
+    let mut devnet = Devnet::start(config, logger.clone()).unwrap_or_else(|err| {
+        log::crit!(logger, "Could not start devnet: {}", err);
+        exit(1);
+    });
+
+    for node in devnet.nodes() {
+        log::info!(logger, "Node {}: {}", node.index, node.client_uri());
+    }
+    if let Some(uri) = devnet.mobilecoind_uri() {
+        log::info!(logger, "mobilecoind: {}", uri);
+    }
+    if let Some(uri) = devnet.watcher_uri() {
+        log::info!(logger, "watcher: {}", uri);
+    }
+    log::info!(
+        logger,
+        "Account keys: {:?}, attestation policy: {:?}",
+        devnet.keys_dir(),
+        devnet.attestation_policy_path()
+    );
+
+    // Stop everything else before exiting, so no child processes are left
+    // behind.
+    let result = devnet.wait();
+    drop(devnet);
+    if let Err(err) = result {
+        log::crit!(logger, "{}", err);
+        exit(1);
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Configuration parameters for the devnet.
+
+use std::{path::PathBuf, time::Duration};
+use structopt::StructOpt;
+
+/// Command line configuration.
+#[derive(Clone, Debug, StructOpt)]
+#[structopt(
+    name = "devnet",
+    about = "Run a local network of consensus services backed by a mock enclave."
+)]
+pub struct DevnetConfig {
+    /// Directory for keys, ledgers and configuration files.
+    #[structopt(long, default_value = "/tmp/mc-devnet", parse(from_os_str))]
+    pub work_dir: PathBuf,
+
+    /// Delete the work directory first, if it already exists.
+    #[structopt(long)]
+    pub clean: bool,
+
+    /// Number of consensus nodes.
+    #[structopt(long, default_value = "3")]
+    pub nodes: usize,
+
+    /// How many of its peers each node's quorum set requires. Defaults to
+    /// enough to tolerate a third of them failing.
+    #[structopt(long)]
+    pub threshold: Option<u32>,
+
+    /// First client port. Peer and admin ports start 100 and 200 above it.
+    #[structopt(long, default_value = "3200")]
+    pub base_port: u16,
+
+    /// Number of accounts to generate keys for, and fund in the origin ledger.
+    #[structopt(long, default_value = "10")]
+    pub num_keys: usize,
+
+    /// Number of outputs each account receives in each origin block.
+    #[structopt(long, default_value = "100")]
+    pub outputs_per_key: usize,
+
+    /// Number of blocks in the origin ledger, so that clients starting from
+    /// an empty ledger have a chain to sync.
+    #[structopt(long, default_value = "1")]
+    pub num_blocks: usize,
+
+    /// Minimum fee the nodes accept.
+    #[structopt(long)]
+    pub minimum_fee: Option<u64>,
+
+    /// Start a mobilecoind connected to every node.
+    #[structopt(long)]
+    pub mobilecoind: bool,
+
+    /// Port the mobilecoind API listens on.
+    #[structopt(long, default_value = "4444")]
+    pub mobilecoind_port: u16,
+
+    /// Start a watcher which syncs and checks the block signatures of every
+    /// node.
+    #[structopt(long)]
+    pub watcher: bool,
+
+    /// Port the watcher API listens on.
+    #[structopt(long, default_value = "4445")]
+    pub watcher_port: u16,
+
+    /// Directory containing the ledger-distribution, mobilecoind and watcher
+    /// binaries.
+    /// Defaults to the directory of this binary.
+    #[structopt(long, parse(from_os_str))]
+    pub bin_dir: Option<PathBuf>,
+
+    /// How often to check that the child processes are still running, in
+    /// seconds.
+    #[structopt(long, default_value = "1", parse(try_from_str=parse_duration_in_seconds))]
+    pub poll_interval: Duration,
+}
+
+fn parse_duration_in_seconds(src: &str) -> Result<Duration, std::num::ParseIntError> {
+    Ok(Duration::from_secs(src.parse()?))
+}
@@ -0,0 +1,488 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Starting and stopping a devnet.
+
+use crate::{
+    attestation::attestation_policy,
+    config::DevnetConfig,
+    enclave::DevnetEnclave,
+    error::DevnetError,
+    network::{default_threshold, network_config, NodeLayout},
+};
+use mc_attest_core::ProviderId;
+use mc_attest_net::{Client, RaClient};
+use mc_common::{
+    logger::{log, o, Logger},
+    time::SystemTimeProvider,
+};
+use mc_consensus_enclave_mock::ConsensusEnclave;
+use mc_consensus_service::{
+    config::Config, consensus_service::ConsensusService, tx_manager::TxManagerImpl,
+    validators::DefaultTxManagerUntrustedInterfaces,
+};
+use mc_crypto_keys::Ed25519Pair;
+use mc_crypto_rand::McRng;
+use mc_ledger_db::LedgerDB;
+use mc_util_from_random::FromRandom;
+use mc_util_generate_sample_ledger::bootstrap_ledger;
+use mc_util_keyfile::keygen;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// The binary which publishes each node's ledger to its block archive.
+pub const LEDGER_DISTRIBUTION_BIN: &str = "ledger-distribution";
+
+/// The mobilecoind binary.
+pub const MOBILECOIND_BIN: &str = "mobilecoind";
+
+/// The standalone watcher binary.
+pub const WATCHER_BIN: &str = "mc-watcher";
+
+type NodeTxManager = TxManagerImpl<DevnetEnclave, DefaultTxManagerUntrustedInterfaces<LedgerDB>>;
+type NodeService = ConsensusService<DevnetEnclave, Client, NodeTxManager>;
+
+/// A running devnet. Dropping it stops every node and child process.
+pub struct Devnet {
+    config: DevnetConfig,
+    bin_dir: PathBuf,
+    nodes: Vec<NodeLayout>,
+    services: Vec<Option<NodeService>>,
+    children: Vec<(String, Child)>,
+    logger: Logger,
+}
+
+impl Devnet {
+    /// Generate account keys, an origin ledger and the configuration of every
+    /// node in the work directory, then start the network.
+    pub fn start(config: DevnetConfig, logger: Logger) -> Result<Self, DevnetError> {
+        if !cfg!(feature = "sgx-sim") {
+            return Err(DevnetError::NotSimulation);
+        }
+        if config.nodes < 2 {
+            return Err(DevnetError::TooFewNodes(config.nodes));
+        }
+        let num_peers = config.nodes - 1;
+        let threshold = config
+            .threshold
+            .unwrap_or_else(|| default_threshold(num_peers));
+        if threshold == 0 || threshold as usize > num_peers {
+            return Err(DevnetError::InvalidThreshold(threshold, num_peers));
+        }
+
+        let bin_dir = match &config.bin_dir {
+            Some(bin_dir) => bin_dir.clone(),
+            None => env::current_exe()?
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        };
+        let mut binaries = vec![LEDGER_DISTRIBUTION_BIN];
+        if config.mobilecoind {
+            binaries.push(MOBILECOIND_BIN);
+        }
+        if config.watcher {
+            binaries.push(WATCHER_BIN);
+        }
+        for binary in binaries {
+            if !bin_dir.join(binary).exists() {
+                return Err(DevnetError::MissingBinary(binary.to_owned()));
+            }
+        }
+
+        if config.work_dir.exists() {
+            if !config.clean {
+                return Err(DevnetError::WorkDirExists(
+                    config.work_dir.display().to_string(),
+                ));
+            }
+            fs::remove_dir_all(&config.work_dir)?;
+        }
+        fs::create_dir_all(&config.work_dir)?;
+
+        let mut devnet = Self {
+            nodes: NodeLayout::for_network(config.nodes, config.base_port),
+            config,
+            bin_dir,
+            services: Vec::new(),
+            children: Vec::new(),
+            logger,
+        };
+        devnet.bootstrap()?;
+        devnet.start_nodes(threshold)?;
+        if devnet.config.mobilecoind {
+            devnet.start_mobilecoind()?;
+        }
+        if devnet.config.watcher {
+            devnet.start_watcher()?;
+        }
+
+        Ok(devnet)
+    }
+
+    /// The layout of each node.
+    pub fn nodes(&self) -> &[NodeLayout] {
+        &self.nodes
+    }
+
+    /// The directory containing the account keys funded in the origin block.
+    pub fn keys_dir(&self) -> PathBuf {
+        self.config.work_dir.join("keys")
+    }
+
+    /// The directory containing the origin ledger.
+    pub fn origin_dir(&self) -> PathBuf {
+        self.config.work_dir.join("origin")
+    }
+
+    /// The attestation policy clients should use to connect to the nodes.
+    pub fn attestation_policy_path(&self) -> PathBuf {
+        self.config.work_dir.join("attestation-policy.json")
+    }
+
+    /// The URI of the mobilecoind API, if mobilecoind is running.
+    pub fn mobilecoind_uri(&self) -> Option<String> {
+        if self.config.mobilecoind {
+            Some(format!(
+                "insecure-mobilecoind://localhost:{}/",
+                self.config.mobilecoind_port
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// The URI of the watcher API, if the watcher is running.
+    pub fn watcher_uri(&self) -> Option<String> {
+        if self.config.watcher {
+            Some(format!(
+                "insecure-watcher://localhost:{}/",
+                self.config.watcher_port
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// The watcher database, which can be opened read-only while the watcher
+    /// is running.
+    pub fn watcher_db_path(&self) -> PathBuf {
+        self.config.work_dir.join("watcher").join("watcher-db")
+    }
+
+    /// Block until one of the child processes exits, or one of the nodes'
+    /// worker threads stops.
+    pub fn wait(&mut self) -> Result<(), DevnetError> {
+        loop {
+            for service in self.services.iter().flatten() {
+                service.check_threads()?;
+            }
+            for (name, child) in self.children.iter_mut() {
+                if let Some(status) = child.try_wait()? {
+                    return Err(DevnetError::ChildExited(name.clone(), status));
+                }
+            }
+            thread::sleep(self.config.poll_interval);
+        }
+    }
+
+    /// Stop every child process and node.
+    pub fn stop(&mut self) -> Result<(), DevnetError> {
+        for (name, mut child) in self.children.drain(..) {
+            log::debug!(self.logger, "Stopping {}", name);
+            // The child may have already exited, in which case there is nothing to
+            // kill.
+            let _ = child.kill();
+            child.wait()?;
+        }
+        for mut service in self.services.drain(..).flatten() {
+            service.stop()?;
+        }
+        Ok(())
+    }
+
+    /// Shut a node down the way it would on SIGTERM, letting it finish the
+    /// slot in progress. Its ledger-distribution keeps publishing whatever
+    /// it committed.
+    pub fn shutdown_node(&mut self, index: usize) -> Result<(), DevnetError> {
+        let mut service = self
+            .services
+            .get_mut(index)
+            .and_then(Option::take)
+            .ok_or(DevnetError::NodeNotRunning(index))?;
+        log::info!(self.logger, "Shutting down node {}", index);
+        service.shutdown()?;
+        Ok(())
+    }
+
+    /// A node's ledger, which can be opened once the node is shut down.
+    pub fn ledger_dir(&self, index: usize) -> PathBuf {
+        self.node_dir(index).join("ledger")
+    }
+
+    /// The directory ledger-distribution publishes a node's blocks to.
+    pub fn archive_dir(&self, index: usize) -> PathBuf {
+        self.node_dir(index).join("archive")
+    }
+
+    /// The tx source URL of each node's block archive.
+    pub fn tx_source_urls(&self) -> Vec<String> {
+        (0..self.nodes.len())
+            .map(|index| self.archive_url(index))
+            .collect()
+    }
+
+    fn node_dir(&self, index: usize) -> PathBuf {
+        self.config.work_dir.join(format!("node{}", index))
+    }
+
+    fn archive_url(&self, index: usize) -> String {
+        format!("file://{}/", self.archive_dir(index).display())
+    }
+
+    /// Write the account keys, origin ledger and attestation policy.
+    fn bootstrap(&self) -> Result<(), DevnetError> {
+        log::info!(
+            self.logger,
+            "Generating {} account keys in {:?}",
+            self.config.num_keys,
+            self.keys_dir()
+        );
+        keygen::write_default_keyfiles(
+            self.keys_dir(),
+            self.config.num_keys,
+            None,
+            None,
+            None,
+            keygen::DEFAULT_SEED,
+        )?;
+        let recipients = keygen::read_default_pubfiles(self.keys_dir())?;
+
+        bootstrap_ledger(
+            &self.origin_dir(),
+            &recipients,
+            self.config.outputs_per_key,
+            self.config.num_blocks,
+            0,
+            None,
+            None,
+            self.logger.clone(),
+        );
+
+        fs::write(
+            self.attestation_policy_path(),
+            serde_json::to_string_pretty(&attestation_policy()?)?,
+        )?;
+        Ok(())
+    }
+
+    /// Start every node, and a ledger-distribution for each.
+    fn start_nodes(&mut self, threshold: u32) -> Result<(), DevnetError> {
+        let tx_source_urls = self.tx_source_urls();
+
+        for node in self.nodes.clone() {
+            let node_dir = self.node_dir(node.index);
+            let ledger_path = self.ledger_dir(node.index);
+            fs::create_dir_all(&ledger_path)?;
+            fs::copy(
+                self.origin_dir().join("data.mdb"),
+                ledger_path.join("data.mdb"),
+            )?;
+
+            let network_path = node_dir.join("network.json");
+            let network = network_config(&self.nodes, node.index, threshold, &tx_source_urls);
+            fs::write(&network_path, serde_json::to_string_pretty(&network)?)?;
+
+            let config = Config {
+                peer_responder_id: node.peer_responder_id(),
+                client_responder_id: node.client_responder_id(),
+                msg_signer_key: node.msg_signer_key.clone(),
+                network_path,
+                ias_api_key: String::new(),
+                ias_spid: ProviderId::default(),
+                peer_listen_uri: node.peer_listen_uri(),
+                client_listen_uri: node.client_listen_uri(),
+                admin_listen_uri: Some(node.admin_listen_uri()),
+                admin_auth_token_secret: None,
+                admin_auth_token_max_lifetime: Duration::from_secs(3600),
+                ledger_path: ledger_path.clone(),
+                origin_block_path: Some(self.origin_dir()),
+                scp_debug_dump: None,
+                sealed_block_signing_key: None,
+                observer: false,
+                client_auth_token_secret: None,
+                client_auth_token_max_lifetime: Duration::from_secs(86400),
+                minimum_fee: self.config.minimum_fee,
+                allow_any_fee: false,
+                tx_cache_capacity: 10000,
+                // Every local client shares an address, so client rate limits
+                // would throttle load tests.
+                client_propose_tx_rate_limit: 0,
+                client_propose_tx_burst: 0,
+                client_get_blocks_rate_limit: 0,
+                client_get_blocks_burst: 0,
+                peer_msg_rate_limit: 100,
+                peer_score_threshold: 100,
+                peer_ban_duration: Duration::from_secs(60),
+                shutdown_timeout: Duration::from_secs(30),
+                attestation_policy: None,
+            };
+
+            let logger = self.logger.new(o!(
+                "mc.local_node_id" => node.peer_responder_id().to_string(),
+            ));
+            log::info!(
+                logger,
+                "Starting node {}: client_port={} peer_port={} admin_port={}",
+                node.index,
+                node.client_port,
+                node.peer_port,
+                node.admin_port
+            );
+
+            let enclave = DevnetEnclave::new(
+                node.client_responder_id(),
+                Ed25519Pair::from_random(&mut McRng::default()),
+            )?;
+            enclave.enclave_init(
+                &config.peer_responder_id,
+                &config.client_responder_id,
+                &None,
+                config.minimum_fee,
+            )?;
+
+            let ledger = LedgerDB::open(&ledger_path)?;
+            let tx_manager = TxManagerImpl::new_with_capacity(
+                enclave.clone(),
+                DefaultTxManagerUntrustedInterfaces::new(ledger.clone()),
+                config.tx_cache_capacity,
+                logger.clone(),
+            );
+            let mut service = ConsensusService::new(
+                config,
+                enclave,
+                ledger,
+                Client::new("")?,
+                Arc::new(tx_manager),
+                Arc::new(SystemTimeProvider::default()),
+                logger,
+            );
+            service.start()?;
+            self.services.push(Some(service));
+
+            self.spawn(
+                format!("{} for node {}", LEDGER_DISTRIBUTION_BIN, node.index),
+                LEDGER_DISTRIBUTION_BIN,
+                vec![
+                    "--ledger-path".to_owned(),
+                    ledger_path.display().to_string(),
+                    "--dest".to_owned(),
+                    tx_source_urls[node.index].clone(),
+                    "--state-file".to_owned(),
+                    node_dir
+                        .join("ledger-distribution-state")
+                        .display()
+                        .to_string(),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Start a mobilecoind which talks to every node.
+    fn start_mobilecoind(&mut self) -> Result<(), DevnetError> {
+        let mobilecoind_dir = self.config.work_dir.join("mobilecoind");
+        let mut args = vec![
+            "--ledger-db".to_owned(),
+            mobilecoind_dir.join("ledger-db").display().to_string(),
+            "--ledger-db-bootstrap".to_owned(),
+            self.origin_dir().display().to_string(),
+            "--mobilecoind-db".to_owned(),
+            mobilecoind_dir.join("mobilecoind-db").display().to_string(),
+            "--listen-uri".to_owned(),
+            format!(
+                "insecure-mobilecoind://0.0.0.0:{}/",
+                self.config.mobilecoind_port
+            ),
+            "--poll-interval".to_owned(),
+            "1".to_owned(),
+            "--attestation-policy".to_owned(),
+            self.attestation_policy_path().display().to_string(),
+        ];
+        for node in self.nodes.iter() {
+            args.push("--peer".to_owned());
+            args.push(node.client_uri().to_string());
+            args.push("--tx-source-url".to_owned());
+            args.push(self.archive_url(node.index));
+        }
+
+        log::info!(
+            self.logger,
+            "Starting mobilecoind on port {}",
+            self.config.mobilecoind_port
+        );
+        self.spawn(MOBILECOIND_BIN.to_owned(), MOBILECOIND_BIN, args)
+    }
+
+    /// Start a watcher which syncs every node's block archive and fetches
+    /// their verification reports.
+    fn start_watcher(&mut self) -> Result<(), DevnetError> {
+        let watcher_dir = self.config.work_dir.join("watcher");
+        fs::create_dir_all(&watcher_dir)?;
+
+        let sources_path = watcher_dir.join("sources.toml");
+        let sources: String = self
+            .nodes
+            .iter()
+            .map(|node| {
+                format!(
+                    "[[sources]]\ntx_source_url = \"{}\"\nconsensus_client_url = \"{}\"\n\n",
+                    self.archive_url(node.index),
+                    node.client_uri()
+                )
+            })
+            .collect();
+        fs::write(&sources_path, sources)?;
+
+        let args = vec![
+            "--watcher-db".to_owned(),
+            self.watcher_db_path().display().to_string(),
+            "--sources-path".to_owned(),
+            sources_path.display().to_string(),
+            "--client-listen-uri".to_owned(),
+            format!("insecure-watcher://0.0.0.0:{}/", self.config.watcher_port),
+            "--attestation-policy".to_owned(),
+            self.attestation_policy_path().display().to_string(),
+            "--store-block-data".to_owned(),
+        ];
+
+        log::info!(
+            self.logger,
+            "Starting watcher on port {}",
+            self.config.watcher_port
+        );
+        self.spawn(WATCHER_BIN.to_owned(), WATCHER_BIN, args)
+    }
+
+    fn spawn(&mut self, name: String, binary: &str, args: Vec<String>) -> Result<(), DevnetError> {
+        log::debug!(self.logger, "Starting {}: {:?}", name, args);
+        let child = Command::new(self.bin_dir.join(binary))
+            .args(&args)
+            .spawn()?;
+        self.children.push((name, child));
+        Ok(())
+    }
+}
+
+impl Drop for Devnet {
+    fn drop(&mut self) {
+        if let Err(err) = self.stop() {
+            log::error!(self.logger, "Failed stopping devnet: {}", err);
+        }
+    }
+}
@@ -0,0 +1,399 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! The enclave used by devnet consensus nodes.
+
+use crate::{attestation::ias_report, error::DevnetError};
+use aes_gcm::Aes256Gcm;
+use mc_attest_ake::{AuthRequestOutput, ClientAuthRequestInput, Ready, Start, Transition};
+use mc_attest_core::{IasNonce, Quote, QuoteNonce, Report, TargetInfo, VerificationReport};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, Error as AttestError,
+    PeerAuthRequest, PeerAuthResponse, PeerSession,
+};
+use mc_common::ResponderId;
+use mc_consensus_enclave_mock::{
+    ConsensusEnclave, ConsensusEnclaveProxy, ConsensusServiceMockEnclave, FeePublicKey,
+    LocallyEncryptedTx, Result, SealedBlockSigningKey, TxContext, WellFormedEncryptedTx,
+    WellFormedTxContext,
+};
+use mc_crypto_keys::{Ed25519Pair, Ed25519Public, X25519Private, X25519Public, X25519};
+use mc_crypto_rand::McRng;
+use mc_sgx_report_cache_api::{ReportableEnclave, Result as ReportableEnclaveResult};
+use mc_transaction_core::{
+    tx::{Tx, TxOutMembershipProof},
+    Block, BlockContents, BlockSignature,
+};
+use mc_util_from_random::FromRandom;
+use sha2::Sha512;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// A [ConsensusServiceMockEnclave] which clients can attest to, and which
+/// relays transactions to its peers.
+///
+/// Client sessions use a real attested key exchange against a simulated IAS
+/// report, so unmodified clients can submit transactions. Peer sessions are
+/// not attested, and transactions are relayed between peers in the clear.
+#[derive(Clone)]
+pub struct DevnetEnclave {
+    mock: ConsensusServiceMockEnclave,
+    client_responder_id: ResponderId,
+    identity: X25519Private,
+    ias_report: VerificationReport,
+    clients: Arc<Mutex<HashMap<ClientSession, Ready<Aes256Gcm>>>>,
+}
+
+impl DevnetEnclave {
+    /// Create an enclave for the node with the given client responder ID,
+    /// which signs blocks with the given key.
+    pub fn new(
+        client_responder_id: ResponderId,
+        signing_keypair: Ed25519Pair,
+    ) -> std::result::Result<Self, DevnetError> {
+        let identity = X25519Private::from_random(&mut McRng::default());
+        let ias_report = ias_report(&X25519Public::from(&identity))?;
+
+        Ok(Self {
+            mock: ConsensusServiceMockEnclave {
+                signing_keypair: Arc::new(signing_keypair),
+                ..Default::default()
+            },
+            client_responder_id,
+            identity,
+            ias_report,
+            clients: Arc::new(Mutex::new(HashMap::default())),
+        })
+    }
+
+    fn client_decrypt(&self, msg: EnclaveMessage<ClientSession>) -> Result<Vec<u8>> {
+        let mut clients = self.clients.lock()?;
+        let session = clients
+            .get_mut(&msg.channel_id)
+            .ok_or(AttestError::NotFound)?;
+        Ok(session
+            .decrypt(&msg.aad, &msg.data)
+            .map_err(AttestError::from)?)
+    }
+}
+
+impl ReportableEnclave for DevnetEnclave {
+    fn new_ereport(&self, qe_info: TargetInfo) -> ReportableEnclaveResult<(Report, QuoteNonce)> {
+        self.mock.new_ereport(qe_info)
+    }
+
+    fn verify_quote(&self, quote: Quote, qe_report: Report) -> ReportableEnclaveResult<IasNonce> {
+        self.mock.verify_quote(quote, qe_report)
+    }
+
+    fn verify_ias_report(&self, ias_report: VerificationReport) -> ReportableEnclaveResult<()> {
+        self.mock.verify_ias_report(ias_report)
+    }
+
+    fn get_ias_report(&self) -> ReportableEnclaveResult<VerificationReport> {
+        Ok(self.ias_report.clone())
+    }
+}
+
+impl ConsensusEnclave for DevnetEnclave {
+    fn enclave_init(
+        &self,
+        self_peer_id: &ResponderId,
+        self_client_id: &ResponderId,
+        sealed_key: &Option<SealedBlockSigningKey>,
+        minimum_fee: Option<u64>,
+    ) -> Result<(SealedBlockSigningKey, Vec<String>)> {
+        self.mock
+            .enclave_init(self_peer_id, self_client_id, sealed_key, minimum_fee)
+    }
+
+    fn get_minimum_fee(&self) -> Result<u64> {
+        self.mock.get_minimum_fee()
+    }
+
+    fn get_identity(&self) -> Result<X25519Public> {
+        Ok(X25519Public::from(&self.identity))
+    }
+
+    fn get_signer(&self) -> Result<Ed25519Public> {
+        self.mock.get_signer()
+    }
+
+    fn get_fee_recipient(&self) -> Result<FeePublicKey> {
+        self.mock.get_fee_recipient()
+    }
+
+    fn client_accept(&self, req: ClientAuthRequest) -> Result<(ClientAuthResponse, ClientSession)> {
+        let auth_request = ClientAuthRequestInput::<X25519, Aes256Gcm, Sha512>::new(
+            AuthRequestOutput::from(Vec::<u8>::from(req)),
+            self.identity.clone(),
+            self.ias_report.clone(),
+        );
+
+        let mut csprng = McRng::default();
+        let (responder, auth_response) = Start::new(self.client_responder_id.to_string())
+            .try_next(&mut csprng, auth_request)
+            .map_err(AttestError::from)?;
+        let session_id = ClientSession::from(responder.binding());
+        self.clients.lock()?.insert(session_id.clone(), responder);
+
+        let auth_response: Vec<u8> = auth_response.into();
+        Ok((ClientAuthResponse::from(auth_response), session_id))
+    }
+
+    fn client_close(&self, channel_id: ClientSession) -> Result<()> {
+        self.clients.lock()?.remove(&channel_id);
+        Ok(())
+    }
+
+    fn client_discard_message(&self, msg: EnclaveMessage<ClientSession>) -> Result<()> {
+        let _ = self.client_decrypt(msg)?;
+        Ok(())
+    }
+
+    fn peer_init(&self, peer_id: &ResponderId) -> Result<PeerAuthRequest> {
+        self.mock.peer_init(peer_id)
+    }
+
+    fn peer_accept(
+        &self,
+        req: PeerAuthRequest,
+    ) -> Result<(PeerAuthResponse, PeerSession, VerificationReport)> {
+        self.mock.peer_accept(req)
+    }
+
+    fn peer_connect(
+        &self,
+        peer_id: &ResponderId,
+        msg: PeerAuthResponse,
+    ) -> Result<(PeerSession, VerificationReport)> {
+        self.mock.peer_connect(peer_id, msg)
+    }
+
+    fn peer_close(&self, session_id: &PeerSession) -> Result<()> {
+        self.mock.peer_close(session_id)
+    }
+
+    fn client_tx_propose(&self, msg: EnclaveMessage<ClientSession>) -> Result<TxContext> {
+        let tx: Tx = mc_util_serial::decode(&self.client_decrypt(msg)?)?;
+        Ok(ConsensusServiceMockEnclave::tx_to_tx_context(&tx))
+    }
+
+    fn peer_tx_propose(&self, msg: EnclaveMessage<PeerSession>) -> Result<Vec<TxContext>> {
+        let encrypted_txs: Vec<Vec<u8>> = mc_util_serial::deserialize(&msg.data)?;
+        encrypted_txs
+            .iter()
+            .map(|tx_bytes| {
+                let tx: Tx = mc_util_serial::decode(tx_bytes)?;
+                Ok(ConsensusServiceMockEnclave::tx_to_tx_context(&tx))
+            })
+            .collect()
+    }
+
+    fn tx_is_well_formed(
+        &self,
+        locally_encrypted_tx: LocallyEncryptedTx,
+        block_index: u64,
+        proofs: Vec<TxOutMembershipProof>,
+    ) -> Result<(WellFormedEncryptedTx, WellFormedTxContext)> {
+        self.mock
+            .tx_is_well_formed(locally_encrypted_tx, block_index, proofs)
+    }
+
+    fn txs_for_peer(
+        &self,
+        encrypted_txs: &[WellFormedEncryptedTx],
+        aad: &[u8],
+        peer: &PeerSession,
+    ) -> Result<EnclaveMessage<PeerSession>> {
+        // The mock enclave's "encrypted" transactions are serialized Txs.
+        let encrypted_txs: Vec<Vec<u8>> = encrypted_txs.iter().map(|tx| tx.0.clone()).collect();
+        Ok(EnclaveMessage {
+            aad: aad.to_vec(),
+            channel_id: peer.clone(),
+            data: mc_util_serial::serialize(&encrypted_txs)?,
+        })
+    }
+
+    fn form_block(
+        &self,
+        parent_block: &Block,
+        encrypted_txs_with_proofs: &[(WellFormedEncryptedTx, Vec<TxOutMembershipProof>)],
+    ) -> Result<(Block, BlockContents, BlockSignature)> {
+        self.mock
+            .form_block(parent_block, encrypted_txs_with_proofs)
+    }
+}
+
+impl ConsensusEnclaveProxy for DevnetEnclave {}
+
+#[cfg(all(test, feature = "sgx-sim"))]
+mod test {
+    use super::*;
+    use crate::attestation::attestation_policy;
+    use mc_attest_ake::{
+        AuthPending, AuthResponseInput, AuthResponseResumeInput, ClientInitiate, Plaintext,
+        RekeyPolicy,
+    };
+    use std::time::Duration;
+
+    /// Start a client handshake with the enclave, returning the pending
+    /// client, the enclave's response and the session ID.
+    fn client_auth(
+        enclave: &DevnetEnclave,
+        responder_id: &ResponderId,
+    ) -> (
+        AuthPending<X25519, Aes256Gcm, Sha512>,
+        Vec<u8>,
+        ClientSession,
+    ) {
+        let (initiator, auth_request) = Start::new(responder_id.to_string())
+            .try_next(
+                &mut McRng::default(),
+                ClientInitiate::<X25519, Aes256Gcm, Sha512>::default(),
+            )
+            .expect("Could not start handshake");
+        let auth_request: Vec<u8> = auth_request.into();
+        let (auth_response, session_id) = enclave
+            .client_accept(ClientAuthRequest::from(auth_request))
+            .expect("Enclave did not accept client");
+        (initiator, auth_response.into(), session_id)
+    }
+
+    /// Send messages from the client to the enclave, and ensure they arrive
+    /// intact.
+    fn send(
+        enclave: &DevnetEnclave,
+        session_id: &ClientSession,
+        client: &mut Ready<Aes256Gcm>,
+        count: u64,
+    ) {
+        for i in 0..count {
+            let msg = i.to_le_bytes();
+            let data = client
+                .encrypt(b"aad", &msg)
+                .expect("Could not encrypt message");
+            let msg_in = EnclaveMessage {
+                aad: b"aad".to_vec(),
+                channel_id: session_id.clone(),
+                data,
+            };
+            assert_eq!(
+                enclave
+                    .client_decrypt(msg_in)
+                    .expect("Could not decrypt message"),
+                msg
+            );
+        }
+    }
+
+    /// Ensure a client can attest to the enclave, using the devnet policy,
+    /// and then talk to it.
+    #[test]
+    fn client_handshake() {
+        let responder_id = ResponderId("localhost:3200".to_owned());
+        let enclave = DevnetEnclave::new(
+            responder_id.clone(),
+            Ed25519Pair::from_random(&mut McRng::default()),
+        )
+        .expect("Could not create enclave");
+        let verifier = attestation_policy()
+            .expect("Could not create policy")
+            .verifier()
+            .expect("Could not create verifier");
+
+        let mut csprng = McRng::default();
+        let (initiator, auth_request) = Start::new(responder_id.to_string())
+            .try_next(
+                &mut csprng,
+                ClientInitiate::<X25519, Aes256Gcm, Sha512>::default(),
+            )
+            .expect("Could not start handshake");
+        let auth_request: Vec<u8> = auth_request.into();
+        let (auth_response, session_id) = enclave
+            .client_accept(ClientAuthRequest::from(auth_request))
+            .expect("Enclave did not accept client");
+
+        let auth_response: Vec<u8> = auth_response.into();
+        let (initiator, _report) = initiator
+            .try_next(
+                &mut csprng,
+                AuthResponseInput::new(auth_response.into(), verifier),
+            )
+            .expect("Client did not accept enclave");
+
+        let (_, ciphertext) = initiator
+            .try_next(&mut csprng, Plaintext::new(b"aad", b"hello"))
+            .expect("Could not encrypt message");
+        let msg = EnclaveMessage {
+            aad: b"aad".to_vec(),
+            channel_id: session_id.clone(),
+            data: ciphertext,
+        };
+        assert_eq!(
+            enclave
+                .client_decrypt(msg)
+                .expect("Could not decrypt message"),
+            b"hello"
+        );
+
+        enclave
+            .client_close(session_id.clone())
+            .expect("Could not close session");
+        let msg = EnclaveMessage {
+            aad: vec![],
+            channel_id: session_id,
+            data: vec![],
+        };
+        assert!(enclave.client_discard_message(msg).is_err());
+    }
+
+    /// Ensure a client session survives rotating its keys, and that the
+    /// client can resume a session with the enclave using a ticket from its
+    /// first session.
+    #[test]
+    fn client_rekey_and_resume() {
+        let responder_id = ResponderId("localhost:3200".to_owned());
+        let enclave = DevnetEnclave::new(
+            responder_id.clone(),
+            Ed25519Pair::from_random(&mut McRng::default()),
+        )
+        .expect("Could not create enclave");
+        let verifier = attestation_policy()
+            .expect("Could not create policy")
+            .verifier()
+            .expect("Could not create verifier");
+
+        let (initiator, auth_response, session_id) = client_auth(&enclave, &responder_id);
+        let (mut client, _report) = initiator
+            .try_next(
+                &mut McRng::default(),
+                AuthResponseInput::new(auth_response.into(), verifier),
+            )
+            .expect("Client did not accept enclave");
+        let ticket = client
+            .resumption_ticket(Duration::from_secs(60))
+            .expect("Client did not learn the enclave identity");
+
+        // Cross several rekey boundaries.
+        client.set_rekey_policy(RekeyPolicy::new(3, Duration::from_secs(60)));
+        send(&enclave, &session_id, &mut client, 10);
+        assert_eq!(client.messages_since_rekey(), 1);
+
+        // Resume without verifying the report again, and keep rotating keys.
+        let (initiator, auth_response, resumed_session_id) = client_auth(&enclave, &responder_id);
+        assert_ne!(resumed_session_id, session_id);
+        let (mut resumed, _ticket) = initiator
+            .try_next(
+                &mut McRng::default(),
+                AuthResponseResumeInput::new(auth_response.into(), ticket, Duration::from_secs(1)),
+            )
+            .expect("Client could not resume session");
+        resumed.set_rekey_policy(RekeyPolicy::new(3, Duration::from_secs(60)));
+        send(&enclave, &resumed_session_id, &mut resumed, 10);
+
+        // The first session is unaffected.
+        send(&enclave, &session_id, &mut client, 3);
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Error data types
+
+use displaydoc::Display;
+use mc_attest_core::QuoteError;
+use mc_attest_net::Error as RaError;
+use mc_consensus_enclave_mock::Error as EnclaveError;
+use mc_consensus_service::consensus_service::ConsensusServiceError;
+use mc_ledger_db::Error as LedgerError;
+use std::{io::Error as IoError, process::ExitStatus};
+
+/// Devnet errors
+#[derive(Debug, Display)]
+pub enum DevnetError {
+    /// The devnet must be built with SGX_MODE=SW
+    NotSimulation,
+
+    /// A devnet needs at least two nodes, got {0}
+    TooFewNodes(usize),
+
+    /// Quorum threshold {0} must be between 1 and the number of peers, {1}
+    InvalidThreshold(u32, usize),
+
+    /// Work directory {0} already exists, pass --clean to replace it
+    WorkDirExists(String),
+
+    /// Could not find the {0} binary, build it or pass --bin-dir
+    MissingBinary(String),
+
+    /// Node {0} is not running
+    NodeNotRunning(usize),
+
+    /// {0} exited: {1}
+    ChildExited(String, ExitStatus),
+
+    /// IO: {0}
+    Io(IoError),
+
+    /// JSON: {0}
+    Json(serde_json::Error),
+
+    /// Quote: {0}
+    Quote(QuoteError),
+
+    /// Remote attestation client: {0}
+    Ra(RaError),
+
+    /// Ledger: {0}
+    Ledger(LedgerError),
+
+    /// Enclave: {0}
+    Enclave(EnclaveError),
+
+    /// Consensus service: {0}
+    Consensus(ConsensusServiceError),
+}
+
+impl From<IoError> for DevnetError {
+    fn from(src: IoError) -> Self {
+        Self::Io(src)
+    }
+}
+
+impl From<serde_json::Error> for DevnetError {
+    fn from(src: serde_json::Error) -> Self {
+        Self::Json(src)
+    }
+}
+
+impl From<QuoteError> for DevnetError {
+    fn from(src: QuoteError) -> Self {
+        Self::Quote(src)
+    }
+}
+
+impl From<RaError> for DevnetError {
+    fn from(src: RaError) -> Self {
+        Self::Ra(src)
+    }
+}
+
+impl From<LedgerError> for DevnetError {
+    fn from(src: LedgerError) -> Self {
+        Self::Ledger(src)
+    }
+}
+
+impl From<EnclaveError> for DevnetError {
+    fn from(src: EnclaveError) -> Self {
+        Self::Enclave(src)
+    }
+}
+
+impl From<ConsensusServiceError> for DevnetError {
+    fn from(src: ConsensusServiceError) -> Self {
+        Self::Consensus(src)
+    }
+}
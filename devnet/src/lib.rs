@@ -0,0 +1,15 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+#![feature(external_doc)]
+#![doc(include = "../README.md")]
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+pub mod attestation;
+pub mod config;
+pub mod devnet;
+pub mod enclave;
+pub mod error;
+pub mod network;
+
+pub use crate::{devnet::Devnet, enclave::DevnetEnclave, error::DevnetError};
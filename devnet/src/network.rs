@@ -0,0 +1,194 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Addresses, keys and network configurations of devnet nodes.
+
+use mc_common::ResponderId;
+use mc_consensus_scp::QuorumSet;
+use mc_consensus_service::config::NetworkConfig;
+use mc_crypto_keys::Ed25519Pair;
+use mc_crypto_rand::McRng;
+use mc_util_from_random::FromRandom;
+use mc_util_uri::{AdminUri, ConsensusClientUri as ClientUri, ConsensusPeerUri as PeerUri};
+use std::{str::FromStr, sync::Arc};
+
+/// The offset of peer ports from client ports.
+const PEER_PORT_OFFSET: u16 = 100;
+
+/// The offset of admin ports from client ports.
+const ADMIN_PORT_OFFSET: u16 = 200;
+
+/// The addresses and message signing key of a single devnet node.
+#[derive(Clone)]
+pub struct NodeLayout {
+    /// The index of this node in the network.
+    pub index: usize,
+    /// The port the client API listens on.
+    pub client_port: u16,
+    /// The port the peer API listens on.
+    pub peer_port: u16,
+    /// The port the admin API listens on.
+    pub admin_port: u16,
+    /// The key used to sign consensus messages.
+    pub msg_signer_key: Arc<Ed25519Pair>,
+}
+
+impl NodeLayout {
+    /// Lay out `num_nodes` nodes, with client ports starting at `base_port`,
+    /// peer ports 100 above, and admin ports 200 above.
+    pub fn for_network(num_nodes: usize, base_port: u16) -> Vec<Self> {
+        let mut csprng = McRng::default();
+        (0..num_nodes)
+            .map(|index| {
+                let client_port = base_port + index as u16;
+                Self {
+                    index,
+                    client_port,
+                    peer_port: client_port + PEER_PORT_OFFSET,
+                    admin_port: client_port + ADMIN_PORT_OFFSET,
+                    msg_signer_key: Arc::new(Ed25519Pair::from_random(&mut csprng)),
+                }
+            })
+            .collect()
+    }
+
+    /// The responder ID peers use to reach this node.
+    pub fn peer_responder_id(&self) -> ResponderId {
+        ResponderId(format!("localhost:{}", self.peer_port))
+    }
+
+    /// The responder ID clients use to reach this node.
+    pub fn client_responder_id(&self) -> ResponderId {
+        ResponderId(format!("localhost:{}", self.client_port))
+    }
+
+    /// The URI peers use to reach this node.
+    pub fn peer_uri(&self) -> PeerUri {
+        PeerUri::from_str(&format!(
+            "insecure-mcp://localhost:{}/?consensus-msg-key={}",
+            self.peer_port,
+            hex::encode(self.msg_signer_key.public_key()),
+        ))
+        .expect("Could not construct peer uri")
+    }
+
+    /// The URI clients use to reach this node.
+    pub fn client_uri(&self) -> ClientUri {
+        ClientUri::from_str(&format!("insecure-mc://localhost:{}/", self.client_port))
+            .expect("Could not construct client uri")
+    }
+
+    /// The URI the peer API listens on.
+    pub fn peer_listen_uri(&self) -> PeerUri {
+        PeerUri::from_str(&format!("insecure-mcp://0.0.0.0:{}/", self.peer_port))
+            .expect("Could not construct peer listen uri")
+    }
+
+    /// The URI the client API listens on.
+    pub fn client_listen_uri(&self) -> ClientUri {
+        ClientUri::from_str(&format!("insecure-mc://0.0.0.0:{}/", self.client_port))
+            .expect("Could not construct client listen uri")
+    }
+
+    /// The URI the admin API listens on.
+    pub fn admin_listen_uri(&self) -> AdminUri {
+        AdminUri::from_str(&format!("insecure-mca://0.0.0.0:{}/", self.admin_port))
+            .expect("Could not construct admin listen uri")
+    }
+}
+
+/// The default quorum threshold for a node with `num_peers` peers, which
+/// tolerates a third of them failing.
+pub fn default_threshold(num_peers: usize) -> u32 {
+    (num_peers - num_peers / 3) as u32
+}
+
+/// The network configuration of the node at `index`: a full mesh, with a
+/// quorum set of `threshold` of all the other nodes.
+///
+/// # Arguments
+/// * `nodes` - The layout of every node in the network.
+/// * `index` - The node to configure.
+/// * `threshold` - The quorum set threshold.
+/// * `tx_source_urls` - The block archive of each node, by index.
+pub fn network_config(
+    nodes: &[NodeLayout],
+    index: usize,
+    threshold: u32,
+    tx_source_urls: &[String],
+) -> NetworkConfig {
+    let peers: Vec<&NodeLayout> = nodes.iter().filter(|node| node.index != index).collect();
+
+    NetworkConfig {
+        quorum_set: QuorumSet::new_with_node_ids(
+            threshold,
+            peers.iter().map(|node| node.peer_responder_id()).collect(),
+        ),
+        broadcast_peers: peers.iter().map(|node| node.peer_uri()).collect(),
+        tx_source_urls: peers
+            .iter()
+            .map(|node| tx_source_urls[node.index].clone())
+            .collect(),
+        known_peers: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_util_uri::ConnectionUri;
+
+    fn tx_source_urls(num_nodes: usize) -> Vec<String> {
+        (0..num_nodes)
+            .map(|index| format!("file:///tmp/devnet/node{}/archive/", index))
+            .collect()
+    }
+
+    #[test]
+    fn layout_ports() {
+        let nodes = NodeLayout::for_network(3, 3200);
+        let ports: Vec<(u16, u16, u16)> = nodes
+            .iter()
+            .map(|node| (node.client_port, node.peer_port, node.admin_port))
+            .collect();
+        assert_eq!(
+            ports,
+            vec![(3200, 3300, 3400), (3201, 3301, 3401), (3202, 3302, 3402)]
+        );
+        assert_eq!(
+            nodes[1].client_uri().responder_id().unwrap(),
+            nodes[1].client_responder_id()
+        );
+        assert_eq!(
+            nodes[1].peer_uri().responder_id().unwrap(),
+            nodes[1].peer_responder_id()
+        );
+    }
+
+    #[test]
+    fn thresholds() {
+        assert_eq!(default_threshold(1), 1);
+        assert_eq!(default_threshold(2), 2);
+        assert_eq!(default_threshold(4), 3);
+        assert_eq!(default_threshold(9), 6);
+    }
+
+    /// Every node's network configuration should pass the consensus
+    /// service's own validation, and resolve to a quorum set of its peers.
+    #[test]
+    fn network_configs_are_valid() {
+        let nodes = NodeLayout::for_network(5, 3200);
+        let urls = tx_source_urls(nodes.len());
+
+        for node in nodes.iter() {
+            let network = network_config(&nodes, node.index, default_threshold(4), &urls);
+            network
+                .validate(&node.peer_responder_id())
+                .expect("Invalid network config");
+
+            let quorum_set = network.quorum_set();
+            assert_eq!(quorum_set.threshold, 3);
+            assert_eq!(quorum_set.nodes().len(), 4);
+            assert!(!network.tx_source_urls.contains(&urls[node.index]));
+        }
+    }
+}
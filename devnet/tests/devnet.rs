@@ -0,0 +1,164 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Start a devnet, submit a transaction to it and wait for the watcher to see
+//! the resulting block.
+
+use grpcio::EnvBuilder;
+use mc_account_keys::AccountKey;
+use mc_attest_ake::RekeyPolicy;
+use mc_common::logger::{log, test_with_logger, Logger};
+use mc_connection::{
+    AttestedConnection, BlockchainConnection, Error as ConnectionError,
+    HardcodedCredentialsProvider, ThickClient, UserTxConnection,
+};
+use mc_crypto_rand::McRng;
+use mc_devnet::{attestation::attestation_policy, config::DevnetConfig, Devnet};
+use mc_ledger_db::{Ledger, LedgerDB};
+use mc_transaction_core_test_utils::create_transaction;
+use mc_util_keyfile::keygen;
+use mc_watcher::watcher_db::WatcherDB;
+use std::{
+    env,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+use tempdir::TempDir;
+
+/// How long to wait for the network to make progress before failing.
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+// Get the build dir, which is one down from current_exe, which is in
+// target/debug/deps,
+fn build_dir() -> PathBuf {
+    let mut result = env::current_exe().unwrap();
+    result.pop();
+    result.pop();
+    result
+}
+
+/// Call `f` until it returns `Some`, panicking if that takes longer than
+/// `TIMEOUT`.
+fn wait_for<T>(what: &str, mut f: impl FnMut() -> Option<T>) -> T {
+    let start = Instant::now();
+    loop {
+        if let Some(value) = f() {
+            return value;
+        }
+        assert!(start.elapsed() < TIMEOUT, "Timed out waiting for {}", what);
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+// Requires SGX_MODE=SW, the sgx-sim feature and the ledger-distribution and
+// mc-watcher binaries in the build dir.
+#[test_with_logger]
+#[ignore]
+fn devnet_externalizes_tx(logger: Logger) {
+    let work_dir = TempDir::new("devnet").unwrap();
+    let config = DevnetConfig::from_iter(&[
+        "devnet",
+        "--work-dir",
+        work_dir.path().join("devnet").to_str().unwrap(),
+        "--bin-dir",
+        build_dir().to_str().unwrap(),
+        "--base-port",
+        "3700",
+        "--num-keys",
+        "2",
+        "--outputs-per-key",
+        "10",
+        "--watcher",
+        "--watcher-port",
+        "3999",
+    ]);
+    let devnet = Devnet::start(config, logger.clone()).expect("Could not start devnet");
+
+    // The origin block pays each account in turn, so its first output belongs
+    // to the first account.
+    let mut ledger = LedgerDB::open(&devnet.origin_dir()).unwrap();
+    let accounts: Vec<AccountKey> = keygen::read_default_root_entropies(devnet.keys_dir())
+        .unwrap()
+        .iter()
+        .map(AccountKey::from)
+        .collect();
+    let tx_out = ledger.get_tx_out_by_index(0).unwrap();
+    let tx = create_transaction(
+        &mut ledger,
+        &tx_out,
+        &accounts[0],
+        &accounts[1].default_subaddress(),
+        10,
+        &mut McRng::default(),
+    );
+
+    let node = &devnet.nodes()[0];
+    let uri = node.client_uri();
+    let verifier = attestation_policy().unwrap().verifier().unwrap();
+    let env = Arc::new(EnvBuilder::new().name_prefix("devnet-test").build());
+    let mut client = ThickClient::new(
+        uri.clone(),
+        verifier,
+        env,
+        HardcodedCredentialsProvider::from(&uri),
+        logger.clone(),
+    )
+    .unwrap();
+    // Rotate the session key before every message after the first.
+    client.set_rekey_policy(RekeyPolicy::new(1, Duration::from_secs(3600)));
+
+    // The node may still be starting up, so retry until it accepts the
+    // transaction.
+    wait_for("the transaction to be accepted", || {
+        client
+            .propose_tx(&tx)
+            .map_err(|err| log::debug!(logger, "propose_tx failed: {}", err))
+            .ok()
+    });
+    wait_for("block 1 to be externalized", || {
+        client
+            .fetch_block_height()
+            .ok()
+            .filter(|height| *height > 1)
+    });
+
+    // The watcher syncs from each node's archive, so it sees the block once
+    // ledger-distribution has published it.
+    let watcher_db = wait_for("the watcher database", || {
+        WatcherDB::open_ro(&devnet.watcher_db_path(), logger.clone()).ok()
+    });
+    wait_for("the watcher to sync block 1 from every node", || {
+        watcher_db
+            .get_block_signatures(1)
+            .ok()
+            .filter(|signatures| signatures.len() == devnet.nodes().len())
+    });
+
+    // Every node externalized the same block, which spends the transaction's
+    // input.
+    let block_data = watcher_db.get_block_data_map(1).unwrap();
+    assert_eq!(block_data.len(), devnet.nodes().len());
+    for (src_url, data) in block_data {
+        assert_eq!(
+            data.contents().key_images,
+            tx.key_images(),
+            "Unexpected block 1 from {}",
+            src_url
+        );
+    }
+
+    // Proposing the transaction again crosses a rekey boundary, and then
+    // resumes a session with the node without verifying its report again.
+    // The node decrypts both proposals, and rejects them because the input is
+    // already spent.
+    for attempt in 0..2 {
+        match client.propose_tx(&tx) {
+            Err(ConnectionError::TransactionValidation(_)) => {}
+            result => panic!("Unexpected result for attempt {}: {:?}", attempt, result),
+        }
+        assert!(client.resumption_ticket().is_some());
+        client.deattest();
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Shut a node down the way it would on SIGTERM while it is externalizing
+//! transactions, and check that its ledger was committed cleanly and that the
+//! rest of the network carries on without it.
+
+use grpcio::EnvBuilder;
+use mc_account_keys::AccountKey;
+use mc_common::logger::{log, test_with_logger, Logger};
+use mc_connection::{
+    BlockchainConnection, HardcodedCredentialsProvider, ThickClient, UserTxConnection,
+};
+use mc_crypto_rand::McRng;
+use mc_devnet::{attestation::attestation_policy, config::DevnetConfig, Devnet, DevnetError};
+use mc_ledger_db::{Ledger, LedgerDB};
+use mc_transaction_core::tx::Tx;
+use mc_transaction_core_test_utils::create_transaction;
+use mc_util_keyfile::keygen;
+use mc_util_uri::ConsensusClientUri;
+use std::{
+    env,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+use tempdir::TempDir;
+
+/// How long to wait for the network to make progress before failing.
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Number of transactions proposed in each batch.
+const BATCH_SIZE: usize = 3;
+
+// Get the build dir, which is one down from current_exe, which is in
+// target/debug/deps,
+fn build_dir() -> PathBuf {
+    let mut result = env::current_exe().unwrap();
+    result.pop();
+    result.pop();
+    result
+}
+
+/// Call `f` until it returns `Some`, panicking if that takes longer than
+/// `TIMEOUT`.
+fn wait_for<T>(what: &str, mut f: impl FnMut() -> Option<T>) -> T {
+    let start = Instant::now();
+    loop {
+        if let Some(value) = f() {
+            return value;
+        }
+        assert!(start.elapsed() < TIMEOUT, "Timed out waiting for {}", what);
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+fn connect(uri: &ConsensusClientUri, logger: &Logger) -> ThickClient<HardcodedCredentialsProvider> {
+    let verifier = attestation_policy().unwrap().verifier().unwrap();
+    let env = Arc::new(EnvBuilder::new().name_prefix("shutdown-test").build());
+    ThickClient::new(
+        uri.clone(),
+        verifier,
+        env,
+        HardcodedCredentialsProvider::from(uri),
+        logger.clone(),
+    )
+    .unwrap()
+}
+
+/// Propose each transaction, retrying while the node is still starting up.
+fn propose_all(
+    client: &mut ThickClient<HardcodedCredentialsProvider>,
+    txs: &[Tx],
+    logger: &Logger,
+) {
+    for tx in txs {
+        wait_for("the transaction to be accepted", || {
+            client
+                .propose_tx(tx)
+                .map_err(|err| log::debug!(logger, "propose_tx failed: {}", err))
+                .ok()
+        });
+    }
+}
+
+// Requires SGX_MODE=SW and the ledger-distribution binary in the build dir,
+// and passes trivially otherwise.
+#[test_with_logger]
+fn node_shutdown_mid_slot_leaves_ledger_consistent(logger: Logger) {
+    let work_dir = TempDir::new("devnet-shutdown").unwrap();
+    // With four nodes, each quorum set needs two of its three peers, so the
+    // others keep externalizing blocks without the node which shuts down.
+    let config = DevnetConfig::from_iter(&[
+        "devnet",
+        "--work-dir",
+        work_dir.path().join("devnet").to_str().unwrap(),
+        "--bin-dir",
+        build_dir().to_str().unwrap(),
+        "--nodes",
+        "4",
+        "--base-port",
+        "3100",
+        "--num-keys",
+        "2",
+        "--outputs-per-key",
+        "10",
+    ]);
+    let mut devnet = match Devnet::start(config, logger.clone()) {
+        Err(DevnetError::NotSimulation) => {
+            log::warn!(logger, "Skipping, the devnet requires SGX_MODE=SW");
+            return;
+        }
+        result => result.expect("Could not start devnet"),
+    };
+
+    // The origin block pays each account in turn, so its first ten outputs
+    // belong to the first account.
+    let mut origin = LedgerDB::open(&devnet.origin_dir()).unwrap();
+    let accounts: Vec<AccountKey> = keygen::read_default_root_entropies(devnet.keys_dir())
+        .unwrap()
+        .iter()
+        .map(AccountKey::from)
+        .collect();
+    let mut txs: Vec<Tx> = (0..2 * BATCH_SIZE as u64 + 1)
+        .map(|index| {
+            let tx_out = origin.get_tx_out_by_index(index).unwrap();
+            create_transaction(
+                &mut origin,
+                &tx_out,
+                &accounts[0],
+                &accounts[1].default_subaddress(),
+                20,
+                &mut McRng::default(),
+            )
+        })
+        .collect();
+    let last_tx = txs.pop().unwrap();
+
+    let mut client0 = connect(&devnet.nodes()[0].client_uri(), &logger);
+    let mut client1 = connect(&devnet.nodes()[1].client_uri(), &logger);
+
+    propose_all(&mut client0, &txs[..BATCH_SIZE], &logger);
+    wait_for("block 1 to be externalized", || {
+        client0
+            .fetch_block_height()
+            .ok()
+            .filter(|height| *height > 1)
+    });
+
+    // Shut node 0 down while it is nominating the second batch.
+    propose_all(&mut client0, &txs[BATCH_SIZE..], &logger);
+    devnet
+        .shutdown_node(0)
+        .expect("Node 0 did not shut down cleanly");
+    assert!(
+        client0.propose_tx(&last_tx).is_err(),
+        "Node 0 accepted a transaction after shutting down"
+    );
+
+    // The rest of the network externalizes a transaction proposed after node 0
+    // stopped.
+    let height = client1.fetch_block_height().unwrap();
+    propose_all(&mut client1, &[last_tx.clone()], &logger);
+    wait_for("the network to externalize a block without node 0", || {
+        client1
+            .fetch_block_height()
+            .ok()
+            .filter(|new_height| *new_height > height)
+    });
+
+    // Every block node 0 committed is whole, chains to its parent, and is the
+    // block the rest of the network externalized at that index.
+    let ledger = LedgerDB::open(&devnet.ledger_dir(0)).unwrap();
+    let num_blocks = ledger.num_blocks().unwrap();
+    assert!(num_blocks > 1);
+    let blocks = wait_for("node 1 to catch up with node 0", || {
+        client1
+            .fetch_blocks(0..num_blocks)
+            .ok()
+            .filter(|blocks| blocks.len() as u64 == num_blocks)
+    });
+    for (index, expected) in blocks.iter().enumerate() {
+        let index = index as u64;
+        let block = ledger.get_block(index).unwrap();
+        assert_eq!(&block, expected, "Block {} differs from node 1's", index);
+        assert!(block.is_block_id_valid());
+        assert_eq!(
+            ledger.get_block_contents(index).unwrap().hash(),
+            block.contents_hash
+        );
+        if index > 0 {
+            assert_eq!(block.parent_id, ledger.get_block(index - 1).unwrap().id);
+        }
+    }
+}
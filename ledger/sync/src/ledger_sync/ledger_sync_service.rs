@@ -20,6 +20,7 @@ use mc_ledger_db::Ledger;
 use mc_transaction_core::{
     compute_block_id, ring_signature::KeyImage, Block, BlockContents, BlockID, BlockIndex,
 };
+use mc_util_telemetry::{global, Context, KeyValue, Span, TraceContextExt, Tracer};
 use mc_util_uri::ConnectionUri;
 use retry::delay::Fibonacci;
 use std::{
@@ -256,6 +257,10 @@ impl<
         limit: u32,
     ) -> Result<(), LedgerSyncError> {
         trace_time!(self.logger, "attempt_ledger_sync");
+        let tracer = global::tracer("mc_ledger_sync");
+        let mut span = tracer.start("attempt_ledger_sync");
+        span.set_attribute(KeyValue::new("limit", limit as i64));
+        let _trace_guard = Context::current_with_span(span).attach();
 
         let (responder_ids, _, potentially_safe_blocks) = self
             .get_potentially_safe_blocks(network_state, limit)
@@ -268,14 +273,16 @@ impl<
         let num_potentially_safe_blocks = potentially_safe_blocks.len();
 
         // Get transactions.
-        let block_index_to_opt_transactions: BTreeMap<BlockIndex, Option<BlockContents>> =
-            get_block_contents(
-                self.transactions_fetcher.clone(),
-                &responder_ids,
-                &potentially_safe_blocks,
-                self.get_transactions_timeout,
-                &self.logger,
-            );
+        let block_index_to_opt_transactions: BTreeMap<BlockIndex, Option<BlockContents>> = tracer
+            .in_span("get_block_contents", |_| {
+                get_block_contents(
+                    self.transactions_fetcher.clone(),
+                    &responder_ids,
+                    &potentially_safe_blocks,
+                    self.get_transactions_timeout,
+                    &self.logger,
+                )
+            });
 
         let mut blocks_and_contents: Vec<(Block, BlockContents)> = Vec::new();
 
@@ -325,7 +332,9 @@ impl<
         if let Ok(safe_blocks) =
             identify_safe_blocks(&self.ledger, &blocks_and_contents, &self.logger)
         {
-            self.append_safe_blocks(&safe_blocks)?;
+            tracer.in_span("append_safe_blocks", |_| {
+                self.append_safe_blocks(&safe_blocks)
+            })?;
         } else {
             log::info!(self.logger, "No safe blocks.");
         }
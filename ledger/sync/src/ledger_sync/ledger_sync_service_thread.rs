@@ -4,6 +4,7 @@
 //! performs the sync in a background thread.
 
 use crate::{LedgerSync, LedgerSyncService, PollingNetworkState, TransactionsFetcher};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
 use mc_common::logger::{log, Logger};
 use mc_connection::{BlockchainConnection, ConnectionManager};
 use mc_ledger_db::Ledger;
@@ -13,7 +14,7 @@ use std::{
         Arc, RwLock,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 /// Maximal number of blocks to attempt to sync at each loop iteration.
@@ -23,6 +24,9 @@ pub struct LedgerSyncServiceThread {
     join_handle: Option<thread::JoinHandle<()>>,
     currently_behind: Arc<AtomicBool>,
     stop_requested: Arc<AtomicBool>,
+    /// Disconnected once the thread has exited.
+    stopped: Receiver<()>,
+    logger: Logger,
 }
 
 impl LedgerSyncServiceThread {
@@ -48,12 +52,17 @@ impl LedgerSyncServiceThread {
         let currently_behind = Arc::new(AtomicBool::new(false));
         let stop_requested = Arc::new(AtomicBool::new(false));
 
+        let (stopped_sender, stopped) = crossbeam_channel::bounded::<()>(0);
+
         let thread_currently_behind = currently_behind.clone();
         let thread_stop_requested = stop_requested.clone();
+        let thread_logger = logger.clone();
         let join_handle = Some(
             thread::Builder::new()
                 .name("LedgerSync".into())
                 .spawn(move || {
+                    // Dropped when the thread exits, including on panic.
+                    let _stopped_sender = stopped_sender;
                     Self::thread_entrypoint(
                         ledger,
                         ledger_sync_service,
@@ -61,7 +70,7 @@ impl LedgerSyncServiceThread {
                         poll_interval,
                         thread_currently_behind,
                         thread_stop_requested,
-                        logger,
+                        thread_logger,
                     );
                 })
                 .expect("Failed spawning LedgerSync thread"),
@@ -71,6 +80,34 @@ impl LedgerSyncServiceThread {
             join_handle,
             currently_behind,
             stop_requested,
+            stopped,
+            logger,
+        }
+    }
+
+    /// Stop the thread once it has finished appending the blocks it is
+    /// syncing, or give up waiting at `deadline`. Each block is appended in
+    /// its own database transaction, so a ledger abandoned mid-batch is left
+    /// at the last appended block.
+    ///
+    /// Returns true if the thread has stopped.
+    pub fn stop_until(&mut self, deadline: Instant) -> bool {
+        self.stop_requested.store(true, Ordering::SeqCst);
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match self.stopped.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => {
+                log::warn!(
+                    self.logger,
+                    "LedgerSyncServiceThread did not stop before the deadline"
+                );
+                // Detach the thread, rather than blocking on it when dropped.
+                self.join_handle = None;
+                false
+            }
+            _ => {
+                self.stop();
+                true
+            }
         }
     }
 
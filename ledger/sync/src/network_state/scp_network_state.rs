@@ -57,6 +57,12 @@ impl<ID: GenericNodeId + Clone + Eq + PartialEq + Hash + Send> SCPNetworkState<I
     pub fn peer_to_current_slot(&self) -> &HashMap<ID, SlotIndex> {
         &self.id_to_current_slot
     }
+
+    /// Replace the quorum set of the node we are tracking state for. Slots
+    /// already reported by peers are kept.
+    pub fn set_local_quorum_set(&mut self, local_quorum_set: QuorumSet<ID>) {
+        self.local_quorum_set = local_quorum_set;
+    }
 }
 
 impl<ID: GenericNodeId + Send + AsRef<ResponderId> + DeserializeOwned + Serialize> NetworkState
@@ -0,0 +1,69 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Verify a ledger db or block archive, writing a JSON report.
+
+use mc_common::logger::{create_app_logger, log, o};
+use mc_ledger_db::LedgerDB;
+use mc_ledger_sync::ReqwestTransactionsFetcher;
+use mc_ledger_verify::{
+    config::LedgerVerifyConfig, verify_chain, ArchiveBlockSource, VerificationReport,
+};
+use std::{fs::File, io, process::exit};
+use structopt::StructOpt;
+
+fn main() {
+    mc_common::setup_panic_handler();
+    let (logger, _global_logger_guard) = create_app_logger(o!());
+
+    let config = LedgerVerifyConfig::from_args();
+
+    let result = if let Some(ledger_db) = &config.ledger_db {
+        log::info!(logger, "Verifying ledger db at {:?}", ledger_db);
+        let ledger = LedgerDB::open(ledger_db).expect("Failed opening LedgerDB");
+        verify_chain(
+            &ledger,
+            &config.trusted_signers,
+            config.blocks_per_batch,
+            &logger,
+        )
+    } else {
+        log::info!(logger, "Verifying archive at {:?}", config.tx_source_urls);
+        let fetcher =
+            ReqwestTransactionsFetcher::new(config.tx_source_urls.clone(), logger.clone())
+                .expect("Failed creating ReqwestTransactionsFetcher");
+        verify_chain(
+            &ArchiveBlockSource::new(fetcher, config.num_blocks),
+            &config.trusted_signers,
+            config.blocks_per_batch,
+            &logger,
+        )
+    };
+
+    let report: VerificationReport = result.unwrap_or_else(|err| {
+        log::crit!(logger, "Verification could not complete: {}", err);
+        exit(2);
+    });
+
+    match &config.report {
+        Some(path) => {
+            let file = File::create(path).expect("Failed creating report file");
+            serde_json::to_writer_pretty(file, &report).expect("Failed writing report");
+        }
+        None => {
+            serde_json::to_writer_pretty(io::stdout(), &report).expect("Failed writing report");
+            println!();
+        }
+    }
+
+    if report.valid {
+        log::info!(logger, "Verified {} blocks", report.num_blocks);
+    } else {
+        log::error!(
+            logger,
+            "Found {} failures in {} blocks",
+            report.failures.len(),
+            report.num_blocks
+        );
+        exit(1);
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Configuration parameters for the ledger verifier.
+
+use mc_crypto_keys::Ed25519Public;
+use std::{convert::TryFrom, path::PathBuf};
+use structopt::StructOpt;
+
+/// Command line configuration.
+#[derive(Clone, Debug, StructOpt)]
+#[structopt(
+    name = "ledger-verify",
+    about = "Verify that a ledger db or block archive is a valid chain."
+)]
+pub struct LedgerVerifyConfig {
+    /// Path to the ledger db (lmdb) to verify.
+    #[structopt(long, parse(from_os_str), required_unless = "tx-source-urls")]
+    pub ledger_db: Option<PathBuf>,
+
+    /// URLs of a block archive to verify, instead of a ledger db.
+    ///
+    /// For example: https://s3-us-west-1.amazonaws.com/mobilecoin.chain/node1.master.mobilecoin.com/
+    #[structopt(long = "tx-source-url", conflicts_with = "ledger-db")]
+    pub tx_source_urls: Vec<String>,
+
+    /// Number of archive blocks to verify. If not given, the archive is
+    /// searched for its last block.
+    #[structopt(long)]
+    pub num_blocks: Option<u64>,
+
+    /// Hex-encoded Ed25519 public key of a trusted block signer. Required, and
+    /// may be repeated.
+    #[structopt(
+        long = "trusted-signer",
+        required = true,
+        parse(try_from_str = parse_signer)
+    )]
+    pub trusted_signers: Vec<Ed25519Public>,
+
+    /// Number of blocks to check in parallel at a time.
+    #[structopt(long, default_value = "1000")]
+    pub blocks_per_batch: u64,
+
+    /// Where to write the JSON report. Defaults to stdout.
+    #[structopt(long, parse(from_os_str))]
+    pub report: Option<PathBuf>,
+}
+
+fn parse_signer(src: &str) -> Result<Ed25519Public, String> {
+    let bytes = hex::decode(src).map_err(|err| format!("Invalid hex: {}", err))?;
+    Ed25519Public::try_from(&bytes[..]).map_err(|err| format!("Invalid public key: {:?}", err))
+}
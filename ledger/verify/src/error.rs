@@ -0,0 +1,29 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Error data types
+
+use displaydoc::Display;
+use mc_ledger_db::Error as LedgerDbError;
+use mc_ledger_sync::ReqwestTransactionsFetcherError;
+
+/// Errors which stop verification before the whole chain has been checked.
+///
+/// Problems with the chain itself are not errors; they are recorded in the
+/// `VerificationReport`.
+#[derive(Debug, Display)]
+pub enum VerifyError {
+    /// Ledger DB: {0}
+    LedgerDb(LedgerDbError),
+
+    /// Fetching block {0} failed: {1}
+    BlockFetch(u64, ReqwestTransactionsFetcherError),
+
+    /// The archive has no origin block
+    EmptyArchive,
+}
+
+impl From<LedgerDbError> for VerifyError {
+    fn from(src: LedgerDbError) -> Self {
+        Self::LedgerDb(src)
+    }
+}
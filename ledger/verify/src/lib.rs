@@ -0,0 +1,18 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+#![feature(external_doc)]
+#![doc(include = "../README.md")]
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+pub mod config;
+pub mod error;
+pub mod merkle;
+pub mod report;
+pub mod source;
+pub mod verifier;
+
+pub use error::VerifyError;
+pub use report::{BlockFailure, FailureKind, SignerSummary, VerificationReport};
+pub use source::{ArchiveBlockSource, BlockSource};
+pub use verifier::verify_chain;
@@ -0,0 +1,153 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Incremental computation of the TxOut Merkle tree's root.
+//!
+//! The ledger's Merkle tree has a leaf for each TxOut, and is padded to a
+//! power of two leaves. A node whose range contains no TxOuts has the nil
+//! hash. Rather than storing the tree, `MerkleFrontier` keeps the root of
+//! each complete subtree along its right edge, which is enough to compute the
+//! root for the TxOuts appended so far.
+
+use mc_transaction_core::{
+    membership_proofs::{hash_nodes, Range, NIL_HASH},
+    tx::{TxOutMembershipElement, TxOutMembershipHash},
+};
+
+/// The complete subtrees of a Merkle tree which may still grow to the right.
+#[derive(Clone, Debug, Default)]
+pub struct MerkleFrontier {
+    /// `peaks[h]` is the root of the complete subtree of `2^h` leaves which
+    /// is present when bit `h` of `num_leaves` is set.
+    peaks: Vec<Option<[u8; 32]>>,
+
+    /// The number of leaves appended.
+    num_leaves: u64,
+}
+
+impl MerkleFrontier {
+    /// The number of leaves appended.
+    pub fn num_leaves(&self) -> u64 {
+        self.num_leaves
+    }
+
+    /// Append the hash of a leaf, e.g. from `hash_leaf`.
+    pub fn push(&mut self, leaf_hash: [u8; 32]) {
+        let mut carry = leaf_hash;
+        let mut height = 0;
+        while let Some(Some(peak)) = self.peaks.get(height) {
+            carry = hash_nodes(peak, &carry);
+            self.peaks[height] = None;
+            height += 1;
+        }
+        if height == self.peaks.len() {
+            self.peaks.push(None);
+        }
+        self.peaks[height] = Some(carry);
+        self.num_leaves += 1;
+    }
+
+    /// The root of the tree, or the nil hash if it is empty.
+    pub fn root_hash(&self) -> [u8; 32] {
+        if self.num_leaves == 0 {
+            return *NIL_HASH;
+        }
+        let height = self.height();
+        if self.num_leaves.is_power_of_two() {
+            return self.peaks[height].expect("A full tree has a peak at its height");
+        }
+
+        // Walk up from the first empty leaf, which is the right neighbour of
+        // the last TxOut. `node` is the hash of the subtree containing it, or
+        // `None` while that subtree is still empty.
+        let mut node: Option<[u8; 32]> = None;
+        for h in 0..height {
+            node = if self.num_leaves & (1 << h) != 0 {
+                // A right child, whose left sibling is complete.
+                let left = self.peaks[h].expect("Set bits have peaks");
+                Some(hash_nodes(&left, &node.unwrap_or(*NIL_HASH)))
+            } else {
+                // A left child, whose right sibling is empty.
+                node.map(|left| hash_nodes(&left, &NIL_HASH))
+            };
+        }
+        node.expect("A non-empty tree has a root")
+    }
+
+    /// The root element that a membership proof against this tree would
+    /// imply, as stored in a block's `root_element`.
+    ///
+    /// Returns `None` if the tree is empty.
+    pub fn root_element(&self) -> Option<TxOutMembershipElement> {
+        if self.num_leaves == 0 {
+            return None;
+        }
+        let range = Range::new(0, (1u64 << self.height()) - 1).ok()?;
+        Some(TxOutMembershipElement {
+            range,
+            hash: TxOutMembershipHash::from(self.root_hash()),
+        })
+    }
+
+    /// The height of the padded tree, i.e. `log2` of its number of leaves.
+    fn height(&self) -> usize {
+        self.num_leaves.next_power_of_two().trailing_zeros() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_transaction_core::membership_proofs::hash_leaf;
+    use mc_transaction_core_test_utils::{get_outputs, AccountKey};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// The root of the subtree over `[low, high]`, computed directly.
+    fn naive_root(leaves: &[[u8; 32]], low: usize, high: usize) -> [u8; 32] {
+        if low >= leaves.len() {
+            *NIL_HASH
+        } else if low == high {
+            leaves[low]
+        } else {
+            let mid = (low + high) / 2;
+            hash_nodes(
+                &naive_root(leaves, low, mid),
+                &naive_root(leaves, mid + 1, high),
+            )
+        }
+    }
+
+    #[test]
+    // The frontier's root should match the root of the whole padded tree after
+    // each leaf is appended.
+    fn test_root_hash_matches_naive_root() {
+        let mut rng: StdRng = SeedableRng::from_seed([7u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let recipients: Vec<_> = (0..40)
+            .map(|i| (account_key.default_subaddress(), i + 1))
+            .collect();
+        let leaves: Vec<[u8; 32]> = get_outputs(&recipients, &mut rng)
+            .iter()
+            .map(hash_leaf)
+            .collect();
+
+        let mut frontier = MerkleFrontier::default();
+        assert_eq!(frontier.root_hash(), *NIL_HASH);
+        assert_eq!(frontier.root_element(), None);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            frontier.push(*leaf);
+            let num_leaves = i + 1;
+            let padded = num_leaves.next_power_of_two();
+            assert_eq!(frontier.num_leaves(), num_leaves as u64);
+            assert_eq!(
+                frontier.root_hash(),
+                naive_root(&leaves[..num_leaves], 0, padded - 1)
+            );
+            let root_element = frontier.root_element().unwrap();
+            assert_eq!(
+                root_element.range,
+                Range::new(0, padded as u64 - 1).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! The machine-readable result of verifying a chain.
+
+use serde::Serialize;
+
+/// What was found when verifying a chain.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct VerificationReport {
+    /// Whether every check passed.
+    pub valid: bool,
+
+    /// The number of blocks verified.
+    pub num_blocks: u64,
+
+    /// The number of TxOuts in the verified blocks.
+    pub num_tx_outs: u64,
+
+    /// The number of key images in the verified blocks.
+    pub num_key_images: u64,
+
+    /// Hex-encoded root hash of the TxOut Merkle tree after the last block.
+    pub tx_out_root_hash: String,
+
+    /// The keys which signed blocks, in the order they first appear.
+    pub signers: Vec<SignerSummary>,
+
+    /// Every failed check, by block.
+    pub failures: Vec<BlockFailure>,
+}
+
+/// The blocks signed by one key.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SignerSummary {
+    /// Hex-encoded Ed25519 public key.
+    pub signer: String,
+
+    /// Whether the key is in the trusted signer list.
+    pub trusted: bool,
+
+    /// The first block it signed.
+    pub first_block: u64,
+
+    /// The last block it signed.
+    pub last_block: u64,
+
+    /// The number of blocks it signed.
+    pub num_blocks: u64,
+}
+
+/// A check which a block failed.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct BlockFailure {
+    /// The index of the block.
+    pub block_index: u64,
+
+    /// The check which failed.
+    pub kind: FailureKind,
+
+    /// Details of the failure.
+    pub message: String,
+}
+
+/// The checks made of each block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The block is not at the index it was fetched from.
+    InvalidIndex,
+
+    /// The block's version is unknown, or lower than its parent's.
+    InvalidVersion,
+
+    /// The block's ID is not `compute_block_id` of its header.
+    InvalidBlockId,
+
+    /// The block's parent ID is not the ID of the previous block.
+    InvalidParentId,
+
+    /// The block's contents hash does not match its contents.
+    InvalidContentsHash,
+
+    /// The block's cumulative TxOut count does not match its parent's plus
+    /// its own outputs.
+    InvalidCumulativeTxoCount,
+
+    /// The block's root element is not the root of the TxOut Merkle tree of
+    /// the ledger it extends.
+    InvalidRootElement,
+
+    /// The block has no outputs.
+    NoOutputs,
+
+    /// A block other than the origin block has no key images.
+    NoKeyImages,
+
+    /// An output's amount commitment or keys are not valid curve points.
+    InvalidOutput,
+
+    /// A key image was spent in an earlier block, or twice in this one.
+    DuplicateKeyImage,
+
+    /// An output public key appeared in an earlier block, or twice in this
+    /// one.
+    DuplicateOutputPublicKey,
+
+    /// A block other than the origin block has no signature.
+    MissingSignature,
+
+    /// The block's signature does not verify.
+    InvalidSignature,
+
+    /// The block was signed by a key which is not trusted.
+    UntrustedSigner,
+}
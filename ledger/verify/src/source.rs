@@ -0,0 +1,92 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Where blocks to verify come from.
+
+use crate::error::VerifyError;
+use mc_ledger_db::{Ledger, LedgerDB};
+use mc_ledger_sync::ReqwestTransactionsFetcher;
+use mc_transaction_core::{BlockData, BlockIndex};
+
+/// A source of blocks, which may be read concurrently by cloning it.
+pub trait BlockSource: Clone + Send {
+    /// The number of blocks to verify.
+    fn num_blocks(&self) -> Result<u64, VerifyError>;
+
+    /// Get a block, with its contents and signature.
+    fn get_block_data(&self, block_index: BlockIndex) -> Result<BlockData, VerifyError>;
+}
+
+impl BlockSource for LedgerDB {
+    fn num_blocks(&self) -> Result<u64, VerifyError> {
+        Ok(Ledger::num_blocks(self)?)
+    }
+
+    fn get_block_data(&self, block_index: BlockIndex) -> Result<BlockData, VerifyError> {
+        Ok(Ledger::get_block_data(self, block_index)?)
+    }
+}
+
+/// Blocks from a block archive, e.g. one in S3 written by
+/// `ledger-distribution`.
+#[derive(Clone)]
+pub struct ArchiveBlockSource {
+    fetcher: ReqwestTransactionsFetcher,
+    num_blocks: Option<u64>,
+}
+
+impl ArchiveBlockSource {
+    /// Read blocks with `fetcher`.
+    ///
+    /// If `num_blocks` is `None`, every block in the archive is verified.
+    /// Since the archive cannot be listed, its length is found by searching
+    /// for the first block which cannot be fetched; a block missing from the
+    /// middle of the archive would end the search early, so pass
+    /// `num_blocks` when it is known.
+    pub fn new(fetcher: ReqwestTransactionsFetcher, num_blocks: Option<u64>) -> Self {
+        Self {
+            fetcher,
+            num_blocks,
+        }
+    }
+
+    fn has_block(&self, block_index: BlockIndex) -> bool {
+        self.fetcher
+            .get_block_data_by_index(block_index, None)
+            .is_ok()
+    }
+}
+
+impl BlockSource for ArchiveBlockSource {
+    fn num_blocks(&self) -> Result<u64, VerifyError> {
+        if let Some(num_blocks) = self.num_blocks {
+            return Ok(num_blocks);
+        }
+        if !self.has_block(0) {
+            return Err(VerifyError::EmptyArchive);
+        }
+
+        // Find a block past the end of the archive, then the end itself. The
+        // archive holds `low` but not `high`.
+        let mut low = 0;
+        let mut high = 1;
+        while self.has_block(high) {
+            low = high;
+            high *= 2;
+        }
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if self.has_block(mid) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(high)
+    }
+
+    fn get_block_data(&self, block_index: BlockIndex) -> Result<BlockData, VerifyError> {
+        self.fetcher
+            .get_block_data_by_index(block_index, None)
+            .map_err(|err| VerifyError::BlockFetch(block_index, err))
+    }
+}
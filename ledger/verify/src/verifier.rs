@@ -0,0 +1,633 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Verification of a chain of blocks.
+//!
+//! Checks which need only a single block (its ID, contents hash, outputs and
+//! signature) are run in parallel over a range of blocks at a time. Checks
+//! which need the chain up to a block (parent linkage, TxOut counts, the
+//! Merkle root and uniqueness of key images and output public keys) are then
+//! run over that range in order.
+
+use crate::{
+    error::VerifyError,
+    merkle::MerkleFrontier,
+    report::{BlockFailure, FailureKind, SignerSummary, VerificationReport},
+    source::BlockSource,
+};
+use mc_common::{
+    logger::{log, Logger},
+    HashMap, HashSet,
+};
+use mc_crypto_keys::{CompressedRistrettoPublic, Ed25519Public, RistrettoPublic};
+use mc_transaction_core::{
+    membership_proofs::hash_leaf, ring_signature::KeyImage, tx::TxOutMembershipElement, Block,
+    BlockID, BlockIndex, Commitment, BLOCK_VERSION,
+};
+use rayon::prelude::*;
+use std::convert::TryFrom;
+
+/// Verify every block in `source`.
+///
+/// # Arguments
+/// * `source` - The blocks to verify.
+/// * `trusted_signers` - Keys allowed to sign blocks. A block signed by any
+///   other key fails, so if this is empty no signed block is valid.
+/// * `blocks_per_batch` - The number of blocks checked in parallel at a time.
+/// * `logger` - Logger.
+pub fn verify_chain<S: BlockSource>(
+    source: &S,
+    trusted_signers: &[Ed25519Public],
+    blocks_per_batch: u64,
+    logger: &Logger,
+) -> Result<VerificationReport, VerifyError> {
+    let num_blocks = source.num_blocks()?;
+    log::info!(logger, "Verifying {} blocks", num_blocks);
+
+    let mut chain = ChainState::default();
+    let mut start = 0;
+    while start < num_blocks {
+        let end = num_blocks.min(start + blocks_per_batch.max(1));
+        let checked_blocks = (start..end)
+            .into_par_iter()
+            .map_with(source.clone(), |source, block_index| {
+                check_block(source, block_index, trusted_signers)
+            })
+            .collect::<Result<Vec<CheckedBlock>, VerifyError>>()?;
+
+        for checked_block in checked_blocks {
+            chain.append(checked_block);
+        }
+        log::info!(
+            logger,
+            "Verified blocks {}-{} ({} failures so far)",
+            start,
+            end - 1,
+            chain.report.failures.len()
+        );
+        start = end;
+    }
+
+    Ok(chain.into_report())
+}
+
+/// A block, and the results of the checks which need only the block itself.
+struct CheckedBlock {
+    block: Block,
+    key_images: Vec<KeyImage>,
+    output_public_keys: Vec<CompressedRistrettoPublic>,
+    leaf_hashes: Vec<[u8; 32]>,
+    signer: Option<(Ed25519Public, bool)>,
+    failures: Vec<BlockFailure>,
+}
+
+/// Fetch a block and run the checks which need only the block itself.
+fn check_block<S: BlockSource>(
+    source: &S,
+    block_index: BlockIndex,
+    trusted_signers: &[Ed25519Public],
+) -> Result<CheckedBlock, VerifyError> {
+    let block_data = source.get_block_data(block_index)?;
+    let block = block_data.block();
+    let contents = block_data.contents();
+
+    let mut failures = Vec::new();
+    let mut fail = |kind, message: String| {
+        failures.push(BlockFailure {
+            block_index,
+            kind,
+            message,
+        })
+    };
+
+    if block.index != block_index {
+        fail(
+            FailureKind::InvalidIndex,
+            format!("Block has index {}", block.index),
+        );
+    }
+    if !block.is_block_id_valid() {
+        fail(
+            FailureKind::InvalidBlockId,
+            format!(
+                "Block ID {} does not match its header",
+                hex::encode(&block.id)
+            ),
+        );
+    }
+    if block.contents_hash != contents.hash() {
+        fail(
+            FailureKind::InvalidContentsHash,
+            "Contents hash does not match the block's contents".to_string(),
+        );
+    }
+    if contents.outputs.is_empty() {
+        fail(FailureKind::NoOutputs, "Block has no outputs".to_string());
+    }
+    if block_index != 0 && contents.key_images.is_empty() {
+        fail(
+            FailureKind::NoKeyImages,
+            "Block has no key images".to_string(),
+        );
+    }
+
+    // The range proofs for output amounts are part of transaction signatures,
+    // which are not kept in blocks, so check what can be checked: that every
+    // commitment and key is a valid point.
+    for (i, output) in contents.outputs.iter().enumerate() {
+        if Commitment::try_from(&output.amount.commitment).is_err() {
+            fail(
+                FailureKind::InvalidOutput,
+                format!("Output {} has an invalid amount commitment", i),
+            );
+        }
+        if RistrettoPublic::try_from(&output.target_key).is_err() {
+            fail(
+                FailureKind::InvalidOutput,
+                format!("Output {} has an invalid target key", i),
+            );
+        }
+        if RistrettoPublic::try_from(&output.public_key).is_err() {
+            fail(
+                FailureKind::InvalidOutput,
+                format!("Output {} has an invalid public key", i),
+            );
+        }
+    }
+
+    let signer = match block_data.signature() {
+        Some(signature) => {
+            let trusted = trusted_signers.contains(signature.signer());
+            if signature.verify(block).is_err() {
+                fail(
+                    FailureKind::InvalidSignature,
+                    format!(
+                        "Signature by {} does not verify",
+                        hex::encode(signature.signer())
+                    ),
+                );
+            } else if !trusted {
+                fail(
+                    FailureKind::UntrustedSigner,
+                    format!(
+                        "Signed by untrusted key {}",
+                        hex::encode(signature.signer())
+                    ),
+                );
+            }
+            Some((*signature.signer(), trusted))
+        }
+        // The origin block is not signed by consensus.
+        None if block_index == 0 => None,
+        None => {
+            fail(
+                FailureKind::MissingSignature,
+                "Block has no signature".to_string(),
+            );
+            None
+        }
+    };
+
+    Ok(CheckedBlock {
+        block: block.clone(),
+        key_images: contents.key_images.clone(),
+        output_public_keys: contents
+            .outputs
+            .iter()
+            .map(|output| output.public_key)
+            .collect(),
+        leaf_hashes: contents.outputs.iter().map(hash_leaf).collect(),
+        signer,
+        failures,
+    })
+}
+
+/// The state of the chain up to the last block appended.
+#[derive(Default)]
+struct ChainState {
+    last_block: Option<Block>,
+    tx_outs: MerkleFrontier,
+    key_images: HashSet<KeyImage>,
+    output_public_keys: HashSet<CompressedRistrettoPublic>,
+    signer_indices: HashMap<Ed25519Public, usize>,
+    report: VerificationReport,
+}
+
+impl ChainState {
+    /// Run the checks which need the chain up to a block, then add the block
+    /// to the chain.
+    fn append(&mut self, checked_block: CheckedBlock) {
+        let CheckedBlock {
+            block,
+            key_images,
+            output_public_keys,
+            leaf_hashes,
+            signer,
+            failures,
+        } = checked_block;
+        let block_index = block.index;
+        self.report.failures.extend(failures);
+
+        let mut failures = Vec::new();
+        let mut fail = |kind, message: String| {
+            failures.push(BlockFailure {
+                block_index,
+                kind,
+                message,
+            })
+        };
+
+        let num_outputs = leaf_hashes.len() as u64;
+        match &self.last_block {
+            None => {
+                // The origin block is version 0, has a default parent ID and
+                // was not validated against any TxOuts.
+                if block.version != 0 {
+                    fail(
+                        FailureKind::InvalidVersion,
+                        format!("Origin block has version {}", block.version),
+                    );
+                }
+                if block.parent_id != BlockID::default() {
+                    fail(
+                        FailureKind::InvalidParentId,
+                        "Origin block has a parent".to_string(),
+                    );
+                }
+                if block.root_element != TxOutMembershipElement::default() {
+                    fail(
+                        FailureKind::InvalidRootElement,
+                        "Origin block has a root element".to_string(),
+                    );
+                }
+                if block.cumulative_txo_count != num_outputs {
+                    fail(
+                        FailureKind::InvalidCumulativeTxoCount,
+                        format!(
+                            "Cumulative TxOut count is {}, expected {}",
+                            block.cumulative_txo_count, num_outputs
+                        ),
+                    );
+                }
+            }
+            Some(parent) => {
+                if block.version < parent.version || block.version > BLOCK_VERSION {
+                    fail(
+                        FailureKind::InvalidVersion,
+                        format!(
+                            "Block has version {}, parent has version {}",
+                            block.version, parent.version
+                        ),
+                    );
+                }
+                if block.parent_id != parent.id {
+                    fail(
+                        FailureKind::InvalidParentId,
+                        format!(
+                            "Parent ID {} is not the previous block's ID {}",
+                            hex::encode(&block.parent_id),
+                            hex::encode(&parent.id)
+                        ),
+                    );
+                }
+                let expected_txo_count = parent.cumulative_txo_count + num_outputs;
+                if block.cumulative_txo_count != expected_txo_count {
+                    fail(
+                        FailureKind::InvalidCumulativeTxoCount,
+                        format!(
+                            "Cumulative TxOut count is {}, expected {}",
+                            block.cumulative_txo_count, expected_txo_count
+                        ),
+                    );
+                }
+                // Transactions were validated against the ledger the block
+                // extends.
+                if self.tx_outs.root_element().as_ref() != Some(&block.root_element) {
+                    fail(
+                        FailureKind::InvalidRootElement,
+                        format!(
+                            "Root element is not the root of the first {} TxOuts",
+                            self.tx_outs.num_leaves()
+                        ),
+                    );
+                }
+            }
+        }
+
+        for key_image in &key_images {
+            if !self.key_images.insert(*key_image) {
+                fail(
+                    FailureKind::DuplicateKeyImage,
+                    format!("Key image {} was already spent", hex::encode(key_image)),
+                );
+            }
+        }
+        for public_key in &output_public_keys {
+            if !self.output_public_keys.insert(*public_key) {
+                fail(
+                    FailureKind::DuplicateOutputPublicKey,
+                    format!(
+                        "Output public key {} is already in the ledger",
+                        hex::encode(public_key)
+                    ),
+                );
+            }
+        }
+        self.report.failures.extend(failures);
+
+        for leaf_hash in leaf_hashes {
+            self.tx_outs.push(leaf_hash);
+        }
+
+        if let Some((signer, trusted)) = signer {
+            let signers = &mut self.report.signers;
+            let index = *self.signer_indices.entry(signer).or_insert_with(|| {
+                signers.push(SignerSummary {
+                    signer: hex::encode(&signer),
+                    trusted,
+                    first_block: block_index,
+                    last_block: block_index,
+                    num_blocks: 0,
+                });
+                signers.len() - 1
+            });
+            let summary = &mut signers[index];
+            summary.last_block = block_index;
+            summary.num_blocks += 1;
+        }
+
+        self.report.num_blocks += 1;
+        self.report.num_tx_outs += num_outputs;
+        self.report.num_key_images += key_images.len() as u64;
+        self.last_block = Some(block);
+    }
+
+    fn into_report(mut self) -> VerificationReport {
+        self.report.valid = self.report.failures.is_empty();
+        self.report.tx_out_root_hash = hex::encode(self.tx_outs.root_hash());
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::Ed25519Pair;
+    use mc_ledger_db::{Ledger, LedgerDB};
+    use mc_transaction_core::{
+        membership_proofs::compute_implied_merkle_root, BlockContents, BlockData, BlockSignature,
+    };
+    use mc_transaction_core_test_utils::{create_ledger, get_outputs, AccountKey};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+    /// Blocks held in memory, which may be tampered with.
+    #[derive(Clone)]
+    struct VecBlockSource(Vec<BlockData>);
+
+    impl BlockSource for VecBlockSource {
+        fn num_blocks(&self) -> Result<u64, VerifyError> {
+            Ok(self.0.len() as u64)
+        }
+
+        fn get_block_data(&self, block_index: BlockIndex) -> Result<BlockData, VerifyError> {
+            Ok(self.0[block_index as usize].clone())
+        }
+    }
+
+    /// Create a valid chain of blocks signed by `signer`, in a ledger and in
+    /// memory.
+    fn create_chain(
+        num_blocks: u64,
+        signer: &Ed25519Pair,
+        rng: &mut StdRng,
+    ) -> (LedgerDB, Vec<BlockData>) {
+        let mut ledger = create_ledger();
+        let account_key = AccountKey::random(rng);
+        let mut blocks = Vec::new();
+
+        for block_index in 0..num_blocks {
+            let recipients: Vec<_> = (0..1 + rng.next_u64() % 5)
+                .map(|_| (account_key.default_subaddress(), 1000))
+                .collect();
+            let outputs = get_outputs(&recipients, rng);
+
+            let block_data = if block_index == 0 {
+                let block = Block::new_origin_block(&outputs);
+                BlockData::new(block, BlockContents::new(Vec::new(), outputs), None)
+            } else {
+                let parent = ledger.get_block(block_index - 1).unwrap();
+                let proofs = ledger.get_tx_out_proof_of_memberships(&[0]).unwrap();
+                let root_element = compute_implied_merkle_root(&proofs[0]).unwrap();
+                let contents = BlockContents::new(vec![KeyImage::from(rng.next_u64())], outputs);
+                let block =
+                    Block::new_with_parent(BLOCK_VERSION, &parent, &root_element, &contents);
+                let signature = BlockSignature::from_block_and_keypair(&block, signer).unwrap();
+                BlockData::new(block, contents, Some(signature))
+            };
+
+            ledger
+                .append_block(
+                    block_data.block(),
+                    block_data.contents(),
+                    block_data.signature().clone(),
+                )
+                .unwrap();
+            blocks.push(block_data);
+        }
+
+        (ledger, blocks)
+    }
+
+    fn failures(report: &VerificationReport) -> Vec<(u64, FailureKind)> {
+        report
+            .failures
+            .iter()
+            .map(|failure| (failure.block_index, failure.kind))
+            .collect()
+    }
+
+    #[test_with_logger]
+    // A valid chain should verify, from a ledger or from memory, in any number of
+    // batches.
+    fn test_verify_valid_chain(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let signer = Ed25519Pair::from_random(&mut rng);
+        let (ledger, blocks) = create_chain(12, &signer, &mut rng);
+        let trusted_signers = [signer.public_key()];
+
+        let report = verify_chain(&ledger, &trusted_signers, 5, &logger).unwrap();
+        assert!(report.valid, "{:?}", report.failures);
+        assert_eq!(report.num_blocks, 12);
+        assert_eq!(report.num_tx_outs, ledger.num_txos().unwrap());
+        assert_eq!(report.num_key_images, 11);
+        assert_eq!(
+            report.signers,
+            vec![SignerSummary {
+                signer: hex::encode(signer.public_key()),
+                trusted: true,
+                first_block: 1,
+                last_block: 11,
+                num_blocks: 11,
+            }]
+        );
+
+        // The reported root is the ledger's.
+        let proofs = ledger.get_tx_out_proof_of_memberships(&[0]).unwrap();
+        let root_element = compute_implied_merkle_root(&proofs[0]).unwrap();
+        assert_eq!(report.tx_out_root_hash, hex::encode(root_element.hash.0));
+
+        for blocks_per_batch in &[1, 12, 100] {
+            let report = verify_chain(
+                &VecBlockSource(blocks.clone()),
+                &trusted_signers,
+                *blocks_per_batch,
+                &logger,
+            )
+            .unwrap();
+            assert!(report.valid, "{:?}", report.failures);
+        }
+    }
+
+    #[test_with_logger]
+    // Without trusted signers, signatures are not anchored to anything, so every
+    // signed block should fail.
+    fn test_verify_requires_trusted_signers(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([5u8; 32]);
+        let signer = Ed25519Pair::from_random(&mut rng);
+        let (ledger, _blocks) = create_chain(4, &signer, &mut rng);
+
+        let report = verify_chain(&ledger, &[], 5, &logger).unwrap();
+        assert!(!report.valid);
+        assert_eq!(
+            failures(&report),
+            (1..4)
+                .map(|block_index| (block_index, FailureKind::UntrustedSigner))
+                .collect::<Vec<_>>()
+        );
+        assert!(report.signers.iter().all(|summary| !summary.trusted));
+    }
+
+    #[test_with_logger]
+    // Each kind of tampering should be reported at the block where it happened.
+    fn test_verify_reports_tampering(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let signer = Ed25519Pair::from_random(&mut rng);
+        let (_ledger, blocks) = create_chain(8, &signer, &mut rng);
+        let trusted_signers = [signer.public_key()];
+
+        // A signer which is not trusted.
+        let other_signer = Ed25519Pair::from_random(&mut rng);
+        let report = verify_chain(
+            &VecBlockSource(blocks.clone()),
+            &[other_signer.public_key()],
+            3,
+            &logger,
+        )
+        .unwrap();
+        assert!(!report.valid);
+        assert_eq!(
+            failures(&report),
+            (1..8)
+                .map(|block_index| (block_index, FailureKind::UntrustedSigner))
+                .collect::<Vec<_>>()
+        );
+        assert!(!report.signers[0].trusted);
+
+        // Contents which do not match the block.
+        let mut tampered = blocks.clone();
+        let block_data = &tampered[2];
+        let mut contents = block_data.contents().clone();
+        contents.outputs.pop();
+        tampered[2] = BlockData::new(
+            block_data.block().clone(),
+            contents,
+            block_data.signature().clone(),
+        );
+        let report = verify_chain(&VecBlockSource(tampered), &trusted_signers, 3, &logger).unwrap();
+        let mut expected = vec![(2, FailureKind::InvalidContentsHash)];
+        if blocks[2].contents().outputs.len() == 1 {
+            expected.push((2, FailureKind::NoOutputs));
+        }
+        expected.push((2, FailureKind::InvalidCumulativeTxoCount));
+        // Every later block was validated against a TxOut the chain is missing.
+        expected.extend((3..8).map(|block_index| (block_index, FailureKind::InvalidRootElement)));
+        assert_eq!(failures(&report), expected);
+
+        // A missing signature.
+        let mut tampered = blocks.clone();
+        tampered[5] = BlockData::new(
+            blocks[5].block().clone(),
+            blocks[5].contents().clone(),
+            None,
+        );
+        let report = verify_chain(&VecBlockSource(tampered), &trusted_signers, 3, &logger).unwrap();
+        assert_eq!(failures(&report), vec![(5, FailureKind::MissingSignature)]);
+
+        // A signature over another block.
+        let mut tampered = blocks.clone();
+        tampered[5] = BlockData::new(
+            blocks[5].block().clone(),
+            blocks[5].contents().clone(),
+            blocks[4].signature().clone(),
+        );
+        let report = verify_chain(&VecBlockSource(tampered), &trusted_signers, 3, &logger).unwrap();
+        assert_eq!(failures(&report), vec![(5, FailureKind::InvalidSignature)]);
+
+        // A block whose header was changed, with its ID and signature updated.
+        let mut tampered = blocks.clone();
+        let mut block = blocks[4].block().clone();
+        block.cumulative_txo_count += 1;
+        block.id = compute_block_id(&block);
+        let signature = BlockSignature::from_block_and_keypair(&block, &signer).unwrap();
+        tampered[4] = BlockData::new(block, blocks[4].contents().clone(), Some(signature));
+        let report = verify_chain(&VecBlockSource(tampered), &trusted_signers, 3, &logger).unwrap();
+        assert_eq!(
+            failures(&report),
+            vec![
+                (4, FailureKind::InvalidCumulativeTxoCount),
+                (5, FailureKind::InvalidParentId),
+                (5, FailureKind::InvalidCumulativeTxoCount),
+            ]
+        );
+    }
+
+    #[test_with_logger]
+    // A key image spent twice should be reported where it is spent again.
+    fn test_verify_reports_double_spend(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([3u8; 32]);
+        let signer = Ed25519Pair::from_random(&mut rng);
+        let (_ledger, mut blocks) = create_chain(6, &signer, &mut rng);
+
+        // Replace the last block with one spending the previous block's key image.
+        let last = &blocks[5];
+        let contents = BlockContents::new(
+            blocks[4].contents().key_images.clone(),
+            last.contents().outputs.clone(),
+        );
+        let block = Block::new_with_parent(
+            BLOCK_VERSION,
+            blocks[4].block(),
+            &last.block().root_element,
+            &contents,
+        );
+        let signature = BlockSignature::from_block_and_keypair(&block, &signer).unwrap();
+        blocks[5] = BlockData::new(block, contents, Some(signature));
+
+        let report =
+            verify_chain(&VecBlockSource(blocks), &[signer.public_key()], 2, &logger).unwrap();
+        assert_eq!(failures(&report), vec![(5, FailureKind::DuplicateKeyImage)]);
+    }
+
+    /// The ID a block's header implies.
+    fn compute_block_id(block: &Block) -> BlockID {
+        mc_transaction_core::compute_block_id(
+            block.version,
+            &block.parent_id,
+            block.index,
+            block.cumulative_txo_count,
+            &block.root_element,
+            &block.contents_hash,
+        )
+    }
+}
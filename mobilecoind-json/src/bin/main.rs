@@ -4,16 +4,25 @@
 
 #![feature(proc_macro_hygiene, decl_macro)]
 
-use grpcio::ChannelBuilder;
+use grpcio::{CallOption, ChannelBuilder};
 use mc_api::external::{CompressedRistretto, PublicAddress, RistrettoPrivate};
 use mc_common::logger::{create_app_logger, log, o};
-use mc_mobilecoind_api::{mobilecoind_api_grpc::MobilecoindApiClient, MobilecoindUri};
+use mc_mobilecoind_api::{
+    mobilecoind_api_grpc::MobilecoindApiClient, ApiAuthorizer, AuthorizationError, MobilecoindUri,
+    Scope,
+};
 use mc_mobilecoind_json::data_types::*;
-use mc_util_grpc::ConnectionUriGrpcioChannel;
+use mc_util_grpc::{BasicCredentials, ConnectionUriGrpcioChannel};
 use protobuf::RepeatedField;
-use rocket::{delete, get, post, routes};
+use rocket::{
+    delete, get,
+    http::Status,
+    post,
+    request::{self, FromRequest, Request},
+    routes, Outcome,
+};
 use rocket_contrib::json::Json;
-use std::{convert::TryFrom, sync::Arc};
+use std::{convert::TryFrom, marker::PhantomData, sync::Arc, time::Duration};
 use structopt::StructOpt;
 
 /// Command line config, set with defaults that will work with
@@ -32,17 +41,116 @@ pub struct Config {
     /// MobileCoinD URI.
     #[structopt(long, default_value = "insecure-mobilecoind://127.0.0.1/")]
     pub mobilecoind_uri: MobilecoindUri,
+
+    /// The shared secret used to authenticate HTTP clients, as 64 hex
+    /// characters. Clients send tokens generated with this secret in a basic
+    /// Authorization header, which is also forwarded to mobilecoind. When
+    /// unset, every request is allowed.
+    #[structopt(long, env = "MC_API_TOKEN_SECRET", parse(try_from_str=hex::FromHex::from_hex))]
+    pub api_token_secret: Option<[u8; 32]>,
+
+    /// The maximum lifetime of an API token, in seconds.
+    #[structopt(long, default_value = "86400", parse(try_from_str=parse_duration_in_seconds))]
+    pub api_token_max_lifetime: Duration,
+}
+
+fn parse_duration_in_seconds(src: &str) -> Result<Duration, std::num::ParseIntError> {
+    Ok(Duration::from_secs(src.parse()?))
 }
 
 /// Connection to the mobilecoind client
 struct State {
     pub mobilecoind_api_client: MobilecoindApiClient,
+    pub authorizer: ApiAuthorizer,
+}
+
+/// A scope a route requires, see `Authorized`.
+trait RequiredScope {
+    const SCOPE: Scope;
+}
+
+/// Routes reading ledger, monitor and transaction data.
+struct ReadScope;
+impl RequiredScope for ReadScope {
+    const SCOPE: Scope = Scope::Read;
+}
+
+/// Routes building and submitting transactions.
+struct SpendScope;
+impl RequiredScope for SpendScope {
+    const SCOPE: Scope = Scope::Spend;
+}
+
+/// Routes handling account secrets, monitors and the database password.
+struct KeysScope;
+impl RequiredScope for KeysScope {
+    const SCOPE: Scope = Scope::KeyManagement;
+}
+
+/// A request guard checking that the caller was granted the scope `S`.
+///
+/// The caller's credentials are kept so they can be forwarded to mobilecoind,
+/// which may enforce its own authorization.
+struct Authorized<S> {
+    call_option: CallOption,
+    _scope: PhantomData<S>,
+}
+
+impl<S> Authorized<S> {
+    /// Call options carrying the caller's credentials.
+    fn call_option(&self) -> CallOption {
+        self.call_option.clone()
+    }
+}
+
+impl<'a, 'r, S: RequiredScope> FromRequest<'a, 'r> for Authorized<S> {
+    type Error = String;
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let state = match request.guard::<rocket::State<State>>() {
+            Outcome::Success(state) => state,
+            _ => {
+                return Outcome::Failure((
+                    Status::InternalServerError,
+                    "Missing gateway state".to_owned(),
+                ))
+            }
+        };
+
+        let credentials = match request
+            .headers()
+            .get_one("Authorization")
+            .map(|header| BasicCredentials::try_from(header.as_bytes()))
+            .transpose()
+        {
+            Ok(credentials) => credentials,
+            Err(err) => return Outcome::Failure((Status::Unauthorized, err.to_string())),
+        };
+
+        let call_option = match credentials.as_ref().map(BasicCredentials::call_option) {
+            Some(Ok(call_option)) => call_option,
+            Some(Err(err)) => return Outcome::Failure((Status::Unauthorized, err.to_string())),
+            None => CallOption::default(),
+        };
+
+        match state.authorizer.authorize(credentials, S::SCOPE) {
+            Ok(_) => Outcome::Success(Self {
+                call_option,
+                _scope: PhantomData,
+            }),
+            Err(err @ AuthorizationError::Forbidden(_, _)) => {
+                Outcome::Failure((Status::Forbidden, err.to_string()))
+            }
+            Err(err) => Outcome::Failure((Status::Unauthorized, err.to_string())),
+        }
+    }
 }
 
 /// Set the password for the mobilecoind-db
 #[post("/set-password", format = "json", data = "<password>")]
 fn set_password(
     state: rocket::State<State>,
+    auth: Authorized<KeysScope>,
     password: Json<JsonPasswordRequest>,
 ) -> Result<Json<JsonPasswordResponse>, String> {
     let mut req = mc_mobilecoind_api::SetDbPasswordRequest::new();
@@ -52,7 +160,7 @@ fn set_password(
     );
     let _resp = state
         .mobilecoind_api_client
-        .set_db_password(&req)
+        .set_db_password_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed setting password: {}", err))?;
     Ok(Json(JsonPasswordResponse { success: true }))
 }
@@ -61,6 +169,7 @@ fn set_password(
 #[post("/unlock-db", format = "json", data = "<password>")]
 fn unlock_db(
     state: rocket::State<State>,
+    auth: Authorized<KeysScope>,
     password: Json<JsonUnlockDbRequest>,
 ) -> Result<Json<JsonUnlockDbResponse>, String> {
     let mut req = mc_mobilecoind_api::UnlockDbRequest::new();
@@ -70,17 +179,20 @@ fn unlock_db(
     );
     let _resp = state
         .mobilecoind_api_client
-        .unlock_db(&req)
+        .unlock_db_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed unlocking database: {}", err))?;
     Ok(Json(JsonUnlockDbResponse { success: true }))
 }
 
 /// Requests a new root entropy from mobilecoind
 #[post("/entropy")]
-fn entropy(state: rocket::State<State>) -> Result<Json<JsonRootEntropyResponse>, String> {
+fn entropy(
+    state: rocket::State<State>,
+    auth: Authorized<KeysScope>,
+) -> Result<Json<JsonRootEntropyResponse>, String> {
     let resp = state
         .mobilecoind_api_client
-        .generate_root_entropy(&mc_mobilecoind_api::Empty::new())
+        .generate_root_entropy_opt(&mc_mobilecoind_api::Empty::new(), auth.call_option())
         .map_err(|err| format!("Failed getting entropy: {}", err))?;
     Ok(Json(JsonRootEntropyResponse::from(&resp)))
 }
@@ -88,6 +200,7 @@ fn entropy(state: rocket::State<State>) -> Result<Json<JsonRootEntropyResponse>,
 #[get("/entropy/<root_entropy>")]
 fn account_key_from_root_entropy(
     state: rocket::State<State>,
+    auth: Authorized<KeysScope>,
     root_entropy: String,
 ) -> Result<Json<JsonAccountKeyResponse>, String> {
     let entropy =
@@ -98,7 +211,7 @@ fn account_key_from_root_entropy(
 
     let resp = state
         .mobilecoind_api_client
-        .get_account_key_from_root_entropy(&req)
+        .get_account_key_from_root_entropy_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting account key for root entropy: {}", err))?;
 
     Ok(Json(JsonAccountKeyResponse::from(&resp)))
@@ -106,10 +219,13 @@ fn account_key_from_root_entropy(
 
 /// Requests a new mnemonic from mobilecoind
 #[post("/mnemonic")]
-fn mnemonic(state: rocket::State<State>) -> Result<Json<JsonMnemonicResponse>, String> {
+fn mnemonic(
+    state: rocket::State<State>,
+    auth: Authorized<KeysScope>,
+) -> Result<Json<JsonMnemonicResponse>, String> {
     let resp = state
         .mobilecoind_api_client
-        .generate_mnemonic(&mc_mobilecoind_api::Empty::new())
+        .generate_mnemonic_opt(&mc_mobilecoind_api::Empty::new(), auth.call_option())
         .map_err(|err| format!("Failed getting entropy: {}", err))?;
     Ok(Json(JsonMnemonicResponse::from(&resp)))
 }
@@ -117,6 +233,7 @@ fn mnemonic(state: rocket::State<State>) -> Result<Json<JsonMnemonicResponse>, S
 #[post("/account-key-from-mnemonic", format = "json", data = "<mnemonic>")]
 fn account_key_from_mnemonic(
     state: rocket::State<State>,
+    auth: Authorized<KeysScope>,
     mnemonic: Json<JsonMnemonicResponse>,
 ) -> Result<Json<JsonAccountKeyResponse>, String> {
     let mut req = mc_mobilecoind_api::GetAccountKeyFromMnemonicRequest::new();
@@ -124,7 +241,7 @@ fn account_key_from_mnemonic(
 
     let resp = state
         .mobilecoind_api_client
-        .get_account_key_from_mnemonic(&req)
+        .get_account_key_from_mnemonic_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting account key for mnemonic: {}", err))?;
 
     Ok(Json(JsonAccountKeyResponse::from(&resp)))
@@ -135,6 +252,7 @@ fn account_key_from_mnemonic(
 #[post("/monitors", format = "json", data = "<monitor>")]
 fn add_monitor(
     state: rocket::State<State>,
+    auth: Authorized<KeysScope>,
     monitor: Json<JsonMonitorRequest>,
 ) -> Result<Json<JsonMonitorResponse>, String> {
     let mut account_key = mc_mobilecoind_api::external::AccountKey::new();
@@ -159,7 +277,7 @@ fn add_monitor(
 
     let monitor_response = state
         .mobilecoind_api_client
-        .add_monitor(&req)
+        .add_monitor_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed adding monitor: {}", err))?;
 
     Ok(Json(JsonMonitorResponse::from(&monitor_response)))
@@ -167,7 +285,11 @@ fn add_monitor(
 
 /// Remove a monitor
 #[delete("/monitors/<monitor_hex>")]
-fn remove_monitor(state: rocket::State<State>, monitor_hex: String) -> Result<(), String> {
+fn remove_monitor(
+    state: rocket::State<State>,
+    auth: Authorized<KeysScope>,
+    monitor_hex: String,
+) -> Result<(), String> {
     let monitor_id =
         hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
 
@@ -176,7 +298,7 @@ fn remove_monitor(state: rocket::State<State>, monitor_hex: String) -> Result<()
 
     let _resp = state
         .mobilecoind_api_client
-        .remove_monitor(&req)
+        .remove_monitor_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed removing monitor: {}", err))?;
 
     Ok(())
@@ -184,10 +306,13 @@ fn remove_monitor(state: rocket::State<State>, monitor_hex: String) -> Result<()
 
 /// Gets a list of existing monitors
 #[get("/monitors")]
-fn monitors(state: rocket::State<State>) -> Result<Json<JsonMonitorListResponse>, String> {
+fn monitors(
+    state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
+) -> Result<Json<JsonMonitorListResponse>, String> {
     let resp = state
         .mobilecoind_api_client
-        .get_monitor_list(&mc_mobilecoind_api::Empty::new())
+        .get_monitor_list_opt(&mc_mobilecoind_api::Empty::new(), auth.call_option())
         .map_err(|err| format!("Failed getting monitor list: {}", err))?;
     Ok(Json(JsonMonitorListResponse::from(&resp)))
 }
@@ -196,6 +321,7 @@ fn monitors(state: rocket::State<State>) -> Result<Json<JsonMonitorListResponse>
 #[get("/monitors/<monitor_hex>")]
 fn monitor_status(
     state: rocket::State<State>,
+    auth: Authorized<KeysScope>,
     monitor_hex: String,
 ) -> Result<Json<JsonMonitorStatusResponse>, String> {
     let monitor_id =
@@ -206,7 +332,7 @@ fn monitor_status(
 
     let resp = state
         .mobilecoind_api_client
-        .get_monitor_status(&req)
+        .get_monitor_status_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting monitor status: {}", err))?;
 
     Ok(Json(JsonMonitorStatusResponse::from(&resp)))
@@ -216,6 +342,7 @@ fn monitor_status(
 #[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/balance")]
 fn balance(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     monitor_hex: String,
     subaddress_index: u64,
 ) -> Result<Json<JsonBalanceResponse>, String> {
@@ -228,7 +355,7 @@ fn balance(
 
     let resp = state
         .mobilecoind_api_client
-        .get_balance(&req)
+        .get_balance_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting balance: {}", err))?;
 
     Ok(Json(JsonBalanceResponse::from(&resp)))
@@ -237,6 +364,7 @@ fn balance(
 #[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/utxos")]
 fn utxos(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     monitor_hex: String,
     subaddress_index: u64,
 ) -> Result<Json<JsonUtxosResponse>, String> {
@@ -249,7 +377,7 @@ fn utxos(
 
     let resp = state
         .mobilecoind_api_client
-        .get_unspent_tx_out_list(&req)
+        .get_unspent_tx_out_list_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting utxos: {}", err))?;
 
     Ok(Json(JsonUtxosResponse::from(&resp)))
@@ -259,6 +387,7 @@ fn utxos(
 #[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/public-address")]
 fn public_address(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     monitor_hex: String,
     subaddress_index: u64,
 ) -> Result<Json<JsonPublicAddressResponse>, String> {
@@ -272,7 +401,7 @@ fn public_address(
 
     let resp = state
         .mobilecoind_api_client
-        .get_public_address(&req)
+        .get_public_address_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting public address: {}", err))?;
 
     Ok(Json(JsonPublicAddressResponse::from(&resp)))
@@ -282,6 +411,7 @@ fn public_address(
 #[post("/codes/request", format = "json", data = "<request>")]
 fn create_request_code(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     request: Json<JsonCreateRequestCodeRequest>,
 ) -> Result<Json<JsonCreateRequestCodeResponse>, String> {
     let receiver = mc_mobilecoind_api::external::PublicAddress::try_from(&request.receiver)
@@ -303,7 +433,7 @@ fn create_request_code(
 
     let resp = state
         .mobilecoind_api_client
-        .create_request_code(&req)
+        .create_request_code_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed creating request code: {}", err))?;
 
     Ok(Json(JsonCreateRequestCodeResponse::from(&resp)))
@@ -313,13 +443,14 @@ fn create_request_code(
 #[get("/codes/request/<b58_code>")]
 fn parse_request_code(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     b58_code: String,
 ) -> Result<Json<JsonParseRequestCodeResponse>, String> {
     let mut req = mc_mobilecoind_api::ParseRequestCodeRequest::new();
     req.set_b58_code(b58_code);
     let resp = state
         .mobilecoind_api_client
-        .parse_request_code(&req)
+        .parse_request_code_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed parsing request code: {}", err))?;
 
     // The response contains the public keys encoded in the read request, as well as
@@ -332,6 +463,7 @@ fn parse_request_code(
 #[post("/codes/address", format = "json", data = "<request>")]
 fn create_address_code(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     request: Json<JsonCreateAddressCodeRequest>,
 ) -> Result<Json<JsonCreateAddressCodeResponse>, String> {
     let receiver = mc_mobilecoind_api::external::PublicAddress::try_from(&request.receiver)
@@ -343,7 +475,7 @@ fn create_address_code(
 
     let resp = state
         .mobilecoind_api_client
-        .create_address_code(&req)
+        .create_address_code_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed creating address code: {}", err))?;
 
     Ok(Json(JsonCreateAddressCodeResponse::from(&resp)))
@@ -353,13 +485,14 @@ fn create_address_code(
 #[get("/codes/address/<b58_code>")]
 fn parse_address_code(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     b58_code: String,
 ) -> Result<Json<JsonParseAddressCodeResponse>, String> {
     let mut req = mc_mobilecoind_api::ParseAddressCodeRequest::new();
     req.set_b58_code(b58_code);
     let resp = state
         .mobilecoind_api_client
-        .parse_address_code(&req)
+        .parse_address_code_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed parding address code: {}", err))?;
 
     // The response contains the public keys encoded in the read request
@@ -375,6 +508,7 @@ fn parse_address_code(
 )]
 fn build_and_submit(
     state: rocket::State<State>,
+    auth: Authorized<SpendScope>,
     monitor_hex: String,
     subaddress_index: u64,
     transfer: Json<JsonSendPaymentRequest>,
@@ -420,7 +554,7 @@ fn build_and_submit(
 
     let resp = state
         .mobilecoind_api_client
-        .send_payment(&req)
+        .send_payment_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed to send payment: {}", err))?;
 
     // The receipt from the payment request can be used by the status check below
@@ -436,6 +570,7 @@ fn build_and_submit(
 )]
 fn pay_address_code(
     state: rocket::State<State>,
+    auth: Authorized<SpendScope>,
     monitor_hex: String,
     subaddress_index: u64,
     transfer: Json<JsonPayAddressCodeRequest>,
@@ -475,7 +610,7 @@ fn pay_address_code(
 
     let resp = state
         .mobilecoind_api_client
-        .pay_address_code(&req)
+        .pay_address_code_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed to send payment: {}", err))?;
 
     // The receipt from the payment request can be used by the status check below
@@ -492,6 +627,7 @@ fn pay_address_code(
 )]
 fn generate_request_code_transaction(
     state: rocket::State<State>,
+    auth: Authorized<SpendScope>,
     monitor_hex: String,
     subaddress_index: u64,
     request: Json<JsonCreateTxProposalRequest>,
@@ -530,7 +666,7 @@ fn generate_request_code_transaction(
 
     let resp = state
         .mobilecoind_api_client
-        .generate_tx(&req)
+        .generate_tx_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed to generate tx: {}", err))?;
 
     Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
@@ -540,6 +676,7 @@ fn generate_request_code_transaction(
 #[post("/submit-tx", format = "json", data = "<proposal>")]
 fn submit_tx(
     state: rocket::State<State>,
+    auth: Authorized<SpendScope>,
     proposal: Json<JsonTxProposalRequest>,
 ) -> Result<Json<JsonSubmitTxResponse>, String> {
     // Send the payment request
@@ -551,7 +688,7 @@ fn submit_tx(
 
     let resp = state
         .mobilecoind_api_client
-        .submit_tx(&req)
+        .submit_tx_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed to send payment: {}", err))?;
 
     // The receipt from the payment request can be used by the status check below
@@ -562,13 +699,15 @@ fn submit_tx(
 #[post("/tx/status-as-sender", format = "json", data = "<submit_response>")]
 fn check_transfer_status(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     submit_response: Json<JsonSubmitTxResponse>,
 ) -> Result<Json<JsonStatusResponse>, String> {
     let resp = state
         .mobilecoind_api_client
-        .get_tx_status_as_sender(
+        .get_tx_status_as_sender_opt(
             &mc_mobilecoind_api::SubmitTxResponse::try_from(&submit_response.0)
                 .map_err(|err| format!("Could not convert JsonSubmitTxResponse: {}", err))?,
+            auth.call_option(),
         )
         .map_err(|err| format!("Failed getting status: {}", err))?;
 
@@ -589,6 +728,7 @@ fn check_transfer_status(
 )]
 fn check_receiver_transfer_status(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     monitor_hex: String,
     receipt: Json<JsonReceiverTxReceipt>,
 ) -> Result<Json<JsonStatusResponse>, String> {
@@ -612,7 +752,7 @@ fn check_receiver_transfer_status(
 
     let resp = state
         .mobilecoind_api_client
-        .get_tx_status_as_receiver(&req)
+        .get_tx_status_as_receiver_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting status: {}", err))?;
 
     Ok(Json(JsonStatusResponse::from(&resp)))
@@ -620,10 +760,13 @@ fn check_receiver_transfer_status(
 
 /// Gets information about the entire ledger
 #[get("/ledger/local")]
-fn ledger_info(state: rocket::State<State>) -> Result<Json<JsonLedgerInfoResponse>, String> {
+fn ledger_info(
+    state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
+) -> Result<Json<JsonLedgerInfoResponse>, String> {
     let resp = state
         .mobilecoind_api_client
-        .get_ledger_info(&mc_mobilecoind_api::Empty::new())
+        .get_ledger_info_opt(&mc_mobilecoind_api::Empty::new(), auth.call_option())
         .map_err(|err| format!("Failed getting ledger info: {}", err))?;
 
     Ok(Json(JsonLedgerInfoResponse::from(&resp)))
@@ -633,6 +776,7 @@ fn ledger_info(state: rocket::State<State>) -> Result<Json<JsonLedgerInfoRespons
 #[get("/ledger/blocks/<block_num>/header")]
 fn block_info(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     block_num: u64,
 ) -> Result<Json<JsonBlockInfoResponse>, String> {
     let mut req = mc_mobilecoind_api::GetBlockInfoRequest::new();
@@ -640,7 +784,7 @@ fn block_info(
 
     let resp = state
         .mobilecoind_api_client
-        .get_block_info(&req)
+        .get_block_info_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting ledger info: {}", err))?;
 
     Ok(Json(JsonBlockInfoResponse::from(&resp)))
@@ -650,6 +794,7 @@ fn block_info(
 #[get("/ledger/blocks/<block_num>")]
 fn block_details(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     block_num: u64,
 ) -> Result<Json<JsonBlockDetailsResponse>, String> {
     let mut req = mc_mobilecoind_api::GetBlockRequest::new();
@@ -657,7 +802,7 @@ fn block_details(
 
     let resp = state
         .mobilecoind_api_client
-        .get_block(&req)
+        .get_block_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting block details: {}", err))?;
 
     Ok(Json(JsonBlockDetailsResponse::from(&resp)))
@@ -666,6 +811,7 @@ fn block_details(
 #[get("/monitors/<monitor_hex>/processed-block/<block_num>")]
 fn processed_block(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     monitor_hex: String,
     block_num: u64,
 ) -> Result<Json<JsonProcessedBlockResponse>, String> {
@@ -678,7 +824,7 @@ fn processed_block(
 
     let resp = state
         .mobilecoind_api_client
-        .get_processed_block(&req)
+        .get_processed_block_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting processed block: {}", err))?;
 
     Ok(Json(JsonProcessedBlockResponse::from(&resp)))
@@ -688,6 +834,7 @@ fn processed_block(
 #[get("/tx-out/<public_key_hex>/block-index")]
 fn tx_out_get_block_index_by_public_key(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     public_key_hex: String,
 ) -> Result<Json<JsonBlockIndexByTxPubKeyResponse>, String> {
     let tx_out_public_key = hex::decode(public_key_hex)
@@ -701,7 +848,7 @@ fn tx_out_get_block_index_by_public_key(
 
     let resp = state
         .mobilecoind_api_client
-        .get_block_index_by_tx_pub_key(&req)
+        .get_block_index_by_tx_pub_key_opt(&req, auth.call_option())
         .map_err(|err| format!("Failed getting block index: {}", err))?;
 
     Ok(Json(JsonBlockIndexByTxPubKeyResponse::from(&resp)))
@@ -711,6 +858,7 @@ fn tx_out_get_block_index_by_public_key(
 /// Get a proof of membership for each queried TxOut.
 fn get_proof_of_membership(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     request: Json<JsonMembershipProofRequest>,
 ) -> Result<Json<JsonMembershipProofResponse>, String> {
     // Requested TxOuts.
@@ -726,7 +874,7 @@ fn get_proof_of_membership(
 
     let get_membership_proofs_response = state
         .mobilecoind_api_client
-        .get_membership_proofs(&get_membership_proofs_request)
+        .get_membership_proofs_opt(&get_membership_proofs_request, auth.call_option())
         .map_err(|err| format!("Failed getting membership proofs: {}", err))?;
 
     // Return JSON response
@@ -756,6 +904,7 @@ fn get_proof_of_membership(
 /// Get a list of TxOuts for use as mixins.
 fn get_mixins(
     state: rocket::State<State>,
+    auth: Authorized<ReadScope>,
     request: Json<JsonMixinRequest>,
 ) -> Result<Json<JsonMixinResponse>, String> {
     let num_mixins = request.num_mixins;
@@ -772,7 +921,7 @@ fn get_mixins(
 
     let get_mixins_response = state
         .mobilecoind_api_client
-        .get_mixins(&get_mixins_request)
+        .get_mixins_opt(&get_mixins_request, auth.call_option())
         .map_err(|err| format!("Failed getting mixins: {}", err))?;
 
     let mixins_and_proofs: Vec<(JsonTxOut, JsonTxOutMembershipProof)> = get_mixins_response
@@ -820,6 +969,11 @@ fn main() {
 
     let mobilecoind_api_client = MobilecoindApiClient::new(ch);
 
+    let authorizer = match config.api_token_secret {
+        Some(secret) => ApiAuthorizer::with_token_secret(secret, config.api_token_max_lifetime),
+        None => ApiAuthorizer::default(),
+    };
+
     let rocket_config = rocket::Config::build(rocket::config::Environment::Production)
         .address(&config.listen_host)
         .port(config.listen_port)
@@ -863,6 +1017,98 @@ fn main() {
         )
         .manage(State {
             mobilecoind_api_client,
+            authorizer,
         })
         .launch();
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_common::time::SystemTimeProvider;
+    use mc_util_grpc::TokenBasicCredentialsGenerator;
+    use rocket::{http::Header, local::Client};
+
+    const SHARED_SECRET: [u8; 32] = [7; 32];
+
+    /// A route which only checks the caller's authorization.
+    #[get("/keys")]
+    fn keys(_auth: Authorized<KeysScope>) -> &'static str {
+        "ok"
+    }
+
+    /// A gateway requiring tokens generated with `SHARED_SECRET`, connected
+    /// to a mobilecoind which is not running.
+    fn client() -> Client {
+        let env = Arc::new(grpcio::EnvBuilder::new().cq_count(1).build());
+        let ch = ChannelBuilder::new(env).connect("127.0.0.1:1");
+        let rocket = rocket::custom(rocket::Config::development())
+            .mount("/", routes![keys, monitor_status])
+            .manage(State {
+                mobilecoind_api_client: MobilecoindApiClient::new(ch),
+                authorizer: ApiAuthorizer::with_token_secret(
+                    SHARED_SECRET,
+                    Duration::from_secs(60),
+                ),
+            });
+        Client::new(rocket).unwrap()
+    }
+
+    fn authorization(username: &str) -> Header<'static> {
+        let generator =
+            TokenBasicCredentialsGenerator::new(SHARED_SECRET, SystemTimeProvider::default());
+        let credentials = generator.generate_for(username).unwrap();
+        Header::new("Authorization", credentials.authorization_header())
+    }
+
+    #[test]
+    fn test_authorized_guard() {
+        let client = client();
+
+        assert_eq!(
+            client.get("/keys").dispatch().status(),
+            Status::Unauthorized
+        );
+
+        let response = client
+            .get("/keys")
+            .header(Header::new("Authorization", "Basic invalid"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        let response = client
+            .get("/keys")
+            .header(authorization("wallet/read,spend"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let mut response = client
+            .get("/keys")
+            .header(authorization("wallet/keys"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.body_string().unwrap(), "ok");
+
+        // A token for another secret is rejected.
+        let generator = TokenBasicCredentialsGenerator::new([8; 32], SystemTimeProvider::default());
+        let forged = generator.generate_for("wallet/keys").unwrap();
+        let response = client
+            .get("/keys")
+            .header(Header::new("Authorization", forged.authorization_header()))
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[test]
+    fn test_monitor_status_requires_keys_scope() {
+        let client = client();
+
+        // Monitor status is backed by a call returning the account key, so a
+        // read-only token is refused before mobilecoind is called.
+        let response = client
+            .get("/monitors/00")
+            .header(authorization("explorer/read"))
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+}
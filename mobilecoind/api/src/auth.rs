@@ -0,0 +1,330 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Authorization of mobilecoind API calls.
+//!
+//! When a shared secret is configured, callers authenticate with tokens
+//! generated by `TokenBasicCredentialsGenerator`. The token's username names
+//! the caller and the scopes they are granted, as `name/scope,scope`, for
+//! example `wallet/read,spend`. A username without scopes only grants `read`.
+//! Since the username is signed, callers cannot grant themselves more scopes.
+
+use displaydoc::Display;
+use grpcio::{RpcContext, RpcStatus, RpcStatusCode};
+use mc_common::time::SystemTimeProvider;
+use mc_util_grpc::{
+    AnonymousAuthenticator, Authenticator, AuthenticatorError, BasicCredentials, TokenAuthenticator,
+};
+use std::{
+    collections::BTreeSet,
+    fmt::{Display as FmtDisplay, Formatter, Result as FmtResult},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+/// The kinds of access a caller may be granted.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Scope {
+    /// Read ledger, monitor and transaction status data, and create and parse
+    /// request and address codes.
+    Read,
+    /// Build and submit transactions.
+    Spend,
+    /// Create, reveal, split and combine account secrets, add and remove
+    /// monitors, read monitor status (which includes the monitor's account
+    /// key), and manage the database password.
+    KeyManagement,
+}
+
+impl Scope {
+    /// The scope required to call the API method with the given name, as it
+    /// appears in `MobilecoindApi`. Unknown methods require key management,
+    /// the most privileged scope.
+    pub fn for_method(method: &str) -> Self {
+        match method {
+            "get_monitor_list"
+            | "get_unspent_tx_out_list"
+            | "get_public_address"
+            | "parse_request_code"
+            | "create_request_code"
+            | "parse_address_code"
+            | "create_address_code"
+            | "get_mixins"
+            | "get_membership_proofs"
+            | "get_ledger_info"
+            | "get_block_info"
+            | "get_block"
+            | "get_tx_status_as_sender"
+            | "get_tx_status_as_receiver"
+            | "get_processed_block"
+            | "get_block_index_by_tx_pub_key"
+            | "get_balance"
+            | "get_network_status" => Self::Read,
+
+            "generate_tx"
+            | "generate_optimization_tx"
+            | "generate_transfer_code_tx"
+            | "generate_tx_from_tx_out_list"
+            | "submit_tx"
+            | "send_payment"
+            | "pay_address_code" => Self::Spend,
+
+            _ => Self::KeyManagement,
+        }
+    }
+}
+
+impl FmtDisplay for Scope {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match self {
+            Self::Read => "read",
+            Self::Spend => "spend",
+            Self::KeyManagement => "keys",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "read" => Ok(Self::Read),
+            "spend" => Ok(Self::Spend),
+            "keys" => Ok(Self::KeyManagement),
+            _ => Err(format!(
+                "Unknown scope {}, expected read, spend or keys",
+                src
+            )),
+        }
+    }
+}
+
+/// An authenticated caller, and the scopes they were granted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grant {
+    /// The caller's name.
+    pub name: String,
+    /// The scopes the caller was granted.
+    pub scopes: BTreeSet<Scope>,
+}
+
+impl Grant {
+    /// A grant of every scope.
+    pub fn all(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            scopes: [Scope::Read, Scope::Spend, Scope::KeyManagement]
+                .iter()
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Whether the grant includes `scope`.
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+impl FromStr for Grant {
+    type Err = String;
+
+    /// Parse a grant from a username of the form `name/scope,scope`.
+    fn from_str(username: &str) -> Result<Self, Self::Err> {
+        let mut parts = username.splitn(2, '/');
+        let name = parts.next().unwrap_or_default().to_owned();
+        let scopes = match parts.next() {
+            Some(scopes) => scopes
+                .split(',')
+                .map(Scope::from_str)
+                .collect::<Result<_, _>>()?,
+            None => [Scope::Read].iter().copied().collect(),
+        };
+        Ok(Self { name, scopes })
+    }
+}
+
+/// An error authorizing a call.
+#[derive(Debug, Display)]
+pub enum AuthorizationError {
+    /// {0}
+    Unauthenticated(AuthenticatorError),
+
+    /// Invalid scopes in username: {0}
+    InvalidScopes(String),
+
+    /// {0} is not granted the {1} scope
+    Forbidden(String, Scope),
+}
+
+impl From<AuthenticatorError> for AuthorizationError {
+    fn from(src: AuthenticatorError) -> Self {
+        Self::Unauthenticated(src)
+    }
+}
+
+impl From<AuthorizationError> for RpcStatus {
+    fn from(src: AuthorizationError) -> Self {
+        let code = match src {
+            AuthorizationError::Forbidden(_, _) => RpcStatusCode::PERMISSION_DENIED,
+            _ => RpcStatusCode::UNAUTHENTICATED,
+        };
+        RpcStatus::with_message(code, src.to_string())
+    }
+}
+
+/// Decides whether a caller may make a call requiring a given scope.
+#[derive(Clone)]
+pub struct ApiAuthorizer {
+    /// Authenticates the caller's credentials.
+    authenticator: Arc<dyn Authenticator + Send + Sync>,
+
+    /// Whether scopes are taken from the authenticated username. When they
+    /// are not, every caller is granted every scope.
+    scoped: bool,
+}
+
+impl Default for ApiAuthorizer {
+    /// Allow every call, as mobilecoind does without a shared secret.
+    fn default() -> Self {
+        Self {
+            authenticator: Arc::new(AnonymousAuthenticator::default()),
+            scoped: false,
+        }
+    }
+}
+
+impl ApiAuthorizer {
+    /// Authenticate callers with tokens generated using `shared_secret`, and
+    /// grant them the scopes named in their usernames.
+    pub fn with_token_secret(shared_secret: [u8; 32], max_token_lifetime: Duration) -> Self {
+        Self {
+            authenticator: Arc::new(TokenAuthenticator::new(
+                shared_secret,
+                max_token_lifetime,
+                SystemTimeProvider::default(),
+            )),
+            scoped: true,
+        }
+    }
+
+    /// Authorize a caller presenting `credentials` to make a call requiring
+    /// `scope`, returning their grant.
+    pub fn authorize(
+        &self,
+        credentials: Option<BasicCredentials>,
+        scope: Scope,
+    ) -> Result<Grant, AuthorizationError> {
+        let username = self.authenticator.authenticate(credentials)?;
+        self.authorize_username(username, scope)
+    }
+
+    /// Authorize a gRPC call requiring `scope`, using the credentials in its
+    /// request headers.
+    pub fn authorize_rpc(
+        &self,
+        context: &RpcContext,
+        scope: Scope,
+    ) -> Result<Grant, AuthorizationError> {
+        let username = self.authenticator.authenticate_rpc(context)?;
+        self.authorize_username(username, scope)
+    }
+
+    fn authorize_username(
+        &self,
+        username: String,
+        scope: Scope,
+    ) -> Result<Grant, AuthorizationError> {
+        let grant = if self.scoped {
+            Grant::from_str(&username).map_err(AuthorizationError::InvalidScopes)?
+        } else {
+            Grant::all(&username)
+        };
+        if !grant.allows(scope) {
+            return Err(AuthorizationError::Forbidden(grant.name, scope));
+        }
+        Ok(grant)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_util_grpc::TokenBasicCredentialsGenerator;
+
+    const SHARED_SECRET: [u8; 32] = [7; 32];
+
+    fn token(username: &str) -> Option<BasicCredentials> {
+        let generator =
+            TokenBasicCredentialsGenerator::new(SHARED_SECRET, SystemTimeProvider::default());
+        Some(generator.generate_for(username).unwrap())
+    }
+
+    #[test]
+    fn parse_grants() {
+        let grant = Grant::from_str("wallet/read,spend").unwrap();
+        assert_eq!(grant.name, "wallet");
+        assert!(grant.allows(Scope::Read));
+        assert!(grant.allows(Scope::Spend));
+        assert!(!grant.allows(Scope::KeyManagement));
+
+        let grant = Grant::from_str("explorer").unwrap();
+        assert_eq!(
+            grant.scopes,
+            [Scope::Read].iter().copied().collect::<BTreeSet<_>>()
+        );
+
+        assert!(Grant::from_str("wallet/everything").is_err());
+    }
+
+    #[test]
+    fn method_scopes() {
+        assert_eq!(Scope::for_method("get_balance"), Scope::Read);
+        assert_eq!(Scope::for_method("send_payment"), Scope::Spend);
+        assert_eq!(
+            Scope::for_method("get_account_key_from_mnemonic"),
+            Scope::KeyManagement
+        );
+        // Monitor status includes the account key.
+        assert_eq!(
+            Scope::for_method("get_monitor_status"),
+            Scope::KeyManagement
+        );
+        assert_eq!(Scope::for_method("unknown"), Scope::KeyManagement);
+    }
+
+    #[test]
+    fn anonymous_allows_everything() {
+        let authorizer = ApiAuthorizer::default();
+        assert!(authorizer.authorize(None, Scope::KeyManagement).is_ok());
+    }
+
+    #[test]
+    fn tokens_are_scoped() {
+        let authorizer = ApiAuthorizer::with_token_secret(SHARED_SECRET, Duration::from_secs(60));
+
+        match authorizer.authorize(None, Scope::Read) {
+            Err(AuthorizationError::Unauthenticated(_)) => {}
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let grant = authorizer
+            .authorize(token("wallet/read,spend"), Scope::Spend)
+            .unwrap();
+        assert_eq!(grant.name, "wallet");
+
+        match authorizer.authorize(token("wallet/read,spend"), Scope::KeyManagement) {
+            Err(AuthorizationError::Forbidden(name, Scope::KeyManagement)) => {
+                assert_eq!(name, "wallet")
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // A token for another secret is rejected.
+        let generator = TokenBasicCredentialsGenerator::new([8; 32], SystemTimeProvider::default());
+        let forged = generator.generate_for("wallet/keys").unwrap();
+        assert!(authorizer.authorize(Some(forged), Scope::Read).is_err());
+    }
+}
@@ -2,6 +2,10 @@
 
 //! mobilecoind gRPC API.
 
+mod auth;
+
+pub use auth::{ApiAuthorizer, AuthorizationError, Grant, Scope};
+
 use mc_util_uri::{Uri, UriScheme};
 
 mod autogenerated_code {
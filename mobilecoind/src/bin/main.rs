@@ -2,17 +2,18 @@
 
 //! mobilecoind daemon entry point
 
-use mc_attest_core::{MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
 use mc_common::logger::{create_app_logger, log, o, Logger};
 use mc_ledger_db::{Ledger, LedgerDB};
 use mc_ledger_sync::{LedgerSyncServiceThread, PollingNetworkState, ReqwestTransactionsFetcher};
 use mc_mobilecoind::{
     config::Config, database::Database, payments::TransactionsManager, service::Service,
 };
+use mc_util_grpc::ShutdownSignal;
 use mc_watcher::{watcher::WatcherSyncThread, watcher_db::create_or_open_rw_watcher_db};
 use std::{
     path::Path,
     sync::{Arc, RwLock},
+    time::Instant,
 };
 use structopt::StructOpt;
 
@@ -24,15 +25,13 @@ fn main() {
 
     mc_common::setup_panic_handler();
     let _sentry_guard = mc_common::sentry::init();
+    let _telemetry_guard = mc_util_telemetry::init("mobilecoind");
     let (logger, _global_logger_guard) = create_app_logger(o!());
 
-    let mut mr_signer_verifier =
-        MrSignerVerifier::from(mc_consensus_enclave_measurement::sigstruct());
-    mr_signer_verifier.allow_hardening_advisory("INTEL-SA-00334");
-
-    let mut verifier = Verifier::default();
-    verifier.mr_signer(mr_signer_verifier).debug(DEBUG_ENCLAVE);
+    let shutdown_signal =
+        ShutdownSignal::register().expect("Could not register shutdown signal handlers");
 
+    let verifier = config.get_consensus_verifier();
     log::debug!(logger, "Verifier: {:?}", verifier);
 
     // Create peer manager.
@@ -55,7 +54,7 @@ fn main() {
     let ledger_db = create_or_open_ledger_db(&config, &logger, &transactions_fetcher);
 
     // Start ledger sync thread unless running in offline mode.
-    let _ledger_sync_service_thread = if config.offline {
+    let mut ledger_sync_service_thread = if config.offline {
         None
     } else {
         Some(LedgerSyncServiceThread::new(
@@ -120,27 +119,27 @@ fn main() {
                 logger.clone(),
             );
 
-            let _api_server = Service::new(
+            let api_server = Service::new(
                 ledger_db,
                 mobilecoind_db,
                 watcher_db,
                 transactions_manager,
                 network_state,
                 listen_uri,
+                config.api_authorizer(),
                 config.num_workers,
-                logger,
+                logger.clone(),
             );
 
-            loop {
-                std::thread::sleep(config.poll_interval);
-            }
+            shutdown_signal.wait();
+            log::info!(logger, "Shutdown requested");
+            api_server.shutdown(config.shutdown_grace_period);
         }
 
         (None, None) => {
             // No mobilecoind service, only ledger syncing.
-            loop {
-                std::thread::sleep(config.poll_interval);
-            }
+            shutdown_signal.wait();
+            log::info!(logger, "Shutdown requested");
         }
 
         _ => {
@@ -149,6 +148,13 @@ fn main() {
             );
         }
     }
+
+    // Let the ledger sync finish the blocks it is appending. The watcher sync
+    // thread stops when dropped, after its current batch.
+    if let Some(ledger_sync_service_thread) = ledger_sync_service_thread.as_mut() {
+        ledger_sync_service_thread.stop_until(Instant::now() + config.shutdown_timeout);
+    }
+    log::info!(logger, "Shutdown complete");
 }
 
 fn create_or_open_ledger_db(
@@ -3,13 +3,16 @@
 //! Configuration parameters for mobilecoind
 
 use displaydoc::Display;
-use mc_attest_core::{MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
+use mc_attest_core::{AttestationPolicy, MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
 use mc_common::{logger::Logger, ResponderId};
-use mc_connection::{ConnectionManager, HardcodedCredentialsProvider, ThickClient};
+use mc_connection::{
+    CircuitBreakerConfig, ConnectionManager, HardcodedCredentialsProvider, SelectionPolicyKind,
+    ThickClient,
+};
 use mc_consensus_scp::QuorumSet;
 use mc_fog_report_connection::GrpcFogReportConnection;
 use mc_fog_report_validation::FogResolver;
-use mc_mobilecoind_api::MobilecoindUri;
+use mc_mobilecoind_api::{ApiAuthorizer, MobilecoindUri};
 use mc_sgx_css::Signature;
 use mc_util_uri::{ConnectionUri, ConsensusClientUri, FogUri};
 #[cfg(feature = "ip-check")]
@@ -67,6 +70,19 @@ pub struct Config {
     #[structopt(long)]
     pub listen_uri: Option<MobilecoindUri>,
 
+    /// Require callers of the API to authenticate with tokens generated using
+    /// this hex-encoded 32 bytes shared secret. A token's username names the
+    /// scopes it grants, as `name/scope,scope` where scopes are `read`,
+    /// `spend` and `keys`. Serve the API over TLS when using this, and add a
+    /// `tls-client-ca` to `--listen-uri` to also require client certificates.
+    #[structopt(long, env = "MC_API_TOKEN_SECRET", parse(try_from_str=hex::FromHex::from_hex))]
+    pub api_token_secret: Option<[u8; 32]>,
+
+    /// Maximal API token lifetime, in seconds (only relevant when
+    /// --api-token-secret is used). Defaults to 86400 - 24 hours.
+    #[structopt(long, default_value = "86400", parse(try_from_str=parse_duration_in_seconds))]
+    pub api_token_max_lifetime: Duration,
+
     /// Number of worker threads to use for view key scanning.
     /// Defaults to number of logical CPU cores.
     #[structopt(long)]
@@ -80,6 +96,22 @@ pub struct Config {
     /// transactions to fog recipients).
     #[structopt(long, parse(try_from_str=load_css_file))]
     pub fog_ingest_enclave_css: Option<Signature>,
+
+    /// Attestation policy (.toml or .json) for consensus node enclaves.
+    /// Defaults to the consensus enclave this build was made for.
+    #[structopt(long, parse(try_from_str=AttestationPolicy::load))]
+    pub attestation_policy: Option<AttestationPolicy>,
+
+    /// How long to wait for in-flight ledger syncing to finish when shutting
+    /// down on SIGTERM or SIGINT, in seconds.
+    #[structopt(long, default_value = "30", parse(try_from_str=parse_duration_in_seconds))]
+    pub shutdown_timeout: Duration,
+
+    /// How long the API keeps serving after its health check reports
+    /// NOT_SERVING on shutdown, so that load balancers stop sending it
+    /// requests first, in seconds.
+    #[structopt(long, default_value = "5", parse(try_from_str=parse_duration_in_seconds))]
+    pub shutdown_grace_period: Duration,
 }
 
 fn parse_duration_in_seconds(src: &str) -> Result<Duration, std::num::ParseIntError> {
@@ -159,6 +191,34 @@ impl Config {
         QuorumSet::new_with_node_ids(node_ids.len() as u32, node_ids)
     }
 
+    /// Get the authorizer for API calls. Without a shared secret, every call
+    /// is allowed.
+    pub fn api_authorizer(&self) -> ApiAuthorizer {
+        match self.api_token_secret {
+            Some(shared_secret) => {
+                ApiAuthorizer::with_token_secret(shared_secret, self.api_token_max_lifetime)
+            }
+            None => ApiAuthorizer::default(),
+        }
+    }
+
+    /// Get the attestation verifier used to verify consensus nodes
+    pub fn get_consensus_verifier(&self) -> Verifier {
+        if let Some(policy) = &self.attestation_policy {
+            return policy
+                .verifier()
+                .expect("Attestation policy was validated when loaded");
+        }
+
+        let mut mr_signer_verifier =
+            MrSignerVerifier::from(mc_consensus_enclave_measurement::sigstruct());
+        mr_signer_verifier.allow_hardening_advisory("INTEL-SA-00334");
+
+        let mut verifier = Verifier::default();
+        verifier.mr_signer(mr_signer_verifier).debug(DEBUG_ENCLAVE);
+        verifier
+    }
+
     /// Get the attestation verifier used to verify fog reports when sending to
     /// fog recipients
     pub fn get_fog_ingest_verifier(&self) -> Option<Verifier> {
@@ -266,6 +326,19 @@ pub struct PeersConfig {
     /// validator nodes to connect to.
     #[structopt(long = "peer", required_unless = "offline")]
     pub peers: Option<Vec<ConsensusClientUri>>,
+
+    /// How to choose which validator node to call next: round-robin,
+    /// least-latency or sticky (keep using a node until it fails).
+    #[structopt(long, default_value = "round-robin")]
+    pub peer_selection: SelectionPolicyKind,
+
+    /// Consecutive failures after which a validator node is skipped.
+    #[structopt(long, default_value = "5")]
+    pub peer_failure_threshold: u32,
+
+    /// Seconds to skip a failing validator node for, before trying it again.
+    #[structopt(long, default_value = "30", parse(try_from_str=parse_duration_in_seconds))]
+    pub peer_circuit_reset: Duration,
 }
 
 impl PeersConfig {
@@ -317,6 +390,14 @@ impl PeersConfig {
         );
         let peers = self.create_peers(verifier, grpc_env, logger.clone());
 
-        ConnectionManager::new(peers, logger.clone())
+        ConnectionManager::with_policy(
+            peers,
+            self.peer_selection.build(),
+            CircuitBreakerConfig {
+                failure_threshold: self.peer_failure_threshold,
+                reset_timeout: self.peer_circuit_reset,
+            },
+            logger.clone(),
+        )
     }
 }
@@ -27,16 +27,7 @@ use mc_transaction_std::{InputCredentials, TransactionBuilder};
 use mc_util_uri::FogUri;
 use rand::Rng;
 use rayon::prelude::*;
-use std::{
-    cmp::Reverse,
-    convert::TryFrom,
-    iter::empty,
-    str::FromStr,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
-};
+use std::{cmp::Reverse, convert::TryFrom, iter::empty, str::FromStr, sync::Arc};
 
 /// Default number of blocks used for calculating transaction tombstone block
 /// number.
@@ -103,10 +94,6 @@ pub struct TransactionsManager<
     /// Logger.
     logger: Logger,
 
-    /// Monotonically increasing counter. This is used for node round-robin
-    /// selection.
-    submit_node_offset: Arc<AtomicUsize>,
-
     /// Fog resolver maker, used when constructing outputs to fog recipients.
     /// This is abstracted because in tests, we don't want to form grpc
     /// connections to fog
@@ -122,7 +109,6 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             mobilecoind_db: self.mobilecoind_db.clone(),
             peer_manager: self.peer_manager.clone(),
             logger: self.logger.clone(),
-            submit_node_offset: self.submit_node_offset.clone(),
             fog_resolver_factory: self.fog_resolver_factory.clone(),
         }
     }
@@ -167,13 +153,11 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         fog_resolver_factory: Arc<dyn Fn(&[FogUri]) -> Result<FPR, String> + Send + Sync>,
         logger: Logger,
     ) -> Self {
-        let mut rng = rand::thread_rng();
         Self {
             ledger_db,
             mobilecoind_db,
             peer_manager,
             logger,
-            submit_node_offset: Arc::new(AtomicUsize::new(rng.next_u64() as usize)),
             fog_resolver_factory,
         }
     }
@@ -484,21 +468,15 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
     /// Submit a previously built tx proposal to the network.
     pub fn submit_tx_proposal(&self, tx_proposal: &TxProposal) -> Result<u64, Error> {
-        // Pick a peer to submit to.
-        let responder_ids = self.peer_manager.responder_ids();
-        if responder_ids.is_empty() {
+        if self.peer_manager.is_empty() {
             return Err(Error::NoPeersConfigured);
         }
 
-        let idx = self.submit_node_offset.fetch_add(1, Ordering::SeqCst);
-        let responder_id = &responder_ids[idx % responder_ids.len()];
-
-        // Try and submit.
+        // Try and submit, failing over to other peers if the selected one is
+        // unavailable.
         let block_height = self
             .peer_manager
-            .conn(responder_id)
-            .ok_or(Error::NodeNotFound)?
-            .propose_tx(&tx_proposal.tx, empty())
+            .call(|conn| conn.propose_tx(&tx_proposal.tx, empty()))
             .map_err(Error::from)?;
 
         log::info!(
@@ -15,6 +15,7 @@ use crate::{
     utxo_store::{UnspentTxOut, UtxoId},
 };
 use bip39::{Language, Mnemonic, MnemonicType};
+use futures::executor::block_on;
 use grpcio::{EnvBuilder, RpcContext, RpcStatus, RpcStatusCode, ServerBuilder, UnarySink};
 use mc_account_keys::{AccountKey, PublicAddress, RootIdentity, DEFAULT_SUBADDRESS_INDEX};
 use mc_account_keys_slip10::Slip10KeyGenerator;
@@ -29,7 +30,7 @@ use mc_ledger_db::{Error as LedgerError, Ledger, LedgerDB};
 use mc_ledger_sync::{NetworkState, PollingNetworkState};
 use mc_mobilecoind_api::{
     mobilecoind_api_grpc::{create_mobilecoind_api, MobilecoindApi},
-    MobilecoindUri,
+    ApiAuthorizer, MobilecoindUri, Scope,
 };
 use mc_transaction_core::{
     get_tx_out_shared_secret,
@@ -39,22 +40,34 @@ use mc_transaction_core::{
 };
 use mc_util_from_random::FromRandom;
 use mc_util_grpc::{
-    rpc_internal_error, rpc_invalid_arg_error, rpc_logger, send_result, AdminService,
-    BuildInfoService, ConnectionUriGrpcioServer,
+    rpc_internal_error, rpc_invalid_arg_error, rpc_logger, rpc_span, send_result, AdminService,
+    AnonymousAuthenticator, BuildInfoService, ConnectionUriGrpcioServer, HealthCheckStatus,
+    HealthService, ReflectionService, ServiceHealthCheckCallback,
 };
+use mc_util_telemetry::global;
 use mc_watcher::watcher_db::WatcherDB;
 use protobuf::{ProtobufEnum, RepeatedField};
 use std::{
     convert::TryFrom,
-    sync::{Arc, Mutex, RwLock},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::Duration,
 };
 
 pub struct Service {
     /// Sync thread.
-    _sync_thread: Arc<Mutex<Option<SyncThread>>>,
+    sync_thread: Arc<Mutex<Option<SyncThread>>>,
 
     /// GRPC server.
-    _server: grpcio::Server,
+    server: grpcio::Server,
+
+    /// Set once a graceful shutdown has begun.
+    shutting_down: Arc<AtomicBool>,
+
+    logger: Logger,
 }
 
 impl Service {
@@ -68,6 +81,7 @@ impl Service {
         transactions_manager: TransactionsManager<T, FPR>,
         network_state: Arc<RwLock<PollingNetworkState<T>>>,
         listen_uri: &MobilecoindUri,
+        authorizer: ApiAuthorizer,
         num_workers: Option<usize>,
         logger: Logger,
     ) -> Self {
@@ -102,6 +116,8 @@ impl Service {
             })
         };
 
+        let shutting_down = Arc::new(AtomicBool::new(false));
+
         let api = ServiceApi::new(
             transactions_manager,
             ledger_db,
@@ -109,6 +125,8 @@ impl Service {
             watcher_db,
             network_state,
             start_sync_thread,
+            authorizer,
+            shutting_down.clone(),
             logger.clone(),
         );
 
@@ -119,13 +137,43 @@ impl Service {
         let build_info_service = BuildInfoService::new(logger.clone()).into_service();
 
         // Health check service.
-        let health_service = mc_util_grpc::HealthService::new(None, logger.clone()).into_service();
+        // The service stops serving once a shutdown has begun.
+        let health_check_callback: ServiceHealthCheckCallback = {
+            let shutting_down = shutting_down.clone();
+            Arc::new(move |_| {
+                if shutting_down.load(Ordering::SeqCst) {
+                    HealthCheckStatus::NOT_SERVING
+                } else {
+                    HealthCheckStatus::SERVING
+                }
+            })
+        };
+        let health_service =
+            HealthService::new(Some(health_check_callback), logger.clone()).into_service();
 
         // Admon service.
         let admin_service = AdminService::new(
             "mobilecoind".to_owned(),
             listen_uri.to_string(),
             None,
+            None,
+            Arc::new(AnonymousAuthenticator::default()),
+            None,
+            logger.clone(),
+        )
+        .into_service();
+
+        // Reflection service, so that generic clients can discover the API.
+        let reflection_service = ReflectionService::new(
+            &[
+                "admin.AdminAPI",
+                "build_info.BuildInfoApi",
+                "grpc.health.v1.Health",
+                "mobilecoind_api.MobilecoindAPI",
+            ],
+            mc_mobilecoind_api::file_descriptors()
+                .into_iter()
+                .chain(mc_api::file_descriptors()),
             logger.clone(),
         )
         .into_service();
@@ -144,14 +192,40 @@ impl Service {
             .register_service(build_info_service)
             .register_service(health_service)
             .register_service(mobilecoind_service)
+            .register_service(reflection_service)
             .bind_using_uri(listen_uri, logger.clone());
 
         let mut server = server_builder.build().unwrap();
         server.start();
 
         Self {
-            _server: server,
-            _sync_thread: sync_thread,
+            server,
+            sync_thread,
+            shutting_down,
+            logger,
+        }
+    }
+
+    /// Stop the service gracefully. The health service reports NOT_SERVING
+    /// and new transactions are rejected right away. Other calls are served
+    /// for `grace_period`, so that load balancers see the health change before
+    /// the server goes away. Then in-flight calls are completed, and the sync
+    /// thread finishes the blocks it is processing.
+    pub fn shutdown(mut self, grace_period: Duration) {
+        log::info!(
+            self.logger,
+            "Shutting down mobilecoind API service in {:?}",
+            grace_period
+        );
+        self.shutting_down.store(true, Ordering::SeqCst);
+        thread::sleep(grace_period);
+
+        if block_on(self.server.shutdown()).is_err() {
+            log::error!(self.logger, "Failed shutting down the gRPC server");
+        }
+
+        if let Some(mut sync_thread) = self.sync_thread.lock().expect("mutex poisoned").take() {
+            sync_thread.stop();
         }
     }
 }
@@ -166,6 +240,8 @@ pub struct ServiceApi<
     watcher_db: Option<WatcherDB>,
     network_state: Arc<RwLock<PollingNetworkState<T>>>,
     start_sync_thread: Arc<dyn Fn() + Send + Sync>,
+    authorizer: ApiAuthorizer,
+    shutting_down: Arc<AtomicBool>,
     logger: Logger,
 }
 
@@ -180,6 +256,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             watcher_db: self.watcher_db.clone(),
             network_state: self.network_state.clone(),
             start_sync_thread: self.start_sync_thread.clone(),
+            authorizer: self.authorizer.clone(),
+            shutting_down: self.shutting_down.clone(),
             logger: self.logger.clone(),
         }
     }
@@ -195,6 +273,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         watcher_db: Option<WatcherDB>,
         network_state: Arc<RwLock<PollingNetworkState<T>>>,
         start_sync_thread: Arc<dyn Fn() + Send + Sync>,
+        authorizer: ApiAuthorizer,
+        shutting_down: Arc<AtomicBool>,
         logger: Logger,
     ) -> Self {
         Self {
@@ -204,6 +284,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             watcher_db,
             network_state,
             start_sync_thread,
+            authorizer,
+            shutting_down,
             logger,
         }
     }
@@ -394,6 +476,95 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(response)
     }
 
+    fn split_mnemonic_impl(
+        &mut self,
+        request: mc_mobilecoind_api::SplitMnemonicRequest,
+    ) -> Result<mc_mobilecoind_api::SplitSecretResponse, RpcStatus> {
+        let mnemonic = Mnemonic::from_phrase(request.get_mnemonic(), Language::English)
+            .map_err(|err| rpc_invalid_arg_error("mnemonic", err, &self.logger))?;
+        let (threshold, share_count) =
+            share_params(request.threshold, request.share_count, &self.logger)?;
+
+        let mut rng = rand::thread_rng();
+        let shares = mc_account_keys_slip39::split_mnemonic(
+            &mnemonic,
+            threshold,
+            share_count,
+            request.get_passphrase().as_bytes(),
+            &mut rng,
+        )
+        .map_err(|err| rpc_invalid_arg_error("split_mnemonic", err, &self.logger))?;
+
+        let mut response = mc_mobilecoind_api::SplitSecretResponse::new();
+        response.set_shares(RepeatedField::from_vec(shares));
+        Ok(response)
+    }
+
+    fn split_root_entropy_impl(
+        &mut self,
+        request: mc_mobilecoind_api::SplitRootEntropyRequest,
+    ) -> Result<mc_mobilecoind_api::SplitSecretResponse, RpcStatus> {
+        if request.get_root_entropy().len() != 32 {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "root_entropy".into(),
+            ));
+        }
+        let (threshold, share_count) =
+            share_params(request.threshold, request.share_count, &self.logger)?;
+
+        let mut rng = rand::thread_rng();
+        let shares = mc_account_keys_slip39::split_secret_simple(
+            threshold,
+            share_count,
+            request.get_root_entropy(),
+            request.get_passphrase().as_bytes(),
+            &mut rng,
+        )
+        .map_err(|err| rpc_invalid_arg_error("split_secret", err, &self.logger))?;
+
+        let mut response = mc_mobilecoind_api::SplitSecretResponse::new();
+        response.set_shares(RepeatedField::from_vec(shares));
+        Ok(response)
+    }
+
+    fn combine_mnemonic_shares_impl(
+        &mut self,
+        request: mc_mobilecoind_api::CombineSharesRequest,
+    ) -> Result<mc_mobilecoind_api::CombineMnemonicSharesResponse, RpcStatus> {
+        let mnemonic = mc_account_keys_slip39::combine_mnemonic(
+            request.get_shares(),
+            request.get_passphrase().as_bytes(),
+        )
+        .map_err(|err| rpc_invalid_arg_error("shares", err, &self.logger))?;
+
+        let mut response = mc_mobilecoind_api::CombineMnemonicSharesResponse::new();
+        response.set_mnemonic(mnemonic.phrase().to_string());
+        response.set_bip39_entropy(mnemonic.entropy().to_vec());
+        Ok(response)
+    }
+
+    fn combine_root_entropy_shares_impl(
+        &mut self,
+        request: mc_mobilecoind_api::CombineSharesRequest,
+    ) -> Result<mc_mobilecoind_api::CombineRootEntropySharesResponse, RpcStatus> {
+        let root_entropy = mc_account_keys_slip39::combine_shares(
+            request.get_shares(),
+            request.get_passphrase().as_bytes(),
+        )
+        .map_err(|err| rpc_invalid_arg_error("shares", err, &self.logger))?;
+        if root_entropy.len() != 32 {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "shares do not hold a root entropy".into(),
+            ));
+        }
+
+        let mut response = mc_mobilecoind_api::CombineRootEntropySharesResponse::new();
+        response.set_root_entropy(root_entropy.to_vec());
+        Ok(response)
+    }
+
     fn get_public_address_impl(
         &mut self,
         request: mc_mobilecoind_api::GetPublicAddressRequest,
@@ -1043,6 +1214,14 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         &mut self,
         request: mc_mobilecoind_api::SubmitTxRequest,
     ) -> Result<mc_mobilecoind_api::SubmitTxResponse, RpcStatus> {
+        // Transactions submitted while shutting down might not be tracked.
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::UNAVAILABLE,
+                "mobilecoind is shutting down".into(),
+            ));
+        }
+
         // Get TxProposal from request.
         let tx_proposal = TxProposal::try_from(request.get_tx_proposal())
             .map_err(|err| rpc_internal_error("tx_proposal.try_from", err, &self.logger))?;
@@ -1823,6 +2002,16 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
     }
 }
 
+/// Check the threshold and share count of a secret-splitting request fit in
+/// the ranges SLIP-0039 supports.
+fn share_params(threshold: u32, share_count: u32, logger: &Logger) -> Result<(u8, u8), RpcStatus> {
+    let threshold =
+        u8::try_from(threshold).map_err(|err| rpc_invalid_arg_error("threshold", err, logger))?;
+    let share_count = u8::try_from(share_count)
+        .map_err(|err| rpc_invalid_arg_error("share_count", err, logger))?;
+    Ok((threshold, share_count))
+}
+
 macro_rules! build_api {
     ($( $service_function_name:ident $service_request_type:ident $service_response_type:ident $service_function_impl:ident ),+)
     =>
@@ -1836,6 +2025,16 @@ macro_rules! build_api {
                     sink: UnarySink<mc_mobilecoind_api::$service_response_type>,
                 ) {
                     let logger = rpc_logger(&ctx, &self.logger);
+                    let _trace_guard = rpc_span(
+                        &global::tracer("mc_mobilecoind"),
+                        &ctx,
+                        stringify!($service_function_name),
+                    ).attach();
+                    let scope = Scope::for_method(stringify!($service_function_name));
+                    if let Err(err) = self.authorizer.authorize_rpc(&ctx, scope) {
+                        log::debug!(logger, "Refusing {}: {}", stringify!($service_function_name), err);
+                        return send_result(ctx, sink, Err(err.into()), &logger);
+                    }
                     send_result(
                         ctx,
                         sink,
@@ -1862,6 +2061,10 @@ build_api! {
     get_account_key_from_root_entropy GetAccountKeyFromRootEntropyRequest GetAccountKeyResponse get_account_key_from_root_entropy_impl,
     get_account_key_from_mnemonic GetAccountKeyFromMnemonicRequest GetAccountKeyResponse get_account_key_from_mnemonic_impl,
     get_public_address GetPublicAddressRequest GetPublicAddressResponse get_public_address_impl,
+    split_mnemonic SplitMnemonicRequest SplitSecretResponse split_mnemonic_impl,
+    split_root_entropy SplitRootEntropyRequest SplitSecretResponse split_root_entropy_impl,
+    combine_mnemonic_shares CombineSharesRequest CombineMnemonicSharesResponse combine_mnemonic_shares_impl,
+    combine_root_entropy_shares CombineSharesRequest CombineRootEntropySharesResponse combine_root_entropy_shares_impl,
 
     // b58 codes
     parse_request_code ParseRequestCodeRequest ParseRequestCodeResponse parse_request_code_impl,
@@ -1916,7 +2119,7 @@ mod test {
     };
     use grpcio::Error as GrpcError;
     use mc_account_keys::{AccountKey, PublicAddress, DEFAULT_SUBADDRESS_INDEX};
-    use mc_common::{logger::test_with_logger, HashSet};
+    use mc_common::{logger::test_with_logger, time::SystemTimeProvider, HashSet};
     use mc_crypto_keys::RistrettoPrivate;
     use mc_crypto_rand::RngCore;
     use mc_fog_report_validation::{FullyValidatedFogPubkey, MockFogPubkeyResolver};
@@ -1930,6 +2133,7 @@ mod test {
         Block, BlockContents, BLOCK_VERSION,
     };
     use mc_transaction_std::TransactionBuilder;
+    use mc_util_grpc::TokenBasicCredentialsGenerator;
     use mc_util_repr_bytes::{typenum::U32, GenericArray, ReprBytes};
     use mc_util_uri::FogUri;
     use rand::{rngs::StdRng, SeedableRng};
@@ -1937,6 +2141,7 @@ mod test {
         convert::{TryFrom, TryInto},
         iter::FromIterator,
         str::FromStr,
+        time::Instant,
     };
 
     #[test_with_logger]
@@ -2327,6 +2532,96 @@ mod test {
         assert!(client.get_account_key_from_mnemonic(&request).is_err());
     }
 
+    #[test_with_logger]
+    fn test_split_and_combine_mnemonic_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(3, &vec![], &vec![], logger.clone(), &mut rng);
+
+        let mnemonic_str =
+            "legal winner thank year wave sausage worth useful legal winner thank yellow";
+
+        let mut request = mc_mobilecoind_api::SplitMnemonicRequest::new();
+        request.set_mnemonic(mnemonic_str.to_string());
+        request.set_threshold(2);
+        request.set_share_count(3);
+        request.set_passphrase("TREZOR".to_string());
+        let response = client.split_mnemonic(&request).unwrap();
+        let shares = response.get_shares().to_vec();
+        assert_eq!(shares.len(), 3);
+
+        // Any two shares should recover the mnemonic.
+        let mut request = mc_mobilecoind_api::CombineSharesRequest::new();
+        request.set_shares(RepeatedField::from_vec(vec![
+            shares[2].clone(),
+            shares[0].clone(),
+        ]));
+        request.set_passphrase("TREZOR".to_string());
+        let response = client.combine_mnemonic_shares(&request).unwrap();
+        assert_eq!(response.get_mnemonic(), mnemonic_str);
+        assert_eq!(
+            response.get_bip39_entropy(),
+            Mnemonic::from_phrase(mnemonic_str, Language::English)
+                .unwrap()
+                .entropy()
+        );
+
+        // A single share is not enough.
+        let mut request = mc_mobilecoind_api::CombineSharesRequest::new();
+        request.set_shares(RepeatedField::from_vec(vec![shares[1].clone()]));
+        request.set_passphrase("TREZOR".to_string());
+        assert!(client.combine_mnemonic_shares(&request).is_err());
+
+        // Invalid thresholds should error.
+        let mut request = mc_mobilecoind_api::SplitMnemonicRequest::new();
+        request.set_mnemonic(mnemonic_str.to_string());
+        request.set_threshold(4);
+        request.set_share_count(3);
+        assert!(client.split_mnemonic(&request).is_err());
+
+        request.set_threshold(2);
+        request.set_share_count(300);
+        assert!(client.split_mnemonic(&request).is_err());
+    }
+
+    #[test_with_logger]
+    fn test_split_and_combine_root_entropy_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(3, &vec![], &vec![], logger.clone(), &mut rng);
+
+        let root_entropy = [123u8; 32];
+
+        let mut request = mc_mobilecoind_api::SplitRootEntropyRequest::new();
+        request.set_root_entropy(root_entropy.to_vec());
+        request.set_threshold(3);
+        request.set_share_count(5);
+        let response = client.split_root_entropy(&request).unwrap();
+        let shares = response.get_shares().to_vec();
+        assert_eq!(shares.len(), 5);
+
+        let mut request = mc_mobilecoind_api::CombineSharesRequest::new();
+        request.set_shares(RepeatedField::from_vec(shares[1..4].to_vec()));
+        let response = client.combine_root_entropy_shares(&request).unwrap();
+        assert_eq!(response.get_root_entropy(), &root_entropy[..]);
+
+        // Combining with the wrong passphrase yields a different secret.
+        request.set_passphrase("wrong".to_string());
+        let response = client.combine_root_entropy_shares(&request).unwrap();
+        assert_ne!(response.get_root_entropy(), &root_entropy[..]);
+
+        // Root entropy must be 32 bytes.
+        let mut request = mc_mobilecoind_api::SplitRootEntropyRequest::new();
+        request.set_root_entropy(vec![1u8; 16]);
+        request.set_threshold(2);
+        request.set_share_count(3);
+        assert!(client.split_root_entropy(&request).is_err());
+    }
+
     #[test_with_logger]
     fn test_get_account_key_from_root_entropy_impl(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -4413,6 +4708,7 @@ mod test {
             None,
             Some(fog_pubkey_resolver_factory),
             &uri,
+            ApiAuthorizer::default(),
         );
         log::debug!(logger, "Setting up client {:?}", port);
         let client = test_utils::setup_client(&uri, &logger);
@@ -5298,4 +5594,184 @@ mod test {
             .expect("Failed getting processed block");
         assert_eq!(response.get_tx_outs().len(), 1);
     }
+
+    #[test_with_logger]
+    // Shutting down in the middle of syncing should stop the API and leave the
+    // monitor consistent with the blocks it has processed.
+    fn test_shutdown(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            account_key.clone(),
+            DEFAULT_SUBADDRESS_INDEX, // first_subaddress
+            1,                        // num_subaddresses
+            0,                        // first_block
+            "",                       // name
+        )
+        .unwrap();
+        let monitor_id = MonitorId::from(&data);
+
+        let (mut ledger_db, mobilecoind_db, client, server, _server_conn_manager) =
+            get_testing_environment(
+                3,
+                &[account_key.default_subaddress()],
+                &[data],
+                logger.clone(),
+                &mut rng,
+            );
+
+        client
+            .get_ledger_info(&mc_mobilecoind_api::Empty::new())
+            .expect("failed getting ledger info");
+
+        // New blocks for the monitor to process.
+        for _ in 0..50 {
+            add_block_to_ledger_db(
+                &mut ledger_db,
+                &[account_key.default_subaddress()],
+                DEFAULT_PER_RECIPIENT_AMOUNT,
+                &[],
+                &mut rng,
+            );
+        }
+
+        // Reads are still served during the grace period.
+        let grace_period = Duration::from_millis(500);
+        let reader = {
+            let client = client.clone();
+            thread::spawn(move || {
+                thread::sleep(grace_period / 2);
+                client.get_ledger_info(&mc_mobilecoind_api::Empty::new())
+            })
+        };
+        let shutdown_started = Instant::now();
+        server.shutdown(grace_period);
+        assert!(shutdown_started.elapsed() >= grace_period);
+        reader
+            .join()
+            .unwrap()
+            .expect("failed getting ledger info during the grace period");
+
+        // The API is no longer served.
+        assert!(client
+            .get_ledger_info(&mc_mobilecoind_api::Empty::new())
+            .is_err());
+
+        // Each block has one output for the monitor, so it has one utxo per
+        // processed block.
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(utxos.len() as u64, monitor_data.next_block);
+    }
+
+    #[test_with_logger]
+    fn test_authorize_rpc(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([24u8; 32]);
+        let shared_secret = [7u8; 32];
+
+        let account_key = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            account_key.clone(),
+            DEFAULT_SUBADDRESS_INDEX, // first_subaddress
+            1,                        // num_subaddresses
+            0,                        // first_block
+            "",                       // name
+        )
+        .unwrap();
+
+        let (ledger_db, mobilecoind_db) = test_utils::get_test_databases(
+            3,
+            &[account_key.default_subaddress()],
+            test_utils::GET_TESTING_ENVIRONMENT_NUM_BLOCKS,
+            logger.clone(),
+            &mut rng,
+        );
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        let port = test_utils::get_free_port();
+        let uri = MobilecoindUri::from_str(&format!("insecure-mobilecoind://127.0.0.1:{}/", port))
+            .unwrap();
+        let (_server, _server_conn_manager) = test_utils::setup_server::<MockFogResolver>(
+            logger.clone(),
+            ledger_db,
+            mobilecoind_db,
+            None,
+            None,
+            &uri,
+            ApiAuthorizer::with_token_secret(shared_secret, Duration::from_secs(60)),
+        );
+        let client = test_utils::setup_client(&uri, &logger);
+
+        let generator =
+            TokenBasicCredentialsGenerator::new(shared_secret, SystemTimeProvider::default());
+        let call_option = |username: &str| {
+            generator
+                .generate_for(username)
+                .unwrap()
+                .call_option()
+                .unwrap()
+        };
+        fn status_code<T: std::fmt::Debug>(result: Result<T, GrpcError>) -> RpcStatusCode {
+            match result {
+                Err(GrpcError::RpcFailure(rpc_status)) => rpc_status.code(),
+                result => panic!("Unexpected result {:?}", result),
+            }
+        }
+
+        // Calls without a token are refused.
+        assert_eq!(
+            status_code(client.get_ledger_info(&mc_mobilecoind_api::Empty::new())),
+            RpcStatusCode::UNAUTHENTICATED
+        );
+
+        // A forged token is refused.
+        let forged = TokenBasicCredentialsGenerator::new([8; 32], SystemTimeProvider::default())
+            .generate_for("wallet/keys")
+            .unwrap()
+            .call_option()
+            .unwrap();
+        assert_eq!(
+            status_code(client.get_ledger_info_opt(&mc_mobilecoind_api::Empty::new(), forged)),
+            RpcStatusCode::UNAUTHENTICATED
+        );
+
+        // A read token may read, but not call methods needing other scopes.
+        client
+            .get_ledger_info_opt(
+                &mc_mobilecoind_api::Empty::new(),
+                call_option("explorer/read"),
+            )
+            .expect("failed getting ledger info");
+        assert_eq!(
+            status_code(client.generate_root_entropy_opt(
+                &mc_mobilecoind_api::Empty::new(),
+                call_option("explorer/read"),
+            )),
+            RpcStatusCode::PERMISSION_DENIED
+        );
+
+        // A read token cannot get key material from the monitor status.
+        let mut request = mc_mobilecoind_api::GetMonitorStatusRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        assert_eq!(
+            status_code(client.get_monitor_status_opt(&request, call_option("explorer/read"))),
+            RpcStatusCode::PERMISSION_DENIED
+        );
+        assert_eq!(
+            status_code(client.get_monitor_status_opt(&request, call_option("wallet/read,spend"))),
+            RpcStatusCode::PERMISSION_DENIED
+        );
+
+        // A token granting key management may.
+        let response = client
+            .get_monitor_status_opt(&request, call_option("wallet/keys"))
+            .expect("failed getting monitor status");
+        assert_eq!(
+            AccountKey::try_from(response.get_status().get_account_key()).unwrap(),
+            account_key
+        );
+    }
 }
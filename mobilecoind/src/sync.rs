@@ -91,6 +91,10 @@ impl SyncThread {
         // preventing them from being sent again until they are processed.
         let queued_monitor_ids = Arc::new(Mutex::new(HashSet::<MonitorId>::default()));
 
+        // Set when the threads should stop. Workers finish the block they are
+        // processing, and skip the rest of the queue.
+        let stop_requested = Arc::new(AtomicBool::new(false));
+
         // Create worker threads.
         let mut worker_join_handles = Vec::new();
 
@@ -100,6 +104,7 @@ impl SyncThread {
             let thread_sender = sender.clone();
             let thread_receiver = receiver.clone();
             let thread_queued_monitor_ids = queued_monitor_ids.clone();
+            let thread_stop_requested = stop_requested.clone();
             let thread_logger = logger.clone();
             let join_handle = thread::Builder::new()
                 .name(format!("sync_worker_{}", idx))
@@ -110,6 +115,7 @@ impl SyncThread {
                         thread_sender,
                         thread_receiver,
                         thread_queued_monitor_ids,
+                        thread_stop_requested,
                         thread_logger,
                     );
                 })
@@ -121,7 +127,6 @@ impl SyncThread {
         // Start the main sync thread.
         // This thread constantly monitors the list of monitor ids we are aware of,
         // and adds new one into our cyclic queue.
-        let stop_requested = Arc::new(AtomicBool::new(false));
         let thread_stop_requested = stop_requested.clone();
 
         let join_handle = Some(
@@ -190,9 +195,8 @@ impl SyncThread {
                         "SyncThread attempting to stop all worker threads..."
                     );
                     for _ in 0..worker_join_handles.len() {
-                        sender
-                            .send(SyncMsg::Stop)
-                            .expect("failed sending stop message");
+                        // Fails once every worker has noticed the stop request on its own.
+                        let _ = sender.send(SyncMsg::Stop);
                     }
 
                     let num_workers = worker_join_handles.len();
@@ -238,12 +242,24 @@ fn sync_thread_entry_point(
     sender: crossbeam_channel::Sender<SyncMsg>,
     receiver: crossbeam_channel::Receiver<SyncMsg>,
     queued_monitor_ids: Arc<Mutex<HashSet<MonitorId>>>,
+    stop_requested: Arc<AtomicBool>,
     logger: Logger,
 ) {
     for msg in receiver.iter() {
         match msg {
             SyncMsg::SyncMonitor(monitor_id) => {
-                match sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger) {
+                // Don't start on more work once asked to stop.
+                if stop_requested.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                match sync_monitor(
+                    &ledger_db,
+                    &mobilecoind_db,
+                    &monitor_id,
+                    &stop_requested,
+                    &logger,
+                ) {
                     // Success - No more blocks are currently available.
                     Ok(SyncMonitorOk::NoMoreBlocks) => {
                         // Remove the monitor id from the list of queued ones so that the main
@@ -286,14 +302,21 @@ fn sync_thread_entry_point(
     }
 }
 
-/// Sync a single monitor.
+/// Sync a single monitor. Each block is recorded in its own database
+/// transaction, so stopping between blocks leaves the monitor consistent.
 fn sync_monitor(
     ledger_db: &LedgerDB,
     mobilecoind_db: &Database,
     monitor_id: &MonitorId,
+    stop_requested: &AtomicBool,
     logger: &Logger,
 ) -> Result<SyncMonitorOk, Error> {
     for _ in 0..MAX_BLOCKS_PROCESSING_CHUNK_SIZE {
+        if stop_requested.load(Ordering::SeqCst) {
+            // The monitor is picked up from its next block when syncing resumes.
+            return Ok(SyncMonitorOk::MoreBlocksPotentiallyAvailable);
+        }
+
         // Get the monitor data. If it is no longer available, the monitor has been
         // removed and we can simply return.
         let monitor_data = mobilecoind_db.get_monitor_data(monitor_id)?;
@@ -416,7 +439,8 @@ mod test {
     use crate::{
         monitor_store::MonitorData,
         test_utils::{
-            self, add_block_to_ledger_db, get_test_databases, DEFAULT_PER_RECIPIENT_AMOUNT,
+            self, add_block_to_ledger_db, get_test_databases, wait_for_monitors,
+            DEFAULT_PER_RECIPIENT_AMOUNT,
         },
     };
     use mc_account_keys::{AccountKey, PublicAddress, DEFAULT_SUBADDRESS_INDEX};
@@ -484,7 +508,14 @@ mod test {
         assert_eq!(monitor_data.next_block, 0);
 
         // Process the first MAX_BLOCKS_PROCESSING_CHUNK_SIZE blocks.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &AtomicBool::new(false),
+            &logger,
+        )
+        .unwrap();
         assert_eq!(result, SyncMonitorOk::MoreBlocksPotentiallyAvailable);
 
         // We should now discover some outputs. Each block has 1 output per recipient,
@@ -509,7 +540,14 @@ mod test {
         }
 
         // Process the second MAX_BLOCKS_PROCESSING_CHUNK_SIZE blocks.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &AtomicBool::new(false),
+            &logger,
+        )
+        .unwrap();
         assert_eq!(result, SyncMonitorOk::MoreBlocksPotentiallyAvailable);
 
         let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
@@ -532,7 +570,14 @@ mod test {
         }
 
         // Process the last remaining block.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &AtomicBool::new(false),
+            &logger,
+        )
+        .unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
@@ -552,7 +597,14 @@ mod test {
         }
 
         // Calling sync_monitor again should not change the results.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &AtomicBool::new(false),
+            &logger,
+        )
+        .unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
@@ -585,7 +637,14 @@ mod test {
             &mut rng,
         );
 
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &AtomicBool::new(false),
+            &logger,
+        )
+        .unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         let utxos = mobilecoind_db
@@ -596,6 +655,107 @@ mod test {
         assert!(!utxos.contains(&first_utxo));
     }
 
+    #[test_with_logger]
+    // sync_monitor should not process any block once a stop is requested.
+    fn test_sync_monitor_stop_requested(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([98u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+        let data =
+            MonitorData::new(account_key.clone(), DEFAULT_SUBADDRESS_INDEX, 1, 0, "").unwrap();
+        let monitor_id = MonitorId::from(&data);
+
+        let (ledger_db, mobilecoind_db) = get_test_databases(
+            0,
+            &[account_key.default_subaddress()],
+            MAX_BLOCKS_PROCESSING_CHUNK_SIZE,
+            logger.clone(),
+            &mut rng,
+        );
+        assert_eq!(mobilecoind_db.add_monitor(&data).unwrap(), monitor_id);
+
+        let result = sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &AtomicBool::new(true),
+            &logger,
+        )
+        .unwrap();
+        assert_eq!(result, SyncMonitorOk::MoreBlocksPotentiallyAvailable);
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 0);
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(utxos.len(), 0);
+    }
+
+    #[test_with_logger]
+    // Stopping the sync thread in the middle of syncing should leave every monitor
+    // consistent with the blocks it has processed, and syncing should resume
+    // where it stopped.
+    fn test_sync_thread_stopped_mid_sync(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([98u8; 32]);
+
+        let account_keys: Vec<_> = (0..3).map(|_i| AccountKey::random(&mut rng)).collect();
+        let recipients: Vec<PublicAddress> = account_keys
+            .iter()
+            .map(AccountKey::default_subaddress)
+            .collect();
+
+        let num_blocks = MAX_BLOCKS_PROCESSING_CHUNK_SIZE * 40;
+        let (ledger_db, mobilecoind_db) =
+            get_test_databases(0, &recipients, num_blocks, logger.clone(), &mut rng);
+
+        let monitor_ids: Vec<MonitorId> = account_keys
+            .iter()
+            .map(|account_key| {
+                let data =
+                    MonitorData::new(account_key.clone(), DEFAULT_SUBADDRESS_INDEX, 1, 0, "")
+                        .unwrap();
+                mobilecoind_db.add_monitor(&data).unwrap()
+            })
+            .collect();
+
+        // Each block has one output per recipient, so a monitor has one utxo per
+        // processed block.
+        let assert_consistent = || {
+            for monitor_id in &monitor_ids {
+                let monitor_data = mobilecoind_db.get_monitor_data(monitor_id).unwrap();
+                let utxos = mobilecoind_db
+                    .get_utxos_for_subaddress(monitor_id, DEFAULT_SUBADDRESS_INDEX)
+                    .unwrap();
+                assert_eq!(utxos.len() as u64, monitor_data.next_block);
+            }
+        };
+
+        let mut sync_thread = SyncThread::start(
+            ledger_db.clone(),
+            mobilecoind_db.clone(),
+            Some(2),
+            logger.clone(),
+        );
+        thread::sleep(std::time::Duration::from_millis(50));
+        sync_thread.stop();
+        assert_consistent();
+
+        // Syncing resumes where it stopped.
+        let _sync_thread = SyncThread::start(
+            ledger_db.clone(),
+            mobilecoind_db.clone(),
+            Some(2),
+            logger.clone(),
+        );
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+        assert_consistent();
+        for monitor_id in &monitor_ids {
+            let monitor_data = mobilecoind_db.get_monitor_data(monitor_id).unwrap();
+            assert_eq!(monitor_data.next_block, num_blocks as u64);
+        }
+    }
+
     #[test_with_logger]
     fn test_utxo_value_zero(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([98u8; 32]);
@@ -626,7 +786,14 @@ mod test {
         assert_eq!(mobilecoind_db.add_monitor(&data).unwrap(), monitor_id);
 
         // Sync.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &AtomicBool::new(false),
+            &logger,
+        )
+        .unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         // Should have a single non-zero utxo for our monitor.
@@ -646,7 +813,14 @@ mod test {
             &mut rng,
         );
 
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &AtomicBool::new(false),
+            &logger,
+        )
+        .unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         // We should now have only a zero utxo.
@@ -22,7 +22,9 @@ use mc_crypto_rand::{CryptoRng, RngCore};
 use mc_fog_report_validation_test_utils::{FogPubkeyResolver, MockFogResolver};
 use mc_ledger_db::{Ledger, LedgerDB};
 use mc_ledger_sync::PollingNetworkState;
-use mc_mobilecoind_api::{mobilecoind_api_grpc::MobilecoindApiClient, MobilecoindUri};
+use mc_mobilecoind_api::{
+    mobilecoind_api_grpc::MobilecoindApiClient, ApiAuthorizer, MobilecoindUri,
+};
 use mc_transaction_core::{
     ring_signature::KeyImage, tx::TxOut, Block, BlockContents, BLOCK_VERSION,
 };
@@ -236,6 +238,7 @@ pub fn setup_server<FPR: FogPubkeyResolver + Default + Send + Sync + 'static>(
     watcher_db: Option<WatcherDB>,
     fog_resolver_factory: Option<Arc<dyn Fn(&[FogUri]) -> Result<FPR, String> + Send + Sync>>,
     uri: &MobilecoindUri,
+    authorizer: ApiAuthorizer,
 ) -> (
     Service,
     ConnectionManager<MockBlockchainConnection<LedgerDB>>,
@@ -279,6 +282,7 @@ pub fn setup_server<FPR: FogPubkeyResolver + Default + Send + Sync + 'static>(
         transactions_manager,
         network_state,
         uri,
+        authorizer,
         None,
         logger,
     );
@@ -343,6 +347,7 @@ pub fn get_testing_environment(
         None,
         None,
         &uri,
+        ApiAuthorizer::default(),
     );
     log::debug!(logger, "Setting up client {:?}", port);
     let client = setup_client(&uri, &logger);
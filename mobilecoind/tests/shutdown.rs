@@ -0,0 +1,198 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Stop a running mobilecoind with SIGTERM while it is syncing the ledger from
+//! a devnet, and check that its databases were left consistent.
+
+use grpcio::{ChannelBuilder, EnvBuilder};
+use mc_account_keys::{AccountKey, DEFAULT_SUBADDRESS_INDEX};
+use mc_api::block_num_to_s3block_path;
+use mc_common::logger::{log, test_with_logger, Logger};
+use mc_devnet::{config::DevnetConfig, Devnet, DevnetError};
+use mc_ledger_db::{Ledger, LedgerDB};
+use mc_mobilecoind::database::Database;
+use mc_mobilecoind_api::{mobilecoind_api_grpc::MobilecoindApiClient, MobilecoindUri};
+use mc_util_grpc::ConnectionUriGrpcioChannel;
+use mc_util_keyfile::keygen;
+use std::{
+    env,
+    path::PathBuf,
+    process::{Command, Stdio},
+    str::FromStr,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+use tempdir::TempDir;
+
+/// Number of blocks in the devnet's origin ledger, which mobilecoind starts
+/// without and has to sync.
+const NUM_BLOCKS: u64 = 2000;
+
+/// How long to wait for the devnet and mobilecoind to start, and to exit.
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+// Get the build dir, which is one down from current_exe, which is in
+// target/debug/deps,
+fn build_dir() -> PathBuf {
+    let mut result = env::current_exe().unwrap();
+    result.pop();
+    result.pop();
+    result
+}
+
+/// Call `f` every `interval` until it returns `Some`, panicking if that takes
+/// longer than `TIMEOUT`.
+fn wait_for<T>(what: &str, interval: Duration, mut f: impl FnMut() -> Option<T>) -> T {
+    let start = Instant::now();
+    loop {
+        if let Some(value) = f() {
+            return value;
+        }
+        assert!(start.elapsed() < TIMEOUT, "Timed out waiting for {}", what);
+        thread::sleep(interval);
+    }
+}
+
+// Requires SGX_MODE=SW and the ledger-distribution binary in the build dir,
+// and passes trivially otherwise.
+#[test_with_logger]
+fn sigterm_during_ledger_sync_leaves_databases_consistent(logger: Logger) {
+    // The origin ledger pays the only account one output in each block.
+    let work_dir = TempDir::new("mobilecoind-shutdown").unwrap();
+    let config = DevnetConfig::from_iter(&[
+        "devnet",
+        "--work-dir",
+        work_dir.path().join("devnet").to_str().unwrap(),
+        "--bin-dir",
+        build_dir().to_str().unwrap(),
+        "--nodes",
+        "2",
+        "--base-port",
+        "4100",
+        "--num-keys",
+        "1",
+        "--outputs-per-key",
+        "1",
+        "--num-blocks",
+        &NUM_BLOCKS.to_string(),
+    ]);
+    let devnet = match Devnet::start(config, logger.clone()) {
+        Err(DevnetError::NotSimulation) => {
+            log::warn!(logger, "Skipping, the devnet requires SGX_MODE=SW");
+            return;
+        }
+        result => result.expect("Could not start devnet"),
+    };
+    let account_key =
+        AccountKey::from(&keygen::read_default_root_entropies(devnet.keys_dir()).unwrap()[0]);
+
+    // mobilecoind fetches blocks from the nodes' archives, so wait until
+    // ledger-distribution has published all of them.
+    let last_block_path = block_num_to_s3block_path(NUM_BLOCKS - 1);
+    wait_for("the archives", Duration::from_millis(500), || {
+        (0..devnet.nodes().len())
+            .all(|index| devnet.archive_dir(index).join(&last_block_path).exists())
+            .then(|| ())
+    });
+
+    // Without --ledger-db-bootstrap, mobilecoind starts from the origin block
+    // in the archives and syncs the rest.
+    let ledger_path = work_dir.path().join("ledger");
+    let mobilecoind_db_path = work_dir.path().join("mobilecoind");
+    let uri = MobilecoindUri::from_str("insecure-mobilecoind://127.0.0.1:4498/").unwrap();
+    let mut args = vec![
+        "--ledger-db".to_owned(),
+        ledger_path.display().to_string(),
+        "--mobilecoind-db".to_owned(),
+        mobilecoind_db_path.display().to_string(),
+        "--listen-uri".to_owned(),
+        uri.to_string(),
+        "--poll-interval".to_owned(),
+        "1".to_owned(),
+        "--attestation-policy".to_owned(),
+        devnet.attestation_policy_path().display().to_string(),
+        "--num-workers".to_owned(),
+        "1".to_owned(),
+        "--shutdown-timeout".to_owned(),
+        "60".to_owned(),
+        "--shutdown-grace-period".to_owned(),
+        "0".to_owned(),
+    ];
+    for (node, tx_source_url) in devnet.nodes().iter().zip(devnet.tx_source_urls()) {
+        args.push("--peer".to_owned());
+        args.push(node.client_uri().to_string());
+        args.push("--tx-source-url".to_owned());
+        args.push(tx_source_url);
+    }
+    let mut mobilecoind = Command::new(env!("CARGO_BIN_EXE_mobilecoind"))
+        .args(&args)
+        .stdout(Stdio::null())
+        .spawn()
+        .expect("Could not start mobilecoind");
+
+    let env = Arc::new(EnvBuilder::new().build());
+    let client = MobilecoindApiClient::new(ChannelBuilder::new(env).connect_to_uri(&uri, &logger));
+    let mut request = mc_mobilecoind_api::AddMonitorRequest::new();
+    request.set_account_key(mc_api::external::AccountKey::from(&account_key));
+    request.set_first_subaddress(DEFAULT_SUBADDRESS_INDEX);
+    request.set_num_subaddresses(1);
+    request.set_first_block(0);
+
+    // Retry until the API is up.
+    wait_for(
+        "the monitor to be added",
+        Duration::from_millis(100),
+        || client.add_monitor(&request).ok(),
+    );
+
+    // Interrupt the ledger sync once it has appended its first block, while
+    // it is still working through the rest.
+    let ledger = LedgerDB::open(&ledger_path).unwrap();
+    let num_blocks_at_signal = wait_for("the ledger sync", Duration::from_millis(10), || {
+        ledger
+            .num_blocks()
+            .ok()
+            .filter(|num_blocks| *num_blocks > 1)
+    });
+    unsafe {
+        libc::kill(mobilecoind.id() as libc::pid_t, libc::SIGTERM);
+    }
+    assert!(
+        num_blocks_at_signal < NUM_BLOCKS,
+        "The ledger sync finished before it could be interrupted"
+    );
+
+    let status = wait_for("mobilecoind to exit", Duration::from_millis(100), || {
+        mobilecoind.try_wait().unwrap()
+    });
+    assert!(status.success(), "mobilecoind exited with {}", status);
+
+    // The sync batch in progress was finished within the shutdown timeout, and
+    // every block was committed whole.
+    let origin = LedgerDB::open(&devnet.origin_dir()).unwrap();
+    assert_eq!(ledger.num_blocks().unwrap(), NUM_BLOCKS);
+    for block_index in 0..NUM_BLOCKS {
+        assert_eq!(
+            ledger.get_block(block_index).unwrap(),
+            origin.get_block(block_index).unwrap()
+        );
+        assert_eq!(
+            ledger.get_block_contents(block_index).unwrap(),
+            origin.get_block_contents(block_index).unwrap()
+        );
+    }
+
+    // The monitor has one utxo per block it has processed, so every processed
+    // block was committed in full.
+    let mobilecoind_db = Database::new(&mobilecoind_db_path, logger).unwrap();
+    let monitors = mobilecoind_db.get_monitor_map().unwrap();
+    assert_eq!(monitors.len(), 1);
+    for (monitor_id, monitor_data) in monitors {
+        assert!(monitor_data.next_block <= NUM_BLOCKS);
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(utxos.len() as u64, monitor_data.next_block);
+    }
+}
@@ -10,7 +10,7 @@ use crate::{
 use core::fmt::{Display, Formatter, Result as FmtResult};
 use grpcio::{ChannelBuilder, Environment, Error as GrpcError};
 use mc_attest_api::attest_grpc::AttestedApiClient;
-use mc_attest_core::VerificationReport;
+use mc_attest_core::{VerificationReport, Verifier};
 use mc_attest_enclave_api::PeerSession;
 use mc_common::{
     logger::{log, o, Logger},
@@ -73,14 +73,22 @@ pub struct PeerConnection<Enclave: ConsensusEnclave + Clone + Send + Sync> {
 
     consensus_api_client: ConsensusPeerApiClient,
     blockchain_api_client: BlockchainApiClient,
+
+    /// An optional operator policy the remote enclave's verification report
+    /// must also satisfy, on top of the local enclave's own checks.
+    verifier: Option<Verifier>,
 }
 
 impl<Enclave: ConsensusEnclave + Clone + Send + Sync> PeerConnection<Enclave> {
     /// Construct a new PeerConnection, optionally with TLS enabled.
+    ///
+    /// If a verifier is given, peers whose verification reports it rejects
+    /// are not attested.
     pub fn new(
         enclave: Enclave,
         local_node_id: NodeID,
         uri: PeerUri,
+        verifier: Option<Verifier>,
         env: Arc<Environment>,
         logger: Logger,
     ) -> Self {
@@ -110,6 +118,7 @@ impl<Enclave: ConsensusEnclave + Clone + Send + Sync> PeerConnection<Enclave> {
             attested_api_client,
             consensus_api_client,
             blockchain_api_client,
+            verifier,
         }
     }
 
@@ -188,6 +197,13 @@ impl<Enclave: ConsensusEnclave + Clone + Send + Sync> AttestedConnection
         let (peer_session, verification_report) = self
             .enclave
             .peer_connect(&self.remote_responder_id(), res.into())?;
+        if let Some(verifier) = &self.verifier {
+            if let Err(err) = verifier.verify(&verification_report) {
+                // Don't leave the rejected session established in the enclave.
+                let _ = self.enclave.peer_close(&peer_session);
+                return Err(err.into());
+            }
+        }
         self.channel_id = Some(peer_session);
 
         Ok(verification_report)
@@ -5,6 +5,7 @@
 use crate::ConsensusMsgError;
 use failure::Fail;
 use grpcio::Error as GrpcError;
+use mc_attest_core::VerifierError;
 use mc_connection::AttestationError;
 use mc_consensus_api::ConversionError;
 use mc_consensus_enclave_api::Error as EnclaveError;
@@ -149,6 +150,8 @@ pub enum PeerAttestationError {
     Grpc(GrpcError),
     #[fail(display = "Local enclave failure during attestation: {}", _0)]
     Enclave(EnclaveError),
+    #[fail(display = "Peer rejected by attestation policy: {}", _0)]
+    Policy(VerifierError),
 }
 
 impl From<GrpcError> for PeerAttestationError {
@@ -163,4 +166,10 @@ impl From<EnclaveError> for PeerAttestationError {
     }
 }
 
+impl From<VerifierError> for PeerAttestationError {
+    fn from(src: VerifierError) -> Self {
+        PeerAttestationError::Policy(src)
+    }
+}
+
 impl AttestationError for PeerAttestationError {}
@@ -11,7 +11,7 @@ use crate::{
 };
 use mc_common::{
     logger::{log, o, Logger},
-    Hash, LruCache, NodeID, ResponderId,
+    Hash, HashSet, LruCache, NodeID, ResponderId,
 };
 use mc_connection::{Connection, ConnectionManager, SyncConnection};
 use mc_consensus_api::consensus_peer::ConsensusMsgResult;
@@ -64,25 +64,9 @@ impl<RP: RetryPolicy> ThreadedBroadcaster<RP> {
         retry_policy: &RP,
         logger: Logger,
     ) -> Self {
-        let peer_threads: Vec<PeerThread> = manager
-            .conns()
+        let peer_threads: Vec<PeerThread> = Self::broadcast_conns(manager)
             .into_iter()
-            .filter(|conn| {
-                conn.uri()
-                    .get_param("broadcast-consensus-msgs")
-                    .unwrap_or_else(|| "1".to_string())
-                    == "1"
-            })
-            .map(|conn| {
-                let peer_name = conn.to_string();
-                PeerThread::new(
-                    conn,
-                    retry_policy,
-                    logger.new(o!(
-                        "mc.peers.peer_name" => peer_name,
-                    )),
-                )
-            })
+            .map(|conn| PeerThread::new(conn, retry_policy, logger.clone()))
             .collect();
         Self {
             peer_threads,
@@ -99,6 +83,64 @@ impl<RP: RetryPolicy> ThreadedBroadcaster<RP> {
         }
     }
 
+    /// Update the list of peers to broadcast to.
+    ///
+    /// Threads of peers that are no longer in `manager` (or whose URI has
+    /// changed) are stopped, and threads are started for new peers. Threads of
+    /// unchanged peers are left untouched.
+    pub fn set_peers<CC: ConsensusConnection + 'static>(
+        &mut self,
+        manager: &ConnectionManager<CC>,
+    ) {
+        let conns = Self::broadcast_conns(manager);
+        let uris: HashSet<String> = conns.iter().map(|conn| conn.uri().to_string()).collect();
+
+        let (kept, removed): (Vec<PeerThread>, Vec<PeerThread>) = self
+            .peer_threads
+            .drain(..)
+            .partition(|peer_thread| uris.contains(peer_thread.uri()));
+        for mut peer_thread in removed {
+            log::info!(
+                self.logger,
+                "Stopping broadcast to {}",
+                peer_thread.responder_id()
+            );
+            peer_thread.stop();
+        }
+
+        let kept_uris: HashSet<String> = kept
+            .iter()
+            .map(|peer_thread| peer_thread.uri().to_string())
+            .collect();
+        self.peer_threads = kept;
+        for conn in conns {
+            if !kept_uris.contains(&conn.uri().to_string()) {
+                log::info!(self.logger, "Starting broadcast to {}", conn);
+                self.peer_threads.push(PeerThread::new(
+                    conn,
+                    &self.retry_policy,
+                    self.logger.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Connections we should broadcast consensus messages to.
+    fn broadcast_conns<CC: ConsensusConnection + 'static>(
+        manager: &ConnectionManager<CC>,
+    ) -> Vec<SyncConnection<CC>> {
+        manager
+            .conns()
+            .into_iter()
+            .filter(|conn| {
+                conn.uri()
+                    .get_param("broadcast-consensus-msgs")
+                    .unwrap_or_else(|| "1".to_string())
+                    == "1"
+            })
+            .collect()
+    }
+
     /// Broadcasts a propose transaction message.
     ///
     /// # Arguments
@@ -256,6 +298,7 @@ enum ThreadMsg {
 /// A single peer thread.
 struct PeerThread {
     responder_id: ResponderId,
+    uri: String,
     sender: crossbeam_channel::Sender<ThreadMsg>,
     join_handle: Option<thread::JoinHandle<()>>,
 }
@@ -269,6 +312,8 @@ impl PeerThread {
         let (sender, receiver) = crossbeam_channel::unbounded();
 
         let responder_id = conn.remote_responder_id();
+        let uri = conn.uri().to_string();
+        let logger = logger.new(o!("mc.peers.peer_name" => conn.to_string()));
 
         let retry_policy = retry_policy.clone();
 
@@ -282,6 +327,7 @@ impl PeerThread {
         );
         Self {
             responder_id,
+            uri,
             sender,
             join_handle,
         }
@@ -291,6 +337,10 @@ impl PeerThread {
         &self.responder_id
     }
 
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
     pub fn send_consensus_msg(
         &self,
         msg: Arc<ConsensusMsg>,
@@ -405,6 +455,16 @@ impl PeerThread {
                     "Peer {}: does not accept broadcast messages from unknown peers",
                     conn
                 ),
+                ConsensusMsgResult::RateLimited => log::debug!(
+                    logger,
+                    "Peer {}: dropped message, we exceeded its rate limit",
+                    conn
+                ),
+                ConsensusMsgResult::Banned => log::warn!(
+                    logger,
+                    "Peer {}: dropped message, we are temporarily banned",
+                    conn
+                ),
             },
             Err(err) => {
                 log::error!(
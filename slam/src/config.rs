@@ -2,6 +2,7 @@
 
 //! Configuration parameters for the slam script
 
+use crate::scenario::Scenario;
 use grpcio::EnvBuilder;
 use mc_attest_core::{MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
 use mc_common::logger::{o, Logger};
@@ -24,7 +25,7 @@ pub struct SlamConfig {
     #[structopt(long, default_value = "-1")]
     pub num_tx_to_send: isize,
 
-    /// Number of inputs in the ring
+    /// Number of inputs in the ring. Consensus only accepts rings of 11.
     #[structopt(long, default_value = "11")]
     pub ring_size: usize,
 
@@ -71,11 +72,31 @@ pub struct SlamConfig {
     #[structopt(long = "tx-source-url")]
     pub tx_source_urls: Vec<String>,
 
+    /// Load testing scenario (TOML) describing arrival rates, transaction
+    /// shapes, fees and invalid transactions. Without one, every worker
+    /// submits transactions as fast as it can, using --num-inputs and
+    /// --ring-size. Requires --with-ledger-sync.
+    #[structopt(long, parse(try_from_str = Scenario::load))]
+    pub scenario: Option<Scenario>,
+
+    /// Where to write the JSON summary once the scenario ends. Defaults to
+    /// stdout.
+    #[structopt(long, parse(from_os_str))]
+    pub summary_file: Option<PathBuf>,
+
     #[structopt(flatten)]
     pub peers_config: PeersConfig,
 }
 
 impl SlamConfig {
+    /// The scenario to run, either loaded from --scenario or built from the
+    /// command line.
+    pub fn scenario(&self) -> Scenario {
+        self.scenario
+            .clone()
+            .unwrap_or_else(|| Scenario::unthrottled(self.num_inputs, self.ring_size))
+    }
+
     pub fn get_connections(
         &self,
         logger: &Logger,
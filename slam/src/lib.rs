@@ -1,5 +1,11 @@
 // Copyright (c) 2018-2021 The MobileCoin Foundation
 
 pub mod config;
+pub mod scenario;
+pub mod stats;
 
-pub use crate::config::SlamConfig;
+pub use crate::{
+    config::SlamConfig,
+    scenario::Scenario,
+    stats::{LoadStats, Summary, TxKind},
+};
@@ -17,7 +17,7 @@ use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
 use mc_fog_report_validation::FogResolver;
 use mc_ledger_db::{Ledger, LedgerDB};
 use mc_ledger_sync::{LedgerSyncServiceThread, PollingNetworkState, ReqwestTransactionsFetcher};
-use mc_slam::SlamConfig;
+use mc_slam::{LoadStats, Scenario, SlamConfig, Summary, TxKind};
 use mc_transaction_core::{
     constants::MILLIMOB_TO_PICOMOB,
     get_tx_out_shared_secret,
@@ -30,6 +30,7 @@ use mc_util_uri::ConnectionUri;
 use rand::{seq::SliceRandom, thread_rng, Rng};
 use rayon::prelude::*;
 use std::{
+    fs,
     iter::empty,
     path::Path,
     sync::{
@@ -37,7 +38,7 @@ use std::{
         Arc, Mutex, RwLock,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use structopt::StructOpt;
 use tempdir::TempDir;
@@ -48,6 +49,12 @@ thread_local! {
 
 const FALLBACK_FEE: u64 = 10 * MILLIMOB_TO_PICOMOB;
 
+/// How many times to try submitting a transaction which should be accepted.
+const MAX_RETRIES: usize = 10;
+
+/// How often to check whether the scenario calls for another transaction.
+const PACER_TICK: Duration = Duration::from_millis(10);
+
 fn set_conns(config: &SlamConfig, logger: &Logger) {
     let conns = config.get_connections(logger).unwrap();
     CONNS.with(|c| *c.borrow_mut() = Some(conns));
@@ -72,6 +79,8 @@ lazy_static! {
 
     pub static ref FEE: AtomicU64 = AtomicU64::default();
 
+    pub static ref STATS: LoadStats = LoadStats::default();
+
     // A map of tx pub keys to account index. This is used in conjunction with ledger syncing to
     // identify which new txs belong to which accounts without having to do any slow crypto.
     pub static ref TX_PUB_KEY_TO_ACCOUNT_KEY: Mutex<HashMap::<CompressedRistrettoPublic, AccountKey>> = Mutex::new(HashMap::default());
@@ -99,19 +108,40 @@ fn main() {
     let (logger, _global_logger_guard) = create_app_logger(o!());
 
     let config = SlamConfig::from_args();
+    let scenario = config.scenario();
+    if let Err(err) = scenario.validate() {
+        panic!("Invalid scenario: {}", err);
+    }
+    if config.scenario.is_some() && !config.with_ledger_sync {
+        panic!("--scenario requires --with-ledger-sync, to see when transactions reach a block");
+    }
 
-    // Read account root_entropies from disk
-    let accounts: Vec<AccountKey> = mc_util_keyfile::keygen::read_default_root_entropies(
-        config.sample_data_dir.join(Path::new("keys")),
-    )
-    .expect("Could not read default root entropies from keys")
-    .iter()
-    .map(|x| {
-        let mut root_id = x.clone();
-        root_id.fog_report_url = Default::default();
-        AccountKey::from(&root_id)
-    })
-    .collect();
+    // Read account root_entropies from disk, asking for a passphrase if any of
+    // the keyfiles are encrypted
+    let keys_dir = config.sample_data_dir.join(Path::new("keys"));
+    let passphrase = if mc_util_keyfile::keygen::default_keyfiles_encrypted(&keys_dir)
+        .expect("Could not read keyfiles from keys")
+    {
+        Some(
+            mc_util_keyfile::passphrase::get_passphrase("Keyfile passphrase: ")
+                .expect("Could not get keyfile passphrase"),
+        )
+    } else {
+        None
+    };
+    let accounts: Vec<AccountKey> =
+        mc_util_keyfile::keygen::read_default_root_entropies_with_passphrase(
+            &keys_dir,
+            passphrase.as_ref().map(|p| p.as_bytes()),
+        )
+        .expect("Could not read default root entropies from keys")
+        .iter()
+        .map(|x| {
+            let mut root_id = x.clone();
+            root_id.fog_report_url = Default::default();
+            AccountKey::from(&root_id)
+        })
+        .collect();
 
     // Open the ledger_db to process the bootstrapped ledger
     log::info!(logger, "Loading ledger");
@@ -221,9 +251,19 @@ fn main() {
         block_count += 1;
     }
 
+    // Pace the workers, unless the scenario runs as fast as possible.
+    let (arrivals_sender, arrivals_receiver) = crossbeam_channel::bounded::<()>(config.max_threads);
+    let arrivals = if scenario.is_open_ended() {
+        None
+    } else {
+        Some(arrivals_receiver)
+    };
+
     // Spawn worker threads
     for i in 0..config.max_threads {
         let spendable_txouts_receiver2 = spendable_txouts_receiver.clone();
+        let spendable_txouts_sender2 = spendable_txouts_sender.clone();
+        let arrivals2 = arrivals.clone();
         let config2 = config.clone();
         let ledger_db2 = ledger_db.clone();
         let mut accounts2 = accounts.clone();
@@ -236,6 +276,8 @@ fn main() {
             .spawn(move || {
                 worker_thread_entry(
                     spendable_txouts_receiver2,
+                    spendable_txouts_sender2,
+                    arrivals2,
                     config2,
                     ledger_db2,
                     accounts2,
@@ -245,6 +287,29 @@ fn main() {
             .expect("failed starting thread");
     }
 
+    let start = Instant::now();
+    if arrivals.is_some() {
+        log::info!(
+            logger,
+            "Running scenario {:?} for {:?}",
+            scenario.name,
+            scenario.duration()
+        );
+        let scenario2 = scenario.clone();
+        thread::Builder::new()
+            .name("pacer".to_owned())
+            .spawn(move || pace_arrivals(&scenario2, arrivals_sender))
+            .expect("failed starting thread");
+    }
+    // Once the scenario ends, wait for submitted transactions to reach a block,
+    // then stop.
+    let scenario_end = if scenario.is_open_ended() {
+        None
+    } else {
+        Some(start + scenario.duration())
+    };
+    let drain_end = scenario_end.map(|end| end + Duration::from_secs(scenario.drain_secs));
+
     if config.with_ledger_sync {
         if config.tx_source_urls.is_empty() {
             panic!("--with-ledger-sync requires at least one --tx-source-url");
@@ -284,7 +349,7 @@ fn main() {
         )));
 
         let transactions_fetcher =
-            ReqwestTransactionsFetcher::new(config.tx_source_urls, logger.clone())
+            ReqwestTransactionsFetcher::new(config.tx_source_urls.clone(), logger.clone())
                 .expect("Failed creating ReqwestTransactionsFetcher");
 
         let mut next_block_idx = ledger_db.num_blocks().unwrap();
@@ -299,6 +364,15 @@ fn main() {
         );
 
         loop {
+            if let (Some(scenario_end), Some(drain_end)) = (scenario_end, drain_end) {
+                let now = Instant::now();
+                if now >= drain_end || (now >= scenario_end && STATS.num_pending() == 0) {
+                    let summary = STATS.summary(&scenario.name, start.elapsed());
+                    write_summary(&config, &summary);
+                    return;
+                }
+            }
+
             let block_contents = match ledger_db.get_block_contents(next_block_idx) {
                 Ok(contents) => contents,
                 Err(_) => {
@@ -311,7 +385,9 @@ fn main() {
             log::debug!(logger, "Synced block #{}", next_block_idx);
             next_block_idx += 1;
 
+            let seen_at = Instant::now();
             for tx_out in block_contents.outputs {
+                STATS.record_output(&tx_out.public_key, seen_at);
                 if let Some(account) = TX_PUB_KEY_TO_ACCOUNT_KEY
                     .lock()
                     .unwrap()
@@ -359,26 +435,55 @@ fn main() {
     }
 }
 
+/// The shape of a transaction to build.
+struct TxParams {
+    ring_size: usize,
+    fee: u64,
+    tombstone_block: u64,
+}
+
+/// Write the summary of a scenario run to the summary file, or stdout.
+fn write_summary(config: &SlamConfig, summary: &Summary) {
+    let json = serde_json::to_string_pretty(summary).expect("failed serializing summary");
+    match &config.summary_file {
+        Some(path) => fs::write(path, json).expect("failed writing summary"),
+        None => println!("{}", json),
+    }
+}
+
 fn worker_thread_entry(
     spendable_txouts_receiver: crossbeam_channel::Receiver<SpendableTxOut>,
+    spendable_txouts_sender: crossbeam_channel::Sender<SpendableTxOut>,
+    arrivals: Option<crossbeam_channel::Receiver<()>>,
     config: SlamConfig,
     ledger_db: LedgerDB,
     accounts: Vec<AccountKey>,
     logger: Logger,
 ) {
     log::info!(logger, "Worker started.");
+    let scenario = config.scenario();
     let mut txs_created: usize = 0;
+    let mut rng = thread_rng();
 
     let mut conns = get_conns(&config, &logger);
-    conns.shuffle(&mut thread_rng());
+    conns.shuffle(&mut rng);
 
     loop {
+        // Wait until the scenario calls for another transaction.
+        if let Some(arrivals) = &arrivals {
+            if arrivals.recv().is_err() {
+                log::info!(logger, "Scenario finished, worker exiting.");
+                return;
+            }
+        }
+
+        let num_inputs = scenario.inputs.sample(&mut rng) as usize;
         let mut pending_spendable_txouts = Vec::<SpendableTxOut>::new();
-        while pending_spendable_txouts.len() < config.num_inputs {
+        while pending_spendable_txouts.len() < num_inputs {
             log::trace!(
                 logger,
                 "Waiting for {} more inputs",
-                config.num_inputs - pending_spendable_txouts.len()
+                num_inputs - pending_spendable_txouts.len()
             );
             pending_spendable_txouts.push(
                 spendable_txouts_receiver
@@ -387,36 +492,135 @@ fn worker_thread_entry(
             );
         }
 
-        // Select a random account to send to
-        let to_account = &accounts[txs_created % accounts.len()];
+        let invalid = rng.gen_bool(scenario.invalid_fraction);
+        let block_height = BLOCK_HEIGHT.load(Ordering::SeqCst);
+        let params = TxParams {
+            ring_size: scenario.ring_size.sample(&mut rng) as usize,
+            fee: scenario
+                .fee
+                .as_ref()
+                .map(|fee| fee.sample(&mut rng))
+                .unwrap_or_else(|| FEE.load(Ordering::SeqCst)),
+            // An invalid transaction's tombstone block has already passed.
+            tombstone_block: if invalid {
+                block_height
+            } else {
+                block_height + config.tombstone_block
+            },
+        };
+
+        // Send to the next few accounts, one per output.
+        let num_outputs = scenario.outputs.sample(&mut rng) as usize;
+        let recipients: Vec<AccountKey> = (0..num_outputs)
+            .map(|i| accounts[(txs_created + i) % accounts.len()].clone())
+            .collect();
 
         // Got our inputs, construct transaction.
-        let tx = build_tx(
+        let (tx, outputs) = match build_tx(
             &pending_spendable_txouts,
-            to_account,
-            &config,
+            &recipients,
+            &params,
             &ledger_db,
             &logger,
-        );
+        ) {
+            Ok(built) => built,
+            Err(err) => {
+                log::warn!(logger, "{}, dropping the inputs", err);
+                continue;
+            }
+        };
 
         txs_created += 1;
 
+        if invalid {
+            // Rejection is expected, so don't retry.
+            let proposed_at = submit_tx(txs_created, &conns, &tx, 1, &config, &logger);
+            STATS.record_proposal(TxKind::Invalid, &tx, proposed_at);
+            if proposed_at.is_some() {
+                log::error!(logger, "Invalid transaction {} was accepted", txs_created);
+                track_outputs(outputs);
+            } else {
+                // The inputs were not spent, so they can be used again.
+                for utxo in pending_spendable_txouts {
+                    spendable_txouts_sender
+                        .send(utxo)
+                        .expect("failed sending to spendable_txouts_sender");
+                }
+            }
+            continue;
+        }
+
         // Submit tx
-        if submit_tx(txs_created, &conns, &tx, &config, &logger) {
-            let mut map = TX_PUB_KEY_TO_ACCOUNT_KEY.lock().unwrap();
-            map.insert(tx.prefix.outputs[0].public_key, to_account.clone());
+        let proposed_at = submit_tx(txs_created, &conns, &tx, MAX_RETRIES, &config, &logger);
+        STATS.record_proposal(TxKind::Valid, &tx, proposed_at);
+        if proposed_at.is_none() {
+            continue;
         }
+        track_outputs(outputs);
+
+        if rng.gen_bool(scenario.double_spend_fraction) {
+            // Spend the same inputs again, and submit to the next node.
+            let recipients: Vec<AccountKey> = recipients
+                .iter()
+                .map(|_| accounts.choose(&mut rng).unwrap().clone())
+                .collect();
+            if let Some((double_spend, outputs)) = build_tx(
+                &pending_spendable_txouts,
+                &recipients,
+                &params,
+                &ledger_db,
+                &logger,
+            ) {
+                let proposed_at =
+                    submit_tx(txs_created + 1, &conns, &double_spend, 1, &config, &logger);
+                STATS.record_proposal(TxKind::DoubleSpend, &double_spend, proposed_at);
+                if proposed_at.is_some() {
+                    track_outputs(outputs);
+                }
+            }
+        }
+    }
+}
+
+/// Remember which account owns each output of a submitted transaction, so that
+/// it can be spent again once it reaches a block.
+fn track_outputs(outputs: Vec<(CompressedRistrettoPublic, AccountKey)>) {
+    let mut map = TX_PUB_KEY_TO_ACCOUNT_KEY.lock().unwrap();
+    map.extend(outputs);
+}
+
+/// Issue an arrival to the workers each time the scenario's arrival rate calls
+/// for another transaction, until the last phase ends.
+fn pace_arrivals(scenario: &Scenario, arrivals: crossbeam_channel::Sender<()>) {
+    let start = Instant::now();
+    let mut last_tick = start;
+    let mut due = 0.0;
+    while let Some(rate) = scenario.rate_at(start.elapsed()) {
+        let now = Instant::now();
+        due += rate * (now - last_tick).as_secs_f64();
+        last_tick = now;
+        while due >= 1.0 {
+            due -= 1.0;
+            if arrivals.try_send(()).is_err() {
+                STATS.record_missed_arrival();
+            }
+        }
+        thread::sleep(PACER_TICK);
     }
 }
 
+/// Submit a transaction, trying each node in turn.
+///
+/// Returns the time of the successful `propose_tx` call, or None if no node
+/// accepted the transaction.
 fn submit_tx(
     counter: usize,
     conns: &[SyncConnection<ThickClient<HardcodedCredentialsProvider>>],
     tx: &Tx,
+    max_retries: usize,
     config: &SlamConfig,
     logger: &Logger,
-) -> bool {
-    let max_retries = 10;
+) -> Option<Instant> {
     let retry_sleep_duration = Duration::from_millis(300);
 
     for i in 0..max_retries {
@@ -432,6 +636,7 @@ fn submit_tx(
             max_retries
         );
         thread::sleep(Duration::from_millis(config.add_tx_delay_ms));
+        let proposed_at = Instant::now();
         match conn.propose_tx(&tx, empty()) {
             Ok(block_height) => {
                 log::debug!(
@@ -444,7 +649,7 @@ fn submit_tx(
                 );
 
                 BLOCK_HEIGHT.fetch_max(block_height, Ordering::SeqCst);
-                return true;
+                return Some(proposed_at);
             }
             Err(RetryError::Operation { error, .. }) => {
                 log::warn!(
@@ -468,7 +673,7 @@ fn submit_tx(
                     max_retries,
                     s
                 );
-                return false;
+                return None;
             }
         }
     }
@@ -478,18 +683,30 @@ fn submit_tx(
         counter,
         max_retries
     );
-    false
+    None
 }
 
+/// Build a transaction spending `spendable_txouts`, splitting their value,
+/// less the fee, between `recipients`.
+///
+/// Returns the transaction and the owner of each of its outputs, or an error
+/// if the inputs do not cover the fee or the ledger is too small to sample
+/// the rings from.
 fn build_tx(
     spendable_txouts: &[SpendableTxOut],
-    to_account: &AccountKey,
-    config: &SlamConfig,
+    recipients: &[AccountKey],
+    params: &TxParams,
     ledger_db: &LedgerDB,
     logger: &Logger,
-) -> Tx {
+) -> Result<(Tx, Vec<(CompressedRistrettoPublic, AccountKey)>), String> {
+    let total_amount: u64 = spendable_txouts.iter().map(|utxo| utxo.amount).sum();
+    let output_amount = total_amount
+        .checked_sub(params.fee)
+        .filter(|amount| *amount > 0)
+        .ok_or_else(|| format!("Inputs do not cover a fee of {}", params.fee))?;
+
+    let rings = get_rings(ledger_db, params.ring_size, spendable_txouts.len())?;
     let utxos_with_proofs = get_membership_proofs(ledger_db, spendable_txouts);
-    let rings = get_rings(ledger_db, config.ring_size, utxos_with_proofs.len());
 
     let mut rng = rand::thread_rng();
 
@@ -499,7 +716,7 @@ fn build_tx(
     // Create tx_builder. No fog reports.
     let mut tx_builder = TransactionBuilder::new(FogResolver::default());
 
-    tx_builder.set_fee(FEE.load(Ordering::SeqCst));
+    tx_builder.set_fee(params.fee);
 
     // Unzip each vec of tuples into a tuple of vecs.
     let mut rings_and_proofs: Vec<(Vec<TxOut>, Vec<TxOutMembershipProof>)> = rings
@@ -508,7 +725,7 @@ fn build_tx(
         .collect();
 
     // Add inputs to the tx.
-    for (utxo, proof) in utxos_with_proofs.clone() {
+    for (utxo, proof) in utxos_with_proofs {
         let (mut ring, mut membership_proofs) = rings_and_proofs.pop().unwrap();
         assert_eq!(
             ring.len(),
@@ -576,25 +793,31 @@ fn build_tx(
         );
     }
 
-    // Add ouputs
-    for (i, (utxo, _proof)) in utxos_with_proofs.iter().enumerate() {
-        let mut amount = utxo.amount;
-        // Use the first input to pay for the fee.
-        if i == 0 {
-            amount -= FEE.load(Ordering::SeqCst);
-        }
+    // Add outputs, each paid an equal share of the amount. Don't create
+    // zero-value outputs.
+    let recipients = &recipients[..recipients.len().min(output_amount as usize)];
+    let share = output_amount / recipients.len() as u64;
+    let mut outputs = Vec::new();
+    for (i, recipient) in recipients.iter().enumerate() {
+        // The first output gets whatever doesn't divide evenly.
+        let amount = if i == 0 {
+            output_amount - share * (recipients.len() as u64 - 1)
+        } else {
+            share
+        };
 
-        tx_builder
-            .add_output(amount, &to_account.default_subaddress(), &mut rng)
+        let (tx_out, _confirmation) = tx_builder
+            .add_output(amount, &recipient.default_subaddress(), &mut rng)
             .expect("failed to add output");
+        outputs.push((tx_out.public_key, recipient.clone()));
     }
 
     // Set tombstone block.
-    let tombstone_block = BLOCK_HEIGHT.load(Ordering::SeqCst) + config.tombstone_block;
-    tx_builder.set_tombstone_block(tombstone_block);
+    tx_builder.set_tombstone_block(params.tombstone_block);
 
     // Build and return tx.
-    tx_builder.build(&mut rng).expect("failed building tx")
+    let tx = tx_builder.build(&mut rng).expect("failed building tx");
+    Ok((tx, outputs))
 }
 
 fn get_membership_proofs(
@@ -614,13 +837,21 @@ fn get_membership_proofs(
     utxos.iter().cloned().zip(proofs.into_iter()).collect()
 }
 
+/// Sample `num_rings` rings of `ring_size` distinct TxOuts from the ledger, or
+/// return an error if the ledger does not have that many TxOuts.
 fn get_rings(
     ledger_db: &LedgerDB,
     ring_size: usize,
     num_rings: usize,
-) -> Vec<Vec<(TxOut, TxOutMembershipProof)>> {
+) -> Result<Vec<Vec<(TxOut, TxOutMembershipProof)>>, String> {
     let num_requested = ring_size * num_rings;
     let num_txos = ledger_db.num_txos().unwrap();
+    if num_requested as u64 > num_txos {
+        return Err(format!(
+            "Cannot sample {} rings of size {} from a ledger of {} TxOuts",
+            num_rings, ring_size, num_txos
+        ));
+    }
 
     // Randomly sample `num_requested` TxOuts, without replacement and convert into
     // a Vec<u64>
@@ -654,7 +885,7 @@ fn get_rings(
         rings_with_proofs.push(ring);
     }
 
-    rings_with_proofs
+    Ok(rings_with_proofs)
 }
 
 fn get_num_transactions_per_account(
@@ -0,0 +1,419 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Load testing scenarios, describing the shape of the transactions slam
+//! submits and how quickly it submits them.
+
+use mc_transaction_core::constants::RING_SIZE;
+use rand::{distributions::WeightedIndex, prelude::Distribution as _, Rng};
+use serde::{Deserialize, Serialize};
+use std::{fs, time::Duration};
+
+/// A load testing scenario, usually loaded from a TOML file.
+///
+/// For example:
+///
+/// ```toml
+/// name = "ramp"
+/// inputs = { min = 1, max = 3 }
+/// outputs = 2
+/// ring_size = 11
+/// fee = { values = [10000000000, 20000000000], weights = [9, 1] }
+/// invalid_fraction = 0.05
+/// double_spend_fraction = 0.01
+///
+/// [[phases]]
+/// duration_secs = 60
+/// rate = 1.0
+/// end_rate = 20.0
+///
+/// [[phases]]
+/// duration_secs = 300
+/// rate = 20.0
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Scenario {
+    /// A name for the scenario, copied into the summary.
+    #[serde(default)]
+    pub name: String,
+
+    /// The arrival rate of transactions over time. Without any phases,
+    /// transactions are submitted as fast as the workers can build them, until
+    /// slam is stopped.
+    #[serde(default)]
+    pub phases: Vec<Phase>,
+
+    /// The number of inputs of each transaction.
+    #[serde(default = "default_inputs")]
+    pub inputs: Distribution,
+
+    /// The number of outputs of each transaction, each to a different account.
+    #[serde(default = "default_outputs")]
+    pub outputs: Distribution,
+
+    /// The ring size of each input. Consensus only accepts rings of exactly
+    /// `RING_SIZE`, so this may not take any other value.
+    #[serde(default = "default_ring_size")]
+    pub ring_size: Distribution,
+
+    /// The fee of each transaction, in picoMOB. Defaults to the largest
+    /// minimum fee of the consensus nodes.
+    #[serde(default)]
+    pub fee: Option<Distribution>,
+
+    /// The fraction of transactions which are made invalid by giving them a
+    /// tombstone block which has already passed. Their inputs are reused.
+    #[serde(default)]
+    pub invalid_fraction: f64,
+
+    /// The fraction of accepted transactions which are followed by a second
+    /// transaction spending the same inputs, submitted to a different node.
+    #[serde(default)]
+    pub double_spend_fraction: f64,
+
+    /// How long to wait for submitted transactions to reach a block after the
+    /// last phase ends, in seconds.
+    #[serde(default = "default_drain_secs")]
+    pub drain_secs: u64,
+}
+
+fn default_inputs() -> Distribution {
+    Distribution::Fixed(1)
+}
+
+fn default_outputs() -> Distribution {
+    Distribution::Fixed(1)
+}
+
+fn default_ring_size() -> Distribution {
+    Distribution::Fixed(RING_SIZE as u64)
+}
+
+fn default_drain_secs() -> u64 {
+    60
+}
+
+impl Scenario {
+    /// A scenario which submits transactions with a fixed number of inputs
+    /// and ring size as fast as possible, forever.
+    pub fn unthrottled(num_inputs: usize, ring_size: usize) -> Self {
+        Self {
+            name: String::new(),
+            phases: Vec::new(),
+            inputs: Distribution::Fixed(num_inputs as u64),
+            outputs: Distribution::Fixed(num_inputs as u64),
+            ring_size: Distribution::Fixed(ring_size as u64),
+            fee: None,
+            invalid_fraction: 0.0,
+            double_spend_fraction: 0.0,
+            drain_secs: default_drain_secs(),
+        }
+    }
+
+    /// Load and validate a scenario from a TOML file.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data =
+            fs::read_to_string(path).map_err(|err| format!("Failed reading {}: {}", path, err))?;
+        let scenario: Self =
+            toml::from_str(&data).map_err(|err| format!("Failed parsing {}: {}", path, err))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Check that every distribution can be sampled, and every fraction and
+    /// rate is in range.
+    pub fn validate(&self) -> Result<(), String> {
+        self.inputs.validate("inputs", 1)?;
+        self.outputs.validate("outputs", 1)?;
+        self.ring_size.validate("ring_size", 1)?;
+        if !self.ring_size.is_always(RING_SIZE as u64) {
+            return Err(format!(
+                "ring_size must be {}, the only ring size consensus accepts",
+                RING_SIZE
+            ));
+        }
+        if let Some(fee) = &self.fee {
+            fee.validate("fee", 0)?;
+        }
+        for (name, fraction) in [
+            ("invalid_fraction", self.invalid_fraction),
+            ("double_spend_fraction", self.double_spend_fraction),
+        ]
+        .iter()
+        {
+            if !(0.0..=1.0).contains(fraction) {
+                return Err(format!("{} must be between 0 and 1", name));
+            }
+        }
+        for (index, phase) in self.phases.iter().enumerate() {
+            if phase.duration_secs == 0 {
+                return Err(format!("phase {} has no duration", index));
+            }
+            if !(phase.rate >= 0.0 && phase.end_rate() >= 0.0) {
+                return Err(format!("phase {} has a negative rate", index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the scenario runs until slam is stopped.
+    pub fn is_open_ended(&self) -> bool {
+        self.phases.is_empty()
+    }
+
+    /// The total length of every phase.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.phases.iter().map(|phase| phase.duration_secs).sum())
+    }
+
+    /// The arrival rate, in transactions per second, `elapsed` after the
+    /// scenario started, or None once the last phase has ended.
+    pub fn rate_at(&self, elapsed: Duration) -> Option<f64> {
+        let mut phase_start = Duration::default();
+        for phase in self.phases.iter() {
+            let phase_duration = Duration::from_secs(phase.duration_secs);
+            if elapsed < phase_start + phase_duration {
+                let progress = (elapsed - phase_start).as_secs_f64() / phase_duration.as_secs_f64();
+                return Some(phase.rate + (phase.end_rate() - phase.rate) * progress);
+            }
+            phase_start += phase_duration;
+        }
+        None
+    }
+}
+
+/// A period of the scenario during which the arrival rate ramps linearly from
+/// `rate` to `end_rate`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Phase {
+    /// Length of the phase, in seconds.
+    pub duration_secs: u64,
+
+    /// Transactions per second at the start of the phase.
+    pub rate: f64,
+
+    /// Transactions per second at the end of the phase. Defaults to `rate`.
+    #[serde(default)]
+    pub end_rate: Option<f64>,
+}
+
+impl Phase {
+    /// Transactions per second at the end of the phase.
+    pub fn end_rate(&self) -> f64 {
+        self.end_rate.unwrap_or(self.rate)
+    }
+}
+
+/// A distribution of integer values. In TOML, this is either a single value,
+/// a `{ min, max }` table for a uniform distribution over an inclusive range,
+/// or a `{ values, weights }` table for a weighted choice.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Distribution {
+    /// Always the same value.
+    Fixed(u64),
+
+    /// Uniformly distributed between `min` and `max`, inclusive.
+    Uniform {
+        /// The smallest value.
+        min: u64,
+        /// The largest value.
+        max: u64,
+    },
+
+    /// One of `values`, chosen with probability proportional to the
+    /// corresponding entry of `weights`.
+    Weighted {
+        /// The possible values.
+        values: Vec<u64>,
+        /// The relative weight of each value.
+        weights: Vec<u32>,
+    },
+}
+
+impl Distribution {
+    /// Draw a value.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> u64 {
+        match self {
+            Self::Fixed(value) => *value,
+            Self::Uniform { min, max } => rng.gen_range(*min..=*max),
+            Self::Weighted { values, weights } => {
+                let index = WeightedIndex::new(weights)
+                    .expect("Weights were validated when loaded")
+                    .sample(rng);
+                values[index]
+            }
+        }
+    }
+
+    /// Whether every value drawn is `value`.
+    fn is_always(&self, value: u64) -> bool {
+        match self {
+            Self::Fixed(fixed) => *fixed == value,
+            Self::Uniform { min, max } => *min == value && *max == value,
+            Self::Weighted { values, .. } => values.iter().all(|v| *v == value),
+        }
+    }
+
+    fn validate(&self, name: &str, min_value: u64) -> Result<(), String> {
+        let smallest = match self {
+            Self::Fixed(value) => *value,
+            Self::Uniform { min, max } => {
+                if min > max {
+                    return Err(format!("{} has min greater than max", name));
+                }
+                *min
+            }
+            Self::Weighted { values, weights } => {
+                if values.len() != weights.len() {
+                    return Err(format!(
+                        "{} has {} values but {} weights",
+                        name,
+                        values.len(),
+                        weights.len()
+                    ));
+                }
+                WeightedIndex::new(weights)
+                    .map_err(|err| format!("{} has invalid weights: {}", name, err))?;
+                values.iter().copied().min().unwrap_or_default()
+            }
+        };
+        if smallest < min_value {
+            return Err(format!("{} must be at least {}", name, min_value));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn parse_scenario() {
+        let input_toml: &str = r#"
+            name = "ramp"
+            inputs = { min = 1, max = 3 }
+            outputs = 2
+            fee = { values = [10, 20], weights = [9, 1] }
+            invalid_fraction = 0.05
+
+            [[phases]]
+            duration_secs = 10
+            rate = 1.0
+            end_rate = 21.0
+
+            [[phases]]
+            duration_secs = 5
+            rate = 4.0
+        "#;
+        let scenario: Scenario = toml::from_str(input_toml).expect("failed parsing toml");
+        scenario.validate().unwrap();
+
+        assert_eq!(scenario.name, "ramp");
+        assert_eq!(scenario.inputs, Distribution::Uniform { min: 1, max: 3 });
+        assert_eq!(scenario.outputs, Distribution::Fixed(2));
+        assert_eq!(scenario.ring_size, Distribution::Fixed(11));
+        assert_eq!(
+            scenario.fee,
+            Some(Distribution::Weighted {
+                values: vec![10, 20],
+                weights: vec![9, 1]
+            })
+        );
+        assert_eq!(scenario.drain_secs, 60);
+        assert_eq!(scenario.duration(), Duration::from_secs(15));
+    }
+
+    #[test]
+    fn example_scenario_is_valid() {
+        let scenario: Scenario =
+            toml::from_str(include_str!("../scenarios/ramp.toml")).expect("failed parsing toml");
+        scenario.validate().unwrap();
+        assert_eq!(scenario.duration(), Duration::from_secs(360));
+    }
+
+    #[test]
+    fn rate_ramps_linearly() {
+        let scenario = Scenario {
+            phases: vec![
+                Phase {
+                    duration_secs: 10,
+                    rate: 1.0,
+                    end_rate: Some(21.0),
+                },
+                Phase {
+                    duration_secs: 5,
+                    rate: 4.0,
+                    end_rate: None,
+                },
+            ],
+            ..Scenario::unthrottled(1, 11)
+        };
+
+        assert_eq!(scenario.rate_at(Duration::from_secs(0)), Some(1.0));
+        assert_eq!(scenario.rate_at(Duration::from_secs(5)), Some(11.0));
+        assert_eq!(scenario.rate_at(Duration::from_secs(10)), Some(4.0));
+        assert_eq!(scenario.rate_at(Duration::from_secs(14)), Some(4.0));
+        assert_eq!(scenario.rate_at(Duration::from_secs(15)), None);
+        assert!(!scenario.is_open_ended());
+        assert!(Scenario::unthrottled(1, 11).is_open_ended());
+    }
+
+    #[test]
+    fn samples_stay_in_range() {
+        let mut rng: StdRng = SeedableRng::from_seed([7u8; 32]);
+        let uniform = Distribution::Uniform { min: 2, max: 4 };
+        let weighted = Distribution::Weighted {
+            values: vec![5, 6],
+            weights: vec![0, 1],
+        };
+        for _ in 0..100 {
+            assert!((2..=4).contains(&uniform.sample(&mut rng)));
+            assert_eq!(weighted.sample(&mut rng), 6);
+        }
+    }
+
+    #[test]
+    fn invalid_scenarios_are_rejected() {
+        let valid = Scenario::unthrottled(1, 11);
+        assert!(valid.validate().is_ok());
+
+        let no_inputs = Scenario {
+            inputs: Distribution::Uniform { min: 0, max: 2 },
+            ..valid.clone()
+        };
+        assert!(no_inputs.validate().is_err());
+
+        let mismatched_weights = Scenario {
+            fee: Some(Distribution::Weighted {
+                values: vec![1, 2],
+                weights: vec![1],
+            }),
+            ..valid.clone()
+        };
+        assert!(mismatched_weights.validate().is_err());
+
+        let bad_fraction = Scenario {
+            invalid_fraction: 1.5,
+            ..valid.clone()
+        };
+        assert!(bad_fraction.validate().is_err());
+
+        let small_rings = Scenario {
+            ring_size: Distribution::Uniform { min: 5, max: 11 },
+            ..valid.clone()
+        };
+        assert!(small_rings.validate().is_err());
+
+        let empty_phase = Scenario {
+            phases: vec![Phase {
+                duration_secs: 0,
+                rate: 1.0,
+                end_rate: None,
+            }],
+            ..valid
+        };
+        assert!(empty_phase.validate().is_err());
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Tracking of submitted transactions, from `propose_tx` to inclusion in a
+//! block, and the JSON summary of a run.
+
+use mc_common::HashMap;
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_transaction_core::tx::{Tx, TxHash};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The kinds of transaction slam submits.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxKind {
+    /// A transaction which should be accepted.
+    Valid,
+    /// A transaction which should be rejected.
+    Invalid,
+    /// A transaction spending the same inputs as a transaction which was
+    /// already accepted, which should never reach a block.
+    DoubleSpend,
+}
+
+#[derive(Default)]
+struct KindStats {
+    submitted: u64,
+    accepted: u64,
+    rejected: u64,
+    included: u64,
+    latencies_ms: Vec<u64>,
+}
+
+struct PendingTx {
+    kind: TxKind,
+    proposed_at: Instant,
+    outputs: Vec<CompressedRistrettoPublic>,
+}
+
+#[derive(Default)]
+struct Inner {
+    kinds: BTreeMap<TxKind, KindStats>,
+    outputs: HashMap<CompressedRistrettoPublic, TxHash>,
+    pending: HashMap<TxHash, PendingTx>,
+    missed_arrivals: u64,
+}
+
+/// Counts of submitted, accepted and included transactions of each kind,
+/// and their latencies.
+#[derive(Default)]
+pub struct LoadStats {
+    inner: Mutex<Inner>,
+}
+
+impl LoadStats {
+    /// Record the result of proposing a transaction.
+    ///
+    /// # Arguments
+    /// * `kind` - The kind of transaction.
+    /// * `tx` - The transaction.
+    /// * `proposed_at` - When the successful `propose_tx` call was made, or
+    ///   None if no node accepted the transaction.
+    pub fn record_proposal(&self, kind: TxKind, tx: &Tx, proposed_at: Option<Instant>) {
+        let mut inner = self.inner.lock().expect("mutex poisoned");
+        let stats = inner.kinds.entry(kind).or_default();
+        stats.submitted += 1;
+
+        let proposed_at = match proposed_at {
+            Some(proposed_at) => proposed_at,
+            None => {
+                stats.rejected += 1;
+                return;
+            }
+        };
+        stats.accepted += 1;
+
+        let tx_hash = tx.tx_hash();
+        let outputs: Vec<CompressedRistrettoPublic> = tx
+            .prefix
+            .outputs
+            .iter()
+            .map(|tx_out| tx_out.public_key)
+            .collect();
+        for public_key in outputs.iter() {
+            inner.outputs.insert(*public_key, tx_hash);
+        }
+        inner.pending.insert(
+            tx_hash,
+            PendingTx {
+                kind,
+                proposed_at,
+                outputs,
+            },
+        );
+    }
+
+    /// Record that an output appeared in a block. The first output of a
+    /// pending transaction to appear marks the whole transaction as included.
+    pub fn record_output(&self, public_key: &CompressedRistrettoPublic, seen_at: Instant) {
+        let mut inner = self.inner.lock().expect("mutex poisoned");
+        let tx_hash = match inner.outputs.remove(public_key) {
+            Some(tx_hash) => tx_hash,
+            None => return,
+        };
+        let pending_tx = match inner.pending.remove(&tx_hash) {
+            Some(pending_tx) => pending_tx,
+            None => return,
+        };
+        for output in pending_tx.outputs.iter() {
+            inner.outputs.remove(output);
+        }
+
+        let latency = seen_at.saturating_duration_since(pending_tx.proposed_at);
+        let stats = inner.kinds.entry(pending_tx.kind).or_default();
+        stats.included += 1;
+        stats.latencies_ms.push(latency.as_millis() as u64);
+    }
+
+    /// Record that a transaction was due to be submitted, but every worker
+    /// was busy.
+    pub fn record_missed_arrival(&self) {
+        self.inner.lock().expect("mutex poisoned").missed_arrivals += 1;
+    }
+
+    /// The number of accepted transactions which have not reached a block.
+    pub fn num_pending(&self) -> usize {
+        self.inner.lock().expect("mutex poisoned").pending.len()
+    }
+
+    /// Summarize the run so far.
+    ///
+    /// # Arguments
+    /// * `scenario` - The name of the scenario.
+    /// * `elapsed` - How long the run took.
+    pub fn summary(&self, scenario: &str, elapsed: Duration) -> Summary {
+        let inner = self.inner.lock().expect("mutex poisoned");
+
+        let mut all_latencies_ms = Vec::new();
+        let mut total = KindSummary::default();
+        let mut kinds = BTreeMap::new();
+        for (kind, stats) in inner.kinds.iter() {
+            let summary = KindSummary::new(stats);
+            total.submitted += summary.submitted;
+            total.accepted += summary.accepted;
+            total.rejected += summary.rejected;
+            total.included += summary.included;
+            total.pending += summary.pending;
+            all_latencies_ms.extend_from_slice(&stats.latencies_ms);
+            kinds.insert(*kind, summary);
+        }
+        total.latency_ms = Latency::from_samples(&all_latencies_ms);
+
+        Summary {
+            scenario: scenario.to_owned(),
+            elapsed_secs: elapsed.as_secs_f64(),
+            missed_arrivals: inner.missed_arrivals,
+            total,
+            kinds,
+        }
+    }
+}
+
+/// The JSON summary of a run.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    /// The name of the scenario.
+    pub scenario: String,
+    /// How long the run took, in seconds.
+    pub elapsed_secs: f64,
+    /// Transactions which were due to be submitted while every worker was
+    /// busy, and so were never built.
+    pub missed_arrivals: u64,
+    /// Totals over every kind of transaction.
+    pub total: KindSummary,
+    /// Totals for each kind of transaction.
+    pub kinds: BTreeMap<TxKind, KindSummary>,
+}
+
+/// Counts and latencies of one kind of transaction.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct KindSummary {
+    /// Transactions submitted.
+    pub submitted: u64,
+    /// Transactions a node accepted.
+    pub accepted: u64,
+    /// Transactions every node rejected.
+    pub rejected: u64,
+    /// Accepted transactions which reached a block.
+    pub included: u64,
+    /// Accepted transactions which have not reached a block.
+    pub pending: u64,
+    /// Latency from `propose_tx` to inclusion in a block, in milliseconds, if
+    /// any transactions were included.
+    pub latency_ms: Option<Latency>,
+}
+
+impl KindSummary {
+    fn new(stats: &KindStats) -> Self {
+        Self {
+            submitted: stats.submitted,
+            accepted: stats.accepted,
+            rejected: stats.rejected,
+            included: stats.included,
+            pending: stats.accepted - stats.included,
+            latency_ms: Latency::from_samples(&stats.latencies_ms),
+        }
+    }
+}
+
+/// Percentiles of a set of latencies.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Latency {
+    /// The smallest latency.
+    pub min: u64,
+    /// The median latency.
+    pub p50: u64,
+    /// The 90th percentile latency.
+    pub p90: u64,
+    /// The 99th percentile latency.
+    pub p99: u64,
+    /// The largest latency.
+    pub max: u64,
+    /// The mean latency.
+    pub mean: f64,
+}
+
+impl Latency {
+    /// Compute percentiles of `samples`, using the nearest-rank method, or
+    /// None if there are no samples.
+    pub fn from_samples(samples: &[u64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.max(1) - 1]
+        };
+
+        Some(Self {
+            min: sorted[0],
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p99: percentile(99.0),
+            max: sorted[sorted.len() - 1],
+            mean: sorted.iter().sum::<u64>() as f64 / sorted.len() as f64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_transaction_core::tx::TxOut;
+
+    #[test]
+    fn latency_percentiles() {
+        assert_eq!(Latency::from_samples(&[]), None);
+
+        let samples: Vec<u64> = (1..=100).rev().collect();
+        assert_eq!(
+            Latency::from_samples(&samples),
+            Some(Latency {
+                min: 1,
+                p50: 50,
+                p90: 90,
+                p99: 99,
+                max: 100,
+                mean: 50.5,
+            })
+        );
+
+        let latency = Latency::from_samples(&[7]).unwrap();
+        assert_eq!((latency.min, latency.p50, latency.p99), (7, 7, 7));
+    }
+
+    fn tx_with_outputs(keys: &[u8]) -> Tx {
+        let mut tx = Tx::default();
+        tx.prefix.tombstone_block = keys[0] as u64;
+        tx.prefix.outputs = keys
+            .iter()
+            .map(|key| TxOut {
+                public_key: CompressedRistrettoPublic::from(&[*key; 32]),
+                ..Default::default()
+            })
+            .collect();
+        tx
+    }
+
+    #[test]
+    fn proposals_are_tracked_until_included() {
+        let stats = LoadStats::default();
+        let start = Instant::now();
+        let valid = tx_with_outputs(&[1, 2]);
+        let double_spend = tx_with_outputs(&[3]);
+
+        stats.record_proposal(TxKind::Valid, &valid, Some(start));
+        stats.record_proposal(TxKind::Invalid, &tx_with_outputs(&[4]), None);
+        stats.record_proposal(TxKind::DoubleSpend, &double_spend, Some(start));
+        stats.record_missed_arrival();
+        assert_eq!(stats.num_pending(), 2);
+
+        // Seeing both outputs of the valid transaction only includes it once.
+        let key = |key: u8| CompressedRistrettoPublic::from(&[key; 32]);
+        stats.record_output(&key(2), start + Duration::from_millis(1500));
+        stats.record_output(&key(1), start + Duration::from_millis(2500));
+        stats.record_output(&key(9), start);
+        assert_eq!(stats.num_pending(), 1);
+
+        let summary = stats.summary("test", Duration::from_secs(3));
+        assert_eq!(
+            summary.kinds[&TxKind::Valid],
+            KindSummary {
+                submitted: 1,
+                accepted: 1,
+                rejected: 0,
+                included: 1,
+                pending: 0,
+                latency_ms: Latency::from_samples(&[1500]),
+            }
+        );
+        assert_eq!(summary.kinds[&TxKind::Invalid].rejected, 1);
+        assert_eq!(summary.kinds[&TxKind::DoubleSpend].pending, 1);
+        assert_eq!(summary.total.submitted, 3);
+        assert_eq!(summary.total.included, 1);
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["scenario"], "test");
+        assert_eq!(json["missed_arrivals"], 1);
+        assert_eq!(json["kinds"]["valid"]["latency_ms"]["p50"], 1500);
+        assert_eq!(json["kinds"]["double_spend"]["included"], 0);
+        assert!(json["kinds"]["invalid"]["latency_ms"].is_null());
+    }
+}
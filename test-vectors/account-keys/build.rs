@@ -197,4 +197,98 @@ fn main() {
             .collect::<Vec<_>>()
     })
     .expect("Unable to write test vectors");
+
+    /// SLIP-0039 shares of BIP-39 entropy, with the passphrase and account
+    /// index to recover. The first two are from
+    /// https://github.com/trezor/python-shamir-mnemonic/blob/master/vectors.json
+    const SLIP39_VECTORS: [(&[&str], &str, u32); 7] = [
+        (
+            &[
+                "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard",
+            ],
+            "TREZOR",
+            0,
+        ),
+        (
+            &[
+                "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+                "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+            ],
+            "TREZOR",
+            0,
+        ),
+        (
+            &[
+                "stay upstairs academic always aspect rival response learn phantom senior friar zero friar discuss favorite adequate havoc music educate painting",
+                "stay upstairs academic acid beyond junk syndrome adorn involve adjust fishing hormone network numb dwarf lying glimpse midst transfer mixed",
+            ],
+            "",
+            0,
+        ),
+        (
+            &[
+                "stay upstairs academic agency campus morning huge estimate wrist grownup soldier visual object emission mandate exclude tolerate smug froth math",
+                "stay upstairs academic always aspect rival response learn phantom senior friar zero friar discuss favorite adequate havoc music educate painting",
+            ],
+            "",
+            1,
+        ),
+        (
+            &[
+                "graduate leaf academic axle aircraft curly imply victim epidemic member obtain pupal visual general alcohol year pharmacy trash emperor capture scroll brother engage painting oven domestic retreat crazy maximum pupal rival album biology",
+                "graduate leaf academic agree aide advocate story mental plot froth general silver dominant always laundry bundle force critical fiber edge pile excuse story artist avoid leaf manual column angel detect kitchen cage amuse",
+                "graduate leaf academic arcade artwork march animal nylon pupal round kind patrol soul military curly olympic crush prevent acquire beard deliver educate pancake dominant negative envelope package fiber rich taught flea include ajar",
+            ],
+            "TREZOR",
+            0,
+        ),
+        (
+            &[
+                "vitamins training acrobat leader adorn exclude gray describe gross daughter vintage pickup insect havoc prisoner screw hairy group therapy lilac therapy stay escape champion dance paper system grasp subject mobile coastal elite episode",
+                "vitamins training ceramic method angry fiber yield dough drink corner iris evaluate acid flexible regret taxi jerky window language bumpy cubic slim making midst squeeze realize presence depend acrobat meaning employer demand hybrid",
+                "vitamins training ceramic lips adapt upgrade railroad injury guard fiction custody briefing parcel guilt mineral broken alive lyrics family recover declare drift eclipse dismiss prospect grasp being main member silent smell eraser equip",
+                "vitamins training ceramic learn airline have browser drink crisis snapshot romp duke earth treat iris satisfy bumpy single aquatic trial pants wine lunch fatal recover auction eraser image society grill terminal guitar paid",
+            ],
+            "",
+            0,
+        ),
+        (
+            &[
+                "vitamins training beard lungs album smear veteran spark declare flame believe slap curious voting deadline again either similar payment purple airline agency fortune medical insect glad blimp rainbow craft standard bolt pleasure academic",
+                "vitamins training beard leaf average welcome drift nylon ending amazing that pupal breathe together shelter oral emphasis stick have domestic listen findings patrol else juice much ruler regular glance envelope shame angry blue",
+                "vitamins training ceramic march argue satisfy believe sweater faint quick tenant advance oasis peasant penalty equip install huge grant inmate ceramic afraid email nail bike award thank hesitate year merit guitar grasp dragon",
+                "vitamins training ceramic lips adapt upgrade railroad injury guard fiction custody briefing parcel guilt mineral broken alive lyrics family recover declare drift eclipse dismiss prospect grasp being main member silent smell eraser equip",
+                "vitamins training ceramic luxury analysis fluff romantic mother campus dining aquatic closet agency chew elbow moisture have crazy domain cleanup prune scatter market woman burning gray rescue muscle pants drove employer endless unfair",
+            ],
+            "",
+            2,
+        ),
+    ];
+
+    write_jsonl("../vectors", || {
+        SLIP39_VECTORS
+            .iter()
+            .map(|(shares, passphrase, account_index)| {
+                let entropy = mc_account_keys_slip39::combine_shares(shares, passphrase.as_bytes())
+                    .expect("Could not combine SLIP-0039 shares");
+                let mnemonic = Mnemonic::from_entropy(&entropy, Language::English)
+                    .expect("Could not create mnemonic from entropy");
+                let mnemonic_text = mnemonic.phrase().to_owned();
+
+                let slip10 = mnemonic.derive_slip10_key(*account_index);
+                let acct_key = AccountKey::from(slip10);
+
+                AcctPrivKeysFromSlip39Shares {
+                    shares: shares.iter().map(|share| (*share).to_owned()).collect(),
+                    passphrase: (*passphrase).to_owned(),
+                    entropy: entropy.to_vec(),
+                    mnemonic: mnemonic_text,
+                    account_index: *account_index,
+                    view_private_key: acct_key.view_private_key().to_bytes(),
+                    spend_private_key: acct_key.spend_private_key().to_bytes(),
+                }
+            })
+            .collect::<Vec<_>>()
+    })
+    .expect("Unable to write test vectors");
 }
@@ -27,6 +27,22 @@ impl TestVector for AcctPrivKeysFromBip39 {
     const MODULE_SUBDIR: &'static str = "account_keys";
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AcctPrivKeysFromSlip39Shares {
+    pub shares: Vec<String>,
+    pub passphrase: String,
+    pub entropy: Vec<u8>,
+    pub mnemonic: String,
+    pub account_index: u32,
+    pub view_private_key: [u8; 32],
+    pub spend_private_key: [u8; 32],
+}
+
+impl TestVector for AcctPrivKeysFromSlip39Shares {
+    const FILE_NAME: &'static str = "acct_priv_keys_from_slip39_shares";
+    const MODULE_SUBDIR: &'static str = "account_keys";
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DefaultSubaddrKeysFromAcctPrivKeys {
     pub view_private_key: [u8; 32],
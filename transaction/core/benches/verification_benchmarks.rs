@@ -0,0 +1,201 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+use bulletproofs::RangeProof;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate, RistrettoPublic};
+use mc_transaction_core::{
+    range_proofs::{check_range_proofs, check_range_proofs_batch, generate_range_proofs},
+    ring_signature::{Scalar, SignatureRctBulletproofs, SignatureToVerify},
+    CompressedCommitment,
+};
+use mc_util_from_random::FromRandom;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+/// Number of inputs and outputs of each signed transaction.
+const NUM_INPUTS: usize = 2;
+
+/// Number of elements in each ring.
+const RING_SIZE: usize = 11;
+
+/// Numbers of transactions to verify together.
+const BATCH_SIZES: [usize; 3] = [1, 8, 32];
+
+/// A signature, and what it signs.
+struct SignedTx {
+    message: [u8; 32],
+    rings: Vec<Vec<(CompressedRistrettoPublic, CompressedCommitment)>>,
+    output_commitments: Vec<CompressedCommitment>,
+    signature: SignatureRctBulletproofs,
+}
+
+impl SignedTx {
+    fn random(rng: &mut StdRng) -> Self {
+        let mut message = [0u8; 32];
+        rng.fill_bytes(&mut message);
+
+        let mut rings = Vec::new();
+        let mut real_input_indices = Vec::new();
+        let mut input_secrets = Vec::new();
+        for _ in 0..NUM_INPUTS {
+            let mut ring: Vec<(CompressedRistrettoPublic, CompressedCommitment)> = (0..RING_SIZE
+                - 1)
+                .map(|_| {
+                    (
+                        CompressedRistrettoPublic::from(RistrettoPublic::from_random(rng)),
+                        CompressedCommitment::new(rng.next_u64(), Scalar::random(rng)),
+                    )
+                })
+                .collect();
+
+            let onetime_private_key = RistrettoPrivate::from_random(rng);
+            let value = rng.next_u64() >> 8;
+            let blinding = Scalar::random(rng);
+            let real_index = rng.next_u64() as usize % RING_SIZE;
+            ring.insert(
+                real_index,
+                (
+                    CompressedRistrettoPublic::from(RistrettoPublic::from(&onetime_private_key)),
+                    CompressedCommitment::new(value, blinding),
+                ),
+            );
+
+            rings.push(ring);
+            real_input_indices.push(real_index);
+            input_secrets.push((onetime_private_key, value, blinding));
+        }
+
+        let output_values_and_blindings: Vec<(u64, Scalar)> = input_secrets
+            .iter()
+            .map(|(_, value, _)| (*value, Scalar::random(rng)))
+            .collect();
+        let output_commitments = output_values_and_blindings
+            .iter()
+            .map(|(value, blinding)| CompressedCommitment::new(*value, *blinding))
+            .collect();
+
+        let signature = SignatureRctBulletproofs::sign(
+            &message,
+            &rings,
+            &real_input_indices,
+            &input_secrets,
+            &output_values_and_blindings,
+            0,
+            rng,
+        )
+        .unwrap();
+
+        Self {
+            message,
+            rings,
+            output_commitments,
+            signature,
+        }
+    }
+
+    fn to_verify(&self) -> SignatureToVerify {
+        SignatureToVerify {
+            signature: &self.signature,
+            message: &self.message,
+            rings: &self.rings,
+            output_commitments: &self.output_commitments,
+            fee: 0,
+        }
+    }
+}
+
+fn range_proof_benchmarks(c: &mut Criterion) {
+    let mut rng: StdRng = SeedableRng::from_seed([100u8; 32]);
+    let mut group = c.benchmark_group("RangeProof");
+
+    let max_batch_size = BATCH_SIZES[BATCH_SIZES.len() - 1];
+    let proofs: Vec<(RangeProof, Vec<CompressedRistretto>)> = (0..max_batch_size)
+        .map(|_| {
+            let values: Vec<u64> = (0..2 * NUM_INPUTS).map(|_| rng.next_u64()).collect();
+            let blindings: Vec<Scalar> = values.iter().map(|_| Scalar::random(&mut rng)).collect();
+            generate_range_proofs(&values, &blindings, &mut rng).unwrap()
+        })
+        .collect();
+
+    for batch_size in BATCH_SIZES.iter() {
+        let batch = &proofs[..*batch_size];
+
+        group.bench_with_input(
+            BenchmarkId::new("check_range_proofs", batch_size),
+            batch,
+            |b, batch| {
+                b.iter(|| {
+                    for (proof, commitments) in batch {
+                        check_range_proofs(proof, commitments, &mut rng).unwrap();
+                    }
+                })
+            },
+        );
+
+        let batch: Vec<(&RangeProof, &[CompressedRistretto])> = batch
+            .iter()
+            .map(|(proof, commitments)| (proof, &commitments[..]))
+            .collect();
+        group.bench_with_input(
+            BenchmarkId::new("check_range_proofs_batch", batch_size),
+            &batch,
+            |b, batch| b.iter(|| check_range_proofs_batch(batch, &mut rng).unwrap()),
+        );
+    }
+
+    group.finish();
+}
+
+fn signature_benchmarks(c: &mut Criterion) {
+    let mut rng: StdRng = SeedableRng::from_seed([100u8; 32]);
+    let mut group = c.benchmark_group("SignatureRctBulletproofs");
+
+    let max_batch_size = BATCH_SIZES[BATCH_SIZES.len() - 1];
+    let signed_txs: Vec<SignedTx> = (0..max_batch_size)
+        .map(|_| SignedTx::random(&mut rng))
+        .collect();
+
+    for batch_size in BATCH_SIZES.iter() {
+        let batch: Vec<SignatureToVerify> = signed_txs[..*batch_size]
+            .iter()
+            .map(SignedTx::to_verify)
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("verify", batch_size),
+            &batch,
+            |b, batch| {
+                b.iter(|| {
+                    for to_verify in batch {
+                        to_verify
+                            .signature
+                            .verify(
+                                to_verify.message,
+                                to_verify.rings,
+                                to_verify.output_commitments,
+                                to_verify.fee,
+                                &mut rng,
+                            )
+                            .unwrap();
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("verify_batch", batch_size),
+            &batch,
+            |b, batch| b.iter(|| SignatureRctBulletproofs::verify_batch(batch, &mut rng).unwrap()),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = range_proof_benchmarks, signature_benchmarks
+}
+
+criterion_main!(benches);
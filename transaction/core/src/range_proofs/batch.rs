@@ -0,0 +1,391 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Batch verification of aggregated 64-bit range proofs.
+//!
+//! Verifying a range proof amounts to checking that a multiscalar
+//! multiplication over the proof's points, the Pedersen generators and the
+//! Bulletproof generators is the identity. Summing the equations of many
+//! proofs, each scaled by a random weight, gives a single check which holds
+//! (except with negligible probability) only if every proof is valid. The
+//! generator terms are shared by all proofs, so one large multiscalar
+//! multiplication replaces many smaller ones.
+//!
+//! `bulletproofs` does not expose the verification equation, so it is
+//! reconstructed here following `RangeProof::verify_multiple_with_rng`.
+
+// Variable names follow the Bulletproofs paper, as `bulletproofs` does.
+#![allow(non_snake_case)]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use bulletproofs::{ProofError, RangeProof};
+use core::iter;
+use curve25519_dalek::{
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::{IsIdentity, VartimeMultiscalarMul},
+};
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
+
+use super::{error::Error, resize_slice_to_pow2, RANGE_PROOF_BITS};
+use crate::{
+    domain_separators::BULLETPROOF_DOMAIN_TAG,
+    ring_signature::{BP_GENERATORS, GENERATORS},
+};
+
+lazy_static! {
+    /// The `G` and `H` vectors of `BP_GENERATORS` for each party, which
+    /// `BulletproofGens` only uses internally.
+    static ref BP_GENERATOR_VECS: (Vec<Vec<RistrettoPoint>>, Vec<Vec<RistrettoPoint>>) = {
+        let parties = 0..BP_GENERATORS.party_capacity;
+        (
+            parties.clone().map(|party| party_generators(b'G', party)).collect(),
+            parties.map(|party| party_generators(b'H', party)).collect(),
+        )
+    };
+}
+
+/// The generators a party uses for one of its `G` or `H` vectors, derived as
+/// by `BulletproofGens`.
+fn party_generators(prefix: u8, party_index: usize) -> Vec<RistrettoPoint> {
+    let mut label = [prefix, 0, 0, 0, 0];
+    label[1..].copy_from_slice(&(party_index as u32).to_le_bytes());
+
+    let mut shake = Shake256::default();
+    shake.update(b"GeneratorsChain");
+    shake.update(&label);
+    let mut reader = shake.finalize_xof();
+
+    (0..BP_GENERATORS.gens_capacity)
+        .map(|_| {
+            let mut uniform_bytes = [0u8; 64];
+            reader.read(&mut uniform_bytes);
+            RistrettoPoint::from_uniform_bytes(&uniform_bytes)
+        })
+        .collect()
+}
+
+/// The transcript operations of `bulletproofs`' `TranscriptProtocol`.
+trait RangeProofTranscript {
+    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto);
+    fn validate_and_append_point(
+        &mut self,
+        label: &'static [u8],
+        point: &CompressedRistretto,
+    ) -> Result<(), Error>;
+    fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar);
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar;
+}
+
+impl RangeProofTranscript for Transcript {
+    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto) {
+        self.append_message(label, point.as_bytes());
+    }
+
+    fn validate_and_append_point(
+        &mut self,
+        label: &'static [u8],
+        point: &CompressedRistretto,
+    ) -> Result<(), Error> {
+        if point.is_identity() {
+            return Err(Error::ProofError(ProofError::VerificationError));
+        }
+        self.append_point(label, point);
+        Ok(())
+    }
+
+    fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar) {
+        self.append_message(label, scalar.as_bytes());
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+        let mut buf = [0u8; 64];
+        self.challenge_bytes(label, &mut buf);
+        Scalar::from_bytes_mod_order_wide(&buf)
+    }
+}
+
+/// The components of a serialized `RangeProof`.
+///
+/// The layout is four points `A, S, T_1, T_2`, three scalars `t_x,
+/// t_x_blinding, e_blinding`, the inner product proof's `(L, R)` point
+/// pairs, and its two scalars `a, b`.
+struct RangeProofParts {
+    A: CompressedRistretto,
+    S: CompressedRistretto,
+    T_1: CompressedRistretto,
+    T_2: CompressedRistretto,
+    t_x: Scalar,
+    t_x_blinding: Scalar,
+    e_blinding: Scalar,
+    L_vec: Vec<CompressedRistretto>,
+    R_vec: Vec<CompressedRistretto>,
+    a: Scalar,
+    b: Scalar,
+}
+
+impl RangeProofParts {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let format_error = Error::ProofError(ProofError::FormatError);
+        if bytes.len() % 32 != 0 || bytes.len() < 9 * 32 || (bytes.len() / 32 - 9) % 2 != 0 {
+            return Err(format_error);
+        }
+        let lg_n = (bytes.len() / 32 - 9) / 2;
+        if lg_n >= 32 {
+            return Err(format_error);
+        }
+
+        let read32 = |i: usize| {
+            let mut buf = [0u8; 32];
+            buf.copy_from_slice(&bytes[i * 32..(i + 1) * 32]);
+            buf
+        };
+        let point = |i: usize| CompressedRistretto(read32(i));
+        let scalar = |i: usize| {
+            Scalar::from_canonical_bytes(read32(i))
+                .ok_or(Error::ProofError(ProofError::FormatError))
+        };
+
+        Ok(Self {
+            A: point(0),
+            S: point(1),
+            T_1: point(2),
+            T_2: point(3),
+            t_x: scalar(4)?,
+            t_x_blinding: scalar(5)?,
+            e_blinding: scalar(6)?,
+            L_vec: (0..lg_n).map(|i| point(7 + 2 * i)).collect(),
+            R_vec: (0..lg_n).map(|i| point(8 + 2 * i)).collect(),
+            a: scalar(7 + 2 * lg_n)?,
+            b: scalar(8 + 2 * lg_n)?,
+        })
+    }
+}
+
+/// `sum_{i=0}^{n-1} x^i`
+fn sum_of_powers(x: &Scalar, n: usize) -> Scalar {
+    exp_iter(*x).take(n).sum()
+}
+
+/// `1, x, x^2, ...`
+fn exp_iter(x: Scalar) -> impl Iterator<Item = Scalar> {
+    iter::successors(Some(Scalar::one()), move |exp_x| Some(exp_x * x))
+}
+
+/// `delta(y, z) = (z - z^2) <1, y^(n*m)> - sum_{j=1}^{m} z^(j+2) <1, 2^n>`
+fn delta(n: usize, m: usize, y: &Scalar, z: &Scalar) -> Scalar {
+    let sum_y = sum_of_powers(y, n * m);
+    let sum_2 = sum_of_powers(&Scalar::from(2u64), n);
+    let sum_z = sum_of_powers(z, m);
+
+    (z - z * z) * sum_y - z * z * z * sum_2 * sum_z
+}
+
+/// The accumulated terms of the combined verification equation.
+struct RangeProofBatch {
+    /// Scalars for each party's `G` generators, `n` per party.
+    g_scalars: Vec<Scalar>,
+    /// Scalars for each party's `H` generators, `n` per party.
+    h_scalars: Vec<Scalar>,
+    /// Scalar for the Pedersen value generator.
+    b_scalar: Scalar,
+    /// Scalar for the Pedersen blinding generator.
+    b_blinding_scalar: Scalar,
+    /// Scalars for the points of the individual proofs.
+    scalars: Vec<Scalar>,
+    /// Points of the individual proofs, `None` if they failed to decompress.
+    points: Vec<Option<RistrettoPoint>>,
+    /// The largest number of parties in any proof.
+    max_parties: usize,
+}
+
+impl RangeProofBatch {
+    fn new() -> Self {
+        let capacity = RANGE_PROOF_BITS * BP_GENERATORS.party_capacity;
+        Self {
+            g_scalars: vec![Scalar::zero(); capacity],
+            h_scalars: vec![Scalar::zero(); capacity],
+            b_scalar: Scalar::zero(),
+            b_blinding_scalar: Scalar::zero(),
+            scalars: Vec::new(),
+            points: Vec::new(),
+            max_parties: 0,
+        }
+    }
+
+    /// Add the verification equation of one proof, scaled by a random weight.
+    fn add<T: RngCore + CryptoRng>(
+        &mut self,
+        range_proof: &RangeProof,
+        value_commitments: &[CompressedRistretto],
+        rng: &mut T,
+    ) -> Result<(), Error> {
+        let n = RANGE_PROOF_BITS;
+        let m = value_commitments.len();
+        if m > BP_GENERATORS.party_capacity || BP_GENERATORS.gens_capacity < n {
+            return Err(Error::ProofError(ProofError::InvalidGeneratorsLength));
+        }
+        let proof = RangeProofParts::from_bytes(&range_proof.to_bytes())?;
+        let lg_nm = proof.L_vec.len();
+        if n * m != 1 << lg_nm {
+            return Err(Error::ProofError(ProofError::VerificationError));
+        }
+
+        // Replay the proof's transcript to recompute its challenges.
+        let mut transcript = Transcript::new(BULLETPROOF_DOMAIN_TAG.as_ref());
+        transcript.append_message(b"dom-sep", b"rangeproof v1");
+        transcript.append_u64(b"n", n as u64);
+        transcript.append_u64(b"m", m as u64);
+        for V in value_commitments {
+            transcript.append_point(b"V", V);
+        }
+        transcript.validate_and_append_point(b"A", &proof.A)?;
+        transcript.validate_and_append_point(b"S", &proof.S)?;
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        let zz = z * z;
+        transcript.validate_and_append_point(b"T_1", &proof.T_1)?;
+        transcript.validate_and_append_point(b"T_2", &proof.T_2)?;
+        let x = transcript.challenge_scalar(b"x");
+        transcript.append_scalar(b"t_x", &proof.t_x);
+        transcript.append_scalar(b"t_x_blinding", &proof.t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &proof.e_blinding);
+        let w = transcript.challenge_scalar(b"w");
+
+        // The inner product proof's challenges, u_k, ..., u_1.
+        transcript.append_message(b"dom-sep", b"ipp v1");
+        transcript.append_u64(b"n", (n * m) as u64);
+        let mut challenges = Vec::with_capacity(lg_nm);
+        for (L, R) in proof.L_vec.iter().zip(proof.R_vec.iter()) {
+            transcript.validate_and_append_point(b"L", L)?;
+            transcript.validate_and_append_point(b"R", R)?;
+            challenges.push(transcript.challenge_scalar(b"u"));
+        }
+        let challenges_inv: Vec<Scalar> = challenges.iter().map(Scalar::invert).collect();
+        let all_inv: Scalar = challenges_inv.iter().product();
+        let challenges_sq: Vec<Scalar> = challenges.iter().map(|u| u * u).collect();
+        let challenges_inv_sq: Vec<Scalar> = challenges_inv.iter().map(|u| u * u).collect();
+
+        // s_i = prod_j u_j^(+-1), built up from s_0 = 1 / (u_k ... u_1).
+        let mut s = Vec::with_capacity(n * m);
+        s.push(all_inv);
+        for i in 1..n * m {
+            let lg_i = (32 - 1 - (i as u32).leading_zeros()) as usize;
+            let k = 1 << lg_i;
+            s.push(s[i - k] * challenges_sq[(lg_nm - 1) - lg_i]);
+        }
+
+        // `c` combines the proof's two equations, as in the unbatched check, and
+        // `weight` combines this proof's equation with the others'.
+        let c = Scalar::random(rng);
+        let weight = Scalar::random(rng);
+
+        self.scalars.push(weight);
+        self.points.push(proof.A.decompress());
+        self.scalars.push(weight * x);
+        self.points.push(proof.S.decompress());
+        self.scalars.push(weight * c * x);
+        self.points.push(proof.T_1.decompress());
+        self.scalars.push(weight * c * x * x);
+        self.points.push(proof.T_2.decompress());
+        for (x_sq, L) in challenges_sq.iter().zip(proof.L_vec.iter()) {
+            self.scalars.push(weight * x_sq);
+            self.points.push(L.decompress());
+        }
+        for (x_inv_sq, R) in challenges_inv_sq.iter().zip(proof.R_vec.iter()) {
+            self.scalars.push(weight * x_inv_sq);
+            self.points.push(R.decompress());
+        }
+        for (z_exp, V) in exp_iter(z).zip(value_commitments.iter()) {
+            self.scalars.push(weight * c * zz * z_exp);
+            self.points.push(V.decompress());
+        }
+
+        self.b_blinding_scalar += weight * (-proof.e_blinding - c * proof.t_x_blinding);
+        self.b_scalar +=
+            weight * (w * (proof.t_x - proof.a * proof.b) + c * (delta(n, m, &y, &z) - proof.t_x));
+
+        // z^j * 2^i for party j and bit i, in the order of the generators.
+        let powers_of_2: Vec<Scalar> = exp_iter(Scalar::from(2u64)).take(n).collect();
+        let concat_z_and_2 = exp_iter(z)
+            .take(m)
+            .flat_map(|exp_z| powers_of_2.iter().map(move |exp_2| exp_2 * exp_z));
+        let minus_z = -z;
+        for (i, (exp_y_inv, z_and_2)) in exp_iter(y.invert()).zip(concat_z_and_2).enumerate() {
+            let s_i = s[i];
+            let s_i_inv = s[n * m - 1 - i];
+            self.g_scalars[i] += weight * (minus_z - proof.a * s_i);
+            self.h_scalars[i] += weight * (z + exp_y_inv * (zz * z_and_2 - proof.b * s_i_inv));
+        }
+
+        self.max_parties = self.max_parties.max(m);
+        Ok(())
+    }
+
+    /// Check the combined verification equation.
+    fn verify(self) -> Result<(), Error> {
+        let n = RANGE_PROOF_BITS;
+        let num_generators = n * self.max_parties;
+        let (g_vecs, h_vecs) = &*BP_GENERATOR_VECS;
+        let generators = |vecs: &'static Vec<Vec<RistrettoPoint>>| {
+            vecs.iter()
+                .take(self.max_parties)
+                .flat_map(move |party| party.iter().take(n))
+                .map(|point| Some(*point))
+        };
+
+        let check = RistrettoPoint::optional_multiscalar_mul(
+            self.scalars
+                .iter()
+                .chain(iter::once(&self.b_blinding_scalar))
+                .chain(iter::once(&self.b_scalar))
+                .chain(self.g_scalars[..num_generators].iter())
+                .chain(self.h_scalars[..num_generators].iter()),
+            self.points
+                .iter()
+                .cloned()
+                .chain(iter::once(Some(GENERATORS.B_blinding)))
+                .chain(iter::once(Some(GENERATORS.B)))
+                .chain(generators(g_vecs))
+                .chain(generators(h_vecs)),
+        )
+        .ok_or(Error::ProofError(ProofError::VerificationError))?;
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(Error::ProofError(ProofError::VerificationError))
+        }
+    }
+}
+
+/// Verifies many aggregated 64-bit RangeProofs with a single multiscalar
+/// multiplication.
+///
+/// Succeeds only if every proof is valid for its commitments, but does not
+/// say which proof is invalid if one is; use `check_range_proofs` for that.
+///
+/// # Arguments
+/// `proofs` - RangeProofs, and the commitments each proves lie in [0,2^64).
+/// `rng` - Randomness.
+pub fn check_range_proofs_batch<T: RngCore + CryptoRng>(
+    proofs: &[(&RangeProof, &[CompressedRistretto])],
+    rng: &mut T,
+) -> Result<(), Error> {
+    let mut batch = RangeProofBatch::new();
+    for (range_proof, commitments) in proofs {
+        if commitments.is_empty() {
+            return Err(Error::ResizeError);
+        }
+        // As in `check_range_proofs`, the commitments are padded to a power of 2.
+        let resized_commitments = resize_slice_to_pow2::<CompressedRistretto>(commitments)?;
+        batch.add(range_proof, &resized_commitments, rng)?;
+    }
+    batch.verify()
+}
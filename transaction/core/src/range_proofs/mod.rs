@@ -7,13 +7,18 @@ use curve25519_dalek::{ristretto::CompressedRistretto, scalar::Scalar};
 use merlin::Transcript;
 use rand_core::{CryptoRng, RngCore};
 
+mod batch;
 pub mod error;
 use crate::{
     domain_separators::BULLETPROOF_DOMAIN_TAG,
     ring_signature::{BP_GENERATORS, GENERATORS},
 };
+pub use batch::check_range_proofs_batch;
 use error::Error;
 
+/// The number of bits in each range proven, i.e. values lie in [0,2^64).
+const RANGE_PROOF_BITS: usize = 64;
+
 /// Create an aggregated 64-bit rangeproof for a set of values.
 ///
 /// Creates a proof that each secret value is in the range [0,2^64).
@@ -45,7 +50,7 @@ pub fn generate_range_proofs<T: RngCore + CryptoRng>(
         &mut Transcript::new(BULLETPROOF_DOMAIN_TAG.as_ref()),
         &values_padded,
         &blindings_padded,
-        64,
+        RANGE_PROOF_BITS,
         rng,
     )
     .map_err(Error::from)
@@ -72,7 +77,7 @@ pub fn check_range_proofs<T: RngCore + CryptoRng>(
             &GENERATORS,
             &mut Transcript::new(BULLETPROOF_DOMAIN_TAG.as_ref()),
             &resized_commitments,
-            64,
+            RANGE_PROOF_BITS,
             rng,
         )
         .map_err(Error::from)
@@ -153,4 +158,72 @@ pub mod tests {
             Err(_e) => {} // This is expected.
         }
     }
+
+    #[test]
+    // `check_range_proofs_batch` should accept valid proofs with any number of
+    // commitments.
+    fn test_check_range_proofs_batch() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        let proofs_and_commitments: Vec<(RangeProof, Vec<CompressedRistretto>)> = [1, 2, 3, 8]
+            .iter()
+            .map(|num_values| {
+                let values: Vec<u64> = (0..*num_values).map(|_| rng.next_u64()).collect();
+                let blindings: Vec<Scalar> =
+                    (0..*num_values).map(|_| Scalar::random(&mut rng)).collect();
+                generate_range_proofs(&values, &blindings, &mut rng).unwrap()
+            })
+            .collect();
+        let proofs: Vec<(&RangeProof, &[CompressedRistretto])> = proofs_and_commitments
+            .iter()
+            .map(|(proof, commitments)| (proof, &commitments[..]))
+            .collect();
+
+        assert_eq!(check_range_proofs_batch(&proofs, &mut rng), Ok(()));
+        assert_eq!(check_range_proofs_batch(&proofs[2..3], &mut rng), Ok(()));
+        assert_eq!(check_range_proofs_batch(&[], &mut rng), Ok(()));
+    }
+
+    #[test]
+    // `check_range_proofs_batch` should return an error if any proof's
+    // commitments do not agree with it.
+    fn test_check_range_proofs_batch_rejects_wrong_commitments() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        let proofs_and_commitments: Vec<(RangeProof, Vec<CompressedRistretto>)> = (0..4)
+            .map(|_| {
+                let values: Vec<u64> = (0..2).map(|_| rng.next_u64()).collect();
+                let blindings: Vec<Scalar> = (0..2).map(|_| Scalar::random(&mut rng)).collect();
+                generate_range_proofs(&values, &blindings, &mut rng).unwrap()
+            })
+            .collect();
+
+        for wrong_index in 0..proofs_and_commitments.len() {
+            let mut wrong_commitments: Vec<Vec<CompressedRistretto>> = proofs_and_commitments
+                .iter()
+                .map(|(_, commitments)| commitments.clone())
+                .collect();
+            wrong_commitments[wrong_index][1] = RistrettoPoint::random(&mut rng).compress();
+
+            let proofs: Vec<(&RangeProof, &[CompressedRistretto])> = proofs_and_commitments
+                .iter()
+                .zip(wrong_commitments.iter())
+                .map(|((proof, _), commitments)| (proof, &commitments[..]))
+                .collect();
+            assert!(check_range_proofs_batch(&proofs, &mut rng).is_err());
+        }
+
+        // A proof checked against another proof's commitments.
+        let proofs = [
+            (
+                &proofs_and_commitments[0].0,
+                &proofs_and_commitments[1].1[..],
+            ),
+            (
+                &proofs_and_commitments[1].0,
+                &proofs_and_commitments[0].1[..],
+            ),
+        ];
+        assert!(check_range_proofs_batch(&proofs, &mut rng).is_err());
+    }
 }
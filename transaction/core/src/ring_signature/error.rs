@@ -48,6 +48,12 @@ pub enum Error {
 
     /// Invalid RangeProof
     RangeProofError,
+
+    /// The ring signer does not hold the key for the requested key image
+    UnknownKeyImage,
+
+    /// The ring signer failed to produce a signature
+    SignerFailed,
 }
 
 impl From<mc_util_repr_bytes::LengthMismatch> for Error {
@@ -15,12 +15,14 @@ pub use key_image::*;
 use mc_crypto_keys::RistrettoPublic;
 pub use mlsag::*;
 pub use rct_bulletproofs::*;
+pub use ring_signer::*;
 
 mod curve_scalar;
 mod error;
 mod key_image;
 mod mlsag;
 mod rct_bulletproofs;
+mod ring_signer;
 
 lazy_static! {
     /// Generators (base points) for Pedersen commitments.
@@ -21,8 +21,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     constants::FEE_BLINDING,
-    range_proofs::{check_range_proofs, generate_range_proofs},
-    ring_signature::{mlsag::RingMLSAG, Error, KeyImage, Scalar, GENERATORS},
+    range_proofs::{check_range_proofs, check_range_proofs_batch, generate_range_proofs},
+    ring_signature::{
+        mlsag::RingMLSAG, Error, KeyImage, LocalRingSigner, RingSigner, Scalar, SignableRing,
+        GENERATORS,
+    },
     Commitment, CompressedCommitment,
 };
 
@@ -45,6 +48,26 @@ pub struct SignatureRctBulletproofs {
     pub range_proof_bytes: Vec<u8>,
 }
 
+/// A signature to be checked by `SignatureRctBulletproofs::verify_batch`,
+/// along with the arguments `SignatureRctBulletproofs::verify` would take.
+#[derive(Clone, Copy)]
+pub struct SignatureToVerify<'a> {
+    /// The signature.
+    pub signature: &'a SignatureRctBulletproofs,
+
+    /// The signed message.
+    pub message: &'a [u8; 32],
+
+    /// One or more rings of one-time addresses and amount commitments.
+    pub rings: &'a [Vec<(CompressedRistrettoPublic, CompressedCommitment)>],
+
+    /// Output amount commitments.
+    pub output_commitments: &'a [CompressedCommitment],
+
+    /// Value of the implicit fee output.
+    pub fee: u64,
+}
+
 impl SignatureRctBulletproofs {
     /// Sign.
     ///
@@ -79,6 +102,46 @@ impl SignatureRctBulletproofs {
         )
     }
 
+    /// Sign, using a [`RingSigner`] to produce the signature for each ring
+    /// rather than holding the inputs' onetime private keys.
+    ///
+    /// # Arguments
+    /// * `message` - The messages to be signed, e.g. Hash(TxPrefix).
+    /// * `rings` - One or more rings of one-time addresses and amount
+    ///   commitments.
+    /// * `real_input_indices` - The index of the real input in each ring.
+    /// * `input_key_images` - The key image of each real input.
+    /// * `input_values_and_blindings` - Amount value and amount blinding for
+    ///   each real input.
+    /// * `output_values_and_blindings` - Value and blinding for each output
+    ///   amount commitment.
+    /// * `fee` - Value of the implicit fee output.
+    /// * `signer` - Holder of the onetime private key of each real input.
+    pub fn sign_with_signer<S: RingSigner, CSPRNG: RngCore + CryptoRng>(
+        message: &[u8; 32],
+        rings: &[Vec<(CompressedRistrettoPublic, CompressedCommitment)>],
+        real_input_indices: &[usize],
+        input_key_images: &[KeyImage],
+        input_values_and_blindings: &[(u64, Scalar)],
+        output_values_and_blindings: &[(u64, Scalar)],
+        fee: u64,
+        signer: &S,
+        rng: &mut CSPRNG,
+    ) -> Result<Self, Error> {
+        sign_with_signer_and_balance_check(
+            message,
+            rings,
+            real_input_indices,
+            input_key_images,
+            input_values_and_blindings,
+            output_values_and_blindings,
+            fee,
+            true,
+            signer,
+            rng,
+        )
+    }
+
     /// Verify.
     ///
     /// # Arguments
@@ -96,6 +159,105 @@ impl SignatureRctBulletproofs {
         fee: u64,
         rng: &mut CSPRNG,
     ) -> Result<(), Error> {
+        self.verify_with_range_check(
+            message,
+            rings,
+            output_commitments,
+            fee,
+            |range_proof, commitments| {
+                check_range_proofs(&range_proof, &commitments, rng)
+                    .map_err(|_e| Error::RangeProofError)
+            },
+        )
+    }
+
+    /// Verify many signatures, checking all of their range proofs together.
+    ///
+    /// This is equivalent to calling `verify` on each signature, but checks
+    /// the range proofs with a single multiscalar multiplication, which is
+    /// substantially faster than checking them one at a time. The MLSAGs are
+    /// still verified individually, since each is a chain of challenges
+    /// which cannot be combined with others.
+    ///
+    /// Because the range proofs are checked last, the error returned for an
+    /// invalid signature may differ from the one `verify` would return, and
+    /// `Error::RangeProofError` does not say which signature is at fault;
+    /// call `verify` on each signature to find out.
+    ///
+    /// # Arguments
+    /// * `signatures` - The signatures to verify, with what each signs.
+    /// * `rng` -
+    pub fn verify_batch<CSPRNG: RngCore + CryptoRng>(
+        signatures: &[SignatureToVerify],
+        rng: &mut CSPRNG,
+    ) -> Result<(), Error> {
+        let range_proofs = signatures
+            .iter()
+            .map(|to_verify| {
+                to_verify.signature.verify_except_range_proof(
+                    to_verify.message,
+                    to_verify.rings,
+                    to_verify.output_commitments,
+                    to_verify.fee,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let range_proofs: Vec<(&RangeProof, &[CompressedRistretto])> = range_proofs
+            .iter()
+            .map(|(range_proof, commitments)| (range_proof, &commitments[..]))
+            .collect();
+        check_range_proofs_batch(&range_proofs, rng).map_err(|_e| Error::RangeProofError)
+    }
+
+    /// Verify everything but the range proof, which is returned along with
+    /// the commitments it must cover, e.g. to be checked with others by
+    /// `check_range_proofs_batch`.
+    ///
+    /// The signature is valid if this succeeds and the range proof is valid.
+    ///
+    /// # Arguments
+    /// * `message` - The signed message.
+    /// * `rings` - One or more rings of one-time addresses and amount
+    ///   commitments.
+    /// * `output_commitments` - Output amount commitments.
+    /// * `fee` - Value of the implicit fee output.
+    pub fn verify_except_range_proof(
+        &self,
+        message: &[u8; 32],
+        rings: &[Vec<(CompressedRistrettoPublic, CompressedCommitment)>],
+        output_commitments: &[CompressedCommitment],
+        fee: u64,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), Error> {
+        let mut unchecked = None;
+        self.verify_with_range_check(
+            message,
+            rings,
+            output_commitments,
+            fee,
+            |range_proof, commitments| {
+                unchecked = Some((range_proof, commitments));
+                Ok(())
+            },
+        )?;
+        Ok(unchecked.expect("verify_with_range_check always checks the range proof"))
+    }
+
+    /// Verify, checking the range proof with `check_range_proof`.
+    ///
+    /// `check_range_proof` is given the parsed range proof and the
+    /// commitments it must cover.
+    fn verify_with_range_check<F>(
+        &self,
+        message: &[u8; 32],
+        rings: &[Vec<(CompressedRistrettoPublic, CompressedCommitment)>],
+        output_commitments: &[CompressedCommitment],
+        fee: u64,
+        check_range_proof: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(RangeProof, Vec<CompressedRistretto>) -> Result<(), Error>,
+    {
         // Signature must contain one ring signature for each ring.
         if rings.len() != self.ring_signatures.len() {
             return Err(Error::LengthMismatch(
@@ -151,8 +313,7 @@ impl SignatureRctBulletproofs {
             let range_proof = RangeProof::from_bytes(&self.range_proof_bytes)
                 .map_err(|_e| Error::RangeProofError)?;
 
-            check_range_proofs(&range_proof, &commitments, rng)
-                .map_err(|_e| Error::RangeProofError)?;
+            check_range_proof(range_proof, commitments)?;
         }
 
         // Output commitments - pseudo_outputs must be zero.
@@ -226,6 +387,57 @@ fn sign_with_balance_check<CSPRNG: RngCore + CryptoRng>(
     fee: u64,
     check_value_is_preserved: bool,
     rng: &mut CSPRNG,
+) -> Result<SignatureRctBulletproofs, Error> {
+    let mut signer = LocalRingSigner::default();
+    let input_key_images: Vec<KeyImage> = input_secrets
+        .iter()
+        .map(|(onetime_private_key, _, _)| signer.add_key(*onetime_private_key))
+        .collect();
+    let input_values_and_blindings: Vec<(u64, Scalar)> = input_secrets
+        .iter()
+        .map(|(_, value, blinding)| (*value, *blinding))
+        .collect();
+
+    sign_with_signer_and_balance_check(
+        message,
+        rings,
+        real_input_indices,
+        &input_key_images,
+        &input_values_and_blindings,
+        output_values_and_blindings,
+        fee,
+        check_value_is_preserved,
+        &signer,
+        rng,
+    )
+}
+
+/// Sign using a [`RingSigner`], with optional check for inputs = outputs.
+///
+/// # Arguments
+/// * `message` - The messages to be signed, e.g. Hash(TxPrefix).
+/// * `rings` - One or more rings of one-time addresses and amount commitments.
+/// * `real_input_indices` - The index of the real input in each ring.
+/// * `input_key_images` - The key image of each real input.
+/// * `input_values_and_blindings` - Amount value and amount blinding for each
+///   real input.
+/// * `output_values_and_blindings` - Value and blinding for each output amount
+///   commitment.
+/// * `fee` - Value of the implicit fee output.
+/// * `check_value_is_preserved` - If true, check that the value of inputs
+///   equals value of outputs.
+/// * `signer` - Holder of the onetime private key of each real input.
+fn sign_with_signer_and_balance_check<S: RingSigner, CSPRNG: RngCore + CryptoRng>(
+    message: &[u8; 32],
+    rings: &[Vec<(CompressedRistrettoPublic, CompressedCommitment)>],
+    real_input_indices: &[usize],
+    input_key_images: &[KeyImage],
+    input_values_and_blindings: &[(u64, Scalar)],
+    output_values_and_blindings: &[(u64, Scalar)],
+    fee: u64,
+    check_value_is_preserved: bool,
+    signer: &S,
+    rng: &mut CSPRNG,
 ) -> Result<SignatureRctBulletproofs, Error> {
     if rings.is_empty() {
         return Err(Error::NoInputs);
@@ -243,9 +455,15 @@ fn sign_with_balance_check<CSPRNG: RngCore + CryptoRng>(
         }
     }
 
-    // `input_secrets` must contain an element for each input.
-    if input_secrets.len() != num_inputs {
-        return Err(Error::InvalidInputSecretsSize(input_secrets.len()));
+    // `input_key_images` and `input_values_and_blindings` must contain an
+    // element for each input.
+    if input_key_images.len() != num_inputs {
+        return Err(Error::InvalidInputSecretsSize(input_key_images.len()));
+    }
+    if input_values_and_blindings.len() != num_inputs {
+        return Err(Error::InvalidInputSecretsSize(
+            input_values_and_blindings.len(),
+        ));
     }
 
     // Each `real_input_index` must be in [0,ring_size - 1].
@@ -274,10 +492,10 @@ fn sign_with_balance_check<CSPRNG: RngCore + CryptoRng>(
     pseudo_output_blindings.push(last_blinding);
 
     // Create Range proofs for outputs and pseudo-outputs.
-    let pseudo_output_values_and_blindings: Vec<(u64, Scalar)> = input_secrets
+    let pseudo_output_values_and_blindings: Vec<(u64, Scalar)> = input_values_and_blindings
         .iter()
         .zip(pseudo_output_blindings.iter())
-        .map(|((_, value, _), blinding)| (*value, *blinding))
+        .map(|((value, _), blinding)| (*value, *blinding))
         .collect();
 
     let (range_proof, commitments) = {
@@ -329,18 +547,24 @@ fn sign_with_balance_check<CSPRNG: RngCore + CryptoRng>(
     // the input's value equals the value of the pseudo_output.
     let mut ring_signatures: Vec<RingMLSAG> = Vec::new();
     for i in 0..num_inputs {
-        let real_index = real_input_indices[i];
-        let (onetime_private_key, value, blinding) = input_secrets[i];
-        let ring_signature = RingMLSAG::sign(
-            &extended_message,
-            &rings[i],
-            real_index,
-            &onetime_private_key,
+        let (value, blinding) = input_values_and_blindings[i];
+        let signable_ring = SignableRing {
+            message: &extended_message,
+            ring: &rings[i],
+            real_index: real_input_indices[i],
+            key_image: input_key_images[i],
             value,
-            &blinding,
-            &pseudo_output_blindings[i],
-            rng,
-        )?;
+            blinding,
+            output_blinding: pseudo_output_blindings[i],
+        };
+        let ring_signature = signer.sign_ring(&signable_ring, rng)?;
+
+        // The signer may be out of process, so check that it signed what was
+        // asked of it.
+        if ring_signature.key_image != input_key_images[i] {
+            return Err(Error::InvalidKeyImage);
+        }
+        ring_signature.verify(&extended_message, &rings[i], &pseudo_output_commitments[i])?;
         ring_signatures.push(ring_signature);
     }
 
@@ -373,7 +597,9 @@ mod rct_bulletproofs_tests {
     use super::sign_with_balance_check;
     use crate::{
         range_proofs::generate_range_proofs,
-        ring_signature::{Error, KeyImage, SignatureRctBulletproofs},
+        ring_signature::{
+            Error, KeyImage, LocalRingSigner, SignatureRctBulletproofs, SignatureToVerify,
+        },
         CompressedCommitment,
     };
     use alloc::vec::Vec;
@@ -473,6 +699,30 @@ mod rct_bulletproofs_tests {
         }
     }
 
+    /// The arguments for `verify_batch` checking each signature against the
+    /// corresponding params.
+    fn to_verify<'a>(
+        signatures: &'a [SignatureRctBulletproofs],
+        params: &'a [SignatureParams],
+        output_commitments: &'a [Vec<CompressedCommitment>],
+        fee: u64,
+    ) -> Vec<SignatureToVerify<'a>> {
+        signatures
+            .iter()
+            .zip(params.iter())
+            .zip(output_commitments.iter())
+            .map(
+                |((signature, params), output_commitments)| SignatureToVerify {
+                    signature,
+                    message: &params.message,
+                    rings: &params.rings,
+                    output_commitments,
+                    fee,
+                },
+            )
+            .collect()
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(3))]
 
@@ -838,5 +1088,127 @@ mod rct_bulletproofs_tests {
 
         }
 
+        #[test]
+        // `sign_with_signer` should produce valid signatures using the keys held by a
+        // `RingSigner`, and fail if it does not hold one of them.
+        fn sign_with_signer_uses_signer_keys(
+            num_inputs in 1..8usize,
+            num_mixins in 1..17usize,
+            seed in any::<[u8; 32]>(),
+        ) {
+            let mut rng: StdRng = SeedableRng::from_seed(seed);
+            let params = SignatureParams::random(num_inputs, num_mixins, &mut rng);
+            let fee = 0;
+
+            let mut signer = LocalRingSigner::default();
+            let input_key_images: Vec<KeyImage> = params
+                .input_secrets
+                .iter()
+                .map(|(onetime_private_key, _, _)| signer.add_key(*onetime_private_key))
+                .collect();
+            let input_values_and_blindings: Vec<(u64, Scalar)> = params
+                .input_secrets
+                .iter()
+                .map(|(_, value, blinding)| (*value, *blinding))
+                .collect();
+
+            let signature = SignatureRctBulletproofs::sign_with_signer(
+                &params.message,
+                &params.rings,
+                &params.real_input_indices,
+                &input_key_images,
+                &input_values_and_blindings,
+                &params.output_values_and_blindings,
+                fee,
+                &signer,
+                &mut rng,
+            )
+            .unwrap();
+
+            let result = signature.verify(
+                &params.message,
+                &params.rings,
+                &params.get_output_commitments(),
+                fee,
+                &mut rng,
+            );
+            assert!(result.is_ok());
+            assert_eq!(signature.key_images(), input_key_images);
+
+            // A signer missing the last input's key.
+            let partial_signer: LocalRingSigner = params.input_secrets[..num_inputs - 1]
+                .iter()
+                .map(|(onetime_private_key, _, _)| *onetime_private_key)
+                .collect();
+            let result = SignatureRctBulletproofs::sign_with_signer(
+                &params.message,
+                &params.rings,
+                &params.real_input_indices,
+                &input_key_images,
+                &input_values_and_blindings,
+                &params.output_values_and_blindings,
+                fee,
+                &partial_signer,
+                &mut rng,
+            );
+            assert_eq!(result, Err(Error::UnknownKeyImage));
+        }
+
+        #[test]
+        // `verify_batch` should accept valid signatures, and reject the batch if any
+        // signature's range proof is wrong.
+        fn verify_batch_checks_each_signature(
+            num_signatures in 1..5usize,
+            num_inputs in 1..4usize,
+            num_mixins in 1..5usize,
+            seed in any::<[u8; 32]>(),
+        ) {
+            let mut rng: StdRng = SeedableRng::from_seed(seed);
+            let fee = 0;
+            let params: Vec<SignatureParams> = (0..num_signatures)
+                .map(|_| SignatureParams::random(num_inputs, num_mixins, &mut rng))
+                .collect();
+            let mut signatures: Vec<SignatureRctBulletproofs> = params
+                .iter()
+                .map(|params| {
+                    SignatureRctBulletproofs::sign(
+                        &params.message,
+                        &params.rings,
+                        &params.real_input_indices,
+                        &params.input_secrets,
+                        &params.output_values_and_blindings,
+                        fee,
+                        &mut rng,
+                    )
+                    .unwrap()
+                })
+                .collect();
+            let output_commitments: Vec<Vec<CompressedCommitment>> = params
+                .iter()
+                .map(|params| params.get_output_commitments())
+                .collect();
+
+            assert_eq!(
+                SignatureRctBulletproofs::verify_batch(&to_verify(&signatures, &params, &output_commitments, fee), &mut rng),
+                Ok(())
+            );
+
+            // Swap in a valid range proof for other commitments.
+            let wrong_range_proof = {
+                let values = vec![13; 2 * num_inputs];
+                let blindings: Vec<Scalar> = values
+                    .iter()
+                    .map(|_value| Scalar::random(&mut rng))
+                    .collect();
+                let (range_proof, _commitments) =
+                    generate_range_proofs(&values, &blindings, &mut rng).unwrap();
+                range_proof
+            };
+            let wrong_index = rng.next_u64() as usize % num_signatures;
+            signatures[wrong_index].range_proof_bytes = wrong_range_proof.to_bytes();
+
+            assert!(SignatureRctBulletproofs::verify_batch(&to_verify(&signatures, &params, &output_commitments, fee), &mut rng).is_err());
+        }
+
     } // end proptest
 }
@@ -0,0 +1,237 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! An abstraction over the holder of the spend authority for transaction
+//! inputs.
+//!
+//! Signing a transaction needs the onetime private key of each real input
+//! only to produce that input's MLSAG. A [`RingSigner`] produces those
+//! signatures on request, so the keys may live somewhere other than the
+//! process building the transaction, e.g. in a hardware wallet or a separate
+//! signing daemon.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use core::iter::FromIterator;
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate, RistrettoPublic};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::{
+    ring_signature::{Error, KeyImage, RingMLSAG, Scalar},
+    CompressedCommitment,
+};
+
+/// A ring to be signed, and everything except the spend authority needed to
+/// sign it.
+#[derive(Clone, Debug)]
+pub struct SignableRing<'a> {
+    /// The message to be signed.
+    pub message: &'a [u8],
+
+    /// A ring of input onetime addresses and amount commitments.
+    pub ring: &'a [(CompressedRistrettoPublic, CompressedCommitment)],
+
+    /// The index in the ring of the real input.
+    pub real_index: usize,
+
+    /// The key image of the real input's onetime private key, which
+    /// identifies the key the signer must use.
+    pub key_image: KeyImage,
+
+    /// Value of the real input.
+    pub value: u64,
+
+    /// Blinding of the real input.
+    pub blinding: Scalar,
+
+    /// Blinding of the pseudo-output paired with this ring.
+    pub output_blinding: Scalar,
+}
+
+/// Something which can produce an MLSAG for a ring on behalf of the owner of
+/// its real input.
+pub trait RingSigner {
+    /// Sign a ring, proving ownership of the real input and that its value
+    /// equals that of the pseudo-output.
+    ///
+    /// # Arguments
+    /// * `signable_ring` - The ring to sign.
+    /// * `rng` - Randomness.
+    fn sign_ring<CSPRNG: RngCore + CryptoRng>(
+        &self,
+        signable_ring: &SignableRing,
+        rng: &mut CSPRNG,
+    ) -> Result<RingMLSAG, Error>;
+}
+
+/// A [`RingSigner`] holding onetime private keys in process.
+#[derive(Clone, Default)]
+pub struct LocalRingSigner {
+    /// Onetime private keys, by their key images.
+    keys: BTreeMap<KeyImage, RistrettoPrivate>,
+}
+
+impl LocalRingSigner {
+    /// Add a onetime private key to this signer, returning its key image.
+    pub fn add_key(&mut self, onetime_private_key: RistrettoPrivate) -> KeyImage {
+        let key_image = KeyImage::from(&onetime_private_key);
+        self.keys.insert(key_image, onetime_private_key);
+        key_image
+    }
+
+    /// Whether this signer holds the key with the given key image.
+    pub fn contains(&self, key_image: &KeyImage) -> bool {
+        self.keys.contains_key(key_image)
+    }
+}
+
+impl FromIterator<RistrettoPrivate> for LocalRingSigner {
+    fn from_iter<I: IntoIterator<Item = RistrettoPrivate>>(iter: I) -> Self {
+        let mut signer = Self::default();
+        for onetime_private_key in iter {
+            signer.add_key(onetime_private_key);
+        }
+        signer
+    }
+}
+
+impl RingSigner for LocalRingSigner {
+    fn sign_ring<CSPRNG: RngCore + CryptoRng>(
+        &self,
+        signable_ring: &SignableRing,
+        rng: &mut CSPRNG,
+    ) -> Result<RingMLSAG, Error> {
+        let onetime_private_key = self
+            .keys
+            .get(&signable_ring.key_image)
+            .ok_or(Error::UnknownKeyImage)?;
+
+        // Refuse to sign a ring whose real input is not owned by this key.
+        let (target_key, _) = signable_ring
+            .ring
+            .get(signable_ring.real_index)
+            .ok_or(Error::IndexOutOfBounds)?;
+        if *target_key
+            != CompressedRistrettoPublic::from(RistrettoPublic::from(onetime_private_key))
+        {
+            return Err(Error::UnknownKeyImage);
+        }
+
+        RingMLSAG::sign(
+            signable_ring.message,
+            signable_ring.ring,
+            signable_ring.real_index,
+            onetime_private_key,
+            signable_ring.value,
+            &signable_ring.blinding,
+            &signable_ring.output_blinding,
+            rng,
+        )
+    }
+}
+
+impl Drop for LocalRingSigner {
+    fn drop(&mut self) {
+        for onetime_private_key in self.keys.values_mut() {
+            onetime_private_key.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod ring_signer_tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    extern crate std;
+
+    /// Returns a ring whose element at `real_index` is owned by
+    /// `onetime_private_key`, along with the real input's value and blinding.
+    fn get_ring(
+        ring_size: usize,
+        real_index: usize,
+        onetime_private_key: &RistrettoPrivate,
+        rng: &mut StdRng,
+    ) -> (
+        Vec<(CompressedRistrettoPublic, CompressedCommitment)>,
+        u64,
+        Scalar,
+    ) {
+        let value = 1234;
+        let blinding = Scalar::random(rng);
+        let ring = (0..ring_size)
+            .map(|i| {
+                if i == real_index {
+                    let target_key =
+                        CompressedRistrettoPublic::from(RistrettoPublic::from(onetime_private_key));
+                    let commitment = CompressedCommitment::new(value, blinding);
+                    (target_key, commitment)
+                } else {
+                    let target_key =
+                        CompressedRistrettoPublic::from(RistrettoPublic::from_random(rng));
+                    let commitment = CompressedCommitment::new(rng.next_u64(), Scalar::random(rng));
+                    (target_key, commitment)
+                }
+            })
+            .collect();
+        (ring, value, blinding)
+    }
+
+    #[test]
+    /// A local signer produces valid signatures for the keys it holds, and
+    /// refuses to sign for any other key.
+    fn local_signer_signs_held_keys() {
+        let mut rng: StdRng = SeedableRng::from_seed([17u8; 32]);
+        let onetime_private_key = RistrettoPrivate::from_random(&mut rng);
+        let real_index = 2;
+        let (ring, value, blinding) = get_ring(5, real_index, &onetime_private_key, &mut rng);
+        let output_blinding = Scalar::random(&mut rng);
+        let message = [7u8; 32];
+
+        let mut signer = LocalRingSigner::default();
+        let key_image = signer.add_key(onetime_private_key);
+        assert!(signer.contains(&key_image));
+
+        let signable_ring = SignableRing {
+            message: &message,
+            ring: &ring,
+            real_index,
+            key_image,
+            value,
+            blinding,
+            output_blinding,
+        };
+        let signature = signer.sign_ring(&signable_ring, &mut rng).unwrap();
+        assert_eq!(signature.key_image, key_image);
+
+        let output_commitment = CompressedCommitment::new(value, output_blinding);
+        assert!(signature
+            .verify(&message, &ring, &output_commitment)
+            .is_ok());
+
+        // A key image the signer does not hold.
+        let other_key_image = KeyImage::from(&RistrettoPrivate::from_random(&mut rng));
+        let signable_ring = SignableRing {
+            key_image: other_key_image,
+            ..signable_ring
+        };
+        assert_eq!(
+            signer.sign_ring(&signable_ring, &mut rng),
+            Err(Error::UnknownKeyImage)
+        );
+
+        // A ring whose real input belongs to some other key.
+        let signable_ring = SignableRing {
+            key_image,
+            real_index: 0,
+            ..signable_ring
+        };
+        assert_eq!(
+            signer.sign_ring(&signable_ring, &mut rng),
+            Err(Error::UnknownKeyImage)
+        );
+    }
+}
@@ -4,4 +4,6 @@ mod error;
 mod validate;
 
 pub use error::{TransactionValidationError, TransactionValidationResult};
-pub use validate::{validate, validate_signature, validate_tombstone};
+pub use validate::{
+    validate, validate_batch, validate_signature, validate_signatures_batch, validate_tombstone,
+};
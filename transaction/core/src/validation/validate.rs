@@ -10,9 +10,13 @@ use super::error::{TransactionValidationError, TransactionValidationResult};
 use crate::{
     constants::*,
     membership_proofs::{derive_proof_at_index, is_membership_proof_valid},
+    range_proofs::check_range_proofs_batch,
+    ring_signature::{SignatureRctBulletproofs, SignatureToVerify},
     tx::{Tx, TxOut, TxOutMembershipProof, TxPrefix},
     CompressedCommitment,
 };
+use bulletproofs::RangeProof;
+use curve25519_dalek::ristretto::CompressedRistretto;
 use mc_common::HashSet;
 use mc_crypto_keys::CompressedRistrettoPublic;
 use rand_core::{CryptoRng, RngCore};
@@ -34,6 +38,104 @@ pub fn validate<R: RngCore + CryptoRng>(
     minimum_fee: u64,
     csprng: &mut R,
 ) -> TransactionValidationResult<()> {
+    validate_with_signature_check(tx, current_block_index, root_proofs, minimum_fee, |tx| {
+        validate_signature(tx, csprng)
+    })
+}
+
+/// Determines if each of the transactions is valid, with respect to the
+/// provided context.
+///
+/// This returns the same result as calling `validate` on each transaction in
+/// turn and stopping at the first error, but checks the transactions' range
+/// proofs together with `check_range_proofs_batch`. If that fails, the range
+/// proofs are bisected to find the first invalid one. Only the first invalid
+/// transaction is validated again, to find its error.
+///
+/// # Arguments
+/// * `txs_with_proofs` - Pending transactions, each with membership proofs for
+///   its input ring elements.
+/// * `current_block_index` - The index of the current block that is being
+///   built.
+/// * `minimum_fee` - The minimum fee each transaction must pay.
+/// * `csprng` - Cryptographically secure random number generator.
+pub fn validate_batch<R: RngCore + CryptoRng>(
+    txs_with_proofs: &[(Tx, Vec<TxOutMembershipProof>)],
+    current_block_index: u64,
+    minimum_fee: u64,
+    csprng: &mut R,
+) -> TransactionValidationResult<()> {
+    // The first transaction failing any check other than its range proof, and
+    // the unchecked range proofs of the transactions before it.
+    let mut range_proofs: Vec<(RangeProof, Vec<CompressedRistretto>)> =
+        Vec::with_capacity(txs_with_proofs.len());
+    let first_invalid = txs_with_proofs.iter().position(|(tx, root_proofs)| {
+        validate_with_signature_check(tx, current_block_index, root_proofs, minimum_fee, |tx| {
+            let (message, rings, output_commitments) = signed_data(tx);
+            let range_proof = tx
+                .signature
+                .verify_except_range_proof(&message, &rings, &output_commitments, tx.prefix.fee)
+                .map_err(TransactionValidationError::InvalidTransactionSignature)?;
+            range_proofs.push(range_proof);
+            Ok(())
+        })
+        .is_err()
+    });
+    // The first invalid transaction may have failed after its signature check.
+    range_proofs.truncate(first_invalid.unwrap_or(txs_with_proofs.len()));
+
+    // Only range proofs before it can produce an earlier error.
+    let first_invalid = first_invalid_range_proof(&range_proofs, csprng).or(first_invalid);
+
+    match first_invalid {
+        Some(index) => {
+            let (tx, root_proofs) = &txs_with_proofs[index];
+            validate(tx, current_block_index, root_proofs, minimum_fee, csprng)
+        }
+        None => Ok(()),
+    }
+}
+
+/// The index of the first invalid range proof, if any, found by bisecting
+/// with `check_range_proofs_batch`.
+fn first_invalid_range_proof<R: RngCore + CryptoRng>(
+    range_proofs: &[(RangeProof, Vec<CompressedRistretto>)],
+    rng: &mut R,
+) -> Option<usize> {
+    let range_proofs: Vec<(&RangeProof, &[CompressedRistretto])> = range_proofs
+        .iter()
+        .map(|(range_proof, commitments)| (range_proof, &commitments[..]))
+        .collect();
+    if check_range_proofs_batch(&range_proofs, rng).is_ok() {
+        return None;
+    }
+
+    // range_proofs[start..end] always contains an invalid range proof.
+    let mut start = 0;
+    let mut end = range_proofs.len();
+    while end - start > 1 {
+        let mid = start + (end - start) / 2;
+        if check_range_proofs_batch(&range_proofs[start..mid], rng).is_err() {
+            end = mid;
+        } else {
+            start = mid;
+        }
+    }
+    Some(start)
+}
+
+/// Determines if the transaction is valid, checking its signature with
+/// `check_signature`.
+fn validate_with_signature_check<F>(
+    tx: &Tx,
+    current_block_index: u64,
+    root_proofs: &[TxOutMembershipProof],
+    minimum_fee: u64,
+    check_signature: F,
+) -> TransactionValidationResult<()>
+where
+    F: FnOnce(&Tx) -> TransactionValidationResult<()>,
+{
     validate_number_of_inputs(&tx.prefix, MAX_INPUTS)?;
 
     validate_number_of_outputs(&tx.prefix, MAX_OUTPUTS)?;
@@ -48,7 +150,7 @@ pub fn validate<R: RngCore + CryptoRng>(
 
     validate_membership_proofs(&tx.prefix, &root_proofs)?;
 
-    validate_signature(&tx, csprng)?;
+    check_signature(tx)?;
 
     validate_transaction_fee(&tx, minimum_fee)?;
 
@@ -202,6 +304,52 @@ pub fn validate_signature<R: RngCore + CryptoRng>(
     tx: &Tx,
     rng: &mut R,
 ) -> TransactionValidationResult<()> {
+    let (message, rings, output_commitments) = signed_data(tx);
+
+    tx.signature
+        .verify(&message, &rings, &output_commitments, tx.prefix.fee, rng)
+        .map_err(TransactionValidationError::InvalidTransactionSignature)
+}
+
+/// Each transaction must have a valid signature, as checked by
+/// `validate_signature`.
+///
+/// The signatures' range proofs are checked together, so an error does not
+/// identify the transaction at fault.
+pub fn validate_signatures_batch<'a, R: RngCore + CryptoRng>(
+    txs: impl IntoIterator<Item = &'a Tx>,
+    rng: &mut R,
+) -> TransactionValidationResult<()> {
+    let txs: Vec<&Tx> = txs.into_iter().collect();
+    let signed: Vec<_> = txs.iter().map(|tx| signed_data(tx)).collect();
+    let signatures: Vec<SignatureToVerify> = txs
+        .iter()
+        .zip(signed.iter())
+        .map(
+            |(tx, (message, rings, output_commitments))| SignatureToVerify {
+                signature: &tx.signature,
+                message,
+                rings,
+                output_commitments,
+                fee: tx.prefix.fee,
+            },
+        )
+        .collect();
+
+    SignatureRctBulletproofs::verify_batch(&signatures, rng)
+        .map_err(TransactionValidationError::InvalidTransactionSignature)
+}
+
+/// The message signed by a transaction's signature, its rings, and its output
+/// commitments.
+#[allow(clippy::type_complexity)]
+fn signed_data(
+    tx: &Tx,
+) -> (
+    [u8; 32],
+    Vec<Vec<(CompressedRistrettoPublic, CompressedCommitment)>>,
+    Vec<CompressedCommitment>,
+) {
     let rings: Vec<Vec<(CompressedRistrettoPublic, CompressedCommitment)>> = tx
         .prefix
         .inputs
@@ -218,11 +366,9 @@ pub fn validate_signature<R: RngCore + CryptoRng>(
     let output_commitments = tx.prefix.output_commitments();
 
     let tx_prefix_hash = tx.prefix.hash();
-    let message = tx_prefix_hash.as_bytes();
+    let message = *tx_prefix_hash.as_bytes();
 
-    tx.signature
-        .verify(message, &rings, &output_commitments, tx.prefix.fee, rng)
-        .map_err(TransactionValidationError::InvalidTransactionSignature)
+    (message, rings, output_commitments)
 }
 
 /// The fee amount must be greater than or equal to the given minimum fee.
@@ -371,7 +517,7 @@ pub fn validate_tombstone(
 mod tests {
     extern crate alloc;
 
-    use alloc::vec::Vec;
+    use alloc::{vec, vec::Vec};
 
     use crate::{
         constants::{MINIMUM_FEE, RING_SIZE},
@@ -379,18 +525,22 @@ mod tests {
         validation::{
             error::TransactionValidationError,
             validate::{
-                validate_inputs_are_sorted, validate_key_images_are_unique,
-                validate_membership_proofs, validate_number_of_inputs, validate_number_of_outputs,
+                first_invalid_range_proof, validate, validate_batch, validate_inputs_are_sorted,
+                validate_key_images_are_unique, validate_membership_proofs,
+                validate_number_of_inputs, validate_number_of_outputs,
                 validate_outputs_public_keys_are_unique, validate_ring_elements_are_unique,
-                validate_ring_sizes, validate_signature, validate_tombstone,
-                validate_transaction_fee, MAX_TOMBSTONE_BLOCKS,
+                validate_ring_sizes, validate_signature, validate_signatures_batch,
+                validate_tombstone, validate_transaction_fee, MAX_TOMBSTONE_BLOCKS,
             },
         },
     };
 
     use crate::{
-        membership_proofs::Range, validation::validate::validate_ring_elements_are_sorted,
+        membership_proofs::Range, range_proofs::generate_range_proofs,
+        validation::validate::validate_ring_elements_are_sorted,
     };
+    use bulletproofs::RangeProof;
+    use curve25519_dalek::{ristretto::CompressedRistretto, scalar::Scalar};
     use mc_crypto_keys::{CompressedRistrettoPublic, ReprBytes};
     use mc_ledger_db::{Ledger, LedgerDB};
     use mc_transaction_core_test_utils::{
@@ -865,6 +1015,100 @@ mod tests {
         }
     }
 
+    #[test]
+    // `validate_signatures_batch` returns OK if every transaction is valid.
+    fn test_validate_signatures_batch_ok() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let (tx, _ledger) = create_test_tx();
+        let (other_tx, _ledger) =
+            create_test_tx_with_amount(INITIALIZE_LEDGER_AMOUNT - MINIMUM_FEE, MINIMUM_FEE);
+        assert_eq!(validate_signatures_batch(&[tx, other_tx], &mut rng), Ok(()));
+        assert_eq!(validate_signatures_batch(&[], &mut rng), Ok(()));
+    }
+
+    #[test]
+    // `validate_signatures_batch` should return InvalidTransactionSignature if any
+    // transaction's signature is invalid.
+    fn test_validate_signatures_batch_err_modified_fee() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let (tx, _ledger) = create_test_tx();
+        let (mut other_tx, _ledger) =
+            create_test_tx_with_amount(INITIALIZE_LEDGER_AMOUNT - MINIMUM_FEE, MINIMUM_FEE);
+        other_tx.prefix.fee = other_tx.prefix.fee + 1;
+
+        match validate_signatures_batch(&[tx, other_tx], &mut rng) {
+            Err(TransactionValidationError::InvalidTransactionSignature(_e)) => {} // Expected.
+            Err(e) => {
+                panic!("Unexpected error {}", e);
+            }
+            Ok(()) => panic!("Unexpected success"),
+        }
+    }
+
+    #[test]
+    // `first_invalid_range_proof` should find the first invalid range proof.
+    fn test_first_invalid_range_proof() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let blindings = vec![Scalar::random(&mut rng), Scalar::random(&mut rng)];
+        let (range_proof, commitments) =
+            generate_range_proofs(&[17, 42], &blindings, &mut rng).unwrap();
+        let valid = (range_proof.clone(), commitments.clone());
+        let invalid = (range_proof, commitments.into_iter().rev().collect());
+
+        assert_eq!(first_invalid_range_proof(&[], &mut rng), None);
+        assert_eq!(
+            first_invalid_range_proof(&[valid.clone(), valid.clone(), valid.clone()], &mut rng),
+            None
+        );
+        assert_eq!(
+            first_invalid_range_proof(&[invalid.clone()], &mut rng),
+            Some(0)
+        );
+        assert_eq!(
+            first_invalid_range_proof(
+                &[
+                    valid.clone(),
+                    valid.clone(),
+                    invalid.clone(),
+                    valid.clone(),
+                    invalid.clone()
+                ],
+                &mut rng
+            ),
+            Some(2)
+        );
+        let mut range_proofs = vec![valid; 5];
+        range_proofs.push(invalid);
+        assert_eq!(first_invalid_range_proof(&range_proofs, &mut rng), Some(5));
+    }
+
+    #[test]
+    // `validate_batch` should return the first error that `validate` returns for
+    // the transactions.
+    fn test_validate_batch_returns_first_error() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let (tx, _ledger) = create_test_tx();
+        let (mut other_tx, _ledger) =
+            create_test_tx_with_amount(INITIALIZE_LEDGER_AMOUNT - MINIMUM_FEE, MINIMUM_FEE);
+        other_tx.prefix.inputs.clear();
+
+        // Neither transaction has membership proofs.
+        let txs_with_proofs = vec![(tx.clone(), Vec::new()), (other_tx.clone(), Vec::new())];
+        let expected = validate(&tx, 1, &[], MINIMUM_FEE, &mut rng);
+        assert!(expected.is_err());
+        assert_eq!(
+            validate_batch(&txs_with_proofs, 1, MINIMUM_FEE, &mut rng),
+            expected
+        );
+
+        let txs_with_proofs = vec![(other_tx, Vec::new()), (tx, Vec::new())];
+        assert_eq!(
+            validate_batch(&txs_with_proofs, 1, MINIMUM_FEE, &mut rng),
+            Err(TransactionValidationError::NoInputs)
+        );
+        assert_eq!(validate_batch(&[], 1, MINIMUM_FEE, &mut rng), Ok(()));
+    }
+
     #[test]
     fn test_validate_transaction_fee() {
         {
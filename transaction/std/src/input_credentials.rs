@@ -2,7 +2,10 @@
 
 use crate::TxBuilderError;
 use mc_crypto_keys::{RistrettoPrivate, RistrettoPublic};
-use mc_transaction_core::tx::{TxOut, TxOutMembershipProof};
+use mc_transaction_core::{
+    ring_signature::KeyImage,
+    tx::{TxOut, TxOutMembershipProof},
+};
 use std::convert::TryFrom;
 use zeroize::Zeroize;
 
@@ -18,8 +21,12 @@ pub struct InputCredentials {
     /// Index in `ring` of the "real" output being spent.
     pub real_index: usize,
 
-    /// Private key for the "real" output being spent.
-    pub onetime_private_key: RistrettoPrivate,
+    /// Private key for the "real" output being spent, if it is held in
+    /// process rather than by a separate `RingSigner`.
+    pub onetime_private_key: Option<RistrettoPrivate>,
+
+    /// Key image of the "real" output being spent.
+    pub key_image: KeyImage,
 
     /// Public key of the transaction that created the "real" output being
     /// spent.
@@ -46,6 +53,37 @@ impl InputCredentials {
         real_index: usize,
         onetime_private_key: RistrettoPrivate,
         view_private_key: RistrettoPrivate,
+    ) -> Result<Self, TxBuilderError> {
+        let key_image = KeyImage::from(&onetime_private_key);
+        let mut input_credentials = Self::new_with_key_image(
+            ring,
+            membership_proofs,
+            real_index,
+            key_image,
+            view_private_key,
+        )?;
+        input_credentials.onetime_private_key = Some(onetime_private_key);
+        Ok(input_credentials)
+    }
+
+    /// Creates an InputCredential instance for an input whose onetime private
+    /// key is held by a `RingSigner`, which must be used to sign the
+    /// transaction.
+    ///
+    /// # Arguments
+    /// * `ring` - A "ring" of transaction outputs.
+    /// * `membership_proofs` - Proof that each TxOut in `ring` is in the
+    ///   ledger.
+    /// * `real_index` - Index in `ring` of the output being spent.
+    /// * `key_image` - Key image of the output being spent.
+    /// * `view_private_key` - The view private key belonging to the owner of
+    ///   the real output.
+    pub fn new_with_key_image(
+        ring: Vec<TxOut>,
+        membership_proofs: Vec<TxOutMembershipProof>,
+        real_index: usize,
+        key_image: KeyImage,
+        view_private_key: RistrettoPrivate,
     ) -> Result<Self, TxBuilderError> {
         debug_assert_eq!(ring.len(), membership_proofs.len());
 
@@ -82,7 +120,8 @@ impl InputCredentials {
             ring,
             membership_proofs,
             real_index,
-            onetime_private_key,
+            onetime_private_key: None,
+            key_image,
             real_output_public_key,
             view_private_key,
         })
@@ -91,7 +130,9 @@ impl InputCredentials {
 
 impl Zeroize for InputCredentials {
     fn zeroize(&mut self) {
-        self.onetime_private_key.zeroize();
+        if let Some(onetime_private_key) = self.onetime_private_key.as_mut() {
+            onetime_private_key.zeroize();
+        }
         self.view_private_key.zeroize();
     }
 }
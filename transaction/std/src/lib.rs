@@ -3,7 +3,12 @@
 mod error;
 mod input_credentials;
 mod transaction_builder;
+#[cfg(unix)]
+mod unix_socket_signer;
 
 pub use error::TxBuilderError;
 pub use input_credentials::InputCredentials;
+pub use mc_transaction_core::ring_signature::{LocalRingSigner, RingSigner, SignableRing};
 pub use transaction_builder::TransactionBuilder;
+#[cfg(unix)]
+pub use unix_socket_signer::{RemoteSignerError, UnixSocketRingSigner, UnixSocketRingSignerServer};
@@ -14,7 +14,7 @@ use mc_transaction_core::{
     encrypted_fog_hint::EncryptedFogHint,
     fog_hint::FogHint,
     onetime_keys::create_shared_secret,
-    ring_signature::SignatureRctBulletproofs,
+    ring_signature::{KeyImage, LocalRingSigner, RingSigner, SignatureRctBulletproofs},
     tx::{Tx, TxIn, TxOut, TxOutConfirmationNumber, TxPrefix},
     CompressedCommitment,
 };
@@ -149,8 +149,28 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
         self.fee = fee;
     }
 
-    /// Consume the builder and return the transaction.
-    pub fn build<RNG: CryptoRng + RngCore>(mut self, rng: &mut RNG) -> Result<Tx, TxBuilderError> {
+    /// Consume the builder and return the transaction, signed with the
+    /// onetime private keys held by the input credentials.
+    pub fn build<RNG: CryptoRng + RngCore>(self, rng: &mut RNG) -> Result<Tx, TxBuilderError> {
+        let signer: LocalRingSigner = self
+            .input_credentials
+            .iter()
+            .filter_map(|input_credential| input_credential.onetime_private_key)
+            .collect();
+        self.build_with_signer(&signer, rng)
+    }
+
+    /// Consume the builder and return the transaction, using `signer` to sign
+    /// each input's ring.
+    ///
+    /// # Arguments
+    /// * `signer` - Holder of the onetime private key of each input.
+    /// * `rng` - Randomness.
+    pub fn build_with_signer<S: RingSigner, RNG: CryptoRng + RngCore>(
+        mut self,
+        signer: &S,
+        rng: &mut RNG,
+    ) -> Result<Tx, TxBuilderError> {
         if self.input_credentials.is_empty() {
             return Err(TxBuilderError::NoInputs);
         }
@@ -219,27 +239,34 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
             .map(|input_credential| input_credential.real_index)
             .collect();
 
-        // One-time private key, amount value, and amount blinding for each real input.
-        let mut input_secrets: Vec<(RistrettoPrivate, u64, Scalar)> = Vec::new();
+        let input_key_images: Vec<KeyImage> = self
+            .input_credentials
+            .iter()
+            .map(|input_credential| input_credential.key_image)
+            .collect();
+
+        // Amount value and amount blinding for each real input.
+        let mut input_values_and_blindings: Vec<(u64, Scalar)> = Vec::new();
         for input_credential in &self.input_credentials {
-            let onetime_private_key = input_credential.onetime_private_key;
             let amount = &input_credential.ring[input_credential.real_index].amount;
             let shared_secret = create_shared_secret(
                 &input_credential.real_output_public_key,
                 &input_credential.view_private_key,
             );
             let (value, blinding) = amount.get_value(&shared_secret)?;
-            input_secrets.push((onetime_private_key, value, blinding));
+            input_values_and_blindings.push((value, blinding));
         }
 
         let message = tx_prefix.hash().0;
-        let signature = SignatureRctBulletproofs::sign(
+        let signature = SignatureRctBulletproofs::sign_with_signer(
             &message,
             &rings,
             &real_input_indices,
-            &input_secrets,
+            &input_key_images,
+            &input_values_and_blindings,
             &output_values_and_blindings,
             self.fee,
+            signer,
             rng,
         )?;
 
@@ -308,7 +335,6 @@ pub mod transaction_builder_tests {
     use mc_transaction_core::{
         constants::{MAX_INPUTS, MAX_OUTPUTS, MILLIMOB_TO_PICOMOB},
         onetime_keys::*,
-        ring_signature::KeyImage,
         tx::TxOutMembershipProof,
         validation::validate_signature,
     };
@@ -462,7 +488,7 @@ pub mod transaction_builder_tests {
         let input_credentials = get_input_credentials(&sender, value, &mut rng);
 
         let membership_proofs = input_credentials.membership_proofs.clone();
-        let key_image = KeyImage::from(&input_credentials.onetime_private_key);
+        let key_image = input_credentials.key_image;
 
         let mut transaction_builder = TransactionBuilder::new(MockFogResolver::default());
 
@@ -521,7 +547,7 @@ pub mod transaction_builder_tests {
         let input_credentials = get_input_credentials(&sender, value, &mut rng);
 
         let membership_proofs = input_credentials.membership_proofs.clone();
-        let key_image = KeyImage::from(&input_credentials.onetime_private_key);
+        let key_image = input_credentials.key_image;
 
         let fog_resolver = MockFogResolver(btreemap! {
                             recipient
@@ -723,6 +749,54 @@ pub mod transaction_builder_tests {
         }
     }
 
+    #[test]
+    // `build_with_signer` should sign inputs whose onetime private keys are only
+    // held by the signer.
+    fn test_build_with_signer() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let sender = AccountKey::random(&mut rng);
+        let recipient = AccountKey::random(&mut rng);
+        let value = 1475 * MILLIMOB_TO_PICOMOB;
+
+        let mut signer = LocalRingSigner::default();
+        let mut transaction_builder = TransactionBuilder::new(MockFogResolver::default());
+        let mut key_images = Vec::new();
+        for _i in 0..2 {
+            let input_credentials = get_input_credentials(&sender, value, &mut rng);
+            let key_image = signer.add_key(input_credentials.onetime_private_key.unwrap());
+            assert_eq!(key_image, input_credentials.key_image);
+            key_images.push(key_image);
+
+            // Only pass the key image to the builder.
+            let input_credentials = InputCredentials::new_with_key_image(
+                input_credentials.ring.clone(),
+                input_credentials.membership_proofs.clone(),
+                input_credentials.real_index,
+                key_image,
+                *sender.view_private_key(),
+            )
+            .unwrap();
+            assert!(input_credentials.onetime_private_key.is_none());
+            transaction_builder.add_input(input_credentials);
+        }
+        transaction_builder
+            .add_output(
+                2 * value - MINIMUM_FEE,
+                &recipient.default_subaddress(),
+                &mut rng,
+            )
+            .unwrap();
+
+        let tx = transaction_builder
+            .build_with_signer(&signer, &mut rng)
+            .unwrap();
+        let mut tx_key_images = tx.key_images();
+        tx_key_images.sort();
+        key_images.sort();
+        assert_eq!(tx_key_images, key_images);
+        assert!(validate_signature(&tx, &mut rng).is_ok());
+    }
+
     #[test]
     // `build` should succeed with MAX_INPUTS and MAX_OUTPUTS.
     fn test_max_transaction_size() {
@@ -0,0 +1,435 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! A reference out-of-process `RingSigner`, speaking to a signing daemon over
+//! a Unix domain socket.
+//!
+//! Each request and response is a protobuf message preceded by its length as
+//! a little-endian u32. The client opens a new connection for each ring it
+//! needs signed; the server answers any number of requests on a connection.
+//!
+//! The server's socket is only accessible to its owner, and the server only
+//! answers clients running as an allowed user (by default, its own).
+
+use failure::Fail;
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_transaction_core::{
+    ring_signature::{CurveScalar, Error, KeyImage, RingMLSAG, RingSigner, SignableRing},
+    CompressedCommitment,
+};
+use prost::Message;
+use rand_core::{CryptoRng, RngCore};
+use std::{
+    fs::{self, Permissions},
+    io::{self, Read, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        io::AsRawFd,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+};
+
+/// The largest request or response accepted, in bytes.
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+/// An error talking to a signing daemon.
+#[derive(Debug, Fail)]
+pub enum RemoteSignerError {
+    #[fail(display = "IO error: {}", _0)]
+    Io(io::Error),
+
+    #[fail(display = "Decode error: {}", _0)]
+    Decode(prost::DecodeError),
+
+    #[fail(display = "Message of {} bytes is too large", _0)]
+    MessageTooLarge(usize),
+
+    #[fail(display = "Ring signer failed: {}", _0)]
+    Signer(String),
+
+    #[fail(display = "Peer with uid {} is not allowed to request signatures", _0)]
+    UnauthorizedPeer(u32),
+}
+
+impl From<io::Error> for RemoteSignerError {
+    fn from(src: io::Error) -> Self {
+        RemoteSignerError::Io(src)
+    }
+}
+
+impl From<prost::DecodeError> for RemoteSignerError {
+    fn from(src: prost::DecodeError) -> Self {
+        RemoteSignerError::Decode(src)
+    }
+}
+
+/// A ring element on the wire.
+#[derive(Clone, Message)]
+struct RingElement {
+    #[prost(message, required, tag = "1")]
+    target_key: CompressedRistrettoPublic,
+
+    #[prost(message, required, tag = "2")]
+    commitment: CompressedCommitment,
+}
+
+/// A `SignableRing` on the wire.
+#[derive(Clone, Message)]
+struct SignRingRequest {
+    #[prost(bytes, tag = "1")]
+    message: Vec<u8>,
+
+    #[prost(message, repeated, tag = "2")]
+    ring: Vec<RingElement>,
+
+    #[prost(uint64, tag = "3")]
+    real_index: u64,
+
+    #[prost(message, required, tag = "4")]
+    key_image: KeyImage,
+
+    #[prost(fixed64, tag = "5")]
+    value: u64,
+
+    #[prost(message, required, tag = "6")]
+    blinding: CurveScalar,
+
+    #[prost(message, required, tag = "7")]
+    output_blinding: CurveScalar,
+}
+
+/// The signer's answer: a signature, or a description of why it could not
+/// produce one.
+#[derive(Clone, Message)]
+struct SignRingResponse {
+    #[prost(message, optional, tag = "1")]
+    signature: Option<RingMLSAG>,
+
+    #[prost(string, tag = "2")]
+    error: String,
+}
+
+fn write_message<M: Message>(
+    stream: &mut UnixStream,
+    message: &M,
+) -> Result<(), RemoteSignerError> {
+    let bytes = mc_util_serial::encode(message);
+    if bytes.len() > MAX_MESSAGE_SIZE {
+        return Err(RemoteSignerError::MessageTooLarge(bytes.len()));
+    }
+    stream.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    stream.write_all(&bytes)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn read_message<M: Message + Default>(stream: &mut UnixStream) -> Result<M, RemoteSignerError> {
+    let mut len_bytes = [0u8; 4];
+    stream.read_exact(&mut len_bytes)?;
+    let len = u32::from_le_bytes(len_bytes) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(RemoteSignerError::MessageTooLarge(len));
+    }
+    let mut bytes = vec![0u8; len];
+    stream.read_exact(&mut bytes)?;
+    Ok(mc_util_serial::decode(&bytes)?)
+}
+
+/// A `RingSigner` which forwards each ring to a signing daemon listening on a
+/// Unix domain socket.
+#[derive(Clone, Debug)]
+pub struct UnixSocketRingSigner {
+    /// Path of the daemon's socket.
+    path: PathBuf,
+}
+
+impl UnixSocketRingSigner {
+    /// Create a signer talking to the daemon listening at `path`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Ask the daemon to sign a ring, reporting why if it could not.
+    pub fn request_signature(
+        &self,
+        signable_ring: &SignableRing,
+    ) -> Result<RingMLSAG, RemoteSignerError> {
+        let request = SignRingRequest {
+            message: signable_ring.message.to_vec(),
+            ring: signable_ring
+                .ring
+                .iter()
+                .map(|(target_key, commitment)| RingElement {
+                    target_key: *target_key,
+                    commitment: *commitment,
+                })
+                .collect(),
+            real_index: signable_ring.real_index as u64,
+            key_image: signable_ring.key_image,
+            value: signable_ring.value,
+            blinding: CurveScalar::from(signable_ring.blinding),
+            output_blinding: CurveScalar::from(signable_ring.output_blinding),
+        };
+
+        let mut stream = UnixStream::connect(&self.path)?;
+        write_message(&mut stream, &request)?;
+        let response: SignRingResponse = read_message(&mut stream)?;
+        response
+            .signature
+            .ok_or(RemoteSignerError::Signer(response.error))
+    }
+}
+
+impl RingSigner for UnixSocketRingSigner {
+    fn sign_ring<CSPRNG: RngCore + CryptoRng>(
+        &self,
+        signable_ring: &SignableRing,
+        _rng: &mut CSPRNG,
+    ) -> Result<RingMLSAG, Error> {
+        self.request_signature(signable_ring)
+            .map_err(|_| Error::SignerFailed)
+    }
+}
+
+/// A signing daemon, answering requests from `UnixSocketRingSigner`s with
+/// another `RingSigner`, typically a `LocalRingSigner` holding the keys.
+pub struct UnixSocketRingSignerServer<S: RingSigner> {
+    listener: UnixListener,
+    signer: S,
+    allowed_uid: u32,
+}
+
+impl<S: RingSigner> UnixSocketRingSignerServer<S> {
+    /// Listen for requests on a new socket at `path`, which is made readable
+    /// and writable only by its owner. Only clients running as the same user
+    /// as the server are answered.
+    pub fn bind(path: impl AsRef<Path>, signer: S) -> io::Result<Self> {
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, Permissions::from_mode(0o600))?;
+        Ok(Self {
+            listener,
+            signer,
+            // Safety: geteuid cannot fail.
+            allowed_uid: unsafe { libc::geteuid() },
+        })
+    }
+
+    /// Answer clients running as `uid` instead of the server's own user.
+    ///
+    /// The socket itself remains accessible only to its owner, so the socket
+    /// (or the directory containing it) must be shared with that user too.
+    pub fn allowed_uid(mut self, uid: u32) -> Self {
+        self.allowed_uid = uid;
+        self
+    }
+
+    /// Answer requests until the listener fails, one connection at a time.
+    ///
+    /// A connection which sends a malformed request is closed, without
+    /// affecting other connections.
+    pub fn serve<CSPRNG: RngCore + CryptoRng>(&self, rng: &mut CSPRNG) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            // The client will see the connection close.
+            let _ = self.handle_connection(stream?, rng);
+        }
+        Ok(())
+    }
+
+    /// Answer requests on a single connection until the client closes it.
+    pub fn handle_connection<CSPRNG: RngCore + CryptoRng>(
+        &self,
+        mut stream: UnixStream,
+        rng: &mut CSPRNG,
+    ) -> Result<(), RemoteSignerError> {
+        let peer_uid = peer_uid(&stream)?;
+        if peer_uid != self.allowed_uid {
+            return Err(RemoteSignerError::UnauthorizedPeer(peer_uid));
+        }
+
+        loop {
+            let request: SignRingRequest = match read_message(&mut stream) {
+                Ok(request) => request,
+                Err(RemoteSignerError::Io(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(())
+                }
+                Err(err) => return Err(err),
+            };
+
+            let ring: Vec<(CompressedRistrettoPublic, CompressedCommitment)> = request
+                .ring
+                .iter()
+                .map(|element| (element.target_key, element.commitment))
+                .collect();
+            let signable_ring = SignableRing {
+                message: &request.message,
+                ring: &ring,
+                real_index: request.real_index as usize,
+                key_image: request.key_image,
+                value: request.value,
+                blinding: request.blinding.scalar,
+                output_blinding: request.output_blinding.scalar,
+            };
+
+            let response = match self.signer.sign_ring(&signable_ring, rng) {
+                Ok(signature) => SignRingResponse {
+                    signature: Some(signature),
+                    error: String::new(),
+                },
+                Err(err) => SignRingResponse {
+                    signature: None,
+                    error: err.to_string(),
+                },
+            };
+            write_message(&mut stream, &response)?;
+        }
+    }
+}
+
+/// Get the uid of the process on the other end of a Unix socket.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = core::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // Safety: cred and len describe a valid, writable ucred.
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(cred.uid)
+}
+
+/// Get the uid of the process on the other end of a Unix socket.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let mut uid = 0;
+    let mut gid = 0;
+    // Safety: uid and gid are valid and writable.
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(uid)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_crypto_keys::{RistrettoPrivate, RistrettoPublic};
+    use mc_transaction_core::ring_signature::{LocalRingSigner, Scalar};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::thread;
+    use tempdir::TempDir;
+
+    #[test]
+    // A remote signer should return the same signatures as the daemon's signer,
+    // and report the daemon's errors.
+    fn test_remote_signature() {
+        let mut rng: StdRng = SeedableRng::from_seed([3u8; 32]);
+        let onetime_private_key = RistrettoPrivate::from_random(&mut rng);
+        let mut local_signer = LocalRingSigner::default();
+        let key_image = local_signer.add_key(onetime_private_key);
+
+        let dir = TempDir::new("ring_signer").unwrap();
+        let path = dir.path().join("signer.sock");
+        let server = UnixSocketRingSignerServer::bind(&path, local_signer).unwrap();
+        thread::spawn(move || {
+            let mut rng: StdRng = SeedableRng::from_seed([4u8; 32]);
+            server.serve(&mut rng).unwrap();
+        });
+
+        let value = 17;
+        let blinding = Scalar::random(&mut rng);
+        let output_blinding = Scalar::random(&mut rng);
+        let ring: Vec<(CompressedRistrettoPublic, CompressedCommitment)> = (0..3)
+            .map(|i| {
+                if i == 1 {
+                    (
+                        CompressedRistrettoPublic::from(RistrettoPublic::from(
+                            &onetime_private_key,
+                        )),
+                        CompressedCommitment::new(value, blinding),
+                    )
+                } else {
+                    (
+                        CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng)),
+                        CompressedCommitment::new(rng.next_u64(), Scalar::random(&mut rng)),
+                    )
+                }
+            })
+            .collect();
+        let message = [9u8; 32];
+        let signable_ring = SignableRing {
+            message: &message,
+            ring: &ring,
+            real_index: 1,
+            key_image,
+            value,
+            blinding,
+            output_blinding,
+        };
+
+        let remote_signer = UnixSocketRingSigner::new(&path);
+        let signature = remote_signer.sign_ring(&signable_ring, &mut rng).unwrap();
+        assert_eq!(signature.key_image, key_image);
+        assert!(signature
+            .verify(
+                &message,
+                &ring,
+                &CompressedCommitment::new(value, output_blinding)
+            )
+            .is_ok());
+
+        // The daemon does not hold this key.
+        let signable_ring = SignableRing {
+            key_image: KeyImage::from(rng.next_u64()),
+            ..signable_ring
+        };
+        match remote_signer.request_signature(&signable_ring) {
+            Err(RemoteSignerError::Signer(msg)) => {
+                assert_eq!(msg, Error::UnknownKeyImage.to_string())
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+        assert_eq!(
+            remote_signer.sign_ring(&signable_ring, &mut rng),
+            Err(Error::SignerFailed)
+        );
+    }
+
+    #[test]
+    // The socket is private to its owner, and clients running as other users
+    // are not answered.
+    fn test_unauthorized_peer() {
+        let dir = TempDir::new("ring_signer").unwrap();
+        let path = dir.path().join("signer.sock");
+        let server = UnixSocketRingSignerServer::bind(&path, LocalRingSigner::default()).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        let our_uid = unsafe { libc::geteuid() };
+        let server = server.allowed_uid(our_uid.wrapping_add(1));
+        let (client, stream) = UnixStream::pair().unwrap();
+        let mut rng: StdRng = SeedableRng::from_seed([4u8; 32]);
+        match server.handle_connection(stream, &mut rng) {
+            Err(RemoteSignerError::UnauthorizedPeer(uid)) => assert_eq!(uid, our_uid),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        drop(client);
+    }
+}
@@ -7,7 +7,8 @@ use mc_util_build_script::Environment;
 use std::{ffi::OsStr, fs, path::PathBuf};
 
 /// Compile protobuf files into Rust code, and generate a mod.rs that references
-/// all the generated modules.
+/// all the generated modules, and defines a `file_descriptors()` function
+/// returning the descriptors of the compiled proto files.
 pub fn compile_protos_and_generate_mod_rs(proto_dirs: &[&str], proto_files: &[&str]) {
     let env = Environment::default();
 
@@ -28,22 +29,41 @@ pub fn compile_protos_and_generate_mod_rs(proto_dirs: &[&str], proto_files: &[&s
         .expect("Failed to compile gRPC definitions!");
 
     // Generate the mod.rs file that includes all the auto-generated code.
-    let mod_file_contents = fs::read_dir(&output_destination)
+    let mut mod_names = fs::read_dir(&output_destination)
         .expect("failed reading output directory")
         .filter_map(|res| res.map(|e| e.path()).ok())
         .filter_map(|path| {
             if path.extension() == Some(&OsStr::new("rs")) {
-                Some(format!(
-                    "pub mod {};",
-                    path.file_stem().unwrap().to_str().unwrap()
-                ))
+                Some(path.file_stem().unwrap().to_str().unwrap().to_owned())
             } else {
                 None
             }
         })
+        .collect::<Vec<String>>();
+    mod_names.sort();
+
+    let mut mod_file_contents = mod_names
+        .iter()
+        .map(|mod_name| format!("pub mod {};", mod_name))
         .collect::<Vec<String>>()
         .join("\n");
 
+    // Expose the descriptors of the compiled proto files, so that servers can
+    // offer them through gRPC server reflection. Only the message modules have
+    // descriptors, the `_grpc` modules hold the service stubs.
+    mod_file_contents.push_str(&format!(
+        "\n\n/// Descriptors of the proto files compiled into this crate.\n\
+         pub fn file_descriptors() -> Vec<&'static ::protobuf::descriptor::FileDescriptorProto> {{\n\
+         \x20   vec![{}]\n\
+         }}\n",
+        mod_names
+            .iter()
+            .filter(|mod_name| !mod_name.ends_with("_grpc"))
+            .map(|mod_name| format!("{}::file_descriptor_proto()", mod_name))
+            .collect::<Vec<String>>()
+            .join(", ")
+    ));
+
     let mod_file_path = output_destination.join("mod.rs");
 
     if fs::read_to_string(&mod_file_path).ok().as_ref() != Some(&mod_file_contents) {
@@ -2,12 +2,16 @@
 
 //! A utility for issueing admin GRPC requests.
 
-use grpcio::ChannelBuilder;
+use grpcio::{CallOption, ChannelBuilder};
+use mc_common::time::SystemTimeProvider;
 use mc_util_grpc::{
-    admin::SetRustLogRequest, admin_grpc::AdminApiClient, empty::Empty, ConnectionUriGrpcioChannel,
+    admin::{SetConfigJsonRequest, SetRustLogRequest},
+    admin_grpc::AdminApiClient,
+    empty::Empty,
+    ConnectionUriGrpcioChannel, TokenBasicCredentialsGenerator,
 };
 use mc_util_uri::AdminUri;
-use std::{str::FromStr, sync::Arc};
+use std::{fs, path::PathBuf, str::FromStr, sync::Arc};
 use structopt::StructOpt;
 
 #[derive(Clone, StructOpt)]
@@ -16,6 +20,11 @@ pub struct Config {
     #[structopt(long)]
     pub uri: String,
 
+    /// Authenticate with a token generated using this shared secret (64 hex
+    /// characters). Required by services which authenticate config changes.
+    #[structopt(long, env = "MC_ADMIN_AUTH_TOKEN_SECRET", parse(try_from_str=hex::FromHex::from_hex))]
+    pub auth_token_secret: Option<[u8; 32]>,
+
     #[structopt(subcommand)]
     pub cmd: Command,
 }
@@ -28,6 +37,9 @@ pub enum Command {
     /// Get information such as build info, logging configuration, etc.
     GetInfo,
 
+    /// Get service-specific status, such as a consensus node's current slot.
+    GetServiceStatus,
+
     /// Update the logging level (RUST_LOG).
     SetRustLog {
         /// New RUST_LOG value to use
@@ -36,6 +48,13 @@ pub enum Command {
 
     /// Logs a test error message.
     TestLogError,
+
+    /// Apply a service-specific configuration change.
+    SetConfigJson {
+        /// Path to a file containing the JSON-encoded configuration
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+    },
 }
 
 fn main() {
@@ -49,6 +68,17 @@ fn main() {
     let ch = ChannelBuilder::default_channel_builder(env).connect_to_uri(&uri, &logger);
     let client = AdminApiClient::new(ch);
 
+    let call_option = match config.auth_token_secret {
+        Some(shared_secret) => {
+            TokenBasicCredentialsGenerator::new(shared_secret, SystemTimeProvider::default())
+                .generate_for("admin")
+                .expect("failed generating token")
+                .call_option()
+                .expect("failed creating call option")
+        }
+        None => CallOption::default(),
+    };
+
     match config.cmd {
         Command::Metrics => {
             let response = client
@@ -69,6 +99,13 @@ fn main() {
             println!("Config json:  {}", response.config_json);
         }
 
+        Command::GetServiceStatus => {
+            let response = client
+                .get_service_status(&Empty::new())
+                .expect("failed calling get_service_status");
+            println!("{}", response.status_json);
+        }
+
         Command::SetRustLog { rust_log } => {
             let mut request = SetRustLogRequest::new();
             request.set_rust_log(rust_log);
@@ -85,6 +122,19 @@ fn main() {
                 .expect("failed calling test_log_error");
             println!("Done.");
         }
+
+        Command::SetConfigJson { path } => {
+            let mut request = SetConfigJsonRequest::new();
+            request.set_config_json(
+                fs::read_to_string(&path)
+                    .unwrap_or_else(|err| panic!("failed reading {:?}: {:?}", path, err)),
+            );
+
+            let _ = client
+                .set_config_json_opt(&request, call_option)
+                .expect("failed calling set_config_json");
+            println!("Done.");
+        }
     };
 
     // Give logger a moment to flush :/
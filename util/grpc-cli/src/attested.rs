@@ -0,0 +1,129 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Calls to the client APIs of consensus nodes, which go through
+//! `ThickClient` so that the node's enclave is attested first, and proposed
+//! transactions are encrypted for it.
+
+use crate::{descriptors::MethodDescriptor, Error};
+use mc_api::{blockchain, external};
+use mc_connection::{
+    BlockchainConnection, CredentialsProvider, Error as ConnectionError, ThickClient,
+    UserTxConnection,
+};
+use mc_consensus_api::{
+    consensus_client::TxStatusRequest,
+    consensus_common::{
+        BlocksRequest, BlocksResponse, LastBlockInfoResponse, ProposeTxResponse, ProposeTxResult,
+    },
+};
+use mc_transaction_core::tx::{Tx, TxHash};
+use protobuf::{parse_from_bytes, Message, RepeatedField};
+use std::convert::TryFrom;
+
+const GET_LAST_BLOCK_INFO: &str = "/consensus_common.BlockchainAPI/GetLastBlockInfo";
+const GET_BLOCKS: &str = "/consensus_common.BlockchainAPI/GetBlocks";
+const GET_TX_STATUS: &str = "/consensus_client.ConsensusClientAPI/GetTxStatus";
+const CLIENT_TX_PROPOSE: &str = "/consensus_client.ConsensusClientAPI/ClientTxPropose";
+const AUTH: &str = "/attest.AttestedApi/Auth";
+
+/// Whether a method of a consensus node must be called through `call()`.
+pub fn is_attested(method: &MethodDescriptor) -> bool {
+    matches!(
+        method.path().as_str(),
+        GET_LAST_BLOCK_INFO | GET_BLOCKS | GET_TX_STATUS | CLIENT_TX_PROPOSE | AUTH
+    )
+}
+
+/// The type of request `call()` takes for a method. Transactions are proposed
+/// as plain `external.Tx` messages, which are encrypted for the enclave.
+pub fn request_type(method: &MethodDescriptor) -> &str {
+    if method.path() == CLIENT_TX_PROPOSE {
+        "external.Tx"
+    } else {
+        &method.input_type
+    }
+}
+
+/// Call an attested method of a consensus node.
+///
+/// # Arguments
+/// * `client` - A client connected to the node.
+/// * `method` - The method to call, see `is_attested()`.
+/// * `request` - The encoded request, of type `request_type(method)`.
+pub fn call<CP: CredentialsProvider>(
+    client: &mut ThickClient<CP>,
+    method: &MethodDescriptor,
+    request: &[u8],
+) -> Result<Vec<u8>, Error> {
+    match method.path().as_str() {
+        GET_LAST_BLOCK_INFO => {
+            let block_info = client.fetch_block_info()?;
+            let mut response = LastBlockInfoResponse::new();
+            response.set_index(block_info.block_index);
+            response.set_minimum_fee(block_info.minimum_fee);
+            Ok(response.write_to_bytes()?)
+        }
+
+        GET_BLOCKS => {
+            let request: BlocksRequest = parse_from_bytes(request)?;
+            let offset = request.get_offset();
+            let blocks = client
+                .fetch_blocks(offset..offset.saturating_add(u64::from(request.get_limit())))?;
+            let mut response = BlocksResponse::new();
+            response.set_blocks(RepeatedField::from_vec(
+                blocks.iter().map(blockchain::Block::from).collect(),
+            ));
+            Ok(response.write_to_bytes()?)
+        }
+
+        GET_TX_STATUS => {
+            let request: TxStatusRequest = parse_from_bytes(request)?;
+            let tx_hash = TxHash::try_from(request.get_tx_hash())?;
+            Ok(client.fetch_tx_status(&tx_hash)?.write_to_bytes()?)
+        }
+
+        CLIENT_TX_PROPOSE => {
+            let tx: external::Tx = parse_from_bytes(request)?;
+            let tx = Tx::try_from(&tx)?;
+
+            // Rejected transactions are reported in the response, as the node
+            // does, rather than as errors.
+            let mut response = ProposeTxResponse::new();
+            match client.propose_tx(&tx) {
+                Ok(block_count) => response.set_block_count(block_count),
+                Err(ConnectionError::TransactionValidation(err)) => {
+                    response.set_result(ProposeTxResult::from(err))
+                }
+                Err(err) => return Err(err.into()),
+            }
+            Ok(response.write_to_bytes()?)
+        }
+
+        path => Err(Error::AttestationMethod(path.to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptors::DescriptorPool;
+
+    #[test]
+    fn attested_methods() {
+        let pool = DescriptorPool::compiled();
+
+        for name in &[GET_LAST_BLOCK_INFO, GET_BLOCKS, GET_TX_STATUS, AUTH] {
+            let method = pool.method(name).unwrap();
+            assert!(is_attested(&method), "{}", name);
+            assert_eq!(request_type(&method), method.input_type);
+        }
+
+        let method = pool.method(CLIENT_TX_PROPOSE).unwrap();
+        assert!(is_attested(&method));
+        assert_eq!(method.input_type, "attest.Message");
+        assert_eq!(request_type(&method), "external.Tx");
+
+        let method = pool.method("grpc.health.v1.Health/Check").unwrap();
+        assert!(!is_attested(&method));
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! A command line client for the gRPC APIs of MobileCoin services, which takes
+//! requests and prints responses as JSON.
+
+use grpcio::{CallOption, Channel, EnvBuilder};
+use mc_attest_core::{AttestationPolicy, MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
+use mc_common::{
+    logger::{create_app_logger, o, Logger},
+    time::SystemTimeProvider,
+};
+use mc_connection::{
+    AnyCredentialsProvider, HardcodedCredentialsProvider, ThickClient,
+    TokenBasicCredentialsProvider,
+};
+use mc_util_grpc::TokenBasicCredentialsGenerator;
+use mc_util_grpc_cli::{
+    attested,
+    call::{call_option, unary_call},
+    json, DescriptorPool, Error, MethodDescriptor, ReflectionClient, ServiceUri,
+};
+use serde_json::Value;
+use std::{
+    io::{self, Read},
+    process,
+    sync::Arc,
+};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "mc-grpc-cli",
+    about = "Call the gRPC APIs of MobileCoin services with JSON messages"
+)]
+pub struct Config {
+    /// URI of the service, e.g. `mc://node1.test.mobilecoin.com/`,
+    /// `insecure-mobilecoind://localhost/` or `insecure-mca://127.0.0.1/`.
+    #[structopt(long)]
+    pub uri: ServiceUri,
+
+    /// Username to authenticate as, e.g. as printed by
+    /// mc-util-grpc-token-generator. Credentials may also be given in the
+    /// URI.
+    #[structopt(long)]
+    pub username: Option<String>,
+
+    /// Password to authenticate with.
+    #[structopt(long, requires = "username")]
+    pub password: Option<String>,
+
+    /// Secret shared with the service, used to generate tokens for
+    /// `--username` rather than passing a password.
+    #[structopt(
+        long,
+        parse(try_from_str=hex::FromHex::from_hex),
+        requires = "username",
+        conflicts_with = "password"
+    )]
+    pub shared_secret: Option<[u8; 32]>,
+
+    /// Use the descriptors of the APIs this tool was built with, for services
+    /// which do not offer gRPC server reflection.
+    #[structopt(long)]
+    pub no_reflection: bool,
+
+    /// Attestation policy (.toml or .json) for consensus node enclaves.
+    /// Defaults to the consensus enclave this build was made for.
+    #[structopt(long, parse(try_from_str=AttestationPolicy::load))]
+    pub attestation_policy: Option<AttestationPolicy>,
+
+    #[structopt(subcommand)]
+    pub cmd: Command,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// List the services of the server, or the methods of one of them.
+    List {
+        /// Full name of a service, e.g. `consensus_common.BlockchainAPI`.
+        service: Option<String>,
+    },
+
+    /// Call a method, and print its response.
+    Call {
+        /// Method to call, e.g. `consensus_common.BlockchainAPI/GetBlocks`.
+        method: String,
+
+        /// Request, as JSON. Read from stdin when not given.
+        #[structopt(long, short)]
+        data: Option<String>,
+
+        /// Include fields with default values in the response.
+        #[structopt(long)]
+        emit_defaults: bool,
+    },
+}
+
+impl Config {
+    /// Credentials to call the service with.
+    fn credentials_provider(&self) -> AnyCredentialsProvider {
+        match (&self.username, &self.password, &self.shared_secret) {
+            (Some(username), _, Some(shared_secret)) => {
+                AnyCredentialsProvider::Token(TokenBasicCredentialsProvider::new(
+                    username,
+                    TokenBasicCredentialsGenerator::new(
+                        *shared_secret,
+                        SystemTimeProvider::default(),
+                    ),
+                ))
+            }
+            (Some(username), password, None) => AnyCredentialsProvider::Hardcoded(
+                HardcodedCredentialsProvider::new(username, password.as_deref().unwrap_or("")),
+            ),
+            (None, _, _) => AnyCredentialsProvider::Hardcoded(self.uri.credentials_provider()),
+        }
+    }
+
+    /// The attestation verifier used to verify consensus nodes.
+    fn consensus_verifier(&self) -> Verifier {
+        if let Some(policy) = &self.attestation_policy {
+            return policy
+                .verifier()
+                .expect("Attestation policy was validated when loaded");
+        }
+
+        let mut mr_signer_verifier =
+            MrSignerVerifier::from(mc_consensus_enclave_measurement::sigstruct());
+        mr_signer_verifier.allow_hardening_advisory("INTEL-SA-00334");
+
+        let mut verifier = Verifier::default();
+        verifier.mr_signer(mr_signer_verifier).debug(DEBUG_ENCLAVE);
+        verifier
+    }
+}
+
+fn main() {
+    mc_common::setup_panic_handler();
+    let (logger, _global_logger_guard) = create_app_logger(o!());
+    let config = Config::from_args();
+
+    if let Err(err) = run(&config, logger) {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(config: &Config, logger: Logger) -> Result<(), Error> {
+    let env = Arc::new(
+        EnvBuilder::new()
+            .name_prefix("GrpcCli-RPC".to_string())
+            .build(),
+    );
+    let channel = config.uri.connect(env.clone(), &logger);
+    let credentials_provider = config.credentials_provider();
+    let (services, pool) = load_descriptors(config, &channel, call_option(&credentials_provider)?)?;
+
+    match &config.cmd {
+        Command::List { service: None } => {
+            for service in services {
+                println!("{}", service);
+            }
+        }
+
+        Command::List {
+            service: Some(service),
+        } => {
+            for method in pool.methods(service)? {
+                println!("{}", describe(&method));
+            }
+        }
+
+        Command::Call {
+            method,
+            data,
+            emit_defaults,
+        } => {
+            let method = pool.method(method)?;
+
+            // The client API of consensus nodes is called through ThickClient,
+            // which attests the node first.
+            let attested_uri = match &config.uri {
+                ServiceUri::Consensus(uri) if attested::is_attested(&method) => Some(uri),
+                _ => None,
+            };
+            let request_type = match attested_uri {
+                Some(_) => attested::request_type(&method),
+                None => &method.input_type,
+            };
+
+            let data = match data {
+                Some(data) => data.clone(),
+                None => {
+                    let mut data = String::new();
+                    io::stdin()
+                        .read_to_string(&mut data)
+                        .map_err(Error::ReadRequest)?;
+                    data
+                }
+            };
+            let request: Value =
+                serde_json::from_str(&data).map_err(|err| Error::InvalidJson(err.to_string()))?;
+            let request = json::encode(&pool, request_type, &request)?;
+
+            let response = match attested_uri {
+                Some(uri) => {
+                    let mut client = ThickClient::new(
+                        uri.clone(),
+                        config.consensus_verifier(),
+                        env,
+                        credentials_provider,
+                        logger,
+                    )?;
+                    attested::call(&mut client, &method, &request)?
+                }
+                None => unary_call(
+                    channel,
+                    &method,
+                    &request,
+                    call_option(&credentials_provider)?,
+                )?,
+            };
+
+            let response = json::decode(&pool, &method.output_type, &response, *emit_defaults)?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&response).expect("Serializing JSON failed")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The services offered by the server, and the descriptors of their messages.
+fn load_descriptors(
+    config: &Config,
+    channel: &Channel,
+    call_option: CallOption,
+) -> Result<(Vec<String>, DescriptorPool), Error> {
+    if config.no_reflection {
+        let pool = DescriptorPool::compiled();
+        let services = pool.services().map(str::to_owned).collect();
+        return Ok((services, pool));
+    }
+
+    let mut client = ReflectionClient::new(channel.clone(), call_option)?;
+    let services = client.list_services()?;
+    let mut pool = DescriptorPool::new();
+    client.load_services(&services, &mut pool)?;
+    Ok((services, pool))
+}
+
+/// A one-line description of a method, e.g.
+/// `GetBlocks(consensus_common.BlocksRequest) returns
+/// (consensus_common.BlocksResponse)`.
+fn describe(method: &MethodDescriptor) -> String {
+    let stream = |streaming| if streaming { "stream " } else { "" };
+    format!(
+        "{}({}{}) returns ({}{})",
+        method.name,
+        stream(method.client_streaming),
+        method.input_type,
+        stream(method.server_streaming),
+        method.output_type
+    )
+}
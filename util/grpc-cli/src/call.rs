@@ -0,0 +1,78 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Calls to any unary gRPC method, with requests and responses passed as
+//! encoded bytes.
+
+use crate::{descriptors::MethodDescriptor, Error};
+use grpcio::{
+    CallOption, Channel, Client, GrpcSlice, Marshaller, MessageReader, MetadataBuilder, Method,
+    MethodType,
+};
+use mc_connection::CredentialsProvider;
+use std::io::Read;
+
+/// Options for a call, carrying the credentials of `credentials_provider`.
+pub fn call_option(credentials_provider: &impl CredentialsProvider) -> Result<CallOption, Error> {
+    let mut metadata_builder = MetadataBuilder::new();
+    if let Some(creds) = credentials_provider
+        .get_credentials()
+        .map_err(|err| Error::Credentials(err.to_string()))?
+    {
+        if !creds.username().is_empty() && !creds.password().is_empty() {
+            metadata_builder
+                .add_str("Authorization", &creds.authorization_header())
+                .map_err(|err| Error::Credentials(err.to_string()))?;
+        }
+    }
+    Ok(CallOption::default().headers(metadata_builder.build()))
+}
+
+/// Call a unary method.
+///
+/// # Arguments
+/// * `channel` - The channel to the server.
+/// * `method` - The method to call.
+/// * `request` - The encoded request.
+/// * `call_option` - Options for the call, e.g. from `call_option()`.
+pub fn unary_call(
+    channel: Channel,
+    method: &MethodDescriptor,
+    request: &[u8],
+    call_option: CallOption,
+) -> Result<Vec<u8>, Error> {
+    if !method.is_unary() {
+        return Err(Error::StreamingMethod(method.path()));
+    }
+
+    let raw_method = Method {
+        ty: MethodType::Unary,
+        // grpcio only takes static method names. This tool makes a single call,
+        // so leaking the name is harmless.
+        name: Box::leak(method.path().into_boxed_str()),
+        req_mar: Marshaller {
+            ser: serialize_bytes,
+            de: deserialize_bytes,
+        },
+        resp_mar: Marshaller {
+            ser: serialize_bytes,
+            de: deserialize_bytes,
+        },
+    };
+
+    let client = Client::new(channel);
+    Ok(client.unary_call(&raw_method, &request.to_vec(), call_option)?)
+}
+
+// The signature is dictated by `Marshaller`.
+#[allow(clippy::ptr_arg)]
+fn serialize_bytes(bytes: &Vec<u8>, buf: &mut GrpcSlice) {
+    *buf = GrpcSlice::from(bytes.clone());
+}
+
+fn deserialize_bytes(mut reader: MessageReader) -> grpcio::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .map_err(|err| grpcio::Error::Codec(Box::new(err)))?;
+    Ok(bytes)
+}
@@ -0,0 +1,257 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! A pool of proto file descriptors, in which messages, enums and methods can
+//! be looked up by their fully-qualified names.
+
+use crate::Error;
+use mc_common::{HashMap, HashSet};
+use protobuf::descriptor::{
+    DescriptorProto, EnumDescriptorProto, FileDescriptorProto, ServiceDescriptorProto,
+};
+use std::collections::BTreeMap;
+
+/// A method of a gRPC service.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MethodDescriptor {
+    /// Full name of the service, e.g. `consensus_common.BlockchainAPI`.
+    pub service: String,
+
+    /// Name of the method, e.g. `GetBlocks`.
+    pub name: String,
+
+    /// Full name of the request type.
+    pub input_type: String,
+
+    /// Full name of the response type.
+    pub output_type: String,
+
+    /// Whether the client sends a stream of requests.
+    pub client_streaming: bool,
+
+    /// Whether the server sends a stream of responses.
+    pub server_streaming: bool,
+}
+
+impl MethodDescriptor {
+    /// The path gRPC uses to call the method, e.g.
+    /// `/consensus_common.BlockchainAPI/GetBlocks`.
+    pub fn path(&self) -> String {
+        format!("/{}/{}", self.service, self.name)
+    }
+
+    /// Whether the method takes a single request and returns a single
+    /// response.
+    pub fn is_unary(&self) -> bool {
+        !self.client_streaming && !self.server_streaming
+    }
+}
+
+/// Messages, enums and services of a set of proto files.
+#[derive(Default)]
+pub struct DescriptorPool {
+    /// Names of the files added to the pool.
+    files: HashSet<String>,
+
+    /// Messages, by full name.
+    messages: HashMap<String, DescriptorProto>,
+
+    /// Enums, by full name.
+    enums: HashMap<String, EnumDescriptorProto>,
+
+    /// Services, by full name.
+    services: BTreeMap<String, ServiceDescriptorProto>,
+}
+
+impl DescriptorPool {
+    /// A pool containing `google/protobuf/empty.proto`, which our APIs import.
+    pub fn new() -> Self {
+        let mut pool = Self::default();
+        pool.add_file(mc_util_grpc::empty_file_descriptor().clone());
+        pool
+    }
+
+    /// A pool containing the descriptors of the MobileCoin APIs this tool was
+    /// built with, for servers which do not offer reflection.
+    pub fn compiled() -> Self {
+        let mut pool = Self::new();
+        for file in mc_api::file_descriptors()
+            .into_iter()
+            .chain(mc_attest_api::file_descriptors())
+            .chain(mc_consensus_api::file_descriptors())
+            .chain(mc_mobilecoind_api::file_descriptors())
+            .chain(mc_util_grpc::file_descriptors())
+        {
+            pool.add_file(file.clone());
+        }
+        pool
+    }
+
+    /// Add a file to the pool. Files which were already added are ignored.
+    pub fn add_file(&mut self, file: FileDescriptorProto) {
+        if !self.files.insert(file.get_name().to_owned()) {
+            return;
+        }
+
+        let package = file.get_package();
+        for message in file.get_message_type() {
+            self.add_message(package, message);
+        }
+        for enumeration in file.get_enum_type() {
+            self.enums.insert(
+                qualify(package, enumeration.get_name()),
+                enumeration.clone(),
+            );
+        }
+        for service in file.get_service() {
+            self.services
+                .insert(qualify(package, service.get_name()), service.clone());
+        }
+    }
+
+    fn add_message(&mut self, scope: &str, message: &DescriptorProto) {
+        let name = qualify(scope, message.get_name());
+        for nested in message.get_nested_type() {
+            self.add_message(&name, nested);
+        }
+        for enumeration in message.get_enum_type() {
+            self.enums
+                .insert(qualify(&name, enumeration.get_name()), enumeration.clone());
+        }
+        self.messages.insert(name, message.clone());
+    }
+
+    /// Full names of the services in the pool, in order.
+    pub fn services(&self) -> impl Iterator<Item = &str> {
+        self.services.keys().map(String::as_str)
+    }
+
+    /// Look up a message by its full name, with or without a leading `.`.
+    pub fn message(&self, name: &str) -> Result<&DescriptorProto, Error> {
+        let name = name.trim_start_matches('.');
+        self.messages
+            .get(name)
+            .ok_or_else(|| Error::UnknownType(name.to_owned()))
+    }
+
+    /// Look up an enum by its full name, with or without a leading `.`.
+    pub fn enumeration(&self, name: &str) -> Result<&EnumDescriptorProto, Error> {
+        let name = name.trim_start_matches('.');
+        self.enums
+            .get(name)
+            .ok_or_else(|| Error::UnknownType(name.to_owned()))
+    }
+
+    /// The methods of a service.
+    pub fn methods(&self, service: &str) -> Result<Vec<MethodDescriptor>, Error> {
+        let descriptor = self
+            .services
+            .get(service)
+            .ok_or_else(|| Error::UnknownMethod(service.to_owned()))?;
+        Ok(descriptor
+            .get_method()
+            .iter()
+            .map(|method| MethodDescriptor {
+                service: service.to_owned(),
+                name: method.get_name().to_owned(),
+                input_type: method.get_input_type().trim_start_matches('.').to_owned(),
+                output_type: method.get_output_type().trim_start_matches('.').to_owned(),
+                client_streaming: method.get_client_streaming(),
+                server_streaming: method.get_server_streaming(),
+            })
+            .collect())
+    }
+
+    /// Look up a method by name, given as `package.Service/Method`,
+    /// `/package.Service/Method` or `package.Service.Method`.
+    pub fn method(&self, name: &str) -> Result<MethodDescriptor, Error> {
+        let (service, method) =
+            split_method_name(name).ok_or_else(|| Error::UnknownMethod(name.to_owned()))?;
+        self.methods(service)
+            .map_err(|_| Error::UnknownMethod(name.to_owned()))?
+            .into_iter()
+            .find(|descriptor| descriptor.name == method)
+            .ok_or_else(|| Error::UnknownMethod(name.to_owned()))
+    }
+}
+
+/// Split a method name into the service's full name and the method's name.
+pub fn split_method_name(name: &str) -> Option<(&str, &str)> {
+    let name = name.trim_start_matches('/');
+    let separator = name.rfind('/').or_else(|| name.rfind('.'))?;
+    let (service, method) = (&name[..separator], &name[separator + 1..]);
+    if service.is_empty() || method.is_empty() {
+        None
+    } else {
+        Some((service, method))
+    }
+}
+
+/// The fully-qualified name of `name` in `scope`.
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_method_names() {
+        for name in &[
+            "consensus_common.BlockchainAPI/GetBlocks",
+            "/consensus_common.BlockchainAPI/GetBlocks",
+            "consensus_common.BlockchainAPI.GetBlocks",
+        ] {
+            assert_eq!(
+                split_method_name(name),
+                Some(("consensus_common.BlockchainAPI", "GetBlocks")),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(split_method_name("GetBlocks"), None);
+        assert_eq!(split_method_name("consensus_common.BlockchainAPI/"), None);
+    }
+
+    #[test]
+    fn compiled_pool_has_our_apis() {
+        let pool = DescriptorPool::compiled();
+
+        let services: Vec<&str> = pool.services().collect();
+        for service in &[
+            "attest.AttestedApi",
+            "consensus_client.ConsensusClientAPI",
+            "consensus_common.BlockchainAPI",
+            "grpc.health.v1.Health",
+            "mobilecoind_api.MobilecoindAPI",
+        ] {
+            assert!(services.contains(service), "{}", service);
+        }
+
+        let method = pool
+            .method("consensus_common.BlockchainAPI/GetLastBlockInfo")
+            .unwrap();
+        assert_eq!(
+            method.path(),
+            "/consensus_common.BlockchainAPI/GetLastBlockInfo"
+        );
+        assert_eq!(method.input_type, "google.protobuf.Empty");
+        assert_eq!(method.output_type, "consensus_common.LastBlockInfoResponse");
+        assert!(method.is_unary());
+
+        // Types referenced by the method must be known.
+        pool.message(&method.input_type).unwrap();
+        pool.message(".consensus_common.LastBlockInfoResponse")
+            .unwrap();
+        pool.enumeration("consensus_client.TxStatus").unwrap();
+
+        assert!(pool
+            .method("consensus_common.BlockchainAPI/Unknown")
+            .is_err());
+        assert!(pool.message("consensus_common.Unknown").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Errors which can occur when calling a gRPC API.
+
+use displaydoc::Display;
+use grpcio::Error as GrpcError;
+use mc_api::ConversionError;
+use mc_connection::Error as ConnectionError;
+use protobuf::ProtobufError;
+
+#[derive(Debug, Display)]
+pub enum Error {
+    /// Unknown method {0}, expected e.g. `package.Service/Method`
+    UnknownMethod(String),
+
+    /// Unknown type {0}
+    UnknownType(String),
+
+    /// Streaming method {0} is not supported
+    StreamingMethod(String),
+
+    /// {0} is called when attesting a node, and cannot be called directly
+    AttestationMethod(String),
+
+    /// Could not read the request: {0}
+    ReadRequest(std::io::Error),
+
+    /// Invalid JSON: {0}
+    InvalidJson(String),
+
+    /// Invalid protobuf message: {0}
+    InvalidMessage(String),
+
+    /// Server reflection failed: {0}
+    Reflection(String),
+
+    /// Could not get credentials: {0}
+    Credentials(String),
+
+    /// gRPC failure: {0}
+    Grpc(GrpcError),
+
+    /// Consensus connection failure: {0}
+    Connection(ConnectionError),
+
+    /// Protobuf failure: {0}
+    Protobuf(ProtobufError),
+
+    /// Conversion failure: {0}
+    Conversion(ConversionError),
+}
+
+impl From<GrpcError> for Error {
+    fn from(src: GrpcError) -> Self {
+        Self::Grpc(src)
+    }
+}
+
+impl From<ConnectionError> for Error {
+    fn from(src: ConnectionError) -> Self {
+        Self::Connection(src)
+    }
+}
+
+impl From<ProtobufError> for Error {
+    fn from(src: ProtobufError) -> Self {
+        Self::Protobuf(src)
+    }
+}
+
+impl From<ConversionError> for Error {
+    fn from(src: ConversionError) -> Self {
+        Self::Conversion(src)
+    }
+}
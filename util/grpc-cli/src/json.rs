@@ -0,0 +1,830 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Conversion between JSON and the protobuf wire format, driven by message
+//! descriptors so that any message known to a server can be used.
+//!
+//! This follows the proto3 JSON mapping: 64-bit integers are strings, bytes
+//! are base64, enums are names, and fields are named in lowerCamelCase (the
+//! original field names are also accepted).
+
+use crate::{descriptors::DescriptorPool, Error};
+use protobuf::descriptor::{
+    DescriptorProto, FieldDescriptorProto, FieldDescriptorProto_Label as Label,
+    FieldDescriptorProto_Type as Type,
+};
+use serde_json::{Map, Number, Value};
+use std::{convert::TryFrom, fmt::Display, str};
+
+const WIRE_VARINT: u32 = 0;
+const WIRE_FIXED64: u32 = 1;
+const WIRE_LENGTH_DELIMITED: u32 = 2;
+const WIRE_START_GROUP: u32 = 3;
+const WIRE_FIXED32: u32 = 5;
+
+/// Encode the JSON representation of a message of type `type_name`.
+pub fn encode(pool: &DescriptorPool, type_name: &str, json: &Value) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    Encoder { pool }.message(type_name, json, type_name, &mut buf)?;
+    Ok(buf)
+}
+
+/// Decode a message of type `type_name` to its JSON representation.
+///
+/// # Arguments
+/// * `pool` - Descriptors of the message and of the types it uses.
+/// * `type_name` - Full name of the message type.
+/// * `bytes` - The encoded message.
+/// * `emit_defaults` - Whether to include fields with default values, which the
+///   proto3 JSON mapping omits.
+pub fn decode(
+    pool: &DescriptorPool,
+    type_name: &str,
+    bytes: &[u8],
+    emit_defaults: bool,
+) -> Result<Value, Error> {
+    Decoder {
+        pool,
+        emit_defaults,
+    }
+    .message(type_name, bytes)
+}
+
+struct Encoder<'a> {
+    pool: &'a DescriptorPool,
+}
+
+impl<'a> Encoder<'a> {
+    fn message(
+        &self,
+        type_name: &str,
+        json: &Value,
+        path: &str,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let descriptor = self.pool.message(type_name)?;
+        let object = json
+            .as_object()
+            .ok_or_else(|| invalid_json(path, "expected an object"))?;
+
+        for key in object.keys() {
+            if !descriptor
+                .get_field()
+                .iter()
+                .any(|field| field.get_name() == key || json_name(field) == *key)
+            {
+                return Err(invalid_json(path, format!("unknown field {}", key)));
+            }
+        }
+
+        for field in descriptor.get_field() {
+            let value = match object
+                .get(&json_name(field))
+                .or_else(|| object.get(field.get_name()))
+            {
+                None | Some(Value::Null) => continue,
+                Some(value) => value,
+            };
+            let path = format!("{}.{}", path, field.get_name());
+
+            if field.get_label() != Label::LABEL_REPEATED {
+                self.field(field, value, &path, buf)?;
+            } else if let Some(entry) = map_entry(self.pool, field)? {
+                let (key_field, value_field) = map_entry_fields(entry)?;
+                let object = value
+                    .as_object()
+                    .ok_or_else(|| invalid_json(&path, "expected an object"))?;
+                for (key, value) in object {
+                    let key = match key_field.get_field_type() {
+                        Type::TYPE_BOOL => Value::Bool(
+                            key.parse()
+                                .map_err(|_| invalid_json(&path, "expected a boolean key"))?,
+                        ),
+                        _ => Value::String(key.clone()),
+                    };
+                    let mut entry_buf = Vec::new();
+                    self.field(key_field, &key, &path, &mut entry_buf)?;
+                    self.field(value_field, value, &path, &mut entry_buf)?;
+                    write_tag(field.get_number(), WIRE_LENGTH_DELIMITED, buf);
+                    write_length_delimited(&entry_buf, buf);
+                }
+            } else {
+                let values = value
+                    .as_array()
+                    .ok_or_else(|| invalid_json(&path, "expected an array"))?;
+                if is_packable(field) {
+                    // Repeated scalars are packed by default in proto3.
+                    let mut packed = Vec::new();
+                    for value in values {
+                        self.scalar(field, value, &path, &mut packed)?;
+                    }
+                    if !packed.is_empty() {
+                        write_tag(field.get_number(), WIRE_LENGTH_DELIMITED, buf);
+                        write_length_delimited(&packed, buf);
+                    }
+                } else {
+                    for value in values {
+                        self.field(field, value, &path, buf)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode a single value of `field`, preceded by its tag.
+    fn field(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &Value,
+        path: &str,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        write_tag(field.get_number(), wire_type(field), buf);
+        match field.get_field_type() {
+            Type::TYPE_MESSAGE => {
+                let mut nested = Vec::new();
+                self.message(field.get_type_name(), value, path, &mut nested)?;
+                write_length_delimited(&nested, buf);
+            }
+            Type::TYPE_STRING => {
+                let string = value
+                    .as_str()
+                    .ok_or_else(|| invalid_json(path, "expected a string"))?;
+                write_length_delimited(string.as_bytes(), buf);
+            }
+            Type::TYPE_BYTES => {
+                let string = value
+                    .as_str()
+                    .ok_or_else(|| invalid_json(path, "expected a base64 string"))?;
+                let bytes = base64::decode(string)
+                    .or_else(|_| base64::decode_config(string, base64::URL_SAFE))
+                    .map_err(|err| invalid_json(path, err))?;
+                write_length_delimited(&bytes, buf);
+            }
+            _ => self.scalar(field, value, path, buf)?,
+        }
+        Ok(())
+    }
+
+    /// Encode a number, boolean or enum value, without a tag.
+    fn scalar(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &Value,
+        path: &str,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        match field.get_field_type() {
+            // Negative int32 values are sign-extended to 64 bits.
+            Type::TYPE_INT32 => write_varint(i64::from(int::<i32>(value, path)?) as u64, buf),
+            Type::TYPE_INT64 => write_varint(int::<i64>(value, path)? as u64, buf),
+            Type::TYPE_UINT32 => write_varint(u64::from(int::<u32>(value, path)?), buf),
+            Type::TYPE_UINT64 => write_varint(int::<u64>(value, path)?, buf),
+            Type::TYPE_SINT32 => {
+                let n = int::<i32>(value, path)?;
+                write_varint(u64::from(((n << 1) ^ (n >> 31)) as u32), buf)
+            }
+            Type::TYPE_SINT64 => {
+                let n = int::<i64>(value, path)?;
+                write_varint(((n << 1) ^ (n >> 63)) as u64, buf)
+            }
+            Type::TYPE_BOOL => {
+                let b = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_json(path, "expected a boolean"))?;
+                write_varint(u64::from(b), buf)
+            }
+            Type::TYPE_ENUM => {
+                let number = self.enum_number(field.get_type_name(), value, path)?;
+                write_varint(i64::from(number) as u64, buf)
+            }
+            Type::TYPE_FIXED32 => buf.extend_from_slice(&int::<u32>(value, path)?.to_le_bytes()),
+            Type::TYPE_SFIXED32 => buf.extend_from_slice(&int::<i32>(value, path)?.to_le_bytes()),
+            Type::TYPE_FLOAT => buf.extend_from_slice(&(float(value, path)? as f32).to_le_bytes()),
+            Type::TYPE_FIXED64 => buf.extend_from_slice(&int::<u64>(value, path)?.to_le_bytes()),
+            Type::TYPE_SFIXED64 => buf.extend_from_slice(&int::<i64>(value, path)?.to_le_bytes()),
+            Type::TYPE_DOUBLE => buf.extend_from_slice(&float(value, path)?.to_le_bytes()),
+            other => {
+                return Err(invalid_json(
+                    path,
+                    format!("unsupported field type {:?}", other),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of an enum value, given by name or by number.
+    fn enum_number(&self, type_name: &str, value: &Value, path: &str) -> Result<i32, Error> {
+        if let Some(name) = value.as_str() {
+            self.pool
+                .enumeration(type_name)?
+                .get_value()
+                .iter()
+                .find(|enum_value| enum_value.get_name() == name)
+                .map(|enum_value| enum_value.get_number())
+                .ok_or_else(|| invalid_json(path, format!("unknown {} value {}", type_name, name)))
+        } else {
+            int::<i32>(value, path)
+        }
+    }
+}
+
+struct Decoder<'a> {
+    pool: &'a DescriptorPool,
+    emit_defaults: bool,
+}
+
+impl<'a> Decoder<'a> {
+    fn message(&self, type_name: &str, bytes: &[u8]) -> Result<Value, Error> {
+        let descriptor = self.pool.message(type_name)?;
+        let mut object = Map::new();
+
+        let mut reader = WireReader(bytes);
+        while let Some((number, wire_value)) = reader.field()? {
+            // Fields unknown to the descriptor are skipped, as protobuf does.
+            let field = match descriptor
+                .get_field()
+                .iter()
+                .find(|field| field.get_number() as u32 == number)
+            {
+                Some(field) => field,
+                None => continue,
+            };
+            let name = json_name(field);
+
+            if field.get_label() != Label::LABEL_REPEATED {
+                object.insert(name, self.value(field, wire_value)?);
+            } else if let Some(entry) = map_entry(self.pool, field)? {
+                let (key, value) = self.map_entry(entry, wire_value)?;
+                if let Value::Object(map) = object
+                    .entry(name)
+                    .or_insert_with(|| Value::Object(Map::new()))
+                {
+                    map.insert(key, value);
+                }
+            } else {
+                let values = match (wire_value, is_packable(field)) {
+                    (WireValue::LengthDelimited(packed), true) => {
+                        let mut reader = WireReader(packed);
+                        let mut values = Vec::new();
+                        while !reader.0.is_empty() {
+                            let wire_value = reader.value(wire_type(field))?;
+                            values.push(self.value(field, wire_value)?);
+                        }
+                        values
+                    }
+                    (wire_value, _) => vec![self.value(field, wire_value)?],
+                };
+                if let Value::Array(array) = object
+                    .entry(name)
+                    .or_insert_with(|| Value::Array(Vec::new()))
+                {
+                    array.extend(values);
+                }
+            }
+        }
+
+        if self.emit_defaults {
+            for field in descriptor.get_field() {
+                // Members of a oneof are only shown when set.
+                if field.has_oneof_index() {
+                    continue;
+                }
+                let name = json_name(field);
+                if !object.contains_key(&name) {
+                    if let Some(value) = self.default_value(field)? {
+                        object.insert(name, value);
+                    }
+                }
+            }
+        }
+
+        Ok(Value::Object(object))
+    }
+
+    /// Decode a single value of `field`.
+    fn value(&self, field: &FieldDescriptorProto, wire_value: WireValue) -> Result<Value, Error> {
+        Ok(match (field.get_field_type(), wire_value) {
+            (Type::TYPE_MESSAGE, WireValue::LengthDelimited(bytes)) => {
+                self.message(field.get_type_name(), bytes)?
+            }
+            (Type::TYPE_STRING, WireValue::LengthDelimited(bytes)) => Value::String(
+                str::from_utf8(bytes)
+                    .map_err(|err| invalid_message(field, err))?
+                    .to_owned(),
+            ),
+            (Type::TYPE_BYTES, WireValue::LengthDelimited(bytes)) => {
+                Value::String(base64::encode(bytes))
+            }
+            (Type::TYPE_INT32, WireValue::Varint(n)) => Value::from(n as i32),
+            (Type::TYPE_INT64, WireValue::Varint(n)) => Value::String((n as i64).to_string()),
+            (Type::TYPE_UINT32, WireValue::Varint(n)) => Value::from(n as u32),
+            (Type::TYPE_UINT64, WireValue::Varint(n)) => Value::String(n.to_string()),
+            (Type::TYPE_SINT32, WireValue::Varint(n)) => {
+                let n = n as u32;
+                Value::from((n >> 1) as i32 ^ -((n & 1) as i32))
+            }
+            (Type::TYPE_SINT64, WireValue::Varint(n)) => {
+                Value::String(((n >> 1) as i64 ^ -((n & 1) as i64)).to_string())
+            }
+            (Type::TYPE_BOOL, WireValue::Varint(n)) => Value::Bool(n != 0),
+            (Type::TYPE_ENUM, WireValue::Varint(n)) => {
+                self.enum_name(field.get_type_name(), n as i32)?
+            }
+            (Type::TYPE_FIXED32, WireValue::Fixed32(n)) => Value::from(n),
+            (Type::TYPE_SFIXED32, WireValue::Fixed32(n)) => Value::from(n as i32),
+            (Type::TYPE_FLOAT, WireValue::Fixed32(n)) => {
+                // Go through the shortest decimal representation of the f32, so
+                // that e.g. 0.1 is not shown as 0.10000000149011612.
+                let f = f32::from_bits(n);
+                float_value(f.to_string().parse().unwrap_or_else(|_| f64::from(f)))
+            }
+            (Type::TYPE_FIXED64, WireValue::Fixed64(n)) => Value::String(n.to_string()),
+            (Type::TYPE_SFIXED64, WireValue::Fixed64(n)) => Value::String((n as i64).to_string()),
+            (Type::TYPE_DOUBLE, WireValue::Fixed64(n)) => float_value(f64::from_bits(n)),
+            (field_type, wire_value) => {
+                return Err(invalid_message(
+                    field,
+                    format!("unexpected {:?} for {:?}", wire_value, field_type),
+                ))
+            }
+        })
+    }
+
+    /// Decode an entry of a map field, returning its key and value.
+    fn map_entry(
+        &self,
+        entry: &DescriptorProto,
+        wire_value: WireValue,
+    ) -> Result<(String, Value), Error> {
+        let (key_field, value_field) = map_entry_fields(entry)?;
+        let bytes = match wire_value {
+            WireValue::LengthDelimited(bytes) => bytes,
+            other => {
+                return Err(invalid_message(
+                    key_field,
+                    format!("unexpected {:?} for a map entry", other),
+                ))
+            }
+        };
+
+        let mut key = None;
+        let mut value = None;
+        let mut reader = WireReader(bytes);
+        while let Some((number, wire_value)) = reader.field()? {
+            if number == key_field.get_number() as u32 {
+                key = Some(self.value(key_field, wire_value)?);
+            } else if number == value_field.get_number() as u32 {
+                value = Some(self.value(value_field, wire_value)?);
+            }
+        }
+
+        // Keys and values missing from an entry have their default values.
+        let key = match key {
+            Some(key) => key,
+            None => self
+                .default_value(key_field)?
+                .unwrap_or_else(|| Value::String(String::new())),
+        };
+        let key = match key {
+            Value::String(key) => key,
+            other => other.to_string(),
+        };
+        let value = match value {
+            Some(value) => value,
+            None => match self.default_value(value_field)? {
+                Some(value) => value,
+                None => Value::Object(Map::new()),
+            },
+        };
+        Ok((key, value))
+    }
+
+    /// The value of an unset field, or `None` for messages, which have no
+    /// default value.
+    fn default_value(&self, field: &FieldDescriptorProto) -> Result<Option<Value>, Error> {
+        if field.get_label() == Label::LABEL_REPEATED {
+            return Ok(Some(if map_entry(self.pool, field)?.is_some() {
+                Value::Object(Map::new())
+            } else {
+                Value::Array(Vec::new())
+            }));
+        }
+        Ok(match field.get_field_type() {
+            Type::TYPE_MESSAGE | Type::TYPE_GROUP => None,
+            Type::TYPE_STRING | Type::TYPE_BYTES => Some(Value::String(String::new())),
+            Type::TYPE_BOOL => Some(Value::Bool(false)),
+            Type::TYPE_ENUM => Some(self.enum_name(field.get_type_name(), 0)?),
+            Type::TYPE_INT64
+            | Type::TYPE_UINT64
+            | Type::TYPE_SINT64
+            | Type::TYPE_FIXED64
+            | Type::TYPE_SFIXED64 => Some(Value::String("0".to_owned())),
+            Type::TYPE_FLOAT | Type::TYPE_DOUBLE => Some(float_value(0.0)),
+            _ => Some(Value::from(0)),
+        })
+    }
+
+    /// The name of an enum value, or its number if it is not known.
+    fn enum_name(&self, type_name: &str, number: i32) -> Result<Value, Error> {
+        Ok(self
+            .pool
+            .enumeration(type_name)?
+            .get_value()
+            .iter()
+            .find(|enum_value| enum_value.get_number() == number)
+            .map(|enum_value| Value::String(enum_value.get_name().to_owned()))
+            .unwrap_or_else(|| Value::from(number)))
+    }
+}
+
+/// A value read from the wire, before it is interpreted according to the type
+/// of its field.
+#[derive(Clone, Copy, Debug)]
+enum WireValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    LengthDelimited(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Reads fields from an encoded message.
+struct WireReader<'a>(&'a [u8]);
+
+impl<'a> WireReader<'a> {
+    /// The number and value of the next field, if any.
+    fn field(&mut self) -> Result<Option<(u32, WireValue<'a>)>, Error> {
+        if self.0.is_empty() {
+            return Ok(None);
+        }
+        let tag = self.varint()?;
+        let number = u32::try_from(tag >> 3)
+            .map_err(|_| Error::InvalidMessage(format!("invalid tag {}", tag)))?;
+        let value = self.value((tag & 0x7) as u32)?;
+        Ok(Some((number, value)))
+    }
+
+    /// Read a value of the given wire type.
+    fn value(&mut self, wire_type: u32) -> Result<WireValue<'a>, Error> {
+        Ok(match wire_type {
+            WIRE_VARINT => WireValue::Varint(self.varint()?),
+            WIRE_FIXED64 => {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(self.take(8)?);
+                WireValue::Fixed64(u64::from_le_bytes(bytes))
+            }
+            WIRE_LENGTH_DELIMITED => {
+                let len = usize::try_from(self.varint()?)
+                    .map_err(|_| Error::InvalidMessage("length overflow".to_owned()))?;
+                WireValue::LengthDelimited(self.take(len)?)
+            }
+            WIRE_FIXED32 => {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(self.take(4)?);
+                WireValue::Fixed32(u32::from_le_bytes(bytes))
+            }
+            // Groups are deprecated, and cannot be declared in proto3.
+            other => {
+                return Err(Error::InvalidMessage(format!(
+                    "unsupported wire type {}",
+                    other
+                )))
+            }
+        })
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let mut result = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            result |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(Error::InvalidMessage("varint overflow".to_owned()))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::InvalidMessage(
+                "unexpected end of message".to_owned(),
+            ));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+}
+
+fn write_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_tag(number: i32, wire_type: u32, buf: &mut Vec<u8>) {
+    write_varint((u64::from(number as u32) << 3) | u64::from(wire_type), buf);
+}
+
+fn write_length_delimited(bytes: &[u8], buf: &mut Vec<u8>) {
+    write_varint(bytes.len() as u64, buf);
+    buf.extend_from_slice(bytes);
+}
+
+/// The wire type of a field's values.
+fn wire_type(field: &FieldDescriptorProto) -> u32 {
+    match field.get_field_type() {
+        Type::TYPE_FIXED64 | Type::TYPE_SFIXED64 | Type::TYPE_DOUBLE => WIRE_FIXED64,
+        Type::TYPE_FIXED32 | Type::TYPE_SFIXED32 | Type::TYPE_FLOAT => WIRE_FIXED32,
+        Type::TYPE_STRING | Type::TYPE_BYTES | Type::TYPE_MESSAGE => WIRE_LENGTH_DELIMITED,
+        Type::TYPE_GROUP => WIRE_START_GROUP,
+        _ => WIRE_VARINT,
+    }
+}
+
+/// Whether repeated values of a field can be packed.
+fn is_packable(field: &FieldDescriptorProto) -> bool {
+    matches!(wire_type(field), WIRE_VARINT | WIRE_FIXED32 | WIRE_FIXED64)
+}
+
+/// The entry message of a map field, if `field` is one.
+fn map_entry<'a>(
+    pool: &'a DescriptorPool,
+    field: &FieldDescriptorProto,
+) -> Result<Option<&'a DescriptorProto>, Error> {
+    if field.get_label() != Label::LABEL_REPEATED || field.get_field_type() != Type::TYPE_MESSAGE {
+        return Ok(None);
+    }
+    let message = pool.message(field.get_type_name())?;
+    Ok(if message.get_options().get_map_entry() {
+        Some(message)
+    } else {
+        None
+    })
+}
+
+/// The key and value fields of a map entry message.
+fn map_entry_fields(
+    entry: &DescriptorProto,
+) -> Result<(&FieldDescriptorProto, &FieldDescriptorProto), Error> {
+    let field = |number| {
+        entry
+            .get_field()
+            .iter()
+            .find(|field| field.get_number() == number)
+            .ok_or_else(|| Error::UnknownType(format!("{} field {}", entry.get_name(), number)))
+    };
+    Ok((field(1)?, field(2)?))
+}
+
+/// The JSON name of a field, which protoc sets to the lowerCamelCase version of
+/// its name.
+fn json_name(field: &FieldDescriptorProto) -> String {
+    if field.has_json_name() {
+        return field.get_json_name().to_owned();
+    }
+    let mut name = String::with_capacity(field.get_name().len());
+    let mut capitalize = false;
+    for c in field.get_name().chars() {
+        if c == '_' {
+            capitalize = true;
+        } else if capitalize {
+            name.extend(c.to_uppercase());
+            capitalize = false;
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// Parse an integer, given as a JSON number or as a string.
+fn int<T: TryFrom<i128>>(value: &Value, path: &str) -> Result<T, Error> {
+    let n = match value {
+        Value::Number(n) => n
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from))
+            .or_else(|| {
+                n.as_f64()
+                    .filter(|f| f.is_finite() && f.fract() == 0.0)
+                    .map(|f| f as i128)
+            }),
+        Value::String(s) => s.trim().parse::<i128>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| invalid_json(path, "expected an integer"))?;
+    T::try_from(n).map_err(|_| invalid_json(path, format!("{} is out of range", n)))
+}
+
+/// Parse a floating point number, given as a JSON number or as a string.
+fn float(value: &Value, path: &str) -> Result<f64, Error> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            s => s.trim().parse().ok(),
+        },
+        _ => None,
+    }
+    .ok_or_else(|| invalid_json(path, "expected a number"))
+}
+
+/// The JSON value of a floating point number. JSON has no representation of
+/// infinities and NaN, so these are strings.
+fn float_value(f: f64) -> Value {
+    match Number::from_f64(f) {
+        Some(n) => Value::Number(n),
+        None if f.is_nan() => Value::String("NaN".to_owned()),
+        None if f > 0.0 => Value::String("Infinity".to_owned()),
+        None => Value::String("-Infinity".to_owned()),
+    }
+}
+
+fn invalid_json(path: &str, message: impl Display) -> Error {
+    Error::InvalidJson(format!("{}: {}", path, message))
+}
+
+fn invalid_message(field: &FieldDescriptorProto, message: impl Display) -> Error {
+    Error::InvalidMessage(format!("{}: {}", field.get_name(), message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_consensus_api::{
+        blockchain::Block,
+        consensus_client::{TxStatus, TxStatusResponse},
+        consensus_common::{BlocksResponse, LastBlockInfoResponse, ProposeTxResult},
+    };
+    use mc_mobilecoind_api::GetNetworkStatusResponse;
+    use protobuf::{parse_from_bytes, Message, RepeatedField};
+    use serde_json::json;
+
+    #[test]
+    fn round_trip_scalars() {
+        let pool = DescriptorPool::compiled();
+        let type_name = "consensus_common.LastBlockInfoResponse";
+
+        let mut response = LastBlockInfoResponse::new();
+        response.set_index(12345);
+        response.set_minimum_fee(u64::MAX);
+        let bytes = response.write_to_bytes().unwrap();
+
+        let json = decode(&pool, type_name, &bytes, false).unwrap();
+        assert_eq!(
+            json,
+            json!({"index": "12345", "minimumFee": "18446744073709551615"})
+        );
+        assert_eq!(encode(&pool, type_name, &json).unwrap(), bytes);
+
+        // Numbers, and the original field names, are also accepted.
+        let json = json!({"index": 12345, "minimum_fee": "18446744073709551615"});
+        assert_eq!(encode(&pool, type_name, &json).unwrap(), bytes);
+    }
+
+    #[test]
+    fn round_trip_enums() {
+        let pool = DescriptorPool::compiled();
+        let type_name = "consensus_client.TxStatusResponse";
+
+        let mut response = TxStatusResponse::new();
+        response.set_status(TxStatus::Rejected);
+        response.set_rejection_reason(ProposeTxResult::Evicted);
+        response.set_block_count(3);
+        let bytes = response.write_to_bytes().unwrap();
+
+        let json = decode(&pool, type_name, &bytes, false).unwrap();
+        assert_eq!(
+            json,
+            json!({"status": "Rejected", "rejectionReason": "Evicted", "blockCount": "3"})
+        );
+        assert_eq!(encode(&pool, type_name, &json).unwrap(), bytes);
+
+        // Enums may also be given by number.
+        let json = json!({"status": 2, "rejectionReason": 5, "blockCount": 3});
+        assert_eq!(encode(&pool, type_name, &json).unwrap(), bytes);
+    }
+
+    #[test]
+    fn round_trip_nested_messages() {
+        let pool = DescriptorPool::compiled();
+        let type_name = "consensus_common.BlocksResponse";
+
+        let mut block = Block::new();
+        block.set_index(7);
+        block.mut_id().set_data(vec![1, 2, 3]);
+        let mut response = BlocksResponse::new();
+        response.set_blocks(RepeatedField::from_vec(vec![block.clone(), block]));
+        let bytes = response.write_to_bytes().unwrap();
+
+        let json = decode(&pool, type_name, &bytes, false).unwrap();
+        let blocks = json["blocks"].as_array().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0]["index"], json!("7"));
+        assert_eq!(blocks[0]["id"]["data"], json!("AQID"));
+
+        let encoded = encode(&pool, type_name, &json).unwrap();
+        assert_eq!(
+            parse_from_bytes::<BlocksResponse>(&encoded).unwrap(),
+            response
+        );
+    }
+
+    #[test]
+    fn round_trip_maps() {
+        let pool = DescriptorPool::compiled();
+        let type_name = "mobilecoind_api.GetNetworkStatusResponse";
+
+        let json = json!({
+            "networkHighestBlockIndex": "10",
+            "peerBlockIndexMap": {"node1:443": "9", "node2:443": "10"},
+            "isBehind": true,
+        });
+        let bytes = encode(&pool, type_name, &json).unwrap();
+
+        let response = parse_from_bytes::<GetNetworkStatusResponse>(&bytes).unwrap();
+        assert_eq!(response.get_network_highest_block_index(), 10);
+        assert_eq!(
+            response.get_peer_block_index_map().get("node1:443"),
+            Some(&9)
+        );
+        assert_eq!(
+            response.get_peer_block_index_map().get("node2:443"),
+            Some(&10)
+        );
+        assert!(response.get_is_behind());
+
+        assert_eq!(decode(&pool, type_name, &bytes, false).unwrap(), json);
+    }
+
+    #[test]
+    fn emit_defaults() {
+        let pool = DescriptorPool::compiled();
+        let type_name = "consensus_client.TxStatusResponse";
+
+        assert_eq!(decode(&pool, type_name, &[], false).unwrap(), json!({}));
+        assert_eq!(
+            decode(&pool, type_name, &[], true).unwrap(),
+            json!({
+                "status": "Unknown",
+                "rejectionReason": "Ok",
+                "blockIndex": "0",
+                "blockCount": "0",
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_json_is_rejected() {
+        let pool = DescriptorPool::compiled();
+        let type_name = "consensus_common.BlocksRequest";
+
+        for json in &[
+            json!([]),
+            json!({"unknown": 1}),
+            json!({"offset": "not a number"}),
+            json!({"limit": -1}),
+            json!({"limit": 4294967296u64}),
+        ] {
+            assert!(encode(&pool, type_name, json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn truncated_messages_are_rejected() {
+        let pool = DescriptorPool::compiled();
+        let mut response = LastBlockInfoResponse::new();
+        response.set_index(u64::MAX);
+        let bytes = response.write_to_bytes().unwrap();
+
+        assert!(decode(
+            &pool,
+            "consensus_common.LastBlockInfoResponse",
+            &bytes[..bytes.len() - 1],
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn varints() {
+        for n in &[0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(*n, &mut buf);
+            let mut reader = WireReader(&buf);
+            assert_eq!(reader.varint().unwrap(), *n);
+            assert!(reader.0.is_empty());
+        }
+
+        let mut buf = Vec::new();
+        write_varint(300, &mut buf);
+        assert_eq!(buf, vec![0xac, 0x02]);
+    }
+}
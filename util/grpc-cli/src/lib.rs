@@ -0,0 +1,21 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! A generic client for the gRPC APIs of MobileCoin services. Services and
+//! message types are discovered through gRPC server reflection, and messages
+//! are converted to and from JSON.
+
+pub mod attested;
+pub mod call;
+pub mod descriptors;
+pub mod json;
+pub mod reflection;
+pub mod uri;
+
+mod error;
+
+pub use crate::{
+    descriptors::{DescriptorPool, MethodDescriptor},
+    error::Error,
+    reflection::ReflectionClient,
+    uri::ServiceUri,
+};
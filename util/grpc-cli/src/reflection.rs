@@ -0,0 +1,118 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! A client for the gRPC Server Reflection Protocol, used to discover the
+//! services of a server and the descriptors of their messages.
+
+use crate::{descriptors::DescriptorPool, Error};
+use futures::{executor::block_on, SinkExt, StreamExt};
+use grpcio::{CallOption, Channel, ClientDuplexReceiver, ClientDuplexSender, WriteFlags};
+use mc_util_grpc::{
+    reflection::{ServerReflectionRequest, ServerReflectionResponse},
+    reflection_grpc::ServerReflectionClient,
+    REFLECTION_SERVICE_NAME,
+};
+use protobuf::{descriptor::FileDescriptorProto, parse_from_bytes};
+
+/// A reflection stream to a server. Requests are answered one at a time.
+pub struct ReflectionClient {
+    /// Kept alive for the duration of the stream.
+    _client: ServerReflectionClient,
+    sender: ClientDuplexSender<ServerReflectionRequest>,
+    receiver: ClientDuplexReceiver<ServerReflectionResponse>,
+}
+
+impl ReflectionClient {
+    /// Open a reflection stream on `channel`.
+    pub fn new(channel: Channel, call_option: CallOption) -> Result<Self, Error> {
+        let client = ServerReflectionClient::new(channel);
+        let (sender, receiver) = client.server_reflection_info_opt(call_option)?;
+        Ok(Self {
+            _client: client,
+            sender,
+            receiver,
+        })
+    }
+
+    /// Full names of the services offered by the server, other than the
+    /// reflection service itself.
+    pub fn list_services(&mut self) -> Result<Vec<String>, Error> {
+        let mut request = ServerReflectionRequest::new();
+        request.set_list_services(String::new());
+        let response = self.request(request)?;
+
+        if !response.has_list_services_response() {
+            return Err(Error::Reflection("expected a list of services".to_owned()));
+        }
+        Ok(response
+            .get_list_services_response()
+            .get_service()
+            .iter()
+            .map(|service| service.get_name().to_owned())
+            .filter(|name| name != REFLECTION_SERVICE_NAME)
+            .collect())
+    }
+
+    /// The file declaring `symbol`, followed by the files it depends on.
+    pub fn file_containing_symbol(
+        &mut self,
+        symbol: &str,
+    ) -> Result<Vec<FileDescriptorProto>, Error> {
+        let mut request = ServerReflectionRequest::new();
+        request.set_file_containing_symbol(symbol.to_owned());
+        let response = self.request(request)?;
+
+        if !response.has_file_descriptor_response() {
+            return Err(Error::Reflection(format!(
+                "expected the descriptors of {}",
+                symbol
+            )));
+        }
+        response
+            .get_file_descriptor_response()
+            .get_file_descriptor_proto()
+            .iter()
+            .map(|bytes| Ok(parse_from_bytes(bytes)?))
+            .collect()
+    }
+
+    /// Add the descriptors of `services`, and of the types they use, to
+    /// `pool`.
+    pub fn load_services(
+        &mut self,
+        services: &[String],
+        pool: &mut DescriptorPool,
+    ) -> Result<(), Error> {
+        for service in services {
+            for file in self.file_containing_symbol(service)? {
+                pool.add_file(file);
+            }
+        }
+        Ok(())
+    }
+
+    fn request(
+        &mut self,
+        request: ServerReflectionRequest,
+    ) -> Result<ServerReflectionResponse, Error> {
+        block_on(self.sender.send((request, WriteFlags::default())))?;
+        let response = block_on(self.receiver.next())
+            .ok_or_else(|| Error::Reflection("the server closed the stream".to_owned()))??;
+
+        if response.has_error_response() {
+            let error = response.get_error_response();
+            return Err(Error::Reflection(format!(
+                "{} (code {})",
+                error.get_error_message(),
+                error.get_error_code()
+            )));
+        }
+        Ok(response)
+    }
+}
+
+impl Drop for ReflectionClient {
+    fn drop(&mut self) {
+        // Let the server know we are done, errors are of no consequence here.
+        let _ = block_on(self.sender.close());
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! URIs of the services this tool can call.
+
+use grpcio::{Channel, ChannelBuilder, Environment};
+use mc_common::logger::Logger;
+use mc_connection::HardcodedCredentialsProvider;
+use mc_mobilecoind_api::MobilecoindUri;
+use mc_util_grpc::ConnectionUriGrpcioChannel;
+use mc_util_uri::{AdminUri, ConsensusClientUri, ConsensusPeerUri, FogUri, WatcherUri};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+    sync::Arc,
+};
+
+/// The URI of a service, told apart by its scheme.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ServiceUri {
+    /// `mca://`, the admin API of any service.
+    Admin(AdminUri),
+
+    /// `mc://`, the client API of a consensus node.
+    Consensus(ConsensusClientUri),
+
+    /// `mcp://`, the peer API of a consensus node.
+    ConsensusPeer(ConsensusPeerUri),
+
+    /// `fog://`, a fog report server.
+    Fog(FogUri),
+
+    /// `mobilecoind://`
+    Mobilecoind(MobilecoindUri),
+
+    /// `watcher://`
+    Watcher(WatcherUri),
+}
+
+/// Evaluate an expression for the URI held by a `ServiceUri`, whatever its
+/// type.
+macro_rules! with_uri {
+    ($service_uri:expr, $uri:ident => $body:expr) => {
+        match $service_uri {
+            ServiceUri::Admin($uri) => $body,
+            ServiceUri::Consensus($uri) => $body,
+            ServiceUri::ConsensusPeer($uri) => $body,
+            ServiceUri::Fog($uri) => $body,
+            ServiceUri::Mobilecoind($uri) => $body,
+            ServiceUri::Watcher($uri) => $body,
+        }
+    };
+}
+
+impl ServiceUri {
+    /// Open a channel to the service.
+    pub fn connect(&self, env: Arc<Environment>, logger: &Logger) -> Channel {
+        let builder = ChannelBuilder::default_channel_builder(env);
+        with_uri!(self, uri => builder.connect_to_uri(uri, logger))
+    }
+
+    /// The credentials given in the URI, e.g. `mc://user:password@host/`.
+    pub fn credentials_provider(&self) -> HardcodedCredentialsProvider {
+        with_uri!(self, uri => HardcodedCredentialsProvider::from(uri))
+    }
+}
+
+impl Display for ServiceUri {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        with_uri!(self, uri => write!(f, "{}", uri))
+    }
+}
+
+impl FromStr for ServiceUri {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        // Each URI type only parses its own schemes.
+        if let Ok(uri) = AdminUri::from_str(src) {
+            return Ok(Self::Admin(uri));
+        }
+        if let Ok(uri) = ConsensusClientUri::from_str(src) {
+            return Ok(Self::Consensus(uri));
+        }
+        if let Ok(uri) = ConsensusPeerUri::from_str(src) {
+            return Ok(Self::ConsensusPeer(uri));
+        }
+        if let Ok(uri) = FogUri::from_str(src) {
+            return Ok(Self::Fog(uri));
+        }
+        if let Ok(uri) = MobilecoindUri::from_str(src) {
+            return Ok(Self::Mobilecoind(uri));
+        }
+        if let Ok(uri) = WatcherUri::from_str(src) {
+            return Ok(Self::Watcher(uri));
+        }
+        Err(format!(
+            "Invalid URI {}, expected a mc, mcp, mca, fog, mobilecoind or watcher URI \
+             (or their insecure- variants)",
+            src
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_service_uris() {
+        assert!(matches!(
+            ServiceUri::from_str("mc://node1.test.mobilecoin.com/"),
+            Ok(ServiceUri::Consensus(_))
+        ));
+        assert!(matches!(
+            ServiceUri::from_str("insecure-mca://127.0.0.1:9090/"),
+            Ok(ServiceUri::Admin(_))
+        ));
+        assert!(matches!(
+            ServiceUri::from_str("insecure-mobilecoind://localhost/"),
+            Ok(ServiceUri::Mobilecoind(_))
+        ));
+        assert!(ServiceUri::from_str("https://localhost/").is_err());
+        assert!(ServiceUri::from_str("not a uri").is_err());
+    }
+
+    #[test]
+    fn credentials_from_uri() {
+        use mc_connection::CredentialsProvider;
+
+        let uri = ServiceUri::from_str("mc://user:pass@node1.test.mobilecoin.com/").unwrap();
+        let creds = uri
+            .credentials_provider()
+            .get_credentials()
+            .unwrap()
+            .unwrap();
+        assert_eq!(creds.username(), "user");
+        assert_eq!(creds.password(), "pass");
+    }
+}
@@ -1,7 +1,8 @@
 //! A standardized admin GRPC server
 
 use crate::{
-    AdminService, BuildInfoService, ConnectionUriGrpcioServer, GetConfigJsonFn, HealthService,
+    AdminService, Authenticator, BuildInfoService, ConnectionUriGrpcioServer, GetConfigJsonFn,
+    GetStatusJsonFn, HealthService, ReflectionService, ServiceHealthCheckCallback, SetConfigJsonFn,
 };
 use grpcio::{Environment, ShutdownFuture};
 use mc_common::logger::{log, Logger};
@@ -19,6 +20,10 @@ impl AdminServer {
         name: String,
         id: String,
         get_config_json: Option<GetConfigJsonFn>,
+        set_config_json: Option<SetConfigJsonFn>,
+        set_config_json_authenticator: Arc<dyn Authenticator + Send + Sync>,
+        get_status_json: Option<GetStatusJsonFn>,
+        health_check_callback: Option<ServiceHealthCheckCallback>,
         logger: Logger,
     ) -> Result<Self, grpcio::Error> {
         log::info!(
@@ -37,15 +42,35 @@ impl AdminServer {
         });
 
         // Initialize services.
-        let admin_service =
-            AdminService::new(name, id, get_config_json, logger.clone()).into_service();
-        let health_service = HealthService::new(None, logger.clone()).into_service();
+        let admin_service = AdminService::new(
+            name,
+            id,
+            get_config_json,
+            set_config_json,
+            set_config_json_authenticator,
+            get_status_json,
+            logger.clone(),
+        )
+        .into_service();
+        let health_service =
+            HealthService::new(health_check_callback, logger.clone()).into_service();
         let build_info_service = BuildInfoService::new(logger.clone()).into_service();
+        let reflection_service = ReflectionService::new(
+            &[
+                "admin.AdminAPI",
+                "build_info.BuildInfoApi",
+                "grpc.health.v1.Health",
+            ],
+            Vec::new(),
+            logger.clone(),
+        )
+        .into_service();
 
         let server_builder = grpcio::ServerBuilder::new(env)
             .register_service(admin_service)
             .register_service(health_service)
             .register_service(build_info_service)
+            .register_service(reflection_service)
             .bind_using_uri(admin_listen_uri, logger.clone());
 
         let mut server = server_builder.build()?;
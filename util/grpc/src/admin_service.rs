@@ -3,11 +3,14 @@
 //! Customizable implementation of the AdminApi service.
 
 use crate::{
-    admin::{GetInfoResponse, GetPrometheusMetricsResponse, SetRustLogRequest},
+    admin::{
+        GetInfoResponse, GetPrometheusMetricsResponse, GetServiceStatusResponse,
+        SetConfigJsonRequest, SetRustLogRequest,
+    },
     admin_grpc::{create_admin_api, AdminApi},
     build_info_service::get_build_info,
     empty::Empty,
-    rpc_logger, send_result,
+    rpc_logger, send_result, Authenticator,
 };
 use grpcio::{RpcContext, RpcStatus, RpcStatusCode, Service, UnarySink};
 use mc_common::logger::{log, Logger};
@@ -18,6 +21,12 @@ use std::{env, sync::Arc};
 /// A callback for getting service-specific configuration data.
 pub type GetConfigJsonFn = Arc<dyn Fn() -> Result<String, RpcStatus> + Sync + Send>;
 
+/// A callback for applying a service-specific configuration change.
+pub type SetConfigJsonFn = Arc<dyn Fn(String) -> Result<(), RpcStatus> + Sync + Send>;
+
+/// A callback for getting service-specific status data.
+pub type GetStatusJsonFn = Arc<dyn Fn() -> Result<String, RpcStatus> + Sync + Send>;
+
 /// Admin GRPC service.
 #[derive(Clone)]
 pub struct AdminService {
@@ -30,6 +39,17 @@ pub struct AdminService {
     /// Optional callback for returning service-specific configuration JSON blob
     get_config_json: Option<GetConfigJsonFn>,
 
+    /// Optional callback for applying a service-specific configuration JSON
+    /// blob
+    set_config_json: Option<SetConfigJsonFn>,
+
+    /// Authenticates callers of SetConfigJson, since config changes may alter
+    /// who the service trusts.
+    set_config_json_authenticator: Arc<dyn Authenticator + Send + Sync>,
+
+    /// Optional callback for returning a service-specific status JSON blob
+    get_status_json: Option<GetStatusJsonFn>,
+
     /// Logger.
     logger: Logger,
 }
@@ -39,12 +59,18 @@ impl AdminService {
         name: String,
         id: String,
         get_config_json: Option<GetConfigJsonFn>,
+        set_config_json: Option<SetConfigJsonFn>,
+        set_config_json_authenticator: Arc<dyn Authenticator + Send + Sync>,
+        get_status_json: Option<GetStatusJsonFn>,
         logger: Logger,
     ) -> Self {
         Self {
             name,
             id,
             get_config_json,
+            set_config_json,
+            set_config_json_authenticator,
+            get_status_json,
             logger,
         }
     }
@@ -131,6 +157,43 @@ impl AdminService {
 
         Ok(Empty::new())
     }
+
+    fn set_config_json_impl(
+        &mut self,
+        request: SetConfigJsonRequest,
+        logger: &Logger,
+    ) -> Result<Empty, RpcStatus> {
+        log::info!(logger, "Applying config change: {}", request.config_json);
+
+        let set_config_json = self.set_config_json.as_ref().ok_or_else(|| {
+            RpcStatus::with_message(
+                RpcStatusCode::UNIMPLEMENTED,
+                format!("{} does not support config changes", self.name),
+            )
+        })?;
+        set_config_json(request.config_json)?;
+
+        Ok(Empty::new())
+    }
+
+    fn get_service_status_impl(
+        &mut self,
+        _request: Empty,
+        logger: &Logger,
+    ) -> Result<GetServiceStatusResponse, RpcStatus> {
+        log::trace!(logger, "get_service_status_impl");
+
+        let get_status_json = self.get_status_json.as_ref().ok_or_else(|| {
+            RpcStatus::with_message(
+                RpcStatusCode::UNIMPLEMENTED,
+                format!("{} does not report its status", self.name),
+            )
+        })?;
+
+        let mut response = GetServiceStatusResponse::new();
+        response.set_status_json(get_status_json()?);
+        Ok(response)
+    }
 }
 
 impl AdminApi for AdminService {
@@ -181,4 +244,155 @@ impl AdminApi for AdminService {
             )
         });
     }
+
+    fn set_config_json(
+        &mut self,
+        ctx: RpcContext,
+        request: SetConfigJsonRequest,
+        sink: UnarySink<Empty>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = self.set_config_json_authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), &logger);
+            }
+            send_result(
+                ctx,
+                sink,
+                self.set_config_json_impl(request, &logger),
+                &logger,
+            )
+        });
+    }
+
+    fn get_service_status(
+        &mut self,
+        ctx: RpcContext,
+        request: Empty,
+        sink: UnarySink<GetServiceStatusResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            send_result(
+                ctx,
+                sink,
+                self.get_service_status_impl(request, &logger),
+                &logger,
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        admin_grpc::AdminApiClient, AnonymousAuthenticator, TokenAuthenticator,
+        TokenBasicCredentialsGenerator,
+    };
+    use grpcio::{ChannelBuilder, EnvBuilder, Error as GrpcError, Server, ServerBuilder};
+    use mc_common::{logger::test_with_logger, time::SystemTimeProvider};
+    use std::{sync::Mutex, time::Duration};
+
+    const SHARED_SECRET: [u8; 32] = [7; 32];
+
+    /// Start an admin service recording the config changes it applies.
+    fn create_test_server(
+        set_config_json_authenticator: Arc<dyn Authenticator + Send + Sync>,
+        logger: Logger,
+    ) -> (Server, AdminApiClient, Arc<Mutex<Vec<String>>>) {
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let set_config_json: SetConfigJsonFn = {
+            let applied = applied.clone();
+            Arc::new(move |config_json| {
+                applied.lock().unwrap().push(config_json);
+                Ok(())
+            })
+        };
+        let service = AdminService::new(
+            "Test Service".to_owned(),
+            "test".to_owned(),
+            None,
+            Some(set_config_json),
+            set_config_json_authenticator,
+            None,
+            logger,
+        )
+        .into_service();
+
+        let env = Arc::new(EnvBuilder::new().build());
+        let mut server = ServerBuilder::new(env.clone())
+            .register_service(service)
+            .bind("127.0.0.1", 0)
+            .build()
+            .unwrap();
+        server.start();
+        let port = server.bind_addrs().next().unwrap().1;
+
+        let ch = ChannelBuilder::new(env).connect(&format!("127.0.0.1:{}", port));
+        (server, AdminApiClient::new(ch), applied)
+    }
+
+    fn set_config_json_request(config_json: &str) -> SetConfigJsonRequest {
+        let mut request = SetConfigJsonRequest::new();
+        request.set_config_json(config_json.to_owned());
+        request
+    }
+
+    #[test_with_logger]
+    fn set_config_json_requires_authentication(logger: Logger) {
+        let authenticator = Arc::new(TokenAuthenticator::new(
+            SHARED_SECRET,
+            Duration::from_secs(60),
+            SystemTimeProvider::default(),
+        ));
+        let (_server, client, applied) = create_test_server(authenticator, logger);
+
+        // Without a token, the change is refused.
+        match client.set_config_json(&set_config_json_request("{\"a\": 1}")) {
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::UNAUTHENTICATED)
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // A token generated with another secret is refused.
+        let forged = TokenBasicCredentialsGenerator::new([8; 32], SystemTimeProvider::default())
+            .generate_for("admin")
+            .unwrap();
+        assert!(client
+            .set_config_json_opt(
+                &set_config_json_request("{\"a\": 2}"),
+                forged.call_option().unwrap()
+            )
+            .is_err());
+        assert!(applied.lock().unwrap().is_empty());
+
+        // A valid token is accepted.
+        let credentials =
+            TokenBasicCredentialsGenerator::new(SHARED_SECRET, SystemTimeProvider::default())
+                .generate_for("admin")
+                .unwrap();
+        client
+            .set_config_json_opt(
+                &set_config_json_request("{\"a\": 3}"),
+                credentials.call_option().unwrap(),
+            )
+            .expect("set_config_json failed");
+        assert_eq!(*applied.lock().unwrap(), vec!["{\"a\": 3}".to_owned()]);
+
+        // Other calls do not need a token.
+        client.get_info(&Empty::new()).expect("get_info failed");
+    }
+
+    #[test_with_logger]
+    fn set_config_json_anonymous(logger: Logger) {
+        let (_server, client, applied) =
+            create_test_server(Arc::new(AnonymousAuthenticator::default()), logger);
+
+        client
+            .set_config_json(&set_config_json_request("{}"))
+            .expect("set_config_json failed");
+        assert_eq!(*applied.lock().unwrap(), vec!["{}".to_owned()]);
+    }
 }
@@ -0,0 +1,85 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! GRPC authenticator that accepts a single, fixed username and password.
+
+use super::*;
+
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+/// Password-based authentication: An object that implements `Authenticator`,
+/// accepting only the configured username and password. This is intended for
+/// operator-facing interfaces, such as admin gateways.
+pub struct PasswordAuthenticator {
+    /// The only username accepted.
+    username: String,
+
+    /// The password the user must present.
+    password: String,
+}
+
+impl PasswordAuthenticator {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        }
+    }
+}
+
+impl Drop for PasswordAuthenticator {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+impl Authenticator for PasswordAuthenticator {
+    fn authenticate(
+        &self,
+        maybe_credentials: Option<BasicCredentials>,
+    ) -> Result<String, AuthenticatorError> {
+        let credentials = maybe_credentials.ok_or(AuthenticatorError::Unauthenticated)?;
+
+        // Compare digests, so that the time taken does not depend on the length
+        // of the expected password either.
+        let expected = Sha256::digest(self.password.as_bytes());
+        let actual = Sha256::digest(credentials.password().as_bytes());
+        let password_matches: bool = expected.as_slice().ct_eq(actual.as_slice()).into();
+
+        if credentials.username() != self.username || !password_matches {
+            return Err(AuthenticatorError::InvalidCredentials);
+        }
+        Ok(self.username.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_password_authenticates_successfully() {
+        let authenticator = PasswordAuthenticator::new("admin", "hunter2");
+        let user = authenticator
+            .authenticate(Some(BasicCredentials::new("admin", "hunter2")))
+            .expect("authenticate failed");
+        assert_eq!(user, "admin");
+    }
+
+    #[test]
+    fn wrong_credentials_fail_authentication() {
+        let authenticator = PasswordAuthenticator::new("admin", "hunter2");
+        for (username, password) in &[
+            ("admin", "hunter3"),
+            ("admin", ""),
+            ("root", "hunter2"),
+            ("", ""),
+        ] {
+            assert!(authenticator
+                .authenticate(Some(BasicCredentials::new(username, password)))
+                .is_err());
+        }
+        assert!(authenticator.authenticate(None).is_err());
+    }
+}
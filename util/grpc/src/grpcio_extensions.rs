@@ -4,10 +4,7 @@
 //! them using URIs.
 
 use crate::ServerCertReloader;
-use grpcio::{
-    CertificateRequestType, Channel, ChannelBuilder, ChannelCredentialsBuilder, Environment,
-    ServerBuilder,
-};
+use grpcio::{Channel, ChannelBuilder, ChannelCredentialsBuilder, Environment, ServerBuilder};
 use mc_common::logger::{log, Logger};
 use mc_util_uri::ConnectionUri;
 use std::{sync::Arc, time::Duration};
@@ -58,7 +55,8 @@ impl ConnectionUriGrpcioChannel for ChannelBuilder {
 /// A trait to ease grpio server construction from URIs.
 pub trait ConnectionUriGrpcioServer {
     /// Bind a ServerBuilder using information from a URI and enable support for
-    /// hot-reloading certificates when TLS is used.
+    /// hot-reloading certificates when TLS is used. When the URI has a
+    /// `tls-client-ca`, clients must present a certificate signed by it.
     fn bind_using_uri(self, uri: &impl ConnectionUri, logger: Logger) -> Self;
 }
 
@@ -72,14 +70,22 @@ impl ConnectionUriGrpcioServer for ServerBuilder {
                 .tls_key_path()
                 .expect("Uri must have tls-key in when using TLS");
 
-            let reloader = ServerCertReloader::new(&tls_chain_path, &tls_key_path, logger)
-                .expect("Failed creating ServerCertReloader");
+            let tls_client_ca_path = uri.tls_client_ca_path();
+
+            let reloader = ServerCertReloader::with_client_ca(
+                &tls_chain_path,
+                &tls_key_path,
+                tls_client_ca_path.as_ref(),
+                logger,
+            )
+            .expect("Failed creating ServerCertReloader");
+            let certificate_request_type = reloader.certificate_request_type();
 
             self.bind_with_fetcher(
                 uri.host(),
                 uri.port(),
                 Box::new(reloader),
-                CertificateRequestType::DontRequestClientCertificate,
+                certificate_request_type,
             )
         } else {
             self.bind(uri.host(), uri.port())
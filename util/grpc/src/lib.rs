@@ -20,22 +20,28 @@ mod build_info_service;
 mod cookie_helper;
 mod grpcio_extensions;
 mod health_service;
+mod reflection_service;
 mod server_cert_reloader;
+mod shutdown_signal;
+mod trace_context;
 
 pub use crate::{
     admin_server::AdminServer,
-    admin_service::{AdminService, GetConfigJsonFn},
+    admin_service::{AdminService, GetConfigJsonFn, GetStatusJsonFn, SetConfigJsonFn},
     auth::{
         AnonymousAuthenticator, Authenticator, AuthenticatorError, AuthorizationHeaderError,
-        BasicCredentials, TokenAuthenticator, TokenBasicCredentialsGenerator,
-        TokenBasicCredentialsGeneratorError, ANONYMOUS_USER,
+        BasicCredentials, PasswordAuthenticator, TokenAuthenticator,
+        TokenBasicCredentialsGenerator, TokenBasicCredentialsGeneratorError, ANONYMOUS_USER,
     },
     autogenerated_code::*,
     build_info_service::BuildInfoService,
     cookie_helper::{Error as CookieError, GrpcCookieStore},
     grpcio_extensions::{ConnectionUriGrpcioChannel, ConnectionUriGrpcioServer},
-    health_service::{HealthCheckStatus, HealthService},
+    health_service::{HealthCheckStatus, HealthService, ServiceHealthCheckCallback},
+    reflection_service::{empty_file_descriptor, ReflectionService, REFLECTION_SERVICE_NAME},
     server_cert_reloader::{ServerCertReloader, ServerCertReloaderError},
+    shutdown_signal::ShutdownSignal,
+    trace_context::{extract_trace_context, inject_trace_context, rpc_span},
 };
 
 use futures::prelude::*;
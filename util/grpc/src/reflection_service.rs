@@ -0,0 +1,419 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Implementation of the [gRPC Server Reflection
+//! Protocol](https://github.com/grpc/grpc/blob/master/doc/server-reflection.md),
+//! which lets generic clients such as `grpcurl` and `mc-grpc-cli` discover the
+//! services offered by a server and the messages they use.
+
+use crate::{
+    reflection::{
+        ErrorResponse, ExtensionNumberResponse, FileDescriptorResponse, ListServiceResponse,
+        ServerReflectionRequest, ServerReflectionResponse, ServiceResponse,
+    },
+    reflection_grpc::{create_server_reflection, ServerReflection},
+};
+use futures::prelude::*;
+use grpcio::{DuplexSink, RequestStream, RpcContext, Service, WriteFlags};
+use mc_common::{
+    logger::{log, Logger},
+    HashMap, HashSet,
+};
+use protobuf::{
+    descriptor::{DescriptorProto, EnumDescriptorProto, FileDescriptorProto},
+    Message, RepeatedField,
+};
+use std::sync::Arc;
+
+/// Name of the reflection service, which is always listed.
+pub const REFLECTION_SERVICE_NAME: &str = "grpc.reflection.v1alpha.ServerReflection";
+
+/// gRPC status code for requests naming unknown files or symbols.
+const NOT_FOUND: i32 = 5;
+
+/// gRPC status code for requests that do not ask for anything.
+const INVALID_ARGUMENT: i32 = 3;
+
+lazy_static::lazy_static! {
+    /// `google/protobuf/empty.proto`, which our APIs import but which is not
+    /// compiled into any of our crates.
+    static ref EMPTY_FILE_DESCRIPTOR: FileDescriptorProto = {
+        let mut empty = DescriptorProto::new();
+        empty.set_name("Empty".to_owned());
+
+        let mut file = FileDescriptorProto::new();
+        file.set_name("google/protobuf/empty.proto".to_owned());
+        file.set_package("google.protobuf".to_owned());
+        file.set_syntax("proto3".to_owned());
+        file.mut_message_type().push(empty);
+        file
+    };
+}
+
+/// The descriptor of `google/protobuf/empty.proto`.
+pub fn empty_file_descriptor() -> &'static FileDescriptorProto {
+    &EMPTY_FILE_DESCRIPTOR
+}
+
+/// Serves the descriptors of the services registered with a server.
+#[derive(Clone)]
+pub struct ReflectionService {
+    index: Arc<DescriptorIndex>,
+    logger: Logger,
+}
+
+impl ReflectionService {
+    /// Create a new ReflectionService.
+    ///
+    /// # Arguments
+    /// * `services` - Full names of the services registered with the server,
+    ///   e.g. `consensus_common.BlockchainAPI`.
+    /// * `file_descriptors` - Descriptors of the proto files defining those
+    ///   services and their dependencies, usually the `file_descriptors()` of
+    ///   the API crates.
+    /// * `logger` - Logger.
+    pub fn new(
+        services: &[&str],
+        file_descriptors: impl IntoIterator<Item = &'static FileDescriptorProto>,
+        logger: Logger,
+    ) -> Self {
+        let index = DescriptorIndex::new(services, file_descriptors);
+        for service in &index.services {
+            if !index.symbols.contains_key(service) {
+                log::warn!(logger, "No descriptor for reflected service {}", service);
+            }
+        }
+        Self {
+            index: Arc::new(index),
+            logger,
+        }
+    }
+
+    pub fn into_service(self) -> Service {
+        create_server_reflection(self)
+    }
+}
+
+impl ServerReflection for ReflectionService {
+    fn server_reflection_info(
+        &mut self,
+        ctx: RpcContext,
+        requests: RequestStream<ServerReflectionRequest>,
+        mut sink: DuplexSink<ServerReflectionResponse>,
+    ) {
+        let index = self.index.clone();
+        let logger = self.logger.clone();
+
+        let mut responses =
+            requests.map_ok(move |request| (index.respond(request), WriteFlags::default()));
+
+        ctx.spawn(async move {
+            let result = match sink.send_all(&mut responses).await {
+                Ok(()) => sink.close().await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                log::debug!(logger, "Reflection stream ended with error: {}", err);
+            }
+        });
+    }
+}
+
+/// Descriptors indexed by file name and by the symbols they define.
+struct DescriptorIndex {
+    /// Full names of the reflected services.
+    services: Vec<String>,
+
+    /// Files, by name.
+    files: HashMap<String, &'static FileDescriptorProto>,
+
+    /// Name of the file defining each fully-qualified symbol.
+    symbols: HashMap<String, String>,
+}
+
+impl DescriptorIndex {
+    fn new(
+        services: &[&str],
+        file_descriptors: impl IntoIterator<Item = &'static FileDescriptorProto>,
+    ) -> Self {
+        let mut index = Self {
+            services: services
+                .iter()
+                .map(|service| (*service).to_owned())
+                .collect(),
+            files: HashMap::default(),
+            symbols: HashMap::default(),
+        };
+        if !index.services.iter().any(|s| s == REFLECTION_SERVICE_NAME) {
+            index.services.push(REFLECTION_SERVICE_NAME.to_owned());
+        }
+        index.services.sort();
+
+        for file in file_descriptors
+            .into_iter()
+            .chain(crate::file_descriptors())
+            .chain(Some(empty_file_descriptor()))
+        {
+            index.add_file(file);
+        }
+        index
+    }
+
+    fn add_file(&mut self, file: &'static FileDescriptorProto) {
+        if self.files.contains_key(file.get_name()) {
+            return;
+        }
+        self.files.insert(file.get_name().to_owned(), file);
+
+        let package = file.get_package();
+        for message in file.get_message_type() {
+            self.add_message(file, package, message);
+        }
+        for enumeration in file.get_enum_type() {
+            self.add_enum(file, package, enumeration);
+        }
+        for service in file.get_service() {
+            let service_name = qualify(package, service.get_name());
+            for method in service.get_method() {
+                self.add_symbol(file, qualify(&service_name, method.get_name()));
+            }
+            self.add_symbol(file, service_name);
+        }
+    }
+
+    fn add_message(&mut self, file: &FileDescriptorProto, scope: &str, message: &DescriptorProto) {
+        let name = qualify(scope, message.get_name());
+        for nested in message.get_nested_type() {
+            self.add_message(file, &name, nested);
+        }
+        for enumeration in message.get_enum_type() {
+            self.add_enum(file, &name, enumeration);
+        }
+        self.add_symbol(file, name);
+    }
+
+    fn add_enum(
+        &mut self,
+        file: &FileDescriptorProto,
+        scope: &str,
+        enumeration: &EnumDescriptorProto,
+    ) {
+        self.add_symbol(file, qualify(scope, enumeration.get_name()));
+    }
+
+    fn add_symbol(&mut self, file: &FileDescriptorProto, symbol: String) {
+        self.symbols.insert(symbol, file.get_name().to_owned());
+    }
+
+    /// Answer a reflection request.
+    fn respond(&self, request: ServerReflectionRequest) -> ServerReflectionResponse {
+        let mut response = ServerReflectionResponse::new();
+        response.set_valid_host(request.get_host().to_owned());
+
+        if request.has_list_services() {
+            let services = self
+                .services
+                .iter()
+                .map(|name| {
+                    let mut service = ServiceResponse::new();
+                    service.set_name(name.clone());
+                    service
+                })
+                .collect();
+            let mut list = ListServiceResponse::new();
+            list.set_service(RepeatedField::from_vec(services));
+            response.set_list_services_response(list);
+        } else if request.has_file_by_filename() {
+            match self.file_with_dependencies(request.get_file_by_filename()) {
+                Some(files) => response.set_file_descriptor_response(files),
+                None => response.set_error_response(error(
+                    NOT_FOUND,
+                    format!("Unknown file {}", request.get_file_by_filename()),
+                )),
+            }
+        } else if request.has_file_containing_symbol() {
+            let symbol = request.get_file_containing_symbol();
+            match self
+                .symbols
+                .get(symbol.trim_start_matches('.'))
+                .and_then(|file_name| self.file_with_dependencies(file_name))
+            {
+                Some(files) => response.set_file_descriptor_response(files),
+                None => response
+                    .set_error_response(error(NOT_FOUND, format!("Unknown symbol {}", symbol))),
+            }
+        } else if request.has_all_extension_numbers_of_type() {
+            // Our APIs are proto3, which has no extensions.
+            let type_name = request.get_all_extension_numbers_of_type();
+            if self.symbols.contains_key(type_name) {
+                let mut numbers = ExtensionNumberResponse::new();
+                numbers.set_base_type_name(type_name.to_owned());
+                response.set_all_extension_numbers_response(numbers);
+            } else {
+                response
+                    .set_error_response(error(NOT_FOUND, format!("Unknown type {}", type_name)));
+            }
+        } else if request.has_file_containing_extension() {
+            response
+                .set_error_response(error(NOT_FOUND, "Extensions are not supported".to_owned()));
+        } else {
+            response.set_error_response(error(INVALID_ARGUMENT, "Empty request".to_owned()));
+        }
+
+        response.set_original_request(request);
+        response
+    }
+
+    /// The serialized descriptors of a file and of the files it depends on,
+    /// directly or not, starting with the file itself.
+    fn file_with_dependencies(&self, file_name: &str) -> Option<FileDescriptorResponse> {
+        let mut seen = HashSet::default();
+        let mut pending = vec![file_name];
+        let mut serialized = Vec::new();
+
+        while let Some(name) = pending.pop() {
+            if !seen.insert(name) {
+                continue;
+            }
+            let file = match self.files.get(name) {
+                Some(file) => file,
+                // The requested file must be known, but a missing dependency is only
+                // reported by the client once it needs it.
+                None if name == file_name => return None,
+                None => continue,
+            };
+            serialized.push(
+                file.write_to_bytes()
+                    .expect("Serializing a file descriptor failed"),
+            );
+            pending.extend(file.get_dependency().iter().map(String::as_str).rev());
+        }
+
+        let mut response = FileDescriptorResponse::new();
+        response.set_file_descriptor_proto(RepeatedField::from_vec(serialized));
+        Some(response)
+    }
+}
+
+/// The fully-qualified name of `name` in `scope`.
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+fn error(code: i32, message: String) -> ErrorResponse {
+    let mut error = ErrorResponse::new();
+    error.set_error_code(code);
+    error.set_error_message(message);
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protobuf::parse_from_bytes;
+
+    fn index() -> DescriptorIndex {
+        DescriptorIndex::new(&["admin.AdminAPI", "grpc.health.v1.Health"], Vec::new())
+    }
+
+    fn file_names(response: &ServerReflectionResponse) -> Vec<String> {
+        response
+            .get_file_descriptor_response()
+            .get_file_descriptor_proto()
+            .iter()
+            .map(|bytes| {
+                parse_from_bytes::<FileDescriptorProto>(bytes)
+                    .unwrap()
+                    .get_name()
+                    .to_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn list_services() {
+        let mut request = ServerReflectionRequest::new();
+        request.set_list_services("*".to_owned());
+        let response = index().respond(request);
+
+        let names: Vec<&str> = response
+            .get_list_services_response()
+            .get_service()
+            .iter()
+            .map(ServiceResponse::get_name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "admin.AdminAPI",
+                "grpc.health.v1.Health",
+                REFLECTION_SERVICE_NAME
+            ]
+        );
+    }
+
+    #[test]
+    // Files should be returned with their dependencies.
+    fn file_containing_symbol() {
+        let index = index();
+        for symbol in &[
+            "admin.AdminAPI",
+            ".admin.AdminAPI",
+            "admin.AdminAPI.GetInfo",
+            "admin.GetInfoResponse",
+        ] {
+            let mut request = ServerReflectionRequest::new();
+            request.set_file_containing_symbol((*symbol).to_owned());
+            let response = index.respond(request);
+            assert_eq!(
+                file_names(&response),
+                vec![
+                    "admin.proto",
+                    "google/protobuf/empty.proto",
+                    "build_info.proto"
+                ],
+                "{}",
+                symbol
+            );
+        }
+    }
+
+    #[test]
+    fn file_by_filename() {
+        let mut request = ServerReflectionRequest::new();
+        request.set_file_by_filename("health_api.proto".to_owned());
+        let response = index().respond(request.clone());
+        assert_eq!(file_names(&response), vec!["health_api.proto"]);
+        assert_eq!(response.get_original_request(), &request);
+    }
+
+    #[test]
+    fn unknown_symbols_are_not_found() {
+        let index = index();
+
+        let mut request = ServerReflectionRequest::new();
+        request.set_file_containing_symbol("admin.Unknown".to_owned());
+        assert_eq!(
+            index.respond(request).get_error_response().get_error_code(),
+            NOT_FOUND
+        );
+
+        let mut request = ServerReflectionRequest::new();
+        request.set_file_by_filename("unknown.proto".to_owned());
+        assert_eq!(
+            index.respond(request).get_error_response().get_error_code(),
+            NOT_FOUND
+        );
+
+        assert_eq!(
+            index
+                .respond(ServerReflectionRequest::new())
+                .get_error_response()
+                .get_error_code(),
+            INVALID_ARGUMENT
+        );
+    }
+}
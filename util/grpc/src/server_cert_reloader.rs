@@ -1,7 +1,8 @@
 // Copyright (c) 2018-2021 The MobileCoin Foundation
 
 //! A `grpcio::ServerCredentialsFetcher` implementation that reloads a GRPC's
-//! server TLS certificate/key when a SIGHUP is received.
+//! server TLS certificate/key, and optionally the CA bundle used to verify
+//! client certificates, when a SIGHUP is received.
 
 use displaydoc::Display;
 use grpcio::{CertificateRequestType, ServerCredentialsBuilder, ServerCredentialsFetcher};
@@ -19,7 +20,7 @@ use std::{
 /// The `grpcio::ServerCredentialsFetcher` demands a root certificate for
 /// verifying client identities, even when we explicitly specify
 /// DontRequestClientCertificate. As such, we need to provide it with some
-/// certificate. We don't use client certificate authentication, so we use a
+/// certificate. When client certificate authentication is not used, we use a
 /// hardcoded certificate that was generated by this command:
 /// openssl req \
 ///     -x509 \
@@ -82,6 +83,11 @@ pub struct ServerCertReloader {
     /// Private key file to watch.
     key_file: PathBuf,
 
+    /// CA bundle file (or directory) to watch, used to verify client
+    /// certificates. When set, clients must present a certificate signed by
+    /// one of its CAs.
+    client_ca_file: Option<PathBuf>,
+
     /// Signal that we need to re-load the certificate/key files.
     load_needed: Arc<AtomicBool>,
 
@@ -95,6 +101,18 @@ impl ServerCertReloader {
         cert_file: &impl AsRef<Path>,
         key_file: &impl AsRef<Path>,
         logger: Logger,
+    ) -> Result<Self, ServerCertReloaderError> {
+        Self::with_client_ca(cert_file, key_file, None::<&PathBuf>, logger)
+    }
+
+    /// Create a new ServerCertReloader that watches `cert_file`/`key_file`,
+    /// and requires clients to present a certificate signed by one of the CAs
+    /// in `client_ca_file`, if it is given.
+    pub fn with_client_ca(
+        cert_file: &impl AsRef<Path>,
+        key_file: &impl AsRef<Path>,
+        client_ca_file: Option<&impl AsRef<Path>>,
+        logger: Logger,
     ) -> Result<Self, ServerCertReloaderError> {
         let load_needed = Arc::new(AtomicBool::new(true));
 
@@ -103,10 +121,21 @@ impl ServerCertReloader {
         Ok(Self {
             cert_file: cert_file.as_ref().to_path_buf(),
             key_file: key_file.as_ref().to_path_buf(),
+            client_ca_file: client_ca_file.map(|path| path.as_ref().to_path_buf()),
             load_needed,
             logger,
         })
     }
+
+    /// Whether clients are asked for a certificate, and whether it is
+    /// verified.
+    pub fn certificate_request_type(&self) -> CertificateRequestType {
+        if self.client_ca_file.is_some() {
+            CertificateRequestType::RequestAndRequireClientCertificateAndVerify
+        } else {
+            CertificateRequestType::DontRequestClientCertificate
+        }
+    }
 }
 
 impl ServerCredentialsFetcher for ServerCertReloader {
@@ -120,15 +149,19 @@ impl ServerCredentialsFetcher for ServerCertReloader {
         let crt = fs::read_to_string(&self.cert_file)?;
         let key = fs::read_to_string(&self.key_file)?;
 
+        // This sets the client root certificate to verify client's identity.
+        // When we are not using this feature, grpcio still requires something to be set
+        // there when using the ServerCredentialsFetcher mechanism. As a workaround we
+        // are using a hardcoded certificate here.
+        let client_root_cert = match &self.client_ca_file {
+            Some(client_ca_file) => {
+                mc_util_host_cert::read_ca_bundle(Some(client_ca_file.clone()))?
+            }
+            None => HARDCODED_CLIENT_ROOT_CERT.as_bytes().to_vec(),
+        };
+
         let new_cred = ServerCredentialsBuilder::new()
-            // This sets the client root certificate to verify client's identity.
-            // We are not using this feature, however grpcio still requires something to be set
-            // there when using the ServerCredentialsFetcher mechanism. As a workaround we are
-            // using the server's certificate chain here.
-            .root_cert(
-                HARDCODED_CLIENT_ROOT_CERT.as_bytes().to_vec(),
-                CertificateRequestType::DontRequestClientCertificate,
-            )
+            .root_cert(client_root_cert, self.certificate_request_type())
             .add_cert(crt.into(), key.into());
 
         self.load_needed.store(false, Ordering::SeqCst);
@@ -430,4 +463,59 @@ mod tests {
         let reply = client1.ping(&req).expect("rpc");
         assert_eq!(reply.get_data(), vec![1, 2, 3]);
     }
+
+    #[test_with_logger]
+    fn test_client_certificate_required(logger: Logger) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cert_file = temp_dir.path().join("server.crt");
+        let key_file = temp_dir.path().join("server.key");
+        let client_ca_file = temp_dir.path().join("client-ca.crt");
+
+        // server1 is the server's identity, server2 is the client's, and is
+        // self-signed, so it is its own CA.
+        let (server1_cert, server1_key) = ok_self_signed_1();
+        let (client_cert, client_key) = ok_self_signed_2();
+        std::fs::write(&cert_file, &server1_cert).unwrap();
+        std::fs::write(&key_file, &server1_key).unwrap();
+        std::fs::write(&client_ca_file, &client_cert).unwrap();
+
+        let port: u16 = 6545;
+        let uri = ConsensusClientUri::from_str(&format!(
+            "mc://localhost:{}/?tls-chain={}&tls-key={}&tls-client-ca={}",
+            port,
+            cert_file.to_str().unwrap(),
+            key_file.to_str().unwrap(),
+            client_ca_file.to_str().unwrap(),
+        ))
+        .unwrap();
+
+        let env = Arc::new(EnvBuilder::new().build());
+        let service = HealthService::new(None, logger.clone()).into_service();
+        let mut server = ServerBuilder::new(env)
+            .register_service(service)
+            .bind_using_uri(&uri, logger)
+            .build()
+            .unwrap();
+        server.start();
+
+        let mut req = PingRequest::default();
+        req.set_data(vec![1, 2, 3]);
+
+        // A client without a certificate is refused.
+        let client1 = create_test_client(&server1_cert, "www.server1.com", port);
+        assert!(client1.ping(&req).is_err());
+
+        // A client presenting a certificate signed by the client CA is accepted.
+        let env = Arc::new(EnvBuilder::new().build());
+        let cred = ChannelCredentialsBuilder::new()
+            .root_cert(server1_cert.into())
+            .cert(client_cert.into(), client_key.into())
+            .build();
+        let ch = ChannelBuilder::new(env)
+            .override_ssl_target("www.server1.com")
+            .secure_connect(&format!("localhost:{}", port), cred);
+        let client2 = HealthClient::new(ch);
+        let reply = client2.ping(&req).expect("rpc");
+        assert_eq!(reply.get_data(), vec![1, 2, 3]);
+    }
 }
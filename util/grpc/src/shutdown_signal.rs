@@ -0,0 +1,108 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Tracks whether the process was asked to terminate, so that services can
+//! drain in-flight work before exiting rather than being cut off.
+
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    flag,
+};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Signals which request a graceful shutdown.
+const SHUTDOWN_SIGNALS: &[i32] = &[SIGTERM, SIGINT];
+
+/// How often `wait()` checks whether a shutdown was requested.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Set when a SIGTERM or SIGINT is received. A second signal terminates the
+/// process immediately, in case draining gets stuck.
+#[derive(Clone)]
+pub struct ShutdownSignal {
+    requested: Arc<AtomicBool>,
+}
+
+impl ShutdownSignal {
+    /// Install the signal handlers.
+    pub fn register() -> io::Result<Self> {
+        let requested = Arc::new(AtomicBool::new(false));
+        for signal in SHUTDOWN_SIGNALS {
+            // The first signal only sets the flag, since the conditional shutdown
+            // is registered (and checked) before the flag is set.
+            flag::register_conditional_shutdown(*signal, 1, requested.clone())?;
+            flag::register(*signal, requested.clone())?;
+        }
+        Ok(Self { requested })
+    }
+
+    /// Whether a shutdown was requested.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Block until a shutdown is requested.
+    pub fn wait(&self) {
+        while !self.is_requested() {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Block until a shutdown is requested, or `check` fails. This lets the
+    /// caller notice that the service it is waiting on has died.
+    pub fn wait_while_ok<E>(&self, mut check: impl FnMut() -> Result<(), E>) -> Result<(), E> {
+        while !self.is_requested() {
+            check()?;
+            thread::sleep(POLL_INTERVAL);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shutdown_requested_by_sigterm() {
+        let shutdown_signal = ShutdownSignal::register().unwrap();
+        assert!(!shutdown_signal.is_requested());
+
+        // Only one signal may be raised, a second one would terminate the test
+        // process.
+        unsafe {
+            libc::kill(libc::getpid(), libc::SIGTERM);
+        }
+
+        // wait() returns once the handler has run.
+        shutdown_signal.wait();
+        assert!(shutdown_signal.is_requested());
+        assert!(shutdown_signal.clone().is_requested());
+    }
+
+    #[test]
+    fn test_wait_while_ok_returns_check_error() {
+        let shutdown_signal = ShutdownSignal {
+            requested: Arc::new(AtomicBool::new(false)),
+        };
+
+        let mut checks = 0;
+        let result = shutdown_signal.wait_while_ok(|| {
+            checks += 1;
+            if checks < 3 {
+                Ok(())
+            } else {
+                Err("thread died")
+            }
+        });
+        assert_eq!(result, Err("thread died"));
+        assert_eq!(checks, 3);
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Propagation of OpenTelemetry trace contexts in gRPC metadata, so that spans
+//! on both sides of a call belong to the same trace.
+
+use grpcio::{Metadata, MetadataBuilder, RpcContext};
+use mc_util_telemetry::{
+    global, BoxedTracer, Context, Extractor, Injector, SpanKind, TraceContextExt, Tracer,
+};
+use std::str;
+
+/// Adds headers to outgoing request metadata.
+struct MetadataInjector<'a>(&'a mut MetadataBuilder);
+
+impl<'a> Injector for MetadataInjector<'a> {
+    fn set(&mut self, key: &str, value: String) {
+        // Trace context headers are always valid ASCII, so this only fails if the
+        // propagator misbehaves, in which case the call is simply not traced.
+        let _ = self.0.add_str(key, &value);
+    }
+}
+
+/// Reads headers from incoming request metadata.
+struct MetadataExtractor<'a>(&'a Metadata);
+
+impl<'a> Extractor for MetadataExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .and_then(|(_, value)| str::from_utf8(value).ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.iter().map(|(name, _)| name).collect()
+    }
+}
+
+/// Add the trace context of `context` to the metadata of an outgoing call.
+pub fn inject_trace_context(context: &Context, metadata_builder: &mut MetadataBuilder) {
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(context, &mut MetadataInjector(metadata_builder))
+    });
+}
+
+/// The trace context of the caller, taken from the metadata of an incoming
+/// call. This is an empty context if the caller did not send one.
+pub fn extract_trace_context(metadata: &Metadata) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&MetadataExtractor(metadata)))
+}
+
+/// Start a server span for an incoming call, as a child of the caller's span
+/// if they sent a trace context. The returned context should be attached for
+/// the duration of the call, e.g. `let _guard = rpc_span(..).attach();`.
+///
+/// # Arguments
+/// * `tracer` - The tracer of the service handling the call.
+/// * `ctx` - The call's context.
+/// * `name` - Name of the span, usually the name of the method called.
+pub fn rpc_span(tracer: &BoxedTracer, ctx: &RpcContext, name: &'static str) -> Context {
+    let parent = extract_trace_context(ctx.request_headers());
+    let span = tracer
+        .span_builder(name)
+        .with_kind(SpanKind::Server)
+        .with_parent_context(parent.clone())
+        .start(tracer);
+    parent.with_span(span)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_util_telemetry::TraceContextPropagator;
+
+    const TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+    #[test]
+    fn trace_context_round_trip() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let mut builder = MetadataBuilder::new();
+        builder.add_str("traceparent", TRACEPARENT).unwrap();
+        let context = extract_trace_context(&builder.build());
+        assert!(context.span().span_context().is_valid());
+        assert!(context.span().span_context().is_remote());
+
+        let mut builder = MetadataBuilder::new();
+        inject_trace_context(&context, &mut builder);
+        let metadata = builder.build();
+        assert_eq!(
+            MetadataExtractor(&metadata).get("traceparent"),
+            Some(TRACEPARENT)
+        );
+    }
+
+    #[test]
+    fn missing_trace_context_is_empty() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let context = extract_trace_context(&MetadataBuilder::new().build());
+        assert!(!context.span().span_context().is_valid());
+    }
+}
@@ -1,20 +1,30 @@
 // Copyright (c) 2018-2021 The MobileCoin Foundation
 
-//! A CLI tool for generating individual MobileCoin identities
+//! A CLI tool for generating individual MobileCoin identities, and for
+//! encrypting, decrypting and changing the passphrase of their keyfiles
 
 use mc_account_keys::{RootEntropy, RootIdentity};
-use mc_util_keyfile::config::Config;
+use mc_util_keyfile::{
+    config::{Command, Config},
+    passphrase::{get_new_passphrase, get_passphrase, NEW_PASSPHRASE_ENV_VAR},
+    read_keyfile, read_keyfile_with_passphrase, write_encrypted_keyfile, write_keyfile,
+};
 use structopt::StructOpt;
 
 fn main() {
-    let config = Config::from_args();
+    let mut config = Config::from_args();
+    if let Some(cmd) = config.cmd.take() {
+        run_command(cmd);
+        return;
+    }
+
     let path = config
         .output_dir
         .clone()
         .unwrap_or_else(|| std::env::current_dir().unwrap());
 
     let fog_url = config.acct.clone();
-    let name = config.name.clone();
+    let name = config.name.clone().expect("--name is required");
     let root_entropy = config.get_root_entropy();
 
     let id = RootIdentity {
@@ -28,3 +38,35 @@ fn main() {
 
     mc_util_keyfile::keygen::write_keyfiles(path, &name, &id).unwrap();
 }
+
+fn run_command(cmd: Command) {
+    match cmd {
+        Command::Encrypt { keyfile, out } => {
+            let root_id = read_keyfile(&keyfile)
+                .unwrap_or_else(|err| panic!("Could not read {:?}: {}", keyfile, err));
+            let passphrase = get_new_passphrase(NEW_PASSPHRASE_ENV_VAR, "New passphrase: ")
+                .expect("Could not get passphrase");
+            let out = out.unwrap_or(keyfile);
+            println!("Writing encrypted keyfile to {:?}", out);
+            write_encrypted_keyfile(&out, &root_id, passphrase.as_bytes()).unwrap();
+        }
+        Command::Decrypt { keyfile, out } => {
+            let passphrase = get_passphrase("Passphrase: ").expect("Could not get passphrase");
+            let root_id = read_keyfile_with_passphrase(&keyfile, Some(passphrase.as_bytes()))
+                .unwrap_or_else(|err| panic!("Could not read {:?}: {}", keyfile, err));
+            let out = out.unwrap_or(keyfile);
+            println!("Writing plaintext keyfile to {:?}", out);
+            write_keyfile(&out, &root_id).unwrap();
+        }
+        Command::ChangePassphrase { keyfile } => {
+            let passphrase =
+                get_passphrase("Current passphrase: ").expect("Could not get passphrase");
+            let root_id = read_keyfile_with_passphrase(&keyfile, Some(passphrase.as_bytes()))
+                .unwrap_or_else(|err| panic!("Could not read {:?}: {}", keyfile, err));
+            let new_passphrase = get_new_passphrase(NEW_PASSPHRASE_ENV_VAR, "New passphrase: ")
+                .expect("Could not get new passphrase");
+            println!("Re-encrypting {:?}", keyfile);
+            write_encrypted_keyfile(&keyfile, &root_id, new_passphrase.as_bytes()).unwrap();
+        }
+    }
+}
@@ -2,18 +2,39 @@
 
 //! A tool for inspecting binary keyfiles
 //! Reads .bin file on stdin, or a path to .bin file, emits description on
-//! stdout
+//! stdout. Encrypted keyfiles are decrypted with the passphrase from
+//! MC_KEYFILE_PASSPHRASE, or one prompted for on the terminal.
 
 use mc_account_keys::{AccountKey, RootIdentity};
+use mc_util_keyfile::passphrase::{get_passphrase, PASSPHRASE_ENV_VAR};
 
 fn main() {
     let root_id: RootIdentity = {
         let args: Vec<String> = std::env::args().collect();
         match args.get(1) {
-            None => mc_util_keyfile::read_keyfile_data(&mut std::io::stdin())
-                .unwrap_or_else(|_| panic!("Failed when reading from stdin")),
-            Some(arg) => mc_util_keyfile::read_keyfile(arg)
-                .unwrap_or_else(|_| panic!("Failed when reading from {}", arg)),
+            // There's no terminal to prompt on when the keyfile is on stdin
+            None => {
+                let passphrase = std::env::var(PASSPHRASE_ENV_VAR).ok();
+                mc_util_keyfile::read_keyfile_data_with_passphrase(
+                    &mut std::io::stdin(),
+                    passphrase.as_ref().map(String::as_bytes),
+                )
+                .unwrap_or_else(|_| panic!("Failed when reading from stdin"))
+            }
+            Some(arg) => {
+                let passphrase = if mc_util_keyfile::is_encrypted_keyfile(arg)
+                    .unwrap_or_else(|_| panic!("Failed when reading from {}", arg))
+                {
+                    Some(get_passphrase("Passphrase: ").expect("Could not get passphrase"))
+                } else {
+                    None
+                };
+                mc_util_keyfile::read_keyfile_with_passphrase(
+                    arg,
+                    passphrase.as_ref().map(|p| p.as_bytes()),
+                )
+                .unwrap_or_else(|_| panic!("Failed when reading from {}", arg))
+            }
         }
     };
     let acct_key = AccountKey::from(&root_id);
@@ -2,18 +2,19 @@
 
 use rand::{rngs::StdRng, SeedableRng};
 use std::path::PathBuf;
-use structopt::StructOpt;
+use structopt::{clap::AppSettings, StructOpt};
 
 /// Configuration for generating key files for a new user identity
 #[derive(Debug, StructOpt)]
+#[structopt(setting = AppSettings::SubcommandsNegateReqs)]
 pub struct Config {
     /// Optional FogURL for the accounts
     #[structopt(short, long)]
     pub acct: Option<String>,
 
     /// Desired name of keyfiles e.g. 'alice' -> alice.pub, alice.bin.
-    #[structopt(short, long)]
-    pub name: String,
+    #[structopt(short, long, required = true)]
+    pub name: Option<String>,
 
     // Root entropy to use, in hex format (e.g.
     // 1234567812345678123456781234567812345678123456781234567812345678).
@@ -27,6 +28,48 @@ pub struct Config {
     /// Output directory, defaults to current directory.
     #[structopt(long)]
     pub output_dir: Option<PathBuf>,
+
+    /// Operate on an existing keyfile instead of generating a new identity.
+    #[structopt(subcommand)]
+    pub cmd: Option<Command>,
+}
+
+/// Operations on existing keyfiles.
+///
+/// Passphrases are read from MC_KEYFILE_PASSPHRASE and
+/// MC_KEYFILE_NEW_PASSPHRASE if set, and prompted for otherwise.
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Encrypt a plaintext keyfile with a passphrase.
+    Encrypt {
+        /// The keyfile to encrypt.
+        #[structopt(parse(from_os_str))]
+        keyfile: PathBuf,
+
+        /// Where to write the encrypted keyfile, defaults to overwriting the
+        /// input.
+        #[structopt(long, parse(from_os_str))]
+        out: Option<PathBuf>,
+    },
+
+    /// Decrypt an encrypted keyfile back to plaintext JSON.
+    Decrypt {
+        /// The keyfile to decrypt.
+        #[structopt(parse(from_os_str))]
+        keyfile: PathBuf,
+
+        /// Where to write the decrypted keyfile, defaults to overwriting the
+        /// input.
+        #[structopt(long, parse(from_os_str))]
+        out: Option<PathBuf>,
+    },
+
+    /// Change the passphrase of an encrypted keyfile.
+    ChangePassphrase {
+        /// The keyfile to re-encrypt.
+        #[structopt(parse(from_os_str))]
+        keyfile: PathBuf,
+    },
 }
 
 impl Config {
@@ -0,0 +1,309 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Passphrase-encrypted keyfile container.
+//!
+//! An encrypted keyfile wraps the JSON keyfile contents in AES-256-GCM, using
+//! a key derived from the passphrase with Argon2id. The layout is:
+//!
+//! ```text
+//! magic      8 bytes   b"MCKEYENC"
+//! version    1 byte    FORMAT_VERSION
+//! kdf        1 byte    KDF_ARGON2ID
+//! mem_cost   4 bytes   little-endian, KiB
+//! time_cost  4 bytes   little-endian, iterations
+//! lanes      4 bytes   little-endian
+//! salt       16 bytes
+//! nonce      12 bytes
+//! ciphertext remainder, including the 16-byte GCM tag
+//! ```
+//!
+//! The entire header is authenticated as associated data, so tampering with
+//! the KDF parameters is detected the same way as tampering with the
+//! ciphertext. That check only happens after the key has been derived, so
+//! the parameters are also bounded by [`KdfParams::check`] first, to stop a
+//! tampered header from making decryption use unbounded memory or time.
+
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+    Aes256Gcm,
+};
+use displaydoc::Display;
+use mc_crypto_rand::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+/// The magic bytes at the start of every encrypted keyfile
+pub const MAGIC: &[u8; 8] = b"MCKEYENC";
+
+/// The current version of the encrypted keyfile format
+pub const FORMAT_VERSION: u8 = 1;
+
+/// The KDF identifier for Argon2id
+pub const KDF_ARGON2ID: u8 = 1;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: u32 = 32;
+const HEADER_LEN: usize = MAGIC.len() + 2 + 12 + SALT_LEN + NONCE_LEN;
+
+/// Argon2id cost parameters used to derive the keyfile encryption key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KdfParams {
+    /// Memory cost, in KiB
+    pub mem_cost: u32,
+    /// Number of passes over memory
+    pub time_cost: u32,
+    /// Degree of parallelism
+    pub lanes: u32,
+}
+
+/// The largest accepted memory cost, 1 GiB
+pub const MAX_MEM_COST: u32 = 1024 * 1024;
+
+/// The largest accepted number of passes over memory
+pub const MAX_TIME_COST: u32 = 16;
+
+/// The largest accepted degree of parallelism
+pub const MAX_LANES: u32 = 16;
+
+impl KdfParams {
+    /// Check that these parameters are within the bounds we are willing to
+    /// spend on deriving a key.
+    pub fn check(&self) -> Result<(), KeyfileCryptoError> {
+        if self.mem_cost > MAX_MEM_COST || self.time_cost > MAX_TIME_COST || self.lanes > MAX_LANES
+        {
+            return Err(KeyfileCryptoError::KdfParamsOutOfRange(*self));
+        }
+        Ok(())
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            mem_cost: 64 * 1024,
+            time_cost: 3,
+            lanes: 1,
+        }
+    }
+}
+
+/// An error encrypting or decrypting a keyfile.
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum KeyfileCryptoError {
+    /// The data is too short to be an encrypted keyfile
+    TooShort,
+    /// The data does not start with the encrypted keyfile magic bytes
+    BadMagic,
+    /// Unsupported encrypted keyfile version: {0}
+    UnsupportedVersion(u8),
+    /// Unsupported key derivation function: {0}
+    UnsupportedKdf(u8),
+    /// Key derivation parameters are out of range: {0:?}
+    KdfParamsOutOfRange(KdfParams),
+    /// Key derivation failed: {0}
+    Kdf(String),
+    /// Encryption failed
+    Encrypt,
+    /// Decryption failed, the passphrase is wrong or the keyfile is corrupt
+    Decrypt,
+}
+
+impl std::error::Error for KeyfileCryptoError {}
+
+impl From<KeyfileCryptoError> for std::io::Error {
+    fn from(src: KeyfileCryptoError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, src)
+    }
+}
+
+/// Check whether the given bytes look like an encrypted keyfile.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypt the given plaintext keyfile contents with a passphrase.
+pub fn encrypt<R: CryptoRng + RngCore>(
+    plaintext: &[u8],
+    passphrase: &[u8],
+    params: &KdfParams,
+    rng: &mut R,
+) -> Result<Vec<u8>, KeyfileCryptoError> {
+    params.check()?;
+    let mut salt = [0u8; SALT_LEN];
+    rng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.push(FORMAT_VERSION);
+    header.push(KDF_ARGON2ID);
+    header.extend_from_slice(&params.mem_cost.to_le_bytes());
+    header.extend_from_slice(&params.time_cost.to_le_bytes());
+    header.extend_from_slice(&params.lanes.to_le_bytes());
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+    debug_assert_eq!(header.len(), HEADER_LEN);
+
+    let key = derive_key(passphrase, &salt, params)?;
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(&key));
+    let ciphertext = cipher
+        .encrypt(
+            GenericArray::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: &header,
+            },
+        )
+        .map_err(|_| KeyfileCryptoError::Encrypt)?;
+
+    let mut result = header;
+    result.extend_from_slice(&ciphertext);
+    Ok(result)
+}
+
+/// Decrypt an encrypted keyfile with a passphrase, returning the plaintext
+/// keyfile contents.
+pub fn decrypt(data: &[u8], passphrase: &[u8]) -> Result<Zeroizing<Vec<u8>>, KeyfileCryptoError> {
+    if data.len() < HEADER_LEN {
+        return Err(KeyfileCryptoError::TooShort);
+    }
+    let (header, ciphertext) = data.split_at(HEADER_LEN);
+    let (magic, rest) = header.split_at(MAGIC.len());
+    if magic != MAGIC {
+        return Err(KeyfileCryptoError::BadMagic);
+    }
+    if rest[0] != FORMAT_VERSION {
+        return Err(KeyfileCryptoError::UnsupportedVersion(rest[0]));
+    }
+    if rest[1] != KDF_ARGON2ID {
+        return Err(KeyfileCryptoError::UnsupportedKdf(rest[1]));
+    }
+    let read_u32 = |offset: usize| {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&rest[offset..offset + 4]);
+        u32::from_le_bytes(bytes)
+    };
+    let params = KdfParams {
+        mem_cost: read_u32(2),
+        time_cost: read_u32(6),
+        lanes: read_u32(10),
+    };
+    params.check()?;
+    let salt = &rest[14..14 + SALT_LEN];
+    let nonce = &rest[14 + SALT_LEN..];
+
+    let key = derive_key(passphrase, salt, &params)?;
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(&key));
+    cipher
+        .decrypt(
+            GenericArray::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| KeyfileCryptoError::Decrypt)
+}
+
+fn derive_key(
+    passphrase: &[u8],
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<Zeroizing<Vec<u8>>, KeyfileCryptoError> {
+    let config = argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        version: argon2::Version::Version13,
+        mem_cost: params.mem_cost,
+        time_cost: params.time_cost,
+        lanes: params.lanes,
+        thread_mode: argon2::ThreadMode::Sequential,
+        secret: &[],
+        ad: &[],
+        hash_length: KEY_LEN,
+    };
+    argon2::hash_raw(passphrase, salt, &config)
+        .map(Zeroizing::new)
+        .map_err(|err| KeyfileCryptoError::Kdf(err.to_string()))
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // Cheap parameters so the tests don't spend their time in the KDF
+    const TEST_PARAMS: KdfParams = KdfParams {
+        mem_cost: 64,
+        time_cost: 1,
+        lanes: 1,
+    };
+
+    #[test]
+    fn test_roundtrip() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let data = encrypt(b"secret", b"hunter2", &TEST_PARAMS, &mut rng).unwrap();
+        assert!(is_encrypted(&data));
+        assert_eq!(&decrypt(&data, b"hunter2").unwrap()[..], b"secret");
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let data = encrypt(b"secret", b"hunter2", &TEST_PARAMS, &mut rng).unwrap();
+        assert_eq!(decrypt(&data, b"hunter3"), Err(KeyfileCryptoError::Decrypt));
+    }
+
+    #[test]
+    fn test_tampered_header() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let mut data = encrypt(b"secret", b"hunter2", &TEST_PARAMS, &mut rng).unwrap();
+        // Bump the time cost, which the attacker could use to force a
+        // different key without touching the ciphertext.
+        data[MAGIC.len() + 6] += 1;
+        assert_eq!(decrypt(&data, b"hunter2"), Err(KeyfileCryptoError::Decrypt));
+
+        let mut data = encrypt(b"secret", b"hunter2", &TEST_PARAMS, &mut rng).unwrap();
+        data[MAGIC.len()] = FORMAT_VERSION + 1;
+        assert_eq!(
+            decrypt(&data, b"hunter2"),
+            Err(KeyfileCryptoError::UnsupportedVersion(FORMAT_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn test_kdf_params_out_of_range() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let params = KdfParams {
+            mem_cost: MAX_MEM_COST + 1,
+            ..TEST_PARAMS
+        };
+        assert_eq!(
+            encrypt(b"secret", b"hunter2", &params, &mut rng),
+            Err(KeyfileCryptoError::KdfParamsOutOfRange(params))
+        );
+
+        // A header claiming a huge memory cost is rejected before deriving the
+        // key, rather than being caught by the authentication tag afterwards.
+        let mut data = encrypt(b"secret", b"hunter2", &TEST_PARAMS, &mut rng).unwrap();
+        data[MAGIC.len() + 2..MAGIC.len() + 6].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            decrypt(&data, b"hunter2"),
+            Err(KeyfileCryptoError::KdfParamsOutOfRange(KdfParams {
+                mem_cost: u32::MAX,
+                ..TEST_PARAMS
+            }))
+        );
+
+        let mut data = encrypt(b"secret", b"hunter2", &TEST_PARAMS, &mut rng).unwrap();
+        data[MAGIC.len() + 10..MAGIC.len() + 14].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            decrypt(&data, b"hunter2"),
+            Err(KeyfileCryptoError::KdfParamsOutOfRange(KdfParams {
+                lanes: u32::MAX,
+                ..TEST_PARAMS
+            }))
+        );
+    }
+}
@@ -4,11 +4,18 @@
 //! corresponding to `mc_account_keys::RootIdentity`, and
 //! `mc_account_keys::PublicAddress` respectively.
 
-use crate::{read_keyfile, read_pubfile, write_keyfile, write_pubfile};
+use crate::{
+    is_encrypted_keyfile, read_keyfile_with_passphrase, read_pubfile, write_keyfile, write_pubfile,
+};
 use mc_account_keys::{AccountKey, PublicAddress, RootIdentity};
 use rand::SeedableRng;
 use rand_hc::Hc128Rng as FixedRng;
-use std::{cmp::Ordering, ffi::OsStr, fs, path::Path};
+use std::{
+    cmp::Ordering,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
 
 pub const DEFAULT_SEED: [u8; 32] = [1; 32];
 
@@ -85,6 +92,34 @@ pub fn read_default_pubfiles<P: AsRef<Path>>(
 pub fn read_default_root_entropies<P: AsRef<Path>>(
     path: P,
 ) -> Result<Vec<RootIdentity>, std::io::Error> {
+    read_default_root_entropies_with_passphrase(path, None)
+}
+
+// Read default root entropies, decrypting any encrypted keyfiles with the
+// given passphrase
+pub fn read_default_root_entropies_with_passphrase<P: AsRef<Path>>(
+    path: P,
+    passphrase: Option<&[u8]>,
+) -> Result<Vec<RootIdentity>, std::io::Error> {
+    let result: Vec<RootIdentity> = default_keyfile_paths(path)?
+        .iter()
+        .map(|f| read_keyfile_with_passphrase(f, passphrase).expect("Could not read keyfile"))
+        .collect();
+    Ok(result)
+}
+
+// Check whether any of the default keyfiles in a directory are encrypted
+pub fn default_keyfiles_encrypted<P: AsRef<Path>>(path: P) -> Result<bool, std::io::Error> {
+    for f in default_keyfile_paths(path)? {
+        if is_encrypted_keyfile(f)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Helper: List the keyfiles in a directory, in the order they were written
+fn default_keyfile_paths<P: AsRef<Path>>(path: P) -> Result<Vec<PathBuf>, std::io::Error> {
     let mut entries = Vec::new();
     for entry in fs::read_dir(path)? {
         let filename = entry?.path();
@@ -93,11 +128,7 @@ pub fn read_default_root_entropies<P: AsRef<Path>>(
         }
     }
     entries.sort_by(|a, b| compare_keyfile_names(a, b));
-    let result: Vec<RootIdentity> = entries
-        .iter()
-        .map(|f| read_keyfile(f).expect("Could not read keyfile"))
-        .collect();
-    Ok(result)
+    Ok(entries)
 }
 
 // This comparator is used when sorting the files so that the i'th keyfile
@@ -115,7 +146,7 @@ fn compare_keyfile_names(a: &Path, b: &Path) -> Ordering {
 #[cfg(test)]
 mod testing {
     use super::*;
-    use std::{collections::HashSet, iter::FromIterator, path::PathBuf};
+    use std::{collections::HashSet, iter::FromIterator};
     use tempdir::TempDir;
 
     #[test]
@@ -204,6 +235,31 @@ mod testing {
         }
     }
 
+    #[test]
+    fn test_default_generation_encrypted() {
+        let dir1 = TempDir::new("test").unwrap();
+        let dir2 = TempDir::new("test").unwrap();
+
+        write_default_keyfiles(&dir1, 3, None, None, None, DEFAULT_SEED).unwrap();
+        write_default_keyfiles(&dir2, 3, None, None, None, DEFAULT_SEED).unwrap();
+        assert!(!default_keyfiles_encrypted(&dir2).unwrap());
+
+        // Encrypt one of the keyfiles in place
+        let params = crate::encrypted::KdfParams {
+            mem_cost: 64,
+            time_cost: 1,
+            lanes: 1,
+        };
+        let f1 = dir2.path().join(keyfile_name(1)).with_extension("json");
+        let root_id = crate::read_keyfile(&f1).unwrap();
+        crate::write_encrypted_keyfile_with_params(&f1, &root_id, b"hunter2", &params).unwrap();
+        assert!(default_keyfiles_encrypted(&dir2).unwrap());
+
+        let bin1 = read_default_root_entropies(&dir1).unwrap();
+        let bin2 = read_default_root_entropies_with_passphrase(&dir2, Some(b"hunter2")).unwrap();
+        assert_eq!(&bin1[..], &bin2[..]);
+    }
+
     #[test]
     fn test_hard_coded_root_entropy() {
         let dir1 = TempDir::new("test").unwrap();
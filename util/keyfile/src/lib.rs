@@ -3,11 +3,15 @@
 mod json_format;
 
 pub mod config;
+pub mod encrypted;
 pub mod keygen;
+pub mod passphrase;
 
+use encrypted::KdfParams;
 use json_format::RootIdentityJson;
 use mc_account_keys::{PublicAddress, RootIdentity};
 use std::{fs::File, io::prelude::*, path::Path};
+use zeroize::Zeroizing;
 
 /// Write user root identity to disk
 pub fn write_keyfile<P: AsRef<Path>>(
@@ -15,8 +19,8 @@ pub fn write_keyfile<P: AsRef<Path>>(
     root_id: &RootIdentity,
 ) -> Result<(), std::io::Error> {
     let json = RootIdentityJson::from(root_id);
-    File::create(path)?.write_all(&serde_json::to_vec(&json).map_err(to_io_error)?)?;
-    Ok(())
+    let data = Zeroizing::new(serde_json::to_vec(&json).map_err(to_io_error)?);
+    write_atomically(path.as_ref(), &data)
 }
 
 /// Read user root identity from disk
@@ -26,15 +30,82 @@ pub fn read_keyfile<P: AsRef<Path>>(path: P) -> Result<RootIdentity, std::io::Er
 
 /// Read user root identity from any implementor of `Read`
 pub fn read_keyfile_data<R: std::io::Read>(buffer: &mut R) -> Result<RootIdentity, std::io::Error> {
+    read_keyfile_data_with_passphrase(buffer, None)
+}
+
+/// Write user root identity to disk, encrypted with a passphrase
+pub fn write_encrypted_keyfile<P: AsRef<Path>>(
+    path: P,
+    root_id: &RootIdentity,
+    passphrase: &[u8],
+) -> Result<(), std::io::Error> {
+    write_encrypted_keyfile_with_params(path, root_id, passphrase, &KdfParams::default())
+}
+
+/// Write user root identity to disk, encrypted with a passphrase using the
+/// given key derivation parameters
+pub fn write_encrypted_keyfile_with_params<P: AsRef<Path>>(
+    path: P,
+    root_id: &RootIdentity,
+    passphrase: &[u8],
+    params: &KdfParams,
+) -> Result<(), std::io::Error> {
+    let json =
+        Zeroizing::new(serde_json::to_vec(&RootIdentityJson::from(root_id)).map_err(to_io_error)?);
+    let data = encrypted::encrypt(
+        &json,
+        passphrase,
+        params,
+        &mut mc_crypto_rand::McRng::default(),
+    )?;
+    write_atomically(path.as_ref(), &data)
+}
+
+/// Read user root identity from disk, decrypting it with the passphrase if
+/// the keyfile is encrypted
+pub fn read_keyfile_with_passphrase<P: AsRef<Path>>(
+    path: P,
+    passphrase: Option<&[u8]>,
+) -> Result<RootIdentity, std::io::Error> {
+    read_keyfile_data_with_passphrase(&mut File::open(path)?, passphrase)
+}
+
+/// Read user root identity from any implementor of `Read`, decrypting it with
+/// the passphrase if the keyfile is encrypted
+pub fn read_keyfile_data_with_passphrase<R: std::io::Read>(
+    buffer: &mut R,
+    passphrase: Option<&[u8]>,
+) -> Result<RootIdentity, std::io::Error> {
     let data = {
         let mut data = Vec::new();
         buffer.read_to_end(&mut data)?;
+        Zeroizing::new(data)
+    };
+    let data = if encrypted::is_encrypted(&data) {
+        let passphrase = passphrase.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "keyfile is encrypted, but no passphrase was provided",
+            )
+        })?;
+        encrypted::decrypt(&data, passphrase)?
+    } else {
         data
     };
     let result: RootIdentityJson = serde_json::from_slice(&data).map_err(to_io_error)?;
     Ok(RootIdentity::from(result))
 }
 
+/// Check whether the keyfile on disk is encrypted
+pub fn is_encrypted_keyfile<P: AsRef<Path>>(path: P) -> Result<bool, std::io::Error> {
+    let mut magic = [0u8; 8];
+    match File::open(path)?.read_exact(&mut magic) {
+        Ok(()) => Ok(encrypted::is_encrypted(&magic)),
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
 /// Write user public address to disk
 pub fn write_pubfile<P: AsRef<Path>>(path: P, addr: &PublicAddress) -> Result<(), std::io::Error> {
     File::create(path)?.write_all(&mc_util_serial::encode(addr))?;
@@ -59,6 +130,33 @@ pub fn read_pubfile_data<R: std::io::Read>(
     Ok(result)
 }
 
+/// Write a file by writing a temporary file next to it and renaming it into
+/// place, so that a keyfile being rewritten in place (e.g. to change its
+/// passphrase) is never left truncated or half-written.
+fn write_atomically(path: &Path, data: &[u8]) -> Result<(), std::io::Error> {
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{:?} is not a file path", path),
+        )
+    })?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = File::create(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
 fn to_io_error(err: serde_json::error::Error) -> std::io::Error {
     std::io::Error::new(std::io::ErrorKind::Other, Box::new(err))
 }
@@ -126,6 +224,49 @@ mod testing {
         }
     }
 
+    #[test]
+    fn test_encrypted_keyfile() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let dir = TempDir::new("test").unwrap();
+        let params = KdfParams {
+            mem_cost: 64,
+            time_cost: 1,
+            lanes: 1,
+        };
+
+        let entropy =
+            RootIdentity::random_with_fog(&mut rng, "fog://foobar.com", "", &[9u8, 9u8, 9u8, 9u8]);
+        let f1 = dir.path().join("f1");
+        write_encrypted_keyfile_with_params(&f1, &entropy, b"hunter2", &params).unwrap();
+        assert!(is_encrypted_keyfile(&f1).unwrap());
+
+        // Reading without a passphrase, or with the wrong one, fails
+        assert!(read_keyfile(&f1).is_err());
+        assert!(read_keyfile_with_passphrase(&f1, Some(b"hunter3")).is_err());
+
+        let result = read_keyfile_with_passphrase(&f1, Some(b"hunter2")).unwrap();
+        assert_eq!(entropy, result);
+
+        // Plaintext keyfiles ignore the passphrase
+        let f2 = dir.path().join("f2");
+        write_keyfile(&f2, &entropy).unwrap();
+        assert!(!is_encrypted_keyfile(&f2).unwrap());
+        let result = read_keyfile_with_passphrase(&f2, Some(b"hunter2")).unwrap();
+        assert_eq!(entropy, result);
+
+        // Rewriting a keyfile in place replaces it without leaving anything
+        // else behind
+        write_encrypted_keyfile_with_params(&f2, &entropy, b"hunter3", &params).unwrap();
+        let result = read_keyfile_with_passphrase(&f2, Some(b"hunter3")).unwrap();
+        assert_eq!(entropy, result);
+        let mut names = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["f1", "f2"]);
+    }
+
     #[test]
     fn test_pubfile() {
         let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
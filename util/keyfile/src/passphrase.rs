@@ -0,0 +1,46 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! Helpers for obtaining keyfile passphrases from the environment or the
+//! terminal.
+
+use std::io::{Error, ErrorKind};
+use zeroize::Zeroizing;
+
+/// Environment variable which, when set, supplies the passphrase for
+/// encrypted keyfiles instead of prompting on the terminal.
+pub const PASSPHRASE_ENV_VAR: &str = "MC_KEYFILE_PASSPHRASE";
+
+/// Environment variable which, when set, supplies the new passphrase when
+/// encrypting a keyfile or changing its passphrase.
+pub const NEW_PASSPHRASE_ENV_VAR: &str = "MC_KEYFILE_NEW_PASSPHRASE";
+
+/// Get the passphrase for an existing encrypted keyfile, from
+/// `MC_KEYFILE_PASSPHRASE` if set, or by prompting on the terminal.
+pub fn get_passphrase(prompt: &str) -> Result<Zeroizing<String>, Error> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV_VAR) {
+        return Ok(Zeroizing::new(passphrase));
+    }
+    rpassword::read_password_from_tty(Some(prompt)).map(Zeroizing::new)
+}
+
+/// Get a new passphrase, from the given environment variable if set, or by
+/// prompting on the terminal twice and checking that both entries match.
+pub fn get_new_passphrase(env_var: &str, prompt: &str) -> Result<Zeroizing<String>, Error> {
+    if let Ok(passphrase) = std::env::var(env_var) {
+        return Ok(Zeroizing::new(passphrase));
+    }
+    let passphrase = Zeroizing::new(rpassword::read_password_from_tty(Some(prompt))?);
+    let confirmation = Zeroizing::new(rpassword::read_password_from_tty(Some(
+        "Confirm passphrase: ",
+    ))?);
+    if passphrase != confirmation {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Passphrases do not match",
+        ));
+    }
+    if passphrase.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "Passphrase is empty"));
+    }
+    Ok(passphrase)
+}
@@ -0,0 +1,244 @@
+// Copyright (c) 2018-2021 The MobileCoin Foundation
+
+//! OpenTelemetry tracing for MobileCoin services.
+//!
+//! Tracing is configured through the environment, so that it can be enabled
+//! without changing the configuration of a service:
+//! * `MC_TELEMETRY_OTLP_ENDPOINT` - Export spans over OTLP/HTTP to a collector,
+//!   e.g. `http://localhost:4318/v1/traces`.
+//! * `MC_TELEMETRY_FILE` - Write spans to a file, one per line. This is
+//!   intended for tests, and takes precedence over the OTLP endpoint.
+//!
+//! When neither is set, spans are discarded by the default no-op tracer.
+//!
+//! Spans are queued when they end and exported in batches from a dedicated
+//! runtime, so a slow or unreachable collector never blocks the traced
+//! service. Spans are dropped when the queue is full.
+
+pub use opentelemetry::{
+    global::{self, BoxedSpan, BoxedTracer},
+    propagation::{Extractor, Injector},
+    sdk::propagation::TraceContextPropagator,
+    trace::{Link, Span, SpanContext, SpanKind, StatusCode, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+
+use displaydoc::Display;
+use opentelemetry::{
+    runtime::Tokio,
+    sdk::{
+        export::trace::{stdout, SpanExporter},
+        trace::{self as sdktrace, BatchSpanProcessor},
+        Resource,
+    },
+    trace::TraceError,
+};
+use opentelemetry_otlp::{SpanExporterBuilder, WithExportConfig};
+use std::{env, fs::File, io, path::PathBuf, time::Duration};
+use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
+
+/// The maximum number of spans waiting to be exported.
+const MAX_QUEUE_SIZE: usize = 2048;
+
+/// The maximum number of spans exported at once.
+const MAX_EXPORT_BATCH_SIZE: usize = 512;
+
+/// How often queued spans are exported.
+const SCHEDULED_DELAY: Duration = Duration::from_secs(5);
+
+/// How long an export may take before it is abandoned.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where spans are exported to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Exporter {
+    /// OTLP over HTTP, to the collector at the given endpoint.
+    Otlp(String),
+
+    /// One span per line, to the given file.
+    File(PathBuf),
+}
+
+impl Exporter {
+    /// The exporter configured by the environment, if any.
+    pub fn from_env() -> Option<Self> {
+        let var = |name| env::var(name).ok().filter(|val| !val.trim().is_empty());
+
+        if let Some(path) = var("MC_TELEMETRY_FILE") {
+            return Some(Self::File(PathBuf::from(path)));
+        }
+        var("MC_TELEMETRY_OTLP_ENDPOINT").map(Self::Otlp)
+    }
+}
+
+/// An error setting up tracing.
+#[derive(Debug, Display)]
+pub enum Error {
+    /// Could not create trace file: {0}
+    File(io::Error),
+
+    /// Could not start export runtime: {0}
+    Runtime(io::Error),
+
+    /// Could not install trace pipeline: {0}
+    Trace(TraceError),
+}
+
+impl From<io::Error> for Error {
+    fn from(src: io::Error) -> Self {
+        Self::File(src)
+    }
+}
+
+impl From<TraceError> for Error {
+    fn from(src: TraceError) -> Self {
+        Self::Trace(src)
+    }
+}
+
+/// Shuts down tracing when dropped, flushing any spans not yet exported.
+pub struct TelemetryGuard {
+    /// Runs the export task. This is dropped after the tracer provider has
+    /// been shut down, so that the final flush can complete.
+    _runtime: Runtime,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        global::shutdown_tracer_provider();
+    }
+}
+
+/// Set up tracing as configured by the environment, see the crate
+/// documentation. Returns `None` when tracing is not enabled.
+///
+/// # Arguments
+/// * `service_name` - Name of the service, as shown by trace viewers.
+pub fn init(service_name: &'static str) -> Option<TelemetryGuard> {
+    let exporter = Exporter::from_env()?;
+    Some(
+        setup(service_name, exporter)
+            .unwrap_or_else(|err| panic!("Could not set up tracing: {}", err)),
+    )
+}
+
+/// Set up tracing, exporting spans to `exporter`. Trace contexts are
+/// propagated using the W3C Trace Context format.
+///
+/// # Arguments
+/// * `service_name` - Name of the service, as shown by trace viewers.
+/// * `exporter` - Where spans are exported to.
+pub fn setup(service_name: &'static str, exporter: Exporter) -> Result<TelemetryGuard, Error> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let config = sdktrace::config().with_resource(Resource::new(vec![KeyValue::new(
+        "service.name",
+        service_name,
+    )]));
+
+    let runtime = RuntimeBuilder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("telemetry")
+        .enable_all()
+        .build()
+        .map_err(Error::Runtime)?;
+
+    // The batch processor spawns its export task onto the current runtime.
+    let processor = {
+        let _entered = runtime.enter();
+        match exporter {
+            Exporter::Otlp(endpoint) => batch_processor(
+                SpanExporterBuilder::from(
+                    opentelemetry_otlp::new_exporter()
+                        .http()
+                        .with_endpoint(endpoint)
+                        .with_timeout(EXPORT_TIMEOUT),
+                )
+                .build_span_exporter()?,
+            ),
+            Exporter::File(path) => {
+                batch_processor(stdout::Exporter::new(File::create(path)?, false))
+            }
+        }
+    };
+
+    let provider = sdktrace::TracerProvider::builder()
+        .with_span_processor(processor)
+        .with_config(config)
+        .build();
+    global::set_tracer_provider(provider);
+
+    Ok(TelemetryGuard { _runtime: runtime })
+}
+
+/// A span processor which exports batches of spans from the current runtime,
+/// with a bounded queue and export timeout.
+fn batch_processor(exporter: impl SpanExporter + 'static) -> BatchSpanProcessor<Tokio> {
+    BatchSpanProcessor::builder(exporter, Tokio)
+        .with_max_queue_size(MAX_QUEUE_SIZE)
+        .with_max_export_batch_size(MAX_EXPORT_BATCH_SIZE)
+        .with_scheduled_delay(SCHEDULED_DELAY)
+        .with_max_timeout(EXPORT_TIMEOUT)
+        .build()
+}
+
+/// The span context of the current span, e.g. to link to it from later work
+/// on the same request.
+pub fn current_span_context() -> SpanContext {
+    Context::current().span().span_context().clone()
+}
+
+/// Start a span in the current context, linked to the spans which caused it.
+/// Invalid span contexts, such as those recorded while tracing was disabled,
+/// are ignored.
+///
+/// # Arguments
+/// * `tracer` - The tracer to start the span with.
+/// * `name` - Name of the span.
+/// * `links` - Span contexts of the spans which caused this one.
+pub fn start_linked_span(
+    tracer: &BoxedTracer,
+    name: &'static str,
+    links: impl IntoIterator<Item = SpanContext>,
+) -> BoxedSpan {
+    let links = links
+        .into_iter()
+        .filter(|span_context| span_context.is_valid())
+        .map(|span_context| Link::new(span_context, Vec::new()))
+        .collect();
+    tracer.span_builder(name).with_links(links).start(tracer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn file_exporter_writes_linked_spans() {
+        let file = NamedTempFile::new().unwrap();
+        let guard = setup("test", Exporter::File(file.path().to_owned())).unwrap();
+
+        let tracer = global::tracer("mc_util_telemetry");
+        let mut proposed = tracer.start("propose");
+        let proposed_context = proposed.span_context().clone();
+        assert!(proposed_context.is_valid());
+        proposed.end();
+
+        let mut nominated = start_linked_span(
+            &tracer,
+            "nominate",
+            vec![proposed_context.clone(), SpanContext::empty_context()],
+        );
+        nominated.end();
+
+        // Shutting down flushes the exported spans.
+        drop(guard);
+
+        let contents = fs::read_to_string(file.path()).unwrap();
+        assert!(contents.contains("\"propose\""));
+        assert!(contents.contains("\"nominate\""));
+        assert!(contents.contains(&format!("{:?}", proposed_context.trace_id())));
+    }
+}
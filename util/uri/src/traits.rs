@@ -160,6 +160,13 @@ pub trait ConnectionUri:
         )
     }
 
+    /// Retrieve the path of the CA bundle used to verify client certificates,
+    /// if the `tls-client-ca` query parameter is present. Servers which have
+    /// one require clients to present a certificate signed by it.
+    fn tls_client_ca_path(&self) -> Option<String> {
+        self.get_param("tls-client-ca")
+    }
+
     /// Retrieve the TLS chain file path to use for this connection.
     fn tls_chain_path(&self) -> StdResult<String, String> {
         self.get_param("tls-chain")
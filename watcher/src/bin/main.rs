@@ -14,7 +14,9 @@ use mc_watcher::{
 use futures::executor::block_on;
 use grpcio::{EnvBuilder, ServerBuilder};
 use mc_common::logger::{create_app_logger, log, o, Logger};
-use mc_util_grpc::{ConnectionUriGrpcioServer, HealthCheckStatus, HealthService};
+use mc_util_grpc::{
+    ConnectionUriGrpcioServer, HealthCheckStatus, HealthService, ReflectionService,
+};
 use std::{
     io::Error as IOError,
     sync::{
@@ -44,6 +46,7 @@ fn main() {
     let _verification_reports_collector = <VerificationReportsCollector>::new(
         watcher_db,
         sources_config.sources().to_vec(),
+        config.verifier(),
         config.poll_interval,
         logger.clone(),
     );
@@ -57,6 +60,9 @@ fn main() {
         Arc::new(move |_| HealthCheckStatus::SERVING);
     let health_service =
         HealthService::new(Some(health_check_callback), logger.clone()).into_service();
+    let reflection_service =
+        ReflectionService::new(&["grpc.health.v1.Health"], Vec::new(), logger.clone())
+            .into_service();
 
     let env = Arc::new(
         EnvBuilder::new()
@@ -66,6 +72,7 @@ fn main() {
 
     let server_builder = ServerBuilder::new(env)
         .register_service(health_service)
+        .register_service(reflection_service)
         .bind_using_uri(&config.client_listen_uri, logger.clone());
 
     let mut server = server_builder.build().unwrap();
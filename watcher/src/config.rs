@@ -2,6 +2,7 @@
 
 //! Configuration parameters for the watcher test utility.
 
+use mc_attest_core::{AttestationPolicy, Verifier};
 use mc_util_uri::{ConsensusClientUri, WatcherUri};
 use serde::{Deserialize, Serialize};
 use std::{fs, path::PathBuf, str::FromStr, time::Duration};
@@ -38,9 +39,28 @@ pub struct WatcherConfig {
     /// gRPC listening URI.
     #[structopt(long, default_value = "insecure-watcher://0.0.0.0:3226/")]
     pub client_listen_uri: WatcherUri,
+
+    /// (Optional) Attestation policy (.toml or .json) that consensus nodes'
+    /// verification reports must satisfy. Defaults to only checking the report
+    /// signature.
+    #[structopt(long, parse(try_from_str=AttestationPolicy::load))]
+    pub attestation_policy: Option<AttestationPolicy>,
 }
 
 impl WatcherConfig {
+    /// Get the verifier used when fetching verification reports from
+    /// consensus nodes.
+    pub fn verifier(&self) -> Verifier {
+        self.attestation_policy
+            .as_ref()
+            .map(|policy| {
+                policy
+                    .verifier()
+                    .expect("Attestation policy was validated when loaded")
+            })
+            .unwrap_or_default()
+    }
+
     /// Load the sources configuration file.
     pub fn sources_config(&self) -> SourcesConfig {
         // Read configuration file.
@@ -37,6 +37,7 @@ pub trait NodeClient {
     /// Get a verification report for a given client.
     fn get_verification_report(
         source_config: &SourceConfig,
+        verifier: &Verifier,
         env: Arc<Environment>,
         logger: Logger,
     ) -> Result<VerificationReport, String>;
@@ -51,6 +52,7 @@ pub struct ConsensusNodeClient;
 impl NodeClient for ConsensusNodeClient {
     fn get_verification_report(
         source_config: &SourceConfig,
+        verifier: &Verifier,
         env: Arc<Environment>,
         logger: Logger,
     ) -> Result<VerificationReport, String> {
@@ -73,10 +75,9 @@ impl NodeClient for ConsensusNodeClient {
             };
 
         // Contact node and get a VerificationReport.
-        let verifier = Verifier::default();
         let mut client = ThickClient::new(
             node_url.clone(),
-            verifier,
+            verifier.clone(),
             env,
             credentials_provider,
             logger,
@@ -135,6 +136,7 @@ impl<NC: NodeClient> VerificationReportsCollector<NC> {
     pub fn new(
         watcher_db: WatcherDB,
         sources: Vec<SourceConfig>,
+        verifier: Verifier,
         poll_interval: Duration,
         logger: Logger,
     ) -> Self {
@@ -148,6 +150,7 @@ impl<NC: NodeClient> VerificationReportsCollector<NC> {
                     let thread = VerificationReportsCollectorThread::<NC>::new(
                         watcher_db,
                         sources,
+                        verifier,
                         poll_interval,
                         logger,
                         thread_stop_requested,
@@ -183,6 +186,7 @@ impl<NC: NodeClient> Drop for VerificationReportsCollector<NC> {
 struct VerificationReportsCollectorThread<NC: NodeClient> {
     watcher_db: WatcherDB,
     sources: Vec<SourceConfig>,
+    verifier: Verifier,
     poll_interval: Duration,
     logger: Logger,
     stop_requested: Arc<AtomicBool>,
@@ -194,6 +198,7 @@ impl<NC: NodeClient> VerificationReportsCollectorThread<NC> {
     pub fn new(
         watcher_db: WatcherDB,
         sources: Vec<SourceConfig>,
+        verifier: Verifier,
         poll_interval: Duration,
         logger: Logger,
         stop_requested: Arc<AtomicBool>,
@@ -207,6 +212,7 @@ impl<NC: NodeClient> VerificationReportsCollectorThread<NC> {
         Self {
             watcher_db,
             sources,
+            verifier,
             poll_interval,
             logger,
             stop_requested,
@@ -279,6 +285,7 @@ impl<NC: NodeClient> VerificationReportsCollectorThread<NC> {
             // Contact node and get a VerificationReport.
             let verification_report = match NC::get_verification_report(
                 &source_config,
+                &self.verifier,
                 self.grpcio_env.clone(),
                 self.logger.clone(),
             ) {
@@ -417,6 +424,7 @@ mod tests {
     impl NodeClient for TestNodeClient {
         fn get_verification_report(
             source_config: &SourceConfig,
+            _verifier: &Verifier,
             _env: Arc<Environment>,
             _logger: Logger,
         ) -> Result<VerificationReport, String> {
@@ -462,6 +470,7 @@ mod tests {
         let _verification_reports_collector = VerificationReportsCollector::<TestNodeClient>::new(
             watcher_db.clone(),
             sources,
+            Verifier::default(),
             Duration::from_millis(100),
             logger,
         );